@@ -26,12 +26,12 @@ use crate::EndorsedAttestationReport;
 
 use std::sync::{Arc, SgxRwLock as RwLock};
 use std::thread;
-use std::time::{Duration, SystemTime};
-use std::untrusted::time::SystemTimeEx;
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
-use log::debug;
+use log::{debug, warn};
 use teaclave_config::build::ATTESTATION_VALIDITY_SECS;
+use teaclave_types::TrustedTime;
 
 const CERT_ISSUER: &str = "Teaclave";
 const CERT_SUBJECT: &str = "CN=Teaclave";
@@ -78,14 +78,24 @@ impl AttestedTlsConfig {
         let report = match attestation_config {
             AttestationConfig::NoAttestation => EndorsedAttestationReport::default(),
             AttestationConfig::WithAttestation(config) => {
-                EndorsedAttestationReport::new(&config, key_pair.pub_k())?
+                let report = EndorsedAttestationReport::new(&config, key_pair.pub_k())?;
+                // The report is fresh from the attestation service, so its
+                // timestamp is the current time attested.
+                match report.timestamp() {
+                    Ok(timestamp) => match TrustedTime::calibrate(timestamp) {
+                        Ok(drift) => debug!("Trusted time calibrated, drift: {}s", drift),
+                        Err(e) => warn!("Failed to calibrate trusted time: {:?}", e),
+                    },
+                    Err(e) => warn!("Failed to get timestamp of report: {:?}", e),
+                }
+                report
             }
         };
 
         let extension = serde_json::to_vec(&report)?;
         let cert = key_pair.create_cert_with_extension(CERT_ISSUER, CERT_SUBJECT, &extension);
         let private_key = key_pair.private_key_into_der();
-        let time = SystemTime::now();
+        let time = TrustedTime::now();
         let validity = Duration::from_secs(ATTESTATION_VALIDITY_SECS);
 
         let attested_tls_config = AttestedTlsConfig {
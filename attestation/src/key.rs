@@ -21,7 +21,7 @@
 
 use std::prelude::v1::*;
 
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use sgx_tcrypto::SgxEccHandle;
 use sgx_types::{sgx_ec256_private_t, sgx_ec256_public_t};
 
@@ -100,9 +100,8 @@ impl NistP256KeyPair {
         use bit_vec::BitVec;
         use chrono::TimeZone;
         use num_bigint::BigUint;
-        use std::time::SystemTime;
         use std::time::UNIX_EPOCH;
-        use std::untrusted::time::SystemTimeEx;
+        use teaclave_types::TrustedTime;
         use yasna::construct_der;
         use yasna::models::{ObjectIdentifier, UTCTime};
 
@@ -116,7 +115,7 @@ impl NistP256KeyPair {
         let pub_key_bytes = self.public_key_into_bytes();
 
         // UNIX_EPOCH is the earliest time stamp. This unwrap should constantly succeed.
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let now = TrustedTime::now().duration_since(UNIX_EPOCH).unwrap();
         let issue_ts = chrono::Utc.timestamp(now.as_secs() as i64, 0);
 
         // This is guaranteed to be a valid duration.
@@ -191,7 +190,31 @@ impl NistP256KeyPair {
         })
     }
 
-    fn public_key_into_bytes(&self) -> Vec<u8> {
+    /// Compute the ECDH shared secret with the peer public key in the
+    /// uncompressed encoding. The secret is the x-coordinate of the shared
+    /// point in big-endian, as in SEC 1.
+    pub fn compute_shared_key(&self, peer_public_key: &[u8]) -> Result<Vec<u8>> {
+        ensure!(
+            peer_public_key.len() == 65 && peer_public_key[0] == 4,
+            "Invalid peer public key"
+        );
+        let mut pub_k = sgx_ec256_public_t::default();
+        pub_k.gx.copy_from_slice(&peer_public_key[1..33]);
+        pub_k.gx.reverse();
+        pub_k.gy.copy_from_slice(&peer_public_key[33..]);
+        pub_k.gy.reverse();
+
+        let ecc_handle = SgxEccHandle::new();
+        ecc_handle.open()?;
+        let shared_key = ecc_handle.compute_shared_dhkey(&self.prv_k, &pub_k);
+        ecc_handle.close()?;
+
+        let mut shared_key = shared_key?.s.to_vec();
+        shared_key.reverse();
+        Ok(shared_key)
+    }
+
+    pub fn public_key_into_bytes(&self) -> Vec<u8> {
         // The first byte must be 4, which indicates the uncompressed encoding.
         let mut pub_key_bytes: Vec<u8> = vec![4];
         pub_key_bytes.extend(self.pub_k.gx.iter().rev());
@@ -28,12 +28,11 @@ use crate::EndorsedAttestationReport;
 use std::convert::TryFrom;
 use std::fmt;
 use std::time::*;
-#[cfg(feature = "mesalock_sgx")]
-use std::untrusted::time::SystemTimeEx;
 
 use anyhow::{anyhow, bail, ensure, Error, Result};
 use chrono::DateTime;
 use serde_json::Value;
+use teaclave_types::TrustedTime;
 use uuid::Uuid;
 
 type SignatureAlgorithms = &'static [&'static webpki::SignatureAlgorithm];
@@ -504,18 +503,7 @@ impl AttestationReport {
     /// service provider.
     pub fn from_cert(cert: &[u8], report_ca_cert: &[u8]) -> Result<Self> {
         // Before we reach here, Webpki already verifed the cert is properly signed.
-        use crate::cert::*;
-
-        // Extract information for attestation from TLS certification.
-        let x509 = yasna::parse_der(cert, X509::load)?;
-        let tbs_cert: <TbsCert as Asn1Ty>::ValueTy = x509.0;
-        let pub_key: <PubKey as Asn1Ty>::ValueTy = ((((((tbs_cert.1).1).1).1).1).1).0;
-        let pub_k = (pub_key.1).0;
-        let cert_ext: <SgxRaCertExt as Asn1Ty>::ValueTy = (((((((tbs_cert.1).1).1).1).1).1).1).0;
-        let cert_ext_payload: Vec<u8> = ((cert_ext.0).1).0;
-
-        // Convert to endorsed report
-        let report: EndorsedAttestationReport = serde_json::from_slice(&cert_ext_payload)?;
+        let (raw_pub_k, report) = parse_cert(cert)?;
 
         // Verify report's signature
         let signing_cert = webpki::EndEntityCert::from(&report.signing_cert)?;
@@ -530,7 +518,7 @@ impl AttestationReport {
             .map(|cert| cert.to_trust_anchor())
             .collect();
         let chain = vec![report_ca_cert];
-        let time = webpki::Time::try_from(SystemTime::now())
+        let time = webpki::Time::try_from(TrustedTime::now())
             .map_err(|_| anyhow!("Cannot convert time."))?;
         signing_cert.verify_is_valid_tls_server_cert(
             SUPPORTED_SIG_ALGS,
@@ -556,17 +544,12 @@ impl AttestationReport {
             .ok_or_else(|| Error::new(AttestationError::ReportError))?;
         ensure!(version == 4, AttestationError::ApiVersionNotCompatible);
 
-        // Get quote freshness
+        // Get quote freshness. The timestamp is attested by the attestation
+        // service, so the trusted time is no earlier than it.
         let freshness = {
-            let time = attn_report["timestamp"]
-                .as_str()
-                .ok_or_else(|| Error::new(AttestationError::ReportError))?;
-            let time_fixed = String::from(time) + "+0000";
-            let date_time = DateTime::parse_from_str(&time_fixed, "%Y-%m-%dT%H:%M:%S%.f%z")?;
-            let ts = date_time.naive_utc();
-            let now = DateTime::<chrono::offset::Utc>::from(SystemTime::now()).naive_utc();
-            let quote_freshness = u64::try_from((now - ts).num_seconds())?;
-            std::time::Duration::from_secs(quote_freshness)
+            let ts = parse_timestamp(&attn_report)?;
+            TrustedTime::observe(ts);
+            TrustedTime::now().duration_since(ts)?
         };
 
         // Get quote status
@@ -578,13 +561,7 @@ impl AttestationReport {
         };
 
         // Get quote body
-        let sgx_quote_body = {
-            let quote_encoded = attn_report["isvEnclaveQuoteBody"]
-                .as_str()
-                .ok_or_else(|| Error::new(AttestationError::ReportError))?;
-            let quote_raw = base64::decode(&quote_encoded.as_bytes())?;
-            SgxQuote::parse_from(quote_raw.as_slice())?
-        };
+        let sgx_quote_body = parse_quote_body(&attn_report)?;
 
         // According to RFC 5480 `Elliptic Curve Cryptography Subject Public Key
         // Information', SEC 2.2: ``The first octet of the OCTET STRING
@@ -595,7 +572,6 @@ impl AttestationReport {
         // octet.''
         //
         // We only accept the uncompressed form here.
-        let raw_pub_k = pub_k.to_bytes();
         let is_uncompressed = raw_pub_k[0] == 4;
         let pub_k = &raw_pub_k.as_slice()[1..];
         if !is_uncompressed || pub_k != &sgx_quote_body.isv_enclave_report.report_data[..] {
@@ -608,6 +584,70 @@ impl AttestationReport {
             sgx_quote_body,
         })
     }
+
+    /// Hex encoded MRENCLAVE of the enclave of a certificate, whose report is
+    /// not verified again. Only for certificates verified already, e.g., the
+    /// peer certificate of an established attested TLS session.
+    pub fn mr_enclave_of_verified_cert(cert: &[u8]) -> Result<String> {
+        let (mr_enclave, _) = Self::measurement_of_verified_cert(cert)?;
+        Ok(mr_enclave)
+    }
+
+    /// Hex encoded MRENCLAVE and MRSIGNER of the enclave of a certificate
+    /// verified already, like mr_enclave_of_verified_cert.
+    pub fn measurement_of_verified_cert(cert: &[u8]) -> Result<(String, String)> {
+        let (_, report) = parse_cert(cert)?;
+        let attn_report: Value = serde_json::from_slice(&report.report)?;
+        let sgx_quote_body = parse_quote_body(&attn_report)?;
+        let enclave_report = sgx_quote_body.isv_enclave_report;
+        Ok((
+            hex::encode(enclave_report.mr_enclave),
+            hex::encode(enclave_report.mr_signer),
+        ))
+    }
+}
+
+impl EndorsedAttestationReport {
+    /// Time the report was generated by the attestation service.
+    pub fn timestamp(&self) -> Result<SystemTime> {
+        let attn_report: Value = serde_json::from_slice(&self.report)?;
+        parse_timestamp(&attn_report)
+    }
+}
+
+// Extracts the public key and the endorsed attestation report from the
+// extension of a TLS certificate.
+fn parse_cert(cert: &[u8]) -> Result<(Vec<u8>, EndorsedAttestationReport)> {
+    use crate::cert::*;
+
+    let x509 = yasna::parse_der(cert, X509::load)?;
+    let tbs_cert: <TbsCert as Asn1Ty>::ValueTy = x509.0;
+    let pub_key: <PubKey as Asn1Ty>::ValueTy = ((((((tbs_cert.1).1).1).1).1).1).0;
+    let pub_k = (pub_key.1).0;
+    let cert_ext: <SgxRaCertExt as Asn1Ty>::ValueTy = (((((((tbs_cert.1).1).1).1).1).1).1).0;
+    let cert_ext_payload: Vec<u8> = ((cert_ext.0).1).0;
+
+    // Convert to endorsed report
+    let report: EndorsedAttestationReport = serde_json::from_slice(&cert_ext_payload)?;
+    Ok((pub_k.to_bytes(), report))
+}
+
+fn parse_timestamp(attn_report: &Value) -> Result<SystemTime> {
+    let time = attn_report["timestamp"]
+        .as_str()
+        .ok_or_else(|| Error::new(AttestationError::ReportError))?;
+    let time_fixed = String::from(time) + "+0000";
+    let date_time = DateTime::parse_from_str(&time_fixed, "%Y-%m-%dT%H:%M:%S%.f%z")?;
+    let millis = u64::try_from(date_time.timestamp_millis())?;
+    Ok(UNIX_EPOCH + Duration::from_millis(millis))
+}
+
+fn parse_quote_body(attn_report: &Value) -> Result<SgxQuote> {
+    let quote_encoded = attn_report["isvEnclaveQuoteBody"]
+        .as_str()
+        .ok_or_else(|| Error::new(AttestationError::ReportError))?;
+    let quote_raw = base64::decode(&quote_encoded.as_bytes())?;
+    SgxQuote::parse_from(quote_raw.as_slice())
 }
 
 #[cfg(all(feature = "enclave_unit_test", feature = "mesalock_sgx"))]
@@ -100,6 +100,25 @@ impl AttestationReportVerifier {
 
         self.verify_measures(&report) && (self.verifier)(&report)
     }
+
+    /// Verify the ECDSA (NIST P-256, SHA-256) signature of the message made
+    /// with the key of an attested TLS certificate.
+    pub fn verify_signature(&self, cert_der: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        debug!("verify signature");
+        if !self.verify_cert(cert_der) {
+            return false;
+        }
+
+        let cert = match webpki::EndEntityCert::from(cert_der) {
+            Ok(cert) => cert,
+            Err(e) => {
+                error!("cert parsing error {:?}", e);
+                return false;
+            }
+        };
+        cert.verify_signature(&webpki::ECDSA_P256_SHA256, message, signature)
+            .is_ok()
+    }
 }
 
 impl rustls::ServerCertVerifier for AttestationReportVerifier {
@@ -0,0 +1,7 @@
+def entrypoint(argv):
+    assert argv[0] == 'iterations'
+    iterations = int(argv[1])
+    acc = 0
+    for i in range(iterations):
+        acc = (acc * 31 + i) % 1000000007
+    return str(acc)
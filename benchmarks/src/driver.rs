@@ -0,0 +1,216 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::workload::{FunctionKind, Mix, WorkloadParams};
+use anyhow::{anyhow, bail, Result};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use teaclave_client_sdk::{
+    AuthenticationService, EnclaveInfo, FrontendClient, FrontendService, GetTaskRequest,
+    TaskResult, TaskStatus,
+};
+
+/// Stages of the pipeline of a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stage {
+    /// Registering a function
+    RegisterFunction,
+    /// Creating a task
+    CreateTask,
+    /// Invoking a task
+    InvokeTask,
+    /// From the invocation to the task being run by an execution service
+    Queue,
+    /// From the task being run to its result being ready
+    Execution,
+    /// From the creation to the result of a task
+    EndToEnd,
+}
+
+impl Stage {
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::RegisterFunction => "register_function",
+            Stage::CreateTask => "create_task",
+            Stage::InvokeTask => "invoke_task",
+            Stage::Queue => "queue",
+            Stage::Execution => "execution",
+            Stage::EndToEnd => "end_to_end",
+        }
+    }
+}
+
+/// Endpoints of the deployment under test.
+pub struct Deployment {
+    pub authentication_address: String,
+    pub frontend_address: String,
+    pub enclave_info: EnclaveInfo,
+    pub as_root_ca_cert: Vec<u8>,
+}
+
+impl Deployment {
+    pub fn register_and_login(&self, user_id: &str, user_password: &str) -> Result<String> {
+        let mut client = AuthenticationService::connect(
+            &self.authentication_address,
+            &self.enclave_info,
+            &self.as_root_ca_cert,
+        )?;
+        client.user_register(user_id, user_password)?;
+        client.user_login(user_id, user_password)
+    }
+
+    pub fn connect(&self, user_id: &str, token: &str) -> Result<FrontendClient> {
+        let mut client = FrontendService::connect(
+            &self.frontend_address,
+            &self.enclave_info,
+            &self.as_root_ca_cert,
+        )?;
+        client.set_credential(user_id, token);
+        Ok(client)
+    }
+}
+
+pub struct Driver {
+    pub deployment: Deployment,
+    pub user_id: String,
+    pub token: String,
+    pub mix: Mix,
+    pub params: WorkloadParams,
+    pub poll_interval: Duration,
+}
+
+/// Latencies of the stages and failures of a run.
+#[derive(Debug, Default)]
+pub struct RunResult {
+    pub samples: Vec<(Stage, Duration)>,
+    pub completed: usize,
+    pub failures: Vec<String>,
+    pub elapsed: Duration,
+}
+
+impl Driver {
+    /// Registers the functions of the mix, then runs the tasks with the
+    /// given number of concurrent clients.
+    pub fn run(self, tasks: usize, concurrency: usize) -> Result<RunResult> {
+        let mut result = RunResult::default();
+        let mut client = self.deployment.connect(&self.user_id, &self.token)?;
+        let mut functions = HashMap::new();
+        for kind in self.mix.kinds() {
+            let start = Instant::now();
+            let function_id = kind.register(&mut client)?;
+            result
+                .samples
+                .push((Stage::RegisterFunction, start.elapsed()));
+            functions.insert(kind, function_id);
+        }
+
+        let driver = Arc::new(self);
+        let functions = Arc::new(functions);
+        let next_task = Arc::new(AtomicUsize::new(0));
+        let result = Arc::new(Mutex::new(result));
+        let start = Instant::now();
+        let workers: Vec<_> = (0..concurrency.max(1))
+            .map(|_| {
+                let driver = driver.clone();
+                let functions = functions.clone();
+                let next_task = next_task.clone();
+                let result = result.clone();
+                thread::spawn(move || -> Result<()> {
+                    let mut client = driver.deployment.connect(&driver.user_id, &driver.token)?;
+                    loop {
+                        let index = next_task.fetch_add(1, Ordering::SeqCst);
+                        if index >= tasks {
+                            return Ok(());
+                        }
+                        let kind = driver.mix.kind_of(index);
+                        let outcome = driver.run_task(&mut client, &functions[&kind], kind);
+                        let mut result = result.lock().unwrap();
+                        match outcome {
+                            Ok(samples) => {
+                                result.samples.extend(samples);
+                                result.completed += 1;
+                            }
+                            Err(e) => result.failures.push(format!("{}: {:?}", kind.name(), e)),
+                        }
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker
+                .join()
+                .map_err(|_| anyhow!("Benchmark client panicked"))??;
+        }
+
+        let mut result = Arc::try_unwrap(result)
+            .map_err(|_| anyhow!("Benchmark clients still running"))?
+            .into_inner()
+            .unwrap();
+        result.elapsed = start.elapsed();
+        Ok(result)
+    }
+
+    // The queueing and execution stages are observed by polling the task, so
+    // their resolution is the polling interval.
+    fn run_task(
+        &self,
+        client: &mut FrontendClient,
+        function_id: &str,
+        kind: FunctionKind,
+    ) -> Result<Vec<(Stage, Duration)>> {
+        let start = Instant::now();
+        let task_id = client.create_task(
+            function_id,
+            Some(kind.arguments(&self.params)),
+            kind.executor(),
+            None,
+            None,
+        )?;
+        let created = Instant::now();
+        client.invoke_task(&task_id)?;
+        let invoked = Instant::now();
+
+        let mut running = None;
+        let finished = loop {
+            let request = GetTaskRequest::new(task_id.as_str().try_into()?);
+            let response = client.get_task_with_request(request)?;
+            match response.result {
+                TaskResult::Ok(_) => break Instant::now(),
+                TaskResult::Err(failure) => bail!("Task {} failed: {}", task_id, failure.reason),
+                TaskResult::NotReady => {}
+            }
+            if running.is_none() && response.status == TaskStatus::Running {
+                running = Some(Instant::now());
+            }
+            thread::sleep(self.poll_interval);
+        };
+        // The task may finish between two polls without being seen running.
+        let running = running.unwrap_or(finished);
+
+        Ok(vec![
+            (Stage::CreateTask, created - start),
+            (Stage::InvokeTask, invoked - created),
+            (Stage::Queue, running - invoked),
+            (Stage::Execution, finished - running),
+            (Stage::EndToEnd, finished - start),
+        ])
+    }
+}
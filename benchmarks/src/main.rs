@@ -0,0 +1,144 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use anyhow::{ensure, Result};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use structopt::StructOpt;
+use teaclave_client_sdk::EnclaveInfo;
+
+mod driver;
+mod report;
+mod workload;
+
+use driver::{Deployment, Driver};
+use report::Report;
+use workload::{Mix, WorkloadParams};
+
+#[cfg(dcap)]
+const AS_ROOT_CA_CERT_PATH: &str = "../keys/dcap_root_ca_cert.pem";
+#[cfg(not(dcap))]
+const AS_ROOT_CA_CERT_PATH: &str = "../keys/ias_root_ca_cert.pem";
+const USER_PASSWORD: &str = "benchmark_password";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "teaclave_benchmarks")]
+struct Opt {
+    /// Address of the authentication service
+    #[structopt(long = "authentication-address", default_value = "localhost:7776")]
+    authentication_address: String,
+
+    /// Address of the frontend service
+    #[structopt(long = "frontend-address", default_value = "localhost:7777")]
+    frontend_address: String,
+
+    /// Path of enclave info
+    #[structopt(
+        long = "enclave-info",
+        default_value = "../release/services/enclave_info.toml"
+    )]
+    enclave_info: PathBuf,
+
+    /// CA cert of attestation service for verifying the attestation report
+    #[structopt(long = "as-root-ca-cert")]
+    as_root_ca_cert: Option<PathBuf>,
+
+    /// Weights of the functions of tasks, e.g., "echo=3,compute=1". Supported
+    /// functions are "echo" and "compute".
+    #[structopt(short, long, default_value = "echo=1")]
+    mix: Mix,
+
+    /// Number of tasks
+    #[structopt(short = "n", long, default_value = "100")]
+    tasks: usize,
+
+    /// Number of concurrent clients
+    #[structopt(short, long, default_value = "4")]
+    concurrency: usize,
+
+    /// Size of the message of the echo function in bytes
+    #[structopt(long = "message-size", default_value = "16")]
+    message_size: usize,
+
+    /// Iterations of the loop of the compute function
+    #[structopt(long = "compute-iterations", default_value = "100000")]
+    compute_iterations: u64,
+
+    /// Interval of polling the status of tasks in milliseconds
+    #[structopt(long = "poll-interval", default_value = "20")]
+    poll_interval: u64,
+
+    /// Path to save the report in JSON
+    #[structopt(short, long)]
+    output: Option<PathBuf>,
+
+    /// Path of the report of an earlier run to compare with
+    #[structopt(short, long)]
+    baseline: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let opt = Opt::from_args();
+    ensure!(opt.tasks > 0, "No tasks to run");
+    let baseline: Option<Report> = match &opt.baseline {
+        Some(path) => Some(serde_json::from_slice(&fs::read(path)?)?),
+        None => None,
+    };
+
+    let as_root_ca_cert_path = opt
+        .as_root_ca_cert
+        .unwrap_or_else(|| PathBuf::from(AS_ROOT_CA_CERT_PATH));
+    let deployment = Deployment {
+        authentication_address: opt.authentication_address,
+        frontend_address: opt.frontend_address,
+        enclave_info: EnclaveInfo::from_file(&opt.enclave_info)?,
+        as_root_ca_cert: pem::parse(fs::read(as_root_ca_cert_path)?)?.contents,
+    };
+
+    // A fresh user for each run, so that runs do not see tasks of each other.
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let user_id = format!("benchmark_user_{}", timestamp);
+    println!("[+] registering user {}", user_id);
+    let token = deployment.register_and_login(&user_id, USER_PASSWORD)?;
+
+    let mix = opt.mix.to_string();
+    let driver = Driver {
+        deployment,
+        user_id,
+        token,
+        mix: opt.mix,
+        params: WorkloadParams {
+            message_size: opt.message_size,
+            compute_iterations: opt.compute_iterations,
+        },
+        poll_interval: Duration::from_millis(opt.poll_interval),
+    };
+    println!("[+] running {} tasks", opt.tasks);
+    let result = driver.run(opt.tasks, opt.concurrency)?;
+    for failure in result.failures.iter() {
+        println!("[-] {}", failure);
+    }
+
+    let report = Report::new(mix, opt.tasks, opt.concurrency, &result);
+    report.print(baseline.as_ref());
+    if let Some(path) = opt.output {
+        fs::write(&path, serde_json::to_string_pretty(&report)?)?;
+        println!("[+] report saved to {}", path.display());
+    }
+    Ok(())
+}
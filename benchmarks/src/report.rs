@@ -0,0 +1,149 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::driver::{RunResult, Stage};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Latency statistics of a stage in milliseconds.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StageStats {
+    pub count: usize,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl StageStats {
+    fn from_latencies(latencies: &mut [f64]) -> Self {
+        latencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let count = latencies.len();
+        let mean = latencies.iter().sum::<f64>() / count as f64;
+        Self {
+            count,
+            mean,
+            p50: percentile(latencies, 50.0),
+            p90: percentile(latencies, 90.0),
+            p99: percentile(latencies, 99.0),
+            max: latencies[count - 1],
+        }
+    }
+}
+
+// Nearest-rank percentile of sorted values.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.max(1) - 1]
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Report of a run, which can be saved and compared with a later run.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Report {
+    pub mix: String,
+    pub tasks: usize,
+    pub concurrency: usize,
+    pub completed: usize,
+    pub failed: usize,
+    /// Wall-clock time of running the tasks in seconds
+    pub elapsed: f64,
+    /// Completed tasks per second
+    pub throughput: f64,
+    pub stages: BTreeMap<String, StageStats>,
+}
+
+impl Report {
+    pub fn new(mix: String, tasks: usize, concurrency: usize, result: &RunResult) -> Self {
+        let mut latencies: BTreeMap<Stage, Vec<f64>> = BTreeMap::new();
+        for (stage, latency) in result.samples.iter() {
+            latencies.entry(*stage).or_default().push(millis(*latency));
+        }
+        let stages = latencies
+            .iter_mut()
+            .map(|(stage, latencies)| {
+                (
+                    stage.name().to_string(),
+                    StageStats::from_latencies(latencies),
+                )
+            })
+            .collect();
+        let elapsed = result.elapsed.as_secs_f64();
+
+        Self {
+            mix,
+            tasks,
+            concurrency,
+            completed: result.completed,
+            failed: result.failures.len(),
+            elapsed,
+            throughput: result.completed as f64 / elapsed,
+            stages,
+        }
+    }
+
+    pub fn print(&self, baseline: Option<&Report>) {
+        println!(
+            "[+] mix: {}, tasks: {}, concurrency: {}",
+            self.mix, self.tasks, self.concurrency
+        );
+        println!(
+            "[+] completed: {}, failed: {}, elapsed: {:.2}s, throughput: {:.2} tasks/s{}",
+            self.completed,
+            self.failed,
+            self.elapsed,
+            self.throughput,
+            baseline
+                .map(|b| format!(" ({})", delta(self.throughput, b.throughput)))
+                .unwrap_or_default()
+        );
+        println!(
+            "{:<18} {:>7} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            "stage (ms)", "count", "mean", "p50", "p90", "p99", "max"
+        );
+        for (name, stats) in self.stages.iter() {
+            println!(
+                "{:<18} {:>7} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2}",
+                name, stats.count, stats.mean, stats.p50, stats.p90, stats.p99, stats.max
+            );
+            if let Some(base) = baseline.and_then(|b| b.stages.get(name)) {
+                println!(
+                    "{:<18} {:>7} {:>10} {:>10} {:>10} {:>10} {:>10}",
+                    "  vs. baseline",
+                    "",
+                    delta(stats.mean, base.mean),
+                    delta(stats.p50, base.p50),
+                    delta(stats.p90, base.p90),
+                    delta(stats.p99, base.p99),
+                    delta(stats.max, base.max)
+                );
+            }
+        }
+    }
+}
+
+fn delta(current: f64, baseline: f64) -> String {
+    if baseline == 0.0 {
+        return "n/a".to_string();
+    }
+    format!("{:+.1}%", (current - baseline) / baseline * 100.0)
+}
@@ -0,0 +1,175 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use anyhow::{anyhow, bail, ensure, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use teaclave_client_sdk::FrontendClient;
+
+const COMPUTE_PAYLOAD: &[u8] = include_bytes!("../payloads/compute_payload.py");
+
+/// Parameters of the synthetic functions.
+#[derive(Debug, Clone)]
+pub struct WorkloadParams {
+    /// Size of the message echoed by the echo function in bytes
+    pub message_size: usize,
+    /// Iterations of the loop of the compute function
+    pub compute_iterations: u64,
+}
+
+/// Synthetic functions registered by the benchmarks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FunctionKind {
+    /// The built-in echo function, measuring the overhead of the platform
+    Echo,
+    /// A Python function running a loop, measuring the MesaPy executor
+    Compute,
+}
+
+impl FunctionKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            FunctionKind::Echo => "echo",
+            FunctionKind::Compute => "compute",
+        }
+    }
+
+    pub fn executor(self) -> &'static str {
+        match self {
+            FunctionKind::Echo => "builtin",
+            FunctionKind::Compute => "mesapy",
+        }
+    }
+
+    pub fn register(self, client: &mut FrontendClient) -> Result<String> {
+        match self {
+            FunctionKind::Echo => client.register_function(
+                "builtin-echo",
+                "Synthetic echo function of benchmarks.",
+                "builtin",
+                None,
+                Some(&["message"]),
+                None,
+                None,
+            ),
+            FunctionKind::Compute => client.register_function(
+                "benchmark-compute",
+                "Synthetic compute function of benchmarks.",
+                "python",
+                Some(COMPUTE_PAYLOAD),
+                Some(&["iterations"]),
+                None,
+                None,
+            ),
+        }
+    }
+
+    pub fn arguments(self, params: &WorkloadParams) -> HashMap<String, String> {
+        let mut arguments = HashMap::new();
+        match self {
+            FunctionKind::Echo => {
+                arguments.insert("message".to_string(), "x".repeat(params.message_size));
+            }
+            FunctionKind::Compute => {
+                arguments.insert(
+                    "iterations".to_string(),
+                    params.compute_iterations.to_string(),
+                );
+            }
+        }
+        arguments
+    }
+}
+
+impl FromStr for FunctionKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "echo" => Ok(FunctionKind::Echo),
+            "compute" => Ok(FunctionKind::Compute),
+            _ => bail!("Unknown function: {}", s),
+        }
+    }
+}
+
+/// Weighted mix of the functions of tasks, e.g., "echo=3,compute=1".
+#[derive(Debug, Clone)]
+pub struct Mix {
+    weights: Vec<(FunctionKind, usize)>,
+}
+
+impl Mix {
+    pub fn kinds(&self) -> impl Iterator<Item = FunctionKind> + '_ {
+        self.weights.iter().map(|(kind, _)| *kind)
+    }
+
+    /// Function of the task at the index. Tasks are interleaved by the
+    /// weights, so that any prefix of the tasks follows the mix.
+    pub fn kind_of(&self, index: usize) -> FunctionKind {
+        let total: usize = self.weights.iter().map(|(_, weight)| weight).sum();
+        let mut slot = index % total;
+        for (kind, weight) in self.weights.iter() {
+            if slot < *weight {
+                return *kind;
+            }
+            slot -= weight;
+        }
+        unreachable!()
+    }
+}
+
+impl FromStr for Mix {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut weights: Vec<(FunctionKind, usize)> = Vec::new();
+        for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            let mut parts = item.splitn(2, '=');
+            let kind: FunctionKind = parts.next().unwrap_or_default().trim().parse()?;
+            let weight = match parts.next() {
+                Some(weight) => weight
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid weight of {}: {}", kind.name(), weight))?,
+                None => 1,
+            };
+            ensure!(
+                weights.iter().all(|(k, _)| *k != kind),
+                "Duplicated function in mix: {}",
+                kind.name()
+            );
+            if weight > 0 {
+                weights.push((kind, weight));
+            }
+        }
+        ensure!(!weights.is_empty(), "Empty task mix");
+        Ok(Self { weights })
+    }
+}
+
+impl fmt::Display for Mix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let items: Vec<String> = self
+            .weights
+            .iter()
+            .map(|(kind, weight)| format!("{}={}", kind.name(), weight))
+            .collect();
+        write!(f, "{}", items.join(","))
+    }
+}
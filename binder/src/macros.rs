@@ -101,13 +101,17 @@ macro_rules! register_ecall_handler {
 
             log::trace!("tee receive cmd: {:x}, input_buf = {:?}", cmd, input_buf);
 
-            let inner_vec = unsafe {
-                match ecall_ipc_lib_dispatcher(cmd, input_buf) {
-                    Ok(out) => out,
-                    Err(e) => {
-                        log::error!("tee execute cmd: {:x}, error: {}", cmd, e);
-                        return teaclave_types::ECallStatus(teaclave_types::ES_ERR_GENERAL);
-                    }
+            // A panic is logged by the panic hook of the enclave and returned
+            // as a general error, instead of unwinding out of the ECALL.
+            let inner_vec = match std::panic::catch_unwind(|| ecall_ipc_lib_dispatcher(cmd, input_buf)) {
+                Ok(Ok(out)) => out,
+                Ok(Err(e)) => {
+                    log::error!("tee execute cmd: {:x}, error: {}", cmd, e);
+                    return teaclave_types::ECallStatus(teaclave_types::ES_ERR_GENERAL);
+                }
+                Err(_) => {
+                    log::error!("tee execute cmd: {:x}, panicked", cmd);
+                    return teaclave_types::ECallStatus(teaclave_types::ES_ERR_GENERAL);
                 }
             };
 
@@ -0,0 +1,288 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use structopt::StructOpt;
+use teaclave_client_sdk::{
+    AuthenticationService, ClientKey, EnclaveInfo, FileCrypto, FrontendClient, FrontendService,
+    FunctionCapabilities, FunctionInput, FunctionOutput, KeyHelper, RegisterFunctionRequest,
+    ResourceProfile, SignatureScheme,
+};
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct ConnectOpt {
+    /// Address of the authentication service
+    #[structopt(long = "authentication-address")]
+    authentication_address: String,
+
+    /// Address of the frontend service
+    #[structopt(long = "frontend-address")]
+    frontend_address: String,
+
+    /// Path of enclave info
+    #[structopt(long = "enclave-info")]
+    enclave_info: PathBuf,
+
+    /// CA cert of attestation service for verifying the attestation report
+    #[structopt(long = "as-ca-cert")]
+    as_ca_cert: PathBuf,
+
+    /// User ID to log in with
+    #[structopt(long = "user-id")]
+    user_id: String,
+
+    /// Password of the user
+    #[structopt(long = "user-password")]
+    user_password: String,
+
+    /// Client certificate chain in PEM, presented to the services whose
+    /// deployment requires client certificates
+    #[structopt(long = "client-cert", requires = "key_helper")]
+    client_cert: Option<PathBuf>,
+
+    /// Helper program signing with the key of the client certificate, which
+    /// is kept in a hardware token
+    #[structopt(long = "key-helper")]
+    key_helper: Option<PathBuf>,
+
+    /// Signature scheme of the client key, supported schemes are
+    /// "ecdsa-p256-sha256", "ecdsa-p384-sha384", "rsa-pss-sha256",
+    /// "rsa-pkcs1-sha256" and "ed25519".
+    #[structopt(long = "key-scheme", default_value = "ecdsa-p256-sha256", parse(try_from_str = parse_scheme))]
+    key_scheme: SignatureScheme,
+}
+
+fn parse_scheme(src: &str) -> Result<SignatureScheme> {
+    let scheme = match src {
+        "ecdsa-p256-sha256" => SignatureScheme::ECDSA_NISTP256_SHA256,
+        "ecdsa-p384-sha384" => SignatureScheme::ECDSA_NISTP384_SHA384,
+        "rsa-pss-sha256" => SignatureScheme::RSA_PSS_SHA256,
+        "rsa-pkcs1-sha256" => SignatureScheme::RSA_PKCS1_SHA256,
+        "ed25519" => SignatureScheme::ED25519,
+        _ => anyhow::bail!("Unsupported signature scheme: {}", src),
+    };
+    Ok(scheme)
+}
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct ExportOpt {
+    #[structopt(flatten)]
+    connect: ConnectOpt,
+
+    /// Name of the bundle, e.g., the project the assets belong to
+    #[structopt(long)]
+    namespace: String,
+
+    /// ID of a function to export
+    #[structopt(required = true, long = "function-id")]
+    function_ids: Vec<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct ImportOpt {
+    #[structopt(flatten)]
+    connect: ConnectOpt,
+
+    /// Path of the bundle
+    #[structopt(short, long)]
+    bundle: PathBuf,
+}
+
+/// Function definition as registered on the exporting deployment.
+#[derive(Debug, Serialize, Deserialize)]
+struct FunctionEntry {
+    id: String,
+    name: String,
+    description: String,
+    executor_type: String,
+    public: bool,
+    /// Payload in the hex format
+    payload: String,
+    arguments: Vec<String>,
+    inputs: Vec<FunctionInput>,
+    outputs: Vec<FunctionOutput>,
+    #[serde(default)]
+    resource_profile: ResourceProfile,
+    #[serde(default)]
+    entry_points: Vec<String>,
+    #[serde(default)]
+    capabilities: Option<FunctionCapabilities>,
+    #[serde(default)]
+    worker_measurements: Vec<String>,
+}
+
+/// Data registration of an owner, identified by a name unique in the bundle,
+/// e.g., the data ID on the exporting deployment.
+#[derive(Debug, Serialize, Deserialize)]
+struct InputFileEntry {
+    id: String,
+    url: String,
+    /// CMAC in the hex format
+    cmac: String,
+    crypto_info: FileCrypto,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OutputFileEntry {
+    id: String,
+    url: String,
+    crypto_info: FileCrypto,
+}
+
+/// Assets to be registered on another deployment. The platform never returns
+/// the location or the key of a data, so data registrations are added to the
+/// bundle by their owners rather than exported.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Bundle {
+    namespace: String,
+    functions: Vec<FunctionEntry>,
+    #[serde(default)]
+    input_files: Vec<InputFileEntry>,
+    #[serde(default)]
+    output_files: Vec<OutputFileEntry>,
+}
+
+/// IDs of the imported assets on the new deployment, keyed by their IDs in
+/// the bundle.
+#[derive(Debug, Default, Serialize)]
+struct ImportedIds {
+    functions: BTreeMap<String, String>,
+    input_files: BTreeMap<String, String>,
+    output_files: BTreeMap<String, String>,
+}
+
+pub(crate) fn connect(opt: &ConnectOpt) -> Result<FrontendClient> {
+    let enclave_info = EnclaveInfo::from_file(&opt.enclave_info)?;
+    let content = fs::read(&opt.as_ca_cert)?;
+    let as_root_ca_cert = pem::parse(content)?.contents;
+    let client_key = match (&opt.client_cert, &opt.key_helper) {
+        (Some(client_cert), Some(key_helper)) => {
+            let content = fs::read(client_cert)?;
+            let cert_chain = pem::parse_many(content)
+                .into_iter()
+                .map(|pem| pem.contents)
+                .collect();
+            let signer = KeyHelper::new(key_helper).scheme(opt.key_scheme);
+            Some(ClientKey::new(cert_chain, Arc::new(signer)))
+        }
+        _ => None,
+    };
+
+    let mut authentication_client = match &client_key {
+        Some(client_key) => AuthenticationService::connect_with_client_key(
+            &opt.authentication_address,
+            &enclave_info,
+            &as_root_ca_cert,
+            client_key,
+        )?,
+        None => AuthenticationService::connect(
+            &opt.authentication_address,
+            &enclave_info,
+            &as_root_ca_cert,
+        )?,
+    };
+    let token = authentication_client.user_login(&opt.user_id, &opt.user_password)?;
+
+    let mut client = match &client_key {
+        Some(client_key) => FrontendService::connect_with_client_key(
+            &opt.frontend_address,
+            &enclave_info,
+            &as_root_ca_cert,
+            client_key,
+        )?,
+        None => FrontendService::connect(&opt.frontend_address, &enclave_info, &as_root_ca_cert)?,
+    };
+    client.set_credential(&opt.user_id, &token);
+    Ok(client)
+}
+
+pub(crate) fn export(opt: ExportOpt) -> Result<String> {
+    let mut client = connect(&opt.connect)?;
+    let mut bundle = Bundle {
+        namespace: opt.namespace,
+        ..Default::default()
+    };
+
+    for function_id in opt.function_ids {
+        let function = client.get_function(&function_id)?;
+        bundle.functions.push(FunctionEntry {
+            id: function_id,
+            name: function.name,
+            description: function.description,
+            executor_type: function.executor_type.to_string(),
+            public: function.public,
+            payload: hex::encode(function.payload),
+            arguments: function.arguments,
+            inputs: function.inputs,
+            outputs: function.outputs,
+            resource_profile: function.resource_profile,
+            entry_points: function.entry_points,
+            capabilities: function.capabilities,
+            worker_measurements: function.worker_measurements,
+        });
+    }
+
+    Ok(serde_json::to_string_pretty(&bundle)?)
+}
+
+pub(crate) fn import(opt: ImportOpt) -> Result<String> {
+    let content = fs::read(&opt.bundle)?;
+    let bundle: Bundle = serde_json::from_slice(&content)?;
+    let mut client = connect(&opt.connect)?;
+    let mut imported = ImportedIds::default();
+
+    for function in bundle.functions {
+        let mut request = RegisterFunctionRequest::new()
+            .name(function.name)
+            .description(function.description)
+            .executor_type(function.executor_type.as_str().try_into()?)
+            .public(function.public)
+            .payload(hex::decode(function.payload)?)
+            .arguments(function.arguments)
+            .inputs(function.inputs)
+            .outputs(function.outputs)
+            .resource_profile(function.resource_profile)
+            .entry_points(function.entry_points)
+            .worker_measurements(function.worker_measurements);
+        if let Some(capabilities) = function.capabilities {
+            request = request.capabilities(capabilities);
+        }
+        let response = client.register_function_with_request(request)?;
+        imported
+            .functions
+            .insert(function.id, response.function_id.to_string());
+    }
+
+    for file in bundle.input_files {
+        let cmac = hex::decode(&file.cmac)?;
+        let data_id = client.register_input_file(&file.url, &cmac, file.crypto_info)?;
+        imported.input_files.insert(file.id, data_id);
+    }
+
+    for file in bundle.output_files {
+        let data_id = client.register_output_file(&file.url, file.crypto_info)?;
+        imported.output_files.insert(file.id, data_id);
+    }
+
+    Ok(serde_json::to_string_pretty(&imported)?)
+}
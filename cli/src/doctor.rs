@@ -0,0 +1,471 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use anyhow::{anyhow, bail, Result};
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use structopt::StructOpt;
+use teaclave_config::RuntimeConfig;
+
+const AESM_SOCKET: &str = "/var/run/aesmd/aesm.socket";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+// PSW version the SGX SDK used by Teaclave is released with.
+const MIN_PSW_VERSION: (u32, u32) = (2, 9);
+// Attestation reports and certificates are rejected by peers whose clocks
+// differ more than this.
+const MAX_CLOCK_SKEW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct DoctorOpt {
+    /// Runtime config of the deployment, checks of service ports and the
+    /// attestation service are skipped without it
+    #[structopt(short, long = "runtime-config")]
+    runtime_config: Option<PathBuf>,
+
+    /// SGX mode the services are built with, "HW" or "SW", defaults to the
+    /// SGX_MODE environment variable or "HW"
+    #[structopt(long = "sgx-mode")]
+    sgx_mode: Option<String>,
+}
+
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+struct Diagnosis {
+    failures: usize,
+    warnings: usize,
+}
+
+impl Diagnosis {
+    fn new() -> Self {
+        Self {
+            failures: 0,
+            warnings: 0,
+        }
+    }
+
+    fn report(&mut self, status: Status, check: &str, message: &str, fix: &str) {
+        let tag = match status {
+            Status::Ok => "ok",
+            Status::Warn => {
+                self.warnings += 1;
+                "warn"
+            }
+            Status::Fail => {
+                self.failures += 1;
+                "fail"
+            }
+        };
+        println!("[{:>4}] {}: {}", tag, check, message);
+        if !fix.is_empty() {
+            println!("       fix: {}", fix);
+        }
+    }
+
+    fn ok(&mut self, check: &str, message: &str) {
+        self.report(Status::Ok, check, message, "");
+    }
+
+    fn warn(&mut self, check: &str, message: &str, fix: &str) {
+        self.report(Status::Warn, check, message, fix);
+    }
+
+    fn fail(&mut self, check: &str, message: &str, fix: &str) {
+        self.report(Status::Fail, check, message, fix);
+    }
+}
+
+pub(crate) fn doctor(opt: DoctorOpt) -> Result<String> {
+    let sgx_mode = opt
+        .sgx_mode
+        .or_else(|| env::var("SGX_MODE").ok())
+        .unwrap_or_else(|| "HW".to_string());
+    let hardware = match sgx_mode.as_str() {
+        "HW" => true,
+        "SW" => false,
+        _ => bail!("Invalid SGX mode {}, expect HW or SW", sgx_mode),
+    };
+
+    let mut diagnosis = Diagnosis::new();
+    check_sgx_mode(&mut diagnosis, hardware);
+    if hardware {
+        check_sgx_driver(&mut diagnosis);
+        check_psw(&mut diagnosis);
+        check_aesm(&mut diagnosis);
+    }
+
+    match opt.runtime_config {
+        Some(path) => match RuntimeConfig::from_toml(&path) {
+            Ok(config) => {
+                diagnosis.ok("runtime config", &format!("{} is valid", path.display()));
+                check_attestation(&mut diagnosis, &config);
+                check_service_ports(&mut diagnosis, &config);
+                check_clock_skew(&mut diagnosis, &config.attestation.url);
+            }
+            Err(e) => diagnosis.fail(
+                "runtime config",
+                &format!("{}: {:#}", path.display(), e),
+                "correct the config file, relative paths of the audit section are resolved \
+                 from the working directory of the services",
+            ),
+        },
+        None => diagnosis.warn(
+            "runtime config",
+            "not given, skip checks of service ports and attestation",
+            "pass the config of the deployment with --runtime-config",
+        ),
+    }
+
+    if diagnosis.failures > 0 {
+        bail!(
+            "Found {} problem(s) and {} warning(s).",
+            diagnosis.failures,
+            diagnosis.warnings
+        );
+    }
+    Ok(format!(
+        "No problem found, {} warning(s).",
+        diagnosis.warnings
+    ))
+}
+
+fn check_sgx_mode(diagnosis: &mut Diagnosis, hardware: bool) {
+    let has_device = sgx_device().is_some();
+    let sgx_sdk = env::var("SGX_SDK").unwrap_or_else(|_| "/opt/sgxsdk".to_string());
+    let sim_urts = Path::new(&sgx_sdk).join("lib64/libsgx_urts_sim.so");
+    match (hardware, has_device) {
+        (true, true) => diagnosis.ok("SGX_MODE", "HW with an SGX device"),
+        (true, false) => diagnosis.fail(
+            "SGX_MODE",
+            "HW but no SGX device is found",
+            "install the SGX driver, or rebuild with -DSGX_SIM_MODE=ON and run with SGX_MODE=SW",
+        ),
+        (false, _) if !sim_urts.exists() => diagnosis.fail(
+            "SGX_MODE",
+            &format!("SW but {} does not exist", sim_urts.display()),
+            "install the SGX SDK and set SGX_SDK to its install directory",
+        ),
+        (false, true) => diagnosis.warn(
+            "SGX_MODE",
+            "SW on a machine with an SGX device, enclaves are not protected",
+            "rebuild without -DSGX_SIM_MODE and run with SGX_MODE=HW for production",
+        ),
+        (false, false) => diagnosis.ok("SGX_MODE", "SW, enclaves are not protected"),
+    }
+}
+
+fn sgx_device() -> Option<&'static str> {
+    // Devices of the in-kernel driver, the DCAP driver and the out-of-tree
+    // driver respectively.
+    ["/dev/sgx_enclave", "/dev/sgx/enclave", "/dev/isgx"]
+        .iter()
+        .find(|device| Path::new(device).exists())
+        .copied()
+}
+
+fn check_sgx_driver(diagnosis: &mut Diagnosis) {
+    let device = match sgx_device() {
+        Some(device) => device,
+        None => return,
+    };
+    let version = ["isgx", "intel_sgx"].iter().find_map(|module| {
+        fs::read_to_string(format!("/sys/module/{}/version", module))
+            .ok()
+            .map(|version| format!("{} {}", module, version.trim()))
+    });
+    let version = version.unwrap_or_else(|| "in-kernel driver".to_string());
+    diagnosis.ok("SGX driver", &format!("{} ({})", device, version));
+
+    if device == "/dev/isgx" {
+        diagnosis.warn(
+            "SGX driver",
+            "the out-of-tree driver does not support DCAP attestation",
+            "use the DCAP driver or a kernel with the in-kernel driver for sgx_ecdsa",
+        );
+    }
+}
+
+fn check_psw(diagnosis: &mut Diagnosis) {
+    let version =
+        installed_version("libsgx-urts").or_else(|| installed_version("libsgx-enclave-common"));
+    let version = match version {
+        Some(version) => version,
+        None => {
+            diagnosis.fail(
+                "SGX PSW",
+                "libsgx-urts is not installed",
+                "install the SGX PSW packages from https://download.01.org/intel-sgx/",
+            );
+            return;
+        }
+    };
+
+    let mut numbers = version.split(|c: char| !c.is_ascii_digit());
+    let major = numbers.next().and_then(|n| n.parse::<u32>().ok());
+    let minor = numbers.next().and_then(|n| n.parse::<u32>().ok());
+    match (major, minor) {
+        (Some(major), Some(minor)) if (major, minor) >= MIN_PSW_VERSION => {
+            diagnosis.ok("SGX PSW", &version)
+        }
+        _ => diagnosis.fail(
+            "SGX PSW",
+            &format!(
+                "{} is older than {}.{}",
+                version, MIN_PSW_VERSION.0, MIN_PSW_VERSION.1
+            ),
+            "upgrade the SGX PSW packages to the release of the SGX SDK",
+        ),
+    }
+}
+
+fn installed_version(package: &str) -> Option<String> {
+    let commands: [(&str, &[&str]); 2] = [
+        ("dpkg-query", &["-W", "-f=${Version}", package]),
+        ("rpm", &["-q", "--qf", "%{VERSION}", package]),
+    ];
+    commands.iter().find_map(|(program, args)| {
+        let output = process::Command::new(program).args(*args).output().ok()?;
+        let version = String::from_utf8(output.stdout).ok()?;
+        if output.status.success() && !version.trim().is_empty() {
+            Some(version.trim().to_string())
+        } else {
+            None
+        }
+    })
+}
+
+fn check_aesm(diagnosis: &mut Diagnosis) {
+    match UnixStream::connect(AESM_SOCKET) {
+        Ok(_) => diagnosis.ok("AESM", &format!("{} is reachable", AESM_SOCKET)),
+        Err(e) => diagnosis.fail(
+            "AESM",
+            &format!("cannot connect to {}: {}", AESM_SOCKET, e),
+            "start aesmd (e.g., systemctl start aesmd), and mount the socket into the \
+             container when running in docker",
+        ),
+    }
+}
+
+fn check_attestation(diagnosis: &mut Diagnosis, config: &RuntimeConfig) {
+    let overrides = ["AS_ALGO", "AS_URL", "AS_SPID", "AS_KEY"];
+    let set = overrides
+        .iter()
+        .filter(|name| env::var(name).is_ok())
+        .count();
+    if set != 0 && set != overrides.len() {
+        diagnosis.warn(
+            "attestation",
+            "only some of AS_ALGO, AS_URL, AS_SPID and AS_KEY are set, they are all ignored",
+            "set all of them to override the attestation section of the runtime config",
+        );
+    }
+
+    let attestation = &config.attestation;
+    if attestation.algorithm == "sgx_ecdsa"
+        && !Path::new("/dev/sgx/provision").exists()
+        && !Path::new("/dev/sgx_provision").exists()
+    {
+        diagnosis.fail(
+            "attestation",
+            "sgx_ecdsa requires an SGX provision device",
+            "install the DCAP driver, or use sgx_epid with an IAS subscription",
+        );
+    }
+
+    match attestation_address(&attestation.url) {
+        Ok(address) => match connect(&address) {
+            Ok(_) => diagnosis.ok(
+                "attestation",
+                &format!(
+                    "{} at {} is reachable",
+                    attestation.algorithm, attestation.url
+                ),
+            ),
+            Err(e) => diagnosis.fail(
+                "attestation",
+                &format!("cannot connect to {}: {}", attestation.url, e),
+                "check the url of the attestation section, and the proxy and firewall settings",
+            ),
+        },
+        Err(e) => diagnosis.fail(
+            "attestation",
+            &format!("{}: {}", attestation.url, e),
+            "correct the url of the attestation section",
+        ),
+    }
+}
+
+fn check_service_ports(diagnosis: &mut Diagnosis, config: &RuntimeConfig) {
+    let api = &config.api_endpoints;
+    let mut services = vec![
+        (
+            "frontend".to_string(),
+            local_address(api.frontend.listen_address),
+        ),
+        (
+            "authentication".to_string(),
+            local_address(api.authentication.listen_address),
+        ),
+    ];
+
+    let internal = &config.internal_endpoints;
+    for (name, endpoint) in &[
+        ("access_control", &internal.access_control),
+        ("internal authentication", &internal.authentication),
+        ("management", &internal.management),
+        ("storage", &internal.storage),
+        ("execution", &internal.execution),
+        ("scheduler", &internal.scheduler),
+    ] {
+        services.push((name.to_string(), endpoint.advertised_address.clone()));
+    }
+    for shard in &config.storage_shards {
+        let name = format!("storage shard {}", shard.name);
+        services.push((name, shard.advertised_address.clone()));
+    }
+
+    for (name, address) in services {
+        let check = format!("{} service", name);
+        match connect(&address) {
+            Ok(_) => diagnosis.ok(&check, &format!("{} is reachable", address)),
+            Err(e) => diagnosis.fail(
+                &check,
+                &format!("cannot connect to {}: {}", address, e),
+                "start the service, and check that the address is not blocked by a firewall \
+                 or taken by another program",
+            ),
+        }
+    }
+}
+
+fn check_clock_skew(diagnosis: &mut Diagnosis, url: &str) {
+    let time = match server_time(url) {
+        Ok(time) => time,
+        Err(e) => {
+            diagnosis.warn(
+                "clock",
+                &format!("cannot get the time of {}: {}", url, e),
+                "compare the clock with the attestation service manually",
+            );
+            return;
+        }
+    };
+
+    let now = SystemTime::now();
+    let skew = now
+        .duration_since(time)
+        .or_else(|_| time.duration_since(now))
+        .unwrap_or_default();
+    if skew > MAX_CLOCK_SKEW {
+        diagnosis.fail(
+            "clock",
+            &format!(
+                "differs from the attestation service by {}s",
+                skew.as_secs()
+            ),
+            "synchronize the clock with NTP (e.g., timedatectl set-ntp true)",
+        );
+    } else {
+        diagnosis.ok("clock", &format!("skew is {}s", skew.as_secs()));
+    }
+}
+
+// Reads the Date header of the attestation service, whose certificate is
+// verified with the system roots.
+fn server_time(url: &str) -> Result<SystemTime> {
+    let uri = url.parse::<http::Uri>()?;
+    if uri.scheme_str() != Some("https") {
+        bail!("not an https url");
+    }
+    let host = uri.host().ok_or_else(|| anyhow!("Invalid hostname."))?;
+    let address = attestation_address(url)?;
+
+    let mut config = rustls::ClientConfig::new();
+    config
+        .root_store
+        .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+    let hostname = webpki::DNSNameRef::try_from_ascii_str(host)?;
+    let mut session = rustls::ClientSession::new(&Arc::new(config), hostname);
+    let mut stream = connect(&address)?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+    let mut tls_stream = rustls::Stream::new(&mut session, &mut stream);
+    write!(
+        tls_stream,
+        "HEAD / HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        host
+    )?;
+
+    let mut response = Vec::new();
+    // Servers may close the connection without a close_notify.
+    let _ = tls_stream.read_to_end(&mut response);
+    let response = String::from_utf8_lossy(&response);
+    let date = response
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .find_map(|line| {
+            let (name, value) = line.split_at(line.find(':')?);
+            if name.eq_ignore_ascii_case("date") {
+                Some(value[1..].trim())
+            } else {
+                None
+            }
+        })
+        .ok_or_else(|| anyhow!("no Date header in the response"))?;
+
+    Ok(httpdate::parse_http_date(date)?)
+}
+
+fn attestation_address(url: &str) -> Result<String> {
+    let uri = url.parse::<http::Uri>()?;
+    let host = uri.host().ok_or_else(|| anyhow!("Invalid hostname."))?;
+    let port = match (uri.port_u16(), uri.scheme_str()) {
+        (Some(port), _) => port,
+        (None, Some("http")) => 80,
+        _ => 443,
+    };
+    Ok(format!("{}:{}", host, port))
+}
+
+// Services listening on all interfaces are checked through the loopback.
+fn local_address(mut address: SocketAddr) -> String {
+    if address.ip().is_unspecified() {
+        address.set_ip([127, 0, 0, 1].into());
+    }
+    address.to_string()
+}
+
+fn connect(address: &str) -> Result<TcpStream> {
+    let addrs = address.to_socket_addrs()?;
+    let mut error = anyhow!("cannot resolve {}", address);
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => error = e.into(),
+        }
+    }
+    Err(error)
+}
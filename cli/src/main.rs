@@ -25,9 +25,15 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use structopt::StructOpt;
 use teaclave_attestation::report::AttestationReport;
+use teaclave_client_sdk::{derive_file_crypto, KeyHelper};
 
 use teaclave_crypto::{AesGcm128Key, AesGcm256Key, TeaclaveFile128Key};
 
+mod bundle;
+mod doctor;
+mod simulate;
+mod snapshot;
+
 const FILE_AUTH_TAG_LENGTH: usize = 16;
 type CMac = [u8; FILE_AUTH_TAG_LENGTH];
 type KeyVec = Vec<u8>; // Need define a type to use parse derive macro
@@ -44,8 +50,18 @@ struct EncryptDecryptOpt {
     algorithm: String,
 
     /// Key in the hex format.
-    #[structopt(short, long, parse(try_from_str = decode_hex))]
-    key: KeyVec,
+    #[structopt(short, long, parse(try_from_str = decode_hex), required_unless = "key_helper")]
+    key: Option<KeyVec>,
+
+    /// Helper program deriving the key from a master key kept in a hardware
+    /// token, instead of giving the key.
+    #[structopt(long = "key-helper", conflicts_with = "key", requires = "key_context")]
+    key_helper: Option<PathBuf>,
+
+    /// Context the key is derived from, e.g., the name and version of the
+    /// file.
+    #[structopt(long = "key-context")]
+    key_context: Option<String>,
 
     /// IV for AES keys in the hex format.
     #[structopt(long, parse(try_from_str = decode_hex))]
@@ -107,6 +123,31 @@ enum Command {
     /// Display the attestation report of remote Teaclave services
     #[structopt(name = "attest")]
     Attest(AttestOpt),
+
+    /// Export functions of a deployment as a bundle
+    #[structopt(name = "export")]
+    Export(bundle::ExportOpt),
+
+    /// Register functions and data of a bundle on a deployment
+    #[structopt(name = "import")]
+    Import(bundle::ImportOpt),
+
+    /// Diagnose the environment of a deployment and suggest fixes
+    #[structopt(name = "doctor")]
+    Doctor(doctor::DoctorOpt),
+
+    /// Seal snapshots of the storage services and write their manifest
+    #[structopt(name = "snapshot")]
+    Snapshot(snapshot::SnapshotOpt),
+
+    /// Restore the storage services from the snapshots of a manifest
+    #[structopt(name = "restore")]
+    Restore(snapshot::RestoreOpt),
+
+    /// Project the wait times of a task arrival trace under scheduling
+    /// policies and numbers of workers
+    #[structopt(name = "simulate")]
+    Simulate(simulate::SimulateOpt),
 }
 
 #[derive(Debug, StructOpt)]
@@ -116,8 +157,31 @@ struct Opt {
     command: Command,
 }
 
+// Keys given by a key helper are derived in the hardware token for the file.
+fn file_key(opt: &EncryptDecryptOpt) -> Result<KeyVec> {
+    if let Some(key) = &opt.key {
+        return Ok(key.clone());
+    }
+    let key_helper = opt
+        .key_helper
+        .as_ref()
+        .ok_or_else(|| anyhow!("Key or key helper is required."))?;
+    let context = opt
+        .key_context
+        .as_ref()
+        .ok_or_else(|| anyhow!("Key context is required."))?;
+    let iv = opt.iv.clone().unwrap_or_default();
+    let crypto = derive_file_crypto(
+        &KeyHelper::new(key_helper),
+        &opt.algorithm,
+        context.as_bytes(),
+        &iv,
+    )?;
+    Ok(crypto.key_iv().0)
+}
+
 fn decrypt(opt: EncryptDecryptOpt) -> Result<CMac> {
-    let key = opt.key;
+    let key = file_key(&opt)?;
     let mut cmac: CMac = [0u8; FILE_AUTH_TAG_LENGTH];
     match opt.algorithm.as_str() {
         AesGcm128Key::SCHEMA => {
@@ -149,7 +213,7 @@ fn decrypt(opt: EncryptDecryptOpt) -> Result<CMac> {
 }
 
 fn encrypt(opt: EncryptDecryptOpt) -> Result<CMac> {
-    let key = opt.key;
+    let key = file_key(&opt)?;
     let mut cmac: CMac = [0u8; FILE_AUTH_TAG_LENGTH];
     match opt.algorithm.as_str() {
         AesGcm128Key::SCHEMA => {
@@ -293,6 +357,12 @@ fn main() -> Result<()> {
             }
         },
         Command::Attest(opt) => attest(opt)?,
+        Command::Export(opt) => println!("{}", bundle::export(opt)?),
+        Command::Import(opt) => println!("{}", bundle::import(opt)?),
+        Command::Doctor(opt) => println!("{}", doctor::doctor(opt)?),
+        Command::Snapshot(opt) => println!("{}", snapshot::snapshot(opt)?),
+        Command::Restore(opt) => println!("{}", snapshot::restore(opt)?),
+        Command::Simulate(opt) => println!("{}", simulate::simulate(opt)?),
     };
 
     Ok(())
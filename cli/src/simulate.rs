@@ -0,0 +1,397 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! What-if runs of the scheduler for capacity planning: a recorded trace of
+//! task arrivals is replayed against a number of workers and a scheduling
+//! policy, and the projected wait times of the tasks are reported. Workers
+//! pull a task whenever they are idle, like the execution service does, and
+//! each task runs for its recorded duration.
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use structopt::StructOpt;
+use teaclave_types::TaskPriority;
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct SimulateOpt {
+    /// Trace of task arrivals, one JSON object per line, e.g.,
+    /// {"arrival": 12.5, "duration": 30, "priority": "high", "user": "alice",
+    /// "locality": "zone-a"}, with times in seconds
+    #[structopt(short, long)]
+    trace: PathBuf,
+
+    /// Number of workers, each number given is simulated on its own
+    #[structopt(short, long = "workers", required = true)]
+    workers: Vec<usize>,
+
+    /// Scheduling policy, "priority", "fair-share" or "locality", each policy
+    /// given is simulated on its own
+    #[structopt(short, long = "policy", default_value = "priority")]
+    policies: Vec<Policy>,
+
+    /// Localities the workers are spread over in turn, e.g., "zone-a,zone-b"
+    #[structopt(long, use_delimiter = true)]
+    localities: Vec<String>,
+
+    /// Share of its duration a task runs longer on a worker away from its
+    /// locality
+    #[structopt(long = "remote-penalty", default_value = "0.25")]
+    remote_penalty: f64,
+
+    /// Print the report in JSON
+    #[structopt(long)]
+    json: bool,
+}
+
+#[derive(Debug, Copy, Clone, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum Policy {
+    /// Tasks of higher priorities first, in the order they arrived, with the
+    /// same turns against starvation as the scheduler service.
+    Priority,
+    /// Tasks of the user who has had the least run time so far first,
+    /// regardless of priorities.
+    FairShare,
+    /// Like priority, but a worker prefers the tasks of its own locality
+    /// among those of the priority it serves.
+    Locality,
+}
+
+impl FromStr for Policy {
+    type Err = anyhow::Error;
+
+    fn from_str(policy: &str) -> Result<Self> {
+        let policy = match policy {
+            "priority" => Policy::Priority,
+            "fair-share" => Policy::FairShare,
+            "locality" => Policy::Locality,
+            _ => bail!("Unsupported scheduling policy: {}", policy),
+        };
+        Ok(policy)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TraceRecord {
+    arrival: f64,
+    duration: f64,
+    #[serde(default)]
+    priority: Option<String>,
+    #[serde(default)]
+    user: String,
+    #[serde(default)]
+    locality: Option<String>,
+}
+
+// Times are kept in milliseconds, so that events are ordered exactly.
+struct Task {
+    arrival: u64,
+    duration: u64,
+    priority: TaskPriority,
+    user: String,
+    locality: Option<String>,
+}
+
+struct Worker {
+    locality: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct WaitStats {
+    tasks: usize,
+    mean: f64,
+    p50: f64,
+    p95: f64,
+    p99: f64,
+    max: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct Projection {
+    workers: usize,
+    policy: Policy,
+    wait: WaitStats,
+    wait_by_priority: Vec<(String, WaitStats)>,
+    makespan: f64,
+    utilization: f64,
+    remote_tasks: usize,
+}
+
+pub(crate) fn simulate(opt: SimulateOpt) -> Result<String> {
+    let tasks = read_trace(&opt.trace)?;
+    if opt.remote_penalty < 0.0 {
+        bail!("Remote penalty must not be negative");
+    }
+    let mut projections = Vec::new();
+    for &workers in &opt.workers {
+        if workers == 0 {
+            bail!("At least one worker is needed");
+        }
+        let workers = (0..workers)
+            .map(|i| Worker {
+                locality: match opt.localities.len() {
+                    0 => None,
+                    n => Some(opt.localities[i % n].clone()),
+                },
+            })
+            .collect::<Vec<_>>();
+        for &policy in &opt.policies {
+            projections.push(run(&tasks, &workers, policy, opt.remote_penalty));
+        }
+    }
+
+    if opt.json {
+        return Ok(serde_json::to_string_pretty(&projections)?);
+    }
+    let mut report = vec![format!(
+        "{:>7}  {:<10}  {:>9}  {:>9}  {:>9}  {:>9}  {:>9}  {:>10}  {:>5}  {:>6}",
+        "workers", "policy", "mean", "p50", "p95", "p99", "max", "makespan", "util", "remote"
+    )];
+    for projection in &projections {
+        let wait = &projection.wait;
+        report.push(format!(
+            "{:>7}  {:<10}  {:>9.1}  {:>9.1}  {:>9.1}  {:>9.1}  {:>9.1}  {:>10.1}  {:>4.0}%  {:>6}",
+            projection.workers,
+            policy_name(projection.policy),
+            wait.mean,
+            wait.p50,
+            wait.p95,
+            wait.p99,
+            wait.max,
+            projection.makespan,
+            projection.utilization * 100.0,
+            projection.remote_tasks,
+        ));
+        for (priority, wait) in &projection.wait_by_priority {
+            report.push(format!(
+                "{:>7}  {:<10}  {:>9.1}  {:>9.1}  {:>9.1}  {:>9.1}  {:>9.1}",
+                "", priority, wait.mean, wait.p50, wait.p95, wait.p99, wait.max,
+            ));
+        }
+    }
+    report.push(format!("{} tasks, wait times in seconds", tasks.len()));
+
+    Ok(report.join("\n"))
+}
+
+fn policy_name(policy: Policy) -> &'static str {
+    match policy {
+        Policy::Priority => "priority",
+        Policy::FairShare => "fair-share",
+        Policy::Locality => "locality",
+    }
+}
+
+fn read_trace(path: &Path) -> Result<Vec<Task>> {
+    let content = fs::read_to_string(path)?;
+    let mut tasks = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: TraceRecord = serde_json::from_str(line)
+            .map_err(|e| anyhow!("Invalid trace record on line {}: {}", i + 1, e))?;
+        if !(record.arrival >= 0.0 && record.duration >= 0.0) {
+            bail!("Invalid times of the trace record on line {}", i + 1);
+        }
+        let priority = match record.priority {
+            Some(priority) => TaskPriority::try_from(priority.as_str())?,
+            None => TaskPriority::default(),
+        };
+        tasks.push(Task {
+            arrival: (record.arrival * 1000.0).round() as u64,
+            duration: (record.duration * 1000.0).round() as u64,
+            priority,
+            user: record.user,
+            locality: record.locality,
+        });
+    }
+    if tasks.is_empty() {
+        bail!("No tasks in the trace");
+    }
+    // A stable sort keeps the order of tasks arriving at once.
+    tasks.sort_by_key(|task| task.arrival);
+
+    Ok(tasks)
+}
+
+fn run(tasks: &[Task], workers: &[Worker], policy: Policy, remote_penalty: f64) -> Projection {
+    let mut waits = vec![0; tasks.len()];
+    let mut pending: Vec<usize> = Vec::new();
+    let mut idle: Vec<usize> = (0..workers.len()).rev().collect();
+    let mut busy = BinaryHeap::new();
+    let mut usage: HashMap<&str, u64> = HashMap::new();
+    let mut pulls = 0;
+    let mut busy_time = 0;
+    let mut remote_tasks = 0;
+    let mut finish = 0;
+    let mut arrived = 0;
+    let mut now = tasks[0].arrival;
+
+    loop {
+        while arrived < tasks.len() && tasks[arrived].arrival <= now {
+            pending.push(arrived);
+            arrived += 1;
+        }
+        while let Some(&Reverse((free_at, worker))) = busy.peek() {
+            if free_at > now {
+                break;
+            }
+            busy.pop();
+            idle.push(worker);
+        }
+        // Idle workers pull in the order of their ids, the lowest first.
+        idle.sort_by(|a, b| b.cmp(a));
+        while !pending.is_empty() {
+            let worker = match idle.pop() {
+                Some(worker) => worker,
+                None => break,
+            };
+            let i = match policy {
+                Policy::Priority | Policy::Locality => {
+                    let order = TaskPriority::pull_order(pulls);
+                    pulls += 1;
+                    let locality = match policy {
+                        Policy::Locality => workers[worker].locality.as_deref(),
+                        _ => None,
+                    };
+                    pick_by_priority(tasks, &pending, &order, locality)
+                }
+                Policy::FairShare => pick_fair_share(tasks, &pending, &usage),
+            };
+            let index = pending.remove(i);
+            let task = &tasks[index];
+            let mut duration = task.duration;
+            if let (Some(locality), Some(home)) = (&task.locality, &workers[worker].locality) {
+                if locality != home {
+                    duration += (task.duration as f64 * remote_penalty).round() as u64;
+                    remote_tasks += 1;
+                }
+            }
+            waits[index] = now - task.arrival;
+            *usage.entry(task.user.as_str()).or_insert(0) += duration;
+            busy_time += duration;
+            finish = finish.max(now + duration);
+            busy.push(Reverse((now + duration, worker)));
+        }
+
+        let next_arrival = tasks.get(arrived).map(|task| task.arrival);
+        let next_free = busy.peek().map(|&Reverse((free_at, _))| free_at);
+        now = match (next_arrival, next_free) {
+            (Some(a), Some(f)) => a.min(f),
+            (Some(a), None) => a,
+            (None, Some(f)) if !pending.is_empty() => f,
+            _ => break,
+        };
+    }
+
+    let makespan = finish - tasks[0].arrival;
+    let wait_by_priority = TaskPriority::levels()
+        .iter()
+        .filter_map(|&priority| {
+            let waits = tasks
+                .iter()
+                .zip(waits.iter())
+                .filter(|(task, _)| task.priority == priority)
+                .map(|(_, &wait)| wait)
+                .collect::<Vec<_>>();
+            if waits.is_empty() {
+                None
+            } else {
+                Some((priority.to_string(), wait_stats(waits)))
+            }
+        })
+        .collect();
+    Projection {
+        workers: workers.len(),
+        policy,
+        wait: wait_stats(waits),
+        wait_by_priority,
+        makespan: makespan as f64 / 1000.0,
+        utilization: if makespan == 0 {
+            0.0
+        } else {
+            busy_time as f64 / (makespan * workers.len() as u64) as f64
+        },
+        remote_tasks,
+    }
+}
+
+// The earliest task of the first priority in the pull order with any pending,
+// preferring those of the locality when one is given.
+fn pick_by_priority(
+    tasks: &[Task],
+    pending: &[usize],
+    order: &[TaskPriority],
+    locality: Option<&str>,
+) -> usize {
+    for &priority in order {
+        let mut candidates = pending
+            .iter()
+            .enumerate()
+            .filter(|(_, &index)| tasks[index].priority == priority);
+        let first = match candidates.next() {
+            Some((i, _)) => i,
+            None => continue,
+        };
+        if let Some(locality) = locality {
+            let local = std::iter::once((first, &pending[first]))
+                .chain(candidates)
+                .find(|(_, &index)| tasks[index].locality.as_deref() == Some(locality));
+            if let Some((i, _)) = local {
+                return i;
+            }
+        }
+        return first;
+    }
+    0
+}
+
+// The earliest task of the user with the least run time so far.
+fn pick_fair_share(tasks: &[Task], pending: &[usize], usage: &HashMap<&str, u64>) -> usize {
+    pending
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, &index)| usage.get(tasks[index].user.as_str()).copied().unwrap_or(0))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+fn wait_stats(mut waits: Vec<u64>) -> WaitStats {
+    if waits.is_empty() {
+        return WaitStats::default();
+    }
+    waits.sort_unstable();
+    let percentile = |p: usize| {
+        let rank = (waits.len() * p + 99) / 100;
+        waits[rank.max(1) - 1] as f64 / 1000.0
+    };
+    WaitStats {
+        tasks: waits.len(),
+        mean: waits.iter().sum::<u64>() as f64 / waits.len() as f64 / 1000.0,
+        p50: percentile(50),
+        p95: percentile(95),
+        p99: percentile(99),
+        max: waits[waits.len() - 1] as f64 / 1000.0,
+    }
+}
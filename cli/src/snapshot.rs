@@ -0,0 +1,122 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::bundle::{connect, ConnectOpt};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use teaclave_client_sdk::{FileAuthTag, StorageSnapshot};
+use teaclave_crypto::TeaclaveFile128Key;
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct SnapshotOpt {
+    #[structopt(flatten)]
+    connect: ConnectOpt,
+
+    /// Name of the snapshots, under which the storage services keep them
+    #[structopt(long)]
+    name: String,
+
+    /// Path of the manifest to write, which holds the keys of the snapshots
+    #[structopt(short, long)]
+    output: PathBuf,
+}
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct RestoreOpt {
+    #[structopt(flatten)]
+    connect: ConnectOpt,
+
+    /// Path of the manifest written by the snapshot subcommand
+    #[structopt(short, long)]
+    manifest: PathBuf,
+}
+
+/// Snapshot of a storage service, which is opened with the key.
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotEntry {
+    name: String,
+    /// Key in the hex format
+    key: String,
+    /// CMAC in the hex format
+    cmac: String,
+    entries: u64,
+}
+
+/// Snapshots of the default storage and its shards in the order they are
+/// exported, which is the order they are restored in.
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotManifest {
+    name: String,
+    snapshots: Vec<SnapshotEntry>,
+}
+
+pub(crate) fn snapshot(opt: SnapshotOpt) -> Result<String> {
+    let mut client = connect(&opt.connect)?;
+    let snapshots = client.export_storage_snapshot(&opt.name)?;
+
+    let mut summary = Vec::new();
+    let mut manifest = SnapshotManifest {
+        name: opt.name,
+        snapshots: Vec::new(),
+    };
+    for snapshot in snapshots {
+        summary.push(format!("{}: {} keys", snapshot.name, snapshot.entries));
+        manifest.snapshots.push(SnapshotEntry {
+            name: snapshot.name,
+            key: hex::encode(snapshot.crypto_info.key),
+            cmac: snapshot.cmac.to_hex(),
+            entries: snapshot.entries,
+        });
+    }
+
+    // The keys open the whole storage, so only the owner can read them.
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&opt.output)?;
+    file.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+
+    Ok(summary.join("\n"))
+}
+
+pub(crate) fn restore(opt: RestoreOpt) -> Result<String> {
+    let content = fs::read(&opt.manifest)?;
+    let manifest: SnapshotManifest = serde_json::from_slice(&content)?;
+    let snapshots = manifest
+        .snapshots
+        .into_iter()
+        .map(|entry| {
+            Ok(StorageSnapshot::new(
+                entry.name,
+                TeaclaveFile128Key::new(&hex::decode(&entry.key)?)?,
+                FileAuthTag::from_hex(&entry.cmac)?,
+                entry.entries,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut client = connect(&opt.connect)?;
+    client.restore_storage_snapshot(snapshots)?;
+
+    Ok(format!("Restored snapshot {}", manifest.name))
+}
@@ -164,9 +164,43 @@ set(TARGET_SGXLIB_ENVS
     TRUSTED_TARGET_DIR=${TRUSTED_TARGET_DIR}
     TARGET=${TARGET})
 
+# Build metadata recorded in the enclave info along with the measurements, so
+# that a measurement can be traced back to the source it is built from.
+execute_process(
+  COMMAND ${GIT_EXECUTABLE} rev-parse HEAD
+  WORKING_DIRECTORY ${PROJECT_SOURCE_DIR}
+  OUTPUT_VARIABLE TEACLAVE_BUILD_GIT_COMMIT
+  OUTPUT_STRIP_TRAILING_WHITESPACE ERROR_QUIET)
+execute_process(
+  COMMAND ${GIT_EXECUTABLE} status --porcelain --untracked-files=no
+  WORKING_DIRECTORY ${PROJECT_SOURCE_DIR}
+  OUTPUT_VARIABLE _git_changes
+  OUTPUT_STRIP_TRAILING_WHITESPACE ERROR_QUIET)
+if(NOT "${_git_changes}" STREQUAL "")
+  set(TEACLAVE_BUILD_GIT_COMMIT "${TEACLAVE_BUILD_GIT_COMMIT}-dirty")
+endif()
+execute_process(
+  COMMAND ${GIT_EXECUTABLE} rev-parse HEAD
+  WORKING_DIRECTORY ${RUST_SGX_SDK}
+  OUTPUT_VARIABLE TEACLAVE_BUILD_RUST_SGX_SDK
+  OUTPUT_STRIP_TRAILING_WHITESPACE ERROR_QUIET)
+execute_process(
+  COMMAND rustup run ${RUSTUP_TOOLCHAIN} rustc --version
+  OUTPUT_VARIABLE TEACLAVE_BUILD_RUSTC
+  OUTPUT_STRIP_TRAILING_WHITESPACE ERROR_QUIET)
+
+set(TEACLAVE_BUILD_INFO_ENVS
+    "TEACLAVE_BUILD_GIT_COMMIT=${TEACLAVE_BUILD_GIT_COMMIT}"
+    "TEACLAVE_BUILD_RUSTC=${TEACLAVE_BUILD_RUSTC}"
+    "TEACLAVE_BUILD_RUST_SGX_SDK=${TEACLAVE_BUILD_RUST_SGX_SDK}"
+    "TEACLAVE_BUILD_SGX_SDK=${INTEL_SGX_SDK_VERSION}"
+    "TEACLAVE_BUILD_SGX_MODE=${SGX_MODE}"
+    "TEACLAVE_BUILD_TYPE=${TARGET}")
+
 message("SGX_SDK=${SGX_SDK}")
 message("SGX_MODE=${SGX_MODE}")
 message("RUSTUP_TOOLCHAIN=${RUSTUP_TOOLCHAIN}")
 message("DCAP=${DCAP}")
 message("BUILD_TYPE=${TARGET}")
+message("GIT_COMMIT=${TEACLAVE_BUILD_GIT_COMMIT}")
 message("TEACLAVE_SYMLINKS=${TEACLAVE_SYMLINKS}")
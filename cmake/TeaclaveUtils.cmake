@@ -208,6 +208,11 @@ function(add_sgx_build_target sgx_lib_path pkg_name)
     set(_enclave_info "/dev/null")
   endif()
 
+  join_string(
+    "${CARGO_BUILD_FLAGS};${SGX_ENCLAVE_FEATURES};${MTEE_EXTRA_CARGO_FLAGS};${RUSTFLAGS}"
+    " " _build_flags)
+  string(STRIP "${_build_flags}" _build_flags)
+
   add_custom_target(
     ${_target_name} ALL
     COMMAND
@@ -221,7 +226,8 @@ function(add_sgx_build_target sgx_lib_path pkg_name)
       CUR_PKG_PATH=${sgx_lib_path} CUR_INSTALL_DIR=${_copy_dir}
       ${MT_SCRIPT_DIR}/sgx_link_sign.sh ${_edl_lib_name} ${_depends}
     COMMAND
-      cat ${TEACLAVE_OUT_DIR}/${pkg_name}.meta.txt | python
+      cat ${TEACLAVE_OUT_DIR}/${pkg_name}.meta.txt | ${CMAKE_COMMAND} -E env
+      ${TEACLAVE_BUILD_INFO_ENVS} "TEACLAVE_BUILD_FLAGS=${_build_flags}" python
       ${MT_SCRIPT_DIR}/gen_enclave_info_toml.py ${pkg_name_no_enclave} >
       ${_enclave_info}
     COMMENT "Building ${_target_name}, enclave info to ${_enclave_info}"
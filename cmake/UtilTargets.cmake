@@ -85,6 +85,9 @@ endif()
 add_custom_target(run-examples COMMAND ${TEACLAVE_COMMON_ENVS}
   ${MT_SCRIPT_DIR}/test.sh example)
 
+add_custom_target(run-benchmarks COMMAND ${TEACLAVE_COMMON_ENVS}
+  ${MT_SCRIPT_DIR}/test.sh benchmark)
+
 add_custom_target(cov COMMAND ${TEACLAVE_COMMON_ENVS}
                               ${MT_SCRIPT_DIR}/gen_cov.sh)
 
@@ -17,6 +17,7 @@
 # specific language governing permissions and limitations
 # under the License.
 
+import os
 import sys
 
 
@@ -39,3 +40,23 @@ sys.stdout.write("""[{}]
 mr_enclave = "{}"
 mr_signer  = "{}"
 """.format(sys.argv[1], mr_enclave_hex, mr_signer_hex))
+
+# Build metadata is passed in the environment by CMake, and signed by the
+# auditors along with the measurements.
+build_info = [
+    ("git_commit", "TEACLAVE_BUILD_GIT_COMMIT"),
+    ("rustc", "TEACLAVE_BUILD_RUSTC"),
+    ("rust_sgx_sdk", "TEACLAVE_BUILD_RUST_SGX_SDK"),
+    ("sgx_sdk", "TEACLAVE_BUILD_SGX_SDK"),
+    ("sgx_mode", "TEACLAVE_BUILD_SGX_MODE"),
+    ("build_type", "TEACLAVE_BUILD_TYPE"),
+    ("build_flags", "TEACLAVE_BUILD_FLAGS"),
+]
+
+sys.stdout.write("\n[{}.build]\n".format(sys.argv[1]))
+for key, env in build_info:
+    value = os.environ.get(env, "").strip()
+    sys.stdout.write('{} = "{}"\n'.format(
+        key,
+        value.replace("\\", "\\\\").replace('"', '\\"')))
+sys.stdout.write("\n")
@@ -96,6 +96,8 @@ DEFAULT_EDL_LIB = "Enclave_common_t"
 PKG_NAME_TO_EDL_LIB = {
     "teaclave_unit_tests_enclave": "Enclave_fa_t",
     "teaclave_execution_service_enclave": "Enclave_fa_t",
+    "teaclave_management_service_enclave": "Enclave_fa_t",
+    "teaclave_storage_service_enclave": "Enclave_fa_t",
 }
 
 
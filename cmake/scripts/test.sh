@@ -249,6 +249,32 @@ run_examples() {
   cleanup
 }
 
+run_benchmarks() {
+  trap cleanup INT TERM ERR
+
+  echo_title "benchmarks"
+  pushd ${TEACLAVE_SERVICE_INSTALL_DIR}
+  ./teaclave_authentication_service &
+  ./teaclave_storage_service &
+  sleep 3    # wait for authentication and storage service
+  ./teaclave_management_service &
+  ./teaclave_scheduler_service &
+  sleep 3    # wait for management service and scheduler_service
+  ./teaclave_access_control_service &
+  ./teaclave_frontend_service &
+  sleep 3    # wait for other services
+  ./teaclave_execution_service &
+  sleep 3    # wait for execution services
+  popd
+
+  pushd ${TEACLAVE_PROJECT_ROOT}/benchmarks
+  RUSTFLAGS=${RUSTFLAGS} cargo run --release
+  popd
+
+  # kill all background services
+  cleanup
+}
+
 case "$1" in
     "unit")
         run_unit_tests
@@ -265,6 +291,9 @@ case "$1" in
     "example")
         run_examples
         ;;
+    "benchmark")
+        run_benchmarks
+        ;;
     *)
         run_unit_tests
         run_integration_tests
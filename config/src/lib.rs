@@ -33,4 +33,10 @@ pub(crate) enum ConfigSource {
 pub mod build;
 mod runtime;
 
-pub use runtime::RuntimeConfig;
+pub use runtime::{
+    ArchiveConfig, ArchiveCredentialConfig, CgroupClassConfig, CgroupsConfig, ExecutorsConfig,
+    ExternalPolicyConfig, ExternalPolicyKind, FederationConfig, FederationPeerConfig, LimitsConfig,
+    PushInputsConfig, QosClassConfig, QosConfig, RegistrationConfig, RegistrationVerifierKind,
+    RuntimeConfig, StorageBackendConfig, StorageBackendKind, StorageCompactionConfig,
+    StorageGcConfig, StorageShardConfig, StorageWalConfig,
+};
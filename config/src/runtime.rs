@@ -25,6 +25,7 @@ use std::untrusted::fs;
 
 use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::net;
 use std::path::{Path, PathBuf};
@@ -38,6 +39,39 @@ pub struct RuntimeConfig {
     pub audit: AuditConfig,
     pub attestation: AttestationServiceConfig,
     pub mount: MountConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub executors: ExecutorsConfig,
+    #[serde(default)]
+    pub storage_shards: Vec<StorageShardConfig>,
+    #[serde(default)]
+    pub storage_replicas: Vec<StorageReplicaConfig>,
+    /// Advertised address of the primary storage followed by a storage service
+    /// serving a read replica, which is set from the TEACLAVE_STORAGE_REPLICA
+    /// environment variable instead of the config file.
+    #[serde(default)]
+    pub storage_replica_primary: Option<String>,
+    #[serde(default)]
+    pub storage_gc: StorageGcConfig,
+    #[serde(default)]
+    pub storage_wal: Option<StorageWalConfig>,
+    #[serde(default)]
+    pub storage_backend: StorageBackendConfig,
+    #[serde(default)]
+    pub archive: ArchiveConfig,
+    #[serde(default)]
+    pub external_policy: Option<ExternalPolicyConfig>,
+    #[serde(default)]
+    pub push_inputs: Option<PushInputsConfig>,
+    #[serde(default)]
+    pub cgroups: Option<CgroupsConfig>,
+    #[serde(default)]
+    pub federation: Option<FederationConfig>,
+    #[serde(default)]
+    pub qos: Option<QosConfig>,
+    #[serde(default)]
+    pub registration: Option<RegistrationConfig>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -92,6 +126,366 @@ pub struct MountConfig {
     pub fusion_base_dir: PathBuf,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LimitsConfig {
+    pub max_function_payload_size: usize,
+    pub max_function_arguments: usize,
+    pub max_function_arguments_size: usize,
+    pub max_task_disk_usage: u64,
+    pub max_tenant_disk_usage: u64,
+    pub function_payload_cache_size: usize,
+    pub tenant_payload_cache_size: usize,
+    pub max_list_page_size: usize,
+    pub max_task_heap_size: u64,
+    pub max_task_timeout: u64,
+    pub task_timeout_grace_period: u64,
+    pub max_task_participants: usize,
+    pub max_file_owners: usize,
+    pub max_task_files: usize,
+    pub max_batch_tasks: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_function_payload_size: 256 * 1024,
+            max_function_arguments: 64,
+            max_function_arguments_size: 64 * 1024,
+            max_task_disk_usage: 1024 * 1024 * 1024,
+            max_tenant_disk_usage: 2 * 1024 * 1024 * 1024,
+            function_payload_cache_size: 64 * 1024 * 1024,
+            tenant_payload_cache_size: 16 * 1024 * 1024,
+            max_list_page_size: 100,
+            max_task_heap_size: 256 * 1024 * 1024,
+            max_task_timeout: 3600,
+            task_timeout_grace_period: 30,
+            max_task_participants: 16,
+            max_file_owners: 8,
+            max_task_files: 64,
+            max_batch_tasks: 64,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ExecutorsConfig {
+    pub allowed: Vec<String>,
+}
+
+impl Default for ExecutorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed: vec!["builtin".to_string(), "mesapy".to_string()],
+        }
+    }
+}
+
+/// Storage service storing the keys starting with one of the key prefixes,
+/// instead of the storage service of the internal endpoints.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StorageShardConfig {
+    pub name: String,
+    pub key_prefixes: Vec<String>,
+    pub listen_address: net::SocketAddr,
+    pub advertised_address: String,
+}
+
+/// Read-only replica of the storage service of the internal endpoints, which
+/// follows its writes and serves queries tolerating stale values.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StorageReplicaConfig {
+    pub name: String,
+    pub listen_address: net::SocketAddr,
+    pub advertised_address: String,
+}
+
+/// Purging of the soft-deleted records, e.g., deleted files, and of the
+/// expired keys by the storage service. Read replicas purge nothing but apply
+/// the purges of the primary.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct StorageGcConfig {
+    /// Seconds a deleted record is kept before it is purged
+    pub retention: u64,
+    /// Seconds between two runs of the purge
+    pub interval: u64,
+}
+
+impl Default for StorageGcConfig {
+    fn default() -> Self {
+        Self {
+            retention: 7 * 24 * 3600,
+            interval: 3600,
+        }
+    }
+}
+
+/// Write-ahead log of the storage service, which recovers its keys from the
+/// log on restart. Keys are only kept in memory without it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StorageWalConfig {
+    /// Directory of the sealed log, in which each storage shard keeps its log
+    /// in a subdirectory named after the shard
+    pub dir: PathBuf,
+    #[serde(default)]
+    pub compaction: StorageCompactionConfig,
+}
+
+/// Compaction of the write-ahead log, which reclaims the bytes of overwritten
+/// and deleted keys once they pass both thresholds.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct StorageCompactionConfig {
+    /// Seconds between two checks of the dead bytes
+    pub interval: u64,
+    /// Dead bytes of the log, below which it is not compacted
+    pub min_dead_bytes: u64,
+    /// Share of the dead bytes in the log, below which it is not compacted
+    pub min_dead_ratio: f64,
+}
+
+impl Default for StorageCompactionConfig {
+    fn default() -> Self {
+        Self {
+            interval: 60,
+            min_dead_bytes: 64 * 1024 * 1024,
+            min_dead_ratio: 0.5,
+        }
+    }
+}
+
+/// Persistence layer of the storage service, in which it keeps its keys.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct StorageBackendConfig {
+    pub kind: StorageBackendKind,
+    /// Directory of the sealed files of the `leveldb` backend, or of the
+    /// sealed manifest of the `remote` backend, in which each storage shard
+    /// keeps its files in a subdirectory named after the shard. Keys of the
+    /// `leveldb` backend are only kept in memory if not set.
+    pub dir: Option<PathBuf>,
+    /// Base URL of the encrypted checkpoints of the `remote` backend, e.g.,
+    /// `s3://bucket/storage/`
+    pub url: Option<String>,
+    pub credential: Option<ArchiveCredentialConfig>,
+    /// Seconds between two checkpoints of the `remote` backend
+    pub sync_interval: u64,
+}
+
+impl Default for StorageBackendConfig {
+    fn default() -> Self {
+        Self {
+            kind: StorageBackendKind::default(),
+            dir: None,
+            url: None,
+            credential: None,
+            sync_interval: 60,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackendKind {
+    /// LevelDB, in memory or in files sealed with the protected file system
+    /// of SGX.
+    Leveldb,
+    /// Sorted map in memory, for tests.
+    Memory,
+    /// Keys in memory, which are checkpointed to an object storage encrypted.
+    Remote,
+}
+
+impl Default for StorageBackendKind {
+    fn default() -> Self {
+        StorageBackendKind::Leveldb
+    }
+}
+
+/// Object storage keeping the archived tasks, which are only archived and
+/// restored by the admins.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    /// Base URL of the archives, e.g., `s3://bucket/archive/`. Tasks are not
+    /// archived if not set.
+    pub url: Option<String>,
+    pub admins: Vec<String>,
+    pub credential: Option<ArchiveCredentialConfig>,
+}
+
+/// Credential of an object storage, e.g., the archive storage, in the schemas
+/// of the file credentials, i.e., "bearer", "aws_s3" and "azure_sas".
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ArchiveCredentialConfig {
+    pub schema: String,
+    pub access_key_id: String,
+    pub secret: String,
+    pub region: String,
+    pub endpoint: String,
+}
+
+impl std::fmt::Debug for ArchiveCredentialConfig {
+    // Never log secrets.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "ArchiveCredentialConfig({})", self.schema)
+    }
+}
+
+/// Policy decision service outside of Teaclave deciding some of the requests
+/// of the access control service, if it is built with the `external_policy`
+/// feature.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExternalPolicyConfig {
+    pub kind: ExternalPolicyKind,
+    /// Address of the service, e.g., `localhost:8181`
+    pub address: String,
+    /// Names of the delegated requests, e.g., `user_access_function`
+    pub decisions: Vec<String>,
+    /// Path of the decision in the data API of OPA, e.g.,
+    /// `teaclave/allow`
+    #[serde(default)]
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalPolicyKind {
+    /// An enclave listed in the enclave info as
+    /// `teaclave_policy_decision_service`, connected with mutual attestation.
+    /// Its decisions replace the ones of the access control service.
+    Attested,
+    /// An Open Policy Agent sidecar, connected without attestation. Its
+    /// decisions can only deny what the access control service accepts.
+    Opa,
+}
+
+/// Upload endpoint of the worker host, to which clients push the inputs
+/// registered with the `push:` url instead of the worker downloading them.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PushInputsConfig {
+    pub listen_address: net::SocketAddr,
+    /// Base url of the endpoint given to clients, e.g.,
+    /// `https://worker.example.com:8443/`
+    pub advertised_url: String,
+}
+
+/// Cgroups (v2) of the execution service host weighting the untrusted-side
+/// work of tasks, i.e., the transfers of the file agent and the enclave host
+/// thread running the task, by the priority of the task.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CgroupsConfig {
+    /// Cgroup delegated to the service, e.g., `/sys/fs/cgroup/teaclave`, under
+    /// which a cgroup is created for each class.
+    pub base_dir: PathBuf,
+    /// Classes named by the task priorities, i.e., "low", "normal" and
+    /// "high". Tasks of a priority without a class are not moved.
+    pub classes: HashMap<String, CgroupClassConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct CgroupClassConfig {
+    /// `cpu.weight` of the cgroup, from 1 to 10000.
+    pub cpu_weight: u64,
+    /// Best-effort I/O priority of the threads, from 0 (highest) to 7.
+    pub io_priority: u8,
+}
+
+/// Teaclave deployments of other organizations to which the management service
+/// delegates tasks, and which delegate tasks to it. Both run the management
+/// enclave of the same enclave info, which is checked by mutual attestation.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FederationConfig {
+    /// Name of this deployment known by the peers.
+    pub name: String,
+    pub peers: Vec<FederationPeerConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FederationPeerConfig {
+    pub name: String,
+    /// Address of the internal management endpoint of the peer, e.g.,
+    /// `teaclave.example.com:17777`
+    pub advertised_address: String,
+}
+
+/// Quality of service classes of the users, e.g., of interactive and batch
+/// tenants, each admitted at a rate of its own by the frontend service and
+/// staged at the priorities of its own by the management service.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QosConfig {
+    /// Class of the users not assigned one.
+    pub default_class: String,
+    pub classes: HashMap<String, QosClassConfig>,
+    /// Classes of the users by their user ids.
+    #[serde(default)]
+    pub users: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QosClassConfig {
+    /// Requests a user of the class is admitted per second on average.
+    pub requests_per_second: u64,
+    /// Requests a user of the class is admitted at once above the rate.
+    pub burst: u64,
+    /// Highest priority the tasks of the users are staged at, i.e., "low",
+    /// "normal" or "high". Tasks asking for higher ones are staged at it.
+    #[serde(default = "default_qos_max_priority")]
+    pub max_priority: String,
+}
+
+fn default_qos_max_priority() -> String {
+    "high".to_string()
+}
+
+/// Out-of-band verification of the users registering themselves with the
+/// authentication service, who cannot log in until they confirm the code sent
+/// to the contact given at the registration.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegistrationConfig {
+    pub verifier: RegistrationVerifierKind,
+    /// Address of the verifier, e.g., `localhost:8025`, whose host is a DNS
+    /// name.
+    pub address: String,
+    /// PEM encoded certificate the verifier has to present, which is pinned
+    /// instead of verified against a CA.
+    #[serde(rename(serialize = "server_cert", deserialize = "server_cert"))]
+    server_cert_source: ConfigSource,
+    #[serde(default = "Default::default")]
+    pub server_cert_bytes: Vec<u8>,
+    /// Path the codes are posted to, e.g., `/teaclave/verify`
+    #[serde(default)]
+    pub path: String,
+    /// Seconds a code can be confirmed in, after which the user id can be
+    /// registered again.
+    #[serde(default = "default_registration_code_ttl")]
+    pub code_ttl: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RegistrationVerifierKind {
+    /// An HTTPS endpoint delivering the codes, e.g., a mail relay, to which the
+    /// user id, the contact and the code are posted as JSON.
+    Webhook,
+}
+
+fn default_registration_code_ttl() -> u64 {
+    3600
+}
+
+impl QosConfig {
+    /// Class of the user, which is the default class if the user is not
+    /// assigned one.
+    pub fn class_of(&self, user_id: &str) -> Option<&QosClassConfig> {
+        let name = self.users.get(user_id).unwrap_or(&self.default_class);
+        self.classes.get(name)
+    }
+}
+
 impl RuntimeConfig {
     pub fn from_toml<T: AsRef<Path>>(path: T) -> Result<Self> {
         let contents = fs::read_to_string(path.as_ref())
@@ -117,6 +511,14 @@ impl RuntimeConfig {
         }
         config.audit.auditor_signatures_bytes = signatures;
 
+        if let Some(registration) = config.registration.as_mut() {
+            registration.server_cert_bytes = match &registration.server_cert_source {
+                ConfigSource::Path(ref path) => fs::read(path).with_context(|| {
+                    format!("Cannot read server_cert of registration from {:?}", path)
+                })?,
+            };
+        }
+
         if env::var("AS_ALGO").is_ok()
             && env::var("AS_URL").is_ok()
             && env::var("AS_SPID").is_ok()
@@ -162,5 +564,103 @@ fn validate_config(config: &RuntimeConfig) -> Result<()> {
         bail!("Invalid URL of attestation service");
     }
 
+    if config.limits.max_list_page_size == 0 {
+        bail!("Invalid max_list_page_size 0");
+    }
+
+    for (i, shard) in config.storage_shards.iter().enumerate() {
+        if shard.key_prefixes.iter().any(|prefix| prefix.is_empty()) {
+            bail!("Empty key prefix of storage shard {}", shard.name);
+        }
+        if config.storage_shards[..i]
+            .iter()
+            .any(|other| other.name == shard.name)
+        {
+            bail!("Duplicated storage shard {}", shard.name);
+        }
+    }
+
+    for (i, replica) in config.storage_replicas.iter().enumerate() {
+        if config.storage_replicas[..i]
+            .iter()
+            .any(|other| other.name == replica.name)
+        {
+            bail!("Duplicated storage replica {}", replica.name);
+        }
+    }
+
+    let backend = &config.storage_backend;
+    if backend.kind == StorageBackendKind::Remote {
+        match &backend.url {
+            Some(url) if url::Url::parse(url).is_ok() => (),
+            _ => bail!("Invalid URL of the remote storage backend"),
+        }
+        if backend.sync_interval == 0 {
+            bail!("Invalid sync_interval 0 of the remote storage backend");
+        }
+    }
+
+    if let Some(cgroups) = &config.cgroups {
+        for (name, class) in &cgroups.classes {
+            match name.as_str() {
+                "low" | "normal" | "high" => (),
+                _ => bail!("Invalid cgroup class {}", name),
+            }
+            if class.cpu_weight < 1 || class.cpu_weight > 10000 {
+                bail!("Invalid cpu_weight of cgroup class {}", name);
+            }
+            if class.io_priority > 7 {
+                bail!("Invalid io_priority of cgroup class {}", name);
+            }
+        }
+    }
+
+    if let Some(federation) = &config.federation {
+        if federation.name.is_empty() || federation.name.contains('/') {
+            bail!("Invalid federation name {}", federation.name);
+        }
+        for (i, peer) in federation.peers.iter().enumerate() {
+            if peer.name == federation.name
+                || federation.peers[..i]
+                    .iter()
+                    .any(|other| other.name == peer.name)
+            {
+                bail!("Duplicated federation peer {}", peer.name);
+            }
+        }
+    }
+
+    if let Some(qos) = &config.qos {
+        if !qos.classes.contains_key(&qos.default_class) {
+            bail!("Unknown default QoS class {}", qos.default_class);
+        }
+        for (name, class) in &qos.classes {
+            if class.requests_per_second == 0 || class.burst == 0 {
+                bail!("Invalid rate of QoS class {}", name);
+            }
+            match class.max_priority.as_str() {
+                "low" | "normal" | "high" => (),
+                _ => bail!("Invalid max_priority of QoS class {}", name),
+            }
+        }
+        for (user_id, name) in &qos.users {
+            if !qos.classes.contains_key(name) {
+                bail!("Unknown QoS class {} of user {}", name, user_id);
+            }
+        }
+    }
+
+    if let Some(registration) = &config.registration {
+        if registration.address.is_empty() {
+            bail!("Empty address of the registration verifier");
+        }
+        if registration.server_cert_bytes.is_empty() {
+            bail!("Empty server_cert of the registration verifier");
+        }
+        if registration.code_ttl == 0 {
+            bail!("Invalid code_ttl 0 of the registration verifier");
+        }
+    }
+
     Ok(())
 }
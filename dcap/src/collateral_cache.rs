@@ -0,0 +1,256 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Caching proxy of the collateral for verifying DCAP quotes, i.e., PCK
+//! certificates, TCB info and QE identity, served at the same paths as the
+//! Intel Provisioning Certification Service (PCS). Platforms pointing their
+//! quote provider library to the proxy share the collateral fetched from PCS,
+//! which is fetched at most once per expiry.
+
+use chrono::prelude::*;
+use rocket::http::uri::Origin;
+use rocket::{http, response, State};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+
+const DEFAULT_PCS_URL: &str = "https://api.trustedservices.intel.com";
+// Collateral is refreshed at least daily, or earlier if PCS says so.
+const DEFAULT_MAX_AGE_SECS: i64 = 24 * 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Collateral {
+    PckCert,
+    TcbInfo,
+    QeIdentity,
+}
+
+impl Collateral {
+    fn from_path(path: &str) -> Option<Self> {
+        match path {
+            "pckcert" => Some(Self::PckCert),
+            "tcb" => Some(Self::TcbInfo),
+            "qe/identity" => Some(Self::QeIdentity),
+            _ => None,
+        }
+    }
+
+    // TCB info and QE identity are signed along with the time of their next
+    // update, after which they are considered expired by the verifier.
+    fn next_update(self, body: &[u8]) -> Option<DateTime<Utc>> {
+        let v: serde_json::Value = serde_json::from_slice(body).ok()?;
+        let next_update = match self {
+            Self::PckCert => return None,
+            Self::TcbInfo => &v["tcbInfo"]["nextUpdate"],
+            Self::QeIdentity => {
+                if v["enclaveIdentity"].is_null() {
+                    &v["qeIdentity"]["nextUpdate"]
+                } else {
+                    &v["enclaveIdentity"]["nextUpdate"]
+                }
+            }
+        };
+        DateTime::parse_from_rfc3339(next_update.as_str()?)
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct CachedCollateral {
+    // Issuer chains and other headers of PCS, e.g., SGX-TCBm and SGX-FMSPC
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    expires_at: DateTime<Utc>,
+}
+
+pub(crate) struct CollateralCache {
+    pcs_url: String,
+    api_key: Option<String>,
+    max_age: chrono::Duration,
+    client: reqwest::blocking::Client,
+    entries: RwLock<HashMap<String, CachedCollateral>>,
+    // Requests of the same collateral during a burst wait for one fetch.
+    fetching: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+}
+
+impl CollateralCache {
+    /// Reads the configuration from the environment:
+    /// - `TEACLAVE_DCAP_PCS_URL`: URL of PCS or another caching service
+    /// - `TEACLAVE_DCAP_PCS_API_KEY`: subscription key for fetching PCK certs
+    /// - `TEACLAVE_DCAP_COLLATERAL_MAX_AGE`: max age of cached collateral in
+    ///   seconds
+    pub(crate) fn from_env() -> Self {
+        let max_age = std::env::var("TEACLAVE_DCAP_COLLATERAL_MAX_AGE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_AGE_SECS);
+        Self {
+            pcs_url: std::env::var("TEACLAVE_DCAP_PCS_URL")
+                .unwrap_or_else(|_| DEFAULT_PCS_URL.to_string()),
+            api_key: std::env::var("TEACLAVE_DCAP_PCS_API_KEY").ok(),
+            max_age: chrono::Duration::seconds(max_age),
+            client: reqwest::blocking::Client::new(),
+            entries: RwLock::new(HashMap::new()),
+            fetching: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get_fresh(&self, key: &str, now: DateTime<Utc>) -> Option<CachedCollateral> {
+        let entries = self.entries.read().ok()?;
+        entries.get(key).filter(|e| e.expires_at > now).cloned()
+    }
+
+    fn get(&self, collateral: Collateral, key: &str) -> CollateralResponse {
+        if let Some(entry) = self.get_fresh(key, Utc::now()) {
+            return CollateralResponse::Cached(entry);
+        }
+
+        let lock = match self.fetching.lock() {
+            Ok(mut fetching) => fetching.entry(key.to_string()).or_default().clone(),
+            Err(_) => return CollateralResponse::InternalError,
+        };
+        let _guard = match lock.lock() {
+            Ok(guard) => guard,
+            Err(_) => return CollateralResponse::InternalError,
+        };
+        // Fetched by another request while waiting.
+        if let Some(entry) = self.get_fresh(key, Utc::now()) {
+            return CollateralResponse::Cached(entry);
+        }
+
+        let response = match self.fetch(collateral, key) {
+            Ok(Ok(entry)) => {
+                if let Ok(mut entries) = self.entries.write() {
+                    entries.insert(key.to_string(), entry.clone());
+                }
+                CollateralResponse::Cached(entry)
+            }
+            // Errors of PCS, e.g., unknown FMSPC or rate limiting, are not
+            // cached.
+            Ok(Err(status)) => CollateralResponse::Upstream(status),
+            Err(e) => {
+                eprintln!("Failed to fetch collateral {}: {:?}", key, e);
+                // Expired collateral is still served if PCS is unreachable,
+                // the verifier reports the expiration in the result.
+                let stale = self
+                    .entries
+                    .read()
+                    .ok()
+                    .and_then(|entries| entries.get(key).cloned());
+                match stale {
+                    Some(entry) => CollateralResponse::Cached(entry),
+                    None => CollateralResponse::Unavailable,
+                }
+            }
+        };
+        if let Ok(mut fetching) = self.fetching.lock() {
+            fetching.remove(key);
+        }
+        response
+    }
+
+    fn fetch(
+        &self,
+        collateral: Collateral,
+        key: &str,
+    ) -> reqwest::Result<std::result::Result<CachedCollateral, u16>> {
+        let mut request = self.client.get(&format!("{}{}", self.pcs_url, key));
+        if let Some(api_key) = &self.api_key {
+            request = request.header("Ocp-Apim-Subscription-Key", api_key);
+        }
+        let response = request.send()?;
+        if !response.status().is_success() {
+            return Ok(Err(response.status().as_u16()));
+        }
+
+        let headers = response
+            .headers()
+            .iter()
+            .filter(|(name, _)| {
+                let name = name.as_str().to_lowercase();
+                name.starts_with("sgx-") || name.starts_with("tcb-") || name == "content-type"
+            })
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| (name.to_string(), value.to_string()))
+            })
+            .collect();
+        let body = response.bytes()?.to_vec();
+        let now = Utc::now();
+        let max_expires_at = now + self.max_age;
+        let expires_at = match collateral.next_update(&body) {
+            Some(next_update) => next_update.min(max_expires_at),
+            None => max_expires_at,
+        };
+
+        Ok(Ok(CachedCollateral {
+            headers,
+            body,
+            expires_at,
+        }))
+    }
+}
+
+pub(crate) enum CollateralResponse {
+    NotFound,
+    InternalError,
+    Unavailable,
+    Upstream(u16),
+    Cached(CachedCollateral),
+}
+
+impl<'r> response::Responder<'r> for CollateralResponse {
+    fn respond_to(self, _: &rocket::Request) -> response::Result<'r> {
+        match self {
+            Self::NotFound => response::Result::Err(http::Status::NotFound),
+            Self::InternalError => response::Result::Err(http::Status::InternalServerError),
+            Self::Unavailable => response::Result::Err(http::Status::ServiceUnavailable),
+            Self::Upstream(code) => response::Result::Err(
+                http::Status::from_code(code).unwrap_or(http::Status::BadGateway),
+            ),
+            Self::Cached(entry) => {
+                let mut response = response::Response::build();
+                for (name, value) in entry.headers {
+                    response.raw_header(name, value);
+                }
+                response.sized_body(std::io::Cursor::new(entry.body)).ok()
+            }
+        }
+    }
+}
+
+#[get("/sgx/certification/<version>/<path..>")]
+pub(crate) fn get_collateral(
+    version: String,
+    path: PathBuf,
+    uri: &Origin,
+    cache: State<CollateralCache>,
+) -> CollateralResponse {
+    if version != "v2" && version != "v3" {
+        return CollateralResponse::NotFound;
+    }
+    let collateral = match path.to_str().and_then(Collateral::from_path) {
+        Some(collateral) => collateral,
+        None => return CollateralResponse::NotFound,
+    };
+    // Collateral is keyed by the path and the query, e.g., the FMSPC of the
+    // TCB info or the PPID and SVNs of the PCK cert.
+    cache.get(collateral, &uri.to_string())
+}
@@ -31,6 +31,8 @@ extern crate sgx_ucrypto;
 extern crate untrusted;
 extern crate uuid;
 
+mod collateral_cache;
+
 use chrono::prelude::*;
 use rand::{RngCore, SeedableRng};
 use ring::signature;
@@ -228,5 +230,8 @@ fn verify_quote(request: String) -> QuoteVerificationResponse {
 }
 
 fn main() {
-    rocket::ignite().mount("/", routes![verify_quote]).launch();
+    rocket::ignite()
+        .manage(collateral_cache::CollateralCache::from_env())
+        .mount("/", routes![verify_quote, collateral_cache::get_collateral])
+        .launch();
 }
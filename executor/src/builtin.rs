@@ -19,9 +19,9 @@
 use std::prelude::v1::*;
 
 use teaclave_function::{
-    Echo, FaceDetection, GbdtPredict, GbdtTrain, LogisticRegressionPredict,
+    Echo, FaceDetection, GbdtPredict, GbdtTrain, ImageHashMatch, LogisticRegressionPredict,
     LogisticRegressionTrain, OnlineDecrypt, OrderedSetIntersect, PasswordCheck,
-    PrincipalComponentsAnalysis, PrivateJoinAndCompute, RsaSign,
+    PrincipalComponentsAnalysis, PrivateJoinAndCompute, RsaSign, TimeSeriesAnomalyDetection,
 };
 use teaclave_types::{FunctionArguments, FunctionRuntime, TeaclaveExecutor};
 
@@ -67,6 +67,12 @@ impl TeaclaveExecutor for BuiltinFunctionExecutor {
             FaceDetection::NAME => FaceDetection::new().run(arguments, runtime),
             #[cfg(feature = "builtin_password_check")]
             PasswordCheck::NAME => PasswordCheck::new().run(arguments, runtime),
+            #[cfg(feature = "builtin_image_hash_match")]
+            ImageHashMatch::NAME => ImageHashMatch::new().run(arguments, runtime),
+            #[cfg(feature = "builtin_time_series_anomaly_detection")]
+            TimeSeriesAnomalyDetection::NAME => {
+                TimeSeriesAnomalyDetection::new().run(arguments, runtime)
+            }
             _ => bail!("Function not found."),
         }
     }
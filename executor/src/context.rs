@@ -63,6 +63,20 @@ impl Context {
         Ok(handle)
     }
 
+    fn publish_artifact(&mut self, name: &str) -> anyhow::Result<FileHandle> {
+        let file = self.runtime.publish_artifact(name)?;
+        let handle = self.seq.next()?.into_write_handle();
+        self.write_handles.add(handle, file)?;
+        Ok(handle)
+    }
+
+    fn consume_artifact(&mut self, name: &str) -> anyhow::Result<FileHandle> {
+        let file = self.runtime.consume_artifact(name)?;
+        let handle = self.seq.next()?.into_read_handle();
+        self.read_handles.add(handle, file)?;
+        Ok(handle)
+    }
+
     fn read_handle(&mut self, handle: FileHandle, buf: &mut [u8]) -> anyhow::Result<usize> {
         let file = self.read_handles.get_mut(handle)?;
         let size = file.read(buf)?;
@@ -208,6 +222,22 @@ pub fn rtc_create_output(fid: &str) -> anyhow::Result<FileHandle> {
     })
 }
 
+pub fn rtc_publish_artifact(name: &str) -> anyhow::Result<FileHandle> {
+    CONTEXT.with(|ctx| {
+        let mut ctx = ctx.borrow_mut();
+        anyhow::ensure!(ctx.is_some(), "Context not initialized");
+        ctx.as_mut().unwrap().publish_artifact(name)
+    })
+}
+
+pub fn rtc_consume_artifact(name: &str) -> anyhow::Result<FileHandle> {
+    CONTEXT.with(|ctx| {
+        let mut ctx = ctx.borrow_mut();
+        anyhow::ensure!(ctx.is_some(), "Context not initialized");
+        ctx.as_mut().unwrap().consume_artifact(name)
+    })
+}
+
 pub fn rtc_read_handle(f: FileHandle, buf: &mut [u8]) -> anyhow::Result<usize> {
     CONTEXT.with(|ctx| {
         let mut ctx = ctx.borrow_mut();
@@ -343,6 +373,52 @@ extern "C" fn c_create_output(fid: *mut c_char, out_handle: *mut c_int) -> c_uin
     }
 }
 
+/*
+ * uint c_publish_artifact(char* name, int* out_fd);
+ *
+ */
+#[allow(unused)]
+#[no_mangle]
+extern "C" fn c_publish_artifact(name: *mut c_char, out_handle: *mut c_int) -> c_uint {
+    debug!("c_publish_artifact");
+    let name = unsafe { CStr::from_ptr(name).to_string_lossy().into_owned() };
+    match rtc_publish_artifact(&name) {
+        Ok(handle) => {
+            unsafe {
+                *out_handle = handle;
+            }
+            FFI_OK
+        }
+        Err(e) => {
+            error!("c_publish_artifact: {:?}", e);
+            FFI_FILE_ERROR
+        }
+    }
+}
+
+/*
+ * uint c_consume_artifact(char* name, int* out_fd);
+ *
+ */
+#[allow(unused)]
+#[no_mangle]
+extern "C" fn c_consume_artifact(name: *mut c_char, out_handle: *mut c_int) -> c_uint {
+    debug!("c_consume_artifact");
+    let name = unsafe { CStr::from_ptr(name).to_string_lossy().into_owned() };
+    match rtc_consume_artifact(&name) {
+        Ok(handle) => {
+            unsafe {
+                *out_handle = handle;
+            }
+            FFI_OK
+        }
+        Err(e) => {
+            error!("c_consume_artifact: {:?}", e);
+            FFI_FILE_ERROR
+        }
+    }
+}
+
 /*
  * uint c_read_file(int fd, void* out_buf, size_t buf_size, size_t* out_size_read);
  *
@@ -21,9 +21,11 @@ use crate::context::reset_thread_context;
 use crate::context::set_thread_context;
 use crate::context::Context;
 
-use std::ffi::CString;
+use std::borrow::Cow;
+use std::ffi::{CStr, CString};
+use std::sync::Arc;
 
-use teaclave_types::{FunctionArguments, FunctionRuntime, TeaclaveExecutor};
+use teaclave_types::{CompiledPayload, FunctionArguments, FunctionRuntime, TeaclaveExecutor};
 
 const MAXPYBUFLEN: usize = 20480;
 const MESAPY_ERROR_BUFFER_TOO_SHORT: i64 = -1i64;
@@ -40,7 +42,9 @@ extern "C" {
 }
 
 #[derive(Default)]
-pub struct MesaPy;
+pub struct MesaPy {
+    entry_point: Option<String>,
+}
 
 impl TeaclaveExecutor for MesaPy {
     fn execute(
@@ -49,6 +53,50 @@ impl TeaclaveExecutor for MesaPy {
         arguments: FunctionArguments,
         payload: String,
         runtime: FunctionRuntime,
+    ) -> anyhow::Result<String> {
+        let script = CString::new(payload)?;
+        self.exec(arguments, &script, runtime)
+    }
+
+    // MesaPy always calls `entrypoint`, which is rebound to the chosen function
+    // at the end of the script.
+    fn set_entry_point(&mut self, entry_point: &str) -> anyhow::Result<()> {
+        let mut chars = entry_point.chars();
+        let is_identifier = chars
+            .next()
+            .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        anyhow::ensure!(is_identifier, "Invalid entry point: {}", entry_point);
+        self.entry_point = Some(entry_point.to_string());
+        Ok(())
+    }
+
+    // The script is compiled to a NUL-terminated string passed to MesaPy as is.
+    fn compile(&self, payload: &str) -> anyhow::Result<Option<CompiledPayload>> {
+        let script = CString::new(payload)?;
+        Ok(Some(Arc::new(script)))
+    }
+
+    fn execute_compiled(
+        &self,
+        _name: String,
+        arguments: FunctionArguments,
+        payload: CompiledPayload,
+        runtime: FunctionRuntime,
+    ) -> anyhow::Result<String> {
+        let script = payload
+            .downcast_ref::<CString>()
+            .ok_or_else(|| anyhow::anyhow!("Invalid compiled payload"))?;
+        self.exec(arguments, script, runtime)
+    }
+}
+
+impl MesaPy {
+    fn exec(
+        &self,
+        arguments: FunctionArguments,
+        script: &CStr,
+        runtime: FunctionRuntime,
     ) -> anyhow::Result<String> {
         let py_argv = arguments.into_vec();
         let cstr_argv: Vec<_> = py_argv
@@ -56,9 +104,6 @@ impl TeaclaveExecutor for MesaPy {
             .map(|arg| CString::new(arg.as_str()).unwrap())
             .collect();
 
-        let mut script_bytes = payload.into_bytes();
-        script_bytes.push(0u8);
-
         let mut p_argv: Vec<_> = cstr_argv
             .iter() // do NOT into_iter()
             .map(|arg| arg.as_ptr())
@@ -66,13 +111,22 @@ impl TeaclaveExecutor for MesaPy {
 
         p_argv.push(std::ptr::null());
 
+        let script: Cow<CStr> = match &self.entry_point {
+            Some(entry_point) => {
+                let mut bytes = script.to_bytes().to_vec();
+                bytes.extend_from_slice(format!("\nentrypoint = {}\n", entry_point).as_bytes());
+                Cow::Owned(CString::new(bytes)?)
+            }
+            None => Cow::Borrowed(script),
+        };
+
         let mut py_result = [0u8; MAXPYBUFLEN];
 
         set_thread_context(Context::new(runtime))?;
 
         let result = unsafe {
             mesapy_exec(
-                script_bytes.as_ptr(),
+                script.as_ptr() as *const u8,
                 p_argv.len() - 1,
                 p_argv.as_ptr(),
                 &mut py_result as *mut _ as *mut u8,
@@ -102,7 +156,7 @@ pub mod tests {
     use teaclave_types::*;
 
     pub fn run_tests() -> bool {
-        run_tests!(test_mesapy,)
+        run_tests!(test_mesapy, test_mesapy_entry_point,)
     }
 
     fn test_mesapy() {
@@ -173,4 +227,44 @@ def entrypoint(argv):
             .unwrap();
         assert_eq!(summary, "");
     }
+
+    fn test_mesapy_entry_point() {
+        let py_payload = r#"
+def entrypoint(argv):
+    return "entrypoint"
+
+def train(argv):
+    return "train"
+"#;
+        let runtime = || {
+            Box::new(RawIoRuntime::new(
+                StagedFiles::default(),
+                StagedFiles::default(),
+            ))
+        };
+
+        let mut function = MesaPy::default();
+        let summary = function
+            .execute(
+                "".to_string(),
+                FunctionArguments::default(),
+                py_payload.to_string(),
+                runtime(),
+            )
+            .unwrap();
+        assert_eq!(summary, "entrypoint");
+
+        function.set_entry_point("train").unwrap();
+        let summary = function
+            .execute(
+                "".to_string(),
+                FunctionArguments::default(),
+                py_payload.to_string(),
+                runtime(),
+            )
+            .unwrap();
+        assert_eq!(summary, "train");
+
+        assert!(function.set_entry_point("train(argv)\nimport os").is_err());
+    }
 }
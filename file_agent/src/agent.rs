@@ -21,16 +21,29 @@ use tokio::io::AsyncWriteExt;
 use tokio_util::codec;
 use url::Url;
 
+use crate::cgroup;
+use crate::chunked;
+use crate::push;
+use crate::s3;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::{Component, Path, PathBuf};
-use teaclave_types::{FileAgentRequest, HandleFileCommand, HandleFileInfo};
+use teaclave_types::{
+    FileAgentRequest, FileCredential, FileRange, HandleFileCommand, HandleFileInfo,
+};
 
 async fn download_remote_input_to_file(
-    presigned_url: Url,
+    remote: Url,
+    credential: Option<FileCredential>,
     dest: impl AsRef<std::path::Path>,
 ) -> anyhow::Result<()> {
-    let mut download = reqwest::get(presigned_url.as_str())
-        .await?
-        .error_for_status()?;
+    let (url, headers) = authorize_request(Method::Get, remote, credential)?;
+
+    let client = reqwest::Client::new();
+    let mut request = client.get(url.as_str());
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let mut download = request.send().await?.error_for_status()?;
 
     let mut outfile = tokio::fs::File::create(dest).await?;
 
@@ -45,6 +58,96 @@ async fn download_remote_input_to_file(
     Ok(())
 }
 
+async fn download_remote_bytes(
+    remote: Url,
+    credential: Option<FileCredential>,
+) -> anyhow::Result<Vec<u8>> {
+    let (url, headers) = authorize_request(Method::Get, remote, credential)?;
+
+    let client = reqwest::Client::new();
+    let mut request = client.get(url.as_str());
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let bytes = request.send().await?.error_for_status()?.bytes().await?;
+    Ok(bytes.to_vec())
+}
+
+// Chunks are downloaded one at a time and appended in the order of the
+// manifest, so that at most one chunk is held in memory.
+async fn download_chunked_input_to_file(
+    remote: Url,
+    credential: Option<FileCredential>,
+    dest: impl AsRef<std::path::Path>,
+) -> anyhow::Result<()> {
+    let manifest_url = chunked::manifest_url(&remote)?;
+    let manifest = download_remote_bytes(manifest_url.clone(), credential.clone()).await?;
+    let manifest = chunked::ChunkManifest::from_slice(&manifest)?;
+
+    let mut outfile = tokio::fs::File::create(dest).await?;
+    for chunk in &manifest.chunks {
+        let url = chunked::chunk_url(&manifest_url, chunk)?;
+        let bytes = download_remote_bytes(url, credential.clone()).await?;
+        chunk.verify(&bytes)?;
+        outfile.write_all(&bytes).await?;
+    }
+    outfile.flush().await?;
+    debug!(
+        "Assembled {} chunks of {} bytes from {}",
+        manifest.chunks.len(),
+        manifest.size(),
+        manifest_url
+    );
+
+    Ok(())
+}
+
+// Servers ignoring the range send the whole file, from which the range is
+// cut, and a range starting at the end of the file is not satisfiable.
+async fn download_remote_range_to_file(
+    remote: Url,
+    credential: Option<FileCredential>,
+    range: FileRange,
+    dest: impl AsRef<std::path::Path>,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(range.length > 0, "Empty range");
+    let (url, headers) = authorize_request(Method::Get, remote, credential)?;
+
+    let client = reqwest::Client::new();
+    let mut request = client.get(url.as_str()).header(
+        reqwest::header::RANGE,
+        format!("bytes={}-{}", range.offset, range.offset + range.length - 1),
+    );
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let mut download = request.send().await?;
+
+    let mut outfile = tokio::fs::File::create(dest).await?;
+    let mut position = match download.status() {
+        http::StatusCode::PARTIAL_CONTENT => range.offset,
+        http::StatusCode::OK => 0,
+        http::StatusCode::RANGE_NOT_SATISFIABLE => return Ok(()),
+        status => anyhow::bail!("{}", status),
+    };
+    let end = range.offset + range.length;
+    while let Some(chunk) = download.chunk().await? {
+        let chunk_end = position + chunk.len() as u64;
+        if chunk_end > range.offset {
+            let start = range.offset.saturating_sub(position) as usize;
+            let stop = (end.min(chunk_end) - position) as usize;
+            outfile.write_all(&chunk[start..stop]).await?;
+        }
+        position = chunk_end;
+        if position >= end {
+            break;
+        }
+    }
+    outfile.flush().await?;
+
+    Ok(())
+}
+
 async fn copy_file(
     src: impl AsRef<std::path::Path>,
     dst: impl AsRef<std::path::Path>,
@@ -53,12 +156,90 @@ async fn copy_file(
     Ok(())
 }
 
+fn copy_file_range(
+    src: impl AsRef<std::path::Path>,
+    dst: impl AsRef<std::path::Path>,
+    range: FileRange,
+) -> anyhow::Result<()> {
+    let mut file = std::fs::File::open(src)?;
+    file.seek(SeekFrom::Start(range.offset))?;
+    let mut data = Vec::new();
+    file.take(range.length).read_to_end(&mut data)?;
+    std::fs::write(dst, data)?;
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Method {
+    Get,
+    Put,
+}
+
+// Returns the url to send the request to and the headers carrying the
+// credential, i.e., to PUT an output file or to GET a file. Without a
+// credential, the url is expected to be presigned.
+fn authorize_request(
+    method: Method,
+    remote: Url,
+    credential: Option<FileCredential>,
+) -> anyhow::Result<(Url, Vec<(&'static str, String)>)> {
+    match credential {
+        None => {
+            anyhow::ensure!(
+                remote.scheme() == "https" || remote.scheme() == "http",
+                "Missing credential for {}",
+                remote
+            );
+            Ok((remote, vec![]))
+        }
+        Some(credential) => {
+            credential.check_url(&remote)?;
+            match credential {
+                FileCredential::Bearer { token } => {
+                    Ok((remote, vec![("authorization", format!("Bearer {}", token))]))
+                }
+                FileCredential::AzureSas { token } => {
+                    let mut url = remote;
+                    url.set_query(Some(&token));
+                    match method {
+                        Method::Get => Ok((url, vec![])),
+                        Method::Put => Ok((url, vec![("x-ms-blob-type", "BlockBlob".to_string())])),
+                    }
+                }
+                FileCredential::AwsS3 {
+                    access_key_id,
+                    secret_access_key,
+                    region,
+                    endpoint,
+                } => {
+                    let credential = s3::S3Credential {
+                        access_key_id: &access_key_id,
+                        secret_access_key: &secret_access_key,
+                        region: &region,
+                        endpoint: endpoint.as_ref(),
+                    };
+                    match method {
+                        Method::Get => {
+                            s3::sign_get_object(&remote, &credential, chrono::Utc::now())
+                        }
+                        Method::Put => {
+                            s3::sign_put_object(&remote, &credential, chrono::Utc::now())
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 async fn upload_output_file_to_remote(
     src: impl AsRef<std::path::Path>,
-    presigned_url: Url,
+    remote: Url,
+    credential: Option<FileCredential>,
 ) -> anyhow::Result<()> {
     let metadata = std::fs::metadata(&src)?;
     let file_len = metadata.len();
+    let (url, headers) = authorize_request(Method::Put, remote, credential)?;
 
     let stream = tokio::fs::File::open(src.as_ref().to_path_buf())
         .map_ok(|file| codec::FramedRead::new(file, codec::BytesCodec::new()))
@@ -67,15 +248,17 @@ async fn upload_output_file_to_remote(
     let body = reqwest::Body::wrap_stream(stream);
 
     let client = reqwest::Client::new();
-    let res = client
-        .put(presigned_url.as_str())
+    let mut request = client
+        .put(url.as_str())
         .header(reqwest::header::CONTENT_TYPE, "application/x-binary")
-        .header(reqwest::header::CONTENT_LENGTH, file_len.to_string())
-        .body(body)
-        .send()
-        .await?;
+        .header(reqwest::header::CONTENT_LENGTH, file_len.to_string());
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let res = request.body(body).send().await?;
+    // Azure Blob Storage responds 201 Created to a successful upload.
     match res.status() {
-        http::StatusCode::OK => Ok(()),
+        http::StatusCode::OK | http::StatusCode::CREATED => Ok(()),
         status => anyhow::bail!("{}", status),
     }
 }
@@ -91,10 +274,24 @@ async fn handle_download(
     );
     let dst = info.local;
     let remote = info.remote;
+    let range = info.range;
 
     match remote.scheme() {
-        "https" | "http" => {
-            download_remote_input_to_file(remote, dst).await?;
+        "https" | "http" | "s3" => match range {
+            Some(range) => {
+                download_remote_range_to_file(remote, info.credential, range, dst).await?;
+            }
+            None => download_remote_input_to_file(remote, info.credential, dst).await?,
+        },
+        "chunked+https" | "chunked+http" => {
+            anyhow::ensure!(
+                range.is_none(),
+                "[Download] Range is not supported for chunked inputs"
+            );
+            download_chunked_input_to_file(remote, info.credential, dst).await?;
+        }
+        _ if info.credential.is_some() => {
+            anyhow::bail!("[Download] Credential is not supported for local sources")
         }
         "file" => {
             let src = remote
@@ -105,7 +302,10 @@ async fn handle_download(
                 "[Download] Src local file: {:?} doesn't exist.",
                 src
             );
-            copy_file(src, dst).await?;
+            match range {
+                Some(range) => copy_file_range(src, dst, range)?,
+                None => copy_file(src, dst).await?,
+            }
         }
         "fusion" => {
             let path = remote
@@ -127,7 +327,19 @@ async fn handle_download(
                 "[Download] Src local file: {:?} doesn't exist.",
                 src
             );
-            copy_file(src, dst).await?;
+            match range {
+                Some(range) => copy_file_range(src, dst, range)?,
+                None => copy_file(src, dst).await?,
+            }
+        }
+        _ if range.is_some() => {
+            anyhow::bail!(
+                "[Download] Range is not supported for {} urls",
+                remote.scheme()
+            )
+        }
+        "push" => {
+            push::download_pushed_input(&remote, dst).await?;
         }
         "data" => {
             let data = remote.path().split(',').collect::<Vec<&str>>();
@@ -152,8 +364,11 @@ async fn handle_upload(info: HandleFileInfo, fusion_base: impl AsRef<Path>) -> a
     let src = info.local;
 
     match info.remote.scheme() {
-        "https" | "http" => {
-            upload_output_file_to_remote(src, info.remote).await?;
+        "https" | "http" | "s3" => {
+            upload_output_file_to_remote(src, info.remote, info.credential).await?;
+        }
+        _ if info.credential.is_some() => {
+            anyhow::bail!("[Upload] Credential is not supported for local destinations")
         }
         "file" => {
             let dst = info
@@ -193,6 +408,15 @@ async fn handle_upload(info: HandleFileInfo, fusion_base: impl AsRef<Path>) -> a
 
 fn handle_file_request(bytes: &[u8]) -> anyhow::Result<()> {
     let req: FileAgentRequest = serde_json::from_slice(bytes)?;
+    // The files are still transferred if the thread cannot be weighted.
+    if let Some(priority) = req.priority {
+        if let Err(e) = cgroup::enter_class(priority) {
+            warn!(
+                "Cannot weight the transfer of {} priority: {:?}",
+                priority, e
+            );
+        }
+    }
     let results = tokio::runtime::Builder::new()
         .threaded_scheduler()
         .enable_all()
@@ -222,6 +446,14 @@ fn handle_file_request(bytes: &[u8]) -> anyhow::Result<()> {
                         .collect();
                     join_all(futures).await
                 }
+                HandleFileCommand::OpenPushSlots => {
+                    let futures: Vec<_> = req
+                        .info
+                        .into_iter()
+                        .map(|info| tokio::spawn(async move { push::open_slot(&info.remote) }))
+                        .collect();
+                    join_all(futures).await
+                }
             }
         });
 
@@ -386,6 +618,34 @@ mod tests {
         std::fs::remove_dir_all(&base).unwrap();
     }
 
+    #[test]
+    fn test_local_range_download() {
+        let base_str = "/tmp/file_agent_local_range";
+        let base = PathBuf::from(&base_str);
+        std::fs::create_dir_all(&base).unwrap();
+
+        let src = base.join("src.txt");
+        std::fs::write(&src, b"Hello Teaclave Results!").unwrap();
+        let url = Url::parse(&format!("file://{}/src.txt", base_str)).unwrap();
+
+        let dest = base.join("range.txt");
+        let info = HandleFileInfo::new(&dest, &url).range(6, 8);
+        let req = FileAgentRequest::new(HandleFileCommand::Download, vec![info], "");
+        let bytes = serde_json::to_vec(&req).unwrap();
+        handle_file_request(&bytes).unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"Teaclave");
+
+        // A range past the end of the file is cut short.
+        let dest = base.join("tail.txt");
+        let info = HandleFileInfo::new(&dest, &url).range(15, 100);
+        let req = FileAgentRequest::new(HandleFileCommand::Download, vec![info], "");
+        let bytes = serde_json::to_vec(&req).unwrap();
+        handle_file_request(&bytes).unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"Results!");
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
     #[test]
     fn test_data_scheme() {
         let url = Url::parse("data:text/plain;base64,SGVsbG8sIFdvcmxkIQ==").unwrap();
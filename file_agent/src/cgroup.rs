@@ -0,0 +1,107 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Cgroups of the worker host weighting the untrusted-side work of a task by
+//! its priority. The enclave host thread running a task makes the file
+//! requests of the task, so the thread is moved to the cgroup of the priority
+//! when it makes one. The transfers run in threads spawned by it, which start
+//! in the same cgroup, and the thread stays there until it makes a request
+//! for a task of another priority.
+//!
+//! Threads are moved between threaded cgroups (v2) weighted by `cpu.weight`.
+//! The io controller cannot weight threads, so their I/O is weighted by the
+//! best-effort I/O priority of the thread instead, which is inherited by the
+//! threads it spawns as well.
+
+use anyhow::Context;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use teaclave_config::CgroupsConfig;
+use teaclave_types::TaskPriority;
+
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+const IOPRIO_CLASS_BE: libc::c_int = 2;
+const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+lazy_static! {
+    static ref CLASSES: RwLock<HashMap<TaskPriority, TaskClass>> = RwLock::new(HashMap::new());
+}
+
+#[derive(Debug, Clone)]
+struct TaskClass {
+    dir: PathBuf,
+    io_priority: u8,
+}
+
+/// Creates a threaded cgroup for each class under the base cgroup and moves
+/// the service into the base cgroup, so that the threads of file requests can
+/// be moved to the classes.
+pub fn setup_cgroups(config: &CgroupsConfig) -> anyhow::Result<()> {
+    let mut classes = HashMap::new();
+    for (name, class) in &config.classes {
+        let priority = TaskPriority::try_from(name.as_str())?;
+        let dir = config.base_dir.join(name);
+        std::fs::create_dir_all(&dir).with_context(|| format!("Cannot create cgroup {:?}", dir))?;
+        write_control(&dir, "cgroup.type", "threaded")?;
+        classes.insert(
+            priority,
+            TaskClass {
+                dir,
+                io_priority: class.io_priority,
+            },
+        );
+    }
+    // The cpu controller is only enabled once the classes are threaded, as
+    // the base cgroup holds the service itself.
+    write_control(&config.base_dir, "cgroup.subtree_control", "+cpu")?;
+    for (name, class) in &config.classes {
+        let dir = config.base_dir.join(name);
+        write_control(&dir, "cpu.weight", &class.cpu_weight.to_string())?;
+    }
+    let pid = std::process::id().to_string();
+    write_control(&config.base_dir, "cgroup.procs", &pid)?;
+
+    *CLASSES.write().unwrap() = classes;
+    Ok(())
+}
+
+/// Moves the calling thread to the cgroup of the priority, if it has a class.
+pub(crate) fn enter_class(priority: TaskPriority) -> anyhow::Result<()> {
+    let class = match CLASSES.read().unwrap().get(&priority) {
+        Some(class) => class.clone(),
+        None => return Ok(()),
+    };
+    let tid = unsafe { libc::syscall(libc::SYS_gettid) };
+    write_control(&class.dir, "cgroup.threads", &tid.to_string())?;
+
+    let ioprio = (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | libc::c_int::from(class.io_priority);
+    let ret = unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) };
+    anyhow::ensure!(
+        ret == 0,
+        "Cannot set I/O priority: {}",
+        std::io::Error::last_os_error()
+    );
+    Ok(())
+}
+
+fn write_control(dir: &Path, file: &str, value: &str) -> anyhow::Result<()> {
+    let path = dir.join(file);
+    std::fs::write(&path, value).with_context(|| format!("Cannot write {:?}", path))
+}
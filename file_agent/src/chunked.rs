@@ -0,0 +1,129 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Inputs uploaded in content-defined chunks by the SDK, so that a new
+//! version of a large input only transfers the chunks which are not uploaded
+//! yet. The input is registered with the url of its manifest, e.g.,
+//! `chunked+https://host/dataset/v2.manifest`, which lists the chunks of the
+//! encrypted file in order. Chunks are stored next to the manifest under the
+//! hex of their SHA-256, e.g., `https://host/dataset/chunks/<hash>`, and
+//! shared by all manifests there. The file is assembled from the chunks
+//! before the enclave checks it against the registered cmac as usual.
+
+use serde::Deserialize;
+use teaclave_types::CHUNKED_URL_SCHEME_PREFIX;
+use url::Url;
+
+const CHUNK_DIR: &str = "chunks";
+const HASH_LENGTH: usize = 32;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ChunkManifest {
+    pub(crate) chunks: Vec<Chunk>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Chunk {
+    pub(crate) hash: String,
+    pub(crate) size: u64,
+}
+
+impl ChunkManifest {
+    pub(crate) fn from_slice(bytes: &[u8]) -> anyhow::Result<Self> {
+        let manifest: ChunkManifest = serde_json::from_slice(bytes)?;
+        for chunk in &manifest.chunks {
+            let hash = hex::decode(&chunk.hash)?;
+            anyhow::ensure!(
+                hash.len() == HASH_LENGTH,
+                "Invalid chunk hash: {}",
+                chunk.hash
+            );
+        }
+        Ok(manifest)
+    }
+
+    pub(crate) fn size(&self) -> u64 {
+        self.chunks.iter().map(|chunk| chunk.size).sum()
+    }
+}
+
+impl Chunk {
+    /// Checks the downloaded chunk against the manifest, so that a missing or
+    /// corrupted chunk fails the download early instead of the cmac check.
+    pub(crate) fn verify(&self, bytes: &[u8]) -> anyhow::Result<()> {
+        let digest = ring::digest::digest(&ring::digest::SHA256, bytes);
+        anyhow::ensure!(
+            bytes.len() as u64 == self.size && hex::encode(digest.as_ref()) == self.hash,
+            "Chunk {} does not match the manifest",
+            self.hash
+        );
+        Ok(())
+    }
+}
+
+pub(crate) fn is_chunked(remote: &Url) -> bool {
+    remote.scheme().starts_with(CHUNKED_URL_SCHEME_PREFIX)
+}
+
+/// Url of the manifest to download it from, without the scheme prefix.
+pub(crate) fn manifest_url(remote: &Url) -> anyhow::Result<Url> {
+    anyhow::ensure!(is_chunked(remote), "Not a chunked input: {}", remote);
+    let url = Url::parse(&remote.as_str()[CHUNKED_URL_SCHEME_PREFIX.len()..])?;
+    anyhow::ensure!(
+        url.scheme() == "https" || url.scheme() == "http",
+        "Scheme not supported for chunked inputs: {}",
+        remote.scheme()
+    );
+    Ok(url)
+}
+
+pub(crate) fn chunk_url(manifest_url: &Url, chunk: &Chunk) -> anyhow::Result<Url> {
+    let url = manifest_url.join(&format!("{}/{}", CHUNK_DIR, chunk.hash))?;
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_urls() {
+        let remote = Url::parse("chunked+https://host/dataset/v2.manifest?token=a").unwrap();
+        assert!(is_chunked(&remote));
+        let manifest_url = manifest_url(&remote).unwrap();
+        assert_eq!(
+            manifest_url.as_str(),
+            "https://host/dataset/v2.manifest?token=a"
+        );
+
+        let hash = hex::encode(ring::digest::digest(&ring::digest::SHA256, b"chunk"));
+        let manifest = format!(r#"{{"chunks": [{{"hash": "{}", "size": 5}}]}}"#, hash);
+        let manifest = ChunkManifest::from_slice(manifest.as_bytes()).unwrap();
+        assert_eq!(manifest.size(), 5);
+        let chunk = &manifest.chunks[0];
+        assert_eq!(
+            chunk_url(&manifest_url, chunk).unwrap().as_str(),
+            format!("https://host/dataset/chunks/{}", hash)
+        );
+        assert!(chunk.verify(b"chunk").is_ok());
+        assert!(chunk.verify(b"chunk2").is_err());
+
+        assert!(ChunkManifest::from_slice(br#"{"chunks": [{"hash": "00", "size": 1}]}"#).is_err());
+        let remote = Url::parse("chunked+file:///dataset/v2.manifest").unwrap();
+        assert!(manifest_url(&remote).is_err());
+    }
+}
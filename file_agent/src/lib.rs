@@ -19,4 +19,10 @@
 extern crate log;
 
 mod agent;
+mod cgroup;
+mod chunked;
+mod push;
+mod s3;
 pub use agent::ocall_handle_file_request;
+pub use cgroup::setup_cgroups;
+pub use push::start_push_server;
@@ -0,0 +1,327 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Upload endpoint of the worker host, to which clients push the inputs of a
+//! task with the capability tokens published by the execution enclave. The
+//! enclave opens a slot for each token before publishing it, and the pushed
+//! input is then downloaded from the `push:<token>` url. Inputs are encrypted
+//! and checked by the enclave, so the endpoint only guards the slots.
+
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use teaclave_types::TaskUploadSlots;
+use url::Url;
+
+const PUSH_DIR_NAME: &str = "teaclave_pushed_inputs";
+const OPEN_SLOT_SUFFIX: &str = "open";
+const PARTIAL_INPUT_SUFFIX: &str = "part";
+// Clients are given as long as participants wrapping keys of a task.
+const PUSH_TIMEOUT_SECS: u64 = 600;
+const PUSH_POLL_SECS: u64 = 1;
+const CONNECTION_TIMEOUT_SECS: u64 = 60;
+
+// Slots are files in a directory shared by the endpoint and the file agent,
+// which are in the same process: $tmp/teaclave_pushed_inputs/$token.open
+// until the input is pushed to $tmp/teaclave_pushed_inputs/$token.
+fn push_dir() -> PathBuf {
+    std::env::temp_dir().join(PUSH_DIR_NAME)
+}
+
+fn slot_path(token: &str, suffix: &str) -> PathBuf {
+    push_dir().join(format!("{}.{}", token, suffix))
+}
+
+fn token_of(remote: &Url) -> anyhow::Result<&str> {
+    let token = remote.path();
+    anyhow::ensure!(
+        TaskUploadSlots::is_token(token),
+        "Invalid upload token: {}",
+        remote
+    );
+    Ok(token)
+}
+
+/// Starts serving `PUT /<token>` requests in the background, and returns the
+/// address it listens on. Slots left by an earlier run are dropped.
+pub fn start_push_server(listen_address: SocketAddr) -> anyhow::Result<SocketAddr> {
+    let dir = push_dir();
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+
+    let listener = TcpListener::bind(listen_address)?;
+    let local_address = listener.local_addr()?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    std::thread::spawn(move || {
+                        if let Err(e) = handle_connection(stream) {
+                            warn!("[Push] Connection error: {:?}", e);
+                        }
+                    });
+                }
+                Err(e) => warn!("[Push] Accept error: {:?}", e),
+            }
+        }
+    });
+    info!("[Push] Listening on {}", local_address);
+    Ok(local_address)
+}
+
+enum Status {
+    Continue,
+    Created,
+    BadRequest,
+    NotFound,
+    MethodNotAllowed,
+    Conflict,
+    LengthRequired,
+}
+
+impl Status {
+    fn status_line(&self) -> &'static str {
+        match self {
+            Status::Continue => "HTTP/1.1 100 Continue",
+            Status::Created => "HTTP/1.1 201 Created",
+            Status::BadRequest => "HTTP/1.1 400 Bad Request",
+            Status::NotFound => "HTTP/1.1 404 Not Found",
+            Status::MethodNotAllowed => "HTTP/1.1 405 Method Not Allowed",
+            Status::Conflict => "HTTP/1.1 409 Conflict",
+            Status::LengthRequired => "HTTP/1.1 411 Length Required",
+        }
+    }
+}
+
+// Each connection pushes one input, and is closed after the response.
+fn handle_connection(stream: TcpStream) -> anyhow::Result<()> {
+    let timeout = Some(Duration::from_secs(CONNECTION_TIMEOUT_SECS));
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+
+    let status = receive_input(&mut reader, &mut writer).unwrap_or_else(|status| status);
+    write!(
+        writer,
+        "{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        status.status_line()
+    )?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn receive_input(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+) -> std::result::Result<Status, Status> {
+    let mut request_line = String::new();
+    reader
+        .read_line(&mut request_line)
+        .map_err(|_| Status::BadRequest)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().ok_or(Status::BadRequest)?;
+    let target = parts.next().ok_or(Status::BadRequest)?;
+
+    let mut content_length = None;
+    let mut expect_continue = false;
+    loop {
+        let mut line = String::new();
+        reader
+            .read_line(&mut line)
+            .map_err(|_| Status::BadRequest)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let colon = line.find(':').ok_or(Status::BadRequest)?;
+        let name = line[..colon].trim().to_ascii_lowercase();
+        let value = line[colon + 1..].trim();
+        match name.as_str() {
+            "content-length" => {
+                content_length = Some(value.parse::<u64>().map_err(|_| Status::BadRequest)?)
+            }
+            // Chunked bodies are not supported.
+            "transfer-encoding" => return Err(Status::LengthRequired),
+            "expect" => expect_continue = value.eq_ignore_ascii_case("100-continue"),
+            _ => (),
+        }
+    }
+
+    if method != "PUT" {
+        return Err(Status::MethodNotAllowed);
+    }
+    let content_length = content_length.ok_or(Status::LengthRequired)?;
+    // The endpoint may be advertised under a path, e.g., by a reverse proxy.
+    let token = target.rsplit('/').next().unwrap_or_default();
+    if !TaskUploadSlots::is_token(token) || !slot_path(token, OPEN_SLOT_SUFFIX).exists() {
+        return Err(Status::NotFound);
+    }
+    let pushed_path = push_dir().join(token);
+    if pushed_path.exists() {
+        return Err(Status::Conflict);
+    }
+    // Concurrent pushes of the same input are rejected.
+    let partial_path = slot_path(token, PARTIAL_INPUT_SUFFIX);
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&partial_path)
+        .map_err(|_| Status::Conflict)?;
+
+    if expect_continue {
+        write!(writer, "{}\r\n\r\n", Status::Continue.status_line())
+            .and_then(|_| writer.flush())
+            .map_err(|_| Status::BadRequest)?;
+    }
+    let received = std::io::copy(&mut reader.take(content_length), &mut file);
+    if received.ok() != Some(content_length) {
+        let _ = std::fs::remove_file(&partial_path);
+        return Err(Status::BadRequest);
+    }
+    std::fs::rename(&partial_path, &pushed_path).map_err(|_| Status::Conflict)?;
+    let _ = std::fs::remove_file(slot_path(token, OPEN_SLOT_SUFFIX));
+    info!("[Push] Received input of {} bytes", content_length);
+    Ok(Status::Created)
+}
+
+/// Accepts a push of the input for the `push:<token>` url.
+pub(crate) fn open_slot(remote: &Url) -> anyhow::Result<()> {
+    let token = token_of(remote)?;
+    anyhow::ensure!(
+        push_dir().exists(),
+        "[Push] The upload endpoint is not started"
+    );
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(slot_path(token, OPEN_SLOT_SUFFIX))?;
+    Ok(())
+}
+
+/// Waits for the input of the `push:<token>` url, and moves it to the
+/// destination. The slot is closed if the input is not pushed in time.
+pub(crate) async fn download_pushed_input(
+    remote: &Url,
+    dest: impl AsRef<Path>,
+) -> anyhow::Result<()> {
+    let token = token_of(remote)?;
+    let pushed_path = push_dir().join(token);
+    let open_slot_path = slot_path(token, OPEN_SLOT_SUFFIX);
+    anyhow::ensure!(
+        pushed_path.exists() || open_slot_path.exists(),
+        "[Download] Upload slot is not open: {}",
+        remote
+    );
+
+    let mut elapsed = 0;
+    while !pushed_path.exists() {
+        if elapsed >= PUSH_TIMEOUT_SECS {
+            let _ = tokio::fs::remove_file(&open_slot_path).await;
+            anyhow::bail!(
+                "[Download] Timed out waiting for the input pushed to {}",
+                remote
+            );
+        }
+        tokio::time::delay_for(Duration::from_secs(PUSH_POLL_SECS)).await;
+        elapsed += PUSH_POLL_SECS;
+    }
+
+    // The push directory may be on another file system than the destination.
+    if tokio::fs::rename(&pushed_path, dest.as_ref())
+        .await
+        .is_err()
+    {
+        tokio::fs::copy(&pushed_path, dest.as_ref()).await?;
+        tokio::fs::remove_file(&pushed_path).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Once;
+
+    static START: Once = Once::new();
+    static mut ADDRESS: Option<SocketAddr> = None;
+
+    fn push_server() -> SocketAddr {
+        START.call_once(|| unsafe {
+            ADDRESS = Some(start_push_server("127.0.0.1:0".parse().unwrap()).unwrap());
+        });
+        unsafe { ADDRESS.unwrap() }
+    }
+
+    fn push(address: SocketAddr, token: &str, body: &[u8]) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "PUT /{} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n",
+            token,
+            body.len()
+        )
+        .unwrap();
+        stream.write_all(body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_push_input() {
+        let address = push_server();
+        let token = TaskUploadSlots::new_token().unwrap();
+        let remote = TaskUploadSlots::push_url(&token).unwrap();
+
+        // Inputs are only accepted for open slots, once.
+        let response = push(address, &token, b"");
+        assert!(response.starts_with("HTTP/1.1 404"));
+
+        open_slot(&remote).unwrap();
+        let response = push(address, &token, b"Hello Teaclave!");
+        assert!(response.starts_with("HTTP/1.1 201"));
+        let response = push(address, &token, b"");
+        assert!(response.starts_with("HTTP/1.1 404"));
+
+        let dest = PathBuf::from("/tmp/pushed_input_test.txt");
+        tokio::runtime::Builder::new()
+            .basic_scheduler()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(download_pushed_input(&remote, &dest))
+            .unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"Hello Teaclave!");
+
+        std::fs::remove_file(&dest).unwrap();
+    }
+
+    #[test]
+    fn test_push_invalid_token() {
+        let address = push_server();
+        let response = push(address, "../../etc/passwd", b"");
+        assert!(response.starts_with("HTTP/1.1 404"));
+
+        let remote = Url::parse("push:../../etc/passwd").unwrap();
+        assert!(open_slot(&remote).is_err());
+    }
+}
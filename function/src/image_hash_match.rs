@@ -0,0 +1,175 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+extern crate base64;
+extern crate image;
+
+#[cfg(feature = "mesalock_sgx")]
+use std::prelude::v1::*;
+
+use anyhow::Context;
+use image::{DynamicImage, FilterType};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::format;
+use std::io::{BufRead, BufReader};
+use teaclave_types::{FunctionArguments, FunctionRuntime};
+
+// Each line of the inputs is a base64 encoded image (e.g., JPEG or PNG).
+const IN_IMAGES1: &str = "images1";
+const IN_IMAGES2: &str = "images2";
+
+const DEFAULT_MAX_DISTANCE: u32 = 10;
+
+#[derive(Default)]
+pub struct ImageHashMatch;
+
+#[derive(serde::Deserialize)]
+struct ImageHashMatchArguments {
+    /// Maximum Hamming distance between the perceptual hashes of two images
+    /// considered as a match, 10 by default. The hashes are 64-bit.
+    max_distance: Option<u32>,
+}
+
+impl TryFrom<FunctionArguments> for ImageHashMatchArguments {
+    type Error = anyhow::Error;
+
+    fn try_from(arguments: FunctionArguments) -> Result<Self, Self::Error> {
+        serde_json::from_str(&arguments.into_string()).context("Cannot deserialize arguments")
+    }
+}
+
+#[derive(serde::Serialize)]
+struct ImageHashMatchSummary {
+    matched_pairs: usize,
+    matched_images1: usize,
+    matched_images2: usize,
+}
+
+impl ImageHashMatch {
+    pub const NAME: &'static str = "builtin-image-hash-match";
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn run(
+        &self,
+        arguments: FunctionArguments,
+        runtime: FunctionRuntime,
+    ) -> anyhow::Result<String> {
+        let args = ImageHashMatchArguments::try_from(arguments)?;
+        let max_distance = args.max_distance.unwrap_or(DEFAULT_MAX_DISTANCE);
+
+        let hashes1 = hash_images(runtime.open_input(IN_IMAGES1)?)?;
+        let hashes2 = hash_images(runtime.open_input(IN_IMAGES2)?)?;
+
+        // Only the counts leave the enclave, neither the hashes nor which
+        // images are matched.
+        let mut matched_pairs = 0;
+        let mut matched_images1 = HashSet::new();
+        let mut matched_images2 = HashSet::new();
+        for (i, hash1) in hashes1.iter().enumerate() {
+            for (j, hash2) in hashes2.iter().enumerate() {
+                if (hash1 ^ hash2).count_ones() <= max_distance {
+                    matched_pairs += 1;
+                    matched_images1.insert(i);
+                    matched_images2.insert(j);
+                }
+            }
+        }
+
+        let summary = ImageHashMatchSummary {
+            matched_pairs,
+            matched_images1: matched_images1.len(),
+            matched_images2: matched_images2.len(),
+        };
+        Ok(serde_json::to_string(&summary)?)
+    }
+}
+
+fn hash_images<R: std::io::Read>(input: R) -> anyhow::Result<Vec<u64>> {
+    let mut hashes = Vec::new();
+    for (lineno, line) in BufReader::new(input).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let bytes = base64::decode(line)
+            .with_context(|| format!("Cannot decode image at line {}", lineno + 1))?;
+        let image = image::load_from_memory(&bytes)
+            .with_context(|| format!("Cannot load image at line {}", lineno + 1))?;
+        hashes.push(difference_hash(&image));
+    }
+    Ok(hashes)
+}
+
+// The difference hash (dHash) of an image: shrink it to 9x8 in grayscale and
+// set a bit for each pixel darker than its right neighbor. Similar images,
+// e.g., resized or recompressed copies, have close hashes.
+fn difference_hash(image: &DynamicImage) -> u64 {
+    let gray = image.resize_exact(9, 8, FilterType::Triangle).to_luma();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if gray.get_pixel(x, y)[0] < gray.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::Path;
+    use teaclave_crypto::*;
+    use teaclave_runtime::*;
+    use teaclave_test_utils::*;
+    use teaclave_types::*;
+
+    pub fn run_tests() -> bool {
+        run_tests!(test_image_hash_match)
+    }
+
+    fn test_image_hash_match() {
+        let arguments = FunctionArguments::from_json(json!({})).unwrap();
+
+        let base = Path::new("fixtures/functions/image_hash_match");
+        let images1 = base.join("images1.txt");
+        let images2 = base.join("images2.txt");
+
+        let input_files = StagedFiles::new(hashmap!(
+            IN_IMAGES1 =>
+            StagedFileInfo::new(&images1, TeaclaveFile128Key::random(), FileAuthTag::mock()),
+            IN_IMAGES2 =>
+            StagedFileInfo::new(&images2, TeaclaveFile128Key::random(), FileAuthTag::mock()),
+        ));
+        let output_files = StagedFiles::new(hashmap!());
+        let runtime = Box::new(RawIoRuntime::new(input_files, output_files));
+
+        let summary = ImageHashMatch::new().run(arguments, runtime).unwrap();
+        let summary: serde_json::Value = serde_json::from_str(&summary).unwrap();
+        assert_eq!(
+            summary,
+            json!({"matched_pairs": 1, "matched_images1": 1, "matched_images2": 1})
+        );
+    }
+}
@@ -26,6 +26,7 @@ mod echo;
 mod face_detection;
 mod gbdt_predict;
 mod gbdt_train;
+mod image_hash_match;
 mod logistic_regression_predict;
 mod logistic_regression_train;
 mod online_decrypt;
@@ -34,11 +35,13 @@ mod password_check;
 mod principal_components_analysis;
 mod private_join_and_compute;
 mod rsa_sign;
+mod time_series_anomaly_detection;
 
 pub use echo::Echo;
 pub use face_detection::FaceDetection;
 pub use gbdt_predict::GbdtPredict;
 pub use gbdt_train::GbdtTrain;
+pub use image_hash_match::ImageHashMatch;
 pub use logistic_regression_predict::LogisticRegressionPredict;
 pub use logistic_regression_train::LogisticRegressionTrain;
 pub use online_decrypt::OnlineDecrypt;
@@ -47,6 +50,7 @@ pub use password_check::PasswordCheck;
 pub use principal_components_analysis::PrincipalComponentsAnalysis;
 pub use private_join_and_compute::PrivateJoinAndCompute;
 pub use rsa_sign::RsaSign;
+pub use time_series_anomaly_detection::TimeSeriesAnomalyDetection;
 
 #[cfg(feature = "enclave_unit_test")]
 pub mod tests {
@@ -59,6 +63,7 @@ pub mod tests {
             face_detection::tests::run_tests(),
             gbdt_predict::tests::run_tests(),
             gbdt_train::tests::run_tests(),
+            image_hash_match::tests::run_tests(),
             logistic_regression_predict::tests::run_tests(),
             logistic_regression_train::tests::run_tests(),
             password_check::tests::run_tests(),
@@ -67,6 +72,7 @@ pub mod tests {
             principal_components_analysis::tests::run_tests(),
             private_join_and_compute::tests::run_tests(),
             rsa_sign::tests::run_tests(),
+            time_series_anomaly_detection::tests::run_tests(),
         )
     }
 }
@@ -0,0 +1,333 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+#[cfg(feature = "mesalock_sgx")]
+use std::prelude::v1::*;
+
+use anyhow::Context;
+use std::convert::TryFrom;
+use std::format;
+use std::io::{BufRead, BufReader, Write};
+use teaclave_types::{FunctionArguments, FunctionRuntime};
+
+// Each line of the input is "<timestamp>,<value>" in the order of time, the
+// timestamp being any string without commas.
+const IN_DATA: &str = "input_data";
+// Each line of the output is a flagged window
+// "<first timestamp>,<last timestamp>,<points>,<max score>".
+const OUT_RESULT: &str = "output_data";
+
+const DEFAULT_WINDOW: usize = 24;
+const DEFAULT_SENSITIVITY: f64 = 3.0;
+
+#[derive(Default)]
+pub struct TimeSeriesAnomalyDetection;
+
+#[derive(serde::Deserialize)]
+struct TimeSeriesAnomalyDetectionArguments {
+    /// Number of preceding points the rolling mean and standard deviation
+    /// are computed over, 24 by default.
+    window: Option<usize>,
+    /// Points deviating from the rolling mean by more than this many
+    /// standard deviations are flagged, 3.0 by default. Lower is more
+    /// sensitive.
+    sensitivity: Option<f64>,
+    /// Length of the seasonal cycle in points, e.g., 24 for hourly data with
+    /// a daily cycle. If set, the series is decomposed into trend, seasonal
+    /// and residual components, and anomalies are detected on the residuals.
+    period: Option<usize>,
+    /// Whether the first line is a header to skip.
+    #[serde(default)]
+    header: bool,
+}
+
+impl TryFrom<FunctionArguments> for TimeSeriesAnomalyDetectionArguments {
+    type Error = anyhow::Error;
+
+    fn try_from(arguments: FunctionArguments) -> Result<Self, Self::Error> {
+        serde_json::from_str(&arguments.into_string()).context("Cannot deserialize arguments")
+    }
+}
+
+#[derive(serde::Serialize)]
+struct TimeSeriesAnomalyDetectionSummary {
+    points: usize,
+    anomalies: usize,
+    windows: usize,
+}
+
+// Consecutive flagged points, by their indices in the series.
+struct FlaggedWindow {
+    first: usize,
+    last: usize,
+    points: usize,
+    max_score: f64,
+}
+
+impl TimeSeriesAnomalyDetection {
+    pub const NAME: &'static str = "builtin-time-series-anomaly-detection";
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn run(
+        &self,
+        arguments: FunctionArguments,
+        runtime: FunctionRuntime,
+    ) -> anyhow::Result<String> {
+        let args = TimeSeriesAnomalyDetectionArguments::try_from(arguments)?;
+        let window = args.window.unwrap_or(DEFAULT_WINDOW);
+        let sensitivity = args.sensitivity.unwrap_or(DEFAULT_SENSITIVITY);
+        anyhow::ensure!(window >= 2, "Window must be at least 2 points");
+        anyhow::ensure!(
+            sensitivity.is_finite() && sensitivity > 0.0,
+            "Sensitivity must be positive"
+        );
+
+        let (timestamps, values) = parse_input_data(runtime.open_input(IN_DATA)?, args.header)?;
+        let residuals = match args.period {
+            Some(period) => {
+                anyhow::ensure!(period >= 2, "Period must be at least 2 points");
+                anyhow::ensure!(
+                    values.len() >= 2 * period,
+                    "Series of {} points is shorter than two periods",
+                    values.len()
+                );
+                seasonal_residuals(&values, period)
+            }
+            None => values.iter().copied().map(Some).collect(),
+        };
+        let scores = rolling_scores(&residuals, window);
+
+        let mut windows: Vec<FlaggedWindow> = Vec::new();
+        let mut anomalies = 0;
+        for (i, score) in scores.iter().enumerate() {
+            let score = match score {
+                Some(score) if *score > sensitivity => *score,
+                _ => continue,
+            };
+            anomalies += 1;
+            match windows.last_mut() {
+                Some(last) if last.last + 1 == i => {
+                    last.last = i;
+                    last.points += 1;
+                    last.max_score = last.max_score.max(score);
+                }
+                _ => windows.push(FlaggedWindow {
+                    first: i,
+                    last: i,
+                    points: 1,
+                    max_score: score,
+                }),
+            }
+        }
+
+        let mut output = runtime.create_output(OUT_RESULT)?;
+        for flagged in windows.iter() {
+            writeln!(
+                &mut output,
+                "{},{},{},{:.4}",
+                timestamps[flagged.first],
+                timestamps[flagged.last],
+                flagged.points,
+                flagged.max_score
+            )?;
+        }
+
+        let summary = TimeSeriesAnomalyDetectionSummary {
+            points: values.len(),
+            anomalies,
+            windows: windows.len(),
+        };
+        Ok(serde_json::to_string(&summary)?)
+    }
+}
+
+fn parse_input_data(
+    input: impl std::io::Read,
+    header: bool,
+) -> anyhow::Result<(Vec<String>, Vec<f64>)> {
+    let mut timestamps = Vec::new();
+    let mut values = Vec::new();
+    let lines = BufReader::new(input)
+        .lines()
+        .enumerate()
+        .skip(header as usize);
+    for (lineno, line) in lines {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut columns = line.split(',');
+        let (timestamp, value) = match (columns.next(), columns.next(), columns.next()) {
+            (Some(timestamp), Some(value), None) => (timestamp.trim(), value.trim()),
+            _ => anyhow::bail!("Expected 2 columns at line {}", lineno + 1),
+        };
+        let value: f64 = value
+            .parse()
+            .with_context(|| format!("Invalid value at line {}", lineno + 1))?;
+        anyhow::ensure!(value.is_finite(), "Invalid value at line {}", lineno + 1);
+        timestamps.push(timestamp.to_string());
+        values.push(value);
+    }
+    Ok((timestamps, values))
+}
+
+// Classical additive decomposition: the trend is the centered moving average
+// over a period, the seasonal component the mean deviation from the trend at
+// each phase of the period, and the residual what is left. The residuals are
+// only defined where the trend is, i.e., not in the first and last half
+// periods.
+fn seasonal_residuals(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let half = period / 2;
+    let trend: Vec<Option<f64>> = (0..values.len())
+        .map(|i| {
+            if i < half || i + half >= values.len() {
+                return None;
+            }
+            let window = &values[i - half..=i + half];
+            // An even period takes half of both ends to stay centered.
+            let sum: f64 = if period % 2 == 0 {
+                window.iter().sum::<f64>() - (window[0] + window[period]) / 2.0
+            } else {
+                window.iter().sum()
+            };
+            Some(sum / period as f64)
+        })
+        .collect();
+
+    let mut deviations = vec![(0.0, 0usize); period];
+    for (i, trend) in trend.iter().enumerate() {
+        if let Some(trend) = trend {
+            deviations[i % period].0 += values[i] - trend;
+            deviations[i % period].1 += 1;
+        }
+    }
+    let mut seasonal: Vec<f64> = deviations
+        .iter()
+        .map(|(sum, count)| sum / *count as f64)
+        .collect();
+    let mean = seasonal.iter().sum::<f64>() / period as f64;
+    seasonal.iter_mut().for_each(|s| *s -= mean);
+
+    trend
+        .iter()
+        .enumerate()
+        .map(|(i, trend)| trend.map(|trend| values[i] - trend - seasonal[i % period]))
+        .collect()
+}
+
+// Scores each point by how many standard deviations it is away from the mean
+// of the preceding window of defined points. Points without a full window
+// before them are not scored. A flat window scores any deviation as
+// infinitely anomalous.
+fn rolling_scores(series: &[Option<f64>], window: usize) -> Vec<Option<f64>> {
+    let mut history: Vec<f64> = Vec::new();
+    series
+        .iter()
+        .map(|value| {
+            let value = (*value)?;
+            let score = if history.len() >= window {
+                let recent = &history[history.len() - window..];
+                let mean = recent.iter().sum::<f64>() / window as f64;
+                let variance =
+                    recent.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / window as f64;
+                let deviation = (value - mean).abs();
+                let std_dev = variance.sqrt();
+                Some(if std_dev > 0.0 {
+                    deviation / std_dev
+                } else if deviation > 0.0 {
+                    f64::INFINITY
+                } else {
+                    0.0
+                })
+            } else {
+                None
+            };
+            history.push(value);
+            score
+        })
+        .collect()
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::Path;
+    use std::untrusted::fs;
+    use teaclave_crypto::*;
+    use teaclave_runtime::*;
+    use teaclave_test_utils::*;
+    use teaclave_types::*;
+
+    pub fn run_tests() -> bool {
+        run_tests!(
+            test_time_series_anomaly_detection,
+            test_time_series_anomaly_detection_seasonal
+        )
+    }
+
+    fn run_detection(arguments: serde_json::Value, result: &str) -> serde_json::Value {
+        let arguments = FunctionArguments::from_json(arguments).unwrap();
+
+        let base = Path::new("fixtures/functions/time_series_anomaly_detection");
+        let input_data_file = base.join("input.csv");
+        let output_data_file = base.join(result);
+
+        let input_files = StagedFiles::new(hashmap!(
+            IN_DATA =>
+            StagedFileInfo::new(&input_data_file, TeaclaveFile128Key::random(), FileAuthTag::mock()),
+        ));
+        let output_files = StagedFiles::new(hashmap!(
+            OUT_RESULT =>
+            StagedFileInfo::new(&output_data_file, TeaclaveFile128Key::random(), FileAuthTag::mock()),
+        ));
+        let runtime = Box::new(RawIoRuntime::new(input_files, output_files));
+
+        let summary = TimeSeriesAnomalyDetection::new()
+            .run(arguments, runtime)
+            .unwrap();
+        serde_json::from_str(&summary).unwrap()
+    }
+
+    fn test_time_series_anomaly_detection() {
+        let summary = run_detection(
+            json!({"window": 12, "sensitivity": 4.0, "header": true}),
+            "result.csv",
+        );
+        assert_eq!(summary, json!({"points": 96, "anomalies": 1, "windows": 1}));
+
+        let base = Path::new("fixtures/functions/time_series_anomaly_detection");
+        let result = fs::read_to_string(base.join("result.csv")).unwrap();
+        assert!(result.starts_with("2020-01-03T12:00,2020-01-03T12:00,1,"));
+    }
+
+    fn test_time_series_anomaly_detection_seasonal() {
+        // Without the daily cycle, the point after the spike stands out too.
+        let summary = run_detection(
+            json!({"window": 24, "sensitivity": 4.0, "period": 24, "header": true}),
+            "seasonal_result.csv",
+        );
+        assert_eq!(summary, json!({"points": 96, "anomalies": 2, "windows": 1}));
+
+        let base = Path::new("fixtures/functions/time_series_anomaly_detection");
+        let result = fs::read_to_string(base.join("seasonal_result.csv")).unwrap();
+        assert!(result.starts_with("2020-01-03T12:00,2020-01-03T13:00,2,"));
+    }
+}
@@ -16,7 +16,7 @@
 // under the License.
 
 use crate::config::SgxTrustedTlsClientConfig;
-use crate::transport::{ClientTransport, SgxTrustedTlsTransport};
+use crate::transport::{ClientTransport, MultiplexedClientTransport, SgxTrustedTlsTransport};
 use crate::Request;
 use anyhow::anyhow;
 use anyhow::Result;
@@ -24,12 +24,58 @@ use http::Uri;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+pub(crate) enum ChannelTransport {
+    Exclusive(SgxTrustedTlsTransport<rustls::ClientSession>),
+    Multiplexed(MultiplexedClientTransport),
+}
+
+impl ClientTransport for ChannelTransport {
+    fn send<U, V>(
+        &mut self,
+        request: Request<U>,
+    ) -> teaclave_types::TeaclaveServiceResponseResult<V>
+    where
+        U: Serialize + std::fmt::Debug,
+        V: for<'de> Deserialize<'de> + std::fmt::Debug,
+    {
+        match self {
+            ChannelTransport::Exclusive(transport) => transport.send(request),
+            ChannelTransport::Multiplexed(transport) => transport.send(request),
+        }
+    }
+
+    fn send_stream<U>(
+        &mut self,
+        request: Request<U>,
+    ) -> teaclave_types::TeaclaveServiceResponseResult<()>
+    where
+        U: Serialize + std::fmt::Debug,
+    {
+        match self {
+            ChannelTransport::Exclusive(transport) => transport.send_stream(request),
+            ChannelTransport::Multiplexed(transport) => transport.send_stream(request),
+        }
+    }
+
+    fn recv_stream_item<V>(&mut self) -> teaclave_types::TeaclaveServiceResponseResult<Option<V>>
+    where
+        V: for<'de> Deserialize<'de> + std::fmt::Debug,
+    {
+        match self {
+            ChannelTransport::Exclusive(transport) => transport.recv_stream_item(),
+            ChannelTransport::Multiplexed(transport) => transport.recv_stream_item(),
+        }
+    }
+}
+
 pub struct SgxTrustedTlsChannel<U, V>
 where
     U: Serialize + std::fmt::Debug,
     V: for<'de> Deserialize<'de> + std::fmt::Debug,
 {
-    transport: SgxTrustedTlsTransport<rustls::ClientSession>,
+    transport: ChannelTransport,
+    #[cfg(not(feature = "mesalock_sgx"))]
+    trace_hook: Option<crate::trace::TraceHook>,
     maker: std::marker::PhantomData<(U, V)>,
 }
 
@@ -42,25 +88,155 @@ where
         address: &str,
         client_config: &SgxTrustedTlsClientConfig,
     ) -> Result<SgxTrustedTlsChannel<U, V>> {
+        let tls_stream = Self::connect(address, client_config)?;
+        let transport = SgxTrustedTlsTransport::new(tls_stream);
+
+        Ok(Self::with_transport(ChannelTransport::Exclusive(transport)))
+    }
+
+    /// Connects a channel whose requests are multiplexed on the connection
+    /// with those of its clones, see `try_clone`. Streaming methods are not
+    /// available on such channels.
+    pub fn new_multiplexed(
+        address: &str,
+        client_config: &SgxTrustedTlsClientConfig,
+    ) -> Result<SgxTrustedTlsChannel<U, V>> {
+        let tls_stream = Self::connect(address, client_config)?;
+        let transport = MultiplexedClientTransport::new(tls_stream)?;
+
+        Ok(Self::with_transport(ChannelTransport::Multiplexed(
+            transport,
+        )))
+    }
+
+    fn connect(
+        address: &str,
+        client_config: &SgxTrustedTlsClientConfig,
+    ) -> Result<rustls::StreamOwned<rustls::ClientSession, std::net::TcpStream>> {
         let uri = address.parse::<Uri>()?;
         let hostname = uri.host().ok_or_else(|| anyhow!("Invalid hostname."))?;
         let stream = std::net::TcpStream::connect(address)?;
         let hostname = webpki::DNSNameRef::try_from_ascii_str(hostname)?;
         let session =
             rustls::ClientSession::new(&Arc::new(client_config.client_config.clone()), hostname);
-        let tls_stream = rustls::StreamOwned::new(session, stream);
-        let transport = SgxTrustedTlsTransport::new(tls_stream);
+        Ok(rustls::StreamOwned::new(session, stream))
+    }
 
-        Ok(Self {
+    fn with_transport(transport: ChannelTransport) -> SgxTrustedTlsChannel<U, V> {
+        Self {
             transport,
+            #[cfg(not(feature = "mesalock_sgx"))]
+            trace_hook: None,
             maker: std::marker::PhantomData::<(U, V)>,
-        })
+        }
+    }
+
+    /// Another channel on the connection of a multiplexed channel, e.g., for
+    /// another thread. Requests of the channels are in flight at the same
+    /// time, and the connection is closed once all of them are dropped.
+    pub fn try_clone(&self) -> Result<SgxTrustedTlsChannel<U, V>> {
+        match &self.transport {
+            ChannelTransport::Multiplexed(transport) => Ok(Self {
+                transport: ChannelTransport::Multiplexed(transport.clone()),
+                #[cfg(not(feature = "mesalock_sgx"))]
+                trace_hook: self.trace_hook.clone(),
+                maker: std::marker::PhantomData::<(U, V)>,
+            }),
+            ChannelTransport::Exclusive(_) => Err(anyhow!("Channel is not multiplexed")),
+        }
+    }
+
+    /// Calls the hook with every request and response, or stops tracing if
+    /// `None` is given.
+    #[cfg(not(feature = "mesalock_sgx"))]
+    pub fn set_trace_hook(&mut self, hook: Option<crate::trace::TraceHook>) {
+        self.trace_hook = hook;
     }
 
     pub fn invoke(
         &mut self,
-        input: Request<U>,
+        mut input: Request<U>,
     ) -> teaclave_types::TeaclaveServiceResponseResult<V> {
+        // Requests sent while handling a request carry on its trace id.
+        if let Some(trace_id) = crate::crash::current_trace_id() {
+            input
+                .metadata
+                .entry(crate::crash::TRACE_ID_METADATA.to_string())
+                .or_insert(trace_id);
+        }
+        #[cfg(not(feature = "mesalock_sgx"))]
+        {
+            if let Some(hook) = &self.trace_hook {
+                return crate::trace::send(&mut self.transport, input, hook);
+            }
+        }
+        self.send(input)
+    }
+
+    /// Sends a request of a streaming method, returning its responses as they
+    /// are read. Streams are neither traced nor subject to fault injection,
+    /// and are not available on multiplexed channels.
+    pub fn invoke_stream(
+        &mut self,
+        mut input: Request<U>,
+    ) -> teaclave_types::TeaclaveServiceResponseResult<ResponseStream<'_, V>> {
+        if let Some(trace_id) = crate::crash::current_trace_id() {
+            input
+                .metadata
+                .entry(crate::crash::TRACE_ID_METADATA.to_string())
+                .or_insert(trace_id);
+        }
+        self.transport.send_stream(input)?;
+        Ok(ResponseStream {
+            transport: &mut self.transport,
+            done: false,
+            maker: std::marker::PhantomData::<V>,
+        })
+    }
+
+    #[cfg(not(feature = "fault_injection"))]
+    fn send(&mut self, input: Request<U>) -> teaclave_types::TeaclaveServiceResponseResult<V> {
         self.transport.send(input)
     }
+
+    #[cfg(feature = "fault_injection")]
+    fn send(&mut self, input: Request<U>) -> teaclave_types::TeaclaveServiceResponseResult<V> {
+        crate::fault::send(&mut self.transport, input)
+    }
+}
+
+/// Responses of a streaming method. The channel is borrowed until the stream
+/// is dropped, and is not usable for other requests unless the stream has been
+/// read to the end.
+pub struct ResponseStream<'a, V>
+where
+    V: for<'de> Deserialize<'de> + std::fmt::Debug,
+{
+    transport: &'a mut ChannelTransport,
+    done: bool,
+    maker: std::marker::PhantomData<V>,
+}
+
+impl<'a, V> Iterator for ResponseStream<'a, V>
+where
+    V: for<'de> Deserialize<'de> + std::fmt::Debug,
+{
+    type Item = teaclave_types::TeaclaveServiceResponseResult<V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.transport.recv_stream_item() {
+            Ok(Some(response)) => Some(Ok(response)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
 }
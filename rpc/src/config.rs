@@ -20,20 +20,20 @@ use log::debug;
 #[cfg(feature = "mesalock_sgx")]
 use std::prelude::v1::*;
 #[cfg(not(feature = "mesalock_sgx"))]
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
 #[cfg(feature = "mesalock_sgx")]
-use std::sync::SgxRwLock as RwLock;
+use std::sync::{SgxMutex as Mutex, SgxRwLock as RwLock};
 
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::SystemTime;
-
-#[cfg(feature = "mesalock_sgx")]
-use std::untrusted::time::SystemTimeEx;
 
 use teaclave_attestation::report::AttestationReport;
 use teaclave_attestation::verifier::AttestationReportVerifier;
 use teaclave_attestation::AttestedTlsConfig;
-use teaclave_types::EnclaveAttr;
+use teaclave_types::{EnclaveAttr, TrustedTime};
+
+// Maximum number of TLS sessions cached for resumption.
+const SESSION_CACHE_SIZE: usize = 256;
 
 #[derive(Clone)]
 pub struct SgxTrustedTlsServerConfig {
@@ -46,8 +46,9 @@ pub struct SgxTrustedTlsServerConfig {
 impl Default for SgxTrustedTlsServerConfig {
     fn default() -> Self {
         let client_cert_verifier = rustls::NoClientAuth::new();
-        let server_config = rustls::ServerConfig::new(client_cert_verifier);
-        let time = SystemTime::now();
+        let mut server_config = rustls::ServerConfig::new(client_cert_verifier);
+        server_config.session_storage = rustls::ServerSessionMemoryCache::new(SESSION_CACHE_SIZE);
+        let time = TrustedTime::now();
         let validity = std::time::Duration::from_secs(u64::max_value());
 
         Self {
@@ -107,7 +108,7 @@ impl SgxTrustedTlsServerConfig {
     }
 
     pub fn need_refresh(&self) -> bool {
-        let current_time = SystemTime::now();
+        let current_time = TrustedTime::now();
         let elapsed_time = current_time
             .duration_since(self.time)
             .unwrap_or(self.validity);
@@ -130,6 +131,10 @@ impl SgxTrustedTlsServerConfig {
 
         let mut new_server_config = self.server_config.clone();
         new_server_config.set_single_cert(cert_chain, key_der)?;
+        // Sessions established with the outdated report cannot be resumed,
+        // clients have to verify the refreshed report in a full handshake.
+        new_server_config.session_storage =
+            rustls::ServerSessionMemoryCache::new(SESSION_CACHE_SIZE);
 
         self.server_config = new_server_config;
         self.time = attested_tls_config.time;
@@ -143,6 +148,7 @@ pub struct SgxTrustedTlsClientConfig {
     pub client_config: rustls::ClientConfig,
     pub attested_tls_config: Option<Arc<RwLock<AttestedTlsConfig>>>,
     pub validity: std::time::Duration,
+    session_identity: Option<Vec<u8>>,
 }
 
 struct NoServerAuth;
@@ -179,11 +185,15 @@ impl Default for SgxTrustedTlsClientConfig {
         client_config
             .versions
             .push(rustls::ProtocolVersion::TLSv1_2);
+        // Sessions are only resumed with servers verified by the attestation
+        // report verifier.
+        client_config.set_persistence(Arc::new(rustls::NoClientSessionStorage {}));
 
         Self {
             client_config,
             attested_tls_config: None,
             validity: std::time::Duration::default(),
+            session_identity: None,
         }
     }
 }
@@ -199,6 +209,7 @@ impl SgxTrustedTlsClientConfig {
         root_ca: &[u8],
         verifier: fn(&AttestationReport) -> bool,
     ) -> Self {
+        let session_identity = session_identity(&accepted_enclave_attrs, root_ca, verifier);
         let verifier = Arc::new(AttestationReportVerifier::new(
             accepted_enclave_attrs,
             root_ca,
@@ -207,10 +218,27 @@ impl SgxTrustedTlsClientConfig {
         self.client_config
             .dangerous()
             .set_certificate_verifier(verifier);
+        self.client_config
+            .set_persistence(rustls::ClientSessionMemoryCache::new(SESSION_CACHE_SIZE));
+        self.session_identity = Some(session_identity);
 
         Self { ..self }
     }
 
+    /// Shares the sessions of `cache` with other configs verifying the same
+    /// enclave identity, so that new channels can resume a previously
+    /// verified session instead of doing a full attested handshake.
+    pub fn session_cache(mut self, cache: &SgxTrustedTlsSessionCache) -> Result<Self> {
+        let identity = match &self.session_identity {
+            Some(identity) => identity,
+            None => bail!("Attestation report verifier is not set"),
+        };
+        let persistence = cache.get(identity)?;
+        self.client_config.set_persistence(persistence);
+
+        Ok(Self { ..self })
+    }
+
     pub fn client_cert(mut self, cert: &[u8], key_der: &[u8]) -> Self {
         let cert_chain = vec![rustls::Certificate(cert.to_vec())];
         let key_der = rustls::PrivateKey(key_der.to_vec());
@@ -220,6 +248,14 @@ impl SgxTrustedTlsClientConfig {
         Self { ..self }
     }
 
+    /// Presents a client certificate resolved by `resolver`, e.g., one whose
+    /// private key is kept in a hardware token rather than in memory.
+    pub fn client_cert_resolver(mut self, resolver: Arc<dyn rustls::ResolvesClientCert>) -> Self {
+        self.client_config.client_auth_cert_resolver = resolver;
+
+        Self { ..self }
+    }
+
     pub fn from_attested_tls_config(
         attested_tls_config: Arc<RwLock<AttestedTlsConfig>>,
     ) -> Result<Self> {
@@ -230,3 +266,43 @@ impl SgxTrustedTlsClientConfig {
         Ok(config)
     }
 }
+
+// Identifies what has been verified in a full handshake: the accepted enclave
+// measurements, the root CA and the verification function.
+fn session_identity(
+    accepted_enclave_attrs: &[EnclaveAttr],
+    root_ca: &[u8],
+    verifier: fn(&AttestationReport) -> bool,
+) -> Vec<u8> {
+    let mut identity = Vec::new();
+    for attr in accepted_enclave_attrs {
+        identity.extend_from_slice(&attr.measurement.mr_signer);
+        identity.extend_from_slice(&attr.measurement.mr_enclave);
+    }
+    identity.extend_from_slice(root_ca);
+    identity.extend_from_slice(&(verifier as usize).to_le_bytes());
+    identity
+}
+
+/// TLS sessions of clients which can be shared by channels, e.g., to avoid
+/// repeated attested handshakes when reconnecting to a service. Sessions are
+/// partitioned by the verified enclave identity and are never resumed with a
+/// server verified against different attributes.
+#[derive(Clone, Default)]
+pub struct SgxTrustedTlsSessionCache {
+    caches: Arc<Mutex<HashMap<Vec<u8>, Arc<rustls::ClientSessionMemoryCache>>>>,
+}
+
+impl SgxTrustedTlsSessionCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn get(&self, identity: &[u8]) -> Result<Arc<rustls::ClientSessionMemoryCache>> {
+        let mut caches = self.caches.lock().map_err(|_| anyhow!("lock error"))?;
+        let cache = caches
+            .entry(identity.to_vec())
+            .or_insert_with(|| rustls::ClientSessionMemoryCache::new(SESSION_CACHE_SIZE));
+        Ok(cache.clone())
+    }
+}
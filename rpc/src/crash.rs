@@ -0,0 +1,121 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Capturing panics of services. Every request is handled with a trace id,
+//! taken from the `trace_id` metadata of the request or generated, and a panic
+//! in the handler is logged as a crash report with the id and answered with an
+//! internal error carrying the id, instead of silently killing the thread.
+
+use log::error;
+use serde::Serialize;
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe, PanicInfo};
+use std::prelude::v1::*;
+use teaclave_types::{TeaclaveServiceResponseError, TeaclaveServiceResponseResult};
+use uuid::Uuid;
+
+pub const TRACE_ID_METADATA: &str = "trace_id";
+const MAX_TRACE_ID_LENGTH: usize = 64;
+
+thread_local! {
+    static TRACE_ID: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Trace id of the request handled by the current thread, if any.
+pub fn current_trace_id() -> Option<String> {
+    TRACE_ID.with(|trace_id| trace_id.borrow().clone())
+}
+
+/// Report of a panic, logged as JSON by the panic hook.
+#[derive(Debug, Serialize)]
+pub struct CrashReport {
+    pub trace_id: Option<String>,
+    pub message: String,
+    pub location: Option<String>,
+}
+
+impl CrashReport {
+    fn new(info: &PanicInfo) -> Self {
+        Self {
+            trace_id: current_trace_id(),
+            message: payload_message(info.payload()),
+            location: info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+        }
+    }
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.to_owned()
+    } else {
+        "Box<Any>".to_string()
+    }
+}
+
+/// Logs a crash report on every panic, before the default hook prints the
+/// message and the backtrace (if backtraces are enabled).
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let report = CrashReport::new(info);
+        match serde_json::to_string(&report) {
+            Ok(report) => error!("Crash report: {}", report),
+            Err(_) => error!("Crash report: {:?}", report),
+        }
+        default_hook(info);
+    }));
+}
+
+// Ids given by clients are used if they cannot garble the logs.
+pub(crate) fn trace_id_of(metadata: &HashMap<String, String>) -> String {
+    match metadata.get(TRACE_ID_METADATA) {
+        Some(trace_id)
+            if !trace_id.is_empty()
+                && trace_id.len() <= MAX_TRACE_ID_LENGTH
+                && trace_id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            trace_id.to_owned()
+        }
+        _ => Uuid::new_v4().to_string(),
+    }
+}
+
+/// Handles a request with the trace id, turning a panic into an internal
+/// error. The panic message is only logged, because it may contain data of
+/// the request.
+pub(crate) fn handle_with_trace_id<U, F>(trace_id: String, f: F) -> TeaclaveServiceResponseResult<U>
+where
+    F: FnOnce() -> TeaclaveServiceResponseResult<U>,
+{
+    TRACE_ID.with(|current| *current.borrow_mut() = Some(trace_id.clone()));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    TRACE_ID.with(|current| *current.borrow_mut() = None);
+
+    result.unwrap_or_else(|_| {
+        Err(TeaclaveServiceResponseError::InternalError(format!(
+            "service panicked, trace id: {}",
+            trace_id
+        )))
+    })
+}
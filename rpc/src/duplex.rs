@@ -0,0 +1,139 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! TLS streams read and written by different threads at the same time, so
+//! that requests multiplexed on a connection are sent while the responses of
+//! earlier ones are read. A rustls session cannot be split into halves, so it
+//! is shared behind a lock, which is only held while records are processed,
+//! never while waiting for the socket.
+
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::prelude::v1::*;
+use std::sync::Arc;
+#[cfg(not(feature = "mesalock_sgx"))]
+use std::sync::{Mutex, MutexGuard};
+#[cfg(feature = "mesalock_sgx")]
+use std::sync::{SgxMutex as Mutex, SgxMutexGuard as MutexGuard};
+
+// Records are read from the socket in chunks of this size at most.
+const READ_CHUNK_SIZE: usize = 16 * 1024;
+
+pub(crate) struct DuplexTlsStream<S>
+where
+    S: rustls::Session,
+{
+    session: Arc<Mutex<S>>,
+    sock: TcpStream,
+    chunk: Vec<u8>,
+}
+
+impl<S> DuplexTlsStream<S>
+where
+    S: rustls::Session,
+{
+    pub fn new(stream: rustls::StreamOwned<S, TcpStream>) -> DuplexTlsStream<S> {
+        Self {
+            session: Arc::new(Mutex::new(stream.sess)),
+            sock: stream.sock,
+            chunk: vec![0u8; READ_CHUNK_SIZE],
+        }
+    }
+
+    /// Another handle of the stream, e.g., for the thread reading the
+    /// responses while others write requests.
+    pub fn try_clone(&self) -> io::Result<DuplexTlsStream<S>> {
+        Ok(Self {
+            session: self.session.clone(),
+            sock: self.sock.try_clone()?,
+            chunk: vec![0u8; READ_CHUNK_SIZE],
+        })
+    }
+
+    pub fn peer_certificates(&self) -> Option<Vec<rustls::Certificate>> {
+        lock(&self.session).ok()?.get_peer_certificates()
+    }
+
+    /// Shuts the connection down, which also wakes up the thread reading it.
+    pub fn shutdown(&self) {
+        let _ = self.sock.shutdown(Shutdown::Both);
+    }
+}
+
+fn lock<S>(session: &Mutex<S>) -> io::Result<MutexGuard<S>> {
+    session
+        .lock()
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "TLS session poisoned"))
+}
+
+fn write_tls<S: rustls::Session>(session: &mut S, sock: &mut TcpStream) -> io::Result<()> {
+    while session.wants_write() {
+        session.write_tls(sock)?;
+    }
+    Ok(())
+}
+
+impl<S> Read for DuplexTlsStream<S>
+where
+    S: rustls::Session,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            {
+                // Handshake messages and the plaintext buffered during the
+                // handshake are written once the peer lets us.
+                let mut session = lock(&self.session)?;
+                write_tls(&mut *session, &mut self.sock)?;
+                let len = session.read(buf)?;
+                if len > 0 || buf.is_empty() {
+                    return Ok(len);
+                }
+            }
+
+            let len = self.sock.read(&mut self.chunk)?;
+            if len == 0 {
+                return Ok(0);
+            }
+            let mut session = lock(&self.session)?;
+            let mut records = &self.chunk[..len];
+            while !records.is_empty() {
+                session.read_tls(&mut records)?;
+                session
+                    .process_new_packets()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            }
+        }
+    }
+}
+
+impl<S> Write for DuplexTlsStream<S>
+where
+    S: rustls::Session,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut session = lock(&self.session)?;
+        let len = session.write(buf)?;
+        write_tls(&mut *session, &mut self.sock)?;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut session = lock(&self.session)?;
+        session.flush()?;
+        write_tls(&mut *session, &mut self.sock)
+    }
+}
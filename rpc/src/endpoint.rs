@@ -43,6 +43,16 @@ impl Endpoint {
         SgxTrustedTlsChannel::<U, V>::new(&self.url, &self.config)
     }
 
+    /// Connects a channel multiplexing the requests of its clones on the
+    /// connection.
+    pub fn connect_multiplexed<U, V>(&self) -> Result<SgxTrustedTlsChannel<U, V>>
+    where
+        U: Serialize + std::fmt::Debug,
+        V: for<'de> Deserialize<'de> + std::fmt::Debug,
+    {
+        SgxTrustedTlsChannel::<U, V>::new_multiplexed(&self.url, &self.config)
+    }
+
     pub fn config(self, config: SgxTrustedTlsClientConfig) -> Self {
         Self {
             url: self.url,
@@ -0,0 +1,186 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Fault injection of the RPC clients, built with the `fault_injection`
+//! feature for tests only. Faults are injected by the thread issuing the
+//! requests, so that each test controls the faults of its own clients,
+//! including the storage clients.
+
+use crate::transport::ClientTransport;
+use crate::Request;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::prelude::v1::*;
+use std::time::Duration;
+use teaclave_types::{TeaclaveServiceResponseError, TeaclaveServiceResponseResult};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fault {
+    /// The request is lost before reaching the service.
+    Drop,
+    /// The request is sent after the delay.
+    Delay(Duration),
+    /// The request is sent twice, and the response of the second is returned.
+    Duplicate,
+    /// The request is handled by the service, but the response is corrupted
+    /// and cannot be decoded.
+    Corrupt,
+}
+
+/// A fault injected into the requests of the given name, e.g., `put` or
+/// `update_task_result`, or into any request if no name is given.
+#[derive(Debug, Clone)]
+pub struct FaultRule {
+    fault: Fault,
+    request: Option<String>,
+    skip: usize,
+    times: Option<usize>,
+}
+
+impl FaultRule {
+    pub fn new(fault: Fault) -> Self {
+        Self {
+            fault,
+            request: None,
+            skip: 0,
+            times: None,
+        }
+    }
+
+    pub fn request(self, request: impl ToString) -> Self {
+        Self {
+            request: Some(request.to_string()),
+            ..self
+        }
+    }
+
+    /// Lets the first `skip` matching requests through.
+    pub fn skip(self, skip: usize) -> Self {
+        Self { skip, ..self }
+    }
+
+    /// Injects the fault `times` times only, every time by default.
+    pub fn times(self, times: usize) -> Self {
+        Self {
+            times: Some(times),
+            ..self
+        }
+    }
+
+    fn apply(&mut self, request: &str) -> Option<Fault> {
+        if self.times == Some(0) || self.request.as_ref().map_or(false, |r| r != request) {
+            return None;
+        }
+        if self.skip > 0 {
+            self.skip -= 1;
+            return None;
+        }
+        if let Some(times) = self.times.as_mut() {
+            *times -= 1;
+        }
+        Some(self.fault)
+    }
+}
+
+thread_local! {
+    static FAULT_RULES: RefCell<Vec<FaultRule>> = RefCell::new(Vec::new());
+}
+
+pub fn inject(rule: FaultRule) {
+    FAULT_RULES.with(|rules| rules.borrow_mut().push(rule));
+}
+
+pub fn clear() {
+    FAULT_RULES.with(|rules| rules.borrow_mut().clear());
+}
+
+/// Faults injected until the scope is dropped, e.g., at the end of a test.
+pub struct FaultScope;
+
+impl FaultScope {
+    pub fn new(rules: Vec<FaultRule>) -> Self {
+        for rule in rules {
+            inject(rule);
+        }
+        Self
+    }
+}
+
+impl Drop for FaultScope {
+    fn drop(&mut self) {
+        clear();
+    }
+}
+
+// The first matching rule decides the fault of a request.
+fn next_fault(request: &str) -> Option<Fault> {
+    FAULT_RULES.with(|rules| {
+        rules
+            .borrow_mut()
+            .iter_mut()
+            .find_map(|rule| rule.apply(request))
+    })
+}
+
+pub(crate) fn send<T, U, V>(
+    transport: &mut T,
+    request: Request<U>,
+) -> TeaclaveServiceResponseResult<V>
+where
+    T: ClientTransport,
+    U: Serialize + std::fmt::Debug,
+    V: for<'de> Deserialize<'de> + std::fmt::Debug,
+{
+    let has_rules = FAULT_RULES.with(|rules| !rules.borrow().is_empty());
+    if !has_rules {
+        return transport.send(request);
+    }
+
+    let message = serde_json::to_value(&request.message)
+        .map_err(|_| TeaclaveServiceResponseError::InternalError("serde".to_string()))?;
+    let name = message["request"].as_str().unwrap_or_default().to_string();
+    let request = Request {
+        metadata: request.metadata,
+        message,
+    };
+
+    match next_fault(&name) {
+        None => transport.send(request),
+        Some(fault) => {
+            log::debug!("Inject fault {:?} into {}", fault, name);
+            match fault {
+                Fault::Drop => Err(TeaclaveServiceResponseError::ConnectionError(
+                    "request dropped by fault injection".to_string(),
+                )),
+                Fault::Delay(delay) => {
+                    std::thread::sleep(delay);
+                    transport.send(request)
+                }
+                Fault::Duplicate => {
+                    let _ = transport.send::<_, serde_json::Value>(request.clone());
+                    transport.send(request)
+                }
+                Fault::Corrupt => {
+                    let _ = transport.send::<_, serde_json::Value>(request);
+                    Err(TeaclaveServiceResponseError::InternalError(
+                        "serde".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+}
@@ -21,7 +21,7 @@
 extern crate sgx_tstd as std;
 
 use serde::{Deserialize, Serialize};
-use teaclave_types::TeaclaveServiceResponseError;
+use teaclave_types::{TeaclaveErrorCode, TeaclaveServiceResponseError};
 
 pub trait TeaclaveService<V, U>
 where
@@ -32,15 +32,40 @@ where
         &self,
         request: Request<V>,
     ) -> std::result::Result<U, TeaclaveServiceResponseError>;
+
+    /// Whether the request is of a method streaming its responses, which is
+    /// handled by `handle_stream_request` instead.
+    fn is_stream_request(&self, _request: &Request<V>) -> bool {
+        false
+    }
+
+    /// Handles a request of a method streaming its responses, sending them
+    /// with `sink` in order. An error ends the stream.
+    fn handle_stream_request(
+        &self,
+        _request: Request<V>,
+        _sink: &mut dyn FnMut(U) -> std::result::Result<(), TeaclaveServiceResponseError>,
+    ) -> std::result::Result<(), TeaclaveServiceResponseError> {
+        Err(TeaclaveServiceResponseError::request_error(
+            TeaclaveErrorCode::Validation,
+            "not a streaming request",
+        ))
+    }
 }
 
 pub mod channel;
 pub mod config;
+pub mod crash;
+mod duplex;
 pub mod endpoint;
+#[cfg(feature = "fault_injection")]
+pub mod fault;
 mod protocol;
 mod request;
 pub use request::{IntoRequest, Request};
 pub use teaclave_rpc_proc_macro::into_request;
 pub mod server;
+#[cfg(not(feature = "mesalock_sgx"))]
+pub mod trace;
 mod transport;
 mod utils;
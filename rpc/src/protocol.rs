@@ -17,6 +17,7 @@
 
 use log::trace;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::io;
 use std::prelude::v1::*;
 use std::vec::Vec;
@@ -37,7 +38,11 @@ impl From<ProtocolError> for TeaclaveServiceResponseError {
     fn from(error: ProtocolError) -> Self {
         match error {
             ProtocolError::IoError(e) => {
-                TeaclaveServiceResponseError::ConnectionError(format!("{}", e))
+                if is_attestation_error(&e) {
+                    TeaclaveServiceResponseError::AttestationError(format!("{}", e))
+                } else {
+                    TeaclaveServiceResponseError::ConnectionError(format!("{}", e))
+                }
             }
             ProtocolError::SerdeError(_) => {
                 TeaclaveServiceResponseError::InternalError("serde".to_string())
@@ -49,6 +54,19 @@ impl From<ProtocolError> for TeaclaveServiceResponseError {
     }
 }
 
+// The attestation report is verified in the TLS handshake, and a rejected
+// certificate is reported by rustls as an I/O error.
+fn is_attestation_error(error: &io::Error) -> bool {
+    match error
+        .get_ref()
+        .and_then(|e| e.downcast_ref::<rustls::TLSError>())
+    {
+        Some(rustls::TLSError::WebPKIError(_))
+        | Some(rustls::TLSError::NoCertificatesPresented) => true,
+        _ => false,
+    }
+}
+
 pub(crate) struct JsonProtocol<'a, T>
 where
     T: io::Read + io::Write,
@@ -136,3 +154,38 @@ impl<T, E> From<JsonProtocolResult<T, E>> for std::result::Result<T, E> {
         }
     }
 }
+
+/// Frames of the responses of a streaming method. The stream ends with an
+/// `end` frame, or an `err` frame if the handler fails midway.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "stream")]
+#[serde(rename_all = "snake_case")]
+pub enum JsonProtocolStreamFrame<T, E> {
+    Item { content: T },
+    End,
+    Err { error: E },
+}
+
+/// Field of the frames of a request multiplexed with others on a connection,
+/// holding the id given by the client. The frames of its response are tagged
+/// with the same id, and may be sent before those of earlier requests.
+/// Requests without an id are answered in order.
+pub(crate) const REQUEST_ID_FIELD: &str = "request_id";
+
+/// Tags a frame with the id of its request.
+pub(crate) fn tag_frame<T>(frame: T, request_id: u64) -> std::result::Result<Value, ProtocolError>
+where
+    T: Serialize,
+{
+    let mut frame = serde_json::to_value(frame)?;
+    frame
+        .as_object_mut()
+        .ok_or_else(|| anyhow::anyhow!("Frame is not an object"))?
+        .insert(REQUEST_ID_FIELD.to_string(), request_id.into());
+    Ok(frame)
+}
+
+/// Takes the id of the request out of a frame, if it is tagged with one.
+pub(crate) fn untag_frame(frame: &mut Value) -> Option<u64> {
+    frame.as_object_mut()?.remove(REQUEST_ID_FIELD)?.as_u64()
+}
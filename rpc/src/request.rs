@@ -19,6 +19,13 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::prelude::v1::*;
 
+/// Metadata set by the server to the hex encoded MRENCLAVE of the attested
+/// peer of the connection. Values sent by clients are dropped.
+pub(crate) const PEER_MR_ENCLAVE_METADATA: &str = "peer_mr_enclave";
+/// Metadata set by the server to the hex encoded MRSIGNER of the attested peer
+/// of the connection. Values sent by clients are dropped.
+pub(crate) const PEER_MR_SIGNER_METADATA: &str = "peer_mr_signer";
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Request<T> {
     #[serde(skip_serializing_if = "HashMap::is_empty")]
@@ -55,6 +62,22 @@ impl<T> Request<T> {
     pub fn metadata_mut(&mut self) -> &mut HashMap<String, String> {
         &mut self.metadata
     }
+
+    /// Hex encoded MRENCLAVE of the enclave sending the request, if the peer
+    /// presented an attested certificate.
+    pub fn peer_mr_enclave(&self) -> Option<&str> {
+        self.metadata
+            .get(PEER_MR_ENCLAVE_METADATA)
+            .map(|mr_enclave| mr_enclave.as_str())
+    }
+
+    /// Hex encoded MRSIGNER of the enclave sending the request, if the peer
+    /// presented an attested certificate.
+    pub fn peer_mr_signer(&self) -> Option<&str> {
+        self.metadata
+            .get(PEER_MR_SIGNER_METADATA)
+            .map(|mr_signer| mr_signer.as_str())
+    }
 }
 
 pub trait IntoRequest<T> {
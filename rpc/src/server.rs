@@ -31,6 +31,7 @@ where
     tls_config: SgxTrustedTlsServerConfig,
     tcp_nodelay: bool,
     n_workers: usize,
+    n_request_workers: usize,
     maker: std::marker::PhantomData<(U, V)>,
 }
 
@@ -48,6 +49,7 @@ where
             tls_config: server_config,
             tcp_nodelay: true,
             n_workers: 8,
+            n_request_workers: 4,
             maker: std::marker::PhantomData::<(U, V)>,
         }
     }
@@ -66,11 +68,24 @@ where
         }
     }
 
+    /// Number of threads handling the requests multiplexed on connections,
+    /// shared by all connections. Other requests are handled by the thread
+    /// serving their connection.
+    pub fn n_request_workers(self, n: usize) -> Self {
+        Self {
+            n_request_workers: n,
+            ..self
+        }
+    }
+
     pub fn start<X>(&mut self, service: X) -> Result<()>
     where
         X: 'static + TeaclaveService<V, U> + Clone + core::marker::Send,
+        U: 'static + core::marker::Send,
+        V: 'static + core::marker::Send,
     {
         let pool = threadpool::ThreadPool::new(self.n_workers);
+        let request_pool = threadpool::ThreadPool::new(self.n_request_workers);
         let listener = std::net::TcpListener::bind(self.addr)?;
         let mut tls_config_ref = self.tls_config.server_config();
         for stream in listener.incoming() {
@@ -92,7 +107,8 @@ where
                     let tls_stream = rustls::StreamOwned::new(session, stream);
                     let mut transport = SgxTrustedTlsTransport::new(tls_stream);
                     let service = service.clone();
-                    pool.execute(move || match transport.serve(service) {
+                    let request_pool = request_pool.clone();
+                    pool.execute(move || match transport.serve(service, &request_pool) {
                         Ok(_) => (),
                         Err(e) => {
                             debug!("serve error: {:?}", e);
@@ -0,0 +1,101 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Tracing of the requests of RPC clients, e.g., for debugging the client
+//! SDKs. A hook set on a channel is called with the serialized request and
+//! response of every call, after secrets in them are redacted.
+
+use crate::transport::ClientTransport;
+use crate::Request;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use teaclave_types::{TeaclaveServiceResponseError, TeaclaveServiceResponseResult};
+
+// Fields holding the passwords and tokens of users, the keys of files, the
+// secrets of file credentials, and the upload tokens of pushed inputs.
+const SECRET_FIELDS: &[&str] = &["password", "token", "key", "iv", "secret", "tokens"];
+const REDACTED: &str = "<redacted>";
+
+#[derive(Debug, Clone)]
+pub struct TraceEvent {
+    /// Name of the request, e.g., `create_task`
+    pub request: String,
+    /// The request in JSON, including its metadata
+    pub serialized_request: String,
+    /// The response in JSON, or the error if the call failed
+    pub serialized_response: String,
+    /// Time between sending the request and receiving the response
+    pub elapsed: Duration,
+}
+
+pub type TraceHook = Arc<dyn Fn(&TraceEvent) + Send + Sync>;
+
+/// Replaces the values of secret fields at any depth.
+pub fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (name, field) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&name.as_str()) && !field.is_null() {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => (),
+    }
+}
+
+pub(crate) fn send<T, U, V>(
+    transport: &mut T,
+    request: Request<U>,
+    hook: &TraceHook,
+) -> TeaclaveServiceResponseResult<V>
+where
+    T: ClientTransport,
+    U: Serialize + std::fmt::Debug,
+    V: for<'de> Deserialize<'de> + std::fmt::Debug,
+{
+    let mut serialized_request = serde_json::to_value(&request)
+        .map_err(|_| TeaclaveServiceResponseError::InternalError("serde".to_string()))?;
+    let name = serialized_request["request"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+
+    let start = Instant::now();
+    let result = transport.send::<_, Value>(request);
+    let elapsed = start.elapsed();
+
+    let mut serialized_response = match &result {
+        Ok(response) => response.clone(),
+        Err(e) => serde_json::to_value(e).unwrap_or_default(),
+    };
+    redact(&mut serialized_request);
+    redact(&mut serialized_response);
+    hook(&TraceEvent {
+        request: name,
+        serialized_request: serialized_request.to_string(),
+        serialized_response: serialized_response.to_string(),
+        elapsed,
+    });
+
+    serde_json::from_value(result?)
+        .map_err(|_| TeaclaveServiceResponseError::InternalError("serde".to_string()))
+}
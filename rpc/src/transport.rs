@@ -15,44 +15,83 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use crate::protocol;
+use crate::crash;
+use crate::duplex::DuplexTlsStream;
+use crate::protocol::{self, JsonProtocol, JsonProtocolResult, ProtocolError};
+use crate::request::{PEER_MR_ENCLAVE_METADATA, PEER_MR_SIGNER_METADATA};
 use crate::Request;
 use crate::TeaclaveService;
 use anyhow::Result;
 use log::debug;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::TcpStream;
 use std::prelude::v1::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(feature = "mesalock_sgx"))]
+use std::sync::Mutex;
+#[cfg(feature = "mesalock_sgx")]
+use std::sync::SgxMutex as Mutex;
+use std::sync::{mpsc, Arc};
+use teaclave_attestation::report::AttestationReport;
+use teaclave_types::{
+    TeaclaveErrorCode, TeaclaveServiceResponseError, TeaclaveServiceResponseResult,
+};
+
+// Requests of a connection handled by the request workers at the same time.
+// The connection is not read any further until one of them is answered.
+const MAX_IN_FLIGHT_REQUESTS: usize = 64;
 
 pub(crate) trait ClientTransport {
-    fn send<U, V>(
-        &mut self,
-        request: Request<U>,
-    ) -> teaclave_types::TeaclaveServiceResponseResult<V>
+    fn send<U, V>(&mut self, request: Request<U>) -> TeaclaveServiceResponseResult<V>
     where
         U: Serialize + std::fmt::Debug,
         V: for<'de> Deserialize<'de> + std::fmt::Debug;
+
+    /// Sends a request of a streaming method, whose responses are then read
+    /// with `recv_stream_item` until it returns `None`.
+    fn send_stream<U>(&mut self, request: Request<U>) -> TeaclaveServiceResponseResult<()>
+    where
+        U: Serialize + std::fmt::Debug;
+
+    fn recv_stream_item<V>(&mut self) -> TeaclaveServiceResponseResult<Option<V>>
+    where
+        V: for<'de> Deserialize<'de> + std::fmt::Debug;
 }
 
 pub(crate) trait ServerTransport {
-    fn serve<U, V, X>(&mut self, service: X) -> Result<()>
+    /// Serves the requests of the connection. Requests tagged with ids are
+    /// handled by `request_pool`, and are answered as soon as they are done.
+    fn serve<U, V, X>(&mut self, service: X, request_pool: &threadpool::ThreadPool) -> Result<()>
     where
-        U: Serialize + std::fmt::Debug,
-        V: for<'de> Deserialize<'de> + std::fmt::Debug,
-        X: TeaclaveService<V, U>;
+        U: Serialize + std::fmt::Debug + Send + 'static,
+        V: for<'de> Deserialize<'de> + std::fmt::Debug + Send + 'static,
+        X: TeaclaveService<V, U> + Clone + Send + 'static;
 }
+
 pub(crate) struct SgxTrustedTlsTransport<S>
 where
     S: rustls::Session,
 {
-    stream: rustls::StreamOwned<S, std::net::TcpStream>,
+    stream: DuplexTlsStream<S>,
 }
 
 impl<S> SgxTrustedTlsTransport<S>
 where
     S: rustls::Session,
 {
-    pub fn new(stream: rustls::StreamOwned<S, std::net::TcpStream>) -> SgxTrustedTlsTransport<S> {
-        SgxTrustedTlsTransport::<S> { stream }
+    pub fn new(stream: rustls::StreamOwned<S, TcpStream>) -> SgxTrustedTlsTransport<S> {
+        SgxTrustedTlsTransport::<S> {
+            stream: DuplexTlsStream::new(stream),
+        }
+    }
+
+    // The certificate was verified by the attestation report verifier during
+    // the handshake.
+    fn peer_measurement(&self) -> Option<(String, String)> {
+        let cert = self.stream.peer_certificates()?.into_iter().next()?;
+        AttestationReport::measurement_of_verified_cert(&cert.0).ok()
     }
 }
 
@@ -60,61 +99,323 @@ impl<S> ClientTransport for SgxTrustedTlsTransport<S>
 where
     S: rustls::Session,
 {
-    fn send<U, V>(
-        &mut self,
-        request: Request<U>,
-    ) -> teaclave_types::TeaclaveServiceResponseResult<V>
+    fn send<U, V>(&mut self, request: Request<U>) -> TeaclaveServiceResponseResult<V>
     where
         U: Serialize + std::fmt::Debug,
         V: for<'de> Deserialize<'de> + std::fmt::Debug,
     {
-        let mut protocol = protocol::JsonProtocol::new(&mut self.stream);
+        let mut protocol = JsonProtocol::new(&mut self.stream);
         protocol.write_message(request)?;
-        protocol.read_message::<protocol::JsonProtocolResult<
-                V,
-                teaclave_types::TeaclaveServiceResponseError,
-            >>()?
+        protocol
+            .read_message::<JsonProtocolResult<V, TeaclaveServiceResponseError>>()?
             .into()
     }
+
+    fn send_stream<U>(&mut self, request: Request<U>) -> TeaclaveServiceResponseResult<()>
+    where
+        U: Serialize + std::fmt::Debug,
+    {
+        JsonProtocol::new(&mut self.stream).write_message(request)?;
+        Ok(())
+    }
+
+    fn recv_stream_item<V>(&mut self) -> TeaclaveServiceResponseResult<Option<V>>
+    where
+        V: for<'de> Deserialize<'de> + std::fmt::Debug,
+    {
+        use crate::protocol::JsonProtocolStreamFrame;
+        let frame = JsonProtocol::new(&mut self.stream)
+            .read_message::<JsonProtocolStreamFrame<V, TeaclaveServiceResponseError>>()?;
+        match frame {
+            JsonProtocolStreamFrame::Item { content } => Ok(Some(content)),
+            JsonProtocolStreamFrame::End => Ok(None),
+            JsonProtocolStreamFrame::Err { error } => Err(error),
+        }
+    }
 }
 
-impl<S> ServerTransport for SgxTrustedTlsTransport<S>
+// Frames are written by the thread reading the connection and by the request
+// workers, each as a whole.
+fn write_frame<S, T>(
+    writer: &Mutex<DuplexTlsStream<S>>,
+    frame: T,
+    request_id: Option<u64>,
+) -> std::result::Result<(), ProtocolError>
 where
     S: rustls::Session,
+    T: Serialize + std::fmt::Debug,
+{
+    let mut stream = writer
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Connection poisoned"))?;
+    let mut protocol = JsonProtocol::new(&mut *stream);
+    match request_id {
+        Some(request_id) => protocol.write_message(protocol::tag_frame(frame, request_id)?),
+        None => protocol.write_message(frame),
+    }
+}
+
+fn invalid_request<U>() -> JsonProtocolResult<U, TeaclaveServiceResponseError> {
+    Err(TeaclaveServiceResponseError::request_error(
+        TeaclaveErrorCode::Validation,
+        "invalid request",
+    ))
+    .into()
+}
+
+impl<S> ServerTransport for SgxTrustedTlsTransport<S>
+where
+    S: rustls::Session + Send + 'static,
 {
-    fn serve<U, V, X>(&mut self, service: X) -> Result<()>
+    fn serve<U, V, X>(&mut self, service: X, request_pool: &threadpool::ThreadPool) -> Result<()>
     where
-        U: Serialize + std::fmt::Debug,
-        V: for<'de> Deserialize<'de> + std::fmt::Debug,
-        X: TeaclaveService<V, U>,
+        U: Serialize + std::fmt::Debug + Send + 'static,
+        V: for<'de> Deserialize<'de> + std::fmt::Debug + Send + 'static,
+        X: TeaclaveService<V, U> + Clone + Send + 'static,
     {
-        use crate::protocol::{JsonProtocol, JsonProtocolResult};
-        use teaclave_types::TeaclaveServiceResponseError;
-        let mut protocol = JsonProtocol::new(&mut self.stream);
+        use crate::protocol::JsonProtocolStreamFrame;
+        let writer = Arc::new(Mutex::new(self.stream.try_clone()?));
+        let (done_sender, done_receiver) = mpsc::channel::<()>();
+        let mut in_flight = 0;
+        // The certificate of the peer is only available once the handshake is
+        // done with the first request read.
+        let mut peer_measurement: Option<(String, String)> = None;
 
         loop {
-            let request: Request<V> = match protocol.read_message::<Request<V>>() {
-                Ok(r) => r,
-                Err(e) => match e {
-                    protocol::ProtocolError::IoError(_) => {
-                        debug!("Connection disconnected.");
-                        return Ok(());
-                    }
-                    _ => {
-                        debug!("{:?}", e);
-                        let response: JsonProtocolResult<U, TeaclaveServiceResponseError> =
-                            Err(TeaclaveServiceResponseError::RequestError(
-                                "invalid request".to_string(),
-                            ))
+            let mut frame = match JsonProtocol::new(&mut self.stream).read_message::<Value>() {
+                Ok(frame) => frame,
+                Err(ProtocolError::IoError(_)) => {
+                    debug!("Connection disconnected.");
+                    return Ok(());
+                }
+                Err(e) => {
+                    debug!("{:?}", e);
+                    write_frame(&writer, invalid_request::<U>(), None)?;
+                    continue;
+                }
+            };
+            let request_id = protocol::untag_frame(&mut frame);
+            let mut request: Request<V> = match serde_json::from_value(frame) {
+                Ok(request) => request,
+                Err(e) => {
+                    debug!("{:?}", e);
+                    write_frame(&writer, invalid_request::<U>(), request_id)?;
+                    continue;
+                }
+            };
+            if peer_measurement.is_none() {
+                peer_measurement = self.peer_measurement();
+            }
+            request.metadata.remove(PEER_MR_ENCLAVE_METADATA);
+            request.metadata.remove(PEER_MR_SIGNER_METADATA);
+            if let Some((mr_enclave, mr_signer)) = &peer_measurement {
+                request
+                    .metadata
+                    .insert(PEER_MR_ENCLAVE_METADATA.to_string(), mr_enclave.clone());
+                request
+                    .metadata
+                    .insert(PEER_MR_SIGNER_METADATA.to_string(), mr_signer.clone());
+            }
+            let trace_id = crash::trace_id_of(request.metadata());
+
+            // Streams are handled in place, even if they are tagged with ids.
+            if service.is_stream_request(&request) {
+                let writer = &writer;
+                let result = crash::handle_with_trace_id(trace_id, || {
+                    service.handle_stream_request(request, &mut |response| {
+                        let frame: JsonProtocolStreamFrame<U, TeaclaveServiceResponseError> =
+                            JsonProtocolStreamFrame::Item { content: response };
+                        write_frame(writer, frame, request_id)?;
+                        Ok(())
+                    })
+                });
+                let frame: JsonProtocolStreamFrame<U, TeaclaveServiceResponseError> = match result {
+                    Ok(()) => JsonProtocolStreamFrame::End,
+                    Err(error) => JsonProtocolStreamFrame::Err { error },
+                };
+                write_frame(writer, frame, request_id)?;
+                continue;
+            }
+
+            let request_id = match request_id {
+                Some(request_id) => request_id,
+                None => {
+                    let response: JsonProtocolResult<U, TeaclaveServiceResponseError> =
+                        crash::handle_with_trace_id(trace_id, || service.handle_request(request))
                             .into();
-                        protocol.write_message(response)?;
-                        continue;
-                    }
-                },
+                    write_frame(&writer, response, None)?;
+                    continue;
+                }
             };
-            let response: JsonProtocolResult<U, TeaclaveServiceResponseError> =
-                service.handle_request(request).into();
-            protocol.write_message(response)?;
+            while done_receiver.try_recv().is_ok() {
+                in_flight -= 1;
+            }
+            if in_flight >= MAX_IN_FLIGHT_REQUESTS {
+                let _ = done_receiver.recv();
+                in_flight -= 1;
+            }
+            in_flight += 1;
+            let service = service.clone();
+            let writer = writer.clone();
+            let done_sender = done_sender.clone();
+            request_pool.execute(move || {
+                let response: JsonProtocolResult<U, TeaclaveServiceResponseError> =
+                    crash::handle_with_trace_id(trace_id, || service.handle_request(request))
+                        .into();
+                if let Err(e) = write_frame(&writer, response, Some(request_id)) {
+                    debug!("{:?}", e);
+                }
+                let _ = done_sender.send(());
+            });
         }
     }
 }
+
+type PendingResponse = mpsc::Sender<TeaclaveServiceResponseResult<Value>>;
+
+#[derive(Default)]
+struct PendingResponses {
+    senders: HashMap<u64, PendingResponse>,
+    // Set once the connection is closed, failing the requests sent later.
+    closed: bool,
+}
+
+struct MultiplexedConnection {
+    writer: Mutex<DuplexTlsStream<rustls::ClientSession>>,
+    pending: Arc<Mutex<PendingResponses>>,
+    next_request_id: AtomicU64,
+}
+
+impl Drop for MultiplexedConnection {
+    // Wakes up the thread reading the responses, which then exits.
+    fn drop(&mut self) {
+        if let Ok(writer) = self.writer.lock() {
+            writer.shutdown();
+        }
+    }
+}
+
+/// Transport of a client sharing its connection with its clones. Requests are
+/// tagged with ids, and a thread reading the connection hands each response
+/// to the request with its id, so that requests sent by different threads are
+/// in flight at the same time. Streaming methods are not multiplexed.
+#[derive(Clone)]
+pub(crate) struct MultiplexedClientTransport {
+    connection: Arc<MultiplexedConnection>,
+}
+
+impl MultiplexedClientTransport {
+    pub fn new(
+        stream: rustls::StreamOwned<rustls::ClientSession, TcpStream>,
+    ) -> Result<MultiplexedClientTransport> {
+        let writer = DuplexTlsStream::new(stream);
+        let mut reader = writer.try_clone()?;
+        let pending = Arc::new(Mutex::new(PendingResponses::default()));
+        let reader_pending = pending.clone();
+        std::thread::spawn(move || read_responses(&mut reader, &reader_pending));
+
+        Ok(Self {
+            connection: Arc::new(MultiplexedConnection {
+                writer: Mutex::new(writer),
+                pending,
+                next_request_id: AtomicU64::new(0),
+            }),
+        })
+    }
+}
+
+fn connection_closed() -> TeaclaveServiceResponseError {
+    TeaclaveServiceResponseError::ConnectionError("connection closed".to_string())
+}
+
+fn read_responses(
+    reader: &mut DuplexTlsStream<rustls::ClientSession>,
+    pending: &Mutex<PendingResponses>,
+) {
+    let error = loop {
+        let mut frame = match JsonProtocol::new(&mut *reader).read_message::<Value>() {
+            Ok(frame) => frame,
+            Err(e) => break TeaclaveServiceResponseError::from(e),
+        };
+        let request_id = match protocol::untag_frame(&mut frame) {
+            Some(request_id) => request_id,
+            None => {
+                debug!("Response without a request id");
+                continue;
+            }
+        };
+        let sender = match pending.lock() {
+            Ok(mut pending) => pending.senders.remove(&request_id),
+            Err(_) => break connection_closed(),
+        };
+        if let Some(sender) = sender {
+            let _ = sender.send(Ok(frame));
+        }
+    };
+
+    if let Ok(mut pending) = pending.lock() {
+        pending.closed = true;
+        for (_, sender) in pending.senders.drain() {
+            let _ = sender.send(Err(error.clone()));
+        }
+    }
+}
+
+impl ClientTransport for MultiplexedClientTransport {
+    fn send<U, V>(&mut self, request: Request<U>) -> TeaclaveServiceResponseResult<V>
+    where
+        U: Serialize + std::fmt::Debug,
+        V: for<'de> Deserialize<'de> + std::fmt::Debug,
+    {
+        let connection = &self.connection;
+        let request_id = connection.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = mpsc::channel();
+        {
+            let mut pending = connection.pending.lock().map_err(|_| connection_closed())?;
+            if pending.closed {
+                return Err(connection_closed());
+            }
+            pending.senders.insert(request_id, sender);
+        }
+
+        let written = (|| {
+            let frame = protocol::tag_frame(request, request_id)?;
+            let mut writer = connection
+                .writer
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Connection poisoned"))?;
+            JsonProtocol::new(&mut *writer).write_message(frame)
+        })();
+        if let Err(e) = written {
+            if let Ok(mut pending) = connection.pending.lock() {
+                pending.senders.remove(&request_id);
+            }
+            return Err(e.into());
+        }
+
+        let frame = receiver.recv().map_err(|_| connection_closed())??;
+        serde_json::from_value::<JsonProtocolResult<V, TeaclaveServiceResponseError>>(frame)
+            .map_err(ProtocolError::from)?
+            .into()
+    }
+
+    fn send_stream<U>(&mut self, _request: Request<U>) -> TeaclaveServiceResponseResult<()>
+    where
+        U: Serialize + std::fmt::Debug,
+    {
+        Err(TeaclaveServiceResponseError::request_error(
+            TeaclaveErrorCode::Validation,
+            "streaming requests are not multiplexed",
+        ))
+    }
+
+    fn recv_stream_item<V>(&mut self) -> TeaclaveServiceResponseResult<Option<V>>
+    where
+        V: for<'de> Deserialize<'de> + std::fmt::Debug,
+    {
+        Err(TeaclaveServiceResponseError::request_error(
+            TeaclaveErrorCode::Validation,
+            "streaming requests are not multiplexed",
+        ))
+    }
+}
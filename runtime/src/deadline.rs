@@ -0,0 +1,200 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+#[cfg(feature = "mesalock_sgx")]
+use std::prelude::v1::*;
+
+use std::io;
+
+use teaclave_types::TaskDeadline;
+use teaclave_types::TeaclaveRuntime;
+
+/// Runtime stopping a function at its deadline. Since a function cannot be
+/// preempted in the enclave, it is stopped at its next call of the runtime or
+/// write of an output after the timeout.
+pub struct DeadlineRuntime {
+    inner: Box<dyn TeaclaveRuntime + Send + Sync>,
+    deadline: TaskDeadline,
+}
+
+impl DeadlineRuntime {
+    pub fn new(
+        inner: Box<dyn TeaclaveRuntime + Send + Sync>,
+        deadline: TaskDeadline,
+    ) -> DeadlineRuntime {
+        DeadlineRuntime { inner, deadline }
+    }
+
+    fn check_timeout(&self, identifier: &str) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            !self.deadline.is_timed_out(),
+            "Task timed out before accessing: {}",
+            identifier
+        );
+        Ok(())
+    }
+}
+
+impl TeaclaveRuntime for DeadlineRuntime {
+    fn open_input(&self, identifier: &str) -> anyhow::Result<Box<dyn io::Read>> {
+        self.check_timeout(identifier)?;
+        self.inner.open_input(identifier)
+    }
+
+    fn create_output(&self, identifier: &str) -> anyhow::Result<Box<dyn io::Write>> {
+        self.check_timeout(identifier)?;
+        let writable = self.inner.create_output(identifier)?;
+        Ok(Box::new(DeadlineWriter::new(
+            writable,
+            identifier,
+            self.deadline.clone(),
+        )))
+    }
+
+    fn publish_artifact(&self, name: &str) -> anyhow::Result<Box<dyn io::Write>> {
+        self.check_timeout(name)?;
+        self.inner.publish_artifact(name)
+    }
+
+    fn consume_artifact(&self, name: &str) -> anyhow::Result<Box<dyn io::Read>> {
+        self.check_timeout(name)?;
+        self.inner.consume_artifact(name)
+    }
+}
+
+// Writes to an output are allowed until the grace period is over. An output
+// is closed when its writer is dropped, and it is only recorded as closed if
+// all the writes to it succeeded.
+struct DeadlineWriter {
+    inner: Option<Box<dyn io::Write>>,
+    identifier: String,
+    deadline: TaskDeadline,
+    failed: bool,
+}
+
+impl DeadlineWriter {
+    fn new(inner: Box<dyn io::Write>, identifier: &str, deadline: TaskDeadline) -> Self {
+        Self {
+            inner: Some(inner),
+            identifier: identifier.to_string(),
+            deadline,
+            failed: false,
+        }
+    }
+
+    fn inner(&mut self) -> io::Result<&mut Box<dyn io::Write>> {
+        if self.deadline.is_grace_period_over() {
+            self.failed = true;
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "Task timed out before the output is written",
+            ));
+        }
+        Ok(self.inner.as_mut().unwrap())
+    }
+}
+
+impl io::Write for DeadlineWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.inner()?.write(buf);
+        self.failed |= result.is_err();
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.inner()?.flush();
+        self.failed |= result.is_err();
+        result
+    }
+}
+
+impl Drop for DeadlineWriter {
+    fn drop(&mut self) {
+        // The output is committed when the inner writer is dropped.
+        if let Some(mut inner) = self.inner.take() {
+            self.failed |= inner.flush().is_err();
+        }
+        if !self.failed {
+            self.deadline.close_output(&self.identifier);
+        }
+    }
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::time::Duration;
+    use teaclave_test_utils::*;
+
+    pub fn run_tests() -> bool {
+        run_tests!(
+            test_deadline_runtime_before_timeout,
+            test_deadline_runtime_grace_period,
+        )
+    }
+
+    struct MemoryRuntime;
+
+    impl TeaclaveRuntime for MemoryRuntime {
+        fn open_input(&self, _identifier: &str) -> anyhow::Result<Box<dyn io::Read>> {
+            Ok(Box::new(io::Cursor::new(b"input".to_vec())))
+        }
+
+        fn create_output(&self, _identifier: &str) -> anyhow::Result<Box<dyn io::Write>> {
+            Ok(Box::new(io::sink()))
+        }
+    }
+
+    fn test_deadline_runtime_before_timeout() {
+        let deadline = TaskDeadline::new(Duration::from_secs(60), Duration::from_secs(10));
+        let runtime = DeadlineRuntime::new(Box::new(MemoryRuntime), deadline.clone());
+        assert!(runtime.open_input("input").is_ok());
+        let mut output = runtime.create_output("output").unwrap();
+        output.write_all(b"output").unwrap();
+        assert!(deadline.closed_outputs().is_empty());
+        drop(output);
+        assert!(deadline.closed_outputs().contains("output"));
+    }
+
+    fn test_deadline_runtime_grace_period() {
+        let deadline = TaskDeadline::new(Duration::from_secs(0), Duration::from_secs(60));
+        let runtime = DeadlineRuntime::new(Box::new(MemoryRuntime), deadline.clone());
+        assert!(runtime.open_input("input").is_err());
+        assert!(runtime.create_output("output").is_err());
+
+        // Outputs created before the timeout can be written in the grace
+        // period.
+        let deadline = TaskDeadline::new(Duration::from_secs(1), Duration::from_secs(60));
+        let runtime = DeadlineRuntime::new(Box::new(MemoryRuntime), deadline.clone());
+        let mut output = runtime.create_output("output").unwrap();
+        std::thread::sleep(Duration::from_secs(1));
+        assert!(runtime.open_input("input").is_err());
+        output.write_all(b"output").unwrap();
+        drop(output);
+        assert!(deadline.closed_outputs().contains("output"));
+
+        // Outputs failed to be written are not closed.
+        let deadline = TaskDeadline::new(Duration::from_secs(1), Duration::from_secs(0));
+        let runtime = DeadlineRuntime::new(Box::new(MemoryRuntime), deadline.clone());
+        let mut output = runtime.create_output("output").unwrap();
+        std::thread::sleep(Duration::from_secs(1));
+        assert!(output.write_all(b"output").is_err());
+        drop(output);
+        assert!(deadline.closed_outputs().is_empty());
+    }
+}
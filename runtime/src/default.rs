@@ -22,10 +22,12 @@ use std::io;
 
 use teaclave_types::StagedFiles;
 use teaclave_types::TeaclaveRuntime;
+use teaclave_types::WorkflowArtifacts;
 
 pub struct DefaultRuntime {
     input_files: StagedFiles,
     output_files: StagedFiles,
+    artifacts: Option<WorkflowArtifacts>,
 }
 
 impl DefaultRuntime {
@@ -33,8 +35,13 @@ impl DefaultRuntime {
         DefaultRuntime {
             input_files,
             output_files,
+            artifacts: None,
         }
     }
+
+    pub fn artifacts(self, artifacts: Option<WorkflowArtifacts>) -> DefaultRuntime {
+        DefaultRuntime { artifacts, ..self }
+    }
 }
 
 impl TeaclaveRuntime for DefaultRuntime {
@@ -59,4 +66,24 @@ impl TeaclaveRuntime for DefaultRuntime {
         let writable = file_info.create_writable_io()?;
         Ok(writable)
     }
+
+    fn publish_artifact(&self, name: &str) -> anyhow::Result<Box<dyn io::Write>> {
+        let artifacts = self
+            .artifacts
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Task is not in a workflow"))?;
+
+        log::debug!("publish_artifact: {}", name);
+        artifacts.create_artifact(name)
+    }
+
+    fn consume_artifact(&self, name: &str) -> anyhow::Result<Box<dyn io::Read>> {
+        let artifacts = self
+            .artifacts
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Task is not in a workflow"))?;
+
+        log::debug!("consume_artifact: {}", name);
+        artifacts.open_artifact(name)
+    }
 }
@@ -19,8 +19,14 @@
 #[cfg(feature = "mesalock_sgx")]
 extern crate sgx_tstd as std;
 
+mod deadline;
+pub use deadline::DeadlineRuntime;
 mod default;
 pub use default::DefaultRuntime;
+mod limits;
+pub use limits::LimitedRuntime;
+mod sandbox;
+pub use sandbox::SandboxRuntime;
 
 #[cfg(any(feature = "enclave_unit_test", test_mode))]
 mod raw_io;
@@ -29,7 +35,14 @@ pub use raw_io::RawIoRuntime;
 
 #[cfg(feature = "enclave_unit_test")]
 pub mod tests {
+    use super::*;
+    use teaclave_test_utils::check_all_passed;
+
     pub fn run_tests() -> bool {
-        true
+        check_all_passed!(
+            deadline::tests::run_tests(),
+            limits::tests::run_tests(),
+            sandbox::tests::run_tests(),
+        )
     }
 }
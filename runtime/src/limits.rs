@@ -0,0 +1,131 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+#[cfg(feature = "mesalock_sgx")]
+use std::prelude::v1::*;
+
+use std::io;
+
+use teaclave_types::ResourceMeter;
+use teaclave_types::TeaclaveRuntime;
+
+/// Runtime metering a function against the resource limits of its task. Like
+/// the deadline, the running time is only checked at calls of the runtime,
+/// and the bytes written to outputs are counted as they are written.
+pub struct LimitedRuntime {
+    inner: Box<dyn TeaclaveRuntime + Send + Sync>,
+    meter: ResourceMeter,
+}
+
+impl LimitedRuntime {
+    pub fn new(inner: Box<dyn TeaclaveRuntime + Send + Sync>, meter: ResourceMeter) -> Self {
+        LimitedRuntime { inner, meter }
+    }
+}
+
+impl TeaclaveRuntime for LimitedRuntime {
+    fn open_input(&self, identifier: &str) -> anyhow::Result<Box<dyn io::Read>> {
+        self.meter.check_cpu()?;
+        self.inner.open_input(identifier)
+    }
+
+    fn create_output(&self, identifier: &str) -> anyhow::Result<Box<dyn io::Write>> {
+        self.meter.check_cpu()?;
+        let writable = self.inner.create_output(identifier)?;
+        Ok(Box::new(LimitedWriter {
+            inner: writable,
+            meter: self.meter.clone(),
+        }))
+    }
+
+    fn publish_artifact(&self, name: &str) -> anyhow::Result<Box<dyn io::Write>> {
+        self.meter.check_cpu()?;
+        self.inner.publish_artifact(name)
+    }
+
+    fn consume_artifact(&self, name: &str) -> anyhow::Result<Box<dyn io::Read>> {
+        self.meter.check_cpu()?;
+        self.inner.consume_artifact(name)
+    }
+}
+
+struct LimitedWriter {
+    inner: Box<dyn io::Write>,
+    meter: ResourceMeter,
+}
+
+impl io::Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let to_io_error = |e| io::Error::new(io::ErrorKind::Other, e);
+        self.meter.check_cpu().map_err(to_io_error)?;
+        self.meter
+            .add_output_bytes(buf.len() as u64)
+            .map_err(to_io_error)?;
+        self.inner.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::time::Duration;
+    use teaclave_test_utils::*;
+    use teaclave_types::{ResourceExceeded, ResourceLimits};
+
+    pub fn run_tests() -> bool {
+        run_tests!(test_limited_runtime_output, test_limited_runtime_cpu,)
+    }
+
+    struct MemoryRuntime;
+
+    impl TeaclaveRuntime for MemoryRuntime {
+        fn open_input(&self, _identifier: &str) -> anyhow::Result<Box<dyn io::Read>> {
+            Ok(Box::new(io::Cursor::new(b"input".to_vec())))
+        }
+
+        fn create_output(&self, _identifier: &str) -> anyhow::Result<Box<dyn io::Write>> {
+            Ok(Box::new(io::sink()))
+        }
+    }
+
+    fn test_limited_runtime_output() {
+        let meter = ResourceMeter::new(ResourceLimits::new().max_output_bytes(10));
+        let runtime = LimitedRuntime::new(Box::new(MemoryRuntime), meter.clone());
+        let mut output = runtime.create_output("output").unwrap();
+        output.write_all(b"output").unwrap();
+        assert!(meter.exceeded().is_none());
+
+        // The limit is on the total of all the outputs.
+        let mut output2 = runtime.create_output("output2").unwrap();
+        assert!(output2.write_all(b"output").is_err());
+        assert_eq!(meter.exceeded(), Some(ResourceExceeded::Output(10)));
+    }
+
+    fn test_limited_runtime_cpu() {
+        let meter = ResourceMeter::new(ResourceLimits::new().max_cpu_seconds(1));
+        let runtime = LimitedRuntime::new(Box::new(MemoryRuntime), meter.clone());
+        assert!(runtime.open_input("input").is_ok());
+        std::thread::sleep(Duration::from_millis(1100));
+        assert!(runtime.open_input("input").is_err());
+        assert_eq!(meter.exceeded(), Some(ResourceExceeded::Cpu(1)));
+    }
+}
@@ -0,0 +1,183 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+#[cfg(feature = "mesalock_sgx")]
+use std::prelude::v1::*;
+
+use std::io;
+
+use teaclave_types::FunctionCapabilities;
+use teaclave_types::TeaclaveRuntime;
+
+/// Runtime confining a function to the capabilities it declared. Every call
+/// of the executor going through the runtime is checked before it is passed
+/// to the runtime the function is run with.
+pub struct SandboxRuntime {
+    inner: Box<dyn TeaclaveRuntime + Send + Sync>,
+    capabilities: FunctionCapabilities,
+}
+
+impl SandboxRuntime {
+    pub fn new(
+        inner: Box<dyn TeaclaveRuntime + Send + Sync>,
+        capabilities: FunctionCapabilities,
+    ) -> SandboxRuntime {
+        SandboxRuntime {
+            inner,
+            capabilities,
+        }
+    }
+}
+
+impl TeaclaveRuntime for SandboxRuntime {
+    fn open_input(&self, identifier: &str) -> anyhow::Result<Box<dyn io::Read>> {
+        self.inner.open_input(identifier)
+    }
+
+    fn create_output(&self, identifier: &str) -> anyhow::Result<Box<dyn io::Write>> {
+        anyhow::ensure!(
+            self.capabilities.egress,
+            "Function is not allowed to write outputs: {}",
+            identifier
+        );
+        let writable = self.inner.create_output(identifier)?;
+        match self.capabilities.max_output_size {
+            0 => Ok(writable),
+            limit => Ok(Box::new(LimitedWriter::new(writable, limit))),
+        }
+    }
+
+    fn publish_artifact(&self, name: &str) -> anyhow::Result<Box<dyn io::Write>> {
+        anyhow::ensure!(
+            self.capabilities.scratch_disk,
+            "Function is not allowed to use the scratch disk: {}",
+            name
+        );
+        self.inner.publish_artifact(name)
+    }
+
+    fn consume_artifact(&self, name: &str) -> anyhow::Result<Box<dyn io::Read>> {
+        anyhow::ensure!(
+            self.capabilities.scratch_disk,
+            "Function is not allowed to use the scratch disk: {}",
+            name
+        );
+        self.inner.consume_artifact(name)
+    }
+}
+
+// Fails a write exceeding the limit as a whole, so that the output is never
+// truncated silently.
+struct LimitedWriter {
+    inner: Box<dyn io::Write>,
+    remaining: u64,
+}
+
+impl LimitedWriter {
+    fn new(inner: Box<dyn io::Write>, limit: u64) -> Self {
+        Self {
+            inner,
+            remaining: limit,
+        }
+    }
+}
+
+impl io::Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() as u64 > self.remaining {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Output exceeds the max output size of the function",
+            ));
+        }
+        let written = self.inner.write(buf)?;
+        self.remaining -= written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use teaclave_test_utils::*;
+
+    pub fn run_tests() -> bool {
+        run_tests!(
+            test_sandbox_runtime_denies_undeclared,
+            test_sandbox_runtime_max_output_size,
+        )
+    }
+
+    struct MemoryRuntime;
+
+    impl TeaclaveRuntime for MemoryRuntime {
+        fn open_input(&self, _identifier: &str) -> anyhow::Result<Box<dyn io::Read>> {
+            Ok(Box::new(io::Cursor::new(b"input".to_vec())))
+        }
+
+        fn create_output(&self, _identifier: &str) -> anyhow::Result<Box<dyn io::Write>> {
+            Ok(Box::new(io::sink()))
+        }
+
+        fn publish_artifact(&self, _name: &str) -> anyhow::Result<Box<dyn io::Write>> {
+            Ok(Box::new(io::sink()))
+        }
+
+        fn consume_artifact(&self, _name: &str) -> anyhow::Result<Box<dyn io::Read>> {
+            Ok(Box::new(io::empty()))
+        }
+    }
+
+    fn test_sandbox_runtime_denies_undeclared() {
+        let runtime = SandboxRuntime::new(Box::new(MemoryRuntime), FunctionCapabilities::new());
+        let mut input = String::new();
+        runtime
+            .open_input("input")
+            .unwrap()
+            .read_to_string(&mut input)
+            .unwrap();
+        assert_eq!(input, "input");
+        assert!(runtime.create_output("output").is_err());
+        assert!(runtime.publish_artifact("artifact").is_err());
+        assert!(runtime.consume_artifact("artifact").is_err());
+
+        let capabilities = FunctionCapabilities::new().scratch_disk(true).egress(true);
+        let runtime = SandboxRuntime::new(Box::new(MemoryRuntime), capabilities);
+        assert!(runtime.create_output("output").is_ok());
+        assert!(runtime.publish_artifact("artifact").is_ok());
+        assert!(runtime.consume_artifact("artifact").is_ok());
+    }
+
+    fn test_sandbox_runtime_max_output_size() {
+        let capabilities = FunctionCapabilities::new().egress(true).max_output_size(8);
+        let runtime = SandboxRuntime::new(Box::new(MemoryRuntime), capabilities);
+        let mut output = runtime.create_output("output").unwrap();
+        output.write_all(b"12345").unwrap();
+        output.write_all(b"678").unwrap();
+        assert!(output.write_all(b"9").is_err());
+
+        // Each output has its own limit.
+        let mut output = runtime.create_output("another").unwrap();
+        assert!(output.write_all(b"123456789").is_err());
+        output.write_all(b"12345678").unwrap();
+    }
+}
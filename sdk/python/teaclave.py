@@ -26,16 +26,34 @@ authentication service and frontend service) through RPC protocols.
 import struct
 import json
 import base64
+import hashlib
+import hmac
 import toml
 import os
 import time
 import ssl
+import select
 import socket
+import threading
+import urllib.error
+import urllib.parse
+import urllib.request
+import uuid
 
-from typing import Tuple, Dict, List, Any
+from typing import Tuple, Dict, List, Any, Callable
 
 from cryptography import x509
 from cryptography.hazmat.backends import default_backend
+from cryptography.hazmat.primitives import cmac, hashes
+from cryptography.hazmat.primitives.ciphers import algorithms
+from cryptography.exceptions import InvalidSignature, InvalidTag
+from cryptography.hazmat.primitives.asymmetric import ec, padding
+from cryptography.hazmat.primitives.asymmetric.ed25519 import Ed25519PrivateKey
+from cryptography.hazmat.primitives.ciphers.aead import AESGCM
+from cryptography.hazmat.primitives.kdf.hkdf import HKDF
+from cryptography.hazmat.primitives.serialization import (Encoding,
+                                                          PublicFormat,
+                                                          load_pem_public_key)
 
 from OpenSSL.crypto import load_certificate, FILETYPE_PEM, FILETYPE_ASN1
 from OpenSSL.crypto import X509Store, X509StoreContext
@@ -44,11 +62,84 @@ from OpenSSL import crypto
 __all__ = [
     'FrontendClient', 'FrontendService', 'AuthenticationClient',
     'AuthenticationService', 'FunctionInput', 'FunctionOutput', 'OwnerList',
-    'DataMap'
+    'DataMap', 'TeaclaveException', 'TraceEvent', 'verify_output'
 ]
 
 Metadata = Dict[str, str]
 
+# Layout of a teaclave-file-128 (SGX protected file) file: a meta data node,
+# whose plain part ends with the GMAC of its encrypted part, followed by
+# Merkle tree nodes each keeping the keys and GMACs of 96 data nodes and 32
+# child tree nodes. Every node is encrypted with AES-GCM under a zero IV.
+_NODE_SIZE = 4096
+_SGX_FILE_ID = 0x5347585F46494C45
+_META_DATA_KEY_ID_OFFSET = 10
+_META_DATA_GMAC_OFFSET = 77
+_META_DATA_UPDATE_FLAG_OFFSET = 93
+_META_DATA_ENCRYPTED_OFFSET = 94
+_META_DATA_ENCRYPTED_SIZE = 3392
+_META_DATA_SIZE_OFFSET = 260
+_META_DATA_MHT_KEY_OFFSET = 288
+_MD_USER_DATA_SIZE = 3072
+_ATTACHED_DATA_NODES_COUNT = 96
+_CHILD_MHT_NODES_COUNT = 32
+_FILE_AUTH_TAG_LENGTH = 16
+
+# Inputs are uploaded in content-defined chunks of 256 KiB to 4 MiB, which
+# end where the top 20 bits of a gear hash over the chunk are zero, i.e.,
+# about 1 MiB past the minimum on average.
+_CHUNKED_URL_SCHEME_PREFIX = "chunked+"
+_MIN_CHUNK_SIZE = 256 * 1024
+_MAX_CHUNK_SIZE = 4 * 1024 * 1024
+_CHUNK_MASK = ((1 << 20) - 1) << 44
+_GEAR = [
+    int.from_bytes(hashlib.sha256(bytes([b])).digest()[:8], "little")
+    for b in range(256)
+]
+# Context of the message signed by the execution enclave offering a task key.
+_TASK_KEY_OFFER_CONTEXT = b"teaclave-task-key-offer"
+# Serialized names of the file crypto schemas, wrapped for the enclave.
+_FILE_CRYPTO_NAMES = {
+    "aes-gcm-128": "AesGcm128",
+    "aes-gcm-256": "AesGcm256",
+    "teaclave-file-128": "TeaclaveFile128",
+}
+# Fields holding the passwords and tokens of users, the keys of files, and the
+# secrets of file credentials, redacted from traced messages.
+_SECRET_FIELDS = ("password", "token", "key", "iv", "secret", "tokens")
+
+
+class TeaclaveException(Exception):
+    """Error returned by Teaclave services.
+
+    Args:
+        code: Kind of the error, one of "auth", "validation", "quota",
+            "not_found", "conflict", "internal" and "attestation".
+        message: Description of the error.
+    """
+    def __init__(self, code: str, message: str):
+        super().__init__(f"{code}: {message}")
+        self.code = code
+        self.message = message
+
+
+class TraceEvent:
+    """A request and its response traced by a client, with secrets redacted.
+
+    Args:
+        request: Name of the request, e.g., "create_task".
+        serialized_request: The request in JSON, including its metadata.
+        serialized_response: The response in JSON, or the error.
+        elapsed: Seconds between sending the request and receiving the
+            response.
+    """
+    def __init__(self, request: str, serialized_request: str,
+                 serialized_response: str, elapsed: float):
+        self.request = request
+        self.serialized_request = serialized_request
+        self.serialized_response = serialized_response
+        self.elapsed = elapsed
+
 
 class FunctionInput:
     """Function input for registering.
@@ -74,6 +165,101 @@ class FunctionOutput:
         self.description = description
 
 
+class ResourceProfile:
+    """Resources a function is expected to need, checked by workers before
+    running its tasks.
+
+    Args:
+        heap_size: Enclave heap in bytes, 0 if unspecified.
+        input_size: Total size of the inputs in bytes, 0 if unspecified.
+        timeout: Running time in seconds, 0 if unspecified.
+    """
+    def __init__(self, heap_size: int = 0, input_size: int = 0,
+                 timeout: int = 0):
+        self.heap_size = heap_size
+        self.input_size = input_size
+        self.timeout = timeout
+
+
+class ResourceLimits:
+    """Limits on the resources of a task enforced by workers, which fail the
+    task if any is exceeded.
+
+    Args:
+        max_memory_bytes: Enclave heap declared by the function in bytes, 0
+            for unlimited.
+        max_cpu_seconds: Running time in seconds, 0 for unlimited.
+        max_output_bytes: Total size written to the outputs in bytes, 0 for
+            unlimited.
+    """
+    def __init__(self, max_memory_bytes: int = 0, max_cpu_seconds: int = 0,
+                 max_output_bytes: int = 0):
+        self.max_memory_bytes = max_memory_bytes
+        self.max_cpu_seconds = max_cpu_seconds
+        self.max_output_bytes = max_output_bytes
+
+
+class RetryPolicy:
+    """Retries of a task failed transiently, e.g., when its input files cannot
+    be fetched or the worker crashes.
+
+    Args:
+        max_retries: Times the task is retried at most, 0 for never.
+        backoff: Seconds to wait before the first retry, doubled with every
+            retry.
+    """
+    def __init__(self, max_retries: int = 0, backoff: int = 0):
+        self.max_retries = max_retries
+        self.backoff = backoff
+
+
+class FunctionCapabilities:
+    """Capabilities a function needs in the sandbox, shown to the participants
+    approving its tasks and enforced by workers. Functions registered without
+    capabilities are not confined.
+
+    Args:
+        scratch_disk: Whether the function writes to the scratch disk of its
+            workflow.
+        egress: Whether the function writes output files.
+        max_output_size: Max size of each output file in bytes, 0 if
+            unlimited.
+    """
+    def __init__(self,
+                 scratch_disk: bool = False,
+                 egress: bool = False,
+                 max_output_size: int = 0):
+        self.scratch_disk = scratch_disk
+        self.egress = egress
+        self.max_output_size = max_output_size
+
+
+class PostProcessingStep:
+    """Step applied to the return value of a function in the execution
+    enclave before it leaves, shown to the participants approving its tasks.
+    Functions declaring any step must return JSON.
+
+    Args:
+        step: "round" to round numbers to the decimals, "top_k" to truncate
+            the returned array, or each array of the returned object, to k
+            elements, or "validate_schema" to fail the task unless the return
+            value matches the JSON schema.
+        decimals: Decimals numbers are rounded to.
+        k: Elements arrays are truncated to.
+        schema: JSON schema using type, properties, required,
+            additionalProperties, items, maxItems and maxLength only.
+    """
+    def __init__(self,
+                 step: str,
+                 decimals: int = 0,
+                 k: int = 0,
+                 schema: Dict[str, Any] = None):
+        self.step = step
+        self.decimals = decimals
+        self.k = k
+        self.schema = json.dumps(schema) if schema is not None else ""
+
+
 class OwnerList:
     """Defines data ownership.
 
@@ -91,13 +277,26 @@ class DataMap:
 
     Args:
         data_name: Name of output data.
-        data_id: Id for the data name.
+        data_id: Id for the data name, or a reference to a model of the user
+            like "model:fraud@7" for input data.
     """
     def __init__(self, data_name, data_id):
         self.data_name = data_name
         self.data_id = data_id
 
 
+class TaskOutputReference:
+    """Refers to an output of an upstream task consumed as an input.
+
+    Args:
+        task_id: ID of the upstream task.
+        output: Name of the output in the function of the upstream task.
+    """
+    def __init__(self, task_id: str, output: str):
+        self.task_id = task_id
+        self.output = output
+
+
 class CryptoInfo:
     """Cryptographic information for the input/output data.
 
@@ -112,11 +311,55 @@ class CryptoInfo:
         self.iv = iv
 
 
+class CredentialInfo:
+    """Credential for uploading the output data to its destination.
+
+    Args:
+        schema: Type of the credential: bearer, aws_s3 or azure_sas.
+        secret: Bearer token, AWS secret access key or Azure SAS token.
+        access_key_id: AWS access key id, only for aws_s3.
+        region: AWS region, only for aws_s3.
+        endpoint: Endpoint of an S3-compatible service, only for aws_s3.
+    """
+    def __init__(self,
+                 schema: str,
+                 secret: str,
+                 access_key_id: str = "",
+                 region: str = "",
+                 endpoint: str = ""):
+        self.schema = schema
+        self.access_key_id = access_key_id
+        self.secret = secret
+        self.region = region
+        self.endpoint = endpoint
+
+
+class KeyBrokerInfo:
+    """Key broker releasing the key of an input file to the execution
+    enclaves it attests, so that the key is never given to Teaclave.
+
+    Args:
+        address: Address of the broker, e.g., broker.example.com:7700.
+        key_id: Id of the key in the broker.
+    """
+    def __init__(self, address: str, key_id: str):
+        self.address = address
+        self.key_id = key_id
+
+
 class UserRegisterReqeust:
-    def __init__(self, user_id: str, user_password: str):
+    def __init__(self, user_id: str, user_password: str, contact: str = ""):
         self.request = "user_register"
         self.id = user_id
         self.password = user_password
+        self.contact = contact
+
+
+class ConfirmRegistrationRequest:
+    def __init__(self, user_id: str, code: str):
+        self.request = "confirm_registration"
+        self.id = user_id
+        self.code = code
 
 
 class UserLoginRequest:
@@ -126,6 +369,17 @@ class UserLoginRequest:
         self.password = user_password
 
 
+class MintScopedTokenRequest:
+    def __init__(self, user_id: str, user_token: str, operations: List[str],
+                 resources: List[str], expires_in: int):
+        self.request = "mint_scoped_token"
+        self.id = user_id
+        self.token = user_token
+        self.operations = operations
+        self.resources = resources
+        self.expires_in = expires_in
+
+
 class AuthenticationService:
     """
     Establish trusted channel with the authentication service and provide
@@ -182,17 +436,37 @@ class AuthenticationClient:
     """
     def __init__(self, channel: ssl.SSLSocket):
         self.channel = channel
+        self.trace_hook = None
+
+    def set_trace_hook(self, hook: Callable[[TraceEvent], None] = None):
+        """Call the hook with every request and response of the client, e.g.,
+        for debugging. Tracing stops if no hook is given."""
+        self.trace_hook = hook
 
-    def user_register(self, user_id: str, user_password: str):
+    def user_register(self,
+                      user_id: str,
+                      user_password: str,
+                      contact: str = ""):
         """Register a new user.
 
         Args:
             user_id: User ID.
             user_password: Password.
+            contact: Where the verification code is sent, e.g., an email
+                address, if the service verifies the registrations.
         """
-        request = UserRegisterReqeust(user_id, user_password)
-        _write_message(self.channel, request)
-        _ = _read_message(self.channel)
+        request = UserRegisterReqeust(user_id, user_password, contact)
+        _ = _send_request(self.channel, request, self.trace_hook)
+
+    def confirm_registration(self, user_id: str, code: str):
+        """Confirm the registration of a user, who can log in afterwards.
+
+        Args:
+            user_id: User ID.
+            code: Verification code sent to the contact of the user.
+        """
+        request = ConfirmRegistrationRequest(user_id, code)
+        _ = _send_request(self.channel, request, self.trace_hook)
 
     def user_login(self, user_id: str, user_password: str) -> str:
         """Login and get a session token.
@@ -205,8 +479,34 @@ class AuthenticationClient:
             str: User login token.
         """
         request = UserLoginRequest(user_id, user_password)
-        _write_message(self.channel, request)
-        response = _read_message(self.channel)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["token"]
+
+    def mint_scoped_token(self,
+                          user_id: str,
+                          user_token: str,
+                          operations: List[str],
+                          resources: List[str] = [],
+                          expires_in: int = 3600) -> str:
+        """Mint a token restricted to some operations and resources from a
+        login token, e.g., for automation agents.
+
+        Args:
+            user_id: User ID.
+            user_token: Login token of the user.
+            operations: Names of the permitted operations, e.g.,
+                "approve_task".
+            resources: IDs of the permitted resources, e.g., of a task. Any
+                resource is permitted if none is given.
+            expires_in: Seconds the token is valid for, no longer than the
+                login token.
+
+        Returns:
+            str: Scoped token.
+        """
+        request = MintScopedTokenRequest(user_id, user_token, operations,
+                                         resources, expires_in)
+        response = _send_request(self.channel, request, self.trace_hook)
         return response["content"]["token"]
 
 
@@ -247,6 +547,19 @@ class FrontendService:
         self._channel = channel
         return self
 
+    def connect_multiplexed(self):
+        """Establish trusted connection shared by all clients of the service,
+        e.g., on different threads. Requests of the clients are in flight at
+        the same time, but task results cannot be streamed.
+
+        Returns:
+            FrontendService: The original object which can be chained
+                with other methods.
+        """
+        self.connect()
+        self._channel = _MultiplexedChannel(self._channel)
+        return self
+
     def get_client(self):
         """Get a client of frontend service to send RPC requests.
 
@@ -260,7 +573,11 @@ class RegisterFunctionRequest:
     def __init__(self, metadata: Metadata, name: str, description: str,
                  executor_type: str, public: bool, payload: List[int],
                  arguments: List[str], inputs: List[FunctionInput],
-                 outputs: List[FunctionOutput]):
+                 outputs: List[FunctionOutput],
+                 resource_profile: ResourceProfile, entry_points: List[str],
+                 capabilities: FunctionCapabilities,
+                 worker_measurements: List[str],
+                 post_processing: List[PostProcessingStep]):
         self.request = "register_function"
         self.metadata = metadata
         self.name = name
@@ -271,24 +588,50 @@ class RegisterFunctionRequest:
         self.arguments = arguments
         self.inputs = inputs
         self.outputs = outputs
+        self.resource_profile = resource_profile
+        self.entry_points = entry_points
+        self.capabilities = capabilities
+        self.worker_measurements = worker_measurements
+        self.post_processing = post_processing
 
 
 class RegisterInputFileRequest:
-    def __init__(self, metadata: Metadata, url: str, cmac: List[int],
-                 crypto_info: CryptoInfo):
+    def __init__(self,
+                 metadata: Metadata,
+                 url: str,
+                 cmac: List[int],
+                 crypto_info: CryptoInfo,
+                 key_broker: KeyBrokerInfo = None):
         self.request = "register_input_file"
         self.metadata = metadata
         self.url = url
         self.cmac = cmac
         self.crypto_info = crypto_info
+        self.key_broker = key_broker
+
+
+class RotateInputFileKeyRequest:
+    def __init__(self, metadata: Metadata, data_id: str, url: str,
+                 cmac: List[int], crypto_info: CryptoInfo):
+        self.request = "rotate_input_file_key"
+        self.metadata = metadata
+        self.data_id = data_id
+        self.url = url
+        self.cmac = cmac
+        self.crypto_info = crypto_info
 
 
 class RegisterOutputFileRequest:
-    def __init__(self, metadata: Metadata, url: str, crypto_info: CryptoInfo):
+    def __init__(self,
+                 metadata: Metadata,
+                 url: str,
+                 crypto_info: CryptoInfo,
+                 credential: CredentialInfo = None):
         self.request = "register_output_file"
         self.metadata = metadata
         self.url = url
         self.crypto_info = crypto_info
+        self.credential = credential
 
 
 class UpdateInputFileRequest:
@@ -307,11 +650,31 @@ class UpdateOutputFileRequest:
         self.url = url
 
 
+class UpdateOutputUrlRequest:
+    def __init__(self,
+                 metadata: Metadata,
+                 data_id: str,
+                 url: str,
+                 credential: CredentialInfo = None):
+        self.request = "update_output_url"
+        self.metadata = metadata
+        self.data_id = data_id
+        self.url = url
+        self.credential = credential
+
+
 class CreateTaskRequest:
     def __init__(self, metadata: Metadata, function_id: str,
                  function_arguments: Dict[str, Any], executor: str,
                  inputs_ownership: List[OwnerList],
-                 outputs_ownership: List[OwnerList]):
+                 outputs_ownership: List[OwnerList], labels: Dict[str, str],
+                 workflow_id: str, entry_point: str, reuse_result: bool,
+                 key_exchange: bool, expires_in: int, approval_window: int,
+                 input_dependencies: Dict[str, TaskOutputReference],
+                 priority: str, resource_limits: ResourceLimits,
+                 timeout: int, retry_policy: RetryPolicy,
+                 worker_measurements: List[str], worker_signers: List[str],
+                 federation_peer: str, signature: List[int] = []):
         self.request = "create_task"
         self.metadata = metadata
         self.function_id = function_id
@@ -319,23 +682,48 @@ class CreateTaskRequest:
         self.executor = executor
         self.inputs_ownership = inputs_ownership
         self.outputs_ownership = outputs_ownership
+        self.labels = labels
+        self.workflow_id = workflow_id
+        self.entry_point = entry_point
+        self.reuse_result = reuse_result
+        self.key_exchange = key_exchange
+        self.expires_in = expires_in
+        self.approval_window = approval_window
+        self.input_dependencies = input_dependencies
+        self.priority = priority
+        self.resource_limits = resource_limits
+        self.timeout = timeout
+        self.retry_policy = retry_policy
+        self.worker_measurements = worker_measurements
+        self.worker_signers = worker_signers
+        self.federation_peer = federation_peer
+        self.signature = signature
 
 
 class AssignDataRequest:
-    def __init__(self, metadata: Metadata, task_id: str, inputs: List[DataMap],
-                 outputs: List[DataMap]):
+    def __init__(self,
+                 metadata: Metadata,
+                 task_id: str,
+                 inputs: List[DataMap],
+                 outputs: List[DataMap],
+                 signature: List[int] = []):
         self.request = "assign_data"
         self.metadata = metadata
         self.task_id = task_id
         self.inputs = inputs
         self.outputs = outputs
+        self.signature = signature
 
 
 class ApproveTaskRequest:
-    def __init__(self, metadata: Metadata, task_id: str):
+    def __init__(self,
+                 metadata: Metadata,
+                 task_id: str,
+                 signature: List[int] = []):
         self.request = "approve_task"
         self.metadata = metadata
         self.task_id = task_id
+        self.signature = signature
 
 
 class InvokeTaskRequest:
@@ -352,10 +740,353 @@ class GetTaskRequest:
         self.task_id = task_id
 
 
+class GetTaskManifestRequest:
+    def __init__(self, metadata: Metadata, task_id: str):
+        self.request = "get_task_manifest"
+        self.metadata = metadata
+        self.task_id = task_id
+
+
+class GetTaskReturnValueRequest:
+    def __init__(self, metadata: Metadata, task_id: str, offset: int,
+                 limit: int):
+        self.request = "get_task_return_value"
+        self.metadata = metadata
+        self.task_id = task_id
+        self.offset = offset
+        self.limit = limit
+
+
+class GetTaskKeyOfferRequest:
+    def __init__(self, metadata: Metadata, task_id: str):
+        self.request = "get_task_key_offer"
+        self.metadata = metadata
+        self.task_id = task_id
+
+
+class PutTaskKeysRequest:
+    def __init__(self, metadata: Metadata, task_id: str,
+                 offer_public_key: List[int], public_key: List[int],
+                 wrapped_keys: Dict[str, List[int]]):
+        self.request = "put_task_keys"
+        self.metadata = metadata
+        self.task_id = task_id
+        self.offer_public_key = offer_public_key
+        self.public_key = public_key
+        self.wrapped_keys = wrapped_keys
+
+
+class GetDataLineageRequest:
+    def __init__(self, metadata: Metadata, data_id: str):
+        self.request = "get_data_lineage"
+        self.metadata = metadata
+        self.data_id = data_id
+
+
+class RegisterApprovalPolicyRequest:
+    def __init__(self, metadata: Metadata, data_id: str,
+                 function_ids: List[str], creators: List[str]):
+        self.request = "register_approval_policy"
+        self.metadata = metadata
+        self.data_id = data_id
+        self.function_ids = function_ids
+        self.creators = creators
+
+
+class RevokeApprovalPolicyRequest:
+    def __init__(self, metadata: Metadata, policy_id: str):
+        self.request = "revoke_approval_policy"
+        self.metadata = metadata
+        self.policy_id = policy_id
+
+
+class RegisterModelRequest:
+    def __init__(self, metadata: Metadata, name: str, task_id: str,
+                 data_id: str, metrics: Dict[str, float]):
+        self.request = "register_model"
+        self.metadata = metadata
+        self.name = name
+        self.task_id = task_id
+        self.data_id = data_id
+        self.metrics = metrics
+
+
+class GetModelRequest:
+    def __init__(self, metadata: Metadata, name: str, version: int = 0):
+        self.request = "get_model"
+        self.metadata = metadata
+        self.name = name
+        self.version = version
+
+
+class CreateTaskShareLinkRequest:
+    def __init__(self, metadata: Metadata, task_id: str, expires_in: int):
+        self.request = "create_task_share_link"
+        self.metadata = metadata
+        self.task_id = task_id
+        self.expires_in = expires_in
+
+
+class GetSharedTaskRequest:
+    """Carries no metadata, the token is the only credential."""
+    def __init__(self, token: str):
+        self.request = "get_shared_task"
+        self.token = token
+
+
+class GetPlatformInfoRequest:
+    """Carries no metadata, the enclave info is public."""
+    def __init__(self):
+        self.request = "get_platform_info"
+
+
+class RegisterSigningKeyRequest:
+    def __init__(self, metadata: Metadata, public_key: List[int]):
+        self.request = "register_signing_key"
+        self.metadata = metadata
+        self.public_key = public_key
+
+
+class ArchiveTaskRequest:
+    def __init__(self, metadata: Metadata, task_id: str):
+        self.request = "archive_task"
+        self.metadata = metadata
+        self.task_id = task_id
+
+
+class RestoreArchivedTaskRequest:
+    def __init__(self, metadata: Metadata, task_id: str):
+        self.request = "restore_archived_task"
+        self.metadata = metadata
+        self.task_id = task_id
+
+
+class ExportStorageSnapshotRequest:
+    def __init__(self, metadata: Metadata, name: str):
+        self.request = "export_storage_snapshot"
+        self.metadata = metadata
+        self.name = name
+
+
+class RestoreStorageSnapshotRequest:
+    def __init__(self, metadata: Metadata, snapshots: List[Dict[str, Any]]):
+        self.request = "restore_storage_snapshot"
+        self.metadata = metadata
+        self.snapshots = snapshots
+
+
+class PromoteStorageReplicaRequest:
+    def __init__(self, metadata: Metadata, name: str):
+        self.request = "promote_storage_replica"
+        self.metadata = metadata
+        self.name = name
+
+
+class CancelTaskRequest:
+    def __init__(self, metadata: Metadata, task_id: str):
+        self.request = "cancel_task"
+        self.metadata = metadata
+        self.task_id = task_id
+
+
+class GetTaskUploadSlotsRequest:
+    def __init__(self, metadata: Metadata, task_id: str):
+        self.request = "get_task_upload_slots"
+        self.metadata = metadata
+        self.task_id = task_id
+
+
+class RejectTaskRequest:
+    def __init__(self, metadata: Metadata, task_id: str, reason: str):
+        self.request = "reject_task"
+        self.metadata = metadata
+        self.task_id = task_id
+        self.reason = reason
+
+
+class GetActivityFeedRequest:
+    def __init__(self, metadata: Metadata, since: int):
+        self.request = "get_activity_feed"
+        self.metadata = metadata
+        self.since = since
+
+
+class GetTaskResultStreamRequest:
+    def __init__(self, metadata: Metadata, task_id: str, output: str):
+        self.request = "get_task_result_stream"
+        self.metadata = metadata
+        self.task_id = task_id
+        self.output = output
+
+
+class RegisterTaskTemplateRequest:
+    def __init__(self, metadata: Metadata, function_id: str,
+                 function_arguments: str, executor: str,
+                 inputs_ownership: List[OwnerList],
+                 outputs_ownership: List[OwnerList]):
+        self.request = "register_task_template"
+        self.metadata = metadata
+        self.function_id = function_id
+        self.function_arguments = function_arguments
+        self.executor = executor
+        self.inputs_ownership = inputs_ownership
+        self.outputs_ownership = outputs_ownership
+
+
+class CreateTaskFromTemplateRequest:
+    def __init__(self, metadata: Metadata, template_id: str,
+                 function_arguments: str, labels: Dict[str, str]):
+        self.request = "create_task_from_template"
+        self.metadata = metadata
+        self.template_id = template_id
+        self.function_arguments = function_arguments
+        self.labels = labels
+
+
+class CreateTasksBatchRequest:
+    def __init__(self, metadata: Metadata, tasks: List[Dict[str, Any]]):
+        self.request = "create_tasks_batch"
+        self.metadata = metadata
+        self.tasks = tasks
+
+
+class TestFunctionRequest:
+    def __init__(self, metadata: Metadata, function_id: str,
+                 function_arguments: str, executor: str,
+                 sample_inputs: Dict[str, List[int]]):
+        self.request = "test_function"
+        self.metadata = metadata
+        self.function_id = function_id
+        self.function_arguments = function_arguments
+        self.executor = executor
+        self.sample_inputs = sample_inputs
+
+
+class RegisterTaskScheduleRequest:
+    def __init__(self, metadata: Metadata, template_id: str, start_in: int,
+                 interval: int, max_runs: int, inputs: List[DataMap]):
+        self.request = "register_task_schedule"
+        self.metadata = metadata
+        self.template_id = template_id
+        self.start_in = start_in
+        self.interval = interval
+        self.max_runs = max_runs
+        self.inputs = inputs
+
+
+class ApproveTaskScheduleRequest:
+    def __init__(self, metadata: Metadata, template_id: str):
+        self.request = "approve_task_schedule"
+        self.metadata = metadata
+        self.template_id = template_id
+
+
+class CancelTaskScheduleRequest:
+    def __init__(self, metadata: Metadata, template_id: str):
+        self.request = "cancel_task_schedule"
+        self.metadata = metadata
+        self.template_id = template_id
+
+
+class GetTaskScheduleRequest:
+    def __init__(self, metadata: Metadata, template_id: str):
+        self.request = "get_task_schedule"
+        self.metadata = metadata
+        self.template_id = template_id
+
+
+class EstimateTaskRequest:
+    def __init__(self,
+                 metadata: Metadata,
+                 function_id: str,
+                 input_size: int = 0,
+                 priority: str = "normal"):
+        self.request = "estimate_task"
+        self.metadata = metadata
+        self.function_id = function_id
+        self.input_size = input_size
+        self.priority = priority
+
+
+class ListPendingApprovalsRequest:
+    def __init__(self,
+                 metadata: Metadata,
+                 page_token: str = "",
+                 page_size: int = 0):
+        self.request = "list_pending_approvals"
+        self.metadata = metadata
+        self.page = {"page_token": page_token, "page_size": page_size}
+
+
+class ListTasksRequest:
+    def __init__(self,
+                 metadata: Metadata,
+                 labels: Dict[str, str],
+                 page_token: str = "",
+                 page_size: int = 0,
+                 statuses: List[int] = [],
+                 function_id: str = "",
+                 participant: str = "",
+                 created_after: int = 0,
+                 created_before: int = 0):
+        self.request = "list_tasks"
+        self.metadata = metadata
+        self.labels = labels
+        self.page = {"page_token": page_token, "page_size": page_size}
+        self.statuses = statuses
+        self.function_id = function_id
+        self.participant = participant
+        self.created_after = created_after
+        self.created_before = created_before
+
+
+class ListFunctionsRequest:
+    def __init__(self,
+                 metadata: Metadata,
+                 page_token: str = "",
+                 page_size: int = 0):
+        self.request = "list_functions"
+        self.metadata = metadata
+        self.page = {"page_token": page_token, "page_size": page_size}
+
+
+class ListFilesRequest:
+    def __init__(self,
+                 metadata: Metadata,
+                 output: bool = False,
+                 page_token: str = "",
+                 page_size: int = 0):
+        self.request = "list_files"
+        self.metadata = metadata
+        self.output = output
+        self.page = {"page_token": page_token, "page_size": page_size}
+
+
+class DeleteInputFileRequest:
+    def __init__(self, metadata: Metadata, data_id: str):
+        self.request = "delete_input_file"
+        self.metadata = metadata
+        self.data_id = data_id
+
+
+class DeleteOutputFileRequest:
+    def __init__(self, metadata: Metadata, data_id: str):
+        self.request = "delete_output_file"
+        self.metadata = metadata
+        self.data_id = data_id
+
+
 class FrontendClient:
     def __init__(self, channel: ssl.SSLSocket, metadata: Metadata = None):
         self.channel = channel
         self.metadata = metadata
+        self.trace_hook = None
+        self.signing_key = None
+
+    def set_trace_hook(self, hook: Callable[[TraceEvent], None] = None):
+        """Call the hook with every request and response of the client, e.g.,
+        for debugging. Tracing stops if no hook is given."""
+        self.trace_hook = hook
 
     def register_function(self,
                           name: str,
@@ -365,85 +1096,1028 @@ class FrontendClient:
                           payload: List[int] = [],
                           arguments: List[str] = [],
                           inputs: List[FunctionInput] = [],
-                          outputs: List[FunctionOutput] = []):
+                          outputs: List[FunctionOutput] = [],
+                          resource_profile: ResourceProfile = None,
+                          entry_points: List[str] = [],
+                          capabilities: FunctionCapabilities = None,
+                          worker_measurements: List[str] = [],
+                          post_processing: List[PostProcessingStep] = []):
+        """Register a function. Python payloads can expose several entry
+        points, one of which is chosen when a task is created. Functions
+        declaring capabilities are denied anything they did not declare.
+        Functions pinned to worker measurements, i.e., hex encoded MRENCLAVE
+        of execution enclaves, are only run by those enclaves. The
+        post-processing steps are applied in order to the return value of
+        the function before it leaves the execution enclave."""
         request = RegisterFunctionRequest(self.metadata, name, description,
                                           executor_type, public, payload,
-                                          arguments, inputs, outputs)
-        _write_message(self.channel, request)
-        response = _read_message(self.channel)
+                                          arguments, inputs, outputs,
+                                          resource_profile, entry_points,
+                                          capabilities, worker_measurements,
+                                          post_processing)
+        response = _send_request(self.channel, request, self.trace_hook)
         return response["content"]["function_id"]
 
     def register_input_file(self, url: str, schema: str, key: List[int],
                             iv: List[int], cmac: List[int]):
         request = RegisterInputFileRequest(self.metadata, url, cmac,
                                            CryptoInfo(schema, key, iv))
-        _write_message(self.channel, request)
-        response = _read_message(self.channel)
+        response = _send_request(self.channel, request, self.trace_hook)
         return response["content"]["data_id"]
 
-    def register_output_file(self, url: str, schema: str, key: List[int],
-                             iv: List[int]):
-        request = RegisterOutputFileRequest(self.metadata, url,
+    def register_brokered_input_file(self, url: str, cmac: List[int],
+                                     key_broker: KeyBrokerInfo):
+        """Register an input file whose key is released by the key broker to
+        the execution enclaves only."""
+        request = RegisterInputFileRequest(self.metadata, url, cmac, None,
+                                           key_broker)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["data_id"]
+
+    def rotate_input_file_key(self, data_id: str, url: str, schema: str,
+                              key: List[int], iv: List[int], cmac: List[int]):
+        """Replace the key of an input file with the one of the file
+        re-encrypted and uploaded to a new url. Returns the unfinished tasks
+        still reading the file at its old url."""
+        request = RotateInputFileKeyRequest(self.metadata, data_id, url, cmac,
                                             CryptoInfo(schema, key, iv))
-        _write_message(self.channel, request)
-        response = _read_message(self.channel)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["in_flight_task_ids"]
+
+    def register_output_file(self,
+                             url: str,
+                             schema: str,
+                             key: List[int],
+                             iv: List[int],
+                             credential: CredentialInfo = None):
+        """Register an output file. If url is None, the url is set later with
+        update_output_url before the task is invoked."""
+        request = RegisterOutputFileRequest(self.metadata, url or "",
+                                            CryptoInfo(schema, key, iv),
+                                            credential)
+        response = _send_request(self.channel, request, self.trace_hook)
         return response["content"]["data_id"]
 
+    def update_output_url(self,
+                          data_id: str,
+                          url: str,
+                          credential: CredentialInfo = None):
+        request = UpdateOutputUrlRequest(self.metadata, data_id, url,
+                                         credential)
+        response = _send_request(self.channel, request, self.trace_hook)
+        assert (response["result"] == "ok")
+
     def create_task(self,
                     function_id: str,
                     function_arguments: Dict[str, Any],
                     executor: str,
                     inputs_ownership: List[OwnerList] = [],
-                    outputs_ownership: List[OwnerList] = []):
+                    outputs_ownership: List[OwnerList] = [],
+                    labels: Dict[str, str] = {},
+                    workflow_id: str = "",
+                    entry_point: str = "",
+                    reuse_result: bool = False,
+                    key_exchange: bool = False,
+                    expires_in: int = 0,
+                    approval_window: int = 0,
+                    input_dependencies: Dict[str, TaskOutputReference] = {},
+                    priority: str = "normal",
+                    resource_limits: ResourceLimits = None,
+                    timeout: int = 0,
+                    retry_policy: RetryPolicy = None,
+                    worker_measurements: List[str] = [],
+                    worker_signers: List[str] = [],
+                    federation_peer: str = ""):
+        """Create a task. With reuse_result, a task writing no output file is
+        served from the result of an identical task consenting to reuse, i.e.,
+        the same function payload, arguments and input files, if any. With
+        key_exchange, the keys of the files are handed to the execution
+        enclave with exchange_task_keys once the task is running. With
+        expires_in, the task expires unless it starts running within the
+        seconds. With approval_window, the task expires unless every
+        participant approves it within the seconds, and the participants are
+        notified. With input_dependencies, the inputs are the outputs of
+        upstream tasks, assigned once the upstream tasks finish, and the task
+        invoked before that is staged then. Staged tasks of a higher priority,
+        "low", "normal" or "high", are run first. With resource_limits, the
+        task fails if it exceeds any of the limits. With timeout, the task
+        fails unless it finishes within the seconds once it is dispatched.
+        With retry_policy, the task failed transiently is run again. With
+        worker_measurements or worker_signers, the task is only pulled by
+        execution enclaves of the hex encoded MRENCLAVE or MRSIGNER. With
+        federation_peer, the task is delegated to the federated deployment of
+        the name once invoked, and its result is taken from the deployment
+        when the task is read."""
+        signature = self._sign_request(
+            "create_task", {
+                "function_id": function_id,
+                "function_arguments": function_arguments,
+                "executor": executor,
+                "inputs_ownership": _sorted_owners(inputs_ownership),
+                "outputs_ownership": _sorted_owners(outputs_ownership),
+            })
         function_arguments = json.dumps(function_arguments)
         request = CreateTaskRequest(self.metadata, function_id,
                                     function_arguments, executor,
-                                    inputs_ownership, outputs_ownership)
-        _write_message(self.channel, request)
-        response = _read_message(self.channel)
+                                    inputs_ownership, outputs_ownership,
+                                    labels, workflow_id, entry_point,
+                                    reuse_result, key_exchange, expires_in,
+                                    approval_window, input_dependencies,
+                                    priority, resource_limits, timeout,
+                                    retry_policy, worker_measurements,
+                                    worker_signers, federation_peer,
+                                    signature)
+        response = _send_request(self.channel, request, self.trace_hook)
         return response["content"]["task_id"]
 
+    def list_tasks(self,
+                   labels: Dict[str, str] = {},
+                   statuses: List[int] = [],
+                   function_id: str = "",
+                   participant: str = "",
+                   created_after: int = 0,
+                   created_before: int = 0):
+        """Yields the ids of the tasks carrying the labels page by page. The
+        tasks can be filtered by their statuses, e.g., 10 for finished tasks,
+        their function, another participant, and the range of seconds since
+        the Unix epoch they are created within."""
+        page_token = ""
+        while True:
+            request = ListTasksRequest(self.metadata, labels, page_token, 0,
+                                       statuses, function_id, participant,
+                                       created_after, created_before)
+            response = _send_request(self.channel, request, self.trace_hook)
+            yield from response["content"]["task_ids"]
+            page_token = response["content"]["page"]["next_page_token"]
+            if not page_token:
+                return
+
+    def list_pending_approvals(self):
+        """Yields the ids of the tasks awaiting approval page by page."""
+        page_token = ""
+        while True:
+            request = ListPendingApprovalsRequest(self.metadata, page_token)
+            response = _send_request(self.channel, request, self.trace_hook)
+            yield from response["content"]["task_ids"]
+            page_token = response["content"]["page"]["next_page_token"]
+            if not page_token:
+                return
+
+    def list_functions(self):
+        """Yields the ids of the functions owned by the user page by page."""
+        page_token = ""
+        while True:
+            request = ListFunctionsRequest(self.metadata, page_token)
+            response = _send_request(self.channel, request, self.trace_hook)
+            yield from response["content"]["function_ids"]
+            page_token = response["content"]["page"]["next_page_token"]
+            if not page_token:
+                return
+
+    def list_files(self, output: bool = False):
+        """Yields the ids of the input files, or the output files if output
+        is set, owned by the user page by page."""
+        page_token = ""
+        while True:
+            request = ListFilesRequest(self.metadata, output, page_token)
+            response = _send_request(self.channel, request, self.trace_hook)
+            yield from response["content"]["data_ids"]
+            page_token = response["content"]["page"]["next_page_token"]
+            if not page_token:
+                return
+
+    def delete_input_file(self, data_id: str):
+        """Delete an input file of the user which no unfinished task is
+        assigned. The file is purged after the retention window.
+
+        Args:
+            data_id: ID of the input file.
+        """
+        request = DeleteInputFileRequest(self.metadata, data_id)
+        _ = _send_request(self.channel, request, self.trace_hook)
+
+    def delete_output_file(self, data_id: str):
+        """Delete an output file of the user which no unfinished task is
+        assigned. The file is purged after the retention window.
+
+        Args:
+            data_id: ID of the output file.
+        """
+        request = DeleteOutputFileRequest(self.metadata, data_id)
+        _ = _send_request(self.channel, request, self.trace_hook)
+
     def assign_data_to_task(self, task_id: str, inputs: List[DataMap],
                             outputs: List[DataMap]):
-        request = AssignDataRequest(self.metadata, task_id, inputs, outputs)
-        _write_message(self.channel, request)
-        _ = _read_message(self.channel)
+        signature = self._sign_request(
+            "assign_data", {
+                "task_id": task_id,
+                "inputs": {data.data_name: data.data_id
+                           for data in inputs},
+                "outputs": {data.data_name: data.data_id
+                            for data in outputs},
+            })
+        request = AssignDataRequest(self.metadata, task_id, inputs, outputs,
+                                    signature)
+        _ = _send_request(self.channel, request, self.trace_hook)
         return
 
     def approve_task(self, task_id: str):
-        request = ApproveTaskRequest(self.metadata, task_id)
-        _write_message(self.channel, request)
-        _ = _read_message(self.channel)
+        signature = []
+        if self.signing_key:
+            # The approval is signed over the spec hash of the task.
+            request = GetTaskRequest(self.metadata, task_id)
+            response = _send_request(self.channel, request, self.trace_hook)
+            signature = self._sign_request(
+                "approve_task", {
+                    "task_id": task_id,
+                    "spec_hash": response["content"]["spec_hash"],
+                })
+        request = ApproveTaskRequest(self.metadata, task_id, signature)
+        _ = _send_request(self.channel, request, self.trace_hook)
         return
 
+    def register_signing_key(self, public_key: bytes):
+        """Register the Ed25519 public key the user signs requests with,
+        replacing the one registered before.
+
+        Args:
+            public_key: Raw 32-byte Ed25519 public key.
+        """
+        request = RegisterSigningKeyRequest(self.metadata, list(public_key))
+        _ = _send_request(self.channel, request, self.trace_hook)
+
+    def set_signing_key(self, private_key: Ed25519PrivateKey = None):
+        """Sign the requests creating tasks, assigning data and approving
+        tasks with the key, whose public key is registered with
+        register_signing_key. The signatures are kept with the tasks as
+        evidence of the requests. Requests are unsigned if no key is given.
+        """
+        self.signing_key = private_key
+
+    def _sign_request(self, action: str, content: Dict[str, Any]) -> List[int]:
+        """Signs "<action>|<user id>|<digest>", where the digest is the hex
+        encoded SHA-256 of the content in JSON with sorted keys and no
+        whitespace, or returns an empty signature without a signing key."""
+        if not self.signing_key:
+            return []
+        canonical = json.dumps(content,
+                               sort_keys=True,
+                               separators=(",", ":"),
+                               ensure_ascii=False)
+        digest = hashlib.sha256(canonical.encode()).hexdigest()
+        message = "{}|{}|{}".format(action, self.metadata["id"], digest)
+        return list(self.signing_key.sign(message.encode()))
+
     def invoke_task(self, task_id: str):
         request = InvokeTaskRequest(self.metadata, task_id)
-        _write_message(self.channel, request)
-        response = _read_message(self.channel)
+        response = _send_request(self.channel, request, self.trace_hook)
         assert (response["result"] == "ok")
 
     def get_task_result(self, task_id: str):
         request = GetTaskRequest(self.metadata, task_id)
 
         while True:
-            _write_message(self.channel, request)
-            response = _read_message(self.channel)
+            response = _send_request(self.channel, request, self.trace_hook)
             time.sleep(1)
             if response["content"]["status"] == 10:
                 break
-
-        return response["content"]["result"]["result"]["Ok"]["return_value"]
+            if response["content"]["status"] == 11:
+                raise TeaclaveException("conflict", "task canceled")
+            if response["content"]["status"] == 12:
+                raise TeaclaveException("conflict", "task expired")
+            if response["content"]["status"] == 13:
+                raise TeaclaveException("conflict", "task rejected")
+
+        outputs = response["content"]["result"]["result"]["Ok"]
+        if outputs.get("return_value_handle"):
+            return self.get_task_return_value(task_id)
+        return outputs["return_value"]
+
+    def get_task_return_value(self, task_id: str):
+        return_value = []
+        offset = 0
+        while True:
+            request = GetTaskReturnValueRequest(self.metadata, task_id, offset,
+                                                0)
+            response = _send_request(self.channel, request, self.trace_hook)
+            return_value.extend(response["content"]["data"])
+            offset = response["content"]["next_offset"]
+            if offset == 0:
+                return return_value
+
+    def exchange_task_keys(self, task_id: str, as_root_ca_cert_path: str,
+                           enclave_info_path: str, keys: Dict[str,
+                                                              CryptoInfo]):
+        """Hand the keys of the files of a running task exchanging keys to the
+        execution enclave. The offer of the enclave is verified against its
+        attestation report, and the keys are wrapped under the secret shared
+        with it, so no other service learns them."""
+        request = GetTaskKeyOfferRequest(self.metadata, task_id)
+        response = _send_request(self.channel, request, self.trace_hook)
+        offer = response["content"]["offer"]
+        offer_public_key = bytes(offer["public_key"])
+        cert = bytes(offer["cert"])
+        _verify_report(as_root_ca_cert_path, enclave_info_path, cert,
+                       "execution")
+        task_uuid = uuid.UUID(task_id.split("-", 1)[1]).bytes
+        _verify_task_key_offer(task_uuid, offer_public_key, cert,
+                               bytes(offer["signature"]))
+
+        private_key = ec.generate_private_key(ec.SECP256R1(),
+                                              default_backend())
+        public_key = private_key.public_key().public_bytes(
+            Encoding.X962, PublicFormat.UncompressedPoint)
+        peer_public_key = ec.EllipticCurvePublicKey.from_encoded_point(
+            ec.SECP256R1(), offer_public_key)
+        shared_secret = private_key.exchange(ec.ECDH(), peer_public_key)
+        wrapped_keys = {
+            fname: list(
+                _wrap_file_crypto(task_uuid, fname, shared_secret, crypto))
+            for fname, crypto in keys.items()
+        }
+
+        request = PutTaskKeysRequest(self.metadata, task_id,
+                                     list(offer_public_key), list(public_key),
+                                     wrapped_keys)
+        response = _send_request(self.channel, request, self.trace_hook)
+        assert (response["result"] == "ok")
 
     def get_output_cmac_by_tag(self, task_id: str, tag: str):
         request = GetTaskRequest(self.metadata, task_id)
         while True:
-            _write_message(self.channel, request)
-            response = _read_message(self.channel)
+            response = _send_request(self.channel, request, self.trace_hook)
             time.sleep(1)
             if response["content"]["status"] == 10:
                 break
+            if response["content"]["status"] == 11:
+                raise TeaclaveException("conflict", "task canceled")
+            if response["content"]["status"] == 12:
+                raise TeaclaveException("conflict", "task expired")
+            if response["content"]["status"] == 13:
+                raise TeaclaveException("conflict", "task rejected")
         return response["content"]["result"]["result"]["Ok"]["tags_map"][tag]
 
+    def register_approval_policy(self, data_id: str, function_ids: List[str],
+                                 creators: List[str]):
+        request = RegisterApprovalPolicyRequest(self.metadata, data_id,
+                                                function_ids, creators)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["policy_id"]
+
+    def revoke_approval_policy(self, policy_id: str):
+        request = RevokeApprovalPolicyRequest(self.metadata, policy_id)
+        response = _send_request(self.channel, request, self.trace_hook)
+        assert (response["result"] == "ok")
+
+    def get_task_manifest(self, task_id: str):
+        request = GetTaskManifestRequest(self.metadata, task_id)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["manifest"]
+
+    def get_data_lineage(self, data_id: str):
+        request = GetDataLineageRequest(self.metadata, data_id)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["steps"]
+
+    def register_model(self,
+                       name: str,
+                       task_id: str,
+                       data_id: str,
+                       metrics: Dict[str, float] = None) -> int:
+        """Register an output of a finished task as a new version of a model,
+        which can be assigned to inputs by "model:<name>@<version>".
+
+        Returns:
+            int: The version of the model.
+        """
+        request = RegisterModelRequest(self.metadata, name, task_id, data_id,
+                                       metrics or {})
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["version"]
+
+    def get_model(self, name: str, version: int = 0):
+        """Get a version of a model, or the latest one if version is 0."""
+        request = GetModelRequest(self.metadata, name, version)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["model"]
+
+    def create_task_share_link(self, task_id: str, expires_in: int) -> str:
+        """Create a token granting read-only access to the status and progress
+        of a task created by the user, without the data and the result.
+
+        Args:
+            task_id: ID of the task.
+            expires_in: Seconds the token is valid for.
+
+        Returns:
+            str: The token, to be passed to get_shared_task.
+        """
+        request = CreateTaskShareLinkRequest(self.metadata, task_id,
+                                             expires_in)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["token"]
+
+    def get_shared_task(self, token: str):
+        """Get the progress of a shared task, which requires no credential,
+        so the client can be created without logging in."""
+        request = GetSharedTaskRequest(token)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["progress"]
+
+    def archive_task(self, task_id: str) -> str:
+        """Move a finished task out of the platform into the archive storage.
+        Only for admins.
+
+        Args:
+            task_id: ID of the task.
+
+        Returns:
+            str: URL of the sealed archive of the task.
+        """
+        request = ArchiveTaskRequest(self.metadata, task_id)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["url"]
+
+    def restore_archived_task(self, task_id: str):
+        """Restore an archived task, which can be queried as before archiving.
+        Only for admins.
+
+        Args:
+            task_id: ID of the task.
+        """
+        request = RestoreArchivedTaskRequest(self.metadata, task_id)
+        _ = _send_request(self.channel, request, self.trace_hook)
+
+    def export_storage_snapshot(self, name: str) -> List[Dict[str, Any]]:
+        """Seal snapshots of the default storage and its shards under the
+        name. Only for admins.
+
+        Args:
+            name: Name of the snapshots.
+
+        Returns:
+            List[Dict[str, Any]]: Snapshots with the keys opening them, which
+            are to be kept as secret as the storage itself.
+        """
+        request = ExportStorageSnapshotRequest(self.metadata, name)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["snapshots"]
+
+    def restore_storage_snapshot(self, snapshots: List[Dict[str, Any]]):
+        """Replace all records of the default storage and its shards with
+        those of the snapshots. Only for admins.
+
+        Args:
+            snapshots: Snapshots in the order they are exported.
+        """
+        request = RestoreStorageSnapshotRequest(self.metadata, snapshots)
+        _ = _send_request(self.channel, request, self.trace_hook)
+
+    def promote_storage_replica(self, name: str):
+        """Promote the read replica of the name to stand in for the default
+        storage after losing it. Only for admins.
+
+        Args:
+            name: Name of the replica in the runtime config.
+        """
+        request = PromoteStorageReplicaRequest(self.metadata, name)
+        _ = _send_request(self.channel, request, self.trace_hook)
+
+    def cancel_task(self, task_id: str):
+        """Cancel a task which has not started running. Only for the creator.
+
+        Args:
+            task_id: ID of the task.
+        """
+        request = CancelTaskRequest(self.metadata, task_id)
+        _ = _send_request(self.channel, request, self.trace_hook)
+
+    def get_task_upload_slots(self, task_id: str) -> Dict[str, str]:
+        """Get the urls to push the inputs of the user registered with the
+        "push:" url to, once the task is running.
+
+        Args:
+            task_id: ID of the task.
+
+        Returns:
+            Dict[str, str]: Upload urls by the names of the inputs.
+        """
+        request = GetTaskUploadSlotsRequest(self.metadata, task_id)
+        response = _send_request(self.channel, request, self.trace_hook)
+        endpoint = response["content"]["endpoint"]
+        tokens = response["content"]["tokens"]
+        return {
+            fname: urllib.parse.urljoin(endpoint, token)
+            for fname, token in tokens.items()
+        }
+
+    def reject_task(self, task_id: str, reason: str):
+        """Reject a task which has not been invoked. Only for the
+        participants.
+
+        Args:
+            task_id: ID of the task.
+            reason: Reason of the rejection, recorded in the task.
+        """
+        request = RejectTaskRequest(self.metadata, task_id, reason)
+        _ = _send_request(self.channel, request, self.trace_hook)
+
+    def get_activity_feed(self, since: int = 0) -> List[Dict[str, Any]]:
+        """Get the recent events of the user, i.e., tasks created, approved
+        and finished, data and functions registered.
+
+        Args:
+            since: Seconds since the Unix epoch of the earliest event.
+
+        Returns:
+            List[Dict[str, Any]]: Events with the kind, object_id and
+            timestamp, newest first.
+        """
+        request = GetActivityFeedRequest(self.metadata, since)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["events"]
+
+    def get_task_result_stream(self, task_id: str, output: str = ""):
+        """Stream the return value of a finished task, or one of its output
+        files as it is stored (i.e., encrypted), chunk by chunk. The request
+        is sent once the first chunk is read, and the client is not usable
+        for other requests until the chunks are read to the end.
+
+        Args:
+            task_id: Task id.
+            output: Name of the output file in the function, or empty for the
+                return value.
+
+        Returns:
+            Iterator[bytes]: Chunks of the result in order.
+        """
+        if isinstance(self.channel, _MultiplexedChannel):
+            raise TeaclaveException("validation",
+                                    "streaming requests are not multiplexed")
+        request = GetTaskResultStreamRequest(self.metadata, task_id, output)
+        _write_message(self.channel, request)
+        while True:
+            frame = _read_message(self.channel)
+            if frame["stream"] == "end":
+                return
+            if frame["stream"] == "err":
+                _check_response(dict(frame["error"], result="err"))
+            yield bytes(frame["content"]["content"]["data"])
+
+    def register_task_template(self,
+                               function_id: str,
+                               function_arguments: Dict[str, Any],
+                               executor: str,
+                               inputs_ownership: List[OwnerList] = [],
+                               outputs_ownership: List[OwnerList] = []) -> str:
+        """Register a template of recurring tasks of a function among the same
+        participants, i.e., the user and the owners of the files.
+
+        Args:
+            function_id: ID of the function.
+            function_arguments: Defaults of some or all of the arguments.
+            executor: Executor of the tasks.
+            inputs_ownership: Owners of the input files.
+            outputs_ownership: Owners of the output files.
+
+        Returns:
+            str: ID of the template.
+        """
+        function_arguments = json.dumps(function_arguments)
+        request = RegisterTaskTemplateRequest(self.metadata, function_id,
+                                              function_arguments, executor,
+                                              inputs_ownership,
+                                              outputs_ownership)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["template_id"]
+
+    def create_task_from_template(self,
+                                  template_id: str,
+                                  function_arguments: Dict[str, Any] = {},
+                                  labels: Dict[str, str] = {}) -> str:
+        """Create a task from a template. Only the participants of the
+        template can create tasks from it.
+
+        Args:
+            template_id: ID of the template.
+            function_arguments: Arguments overriding the defaults of the
+                template.
+            labels: Labels of the task.
+
+        Returns:
+            str: ID of the task.
+        """
+        function_arguments = json.dumps(function_arguments)
+        request = CreateTaskFromTemplateRequest(self.metadata, template_id,
+                                                function_arguments, labels)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["task_id"]
+
+    def create_tasks_batch(self, tasks: List[Dict[str, Any]]) -> List[str]:
+        """Create tasks all at once, either all of them or none. Each task is
+        a dict of the arguments of create_task.
+
+        Args:
+            tasks: Tasks to create.
+
+        Returns:
+            List[str]: IDs of the tasks in order.
+        """
+        specs = []
+        for task in tasks:
+            specs.append({
+                "function_id": task["function_id"],
+                "function_arguments":
+                json.dumps(task.get("function_arguments", {})),
+                "executor": task["executor"],
+                "inputs_ownership": task.get("inputs_ownership", []),
+                "outputs_ownership": task.get("outputs_ownership", []),
+                "labels": task.get("labels", {}),
+                "workflow_id": task.get("workflow_id", ""),
+                "entry_point": task.get("entry_point", ""),
+                "reuse_result": task.get("reuse_result", False),
+                "key_exchange": task.get("key_exchange", False),
+                "expires_in": task.get("expires_in", 0),
+                "approval_window": task.get("approval_window", 0),
+                "input_dependencies": task.get("input_dependencies", {}),
+                "priority": task.get("priority", "normal"),
+                "resource_limits": task.get("resource_limits"),
+                "timeout": task.get("timeout", 0),
+                "retry_policy": task.get("retry_policy"),
+            })
+        request = CreateTasksBatchRequest(self.metadata, specs)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["task_ids"]
+
+    def test_function(self,
+                      function_id: str,
+                      executor: str,
+                      function_arguments: Dict[str, Any] = {},
+                      sample_inputs: Dict[str, bytes] = {}) -> str:
+        """Test a function of the user in a throwaway sandbox against small
+        sample inputs, before any real data is attached to its tasks.
+
+        Args:
+            function_id: ID of the function.
+            executor: Executor of the test.
+            function_arguments: Arguments of the function.
+            sample_inputs: Contents of the inputs by their names, inputs
+                without samples are empty.
+
+        Returns:
+            str: ID of the test task, see get_function_test_result.
+        """
+        function_arguments = json.dumps(function_arguments)
+        sample_inputs = {
+            fname: list(sample)
+            for fname, sample in sample_inputs.items()
+        }
+        request = TestFunctionRequest(self.metadata, function_id,
+                                      function_arguments, executor,
+                                      sample_inputs)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]["task_id"]
+
+    def register_task_schedule(self,
+                               template_id: str,
+                               interval: int,
+                               inputs: List[DataMap],
+                               start_in: int = 0,
+                               max_runs: int = 0):
+        """Run tasks of a template created by the user periodically. Every
+        task is assigned the same inputs, and new fusion outputs of the owners
+        of the outputs. The tasks run once every participant has approved the
+        schedule, which the user does by registering it.
+
+        Args:
+            template_id: ID of the template.
+            interval: Seconds between two runs, at least 60.
+            inputs: Input files of every run.
+            start_in: Seconds from now to the first run.
+            max_runs: Number of runs, or 0 to run until canceled.
+        """
+        request = RegisterTaskScheduleRequest(self.metadata, template_id,
+                                              start_in, interval, max_runs,
+                                              inputs)
+        _ = _send_request(self.channel, request, self.trace_hook)
+
+    def approve_task_schedule(self, template_id: str):
+        """Approve the schedule of a template, i.e., the tasks of all its
+        runs.
+
+        Args:
+            template_id: ID of the template.
+        """
+        request = ApproveTaskScheduleRequest(self.metadata, template_id)
+        _ = _send_request(self.channel, request, self.trace_hook)
+
+    def cancel_task_schedule(self, template_id: str):
+        """Stop the schedule of a template for good. For any participant.
+
+        Args:
+            template_id: ID of the template.
+        """
+        request = CancelTaskScheduleRequest(self.metadata, template_id)
+        _ = _send_request(self.channel, request, self.trace_hook)
+
+    def get_task_schedule(self, template_id: str) -> Dict[str, Any]:
+        """Get the schedule of a template, with its approvals and the IDs of
+        the tasks of the runs so far.
+
+        Args:
+            template_id: ID of the template.
+
+        Returns:
+            Dict[str, Any]: The schedule.
+        """
+        request = GetTaskScheduleRequest(self.metadata, template_id)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]
+
+    def estimate_task(self,
+                      function_id: str,
+                      input_size: int = 0,
+                      priority: str = "normal") -> Dict[str, Any]:
+        """Estimate how long a task of a function would run, and how long it
+        would wait to run if it were staged now.
+
+        Args:
+            function_id: ID of the function.
+            input_size: Total bytes of the input files of the task.
+            priority: Priority of the task, "low", "normal" or "high".
+
+        Returns:
+            Dict[str, Any]: The run time and queue wait in milliseconds, the
+            runs of the function the run time is estimated from, and the
+            live workers.
+        """
+        request = EstimateTaskRequest(self.metadata, function_id, input_size,
+                                      priority)
+        response = _send_request(self.channel, request, self.trace_hook)
+        return response["content"]
+
+    def get_platform_info(self,
+                          auditor_public_keys: List[bytes]) -> Dict[str, Any]:
+        """Get the enclave info of the platform, which maps each service to
+        the measurement of its enclave and the build the measurement comes
+        from, i.e., the git commit, the toolchains and the build flags. The
+        info is only returned once every signature of the auditors over it is
+        verified with one of the keys. No credential is required.
+
+        Args:
+            auditor_public_keys: PEM public keys of the trusted auditors.
+
+        Returns:
+            Dict[str, Any]: The enclave info keyed by service.
+        """
+        request = GetPlatformInfoRequest()
+        response = _send_request(self.channel, request, self.trace_hook)
+        enclave_info = bytes(response["content"]["enclave_info"])
+        signatures = response["content"]["auditor_signatures"]
+        keys = [
+            load_pem_public_key(key, backend=default_backend())
+            for key in auditor_public_keys
+        ]
+        if not signatures:
+            raise TeaclaveException("attestation", "enclave info not signed")
+        for signature in signatures:
+            if not any(
+                    _verify_signature(key, bytes(signature), enclave_info)
+                    for key in keys):
+                raise TeaclaveException("attestation",
+                                        "invalid signature of enclave info")
+        return toml.loads(enclave_info.decode())
+
+    def get_function_test_result(self, task_id: str):
+        """Wait for a function test to finish.
+
+        Returns:
+            Tuple[str, Dict[str, bytes]]: The log of the test, i.e., the
+                return value or the failure of the function, and the contents
+                of the outputs it wrote.
+        """
+        request = GetTaskRequest(self.metadata, task_id)
+        while True:
+            response = _send_request(self.channel, request, self.trace_hook)
+            if response["content"]["status"] == 10:
+                break
+            time.sleep(1)
+
+        result = response["content"]["result"]["result"]
+        if "Err" in result:
+            return result["Err"]["reason"], {}
+        outputs = result["Ok"]
+        if outputs.get("return_value_handle"):
+            return_value = self.get_task_return_value(task_id)
+        else:
+            return_value = outputs["return_value"]
+        sample_outputs = {
+            fname: bytes(content)
+            for fname, content in outputs.get("sample_outputs", {}).items()
+        }
+        return bytes(return_value).decode(errors="replace"), sample_outputs
+
+
+def push_input(upload_url: str, path: str):
+    """Push an encrypted input file to its upload url, which is only good
+    for one push.
+
+    Args:
+        upload_url: Url returned by FrontendClient.get_task_upload_slots.
+        path: Path of the encrypted file registered with the "push:" url.
+    """
+    with open(path, "rb") as f:
+        data = f.read()
+    request = urllib.request.Request(upload_url, data=data, method="PUT")
+    request.add_header("Content-Type", "application/octet-stream")
+    with urllib.request.urlopen(request) as response:
+        if response.status != 201:
+            raise TeaclaveException("internal",
+                                     "push failed: %d" % response.status)
+
+
+def upload_chunked_input(manifest_url: str,
+                         path: str,
+                         token: str = None) -> Tuple[str, int, int]:
+    """Upload an encrypted input file in content-defined chunks, skipping the
+    chunks already uploaded, e.g., for an earlier version of the input.
+
+    The chunks are stored next to the manifest under "chunks/<sha256>", and
+    shared by all manifests there. Since the input is encrypted before it is
+    chunked, only the regions of the encrypted file which stay unchanged are
+    deduplicated, i.e., not those of a file encrypted with a fresh key or IV.
+
+    Args:
+        manifest_url: Http(s) url to put the manifest of the file to, e.g.,
+            "https://host/dataset/v2.manifest", without a query string.
+        path: Path of the encrypted file.
+        token: Bearer token of the storage, which is also to be registered
+            as the credential of the input.
+
+    Returns:
+        Tuple[str, int, int]: The url to register the input with, along with
+            the number of chunks of the file and of chunks uploaded.
+    """
+    headers = {"Authorization": "Bearer " + token} if token else {}
+    chunks = []
+    uploaded = 0
+    with open(path, "rb") as f:
+        for chunk in _read_chunks(f):
+            digest = hashlib.sha256(chunk).hexdigest()
+            chunks.append({"hash": digest, "size": len(chunk)})
+            chunk_url = urllib.parse.urljoin(manifest_url, "chunks/" + digest)
+            if not _remote_exists(chunk_url, headers):
+                _put(chunk_url, chunk, headers)
+                uploaded += 1
+    manifest = json.dumps({"chunks": chunks}).encode()
+    _put(manifest_url, manifest, headers)
+    return _CHUNKED_URL_SCHEME_PREFIX + manifest_url, len(chunks), uploaded
+
+
+def verify_output(path: str, file_auth_tag: List[int],
+                  key: List[int]) -> bool:
+    """Verify the cmac of a downloaded teaclave-file-128 output file. Every
+    node of the file is authenticated with the key, as when decrypting it, so
+    that the cmac covers the contents rather than the header only.
+
+    Args:
+        path: Path of the downloaded output file.
+        file_auth_tag: Cmac of the output file in the task result, which can be
+            get with FrontendClient.get_output_cmac_by_tag.
+        key: Key of the output file, which is the one used when registering
+            the output file.
+
+    Returns:
+        bool: True if the file is intact and its cmac matches file_auth_tag.
+    """
+    with open(path, "rb") as f:
+        content = f.read()
+    try:
+        cmac = _teaclave_file_128_cmac(content, bytes(key))
+    except (InvalidTag, ValueError, struct.error):
+        return False
+    return hmac.compare_digest(cmac, bytes(file_auth_tag))
+
+
+def _teaclave_file_128_cmac(content: bytes, key: bytes) -> bytes:
+    meta = _file_node(content, 0)
+    file_id, major_version = struct.unpack_from("<QB", meta)
+    if file_id != _SGX_FILE_ID or major_version != 1:
+        raise ValueError("Not a teaclave-file-128 file")
+    if meta[_META_DATA_UPDATE_FLAG_OFFSET] != 0:
+        raise ValueError("Teaclave-file-128 file needs recovery")
+    # The meta data key is derived from the user key (SP800-108 with CMAC) and
+    # the key id in the plain part.
+    key_id = meta[_META_DATA_KEY_ID_OFFSET:_META_DATA_KEY_ID_OFFSET + 32]
+    kdf_input = struct.pack("<I64s4xQ32sI4x", 1,
+                            b"SGX-PROTECTED-FS-METADATA-KEY", 0, key_id, 128)
+    kdf = cmac.CMAC(algorithms.AES(key), backend=default_backend())
+    kdf.update(kdf_input)
+    meta_data_key = kdf.finalize()
+
+    gmac = meta[_META_DATA_GMAC_OFFSET:_META_DATA_GMAC_OFFSET +
+                _FILE_AUTH_TAG_LENGTH]
+    encrypted = meta[_META_DATA_ENCRYPTED_OFFSET:_META_DATA_ENCRYPTED_OFFSET +
+                     _META_DATA_ENCRYPTED_SIZE]
+    meta_data = _decrypt_file_node(meta_data_key, encrypted, gmac)
+    size, = struct.unpack_from("<q", meta_data, _META_DATA_SIZE_OFFSET)
+    if size <= _MD_USER_DATA_SIZE:
+        return gmac
+
+    data_nodes = -(-(size - _MD_USER_DATA_SIZE) // _NODE_SIZE)
+    mht_nodes = -(-data_nodes // _ATTACHED_DATA_NODES_COUNT)
+    root_crypto = meta_data[_META_DATA_MHT_KEY_OFFSET:
+                            _META_DATA_MHT_KEY_OFFSET + 32]
+    mhts = []
+    for n in range(mht_nodes):
+        if n == 0:
+            crypto, physical = root_crypto, 1
+        else:
+            child = n - 1
+            parent = mhts[child // _CHILD_MHT_NODES_COUNT]
+            index = _ATTACHED_DATA_NODES_COUNT + child % _CHILD_MHT_NODES_COUNT
+            crypto = parent[index * 32:index * 32 + 32]
+            physical = 1 + n * (1 + _ATTACHED_DATA_NODES_COUNT)
+        mhts.append(
+            _decrypt_file_node(crypto[:16], _file_node(content, physical),
+                               crypto[16:]))
+    for n in range(data_nodes):
+        parent = mhts[n // _ATTACHED_DATA_NODES_COUNT]
+        index = n % _ATTACHED_DATA_NODES_COUNT
+        crypto = parent[index * 32:index * 32 + 32]
+        physical = n + 2 + n // _ATTACHED_DATA_NODES_COUNT
+        _decrypt_file_node(crypto[:16], _file_node(content, physical),
+                           crypto[16:])
+    return gmac
+
+
+def _file_node(content: bytes, physical: int) -> bytes:
+    node = content[physical * _NODE_SIZE:(physical + 1) * _NODE_SIZE]
+    if len(node) != _NODE_SIZE:
+        raise ValueError("Truncated teaclave-file-128 file")
+    return node
+
+
+def _decrypt_file_node(key: bytes, cipher: bytes, gmac: bytes) -> bytes:
+    return AESGCM(key).decrypt(bytes(12), cipher + gmac, None)
+
+
+def _sorted_owners(ownership: List[OwnerList]) -> Dict[str, List[str]]:
+    return {owners.data_name: sorted(owners.uids) for owners in ownership}
+
+
+def _read_chunks(f: Any):
+    pending = b""
+    while True:
+        pending += f.read(_MAX_CHUNK_SIZE - len(pending))
+        if not pending:
+            return
+        size = _chunk_size(pending)
+        yield pending[:size]
+        pending = pending[size:]
+
+
+# The boundary only depends on the bytes of the chunk past the minimum size, so
+# that an insertion shifts the boundaries of the chunks around it only.
+def _chunk_size(data: bytes) -> int:
+    h = 0
+    for i in range(_MIN_CHUNK_SIZE, len(data)):
+        h = ((h << 1) + _GEAR[data[i]]) & 0xFFFFFFFFFFFFFFFF
+        if h & _CHUNK_MASK == 0:
+            return i + 1
+    return len(data)
+
+
+def _remote_exists(url: str, headers: Dict[str, str]) -> bool:
+    request = urllib.request.Request(url, headers=headers, method="HEAD")
+    try:
+        with urllib.request.urlopen(request):
+            return True
+    except urllib.error.HTTPError as e:
+        if e.code == 404:
+            return False
+        raise
+
+
+def _put(url: str, data: bytes, headers: Dict[str, str]):
+    request = urllib.request.Request(url,
+                                     data=data,
+                                     headers=headers,
+                                     method="PUT")
+    request.add_header("Content-Type", "application/octet-stream")
+    with urllib.request.urlopen(request) as response:
+        if response.status not in (200, 201, 204):
+            raise TeaclaveException("internal",
+                                     "upload failed: %d" % response.status)
+
+
+def _verify_signature(key, signature: bytes, content: bytes) -> bool:
+    try:
+        key.verify(signature, content, padding.PKCS1v15(), hashes.SHA256())
+        return True
+    except InvalidSignature:
+        return False
+
 
 def _write_message(sock: ssl.SSLSocket, message: Any):
     class RequestEncoder(json.JSONEncoder):
@@ -463,10 +2137,140 @@ def _read_message(sock: ssl.SSLSocket):
         data = sock.recv()
         total_recv += len(data)
         raw += data
-    response = json.loads(raw)
+    return json.loads(raw)
+
+
+class _MultiplexedChannel:
+    """Channel shared by clients on different threads. Requests are tagged
+    with ids, so that they are sent before the responses of earlier ones are
+    received. The TLS socket is used by one thread at a time, and the thread
+    waiting for the socket to be readable reads the responses of the others
+    as well."""
+    def __init__(self, sock: ssl.SSLSocket):
+        self.sock = sock
+        self._sock_lock = threading.Lock()
+        self._responses_cond = threading.Condition()
+        self._responses = {}
+        self._reading = False
+        self._next_request_id = 0
+
+    def send_request(self, request: Any):
+        message = json.loads(json.dumps(request, default=lambda o: o.__dict__))
+        with self._responses_cond:
+            request_id = self._next_request_id
+            self._next_request_id += 1
+        message["request_id"] = request_id
+        with self._sock_lock:
+            _write_message(self.sock, message)
+
+        while True:
+            with self._responses_cond:
+                if request_id in self._responses:
+                    return self._responses.pop(request_id)
+                if self._reading:
+                    self._responses_cond.wait()
+                    continue
+                self._reading = True
+            response = None
+            try:
+                if self.sock.pending() == 0:
+                    select.select([self.sock], [], [])
+                with self._sock_lock:
+                    response = _read_message(self.sock)
+            finally:
+                with self._responses_cond:
+                    self._reading = False
+                    if response is not None:
+                        response_id = response.pop("request_id", None)
+                        self._responses[response_id] = response
+                    self._responses_cond.notify_all()
+
+
+def _check_response(response: Dict[str, Any]):
+    if response["result"] == "err":
+        code = response.get("code", "internal")
+        message = next((v for k, v in response.items()
+                        if k not in ("result", "code")), "")
+        raise TeaclaveException(code, str(message))
     return response
 
 
+def _redact(value: Any):
+    if isinstance(value, dict):
+        return {
+            k: "<redacted>" if k in _SECRET_FIELDS and v is not None else
+            _redact(v)
+            for k, v in value.items()
+        }
+    if isinstance(value, list):
+        return [_redact(v) for v in value]
+    return value
+
+
+def _send_request(sock: Any,
+                  request: Any,
+                  trace_hook: Callable[[TraceEvent], None] = None):
+    start = time.monotonic()
+    if isinstance(sock, _MultiplexedChannel):
+        response = sock.send_request(request)
+    else:
+        _write_message(sock, request)
+        response = _read_message(sock)
+    if trace_hook is not None:
+        serialized_request = json.loads(
+            json.dumps(request, default=lambda o: o.__dict__))
+        trace_hook(
+            TraceEvent(request.request,
+                       json.dumps(_redact(serialized_request)),
+                       json.dumps(_redact(response)),
+                       time.monotonic() - start))
+    return _check_response(response)
+
+
+def _verify_task_key_offer(task_uuid: bytes, public_key: bytes, cert: bytes,
+                           signature: bytes):
+    cert = x509.load_der_x509_certificate(cert, default_backend())
+    cert_public_key = cert.public_key()
+    message = _TASK_KEY_OFFER_CONTEXT + task_uuid + public_key
+    cert_public_key.verify(signature, message, ec.ECDSA(hashes.SHA256()))
+
+    if os.environ.get('SGX_MODE') == 'SW':
+        return
+
+    # The key signing the offer must be the one in the report data.
+    ext = json.loads(cert.extensions[0].value.value)
+    report = json.loads(bytes(ext["report"]))
+    quote = base64.b64decode(report['isvEnclaveQuoteBody'])
+    report_data = quote[368:368 + 64]
+    cert_public_key = cert_public_key.public_bytes(
+        Encoding.X962, PublicFormat.UncompressedPoint)
+    if report_data != cert_public_key[1:]:
+        raise Exception("report data error")
+
+
+def _wrap_file_crypto(task_uuid: bytes, fname: str, shared_secret: bytes,
+                      crypto: CryptoInfo) -> bytes:
+    if crypto.schema == "raw":
+        file_crypto = "Raw"
+    elif crypto.schema == "teaclave-file-128":
+        file_crypto = {"TeaclaveFile128": {"key": crypto.key}}
+    else:
+        file_crypto = {
+            _FILE_CRYPTO_NAMES[crypto.schema]: {
+                "key": crypto.key,
+                "iv": crypto.iv
+            }
+        }
+    okm = HKDF(algorithm=hashes.SHA256(),
+               length=44,
+               salt=task_uuid,
+               info=fname.encode(),
+               backend=default_backend()).derive(shared_secret)
+    return AESGCM(okm[:32]).encrypt(okm[32:],
+                                    json.dumps(file_crypto).encode(),
+                                    bytes(8))
+
+
 def _verify_report(as_root_ca_cert_path: str, enclave_info_path: str,
                    cert: Dict[str, Any], endpoint_name: str):
     if os.environ.get('SGX_MODE') == 'SW':
@@ -0,0 +1,211 @@
+#!/usr/bin/env python3
+
+# Licensed to the Apache Software Foundation (ASF) under one
+# or more contributor license agreements.  See the NOTICE file
+# distributed with this work for additional information
+# regarding copyright ownership.  The ASF licenses this file
+# to you under the Apache License, Version 2.0 (the
+# "License"); you may not use this file except in compliance
+# with the License.  You may obtain a copy of the License at
+#
+#   http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing,
+# software distributed under the License is distributed on an
+# "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+# KIND, either express or implied.  See the License for the
+# specific language governing permissions and limitations
+# under the License.
+"""
+Helpers of the Teaclave Python SDK for pandas DataFrames: serialize a
+DataFrame, encrypt, upload and register it as an input file, and register an
+output file of a task and load it back into a DataFrame. Inputs are encrypted
+with AES-GCM, outputs written by the platform in the teaclave-file-128 format
+are decrypted with the command line tool (teaclave_cli).
+"""
+
+import io
+import os
+import subprocess
+import tempfile
+
+from typing import List
+
+import pandas as pd
+import requests
+
+from cryptography.hazmat.primitives.ciphers.aead import AESGCM
+
+from teaclave import CredentialInfo, FrontendClient, verify_output
+
+__all__ = [
+    'EncryptedFile', 'upload_dataframe', 'register_output', 'load_dataframe'
+]
+
+_KEY_LENGTHS = {"aes-gcm-128": 16, "aes-gcm-256": 32}
+_TEACLAVE_FILE_128 = "teaclave-file-128"
+_TEACLAVE_FILE_128_KEY_LENGTH = 16
+_IV_LENGTH = 12
+_TAG_LENGTH = 16
+# Teaclave seals AES-GCM files with 8 zero bytes as the additional data.
+_AAD = bytes(8)
+
+
+class EncryptedFile:
+    """Registered file and the key to decrypt it, which is never returned by
+    the platform and has to be kept by the owner.
+
+    Args:
+        data_id: ID of the registered file.
+        url: URL of the file.
+        schema: Encryption algorithm of the file.
+        key: Key for encryption and decryption, bytes in list.
+        iv: IV, bytes in list.
+        cmac: Authentication tag of an input file, bytes in list.
+    """
+    def __init__(self,
+                 data_id: str,
+                 url: str,
+                 schema: str,
+                 key: List[int],
+                 iv: List[int],
+                 cmac: List[int] = None):
+        self.data_id = data_id
+        self.url = url
+        self.schema = schema
+        self.key = key
+        self.iv = iv
+        self.cmac = cmac
+
+
+def upload_dataframe(client: FrontendClient,
+                     df: pd.DataFrame,
+                     url: str,
+                     upload_url: str = None,
+                     file_format: str = "csv",
+                     schema: str = "aes-gcm-128") -> EncryptedFile:
+    """Serialize a DataFrame, encrypt it with a fresh key, upload it with an
+    HTTP PUT request and register it as an input file.
+
+    Args:
+        client: Frontend client of the data owner.
+        df: DataFrame to upload. CSV files are written without the index and
+            the header, which is the layout expected by the built-in functions.
+        url: URL the platform downloads the file from.
+        upload_url: URL to upload the file to if different from url, e.g., a
+            pre-signed URL of an object storage.
+        file_format: "csv" or "parquet".
+        schema: Encryption algorithm, "aes-gcm-128" or "aes-gcm-256".
+
+    Returns:
+        EncryptedFile: The registered file and its key.
+    """
+    if schema not in _KEY_LENGTHS:
+        raise ValueError("Unsupported schema: {}".format(schema))
+    if file_format == "csv":
+        content = df.to_csv(index=False, header=False).encode()
+    elif file_format == "parquet":
+        buffer = io.BytesIO()
+        df.to_parquet(buffer, index=False)
+        content = buffer.getvalue()
+    else:
+        raise ValueError("Unsupported file format: {}".format(file_format))
+
+    key = list(os.urandom(_KEY_LENGTHS[schema]))
+    iv = list(os.urandom(_IV_LENGTH))
+    encrypted = AESGCM(bytes(key)).encrypt(bytes(iv), content, _AAD)
+    cmac = list(encrypted[-_TAG_LENGTH:])
+
+    response = requests.put(upload_url or url, data=encrypted)
+    response.raise_for_status()
+
+    data_id = client.register_input_file(url, schema, key, iv, cmac)
+    return EncryptedFile(data_id, url, schema, key, iv, cmac)
+
+
+def register_output(client: FrontendClient,
+                    url: str,
+                    credential: CredentialInfo = None) -> EncryptedFile:
+    """Register an output file encrypted with a fresh teaclave-file-128 key.
+
+    Args:
+        client: Frontend client of the data owner.
+        url: URL the platform uploads the file to.
+        credential: Credential for uploading the file if required.
+
+    Returns:
+        EncryptedFile: The registered file and its key.
+    """
+    key = list(os.urandom(_TEACLAVE_FILE_128_KEY_LENGTH))
+    data_id = client.register_output_file(url, _TEACLAVE_FILE_128, key, [],
+                                          credential)
+    return EncryptedFile(data_id, url, _TEACLAVE_FILE_128, key, [])
+
+
+def load_dataframe(file: EncryptedFile,
+                   cmac: List[int] = None,
+                   download_url: str = None,
+                   file_format: str = "csv",
+                   header: bool = False,
+                   cli_path: str = "teaclave_cli") -> pd.DataFrame:
+    """Download an encrypted file, e.g., an output of a task, decrypt it and
+    load it into a DataFrame.
+
+    Args:
+        file: The registered file and its key.
+        cmac: Expected authentication tag, e.g., the tag of the output in the
+            task result got with FrontendClient.get_output_cmac_by_tag.
+            Defaults to the tag of an input file.
+        download_url: URL or local path to read the file from if different
+            from the registered URL.
+        file_format: "csv" or "parquet".
+        header: Whether the first row of the CSV file is the header.
+        cli_path: Path of teaclave_cli for decrypting teaclave-file-128 files.
+
+    Returns:
+        pd.DataFrame: Content of the file.
+    """
+    url = download_url or file.url
+    cmac = cmac if cmac is not None else file.cmac
+    if url.startswith("http://") or url.startswith("https://"):
+        response = requests.get(url)
+        response.raise_for_status()
+        encrypted = response.content
+    else:
+        with open(url, "rb") as f:
+            encrypted = f.read()
+
+    if file.schema == _TEACLAVE_FILE_128:
+        content = _decrypt_teaclave_file(encrypted, file.key, cmac, cli_path)
+    elif file.schema in _KEY_LENGTHS:
+        if cmac is not None and encrypted[-_TAG_LENGTH:] != bytes(cmac):
+            raise ValueError("CMAC of the file does not match")
+        content = AESGCM(bytes(file.key)).decrypt(bytes(file.iv), encrypted,
+                                                  _AAD)
+    else:
+        raise ValueError("Unsupported schema: {}".format(file.schema))
+
+    if file_format == "csv":
+        return pd.read_csv(io.BytesIO(content), header=0 if header else None)
+    if file_format == "parquet":
+        return pd.read_parquet(io.BytesIO(content))
+    raise ValueError("Unsupported file format: {}".format(file_format))
+
+
+def _decrypt_teaclave_file(encrypted: bytes, key: List[int], cmac: List[int],
+                           cli_path: str) -> bytes:
+    with tempfile.TemporaryDirectory() as directory:
+        input_path = os.path.join(directory, "input.enc")
+        output_path = os.path.join(directory, "output")
+        with open(input_path, "wb") as f:
+            f.write(encrypted)
+        if cmac is not None and not verify_output(input_path, cmac, key):
+            raise ValueError("CMAC of the file does not match")
+        subprocess.run([
+            cli_path, "decrypt", "--algorithm", _TEACLAVE_FILE_128, "--key",
+            bytes(key).hex(), "--input-file", input_path, "--output-file",
+            output_path
+        ],
+                       check=True)
+        with open(output_path, "rb") as f:
+            return f.read()
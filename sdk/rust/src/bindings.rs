@@ -15,6 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use anyhow::Result;
 use libc::size_t;
 use std::ffi::CStr;
 use std::ffi::CString;
@@ -133,6 +134,26 @@ pub extern "C" fn teaclave_user_register(
     0
 }
 
+/// Confirm the registration of `user_id` with the `code` sent to the contact
+/// of the user. The function returns 0 for success. On error, the function
+/// returns 1.
+#[no_mangle]
+pub extern "C" fn teaclave_confirm_registration(
+    client: &mut AuthenticationClient,
+    user_id: *const c_char,
+    code: *const c_char,
+) -> c_int {
+    if (client as *mut AuthenticationClient).is_null() || user_id.is_null() || code.is_null() {
+        return 1;
+    }
+
+    let user_id = unsafe { CStr::from_ptr(user_id).to_string_lossy().into_owned() };
+    let code = unsafe { CStr::from_ptr(code).to_string_lossy().into_owned() };
+    unwrap_or_return_one!(client.confirm_registration(&user_id, &code));
+
+    0
+}
+
 /// Login a new user with `user_id` and `user_password`. The login session token
 /// will be save in the `token` buffer, and length will be set in the
 /// `token_len` argument. The function returns 0 for success. On error, the
@@ -195,6 +216,39 @@ pub extern "C" fn teaclave_connect_frontend_service(
     address: *const c_char,
     enclave_info_path: *const c_char,
     as_root_ca_cert_path: *const c_char,
+) -> *mut FrontendClient {
+    connect_frontend_service(
+        address,
+        enclave_info_path,
+        as_root_ca_cert_path,
+        FrontendService::connect,
+    )
+}
+
+/// Connect to Teaclave Frontend Service with a connection shared by the
+/// handle and its clones made with `teaclave_clone_frontend_client`. Requests
+/// of the handles are in flight at the same time, e.g., when they are used by
+/// different threads. The arguments and the return value are the same as
+/// `teaclave_connect_frontend_service`.
+#[no_mangle]
+pub extern "C" fn teaclave_connect_frontend_service_multiplexed(
+    address: *const c_char,
+    enclave_info_path: *const c_char,
+    as_root_ca_cert_path: *const c_char,
+) -> *mut FrontendClient {
+    connect_frontend_service(
+        address,
+        enclave_info_path,
+        as_root_ca_cert_path,
+        FrontendService::connect_multiplexed,
+    )
+}
+
+fn connect_frontend_service(
+    address: *const c_char,
+    enclave_info_path: *const c_char,
+    as_root_ca_cert_path: *const c_char,
+    connect: fn(&str, &EnclaveInfo, &[u8]) -> Result<FrontendClient>,
 ) -> *mut FrontendClient {
     if address.is_null() || enclave_info_path.is_null() || as_root_ca_cert_path.is_null() {
         return ptr::null_mut();
@@ -214,11 +268,25 @@ pub extern "C" fn teaclave_connect_frontend_service(
     let enclave_info = unwrap_or_return_null!(EnclaveInfo::from_file(enclave_info_path));
     let bytes = unwrap_or_return_null!(fs::read(as_root_ca_cert_path));
     let as_root_ca_cert = unwrap_or_return_null!(pem::parse(bytes)).contents;
-    let client = unwrap_or_return_null!(FrontendService::connect(
-        &address,
-        &enclave_info,
-        &as_root_ca_cert
-    ));
+    let client = unwrap_or_return_null!(connect(&address, &enclave_info, &as_root_ca_cert));
+
+    Box::into_raw(Box::new(client))
+}
+
+/// Clone a frontend service handle connected with
+/// `teaclave_connect_frontend_service_multiplexed`, sharing its connection and
+/// credential. The clone is closed with `teaclave_close_frontend_service` as
+/// well. On error, the function returns NULL.
+#[no_mangle]
+pub extern "C" fn teaclave_clone_frontend_client(
+    client: *const FrontendClient,
+) -> *mut FrontendClient {
+    if client.is_null() {
+        return ptr::null_mut();
+    }
+
+    let client = unsafe { &*client };
+    let client = unwrap_or_return_null!(client.try_clone());
 
     Box::into_raw(Box::new(client))
 }
@@ -367,11 +435,21 @@ generate_function_serialized!(
     teaclave_user_register_serialized,
     user_register_serialized
 );
+generate_function_serialized!(
+    AuthenticationClient,
+    teaclave_confirm_registration_serialized,
+    confirm_registration_serialized
+);
 generate_function_serialized!(
     AuthenticationClient,
     teaclave_user_login_serialized,
     user_login_serialized
 );
+generate_function_serialized!(
+    AuthenticationClient,
+    teaclave_mint_scoped_token_serialized,
+    mint_scoped_token_serialized
+);
 generate_function_serialized!(
     FrontendClient,
     teaclave_register_function_serialized,
@@ -387,11 +465,21 @@ generate_function_serialized!(
     teaclave_register_input_file_serialized,
     register_input_file_serialized
 );
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_rotate_input_file_key_serialized,
+    rotate_input_file_key_serialized
+);
 generate_function_serialized!(
     FrontendClient,
     teaclave_register_output_file_serialized,
     register_output_file_serialized
 );
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_update_output_url_serialized,
+    update_output_url_serialized
+);
 generate_function_serialized!(
     FrontendClient,
     teaclave_create_task_serialized,
@@ -417,3 +505,188 @@ generate_function_serialized!(
     teaclave_get_task_serialized,
     get_task_serialized
 );
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_list_tasks_serialized,
+    list_tasks_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_list_pending_approvals_serialized,
+    list_pending_approvals_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_list_functions_serialized,
+    list_functions_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_list_files_serialized,
+    list_files_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_delete_input_file_serialized,
+    delete_input_file_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_delete_output_file_serialized,
+    delete_output_file_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_get_task_manifest_serialized,
+    get_task_manifest_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_get_task_return_value_serialized,
+    get_task_return_value_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_get_task_key_offer_serialized,
+    get_task_key_offer_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_put_task_keys_serialized,
+    put_task_keys_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_get_data_lineage_serialized,
+    get_data_lineage_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_register_approval_policy_serialized,
+    register_approval_policy_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_revoke_approval_policy_serialized,
+    revoke_approval_policy_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_register_model_serialized,
+    register_model_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_get_model_serialized,
+    get_model_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_create_task_share_link_serialized,
+    create_task_share_link_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_get_shared_task_serialized,
+    get_shared_task_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_archive_task_serialized,
+    archive_task_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_restore_archived_task_serialized,
+    restore_archived_task_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_export_storage_snapshot_serialized,
+    export_storage_snapshot_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_restore_storage_snapshot_serialized,
+    restore_storage_snapshot_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_promote_storage_replica_serialized,
+    promote_storage_replica_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_cancel_task_serialized,
+    cancel_task_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_get_task_upload_slots_serialized,
+    get_task_upload_slots_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_reject_task_serialized,
+    reject_task_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_get_activity_feed_serialized,
+    get_activity_feed_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_register_task_template_serialized,
+    register_task_template_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_create_task_from_template_serialized,
+    create_task_from_template_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_create_tasks_batch_serialized,
+    create_tasks_batch_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_test_function_serialized,
+    test_function_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_register_task_schedule_serialized,
+    register_task_schedule_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_approve_task_schedule_serialized,
+    approve_task_schedule_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_cancel_task_schedule_serialized,
+    cancel_task_schedule_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_get_task_schedule_serialized,
+    get_task_schedule_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_estimate_task_serialized,
+    estimate_task_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_get_platform_info_serialized,
+    get_platform_info_serialized
+);
+generate_function_serialized!(
+    FrontendClient,
+    teaclave_register_signing_key_serialized,
+    register_signing_key_serialized
+);
@@ -15,9 +15,11 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use anyhow::Result;
+use anyhow::{bail, ensure, Result};
 use std::collections::HashMap;
 use std::convert::TryInto;
+use std::path::Path;
+use std::sync::Arc;
 use teaclave_attestation::verifier;
 use teaclave_proto::teaclave_authentication_service::TeaclaveAuthenticationApiClient;
 use teaclave_proto::teaclave_authentication_service_proto as authentication_proto;
@@ -25,25 +27,62 @@ use teaclave_proto::teaclave_frontend_service::TeaclaveFrontendClient;
 use teaclave_proto::teaclave_frontend_service_proto as frontend_proto;
 use teaclave_rpc::config::SgxTrustedTlsClientConfig;
 use teaclave_rpc::endpoint::Endpoint;
-use teaclave_types::FileAuthTag;
 use url::Url;
+use uuid::Uuid;
 
 pub use teaclave_proto::teaclave_authentication_service::{
-    UserLoginRequest, UserLoginResponse, UserRegisterRequest, UserRegisterResponse,
+    ConfirmRegistrationRequest, ConfirmRegistrationResponse, MintScopedTokenRequest,
+    MintScopedTokenResponse, UserLoginRequest, UserLoginResponse, UserRegisterRequest,
+    UserRegisterResponse,
 };
 pub use teaclave_proto::teaclave_frontend_service::GetFunctionResponse as Function;
 pub use teaclave_proto::teaclave_frontend_service::{
-    ApproveTaskRequest, ApproveTaskResponse, AssignDataRequest, AssignDataResponse,
-    CreateTaskRequest, CreateTaskResponse, GetFunctionRequest, GetFunctionResponse, GetTaskRequest,
-    GetTaskResponse, InvokeTaskRequest, InvokeTaskResponse, RegisterFunctionRequest,
-    RegisterFunctionResponse, RegisterInputFileRequest, RegisterInputFileResponse,
-    RegisterOutputFileRequest, RegisterOutputFileResponse,
+    ApproveTaskRequest, ApproveTaskResponse, ApproveTaskScheduleRequest,
+    ApproveTaskScheduleResponse, ArchiveTaskRequest, ArchiveTaskResponse, AssignDataRequest,
+    AssignDataResponse, CancelTaskRequest, CancelTaskResponse, CancelTaskScheduleRequest,
+    CancelTaskScheduleResponse, CreateTaskFromTemplateRequest, CreateTaskFromTemplateResponse,
+    CreateTaskRequest, CreateTaskResponse, CreateTaskShareLinkRequest, CreateTaskShareLinkResponse,
+    CreateTasksBatchRequest, CreateTasksBatchResponse, DeleteInputFileRequest,
+    DeleteInputFileResponse, DeleteOutputFileRequest, DeleteOutputFileResponse,
+    EstimateTaskRequest, EstimateTaskResponse, ExportStorageSnapshotRequest,
+    ExportStorageSnapshotResponse, GetActivityFeedRequest, GetActivityFeedResponse,
+    GetDataLineageRequest, GetDataLineageResponse, GetFunctionRequest, GetFunctionResponse,
+    GetModelRequest, GetModelResponse, GetPlatformInfoRequest, GetPlatformInfoResponse,
+    GetSharedTaskRequest, GetSharedTaskResponse, GetTaskKeyOfferRequest, GetTaskKeyOfferResponse,
+    GetTaskManifestRequest, GetTaskManifestResponse, GetTaskRequest, GetTaskResponse,
+    GetTaskResultStreamRequest, GetTaskResultStreamResponse, GetTaskReturnValueRequest,
+    GetTaskReturnValueResponse, GetTaskScheduleRequest, GetTaskScheduleResponse,
+    GetTaskUploadSlotsRequest, GetTaskUploadSlotsResponse, InvokeTaskRequest, InvokeTaskResponse,
+    ListFilesRequest, ListFilesResponse, ListFunctionsRequest, ListFunctionsResponse,
+    ListPendingApprovalsRequest, ListPendingApprovalsResponse, ListTasksRequest, ListTasksResponse,
+    PageRequest, PageResponse, PromoteStorageReplicaRequest, PromoteStorageReplicaResponse,
+    PutTaskKeysRequest, PutTaskKeysResponse, RegisterApprovalPolicyRequest,
+    RegisterApprovalPolicyResponse, RegisterFunctionRequest, RegisterFunctionResponse,
+    RegisterInputFileRequest, RegisterInputFileResponse, RegisterModelRequest,
+    RegisterModelResponse, RegisterOutputFileRequest, RegisterOutputFileResponse,
+    RegisterSigningKeyRequest, RegisterSigningKeyResponse, RegisterTaskScheduleRequest,
+    RegisterTaskScheduleResponse, RegisterTaskTemplateRequest, RegisterTaskTemplateResponse,
+    RejectTaskRequest, RejectTaskResponse, RestoreArchivedTaskRequest, RestoreArchivedTaskResponse,
+    RestoreStorageSnapshotRequest, RestoreStorageSnapshotResponse, RevokeApprovalPolicyRequest,
+    RevokeApprovalPolicyResponse, RotateInputFileKeyRequest, RotateInputFileKeyResponse,
+    TestFunctionRequest, TestFunctionResponse, UpdateOutputUrlRequest, UpdateOutputUrlResponse,
 };
+pub use teaclave_rpc::config::SgxTrustedTlsSessionCache as SessionCache;
+pub use teaclave_rpc::trace::TraceEvent;
 pub use teaclave_types::{
-    EnclaveInfo, Executor, FileCrypto, FunctionInput, FunctionOutput, TaskResult,
+    ActivityEvent, ActivityKind, EnclaveBuildInfo, EnclaveInfo, Executor, FileAuthTag,
+    FileCredential, FileCrypto, FunctionCapabilities, FunctionInput, FunctionOutput,
+    KeyBrokerReference, LineageStep, ModelReference, ModelVersion, PostProcessingStep,
+    RequestSignature, ResourceProfile, ScheduleSpec, SignedAction, StorageSnapshot, TaskFilter,
+    TaskKeyOffer, TaskManifest, TaskPriority, TaskProgress, TaskResult, TaskStatus, TokenScope,
+    UserID, WrappedTaskKeys,
 };
 
 pub mod bindings;
+mod signer;
+
+pub use rustls::SignatureScheme;
+pub use signer::{derive_file_crypto, ClientKey, ClientSigner, DataKeyDeriver, KeyHelper};
 
 pub struct AuthenticationClient {
     api_client: TeaclaveAuthenticationApiClient,
@@ -56,6 +95,16 @@ impl AuthenticationClient {
         Self { api_client }
     }
 
+    /// Calls the hook with every request and response of the client, with
+    /// secrets redacted, e.g., for debugging.
+    pub fn set_trace_hook(&mut self, hook: impl Fn(&TraceEvent) + Send + Sync + 'static) {
+        self.api_client.set_trace_hook(Some(Arc::new(hook)));
+    }
+
+    pub fn clear_trace_hook(&mut self) {
+        self.api_client.set_trace_hook(None);
+    }
+
     pub fn user_register_with_request(
         &mut self,
         request: UserRegisterRequest,
@@ -82,6 +131,47 @@ impl AuthenticationClient {
         Ok(())
     }
 
+    /// Registers a user to be confirmed with the code sent to the contact,
+    /// e.g., an email address, if the service verifies the registrations.
+    pub fn user_register_with_contact(
+        &mut self,
+        user_id: &str,
+        user_password: &str,
+        contact: &str,
+    ) -> Result<()> {
+        let request = UserRegisterRequest::new(user_id, user_password).contact(contact);
+        let _response = self.user_register_with_request(request)?;
+
+        Ok(())
+    }
+
+    pub fn confirm_registration_with_request(
+        &mut self,
+        request: ConfirmRegistrationRequest,
+    ) -> Result<ConfirmRegistrationResponse> {
+        let response = self.api_client.confirm_registration(request)?;
+
+        Ok(response)
+    }
+
+    pub fn confirm_registration_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: authentication_proto::ConfirmRegistrationRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: authentication_proto::ConfirmRegistrationResponse = self
+            .confirm_registration_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    pub fn confirm_registration(&mut self, user_id: &str, code: &str) -> Result<()> {
+        let request = ConfirmRegistrationRequest::new(user_id, code);
+        let _response = self.confirm_registration_with_request(request)?;
+
+        Ok(())
+    }
+
     pub fn user_login_with_request(
         &mut self,
         request: UserLoginRequest,
@@ -107,6 +197,47 @@ impl AuthenticationClient {
 
         Ok(response.token)
     }
+
+    pub fn mint_scoped_token_with_request(
+        &mut self,
+        request: MintScopedTokenRequest,
+    ) -> Result<MintScopedTokenResponse> {
+        let response = self.api_client.mint_scoped_token(request)?;
+
+        Ok(response)
+    }
+
+    pub fn mint_scoped_token_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: authentication_proto::MintScopedTokenRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: authentication_proto::MintScopedTokenResponse = self
+            .mint_scoped_token_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Mints a token restricted to the operations (e.g., `approve_task`) and
+    /// resources (e.g., the ID of a task) from a login token of the user. The
+    /// token expires in `expires_in` seconds or with the login token.
+    pub fn mint_scoped_token(
+        &mut self,
+        user_id: &str,
+        user_token: &str,
+        operations: &[&str],
+        resources: &[&str],
+        expires_in: u64,
+    ) -> Result<String> {
+        let scope = TokenScope::new(
+            operations.iter().map(|o| o.to_string()).collect(),
+            resources.iter().map(|r| r.to_string()).collect(),
+        );
+        let request = MintScopedTokenRequest::new(user_id, user_token, scope, expires_in);
+        let response = self.mint_scoped_token_with_request(request)?;
+
+        Ok(response.token)
+    }
 }
 
 impl AuthenticationService {
@@ -115,18 +246,58 @@ impl AuthenticationService {
         enclave_info: &EnclaveInfo,
         as_root_ca_cert: &[u8],
     ) -> Result<AuthenticationClient> {
+        let config = Self::client_config(enclave_info, as_root_ca_cert);
+        let channel = Endpoint::new(url).config(config).connect()?;
+        let client = TeaclaveAuthenticationApiClient::new(channel)?;
+
+        Ok(AuthenticationClient::new(client))
+    }
+
+    /// Connects to the service, resuming a TLS session in `session_cache`
+    /// which was established with the same verified enclave if possible.
+    pub fn connect_with_session_cache(
+        url: &str,
+        enclave_info: &EnclaveInfo,
+        as_root_ca_cert: &[u8],
+        session_cache: &SessionCache,
+    ) -> Result<AuthenticationClient> {
+        let config =
+            Self::client_config(enclave_info, as_root_ca_cert).session_cache(session_cache)?;
+        let channel = Endpoint::new(url).config(config).connect()?;
+        let client = TeaclaveAuthenticationApiClient::new(channel)?;
+
+        Ok(AuthenticationClient::new(client))
+    }
+
+    /// Connects to the service presenting the client certificate of
+    /// `client_key`, whose private key is used through its signer, e.g., in a
+    /// hardware token.
+    pub fn connect_with_client_key(
+        url: &str,
+        enclave_info: &EnclaveInfo,
+        as_root_ca_cert: &[u8],
+        client_key: &ClientKey,
+    ) -> Result<AuthenticationClient> {
+        let config = Self::client_config(enclave_info, as_root_ca_cert)
+            .client_cert_resolver(client_key.cert_resolver());
+        let channel = Endpoint::new(url).config(config).connect()?;
+        let client = TeaclaveAuthenticationApiClient::new(channel)?;
+
+        Ok(AuthenticationClient::new(client))
+    }
+
+    fn client_config(
+        enclave_info: &EnclaveInfo,
+        as_root_ca_cert: &[u8],
+    ) -> SgxTrustedTlsClientConfig {
         let enclave_attr = enclave_info
             .get_enclave_attr("teaclave_authentication_service")
             .expect("enclave attr");
-        let config = SgxTrustedTlsClientConfig::new().attestation_report_verifier(
+        SgxTrustedTlsClientConfig::new().attestation_report_verifier(
             vec![enclave_attr],
             as_root_ca_cert,
             verifier::universal_quote_verifier,
-        );
-        let channel = Endpoint::new(url).config(config).connect()?;
-        let client = TeaclaveAuthenticationApiClient::new(channel)?;
-
-        Ok(AuthenticationClient::new(client))
+        )
     }
 }
 
@@ -139,18 +310,75 @@ impl FrontendService {
         enclave_info: &EnclaveInfo,
         as_root_ca_cert: &[u8],
     ) -> Result<FrontendClient> {
+        let config = Self::client_config(enclave_info, as_root_ca_cert);
+        let channel = Endpoint::new(url).config(config).connect()?;
+        let client = TeaclaveFrontendClient::new(channel)?;
+
+        Ok(FrontendClient::new(client))
+    }
+
+    /// Connects to the service with a connection shared by the client and its
+    /// clones made with `FrontendClient::try_clone`, whose requests are in
+    /// flight at the same time, e.g., for clients orchestrating many tasks
+    /// from several threads. Task results cannot be streamed on the
+    /// connection.
+    pub fn connect_multiplexed(
+        url: &str,
+        enclave_info: &EnclaveInfo,
+        as_root_ca_cert: &[u8],
+    ) -> Result<FrontendClient> {
+        let config = Self::client_config(enclave_info, as_root_ca_cert);
+        let channel = Endpoint::new(url).config(config).connect_multiplexed()?;
+        let client = TeaclaveFrontendClient::new(channel)?;
+
+        Ok(FrontendClient::new(client))
+    }
+
+    /// Connects to the service, resuming a TLS session in `session_cache`
+    /// which was established with the same verified enclave if possible.
+    pub fn connect_with_session_cache(
+        url: &str,
+        enclave_info: &EnclaveInfo,
+        as_root_ca_cert: &[u8],
+        session_cache: &SessionCache,
+    ) -> Result<FrontendClient> {
+        let config =
+            Self::client_config(enclave_info, as_root_ca_cert).session_cache(session_cache)?;
+        let channel = Endpoint::new(url).config(config).connect()?;
+        let client = TeaclaveFrontendClient::new(channel)?;
+
+        Ok(FrontendClient::new(client))
+    }
+
+    /// Connects to the service presenting the client certificate of
+    /// `client_key`, whose private key is used through its signer, e.g., in a
+    /// hardware token.
+    pub fn connect_with_client_key(
+        url: &str,
+        enclave_info: &EnclaveInfo,
+        as_root_ca_cert: &[u8],
+        client_key: &ClientKey,
+    ) -> Result<FrontendClient> {
+        let config = Self::client_config(enclave_info, as_root_ca_cert)
+            .client_cert_resolver(client_key.cert_resolver());
+        let channel = Endpoint::new(url).config(config).connect()?;
+        let client = TeaclaveFrontendClient::new(channel)?;
+
+        Ok(FrontendClient::new(client))
+    }
+
+    fn client_config(
+        enclave_info: &EnclaveInfo,
+        as_root_ca_cert: &[u8],
+    ) -> SgxTrustedTlsClientConfig {
         let enclave_attr = enclave_info
             .get_enclave_attr("teaclave_frontend_service")
             .expect("enclave attr");
-        let config = SgxTrustedTlsClientConfig::new().attestation_report_verifier(
+        SgxTrustedTlsClientConfig::new().attestation_report_verifier(
             vec![enclave_attr],
             as_root_ca_cert,
             verifier::universal_quote_verifier,
-        );
-        let channel = Endpoint::new(url).config(config).connect()?;
-        let client = TeaclaveFrontendClient::new(channel)?;
-
-        Ok(FrontendClient::new(client))
+        )
     }
 }
 
@@ -163,6 +391,23 @@ impl FrontendClient {
         Self { api_client }
     }
 
+    /// Another client with the same credential on the connection of a client
+    /// connected with `FrontendService::connect_multiplexed`.
+    pub fn try_clone(&self) -> Result<Self> {
+        let api_client = self.api_client.try_clone()?;
+        Ok(Self { api_client })
+    }
+
+    /// Calls the hook with every request and response of the client, with
+    /// secrets redacted, e.g., for debugging.
+    pub fn set_trace_hook(&mut self, hook: impl Fn(&TraceEvent) + Send + Sync + 'static) {
+        self.api_client.set_trace_hook(Some(Arc::new(hook)));
+    }
+
+    pub fn clear_trace_hook(&mut self) {
+        self.api_client.set_trace_hook(None);
+    }
+
     pub fn set_credential(&mut self, id: &str, token: &str) {
         let mut metadata = HashMap::new();
         metadata.insert("id".to_string(), id.to_string());
@@ -282,6 +527,65 @@ impl FrontendClient {
         Ok(response.data_id.to_string())
     }
 
+    /// Registers an input file whose key is released by the key broker to the
+    /// execution enclaves only.
+    pub fn register_brokered_input_file(
+        &mut self,
+        url: &str,
+        cmac: &[u8],
+        key_broker: KeyBrokerReference,
+    ) -> Result<String> {
+        let url = Url::parse(url)?;
+        let cmac = FileAuthTag::from_bytes(cmac)?;
+        let request = RegisterInputFileRequest::with_key_broker(url, cmac, key_broker);
+        let response = self.register_input_file_with_request(request)?;
+
+        Ok(response.data_id.to_string())
+    }
+
+    pub fn rotate_input_file_key_with_request(
+        &mut self,
+        request: RotateInputFileKeyRequest,
+    ) -> Result<RotateInputFileKeyResponse> {
+        let response = self.api_client.rotate_input_file_key(request)?;
+
+        Ok(response)
+    }
+
+    pub fn rotate_input_file_key_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::RotateInputFileKeyRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::RotateInputFileKeyResponse = self
+            .rotate_input_file_key_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Replaces the key of an input file with the one of the file re-encrypted
+    /// and uploaded to a new url. Returns the unfinished tasks still reading
+    /// the file at its old url, which should be kept until they finish.
+    pub fn rotate_input_file_key(
+        &mut self,
+        data_id: &str,
+        url: &str,
+        cmac: &[u8],
+        file_crypto: FileCrypto,
+    ) -> Result<Vec<String>> {
+        let data_id = data_id.try_into()?;
+        let url = Url::parse(url)?;
+        let cmac = FileAuthTag::from_bytes(cmac)?;
+        let request = RotateInputFileKeyRequest::new(data_id, url, cmac, file_crypto);
+        let response = self.rotate_input_file_key_with_request(request)?;
+
+        Ok(response
+            .in_flight_task_ids
+            .iter()
+            .map(|task_id| task_id.to_string())
+            .collect())
+    }
+
     pub fn register_output_file_with_request(
         &mut self,
         request: RegisterOutputFileRequest,
@@ -310,6 +614,65 @@ impl FrontendClient {
         Ok(response.data_id.to_string())
     }
 
+    pub fn register_output_file_with_credential(
+        &mut self,
+        url: &str,
+        file_crypto: FileCrypto,
+        credential: FileCredential,
+    ) -> Result<String> {
+        let url = Url::parse(url)?;
+        let request = RegisterOutputFileRequest::new(url, file_crypto).credential(credential);
+        let response = self.register_output_file_with_request(request)?;
+
+        Ok(response.data_id.to_string())
+    }
+
+    /// Registers an output file whose URL is set later with `update_output_url`,
+    /// before the task it is assigned to is invoked.
+    pub fn register_deferred_output_file(&mut self, file_crypto: FileCrypto) -> Result<String> {
+        let request = RegisterOutputFileRequest::deferred(file_crypto);
+        let response = self.register_output_file_with_request(request)?;
+
+        Ok(response.data_id.to_string())
+    }
+
+    pub fn update_output_url_with_request(
+        &mut self,
+        request: UpdateOutputUrlRequest,
+    ) -> Result<UpdateOutputUrlResponse> {
+        let response = self.api_client.update_output_url(request)?;
+
+        Ok(response)
+    }
+
+    pub fn update_output_url_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::UpdateOutputUrlRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::UpdateOutputUrlResponse = self
+            .update_output_url_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    pub fn update_output_url(
+        &mut self,
+        data_id: &str,
+        url: &str,
+        credential: Option<FileCredential>,
+    ) -> Result<()> {
+        let data_id = data_id.try_into()?;
+        let url = Url::parse(url)?;
+        let mut request = UpdateOutputUrlRequest::new(data_id, url);
+        if let Some(credential) = credential {
+            request = request.credential(credential);
+        }
+        self.update_output_url_with_request(request)?;
+
+        Ok(())
+    }
+
     pub fn create_task_serialized(&mut self, serialized_request: &str) -> Result<String> {
         let request: frontend_proto::CreateTaskRequest = serde_json::from_str(serialized_request)?;
         let response: frontend_proto::CreateTaskResponse =
@@ -394,10 +757,16 @@ impl FrontendClient {
         outputs: Option<HashMap<String, String>>,
     ) -> Result<()> {
         let mut input_data = HashMap::new();
+        let mut input_models = HashMap::new();
         let mut output_data = HashMap::new();
         if let Some(inputs) = inputs {
             for (k, v) in inputs.iter() {
-                input_data.insert(k.into(), v.clone().try_into()?);
+                // Models are assigned by references, e.g., "model:fraud@7".
+                if ModelReference::is_model_reference(v) {
+                    input_models.insert(k.into(), v.as_str().try_into()?);
+                } else {
+                    input_data.insert(k.into(), v.clone().try_into()?);
+                }
             }
         }
 
@@ -406,7 +775,8 @@ impl FrontendClient {
                 output_data.insert(k.into(), v.clone().try_into()?);
             }
         }
-        let request = AssignDataRequest::new(task_id.try_into()?, input_data, output_data);
+        let request = AssignDataRequest::new(task_id.try_into()?, input_data, output_data)
+            .input_models(input_models);
         let _ = self.assign_data_with_request(request)?;
 
         Ok(())
@@ -477,58 +847,1381 @@ impl FrontendClient {
         Ok(serialized_response)
     }
 
-    pub fn get_task_result(&mut self, task_id: &str) -> Result<Vec<u8>> {
+    pub fn list_tasks_with_request(
+        &mut self,
+        request: ListTasksRequest,
+    ) -> Result<ListTasksResponse> {
+        let response = self.api_client.list_tasks(request)?;
+
+        Ok(response)
+    }
+
+    pub fn list_tasks_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::ListTasksRequest = serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::ListTasksResponse =
+            self.list_tasks_with_request(request.try_into()?)?.into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    pub fn list_tasks(&mut self, labels: Option<HashMap<String, String>>) -> Result<Vec<String>> {
+        self.list_filtered_tasks(labels, TaskFilter::new())
+    }
+
+    pub fn list_filtered_tasks(
+        &mut self,
+        labels: Option<HashMap<String, String>>,
+        filter: TaskFilter,
+    ) -> Result<Vec<String>> {
+        let labels = labels.unwrap_or_default();
+        let mut task_ids = Vec::new();
+        let mut page = PageRequest::new();
         loop {
-            let request = GetTaskRequest::new(task_id.try_into()?);
-            let response = self.get_task_with_request(request)?;
-            if let TaskResult::Ok(task_outputs) = response.result {
-                return Ok(task_outputs.return_value);
+            let request = ListTasksRequest::new()
+                .labels(labels.clone())
+                .filter(filter.clone())
+                .page(page.clone());
+            let response = self.list_tasks_with_request(request)?;
+            task_ids.extend(response.task_ids.iter().map(|id| id.to_string()));
+            match page.next(&response.page) {
+                Some(next_page) => page = next_page,
+                None => return Ok(task_ids),
             }
-            let one_second = std::time::Duration::from_secs(1);
-            std::thread::sleep(one_second);
         }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::convert::TryInto;
-    use std::fs;
-    use teaclave_types::hashmap;
 
-    const ENCLAVE_INFO_PATH: &str = "../../release/services/enclave_info.toml";
-    #[cfg(dcap)]
-    const AS_ROOT_CA_CERT_PATH: &str = "../../keys/dcap_root_ca_cert.pem";
-    #[cfg(not(dcap))]
-    const AS_ROOT_CA_CERT_PATH: &str = "../../keys/ias_root_ca_cert.pem";
-    const USER_ID: &str = "rust_client_sdk_test_user";
-    const USER_PASSWORD: &str = "test_password";
+    pub fn list_pending_approvals_with_request(
+        &mut self,
+        request: ListPendingApprovalsRequest,
+    ) -> Result<ListPendingApprovalsResponse> {
+        let response = self.api_client.list_pending_approvals(request)?;
 
-    #[test]
-    fn test_authentication_service() {
-        let enclave_info = EnclaveInfo::from_file(ENCLAVE_INFO_PATH).unwrap();
-        let bytes = fs::read(AS_ROOT_CA_CERT_PATH).unwrap();
-        let as_root_ca_cert = pem::parse(bytes).unwrap().contents;
-        let mut client =
-            AuthenticationService::connect("localhost:7776", &enclave_info, &as_root_ca_cert)
-                .unwrap();
-        let _ = client.user_register(USER_ID, USER_PASSWORD);
-        client.user_login(USER_ID, USER_PASSWORD).unwrap();
+        Ok(response)
     }
 
-    #[test]
-    fn test_frontend_service() {
-        let enclave_info = EnclaveInfo::from_file(ENCLAVE_INFO_PATH).unwrap();
-        let bytes = fs::read(AS_ROOT_CA_CERT_PATH).unwrap();
-        let as_root_ca_cert = pem::parse(bytes).unwrap().contents;
-        let mut client =
-            AuthenticationService::connect("localhost:7776", &enclave_info, &as_root_ca_cert)
-                .unwrap();
-        let _ = client.user_register(USER_ID, USER_PASSWORD);
-        let token = client.user_login(USER_ID, USER_PASSWORD).unwrap();
+    pub fn list_pending_approvals_serialized(
+        &mut self,
+        serialized_request: &str,
+    ) -> Result<String> {
+        let request: frontend_proto::ListPendingApprovalsRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::ListPendingApprovalsResponse = self
+            .list_pending_approvals_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
 
-        let mut client =
+        Ok(serialized_response)
+    }
+
+    pub fn list_pending_approvals(&mut self) -> Result<Vec<String>> {
+        let mut task_ids = Vec::new();
+        let mut page = PageRequest::new();
+        loop {
+            let request = ListPendingApprovalsRequest::new().page(page.clone());
+            let response = self.list_pending_approvals_with_request(request)?;
+            task_ids.extend(response.task_ids.iter().map(|id| id.to_string()));
+            match page.next(&response.page) {
+                Some(next_page) => page = next_page,
+                None => return Ok(task_ids),
+            }
+        }
+    }
+
+    pub fn list_functions_with_request(
+        &mut self,
+        request: ListFunctionsRequest,
+    ) -> Result<ListFunctionsResponse> {
+        let response = self.api_client.list_functions(request)?;
+
+        Ok(response)
+    }
+
+    pub fn list_functions_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::ListFunctionsRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::ListFunctionsResponse = self
+            .list_functions_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Lists the ids of the functions owned by the user.
+    pub fn list_functions(&mut self) -> Result<Vec<String>> {
+        let mut function_ids = Vec::new();
+        let mut page = PageRequest::new();
+        loop {
+            let request = ListFunctionsRequest::new().page(page.clone());
+            let response = self.list_functions_with_request(request)?;
+            function_ids.extend(response.function_ids.iter().map(|id| id.to_string()));
+            match page.next(&response.page) {
+                Some(next_page) => page = next_page,
+                None => return Ok(function_ids),
+            }
+        }
+    }
+
+    pub fn list_files_with_request(
+        &mut self,
+        request: ListFilesRequest,
+    ) -> Result<ListFilesResponse> {
+        let response = self.api_client.list_files(request)?;
+
+        Ok(response)
+    }
+
+    pub fn list_files_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::ListFilesRequest = serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::ListFilesResponse =
+            self.list_files_with_request(request.try_into()?)?.into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Lists the ids of the input files, or the output files if output is
+    /// set, owned by the user.
+    pub fn list_files(&mut self, output: bool) -> Result<Vec<String>> {
+        let mut data_ids = Vec::new();
+        let mut page = PageRequest::new();
+        loop {
+            let request = if output {
+                ListFilesRequest::output()
+            } else {
+                ListFilesRequest::input()
+            };
+            let response = self.list_files_with_request(request.page(page.clone()))?;
+            data_ids.extend(response.data_ids.iter().map(|id| id.to_string()));
+            match page.next(&response.page) {
+                Some(next_page) => page = next_page,
+                None => return Ok(data_ids),
+            }
+        }
+    }
+
+    pub fn delete_input_file_with_request(
+        &mut self,
+        request: DeleteInputFileRequest,
+    ) -> Result<DeleteInputFileResponse> {
+        let response = self.api_client.delete_input_file(request)?;
+
+        Ok(response)
+    }
+
+    pub fn delete_input_file_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::DeleteInputFileRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::DeleteInputFileResponse = self
+            .delete_input_file_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Deletes an input file of the user which no unfinished task is assigned.
+    /// The file is purged by the storage service after the retention window.
+    pub fn delete_input_file(&mut self, data_id: &str) -> Result<()> {
+        let request = DeleteInputFileRequest::new(data_id.try_into()?);
+        self.delete_input_file_with_request(request)?;
+
+        Ok(())
+    }
+
+    pub fn delete_output_file_with_request(
+        &mut self,
+        request: DeleteOutputFileRequest,
+    ) -> Result<DeleteOutputFileResponse> {
+        let response = self.api_client.delete_output_file(request)?;
+
+        Ok(response)
+    }
+
+    pub fn delete_output_file_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::DeleteOutputFileRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::DeleteOutputFileResponse = self
+            .delete_output_file_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Deletes an output file of the user which no unfinished task is assigned.
+    /// The file is purged by the storage service after the retention window.
+    pub fn delete_output_file(&mut self, data_id: &str) -> Result<()> {
+        let request = DeleteOutputFileRequest::new(data_id.try_into()?);
+        self.delete_output_file_with_request(request)?;
+
+        Ok(())
+    }
+
+    pub fn get_task_result(&mut self, task_id: &str) -> Result<Vec<u8>> {
+        loop {
+            let request = GetTaskRequest::new(task_id.try_into()?);
+            let response = self.get_task_with_request(request)?;
+            match response.result {
+                TaskResult::Ok(task_outputs) => {
+                    if task_outputs.return_value_handle.is_some() {
+                        return self.get_task_return_value(task_id);
+                    }
+                    return Ok(task_outputs.return_value);
+                }
+                TaskResult::Err(failure) => bail!("Task failed: {}", failure.reason),
+                TaskResult::NotReady => (),
+            }
+            let one_second = std::time::Duration::from_secs(1);
+            std::thread::sleep(one_second);
+        }
+    }
+
+    pub fn get_output_cmac(&mut self, task_id: &str, output_name: &str) -> Result<FileAuthTag> {
+        loop {
+            let request = GetTaskRequest::new(task_id.try_into()?);
+            let response = self.get_task_with_request(request)?;
+            match response.result {
+                TaskResult::Ok(task_outputs) => match task_outputs.tags_map.get(output_name) {
+                    Some(tag) => return Ok(*tag),
+                    None => bail!("Output not found: {}", output_name),
+                },
+                TaskResult::Err(failure) => bail!("Task failed: {}", failure.reason),
+                TaskResult::NotReady => (),
+            }
+            let one_second = std::time::Duration::from_secs(1);
+            std::thread::sleep(one_second);
+        }
+    }
+
+    pub fn get_task_return_value_with_request(
+        &mut self,
+        request: GetTaskReturnValueRequest,
+    ) -> Result<GetTaskReturnValueResponse> {
+        let response = self.api_client.get_task_return_value(request)?;
+
+        Ok(response)
+    }
+
+    pub fn get_task_return_value_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::GetTaskReturnValueRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::GetTaskReturnValueResponse = self
+            .get_task_return_value_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Fetches the whole return value of a finished task page by page.
+    pub fn get_task_return_value(&mut self, task_id: &str) -> Result<Vec<u8>> {
+        let mut return_value = Vec::new();
+        let mut offset = 0;
+        loop {
+            let request = GetTaskReturnValueRequest::new(task_id.try_into()?).page(offset, 0);
+            let response = self.get_task_return_value_with_request(request)?;
+            return_value.extend(response.data);
+            match response.next_offset {
+                Some(next_offset) => offset = next_offset,
+                None => return Ok(return_value),
+            }
+        }
+    }
+
+    pub fn get_task_key_offer_with_request(
+        &mut self,
+        request: GetTaskKeyOfferRequest,
+    ) -> Result<GetTaskKeyOfferResponse> {
+        let response = self.api_client.get_task_key_offer(request)?;
+
+        Ok(response)
+    }
+
+    pub fn get_task_key_offer_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::GetTaskKeyOfferRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::GetTaskKeyOfferResponse = self
+            .get_task_key_offer_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    pub fn put_task_keys_with_request(
+        &mut self,
+        request: PutTaskKeysRequest,
+    ) -> Result<PutTaskKeysResponse> {
+        let response = self.api_client.put_task_keys(request)?;
+
+        Ok(response)
+    }
+
+    pub fn put_task_keys_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::PutTaskKeysRequest = serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::PutTaskKeysResponse =
+            self.put_task_keys_with_request(request.try_into()?)?.into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Hands the keys of the files of a running task exchanging keys to the
+    /// execution enclave, after verifying that its offer is signed by an
+    /// attested execution service enclave. The keys are wrapped under the
+    /// secret shared with the enclave, so no other service learns them.
+    pub fn exchange_task_keys(
+        &mut self,
+        task_id: &str,
+        enclave_info: &EnclaveInfo,
+        as_root_ca_cert: &[u8],
+        keys: &HashMap<String, FileCrypto>,
+    ) -> Result<()> {
+        let task_id: teaclave_types::ExternalID = task_id.try_into()?;
+        let request = GetTaskKeyOfferRequest::new(task_id.clone());
+        let offer = self.get_task_key_offer_with_request(request)?.offer;
+
+        let enclave_attr = enclave_info
+            .get_enclave_attr("teaclave_execution_service")
+            .ok_or_else(|| anyhow::anyhow!("Unknown execution service enclave"))?;
+        let verifier = verifier::AttestationReportVerifier::new(
+            vec![enclave_attr],
+            as_root_ca_cert,
+            verifier::universal_quote_verifier,
+        );
+        let message = TaskKeyOffer::message(&task_id.uuid, &offer.public_key);
+        ensure!(
+            verifier.verify_signature(&offer.cert, &message, &offer.signature),
+            "Invalid task key offer"
+        );
+
+        let keys = WrappedTaskKeys::wrap(&task_id.uuid, &offer.public_key, keys)?;
+        let request = PutTaskKeysRequest::new(task_id, keys);
+        self.put_task_keys_with_request(request)?;
+
+        Ok(())
+    }
+
+    pub fn get_task_manifest_with_request(
+        &mut self,
+        request: GetTaskManifestRequest,
+    ) -> Result<GetTaskManifestResponse> {
+        let response = self.api_client.get_task_manifest(request)?;
+
+        Ok(response)
+    }
+
+    pub fn get_task_manifest_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::GetTaskManifestRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::GetTaskManifestResponse = self
+            .get_task_manifest_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    pub fn get_task_manifest(&mut self, task_id: &str) -> Result<TaskManifest> {
+        let request = GetTaskManifestRequest::new(task_id.try_into()?);
+        let response = self.get_task_manifest_with_request(request)?;
+
+        Ok(response.manifest)
+    }
+
+    pub fn get_data_lineage_with_request(
+        &mut self,
+        request: GetDataLineageRequest,
+    ) -> Result<GetDataLineageResponse> {
+        let response = self.api_client.get_data_lineage(request)?;
+
+        Ok(response)
+    }
+
+    pub fn get_data_lineage_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::GetDataLineageRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::GetDataLineageResponse = self
+            .get_data_lineage_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    pub fn get_data_lineage(&mut self, data_id: &str) -> Result<Vec<LineageStep>> {
+        let request = GetDataLineageRequest::new(data_id.try_into()?);
+        let response = self.get_data_lineage_with_request(request)?;
+
+        Ok(response.steps)
+    }
+
+    pub fn register_approval_policy_with_request(
+        &mut self,
+        request: RegisterApprovalPolicyRequest,
+    ) -> Result<RegisterApprovalPolicyResponse> {
+        let response = self.api_client.register_approval_policy(request)?;
+
+        Ok(response)
+    }
+
+    pub fn register_approval_policy_serialized(
+        &mut self,
+        serialized_request: &str,
+    ) -> Result<String> {
+        let request: frontend_proto::RegisterApprovalPolicyRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::RegisterApprovalPolicyResponse = self
+            .register_approval_policy_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    pub fn register_approval_policy(
+        &mut self,
+        data_id: &str,
+        function_ids: &[&str],
+        creators: &[&str],
+    ) -> Result<String> {
+        let function_ids = function_ids
+            .iter()
+            .map(|function_id| (*function_id).try_into())
+            .collect::<Result<Vec<_>>>()?;
+        let request = RegisterApprovalPolicyRequest::new(
+            data_id.try_into()?,
+            function_ids,
+            creators.to_vec(),
+        );
+        let response = self.register_approval_policy_with_request(request)?;
+
+        Ok(response.policy_id.to_string())
+    }
+
+    pub fn revoke_approval_policy_with_request(
+        &mut self,
+        request: RevokeApprovalPolicyRequest,
+    ) -> Result<RevokeApprovalPolicyResponse> {
+        let response = self.api_client.revoke_approval_policy(request)?;
+
+        Ok(response)
+    }
+
+    pub fn revoke_approval_policy_serialized(
+        &mut self,
+        serialized_request: &str,
+    ) -> Result<String> {
+        let request: frontend_proto::RevokeApprovalPolicyRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::RevokeApprovalPolicyResponse = self
+            .revoke_approval_policy_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    pub fn revoke_approval_policy(&mut self, policy_id: &str) -> Result<()> {
+        let request = RevokeApprovalPolicyRequest::new(Uuid::parse_str(policy_id)?);
+        let _ = self.revoke_approval_policy_with_request(request)?;
+
+        Ok(())
+    }
+
+    pub fn register_model_with_request(
+        &mut self,
+        request: RegisterModelRequest,
+    ) -> Result<RegisterModelResponse> {
+        let response = self.api_client.register_model(request)?;
+
+        Ok(response)
+    }
+
+    pub fn register_model_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::RegisterModelRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::RegisterModelResponse = self
+            .register_model_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Registers an output of a finished task as a new version of the model,
+    /// the version is returned.
+    pub fn register_model(
+        &mut self,
+        name: &str,
+        task_id: &str,
+        data_id: &str,
+        metrics: Option<HashMap<String, f64>>,
+    ) -> Result<u32> {
+        let request = RegisterModelRequest::new(name, task_id.try_into()?, data_id.try_into()?)
+            .metrics(metrics.unwrap_or_default());
+        let response = self.register_model_with_request(request)?;
+
+        Ok(response.version)
+    }
+
+    pub fn get_model_with_request(&mut self, request: GetModelRequest) -> Result<GetModelResponse> {
+        let response = self.api_client.get_model(request)?;
+
+        Ok(response)
+    }
+
+    pub fn get_model_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::GetModelRequest = serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::GetModelResponse =
+            self.get_model_with_request(request.try_into()?)?.into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    pub fn get_model(&mut self, name: &str, version: Option<u32>) -> Result<ModelVersion> {
+        let request = GetModelRequest::new(name, version);
+        let response = self.get_model_with_request(request)?;
+
+        Ok(response.model)
+    }
+
+    pub fn create_task_share_link_with_request(
+        &mut self,
+        request: CreateTaskShareLinkRequest,
+    ) -> Result<CreateTaskShareLinkResponse> {
+        let response = self.api_client.create_task_share_link(request)?;
+
+        Ok(response)
+    }
+
+    pub fn create_task_share_link_serialized(
+        &mut self,
+        serialized_request: &str,
+    ) -> Result<String> {
+        let request: frontend_proto::CreateTaskShareLinkRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::CreateTaskShareLinkResponse = self
+            .create_task_share_link_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Creates a token granting read-only access to the progress of a task
+    /// for `expires_in` seconds, the token is returned.
+    pub fn create_task_share_link(&mut self, task_id: &str, expires_in: u64) -> Result<String> {
+        let request = CreateTaskShareLinkRequest::new(task_id.try_into()?, expires_in);
+        let response = self.create_task_share_link_with_request(request)?;
+
+        Ok(response.token)
+    }
+
+    pub fn get_shared_task_with_request(
+        &mut self,
+        request: GetSharedTaskRequest,
+    ) -> Result<GetSharedTaskResponse> {
+        let response = self.api_client.get_shared_task(request)?;
+
+        Ok(response)
+    }
+
+    pub fn get_shared_task_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::GetSharedTaskRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::GetSharedTaskResponse = self
+            .get_shared_task_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Gets the progress of a shared task with its token, the client does not
+    /// need any credential.
+    pub fn get_shared_task(&mut self, token: &str) -> Result<TaskProgress> {
+        let request = GetSharedTaskRequest::new(token);
+        let response = self.get_shared_task_with_request(request)?;
+
+        Ok(response.progress)
+    }
+
+    pub fn archive_task_with_request(
+        &mut self,
+        request: ArchiveTaskRequest,
+    ) -> Result<ArchiveTaskResponse> {
+        let response = self.api_client.archive_task(request)?;
+
+        Ok(response)
+    }
+
+    pub fn archive_task_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::ArchiveTaskRequest = serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::ArchiveTaskResponse =
+            self.archive_task_with_request(request.try_into()?)?.into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Moves a finished task out of the platform into the archive storage,
+    /// returning the url of the sealed archive. Only for admins.
+    pub fn archive_task(&mut self, task_id: &str) -> Result<String> {
+        let request = ArchiveTaskRequest::new(task_id.try_into()?);
+        let response = self.archive_task_with_request(request)?;
+
+        Ok(response.url.into_string())
+    }
+
+    pub fn restore_archived_task_with_request(
+        &mut self,
+        request: RestoreArchivedTaskRequest,
+    ) -> Result<RestoreArchivedTaskResponse> {
+        let response = self.api_client.restore_archived_task(request)?;
+
+        Ok(response)
+    }
+
+    pub fn restore_archived_task_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::RestoreArchivedTaskRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::RestoreArchivedTaskResponse = self
+            .restore_archived_task_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Restores an archived task, which can be queried as before archiving.
+    /// Only for admins.
+    pub fn restore_archived_task(&mut self, task_id: &str) -> Result<()> {
+        let request = RestoreArchivedTaskRequest::new(task_id.try_into()?);
+        self.restore_archived_task_with_request(request)?;
+
+        Ok(())
+    }
+
+    pub fn export_storage_snapshot_with_request(
+        &mut self,
+        request: ExportStorageSnapshotRequest,
+    ) -> Result<ExportStorageSnapshotResponse> {
+        let response = self.api_client.export_storage_snapshot(request)?;
+
+        Ok(response)
+    }
+
+    pub fn export_storage_snapshot_serialized(
+        &mut self,
+        serialized_request: &str,
+    ) -> Result<String> {
+        let request: frontend_proto::ExportStorageSnapshotRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::ExportStorageSnapshotResponse = self
+            .export_storage_snapshot_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Seals snapshots of the default storage and its shards under the name,
+    /// and returns the keys opening them, which are to be kept as secret as
+    /// the storage itself. Only for admins.
+    pub fn export_storage_snapshot(&mut self, name: &str) -> Result<Vec<StorageSnapshot>> {
+        let request = ExportStorageSnapshotRequest::new(name);
+        let response = self.export_storage_snapshot_with_request(request)?;
+
+        Ok(response.snapshots)
+    }
+
+    pub fn restore_storage_snapshot_with_request(
+        &mut self,
+        request: RestoreStorageSnapshotRequest,
+    ) -> Result<RestoreStorageSnapshotResponse> {
+        let response = self.api_client.restore_storage_snapshot(request)?;
+
+        Ok(response)
+    }
+
+    pub fn restore_storage_snapshot_serialized(
+        &mut self,
+        serialized_request: &str,
+    ) -> Result<String> {
+        let request: frontend_proto::RestoreStorageSnapshotRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::RestoreStorageSnapshotResponse = self
+            .restore_storage_snapshot_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Replaces all records of the default storage and its shards with those
+    /// of the snapshots, in the order they are exported. Only for admins.
+    pub fn restore_storage_snapshot(&mut self, snapshots: Vec<StorageSnapshot>) -> Result<()> {
+        let request = RestoreStorageSnapshotRequest::new(snapshots);
+        self.restore_storage_snapshot_with_request(request)?;
+
+        Ok(())
+    }
+
+    pub fn promote_storage_replica_with_request(
+        &mut self,
+        request: PromoteStorageReplicaRequest,
+    ) -> Result<PromoteStorageReplicaResponse> {
+        let response = self.api_client.promote_storage_replica(request)?;
+
+        Ok(response)
+    }
+
+    pub fn promote_storage_replica_serialized(
+        &mut self,
+        serialized_request: &str,
+    ) -> Result<String> {
+        let request: frontend_proto::PromoteStorageReplicaRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::PromoteStorageReplicaResponse = self
+            .promote_storage_replica_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Promotes the read replica of the name to stand in for the default
+    /// storage after losing it. Only for admins.
+    pub fn promote_storage_replica(&mut self, name: &str) -> Result<()> {
+        let request = PromoteStorageReplicaRequest::new(name);
+        self.promote_storage_replica_with_request(request)?;
+
+        Ok(())
+    }
+
+    pub fn cancel_task_with_request(
+        &mut self,
+        request: CancelTaskRequest,
+    ) -> Result<CancelTaskResponse> {
+        let response = self.api_client.cancel_task(request)?;
+
+        Ok(response)
+    }
+
+    pub fn cancel_task_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::CancelTaskRequest = serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::CancelTaskResponse =
+            self.cancel_task_with_request(request.try_into()?)?.into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Cancels a task which has not started running. Only for the creator.
+    pub fn cancel_task(&mut self, task_id: &str) -> Result<()> {
+        let request = CancelTaskRequest::new(task_id.try_into()?);
+        self.cancel_task_with_request(request)?;
+
+        Ok(())
+    }
+
+    pub fn get_task_upload_slots_with_request(
+        &mut self,
+        request: GetTaskUploadSlotsRequest,
+    ) -> Result<GetTaskUploadSlotsResponse> {
+        let response = self.api_client.get_task_upload_slots(request)?;
+
+        Ok(response)
+    }
+
+    pub fn get_task_upload_slots_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::GetTaskUploadSlotsRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::GetTaskUploadSlotsResponse = self
+            .get_task_upload_slots_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Returns the urls to push the inputs of the user registered with the
+    /// `push:` url to, by their names in the function. The inputs are pushed
+    /// with an HTTP PUT of the encrypted file once the task is running.
+    pub fn get_task_upload_slots(&mut self, task_id: &str) -> Result<HashMap<String, Url>> {
+        let request = GetTaskUploadSlotsRequest::new(task_id.try_into()?);
+        let response = self.get_task_upload_slots_with_request(request)?;
+        response
+            .tokens
+            .keys()
+            .map(|fname| Ok((fname.to_owned(), response.upload_url(fname)?)))
+            .collect()
+    }
+
+    pub fn reject_task_with_request(
+        &mut self,
+        request: RejectTaskRequest,
+    ) -> Result<RejectTaskResponse> {
+        let response = self.api_client.reject_task(request)?;
+
+        Ok(response)
+    }
+
+    pub fn reject_task_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::RejectTaskRequest = serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::RejectTaskResponse =
+            self.reject_task_with_request(request.try_into()?)?.into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Rejects a task which has not been invoked with the reason. Only for
+    /// the participants.
+    pub fn reject_task(&mut self, task_id: &str, reason: &str) -> Result<()> {
+        let request = RejectTaskRequest::new(task_id.try_into()?, reason);
+        self.reject_task_with_request(request)?;
+
+        Ok(())
+    }
+
+    pub fn get_activity_feed_with_request(
+        &mut self,
+        request: GetActivityFeedRequest,
+    ) -> Result<GetActivityFeedResponse> {
+        let response = self.api_client.get_activity_feed(request)?;
+
+        Ok(response)
+    }
+
+    pub fn get_activity_feed_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::GetActivityFeedRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::GetActivityFeedResponse = self
+            .get_activity_feed_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Returns the recent events of the user at or after the time in seconds
+    /// since the Unix epoch, newest first.
+    pub fn get_activity_feed(&mut self, since: u64) -> Result<Vec<ActivityEvent>> {
+        let request = GetActivityFeedRequest::new(since);
+        let response = self.get_activity_feed_with_request(request)?;
+
+        Ok(response.events)
+    }
+
+    /// Streams the result of a finished task. The client is not usable for
+    /// other requests until the returned responses are read to the end.
+    pub fn get_task_result_stream_with_request(
+        &mut self,
+        request: GetTaskResultStreamRequest,
+    ) -> Result<impl Iterator<Item = Result<GetTaskResultStreamResponse>> + '_> {
+        let responses = self.api_client.get_task_result_stream(request)?;
+
+        Ok(responses.map(|response| response.map_err(Into::into)))
+    }
+
+    /// Writes the return value of a finished task, or the output file of the
+    /// name as it is stored (i.e., encrypted), to the writer chunk by chunk,
+    /// returning the number of bytes written.
+    pub fn get_task_result_stream(
+        &mut self,
+        task_id: &str,
+        output: Option<&str>,
+        mut writer: impl std::io::Write,
+    ) -> Result<usize> {
+        let mut request = GetTaskResultStreamRequest::new(task_id.try_into()?);
+        if let Some(output) = output {
+            request = request.output(output);
+        }
+        let mut written = 0;
+        for response in self.get_task_result_stream_with_request(request)? {
+            let response = response?;
+            ensure!(response.offset == written, "Unexpected chunk offset");
+            writer.write_all(&response.data)?;
+            written += response.data.len();
+        }
+        writer.flush()?;
+
+        Ok(written)
+    }
+
+    pub fn register_task_template_with_request(
+        &mut self,
+        request: RegisterTaskTemplateRequest,
+    ) -> Result<RegisterTaskTemplateResponse> {
+        let response = self.api_client.register_task_template(request)?;
+
+        Ok(response)
+    }
+
+    pub fn register_task_template_serialized(
+        &mut self,
+        serialized_request: &str,
+    ) -> Result<String> {
+        let request: frontend_proto::RegisterTaskTemplateRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::RegisterTaskTemplateResponse = self
+            .register_task_template_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Registers a template of recurring tasks of the function, with the
+    /// defaults of some or all of the arguments, returning the template id.
+    pub fn register_task_template(
+        &mut self,
+        function_id: &str,
+        function_arguments: Option<HashMap<String, String>>,
+        executor: &str,
+        inputs_ownership: HashMap<String, Vec<String>>,
+        outputs_ownership: HashMap<String, Vec<String>>,
+    ) -> Result<String> {
+        use teaclave_types::OwnerList;
+        let function_id = function_id.try_into()?;
+        let executor = executor.try_into()?;
+
+        let inputs_ownership: HashMap<String, OwnerList> = inputs_ownership
+            .into_iter()
+            .map(|(k, v)| (k, v.into()))
+            .collect();
+        let outputs_ownership: HashMap<String, OwnerList> = outputs_ownership
+            .into_iter()
+            .map(|(k, v)| (k, v.into()))
+            .collect();
+        let mut request = RegisterTaskTemplateRequest::new()
+            .function_id(function_id)
+            .executor(executor)
+            .inputs_ownership(inputs_ownership)
+            .outputs_ownership(outputs_ownership);
+        if let Some(function_arguments) = function_arguments {
+            request = request.function_arguments(function_arguments);
+        }
+        let response = self.register_task_template_with_request(request)?;
+
+        Ok(response.template_id.to_string())
+    }
+
+    pub fn create_task_from_template_with_request(
+        &mut self,
+        request: CreateTaskFromTemplateRequest,
+    ) -> Result<CreateTaskFromTemplateResponse> {
+        let response = self.api_client.create_task_from_template(request)?;
+
+        Ok(response)
+    }
+
+    pub fn create_task_from_template_serialized(
+        &mut self,
+        serialized_request: &str,
+    ) -> Result<String> {
+        let request: frontend_proto::CreateTaskFromTemplateRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::CreateTaskFromTemplateResponse = self
+            .create_task_from_template_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Creates a task from the template, with the arguments overriding the
+    /// defaults of the template, returning the task id.
+    pub fn create_task_from_template(
+        &mut self,
+        template_id: &str,
+        function_arguments: Option<HashMap<String, String>>,
+    ) -> Result<String> {
+        let mut request = CreateTaskFromTemplateRequest::new(template_id.try_into()?);
+        if let Some(function_arguments) = function_arguments {
+            request = request.function_arguments(function_arguments);
+        }
+        let response = self.create_task_from_template_with_request(request)?;
+
+        Ok(response.task_id.to_string())
+    }
+
+    pub fn create_tasks_batch_with_request(
+        &mut self,
+        request: CreateTasksBatchRequest,
+    ) -> Result<CreateTasksBatchResponse> {
+        let response = self.api_client.create_tasks_batch(request)?;
+
+        Ok(response)
+    }
+
+    pub fn create_tasks_batch_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::CreateTasksBatchRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::CreateTasksBatchResponse = self
+            .create_tasks_batch_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Creates the tasks all at once, either all of them or none, returning
+    /// the task ids in order.
+    pub fn create_tasks_batch(&mut self, tasks: Vec<CreateTaskRequest>) -> Result<Vec<String>> {
+        let request = CreateTasksBatchRequest::new(tasks);
+        let response = self.create_tasks_batch_with_request(request)?;
+
+        Ok(response
+            .task_ids
+            .iter()
+            .map(|task_id| task_id.to_string())
+            .collect())
+    }
+
+    pub fn test_function_with_request(
+        &mut self,
+        request: TestFunctionRequest,
+    ) -> Result<TestFunctionResponse> {
+        let response = self.api_client.test_function(request)?;
+
+        Ok(response)
+    }
+
+    pub fn test_function_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::TestFunctionRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::TestFunctionResponse =
+            self.test_function_with_request(request.try_into()?)?.into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Tests the function owned by the user against the sample inputs,
+    /// returning the id of the test task. The logs and the outputs are in the
+    /// result of the task.
+    pub fn test_function(
+        &mut self,
+        function_id: &str,
+        executor: &str,
+        function_arguments: Option<HashMap<String, String>>,
+        sample_inputs: HashMap<String, Vec<u8>>,
+    ) -> Result<String> {
+        let mut request = TestFunctionRequest::new(function_id.try_into()?, executor.try_into()?);
+        if let Some(function_arguments) = function_arguments {
+            request = request.function_arguments(function_arguments);
+        }
+        for (fname, sample) in sample_inputs {
+            request = request.sample_input(fname, sample);
+        }
+        let response = self.test_function_with_request(request)?;
+
+        Ok(response.task_id.to_string())
+    }
+
+    pub fn register_task_schedule_with_request(
+        &mut self,
+        request: RegisterTaskScheduleRequest,
+    ) -> Result<RegisterTaskScheduleResponse> {
+        let response = self.api_client.register_task_schedule(request)?;
+
+        Ok(response)
+    }
+
+    pub fn register_task_schedule_serialized(
+        &mut self,
+        serialized_request: &str,
+    ) -> Result<String> {
+        let request: frontend_proto::RegisterTaskScheduleRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::RegisterTaskScheduleResponse = self
+            .register_task_schedule_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Schedules the tasks of the template created by the user to run every
+    /// `interval` seconds, `start_in` seconds from now, with the given input
+    /// files. The tasks run once every participant approved the schedule.
+    pub fn register_task_schedule(
+        &mut self,
+        template_id: &str,
+        start_in: u64,
+        interval: u64,
+        max_runs: Option<u64>,
+        inputs: HashMap<String, String>,
+    ) -> Result<()> {
+        let mut request =
+            RegisterTaskScheduleRequest::new(template_id.try_into()?, interval).start_in(start_in);
+        if let Some(max_runs) = max_runs {
+            request = request.max_runs(max_runs);
+        }
+        for (data_name, data_id) in inputs {
+            request = request.input(data_name, data_id.try_into()?);
+        }
+        self.register_task_schedule_with_request(request)?;
+
+        Ok(())
+    }
+
+    pub fn approve_task_schedule_with_request(
+        &mut self,
+        request: ApproveTaskScheduleRequest,
+    ) -> Result<ApproveTaskScheduleResponse> {
+        let response = self.api_client.approve_task_schedule(request)?;
+
+        Ok(response)
+    }
+
+    pub fn approve_task_schedule_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::ApproveTaskScheduleRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::ApproveTaskScheduleResponse = self
+            .approve_task_schedule_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Approves the schedule of the template, and the tasks of all its runs.
+    pub fn approve_task_schedule(&mut self, template_id: &str) -> Result<()> {
+        let request = ApproveTaskScheduleRequest::new(template_id.try_into()?);
+        self.approve_task_schedule_with_request(request)?;
+
+        Ok(())
+    }
+
+    pub fn cancel_task_schedule_with_request(
+        &mut self,
+        request: CancelTaskScheduleRequest,
+    ) -> Result<CancelTaskScheduleResponse> {
+        let response = self.api_client.cancel_task_schedule(request)?;
+
+        Ok(response)
+    }
+
+    pub fn cancel_task_schedule_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::CancelTaskScheduleRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::CancelTaskScheduleResponse = self
+            .cancel_task_schedule_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Stops the schedule of the template for good. For any participant.
+    pub fn cancel_task_schedule(&mut self, template_id: &str) -> Result<()> {
+        let request = CancelTaskScheduleRequest::new(template_id.try_into()?);
+        self.cancel_task_schedule_with_request(request)?;
+
+        Ok(())
+    }
+
+    pub fn get_task_schedule_with_request(
+        &mut self,
+        request: GetTaskScheduleRequest,
+    ) -> Result<GetTaskScheduleResponse> {
+        let response = self.api_client.get_task_schedule(request)?;
+
+        Ok(response)
+    }
+
+    pub fn get_task_schedule_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::GetTaskScheduleRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::GetTaskScheduleResponse = self
+            .get_task_schedule_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Gets the schedule of the template, with the approvals and the tasks of
+    /// the runs so far.
+    pub fn get_task_schedule(&mut self, template_id: &str) -> Result<GetTaskScheduleResponse> {
+        let request = GetTaskScheduleRequest::new(template_id.try_into()?);
+        let response = self.get_task_schedule_with_request(request)?;
+
+        Ok(response)
+    }
+
+    pub fn estimate_task_with_request(
+        &mut self,
+        request: EstimateTaskRequest,
+    ) -> Result<EstimateTaskResponse> {
+        let response = self.api_client.estimate_task(request)?;
+
+        Ok(response)
+    }
+
+    pub fn estimate_task_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::EstimateTaskRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::EstimateTaskResponse =
+            self.estimate_task_with_request(request.try_into()?)?.into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Estimates how long a task of the function would run with inputs of
+    /// `input_size` bytes, and how long it would wait to run if it were
+    /// staged now with the priority, both in milliseconds.
+    pub fn estimate_task(
+        &mut self,
+        function_id: &str,
+        input_size: u64,
+        priority: TaskPriority,
+    ) -> Result<EstimateTaskResponse> {
+        let request = EstimateTaskRequest::new(function_id.try_into()?)
+            .input_size(input_size)
+            .priority(priority);
+        let response = self.estimate_task_with_request(request)?;
+
+        Ok(response)
+    }
+
+    pub fn get_platform_info_with_request(
+        &mut self,
+        request: GetPlatformInfoRequest,
+    ) -> Result<GetPlatformInfoResponse> {
+        let response = self.api_client.get_platform_info(request)?;
+
+        Ok(response)
+    }
+
+    pub fn get_platform_info_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::GetPlatformInfoRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::GetPlatformInfoResponse = self
+            .get_platform_info_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Gets the enclave info of the platform, i.e., the measurement of each
+    /// service and the build it comes from, after verifying every signature
+    /// of the auditors over it with one of `auditor_public_keys`. The client
+    /// does not need any credential.
+    pub fn get_platform_info<T: AsRef<[u8]>>(
+        &mut self,
+        auditor_public_keys: &[T],
+    ) -> Result<EnclaveInfo> {
+        let response = self.get_platform_info_with_request(GetPlatformInfoRequest::new())?;
+        ensure!(
+            !response.auditor_signatures.is_empty(),
+            "Enclave info is not signed"
+        );
+
+        EnclaveInfo::verify_and_new(
+            &response.enclave_info,
+            auditor_public_keys,
+            &response.auditor_signatures,
+        )
+    }
+
+    pub fn register_signing_key_with_request(
+        &mut self,
+        request: RegisterSigningKeyRequest,
+    ) -> Result<RegisterSigningKeyResponse> {
+        let response = self.api_client.register_signing_key(request)?;
+
+        Ok(response)
+    }
+
+    pub fn register_signing_key_serialized(&mut self, serialized_request: &str) -> Result<String> {
+        let request: frontend_proto::RegisterSigningKeyRequest =
+            serde_json::from_str(serialized_request)?;
+        let response: frontend_proto::RegisterSigningKeyResponse = self
+            .register_signing_key_with_request(request.try_into()?)?
+            .into();
+        let serialized_response = serde_json::to_string(&response)?;
+
+        Ok(serialized_response)
+    }
+
+    /// Registers the Ed25519 public key the user signs requests with. A
+    /// request is signed by signing the message of its `request_signature`,
+    /// e.g., `CreateTaskRequest::request_signature`, and setting the
+    /// signature of the request.
+    pub fn register_signing_key(&mut self, public_key: &[u8]) -> Result<()> {
+        let request = RegisterSigningKeyRequest::new(public_key.to_vec());
+        self.register_signing_key_with_request(request)?;
+
+        Ok(())
+    }
+}
+
+/// Recompute the cmac of a downloaded output file and compare it with the
+/// `file_auth_tag` reported in the task result. The `file_crypto` is the one
+/// used when registering the output file.
+pub fn verify_output(
+    path: impl AsRef<Path>,
+    file_auth_tag: &FileAuthTag,
+    file_crypto: FileCrypto,
+) -> Result<()> {
+    let cmac = match file_crypto {
+        FileCrypto::TeaclaveFile128(crypto) => crypto.decrypt(path, &mut std::io::sink())?,
+        _ => bail!("Unsupported output file crypto: {}", file_crypto.schema()),
+    };
+    ensure!(file_auth_tag == &cmac, "Output file cmac mismatch");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+    use std::fs;
+    use teaclave_types::hashmap;
+
+    const ENCLAVE_INFO_PATH: &str = "../../release/services/enclave_info.toml";
+    #[cfg(dcap)]
+    const AS_ROOT_CA_CERT_PATH: &str = "../../keys/dcap_root_ca_cert.pem";
+    #[cfg(not(dcap))]
+    const AS_ROOT_CA_CERT_PATH: &str = "../../keys/ias_root_ca_cert.pem";
+    const USER_ID: &str = "rust_client_sdk_test_user";
+    const USER_PASSWORD: &str = "test_password";
+
+    #[test]
+    fn test_authentication_service() {
+        let enclave_info = EnclaveInfo::from_file(ENCLAVE_INFO_PATH).unwrap();
+        let bytes = fs::read(AS_ROOT_CA_CERT_PATH).unwrap();
+        let as_root_ca_cert = pem::parse(bytes).unwrap().contents;
+        let mut client =
+            AuthenticationService::connect("localhost:7776", &enclave_info, &as_root_ca_cert)
+                .unwrap();
+        let _ = client.user_register(USER_ID, USER_PASSWORD);
+        client.user_login(USER_ID, USER_PASSWORD).unwrap();
+    }
+
+    #[test]
+    fn test_frontend_service() {
+        let enclave_info = EnclaveInfo::from_file(ENCLAVE_INFO_PATH).unwrap();
+        let bytes = fs::read(AS_ROOT_CA_CERT_PATH).unwrap();
+        let as_root_ca_cert = pem::parse(bytes).unwrap().contents;
+        let mut client =
+            AuthenticationService::connect("localhost:7776", &enclave_info, &as_root_ca_cert)
+                .unwrap();
+        let _ = client.user_register(USER_ID, USER_PASSWORD);
+        let token = client.user_login(USER_ID, USER_PASSWORD).unwrap();
+
+        let mut client =
             FrontendService::connect("localhost:7777", &enclave_info, &as_root_ca_cert).unwrap();
         client.set_credential(USER_ID, &token);
         let function_id = client
@@ -674,6 +2367,22 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_verify_output() {
+        let path = std::env::temp_dir().join("teaclave_client_sdk_verify_output.enc");
+        let file_crypto = FileCrypto::default();
+        let cmac = match file_crypto {
+            FileCrypto::TeaclaveFile128(crypto) => {
+                crypto.encrypt(&path, &b"Hello, Teaclave!"[..]).unwrap()
+            }
+            _ => unreachable!(),
+        };
+        let file_auth_tag = FileAuthTag::from(cmac);
+        assert!(verify_output(&path, &file_auth_tag, file_crypto).is_ok());
+        assert!(verify_output(&path, &FileAuthTag::default(), file_crypto).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
     #[test]
     fn test_approve_task() {
         let enclave_info = EnclaveInfo::from_file(ENCLAVE_INFO_PATH).unwrap();
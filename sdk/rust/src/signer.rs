@@ -0,0 +1,254 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Client keys kept in a hardware token, e.g., a TPM or a PKCS#11 token,
+//! which are used through the token and never exported to the SDK.
+
+use anyhow::{anyhow, bail, ensure, Result};
+use rustls::internal::msgs::enums::SignatureAlgorithm;
+use rustls::sign::{CertifiedKey, Signer, SigningKey};
+use rustls::{ResolvesClientCert, SignatureScheme, TLSError};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use teaclave_types::FileCrypto;
+
+/// Private key of the client TLS certificate, signing the handshake on behalf
+/// of the client.
+pub trait ClientSigner: Send + Sync {
+    fn scheme(&self) -> SignatureScheme;
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Master key of the data-encryption keys, deriving the key of a file from a
+/// context identifying the file, e.g., with HMAC. The same context always
+/// derives the same key.
+pub trait DataKeyDeriver: Send + Sync {
+    fn derive_key(&self, context: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Key kept in a hardware token and used through a helper program, e.g., a
+/// script driving `pkcs11-tool` or the TPM2 tools. The helper is run as
+/// `<program> sign <scheme>` or `<program> derive`, reads the message or the
+/// context from the standard input, and writes the raw signature or the
+/// derived secret to the standard output.
+#[derive(Debug, Clone)]
+pub struct KeyHelper {
+    program: PathBuf,
+    scheme: SignatureScheme,
+}
+
+impl KeyHelper {
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            scheme: SignatureScheme::ECDSA_NISTP256_SHA256,
+        }
+    }
+
+    pub fn scheme(self, scheme: SignatureScheme) -> Self {
+        Self { scheme, ..self }
+    }
+
+    fn run(&self, args: &[&str], input: &[u8]) -> Result<Vec<u8>> {
+        let mut child = Command::new(&self.program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("Cannot write to the key helper"))?
+            .write_all(input)?;
+        let output = child.wait_with_output()?;
+        ensure!(
+            output.status.success(),
+            "Key helper {:?} failed: {}",
+            self.program,
+            output.status
+        );
+        Ok(output.stdout)
+    }
+}
+
+impl ClientSigner for KeyHelper {
+    fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+        let scheme = format!("{:?}", self.scheme);
+        self.run(&["sign", &scheme], message)
+    }
+}
+
+impl DataKeyDeriver for KeyHelper {
+    fn derive_key(&self, context: &[u8]) -> Result<Vec<u8>> {
+        self.run(&["derive"], context)
+    }
+}
+
+/// Certificate chain of the client along with the signer of its key.
+#[derive(Clone)]
+pub struct ClientKey {
+    cert_chain: Vec<rustls::Certificate>,
+    signer: Arc<dyn ClientSigner>,
+}
+
+impl ClientKey {
+    /// Certificates are in DER, starting with the certificate of the client.
+    pub fn new(cert_chain: Vec<Vec<u8>>, signer: Arc<dyn ClientSigner>) -> Self {
+        Self {
+            cert_chain: cert_chain.into_iter().map(rustls::Certificate).collect(),
+            signer,
+        }
+    }
+
+    pub(crate) fn cert_resolver(&self) -> Arc<dyn ResolvesClientCert> {
+        Arc::new(self.clone())
+    }
+}
+
+impl ResolvesClientCert for ClientKey {
+    fn resolve(
+        &self,
+        _acceptable_issuers: &[&[u8]],
+        sigschemes: &[SignatureScheme],
+    ) -> Option<CertifiedKey> {
+        if !sigschemes.contains(&self.signer.scheme()) {
+            return None;
+        }
+        let key: Box<dyn SigningKey> = Box::new(TokenKey(self.signer.clone()));
+        Some(CertifiedKey::new(self.cert_chain.clone(), Arc::new(key)))
+    }
+
+    fn has_certs(&self) -> bool {
+        !self.cert_chain.is_empty()
+    }
+}
+
+struct TokenKey(Arc<dyn ClientSigner>);
+
+impl SigningKey for TokenKey {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
+        if offered.contains(&self.0.scheme()) {
+            Some(Box::new(TokenKey(self.0.clone())))
+        } else {
+            None
+        }
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        match self.0.scheme() {
+            SignatureScheme::ECDSA_NISTP256_SHA256
+            | SignatureScheme::ECDSA_NISTP384_SHA384
+            | SignatureScheme::ECDSA_NISTP521_SHA512 => SignatureAlgorithm::ECDSA,
+            SignatureScheme::ED25519 => SignatureAlgorithm::ED25519,
+            _ => SignatureAlgorithm::RSA,
+        }
+    }
+}
+
+impl Signer for TokenKey {
+    fn sign(&self, message: &[u8]) -> std::result::Result<Vec<u8>, TLSError> {
+        self.0
+            .sign(message)
+            .map_err(|e| TLSError::General(format!("Failed to sign with the client key: {}", e)))
+    }
+
+    fn get_scheme(&self) -> SignatureScheme {
+        self.0.scheme()
+    }
+}
+
+/// Crypto info of a file whose key is derived by `deriver` from `context`,
+/// e.g., the name and version of the file. The IV is not secret and is given
+/// as is, it must not be reused with the same key for AES-GCM.
+pub fn derive_file_crypto(
+    deriver: &dyn DataKeyDeriver,
+    schema: &str,
+    context: &[u8],
+    iv: &[u8],
+) -> Result<FileCrypto> {
+    let key_length = match schema {
+        "aes-gcm-128" | "teaclave-file-128" => 16,
+        "aes-gcm-256" => 32,
+        _ => bail!("Unsupported crypto schema for derived keys: {}", schema),
+    };
+    let secret = deriver.derive_key(context)?;
+    ensure!(
+        secret.len() >= key_length,
+        "Derived secret is shorter than the key of {}",
+        schema
+    );
+    FileCrypto::new(schema, &secret[..key_length], iv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockDeriver;
+
+    impl DataKeyDeriver for MockDeriver {
+        fn derive_key(&self, context: &[u8]) -> Result<Vec<u8>> {
+            Ok(context.iter().cycle().take(32).cloned().collect())
+        }
+    }
+
+    struct MockSigner;
+
+    impl ClientSigner for MockSigner {
+        fn scheme(&self) -> SignatureScheme {
+            SignatureScheme::ECDSA_NISTP256_SHA256
+        }
+
+        fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+            Ok(message.to_vec())
+        }
+    }
+
+    #[test]
+    fn test_derive_file_crypto() {
+        let crypto = derive_file_crypto(&MockDeriver, "aes-gcm-128", b"a", &[0; 12]).unwrap();
+        assert_eq!(crypto.key_iv(), (vec![b'a'; 16], vec![0; 12]));
+
+        let crypto = derive_file_crypto(&MockDeriver, "teaclave-file-128", b"b", &[]).unwrap();
+        assert_eq!(crypto.key_iv(), (vec![b'b'; 16], vec![]));
+
+        assert!(derive_file_crypto(&MockDeriver, "raw", b"c", &[]).is_err());
+    }
+
+    #[test]
+    fn test_client_key_resolve() {
+        let client_key = ClientKey::new(vec![b"cert".to_vec()], Arc::new(MockSigner));
+        assert!(client_key
+            .resolve(&[], &[SignatureScheme::RSA_PSS_SHA256])
+            .is_none());
+
+        let certified_key = client_key
+            .resolve(&[], &[SignatureScheme::ECDSA_NISTP256_SHA256])
+            .unwrap();
+        let signer = certified_key
+            .key
+            .choose_scheme(&[SignatureScheme::ECDSA_NISTP256_SHA256])
+            .unwrap();
+        assert_eq!(signer.sign(b"message").unwrap(), b"message".to_vec());
+    }
+}
@@ -53,6 +53,23 @@ pub(crate) enum EnforceRequest {
     TaskAccessData(String, String),
 }
 
+impl EnforceRequest {
+    /// Name, subject and object of the request.
+    pub(crate) fn terms(&self) -> (&'static str, &str, &str) {
+        match self {
+            EnforceRequest::UserAccessData(usr, data) => ("user_access_data", usr, data),
+            EnforceRequest::UserAccessFunction(usr, function) => {
+                ("user_access_function", usr, function)
+            }
+            EnforceRequest::UserAccessTask(usr, task) => ("user_access_task", usr, task),
+            EnforceRequest::TaskAccessFunction(task, function) => {
+                ("task_access_function", task, function)
+            }
+            EnforceRequest::TaskAccessData(task, data) => ("task_access_data", task, data),
+        }
+    }
+}
+
 #[cfg(test_mode)]
 pub(crate) enum AccessControlTerms {
     // data_owner = data, usr
@@ -255,43 +272,46 @@ pub(crate) fn init_mock_data() -> Result<()> {
 #[derive(Clone)]
 pub(crate) struct AccessControlModule {
     lock: Arc<Mutex<u32>>,
+    #[cfg(feature = "external_policy")]
+    external_policy: Option<Arc<crate::external::ExternalPolicy>>,
 }
 
 impl AccessControlModule {
     pub(crate) fn new() -> Self {
         AccessControlModule {
             lock: Arc::new(Mutex::new(0)),
+            #[cfg(feature = "external_policy")]
+            external_policy: None,
+        }
+    }
+
+    #[cfg(feature = "external_policy")]
+    pub(crate) fn external_policy(self, external_policy: crate::external::ExternalPolicy) -> Self {
+        Self {
+            external_policy: Some(Arc::new(external_policy)),
+            ..self
         }
     }
 
     pub(crate) fn enforce_request(&self, request: EnforceRequest) -> Result<bool> {
-        let (request_type, request_content) = match request {
-            EnforceRequest::UserAccessData(usr, data) => {
-                let mut buffer = String::new();
-                (usr, data).marshal(&mut buffer);
-                ("user_access_data", buffer)
-            }
-            EnforceRequest::UserAccessFunction(usr, function) => {
-                let mut buffer = String::new();
-                (usr, function).marshal(&mut buffer);
-                ("user_access_function", buffer)
-            }
-            EnforceRequest::UserAccessTask(usr, task) => {
-                let mut buffer = String::new();
-                (usr, task).marshal(&mut buffer);
-                ("user_access_task", buffer)
-            }
-            EnforceRequest::TaskAccessFunction(task, function) => {
-                let mut buffer = String::new();
-                (task, function).marshal(&mut buffer);
-                ("task_access_function", buffer)
-            }
-            EnforceRequest::TaskAccessData(task, data) => {
-                let mut buffer = String::new();
-                (task, data).marshal(&mut buffer);
-                ("task_access_data", buffer)
+        #[cfg(feature = "external_policy")]
+        {
+            if let Some(external_policy) = &self.external_policy {
+                let (request_type, subject, object) = request.terms();
+                if external_policy.delegates(request_type) {
+                    return external_policy.decide(request_type, subject, object, || {
+                        self.enforce_locally(&request)
+                    });
+                }
             }
-        };
+        }
+        self.enforce_locally(&request)
+    }
+
+    fn enforce_locally(&self, request: &EnforceRequest) -> Result<bool> {
+        let (request_type, subject, object) = request.terms();
+        let mut request_content = String::new();
+        (subject.to_string(), object.to_string()).marshal(&mut request_content);
 
         let c_request_type = CString::new(request_type.to_string())?;
         let c_request_content = CString::new(request_content)?;
@@ -17,7 +17,7 @@
 
 use std::prelude::v1::*;
 
-use teaclave_types::TeaclaveServiceResponseError;
+use teaclave_types::{TeaclaveErrorCode, TeaclaveServiceResponseError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -26,8 +26,16 @@ pub(crate) enum TeaclavAccessControlError {
     AccessControlError,
 }
 
+impl TeaclavAccessControlError {
+    fn code(&self) -> TeaclaveErrorCode {
+        match self {
+            TeaclavAccessControlError::AccessControlError => TeaclaveErrorCode::Auth,
+        }
+    }
+}
+
 impl From<TeaclavAccessControlError> for TeaclaveServiceResponseError {
     fn from(error: TeaclavAccessControlError) -> Self {
-        TeaclaveServiceResponseError::RequestError(error.to_string())
+        TeaclaveServiceResponseError::request_error(error.code(), error)
     }
 }
@@ -0,0 +1,188 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Connector delegating decisions of the access control service to a policy
+//! decision service outside of Teaclave, built with the `external_policy`
+//! feature.
+
+use anyhow::{anyhow, bail, ensure, Result};
+use cfg_if::cfg_if;
+use serde_json::json;
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::prelude::v1::*;
+use std::sync::Arc;
+use std::time::Duration;
+use teaclave_attestation::verifier;
+use teaclave_attestation::AttestedTlsConfig;
+use teaclave_config::build::AS_ROOT_CA_CERT;
+use teaclave_config::{ExternalPolicyConfig, ExternalPolicyKind};
+use teaclave_proto::teaclave_policy_decision_service::{
+    DecideRequest, TeaclavePolicyDecisionClient,
+};
+use teaclave_rpc::config::SgxTrustedTlsClientConfig;
+use teaclave_rpc::endpoint::Endpoint;
+use teaclave_types::EnclaveInfo;
+cfg_if! {
+    if #[cfg(feature = "mesalock_sgx")]  {
+        use std::sync::{SgxMutex as Mutex, SgxRwLock as RwLock};
+    } else {
+        use std::sync::{Mutex, RwLock};
+    }
+}
+
+const POLICY_DECISION_SERVICE: &str = "teaclave_policy_decision_service";
+const OPA_TIMEOUT: Duration = Duration::from_secs(5);
+// Decisions of OPA are small JSON documents.
+const MAX_OPA_RESPONSE_SIZE: u64 = 64 * 1024;
+
+enum PolicyDecisionService {
+    Attested(Mutex<TeaclavePolicyDecisionClient>),
+    Opa { address: String, path: String },
+}
+
+pub(crate) struct ExternalPolicy {
+    service: PolicyDecisionService,
+    decisions: HashSet<String>,
+}
+
+impl ExternalPolicy {
+    pub(crate) fn new(
+        config: &ExternalPolicyConfig,
+        enclave_info: &EnclaveInfo,
+        attested_tls_config: Arc<RwLock<AttestedTlsConfig>>,
+    ) -> Result<Self> {
+        let service = match config.kind {
+            ExternalPolicyKind::Attested => {
+                let enclave_attr = enclave_info
+                    .get_enclave_attr(POLICY_DECISION_SERVICE)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "cannot get enclave attribute of {}",
+                            POLICY_DECISION_SERVICE
+                        )
+                    })?;
+                let client_config =
+                    SgxTrustedTlsClientConfig::from_attested_tls_config(attested_tls_config)?
+                        .attestation_report_verifier(
+                            vec![enclave_attr],
+                            AS_ROOT_CA_CERT,
+                            verifier::universal_quote_verifier,
+                        );
+                let channel = Endpoint::new(&config.address)
+                    .config(client_config)
+                    .connect()?;
+                let client = TeaclavePolicyDecisionClient::new(channel)?;
+                PolicyDecisionService::Attested(Mutex::new(client))
+            }
+            ExternalPolicyKind::Opa => {
+                ensure!(
+                    !config.path.is_empty(),
+                    "path of the OPA decision is not set"
+                );
+                PolicyDecisionService::Opa {
+                    address: config.address.to_owned(),
+                    path: config.path.trim_matches('/').to_owned(),
+                }
+            }
+        };
+
+        Ok(Self {
+            service,
+            decisions: config.decisions.iter().cloned().collect(),
+        })
+    }
+
+    pub(crate) fn delegates(&self, decision: &str) -> bool {
+        self.decisions.contains(decision)
+    }
+
+    /// Decides the request with the external service. The host can forge the
+    /// decisions of an OPA sidecar, so they can only deny the requests
+    /// accepted by the built-in policy.
+    pub(crate) fn decide(
+        &self,
+        decision: &str,
+        subject: &str,
+        object: &str,
+        enforce_locally: impl FnOnce() -> Result<bool>,
+    ) -> Result<bool> {
+        match &self.service {
+            PolicyDecisionService::Attested(client) => {
+                let mut client = client
+                    .lock()
+                    .map_err(|_| anyhow!("failed to accquire lock"))?;
+                let response = client.decide(DecideRequest::new(decision, subject, object))?;
+                Ok(response.accept)
+            }
+            PolicyDecisionService::Opa { address, path } => {
+                if !enforce_locally()? {
+                    return Ok(false);
+                }
+                query_opa(address, path, decision, subject, object)
+            }
+        }
+    }
+}
+
+// Queries the data API of OPA over HTTP/1.0, so that the response is neither
+// chunked nor kept alive. An undefined decision denies the request.
+fn query_opa(
+    address: &str,
+    path: &str,
+    decision: &str,
+    subject: &str,
+    object: &str,
+) -> Result<bool> {
+    let body = json!({
+        "input": {
+            "decision": decision,
+            "subject": subject,
+            "object": object,
+        }
+    })
+    .to_string();
+
+    let mut stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(Some(OPA_TIMEOUT))?;
+    stream.set_write_timeout(Some(OPA_TIMEOUT))?;
+    write!(
+        stream,
+        "POST /v1/data/{} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        path,
+        address,
+        body.len(),
+        body
+    )?;
+
+    let mut response = Vec::new();
+    stream
+        .take(MAX_OPA_RESPONSE_SIZE)
+        .read_to_end(&mut response)?;
+    let response = String::from_utf8(response)?;
+    let separator = response
+        .find("\r\n\r\n")
+        .ok_or_else(|| anyhow!("invalid OPA response"))?;
+    let (head, body) = (&response[..separator], &response[separator + 4..]);
+    let status = head.split(' ').nth(1).unwrap_or_default();
+    if status != "200" {
+        bail!("OPA responded with status {}", status);
+    }
+
+    let document: serde_json::Value = serde_json::from_str(body)?;
+    Ok(document["result"].as_bool().unwrap_or(false))
+}
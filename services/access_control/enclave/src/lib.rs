@@ -45,6 +45,8 @@ use teaclave_types::{EnclaveInfo, TeeServiceError, TeeServiceResult};
 
 mod acs;
 mod error;
+#[cfg(feature = "external_policy")]
+mod external;
 mod service;
 
 fn start_service(config: &RuntimeConfig) -> Result<()> {
@@ -66,6 +68,8 @@ fn start_service(config: &RuntimeConfig) -> Result<()> {
             None => Err(anyhow!("cannot get enclave attribute of {}", service)),
         })
         .collect::<Result<_>>()?;
+    #[cfg(feature = "external_policy")]
+    let external_policy_tls_config = attested_tls_config.clone();
     let server_config = SgxTrustedTlsServerConfig::from_attested_tls_config(attested_tls_config)?
         .attestation_report_verifier(
         accepted_enclave_attrs,
@@ -79,6 +83,15 @@ fn start_service(config: &RuntimeConfig) -> Result<()> {
         TeaclaveAccessControlRequest,
     >::new(listen_address, server_config);
     let service = service::TeaclaveAccessControlService::new();
+    #[cfg(feature = "external_policy")]
+    let service = match &config.external_policy {
+        Some(external_policy) => service.external_policy(external::ExternalPolicy::new(
+            external_policy,
+            &enclave_info,
+            external_policy_tls_config,
+        )?),
+        None => service,
+    };
     match server.start(service) {
         Ok(_) => (),
         Err(e) => {
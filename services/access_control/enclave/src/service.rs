@@ -39,6 +39,13 @@ impl TeaclaveAccessControlService {
             access_control_module: AccessControlModule::new(),
         }
     }
+
+    #[cfg(feature = "external_policy")]
+    pub(crate) fn external_policy(self, external_policy: crate::external::ExternalPolicy) -> Self {
+        Self {
+            access_control_module: self.access_control_module.external_policy(external_policy),
+        }
+    }
 }
 
 impl TeaclaveAccessControl for TeaclaveAccessControlService {
@@ -18,16 +18,18 @@
 use crate::error::TeaclaveAuthenticationApiError;
 use crate::user_db::{DbClient, DbError};
 use crate::user_info::UserInfo;
+use crate::verification::{generate_code, RegistrationVerification};
 use std::prelude::v1::*;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::untrusted::time::SystemTimeEx;
+use std::sync::Arc;
 use teaclave_proto::teaclave_authentication_service::{
-    TeaclaveAuthenticationApi, UserLoginRequest, UserLoginResponse, UserRegisterRequest,
-    UserRegisterResponse,
+    ConfirmRegistrationRequest, ConfirmRegistrationResponse, MintScopedTokenRequest,
+    MintScopedTokenResponse, TeaclaveAuthenticationApi, UserLoginRequest, UserLoginResponse,
+    UserRegisterRequest, UserRegisterResponse,
 };
 use teaclave_rpc::Request;
 use teaclave_service_enclave_utils::{bail, ensure, teaclave_service};
-use teaclave_types::TeaclaveServiceResponseResult;
+use teaclave_storage_client::{TeaclaveStorageRouter, TypedStorage};
+use teaclave_types::{ScopedTokenRecord, TeaclaveServiceResponseResult, TrustedTime};
 
 #[teaclave_service(
     teaclave_authentication_service,
@@ -38,13 +40,68 @@ use teaclave_types::TeaclaveServiceResponseResult;
 pub(crate) struct TeaclaveAuthenticationApiService {
     db_client: DbClient,
     jwt_secret: Vec<u8>,
+    // Records of the scoped tokens, which are not kept without the storage,
+    // e.g., in the unit tests.
+    storage_client: Option<Arc<TeaclaveStorageRouter>>,
+    // Users registering themselves stay pending until they confirm the code
+    // sent by the verifier, if any.
+    registration: Option<RegistrationVerification>,
 }
 
 impl TeaclaveAuthenticationApiService {
-    pub(crate) fn new(db_client: DbClient, jwt_secret: Vec<u8>) -> Self {
+    pub(crate) fn new(
+        db_client: DbClient,
+        jwt_secret: Vec<u8>,
+        storage_client: Arc<TeaclaveStorageRouter>,
+        registration: Option<RegistrationVerification>,
+    ) -> Self {
         Self {
             db_client,
             jwt_secret,
+            storage_client: Some(storage_client),
+            registration,
+        }
+    }
+
+    // The code is sent before the user is created, so that a user id is not
+    // held by a registration which cannot be confirmed. A lapsed registration
+    // is replaced.
+    fn register_pending(
+        &self,
+        registration: &RegistrationVerification,
+        request: UserRegisterRequest,
+    ) -> TeaclaveServiceResponseResult<UserRegisterResponse> {
+        ensure!(
+            !request.contact.is_empty(),
+            TeaclaveAuthenticationApiError::InvalidContact
+        );
+        let now = TrustedTime::now_secs();
+        let lapsed = match self.db_client.get_user(&request.id) {
+            Ok(user) if user.is_registration_lapsed(now) => true,
+            Ok(_) => bail!(TeaclaveAuthenticationApiError::UserExists),
+            Err(DbError::UserNotExist) => false,
+            Err(_) => bail!(TeaclaveAuthenticationApiError::ServiceUnavailable),
+        };
+
+        let code = generate_code();
+        registration
+            .verifier
+            .send_code(&request.id, &request.contact, &code)
+            .map_err(|e| {
+                warn!("Failed to send the verification code: {}", e);
+                TeaclaveAuthenticationApiError::ServiceUnavailable
+            })?;
+        let expires_at = now.saturating_add(registration.code_ttl);
+        let new_user = UserInfo::new_pending(&request.id, &request.password, &code, expires_at);
+        let result = if lapsed {
+            self.db_client.update_user(&new_user)
+        } else {
+            self.db_client.create_user(&new_user)
+        };
+        match result {
+            Ok(_) => Ok(UserRegisterResponse {}),
+            Err(DbError::UserExist) => Err(TeaclaveAuthenticationApiError::UserExists.into()),
+            Err(_) => Err(TeaclaveAuthenticationApiError::ServiceUnavailable.into()),
         }
     }
 }
@@ -59,17 +116,47 @@ impl TeaclaveAuthenticationApi for TeaclaveAuthenticationApiService {
             !request.id.is_empty(),
             TeaclaveAuthenticationApiError::InvalidUserId
         );
+        if let Some(registration) = &self.registration {
+            return self.register_pending(registration, request);
+        }
         if self.db_client.get_user(&request.id).is_ok() {
-            bail!(TeaclaveAuthenticationApiError::InvalidUserId);
+            bail!(TeaclaveAuthenticationApiError::UserExists);
         }
         let new_user = UserInfo::new(&request.id, &request.password);
         match self.db_client.create_user(&new_user) {
             Ok(_) => Ok(UserRegisterResponse {}),
-            Err(DbError::UserExist) => Err(TeaclaveAuthenticationApiError::InvalidUserId.into()),
+            Err(DbError::UserExist) => Err(TeaclaveAuthenticationApiError::UserExists.into()),
             Err(_) => Err(TeaclaveAuthenticationApiError::ServiceUnavailable.into()),
         }
     }
 
+    // A wrong code is not told apart from an unknown or a confirmed user.
+    fn confirm_registration(
+        &self,
+        request: Request<ConfirmRegistrationRequest>,
+    ) -> TeaclaveServiceResponseResult<ConfirmRegistrationResponse> {
+        let request = request.message;
+        ensure!(
+            !request.id.is_empty(),
+            TeaclaveAuthenticationApiError::InvalidUserId
+        );
+        let mut user = self
+            .db_client
+            .get_user(&request.id)
+            .map_err(|_| TeaclaveAuthenticationApiError::PermissionDenied)?;
+        ensure!(
+            user.is_pending(),
+            TeaclaveAuthenticationApiError::PermissionDenied
+        );
+        let confirmed = user.confirm_registration(&request.code, TrustedTime::now_secs());
+        // Keeps the count of the wrong attempts as well.
+        self.db_client
+            .update_user(&user)
+            .map_err(|_| TeaclaveAuthenticationApiError::ServiceUnavailable)?;
+        ensure!(confirmed, TeaclaveAuthenticationApiError::PermissionDenied);
+        Ok(ConfirmRegistrationResponse {})
+    }
+
     fn user_login(
         &self,
         request: Request<UserLoginRequest>,
@@ -87,19 +174,57 @@ impl TeaclaveAuthenticationApi for TeaclaveAuthenticationApiService {
             .db_client
             .get_user(&request.id)
             .map_err(|_| TeaclaveAuthenticationApiError::PermissionDenied)?;
-        if !user.verify_password(&request.password) {
+        if !user.verify_password(&request.password) || user.is_pending() {
             bail!(TeaclaveAuthenticationApiError::PermissionDenied)
         } else {
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .map_err(|_| TeaclaveAuthenticationApiError::ServiceUnavailable)?;
-            let exp = (now + Duration::from_secs(24 * 60)).as_secs();
+            let exp = TrustedTime::now_secs() + 24 * 60;
             match user.get_token(exp, &self.jwt_secret) {
                 Ok(token) => Ok(UserLoginResponse { token }),
                 Err(_) => Err(TeaclaveAuthenticationApiError::ServiceUnavailable.into()),
             }
         }
     }
+
+    // Only a full login token can mint scoped tokens, which never outlive it.
+    // The record of a scoped token expires along with it.
+    fn mint_scoped_token(
+        &self,
+        request: Request<MintScopedTokenRequest>,
+    ) -> TeaclaveServiceResponseResult<MintScopedTokenResponse> {
+        let request = request.message;
+        ensure!(
+            !request.id.is_empty(),
+            TeaclaveAuthenticationApiError::InvalidUserId
+        );
+        ensure!(
+            request.scope.is_valid(),
+            TeaclaveAuthenticationApiError::InvalidScope
+        );
+        let user = self
+            .db_client
+            .get_user(&request.id)
+            .map_err(|_| TeaclaveAuthenticationApiError::PermissionDenied)?;
+        let claims = user
+            .decode_token(&self.jwt_secret, &request.token)
+            .ok_or(TeaclaveAuthenticationApiError::PermissionDenied)?;
+        ensure!(
+            claims.scope.is_none(),
+            TeaclaveAuthenticationApiError::PermissionDenied
+        );
+        let now = TrustedTime::now_secs();
+        let exp = std::cmp::min(now.saturating_add(request.expires_in), claims.exp);
+        let record = ScopedTokenRecord::new(&request.id, exp);
+        if let Some(storage_client) = &self.storage_client {
+            let ttl = std::cmp::max(exp.saturating_sub(now), 1);
+            storage_client
+                .put_item_with_ttl(&record, ttl)
+                .map_err(|_| TeaclaveAuthenticationApiError::ServiceUnavailable)?;
+        }
+        match user.get_scoped_token(&record, request.scope, &self.jwt_secret) {
+            Ok(token) => Ok(MintScopedTokenResponse::new(token, exp)),
+            Err(_) => Err(TeaclaveAuthenticationApiError::ServiceUnavailable.into()),
+        }
+    }
 }
 
 #[cfg(feature = "enclave_unit_test")]
@@ -107,9 +232,19 @@ pub mod tests {
     use super::*;
     use crate::user_db::*;
     use crate::user_info::*;
+    use crate::verification::RegistrationVerifier;
     use rand::RngCore;
+    use std::collections::HashMap;
     use std::vec;
     use teaclave_rpc::IntoRequest;
+    use teaclave_types::TokenScope;
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "mesalock_sgx")]  {
+            use std::sync::SgxMutex as Mutex;
+        } else {
+            use std::sync::Mutex;
+        }
+    }
 
     fn get_mock_service() -> TeaclaveAuthenticationApiService {
         let database = Database::open().unwrap();
@@ -119,6 +254,41 @@ pub mod tests {
         TeaclaveAuthenticationApiService {
             db_client: database.get_client(),
             jwt_secret,
+            storage_client: None,
+            registration: None,
+        }
+    }
+
+    // Keeps the codes sent to the contacts.
+    struct MockVerifier {
+        codes: Mutex<HashMap<String, String>>,
+    }
+
+    impl MockVerifier {
+        fn new() -> Self {
+            Self {
+                codes: Mutex::new(HashMap::new()),
+            }
+        }
+    }
+
+    impl RegistrationVerifier for MockVerifier {
+        fn send_code(&self, _user_id: &str, contact: &str, code: &str) -> anyhow::Result<()> {
+            self.codes
+                .lock()
+                .unwrap()
+                .insert(contact.to_string(), code.to_string());
+            Ok(())
+        }
+    }
+
+    fn get_mock_verified_service(
+        verifier: Arc<MockVerifier>,
+        code_ttl: u64,
+    ) -> TeaclaveAuthenticationApiService {
+        TeaclaveAuthenticationApiService {
+            registration: Some(RegistrationVerification { verifier, code_ttl }),
+            ..get_mock_service()
         }
     }
 
@@ -143,4 +313,95 @@ pub mod tests {
         let request = UserLoginRequest::new("test_login_id", "test_password1").into_request();
         assert!(service.user_login(request).is_err());
     }
+
+    pub fn test_mint_scoped_token() {
+        let service = get_mock_service();
+        let request = UserRegisterRequest::new("test_scope_id", "test_password").into_request();
+        assert!(service.user_register(request).is_ok());
+        let request = UserLoginRequest::new("test_scope_id", "test_password").into_request();
+        let token = service.user_login(request).unwrap().token;
+
+        let scope = TokenScope::new(vec!["approve_task".to_string()], vec![]);
+        let request =
+            MintScopedTokenRequest::new("test_scope_id", &token, scope.clone(), 60).into_request();
+        let response = service.mint_scoped_token(request).unwrap();
+        let user = service.db_client.get_user("test_scope_id").unwrap();
+        let claims = user
+            .decode_token(&service.jwt_secret, &response.token)
+            .unwrap();
+        assert_eq!(claims.scope, Some(scope.clone()));
+        assert_eq!(claims.exp, response.expires_at);
+        assert!(claims.jti.is_some());
+
+        // A scoped token cannot mint further tokens.
+        let request =
+            MintScopedTokenRequest::new("test_scope_id", &response.token, scope, 60).into_request();
+        assert!(service.mint_scoped_token(request).is_err());
+
+        let scope = TokenScope::new(vec![], vec![]);
+        let request =
+            MintScopedTokenRequest::new("test_scope_id", &token, scope, 60).into_request();
+        assert!(service.mint_scoped_token(request).is_err());
+    }
+
+    pub fn test_confirm_registration() {
+        let verifier = Arc::new(MockVerifier::new());
+        let service = get_mock_verified_service(verifier.clone(), 60);
+        let request = UserRegisterRequest::new("test_confirm_id", "test_password").into_request();
+        assert!(service.user_register(request).is_err());
+        let request = UserRegisterRequest::new("test_confirm_id", "test_password")
+            .contact("user@example.com")
+            .into_request();
+        assert!(service.user_register(request).is_ok());
+        let code = verifier.codes.lock().unwrap()["user@example.com"].clone();
+
+        // Pending users cannot log in nor register again.
+        let request = UserLoginRequest::new("test_confirm_id", "test_password").into_request();
+        assert!(service.user_login(request).is_err());
+        let request = UserRegisterRequest::new("test_confirm_id", "test_password")
+            .contact("other@example.com")
+            .into_request();
+        assert!(service.user_register(request).is_err());
+
+        let request = ConfirmRegistrationRequest::new("test_confirm_id", "wrong").into_request();
+        assert!(service.confirm_registration(request).is_err());
+        let user = service.db_client.get_user("test_confirm_id").unwrap();
+        assert_eq!(user.pending.unwrap().attempts, 1);
+
+        let request = ConfirmRegistrationRequest::new("test_confirm_id", &code).into_request();
+        assert!(service.confirm_registration(request).is_ok());
+        let request = UserLoginRequest::new("test_confirm_id", "test_password").into_request();
+        assert!(service.user_login(request).is_ok());
+
+        // A confirmed registration cannot be confirmed again.
+        let request = ConfirmRegistrationRequest::new("test_confirm_id", &code).into_request();
+        assert!(service.confirm_registration(request).is_err());
+    }
+
+    pub fn test_lapsed_registration() {
+        let verifier = Arc::new(MockVerifier::new());
+        let service = get_mock_verified_service(verifier.clone(), 60);
+        let request = UserRegisterRequest::new("test_lapsed_id", "test_password")
+            .contact("user@example.com")
+            .into_request();
+        assert!(service.user_register(request).is_ok());
+        let code = verifier.codes.lock().unwrap()["user@example.com"].clone();
+
+        // The registration is given up after too many wrong codes.
+        for _ in 0..5 {
+            let request = ConfirmRegistrationRequest::new("test_lapsed_id", "wrong").into_request();
+            assert!(service.confirm_registration(request).is_err());
+        }
+        let request = ConfirmRegistrationRequest::new("test_lapsed_id", &code).into_request();
+        assert!(service.confirm_registration(request).is_err());
+
+        // Then the user id can be registered again.
+        let request = UserRegisterRequest::new("test_lapsed_id", "test_password")
+            .contact("other@example.com")
+            .into_request();
+        assert!(service.user_register(request).is_ok());
+        let code = verifier.codes.lock().unwrap()["other@example.com"].clone();
+        let request = ConfirmRegistrationRequest::new("test_lapsed_id", &code).into_request();
+        assert!(service.confirm_registration(request).is_ok());
+    }
 }
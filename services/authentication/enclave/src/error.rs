@@ -17,7 +17,7 @@
 
 use std::prelude::v1::*;
 
-use teaclave_types::TeaclaveServiceResponseError;
+use teaclave_types::{TeaclaveErrorCode, TeaclaveServiceResponseError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -28,12 +28,32 @@ pub(crate) enum TeaclaveAuthenticationApiError {
     InvalidUserId,
     #[error("invalid password")]
     InvalidPassword,
+    #[error("user already exists")]
+    UserExists,
+    #[error("invalid token scope")]
+    InvalidScope,
+    #[error("invalid contact")]
+    InvalidContact,
     #[error("service unavailable")]
     ServiceUnavailable,
 }
 
+impl TeaclaveAuthenticationApiError {
+    fn code(&self) -> TeaclaveErrorCode {
+        match self {
+            TeaclaveAuthenticationApiError::PermissionDenied => TeaclaveErrorCode::Auth,
+            TeaclaveAuthenticationApiError::InvalidUserId => TeaclaveErrorCode::Validation,
+            TeaclaveAuthenticationApiError::InvalidPassword => TeaclaveErrorCode::Validation,
+            TeaclaveAuthenticationApiError::UserExists => TeaclaveErrorCode::Conflict,
+            TeaclaveAuthenticationApiError::InvalidScope => TeaclaveErrorCode::Validation,
+            TeaclaveAuthenticationApiError::InvalidContact => TeaclaveErrorCode::Validation,
+            TeaclaveAuthenticationApiError::ServiceUnavailable => TeaclaveErrorCode::Internal,
+        }
+    }
+}
+
 impl From<TeaclaveAuthenticationApiError> for TeaclaveServiceResponseError {
     fn from(error: TeaclaveAuthenticationApiError) -> Self {
-        TeaclaveServiceResponseError::RequestError(error.to_string())
+        TeaclaveServiceResponseError::request_error(error.code(), error)
     }
 }
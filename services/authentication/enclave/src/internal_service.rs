@@ -16,29 +16,70 @@
 // under the License.
 
 use crate::user_db::DbClient;
-use crate::user_info::UserInfo;
+use crate::user_info::{Claims, UserInfo};
+use anyhow::{anyhow, Result};
+use ring::signature::{Ed25519KeyPair, KeyPair};
 use std::prelude::v1::*;
+use std::sync::Arc;
 use teaclave_proto::teaclave_authentication_service::{
-    TeaclaveAuthenticationInternal, UserAuthenticateRequest, UserAuthenticateResponse,
+    SignApprovalReceiptRequest, SignApprovalReceiptResponse, TeaclaveAuthenticationInternal,
+    UserAuthenticateRequest, UserAuthenticateResponse,
 };
 use teaclave_rpc::Request;
 use teaclave_service_enclave_utils::teaclave_service;
-use teaclave_types::TeaclaveServiceResponseResult;
+use teaclave_storage_client::{TeaclaveStorageRouter, TypedStorage};
+use teaclave_types::{ScopedTokenRecord, TeaclaveServiceResponseResult, TrustedTime};
+use uuid::Uuid;
 
 #[teaclave_service(teaclave_authentication_service, TeaclaveAuthenticationInternal)]
 #[derive(Clone)]
 pub(crate) struct TeaclaveAuthenticationInternalService {
     db_client: DbClient,
     jwt_secret: Vec<u8>,
+    receipt_key: Arc<Ed25519KeyPair>,
+    // Scoped tokens are accepted until they expire without the storage, e.g.,
+    // in the unit tests.
+    storage_client: Option<Arc<TeaclaveStorageRouter>>,
 }
 
 impl TeaclaveAuthenticationInternalService {
-    pub(crate) fn new(db_client: DbClient, jwt_secret: Vec<u8>) -> Self {
+    pub(crate) fn new(
+        db_client: DbClient,
+        jwt_secret: Vec<u8>,
+        receipt_key: Arc<Ed25519KeyPair>,
+        storage_client: Arc<TeaclaveStorageRouter>,
+    ) -> Self {
         Self {
             db_client,
             jwt_secret,
+            receipt_key,
+            storage_client: Some(storage_client),
         }
     }
+
+    // A scoped token is only accepted while its record is kept, which expires
+    // along with the token, or is deleted to revoke it.
+    fn is_recorded(&self, claims: &Claims) -> bool {
+        let storage_client = match &self.storage_client {
+            Some(storage_client) => storage_client,
+            None => return true,
+        };
+        let token_id = match claims.jti.as_deref().map(Uuid::parse_str) {
+            Some(Ok(token_id)) => token_id,
+            _ => return false,
+        };
+        storage_client
+            .get_item::<ScopedTokenRecord>(&ScopedTokenRecord::external_id_of(&token_id))
+            .map_or(false, |record| record.user_id == claims.sub)
+    }
+}
+
+/// Generate the key signing approval receipts, which never leaves the enclave.
+pub(crate) fn generate_receipt_key() -> Result<Ed25519KeyPair> {
+    let rng = ring::rand::SystemRandom::new();
+    let pkcs8 =
+        Ed25519KeyPair::generate_pkcs8(&rng).map_err(|_| anyhow!("cannot generate receipt key"))?;
+    Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).map_err(|_| anyhow!("invalid receipt key"))
 }
 
 impl TeaclaveAuthenticationInternal for TeaclaveAuthenticationInternalService {
@@ -54,8 +95,23 @@ impl TeaclaveAuthenticationInternal for TeaclaveAuthenticationInternalService {
             Ok(value) => value,
             Err(_) => return Ok(UserAuthenticateResponse::new(false)),
         };
-        let accept = user.validate_token(&self.jwt_secret, &request.credential.token);
-        Ok(UserAuthenticateResponse::new(accept))
+        match user.decode_token(&self.jwt_secret, &request.credential.token) {
+            Some(claims) if claims.scope.is_none() || self.is_recorded(&claims) => {
+                Ok(UserAuthenticateResponse::new(true).scope(claims.scope))
+            }
+            _ => Ok(UserAuthenticateResponse::new(false)),
+        }
+    }
+
+    fn sign_approval_receipt(
+        &self,
+        request: Request<SignApprovalReceiptRequest>,
+    ) -> TeaclaveServiceResponseResult<SignApprovalReceiptResponse> {
+        let mut receipt = request.message.receipt;
+        receipt.timestamp = TrustedTime::now_secs();
+        receipt.signature = self.receipt_key.sign(&receipt.message()).as_ref().to_vec();
+        receipt.public_key = self.receipt_key.public_key().as_ref().to_vec();
+        Ok(SignApprovalReceiptResponse::new(receipt))
     }
 }
 
@@ -65,11 +121,10 @@ pub mod tests {
     use crate::user_db::*;
     use crate::user_info::*;
     use rand::RngCore;
-    use std::time::{Duration, SystemTime, UNIX_EPOCH};
-    use std::untrusted::time::SystemTimeEx;
     use std::vec;
     use teaclave_proto::teaclave_common::UserCredential;
     use teaclave_rpc::IntoRequest;
+    use teaclave_types::{ApprovalReceipt, TokenScope};
 
     fn get_mock_service() -> TeaclaveAuthenticationInternalService {
         let database = Database::open().unwrap();
@@ -81,24 +136,50 @@ pub mod tests {
         TeaclaveAuthenticationInternalService {
             db_client: database.get_client(),
             jwt_secret,
+            receipt_key: Arc::new(generate_receipt_key().unwrap()),
+            storage_client: None,
         }
     }
 
+    pub fn test_sign_approval_receipt() {
+        let service = get_mock_service();
+        let receipt = ApprovalReceipt::new("mock_user".into(), Default::default(), "mock_hash");
+        let request = SignApprovalReceiptRequest::new(receipt).into_request();
+        let response = service.sign_approval_receipt(request).unwrap();
+        assert!(response.receipt.verify().is_ok());
+
+        let mut receipt = response.receipt;
+        receipt.task_spec_hash = "other_hash".to_string();
+        assert!(receipt.verify().is_err());
+    }
+
     pub fn test_user_authenticate() {
         let id = "test_authenticate_id";
         let service = get_mock_service();
         let user = service.db_client.get_user(id).unwrap();
 
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-        let exp = (now + Duration::from_secs(24 * 60)).as_secs();
+        let exp = TrustedTime::now_secs() + 24 * 60;
         let token = user.get_token(exp, &service.jwt_secret).unwrap();
 
         let response = get_authenticate_response(id, &token, &service);
         assert!(response.accept);
+        assert!(response.scope.is_none());
         let token = validate_token(id, &service.jwt_secret, &token);
         debug!("valid token: {:?}", token.unwrap());
     }
 
+    pub fn test_scoped_token() {
+        let id = "test_authenticate_id";
+        let service = get_mock_service();
+        let mut my_claims = get_correct_claim(id);
+        let scope = TokenScope::new(vec!["approve_task".to_string()], vec![]);
+        my_claims.scope = Some(scope.clone());
+        let token = gen_token(my_claims, None, &service.jwt_secret);
+        let response = get_authenticate_response(id, &token, &service);
+        assert!(response.accept);
+        assert_eq!(response.scope, Some(scope));
+    }
+
     pub fn test_invalid_algorithm() {
         let id = "test_authenticate_id";
         let service = get_mock_service();
@@ -182,14 +263,13 @@ pub mod tests {
     }
 
     fn get_correct_claim(id: &str) -> Claims {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = TrustedTime::now_secs();
         Claims {
             sub: id.to_string(),
             iss: ISSUER_NAME.to_string(),
             exp: now + 24 * 60,
+            scope: None,
+            jti: None,
         }
     }
 
@@ -45,7 +45,8 @@ use teaclave_proto::teaclave_authentication_service::{
 };
 use teaclave_rpc::config::SgxTrustedTlsServerConfig;
 use teaclave_rpc::server::SgxTrustedTlsServer;
-use teaclave_service_enclave_utils::ServiceEnclave;
+use teaclave_service_enclave_utils::{create_trusted_storage_endpoint, ServiceEnclave};
+use teaclave_storage_client::{connect_router, TeaclaveStorageRouter};
 use teaclave_types::{EnclaveInfo, TeeServiceError, TeeServiceResult};
 
 mod api_service;
@@ -53,11 +54,14 @@ mod error;
 mod internal_service;
 mod user_db;
 mod user_info;
+mod verification;
 
 fn start_internal_endpoint(
     addr: std::net::SocketAddr,
     db_client: user_db::DbClient,
     jwt_secret: Vec<u8>,
+    receipt_key: Arc<ring::signature::Ed25519KeyPair>,
+    storage_client: Arc<TeaclaveStorageRouter>,
     attested_tls_config: Arc<RwLock<AttestedTlsConfig>>,
     accepted_enclave_attrs: Vec<teaclave_types::EnclaveAttr>,
 ) -> Result<()> {
@@ -73,8 +77,12 @@ fn start_internal_endpoint(
         TeaclaveAuthenticationInternalRequest,
     >::new(addr, server_config);
 
-    let service =
-        internal_service::TeaclaveAuthenticationInternalService::new(db_client, jwt_secret);
+    let service = internal_service::TeaclaveAuthenticationInternalService::new(
+        db_client,
+        jwt_secret,
+        receipt_key,
+        storage_client,
+    );
 
     match server.start(service) {
         Ok(_) => Ok(()),
@@ -89,6 +97,8 @@ fn start_api_endpoint(
     addr: std::net::SocketAddr,
     db_client: user_db::DbClient,
     jwt_secret: Vec<u8>,
+    storage_client: Arc<TeaclaveStorageRouter>,
+    registration: Option<verification::RegistrationVerification>,
     attested_tls_config: Arc<RwLock<AttestedTlsConfig>>,
 ) -> Result<()> {
     let server_config = SgxTrustedTlsServerConfig::from_attested_tls_config(attested_tls_config)?;
@@ -98,7 +108,12 @@ fn start_api_endpoint(
         TeaclaveAuthenticationApiRequest,
     >::new(addr, server_config);
 
-    let service = api_service::TeaclaveAuthenticationApiService::new(db_client, jwt_secret);
+    let service = api_service::TeaclaveAuthenticationApiService::new(
+        db_client,
+        jwt_secret,
+        storage_client,
+        registration,
+    );
 
     match server.start(service) {
         Ok(_) => Ok(()),
@@ -134,14 +149,52 @@ fn start_service(config: &RuntimeConfig) -> Result<()> {
     let mut rng = rand::thread_rng();
     rng.fill_bytes(&mut api_jwt_secret);
     let internal_jwt_secret = api_jwt_secret.to_owned();
+    let receipt_key = Arc::new(internal_service::generate_receipt_key()?);
+
+    // Records of the scoped tokens are kept in the storage.
+    let storage_endpoint = create_trusted_storage_endpoint(
+        &config.internal_endpoints.storage.advertised_address,
+        &enclave_info,
+        AS_ROOT_CA_CERT,
+        verifier::universal_quote_verifier,
+        attested_tls_config.clone(),
+    )?;
+    let storage_shard_endpoints = config
+        .storage_shards
+        .iter()
+        .map(|shard| {
+            let endpoint = create_trusted_storage_endpoint(
+                &shard.advertised_address,
+                &enclave_info,
+                AS_ROOT_CA_CERT,
+                verifier::universal_quote_verifier,
+                attested_tls_config.clone(),
+            )?;
+            Ok((shard.key_prefixes.clone(), endpoint))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let storage_client = Arc::new(connect_router(
+        &storage_endpoint,
+        &storage_shard_endpoints,
+        &[],
+    )?);
+
+    let registration = config
+        .registration
+        .as_ref()
+        .map(verification::RegistrationVerification::new)
+        .transpose()?;
 
     let attested_tls_config_ref = attested_tls_config.clone();
     let client = database.get_client();
+    let storage_client_ref = storage_client.clone();
     let api_endpoint_thread_handler = thread::spawn(move || {
         let _ = start_api_endpoint(
             api_listen_address,
             client,
             api_jwt_secret,
+            storage_client_ref,
+            registration,
             attested_tls_config_ref,
         );
     });
@@ -152,6 +205,8 @@ fn start_service(config: &RuntimeConfig) -> Result<()> {
             internal_listen_address,
             client,
             internal_jwt_secret,
+            receipt_key,
+            storage_client,
             attested_tls_config,
             accepted_enclave_attrs,
         );
@@ -206,12 +261,19 @@ pub mod tests {
         run_tests!(
             api_service::tests::test_user_login,
             api_service::tests::test_user_register,
+            api_service::tests::test_mint_scoped_token,
+            api_service::tests::test_confirm_registration,
+            api_service::tests::test_lapsed_registration,
+            verification::tests::test_generate_code,
+            verification::tests::test_parse_status,
             internal_service::tests::test_user_authenticate,
+            internal_service::tests::test_scoped_token,
             internal_service::tests::test_invalid_algorithm,
             internal_service::tests::test_invalid_issuer,
             internal_service::tests::test_expired_token,
             internal_service::tests::test_invalid_user,
             internal_service::tests::test_wrong_secret,
+            internal_service::tests::test_sign_approval_receipt,
         )
     }
 }
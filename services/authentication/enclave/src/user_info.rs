@@ -18,16 +18,19 @@
 use anyhow::Result;
 use jsonwebtoken as jwt;
 use rand::prelude::RngCore;
-use ring::{digest, pbkdf2};
+use ring::{constant_time, digest, pbkdf2};
 use serde::{Deserialize, Serialize};
 use std::num;
 use std::prelude::v1::*;
 use std::vec;
+use teaclave_types::{ScopedTokenRecord, TokenScope, TrustedTime};
 
 const SALT_LEN: usize = 16;
 const PASSWORD_DIGEST_LEN: usize = digest::SHA512_OUTPUT_LEN;
 const PBKDF2_ITERATIONS: u32 = 100_000;
 static PBKDF2_ALG: pbkdf2::Algorithm = pbkdf2::PBKDF2_HMAC_SHA512;
+// Wrong codes a pending registration is given up after.
+const MAX_CONFIRMATION_ATTEMPTS: u32 = 5;
 
 pub(crate) const ISSUER_NAME: &str = "Teaclave";
 pub(crate) static JWT_ALG: jwt::Algorithm = jwt::Algorithm::HS512;
@@ -38,6 +41,16 @@ pub(crate) struct UserInfo {
     pub id: String,
     pub salt: Vec<u8>,
     pub salted_password_hash: Vec<u8>,
+    // registration not confirmed yet, if it is verified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending: Option<PendingRegistration>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub(crate) struct PendingRegistration {
+    pub code_digest: Vec<u8>,
+    pub expires_at: u64,
+    pub attempts: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -48,6 +61,12 @@ pub(crate) struct Claims {
     pub iss: String,
     // expiration time
     pub exp: u64,
+    // operations and resources a down-scoped token is restricted to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<TokenScope>,
+    // id of the record of a scoped token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
 }
 
 impl UserInfo {
@@ -68,6 +87,54 @@ impl UserInfo {
             id: id.to_string(),
             salt,
             salted_password_hash,
+            pending: None,
+        }
+    }
+
+    /// Creates a user who cannot log in until the code is confirmed.
+    pub(crate) fn new_pending(id: &str, password: &str, code: &str, expires_at: u64) -> Self {
+        let pending = PendingRegistration {
+            code_digest: digest::digest(&digest::SHA256, code.as_bytes())
+                .as_ref()
+                .to_vec(),
+            expires_at,
+            attempts: 0,
+        };
+        Self {
+            pending: Some(pending),
+            ..Self::new(id, password)
+        }
+    }
+
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Whether the registration can no longer be confirmed, so that the user
+    /// id can be registered again.
+    pub(crate) fn is_registration_lapsed(&self, now: u64) -> bool {
+        self.pending.as_ref().map_or(false, |pending| {
+            pending.expires_at <= now || pending.attempts >= MAX_CONFIRMATION_ATTEMPTS
+        })
+    }
+
+    /// Confirms the pending registration with the code sent to the user. A
+    /// wrong code counts as an attempt.
+    pub(crate) fn confirm_registration(&mut self, code: &str, now: u64) -> bool {
+        if self.is_registration_lapsed(now) {
+            return false;
+        }
+        let pending = match &mut self.pending {
+            Some(pending) => pending,
+            None => return false,
+        };
+        let digest = digest::digest(&digest::SHA256, code.as_bytes());
+        if constant_time::verify_slices_are_equal(digest.as_ref(), &pending.code_digest).is_ok() {
+            self.pending = None;
+            true
+        } else {
+            pending.attempts += 1;
+            false
         }
     }
 
@@ -84,11 +151,34 @@ impl UserInfo {
     }
 
     pub(crate) fn get_token(&self, exp: u64, secret: &[u8]) -> Result<String> {
+        self.encode_token(exp, None, None, secret)
+    }
+
+    /// Mints a token of the scope, which expires along with its record.
+    pub(crate) fn get_scoped_token(
+        &self,
+        record: &ScopedTokenRecord,
+        scope: TokenScope,
+        secret: &[u8],
+    ) -> Result<String> {
+        let jti = Some(record.token_id.to_string());
+        self.encode_token(record.expires_at, Some(scope), jti, secret)
+    }
+
+    fn encode_token(
+        &self,
+        exp: u64,
+        scope: Option<TokenScope>,
+        jti: Option<String>,
+        secret: &[u8],
+    ) -> Result<String> {
         let iss = ISSUER_NAME.to_string();
         let claims = Claims {
             sub: self.id.to_string(),
             iss,
             exp,
+            scope,
+            jti,
         };
         let mut header = jwt::Header::default();
         header.alg = JWT_ALG;
@@ -97,10 +187,25 @@ impl UserInfo {
     }
 
     pub(crate) fn validate_token(&self, secret: &[u8], token: &str) -> bool {
+        self.decode_token(secret, token).is_some()
+    }
+
+    /// Returns the claims of a valid token of the user.
+    pub(crate) fn decode_token(&self, secret: &[u8], token: &str) -> Option<Claims> {
         let iss = ISSUER_NAME.to_string();
         let mut validation = jwt::Validation::new(JWT_ALG);
         validation.iss = Some(iss);
         validation.sub = Some(self.id.to_string());
-        jwt::decode::<Claims>(token, secret, &validation).is_ok()
+        // Expiry is checked against the trusted time instead of the clock of
+        // the platform.
+        validation.validate_exp = false;
+        let claims = jwt::decode::<Claims>(token, secret, &validation)
+            .ok()?
+            .claims;
+        if claims.exp >= TrustedTime::now_secs() {
+            Some(claims)
+        } else {
+            None
+        }
     }
 }
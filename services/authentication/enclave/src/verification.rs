@@ -0,0 +1,210 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Out-of-band verification of the users registering themselves. A code is
+//! delivered to the contact given at the registration, and the user stays
+//! pending until the code is confirmed.
+
+use anyhow::{anyhow, bail, ensure, Result};
+use rand::Rng;
+use rustls::internal::pemfile;
+use serde_json::json;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::prelude::v1::*;
+use std::sync::Arc;
+use std::time::Duration;
+use teaclave_config::{RegistrationConfig, RegistrationVerifierKind};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+// Only the status of the response is read.
+const MAX_WEBHOOK_RESPONSE_SIZE: u64 = 64 * 1024;
+const CODE_DIGITS: usize = 8;
+
+/// Delivers the verification codes to the users, e.g., by email.
+pub(crate) trait RegistrationVerifier: Send + Sync {
+    fn send_code(&self, user_id: &str, contact: &str, code: &str) -> Result<()>;
+}
+
+#[derive(Clone)]
+pub(crate) struct RegistrationVerification {
+    pub verifier: Arc<dyn RegistrationVerifier>,
+    /// Seconds a code can be confirmed in
+    pub code_ttl: u64,
+}
+
+impl RegistrationVerification {
+    pub(crate) fn new(config: &RegistrationConfig) -> Result<Self> {
+        let verifier = match config.verifier {
+            RegistrationVerifierKind::Webhook => {
+                WebhookVerifier::new(&config.address, &config.path, &config.server_cert_bytes)?
+            }
+        };
+        Ok(Self {
+            verifier: Arc::new(verifier),
+            code_ttl: config.code_ttl,
+        })
+    }
+}
+
+/// Generates a random numeric code, which users can type in easily.
+pub(crate) fn generate_code() -> String {
+    let bound = 10u32.pow(CODE_DIGITS as u32);
+    let code = rand::thread_rng().gen_range(0, bound);
+    format!("{:0width$}", code, width = CODE_DIGITS)
+}
+
+struct WebhookVerifier {
+    address: String,
+    hostname: webpki::DNSName,
+    path: String,
+    tls_config: Arc<rustls::ClientConfig>,
+}
+
+impl WebhookVerifier {
+    fn new(address: &str, path: &str, server_cert: &[u8]) -> Result<Self> {
+        let hostname = address
+            .rsplitn(2, ':')
+            .nth(1)
+            .ok_or_else(|| anyhow!("webhook address without port: {}", address))?;
+        let hostname = webpki::DNSNameRef::try_from_ascii_str(hostname)
+            .map_err(|_| anyhow!("webhook host is not a DNS name: {}", hostname))?
+            .to_owned();
+        let pinned_cert = pemfile::certs(&mut &server_cert[..])
+            .ok()
+            .and_then(|certs| certs.into_iter().next())
+            .ok_or_else(|| anyhow!("invalid webhook server certificate"))?;
+
+        let mut tls_config = rustls::ClientConfig::new();
+        tls_config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(PinnedCertVerifier { pinned_cert }));
+
+        Ok(Self {
+            address: address.to_owned(),
+            hostname,
+            path: path.trim_start_matches('/').to_owned(),
+            tls_config: Arc::new(tls_config),
+        })
+    }
+}
+
+impl RegistrationVerifier for WebhookVerifier {
+    // Posts the code over HTTP/1.0, so that the response is neither chunked
+    // nor kept alive. The code is sent once a 2xx status is responded.
+    fn send_code(&self, user_id: &str, contact: &str, code: &str) -> Result<()> {
+        let body = json!({
+            "user_id": user_id,
+            "contact": contact,
+            "code": code,
+        })
+        .to_string();
+
+        let stream = TcpStream::connect(&self.address)?;
+        stream.set_read_timeout(Some(WEBHOOK_TIMEOUT))?;
+        stream.set_write_timeout(Some(WEBHOOK_TIMEOUT))?;
+        let session = rustls::ClientSession::new(&self.tls_config, self.hostname.as_ref());
+        let mut stream = rustls::StreamOwned::new(session, stream);
+        write!(
+            stream,
+            "POST /{} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            self.path,
+            self.address,
+            body.len(),
+            body
+        )?;
+        stream.flush()?;
+
+        let mut response = Vec::new();
+        stream
+            .take(MAX_WEBHOOK_RESPONSE_SIZE)
+            .read_to_end(&mut response)?;
+        let status = parse_status(&response)?;
+        ensure!(
+            status >= 200 && status < 300,
+            "webhook responded with status {}",
+            status
+        );
+
+        Ok(())
+    }
+}
+
+/// Accepts the certificate pinned in the config only, so that the codes are
+/// sent to the verifier rather than anyone the host redirects them to.
+struct PinnedCertVerifier {
+    pinned_cert: rustls::Certificate,
+}
+
+impl rustls::ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _roots: &rustls::RootCertStore,
+        certs: &[rustls::Certificate],
+        _hostname: webpki::DNSNameRef<'_>,
+        _ocsp: &[u8],
+    ) -> std::result::Result<rustls::ServerCertVerified, rustls::TLSError> {
+        match certs.first() {
+            Some(cert) if *cert == self.pinned_cert => Ok(rustls::ServerCertVerified::assertion()),
+            Some(_) => Err(rustls::TLSError::WebPKIError(webpki::Error::UnknownIssuer)),
+            None => Err(rustls::TLSError::NoCertificatesPresented),
+        }
+    }
+}
+
+/// Parses the status code from the status line of an HTTP response, e.g.,
+/// `HTTP/1.1 200 OK`.
+fn parse_status(response: &[u8]) -> Result<u16> {
+    let response = String::from_utf8_lossy(response);
+    let status_line = match response.lines().next() {
+        Some(line) if !line.is_empty() => line,
+        _ => bail!("empty webhook response"),
+    };
+    let mut parts = status_line.splitn(3, ' ');
+    let status = match (parts.next(), parts.next()) {
+        (Some(version), Some(status)) if version.starts_with("HTTP/") && status.len() == 3 => {
+            status.parse::<u16>().ok()
+        }
+        _ => None,
+    };
+    status.ok_or_else(|| anyhow!("malformed webhook status line: {}", status_line))
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_generate_code() {
+        let code = generate_code();
+        assert_eq!(code.len(), CODE_DIGITS);
+        assert!(code.chars().all(|c| c.is_ascii_digit()));
+    }
+
+    pub fn test_parse_status() {
+        assert_eq!(parse_status(b"HTTP/1.1 200 OK\r\n\r\n").unwrap(), 200);
+        assert_eq!(parse_status(b"HTTP/1.0 204 No Content\r\n").unwrap(), 204);
+        assert_eq!(parse_status(b"HTTP/1.1 403 Forbidden\r\n").unwrap(), 403);
+        assert_eq!(parse_status(b"HTTP/1.1 500\r\n").unwrap(), 500);
+
+        assert!(parse_status(b"").is_err());
+        assert!(parse_status(b"\r\nHTTP/1.1 200 OK\r\n").is_err());
+        assert!(parse_status(b"200 OK\r\n").is_err());
+        assert!(parse_status(b"HTTP/1.1 2xx OK\r\n").is_err());
+        assert!(parse_status(b"HTTP/1.1 20 OK\r\n").is_err());
+        assert!(parse_status(b"HTTP/1.1\r\n").is_err());
+    }
+}
@@ -19,6 +19,8 @@ use anyhow::{Context, Result};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
+use teaclave_config::RuntimeConfig;
+use teaclave_file_agent::{setup_cgroups, start_push_server};
 use teaclave_service_app_utils::{register_signals, TeaclaveServiceLauncher};
 
 // Use to import ocall
@@ -33,10 +35,21 @@ fn main() -> Result<()> {
             .write_style_or("TEACLAVE_LOG_STYLE", "RUST_LOG_STYLE"),
     );
 
-    let launcher = Arc::new(TeaclaveServiceLauncher::new(
-        PACKAGE_NAME,
-        "runtime.config.toml",
-    )?);
+    let config =
+        RuntimeConfig::from_toml("runtime.config.toml").context("Failed to load config file.")?;
+    // Clients push inputs to the host, which are then downloaded by the
+    // enclave through the file agent.
+    if let Some(push_inputs) = &config.push_inputs {
+        start_push_server(push_inputs.listen_address)
+            .context("Failed to start the upload endpoint of pushed inputs")?;
+    }
+    // Enclave host threads and the transfers of the file agent are weighted
+    // by the priority of their tasks.
+    if let Some(cgroups) = &config.cgroups {
+        setup_cgroups(cgroups).context("Failed to set up the cgroups of task classes")?;
+    }
+
+    let launcher = Arc::new(TeaclaveServiceLauncher::with_config(PACKAGE_NAME, config)?);
     let launcher_ref = launcher.clone();
     thread::spawn(move || {
         let _ = launcher_ref.start();
@@ -36,7 +36,7 @@ use teaclave_config::build::{AS_ROOT_CA_CERT, AUDITOR_PUBLIC_KEYS};
 use teaclave_config::RuntimeConfig;
 use teaclave_service_enclave_utils::create_trusted_scheduler_endpoint;
 use teaclave_service_enclave_utils::ServiceEnclave;
-use teaclave_types::{EnclaveInfo, TeeServiceError, TeeServiceResult};
+use teaclave_types::{EnclaveInfo, ResourceProfile, TeeServiceError, TeeServiceResult};
 
 mod ocall;
 mod service;
@@ -59,7 +59,7 @@ fn start_service(config: &RuntimeConfig) -> Result<()> {
         &enclave_info,
         AS_ROOT_CA_CERT,
         verifier::universal_quote_verifier,
-        attested_tls_config,
+        attested_tls_config.clone(),
     )?;
 
     let fusion_base = config.mount.fusion_base_dir.clone();
@@ -75,8 +75,24 @@ fn start_service(config: &RuntimeConfig) -> Result<()> {
         fusion_base.display()
     );
 
-    let mut service =
-        service::TeaclaveExecutionService::new(scheduler_service_endpoint, fusion_base)?;
+    let capacity = ResourceProfile::new()
+        .heap_size(config.limits.max_task_heap_size)
+        .input_size(config.limits.max_task_disk_usage)
+        .timeout(config.limits.max_task_timeout);
+    let mut service = service::TeaclaveExecutionService::new(
+        scheduler_service_endpoint,
+        attested_tls_config,
+        fusion_base,
+        config.limits.max_task_disk_usage,
+        config.limits.max_tenant_disk_usage,
+        config.limits.function_payload_cache_size,
+        config.limits.tenant_payload_cache_size,
+        capacity,
+    )?
+    .timeout_grace_period(config.limits.task_timeout_grace_period);
+    if let Some(push_inputs) = &config.push_inputs {
+        service = service.push_inputs_endpoint(url::Url::parse(&push_inputs.advertised_url)?);
+    }
     let _ = service.start();
 
     Ok(())
@@ -122,7 +138,12 @@ pub mod tests {
             ocall::tests::test_handle_file_request,
             service::tests::test_invoke_echo,
             service::tests::test_invoke_gbdt_train,
+            service::tests::test_check_resource_profile,
+            service::tests::test_check_resource_limits,
+            service::tests::test_task_deadline,
             task_file_manager::tests::test_input,
+            task_file_manager::tests::test_disk_quota_and_cleanup,
+            task_file_manager::tests::test_prefetch_inputs,
         )
     }
 }
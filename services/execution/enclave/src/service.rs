@@ -15,33 +15,73 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
 use std::prelude::v1::*;
-use std::sync::{Arc, SgxMutex as Mutex};
-
-use crate::task_file_manager::TaskFileManager;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::{Arc, SgxMutex as Mutex, SgxRwLock as RwLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::task_file_manager::{
+    is_pushed, open_push_slots, prefetch_inputs, remove_prefetched_inputs, sweep_stale_task_files,
+    sweep_task_files, TaskFileManager, TenantDir,
+};
+use teaclave_attestation::key::NistP256KeyPair;
+use teaclave_attestation::AttestedTlsConfig;
+use teaclave_proto::teaclave_key_broker_service::{ReleaseKeyRequest, TeaclaveKeyBrokerClient};
 use teaclave_proto::teaclave_scheduler_service::*;
+use teaclave_rpc::config::SgxTrustedTlsClientConfig;
 use teaclave_rpc::endpoint::Endpoint;
 use teaclave_types::*;
 use teaclave_worker::Worker;
 
 use anyhow::Result;
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
+use url::Url;
 use uuid::Uuid;
 
 static WORKER_BASE_DIR: &str = "/tmp/teaclave_agent/";
+// Prewarmed tasks which are not staged yet, the oldest one is dropped first.
+// A tenant holding its share of prewarmed tasks drops its own oldest one.
+const MAX_PREWARMED_TASKS: usize = 4;
+const MAX_TENANT_PREWARMED_TASKS: usize = 2;
+// A task exchanging keys fails if the participants do not wrap the keys of
+// all its files for the offer in time.
+const TASK_KEYS_POLL_SECS: u64 = 3;
+const TASK_KEYS_TIMEOUT_SECS: u64 = 600;
+// Function tests have a small scratch disk of their own.
+const FUNCTION_TEST_DISK_QUOTA: u64 = 4 * 1024 * 1024;
 
 #[derive(Clone)]
 pub(crate) struct TeaclaveExecutionService {
     worker: Arc<Worker>,
     scheduler_client: Arc<Mutex<TeaclaveSchedulerClient>>,
+    attested_tls_config: Arc<RwLock<AttestedTlsConfig>>,
     fusion_base: PathBuf,
+    disk_quota: u64,
+    tenant_disk_quota: u64,
+    capacity: ResourceProfile,
+    timeout_grace_period: u64,
+    prewarmed_tasks: Vec<PrewarmTask>,
+    push_inputs_endpoint: Option<Url>,
+    worker_id: Arc<Mutex<Option<Uuid>>>,
+    running_tasks: Arc<AtomicU32>,
 }
 
 impl TeaclaveExecutionService {
     pub(crate) fn new(
         scheduler_service_endpoint: Endpoint,
+        attested_tls_config: Arc<RwLock<AttestedTlsConfig>>,
         fusion_base: impl AsRef<Path>,
+        disk_quota: u64,
+        tenant_disk_quota: u64,
+        payload_cache_size: usize,
+        tenant_payload_cache_size: usize,
+        capacity: ResourceProfile,
     ) -> Result<Self> {
         let mut i = 0;
         let channel = loop {
@@ -58,28 +98,74 @@ impl TeaclaveExecutionService {
         let scheduler_client = Arc::new(Mutex::new(TeaclaveSchedulerClient::new(channel)?));
 
         Ok(TeaclaveExecutionService {
-            worker: Arc::new(Worker::default()),
+            worker: Arc::new(
+                Worker::default().payload_cache_size(payload_cache_size, tenant_payload_cache_size),
+            ),
             scheduler_client,
+            attested_tls_config,
             fusion_base: fusion_base.as_ref().to_owned(),
+            disk_quota,
+            tenant_disk_quota,
+            capacity,
+            timeout_grace_period: 0,
+            prewarmed_tasks: Vec::new(),
+            push_inputs_endpoint: None,
+            worker_id: Arc::new(Mutex::new(None)),
+            running_tasks: Arc::new(AtomicU32::new(0)),
         })
     }
 
+    /// Sets the seconds a timed out function is given to finish writing its
+    /// outputs.
+    pub(crate) fn timeout_grace_period(self, timeout_grace_period: u64) -> Self {
+        Self {
+            timeout_grace_period,
+            ..self
+        }
+    }
+
+    /// Sets the upload endpoint of the host given to clients pushing inputs.
+    pub(crate) fn push_inputs_endpoint(self, endpoint: Url) -> Self {
+        Self {
+            push_inputs_endpoint: Some(endpoint),
+            ..self
+        }
+    }
+
     pub(crate) fn start(&mut self) -> Result<()> {
+        sweep_stale_task_files(WORKER_BASE_DIR)?;
+        self.start_heartbeats();
+
         loop {
             std::thread::sleep(std::time::Duration::from_secs(3));
             let staged_task = match self.pull_task() {
                 Ok(staged_task) => staged_task,
                 Err(e) => {
                     log::warn!("PullTask Error: {:?}", e);
+                    // Prepare upcoming tasks while idle.
+                    self.prewarm();
                     continue;
                 }
             };
 
             log::debug!("InvokeTask: {:?}", staged_task);
-            let result = self.invoke_task(&staged_task);
+            self.running_tasks.fetch_add(1, Ordering::Relaxed);
+            let started = TrustedTime::now();
+            let mut usage = TaskUsage::default();
+            let result = self.invoke_task(&staged_task, &mut usage);
+            self.running_tasks.fetch_sub(1, Ordering::Relaxed);
             log::debug!("InvokeTask result: {:?}", result);
 
-            match self.update_task_result(&staged_task.task_id, result) {
+            let tenant_dir = self.tenant_dir(&staged_task.creator);
+            if let Err(e) = sweep_task_files(&tenant_dir, &staged_task.task_id) {
+                log::error!("SweepTaskFiles Error: {:?}", e);
+            }
+
+            usage.run_time = TrustedTime::now()
+                .duration_since(started)
+                .map(|run_time| run_time.as_millis() as u64)
+                .unwrap_or_default();
+            match self.update_task_result(&staged_task.task_id, result, usage) {
                 Ok(_) => (),
                 Err(e) => {
                     log::error!("UpdateResult Error: {:?}", e);
@@ -89,8 +175,84 @@ impl TeaclaveExecutionService {
         }
     }
 
+    // Registers the worker with the scheduler, and keeps it registered with
+    // heartbeats from a thread of their own, which go on while a function
+    // runs. The worker registers again if the scheduler has lost track of it,
+    // e.g. after a restart.
+    fn start_heartbeats(&self) {
+        let service = self.clone();
+        thread::spawn(move || {
+            let mut interval = WORKER_HEARTBEAT_INTERVAL;
+            loop {
+                if let Err(e) = service.heartbeat() {
+                    log::debug!("Heartbeat Error: {:?}", e);
+                    match service.register_worker() {
+                        Ok(heartbeat_interval) => interval = heartbeat_interval,
+                        Err(e) => log::warn!("RegisterWorker Error: {:?}", e),
+                    }
+                }
+                thread::sleep(Duration::from_secs(interval));
+            }
+        });
+    }
+
+    fn register_worker(&self) -> Result<u64> {
+        let executors = self
+            .worker
+            .executors()
+            .into_iter()
+            .map(|(executor_type, executor)| WorkerExecutor::new(executor_type, executor))
+            .collect();
+        let capabilities = WorkerCapabilities::new(executors, self.capacity);
+        let request = RegisterWorkerRequest::new(capabilities);
+        let response = self
+            .scheduler_client
+            .clone()
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Cannot lock scheduler client"))?
+            .register_worker(request)?;
+
+        log::info!(
+            "RegisterWorker: registered as worker {}",
+            response.worker_id
+        );
+        *self
+            .worker_id
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Cannot lock worker id"))? = Some(response.worker_id);
+        Ok(response.heartbeat_interval)
+    }
+
+    fn heartbeat(&self) -> Result<()> {
+        let worker_id = self
+            .registered_worker_id()?
+            .ok_or_else(|| anyhow::anyhow!("Worker not registered"))?;
+        let load = self.running_tasks.load(Ordering::Relaxed);
+        let request = HeartbeatRequest::new(worker_id, load);
+        let _response = self
+            .scheduler_client
+            .clone()
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Cannot lock scheduler client"))?
+            .heartbeat(request)?;
+        Ok(())
+    }
+
+    fn registered_worker_id(&self) -> Result<Option<Uuid>> {
+        let worker_id = self
+            .worker_id
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Cannot lock worker id"))?;
+        Ok(*worker_id)
+    }
+
+    // Tasks pulled before the worker is registered are not queued again if
+    // the worker is lost.
     fn pull_task(&mut self) -> Result<StagedTask> {
-        let request = PullTaskRequest {};
+        let request = match self.registered_worker_id()? {
+            Some(worker_id) => PullTaskRequest::new().worker_id(worker_id),
+            None => PullTaskRequest::new(),
+        };
         let response = self
             .scheduler_client
             .clone()
@@ -102,33 +264,397 @@ impl TeaclaveExecutionService {
         Ok(response.staged_task)
     }
 
-    fn invoke_task(&mut self, task: &StagedTask) -> Result<TaskOutputs> {
-        self.update_task_status(&task.task_id, TaskStatus::Running)?;
+    fn pull_prewarm_task(&mut self) -> Result<PrewarmTask> {
+        let request = PullPrewarmTaskRequest {};
+        let response = self
+            .scheduler_client
+            .clone()
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Cannot lock scheduler client"))?
+            .pull_prewarm_task(request)?;
 
-        let file_mgr = TaskFileManager::new(
+        log::debug!("pull_prewarm_task response: {:?}", response);
+        Ok(response.prewarm_task)
+    }
+
+    fn prewarm(&mut self) {
+        let prewarm_task = match self.pull_prewarm_task() {
+            Ok(prewarm_task) => prewarm_task,
+            Err(_) => return,
+        };
+
+        log::debug!("PrewarmTask: {:?}", prewarm_task);
+        let tenant_dir = self.tenant_dir(&prewarm_task.creator);
+        if let Err(e) = prewarm_task_on_worker(
+            &self.worker,
+            &tenant_dir,
+            &self.fusion_base,
+            &prewarm_task,
+            self.disk_quota,
+        ) {
+            log::warn!("PrewarmTask Error: {:?}", e);
+            if let Err(e) = remove_prefetched_inputs(&tenant_dir, &prewarm_task.task_id) {
+                log::error!("RemovePrefetchedInputs Error: {:?}", e);
+            }
+            return;
+        }
+
+        let tenant_tasks = self
+            .prewarmed_tasks
+            .iter()
+            .filter(|task| task.creator == prewarm_task.creator)
+            .count();
+        let position = if tenant_tasks >= MAX_TENANT_PREWARMED_TASKS {
+            self.prewarmed_tasks
+                .iter()
+                .position(|task| task.creator == prewarm_task.creator)
+        } else if self.prewarmed_tasks.len() >= MAX_PREWARMED_TASKS {
+            Some(0)
+        } else {
+            None
+        };
+        if let Some(position) = position {
+            let evicted = self.prewarmed_tasks.remove(position);
+            let tenant_dir = self.tenant_dir(&evicted.creator);
+            if let Err(e) = remove_prefetched_inputs(&tenant_dir, &evicted.task_id) {
+                log::error!("RemovePrefetchedInputs Error: {:?}", e);
+            }
+        }
+        self.prewarmed_tasks.push(prewarm_task);
+    }
+
+    fn take_prewarmed_task(&mut self, task: &StagedTask) -> Option<PrewarmTask> {
+        let position = self.prewarmed_tasks.iter().position(|prewarm_task| {
+            prewarm_task.task_id == task.task_id && prewarm_task.creator == task.creator
+        })?;
+        Some(self.prewarmed_tasks.remove(position))
+    }
+
+    // Scratch files are isolated by the creator of the task.
+    fn tenant_dir(&self, creator: &UserID) -> TenantDir {
+        TenantDir::new(
             WORKER_BASE_DIR,
+            &creator.tenant_id(),
+            self.tenant_disk_quota,
+        )
+    }
+
+    // The size of the inputs is recorded in the usage of the task once they
+    // are fetched.
+    fn invoke_task(&mut self, task: &StagedTask, usage: &mut TaskUsage) -> Result<TaskOutputs> {
+        self.update_task_status(&task.task_id, TaskStatus::Running)?;
+        // Fail the task before fetching any input if the worker cannot afford
+        // the resources declared by the function.
+        task.resource_profile.check(&self.capacity)?;
+        task.resource_limits.check_memory(&task.resource_profile)?;
+        if let Some(function_test) = &task.function_test {
+            return self.invoke_function_test(task, function_test);
+        }
+
+        let (input_data, output_data) = if task.key_exchange {
+            self.exchange_task_keys(task)?
+        } else {
+            (task.input_data.clone(), task.output_data.clone())
+        };
+        let input_data = if input_data.iter().any(|(_, file)| file.key_broker.is_some()) {
+            self.release_brokered_keys(task, input_data)?
+        } else {
+            input_data
+        };
+        let input_data = if input_data.iter().any(|(_, file)| is_pushed(file)) {
+            self.open_upload_slots(task, input_data)?
+        } else {
+            input_data
+        };
+        let mut file_mgr = TaskFileManager::new(
+            &self.tenant_dir(&task.creator),
             &self.fusion_base,
             &task.task_id,
-            &task.input_data,
-            &task.output_data,
-        )?;
-        let invocation = prepare_task(&task, &file_mgr)?;
+            &input_data,
+            &output_data,
+            self.disk_quota,
+        )?
+        .priority(task.priority);
+        if let Some(prewarm_task) = self.take_prewarmed_task(task) {
+            file_mgr = file_mgr.prefetched_inputs(&prewarm_task)?;
+        }
+        let deadline = task_deadline(task, &self.capacity, self.timeout_grace_period);
+        let meter = Some(task.resource_limits)
+            .filter(|limits| !limits.is_unlimited())
+            .map(ResourceMeter::new);
+        let invocation = prepare_task(&task, &file_mgr)?
+            .deadline(deadline.clone())
+            .meter(meter.clone());
+        usage.input_size = file_mgr.input_size().unwrap_or_default();
 
         log::debug!("Invoke function: {:?}", invocation);
-        let worker = Worker::default();
-        let summary = worker.invoke_function(invocation)?;
+        let result = match task.timeout {
+            Some(timeout) => self.invoke_function_within(invocation, timeout),
+            None => invoke_function(&self.worker, invocation),
+        };
+        // The function may have caught the failed access exceeding a limit,
+        // and a function not accessing any file is only metered once done.
+        if let Some(meter) = meter {
+            meter.check_cpu()?;
+            if let Some(resource) = meter.exceeded() {
+                return Err(resource.into());
+            }
+        }
+        let summary = match (result, deadline) {
+            (Ok(summary), _) => summary,
+            (Err(e), Some(deadline)) if deadline.is_timed_out() => {
+                return salvage_task(&file_mgr, &deadline, e);
+            }
+            (Err(e), _) => return Err(e),
+        };
 
         let outputs_tag = finalize_task(&file_mgr)?;
-        let task_outputs = TaskOutputs::new(summary.as_bytes(), outputs_tag);
+        // Only the post-processed return value leaves the enclave.
+        let return_value = post_process(&task.post_processing, summary.as_bytes())?;
+        let task_outputs = TaskOutputs::new(return_value, outputs_tag);
+        Ok(task_outputs)
+    }
+
+    // A function cannot be killed in the enclave, so the invocation of a task
+    // with a timeout runs in a thread of its own, which is abandoned if it
+    // does not return within the timeout and the grace period. The thread
+    // keeps running until the function returns or fails at its next call of
+    // the runtime past the deadline.
+    fn invoke_function_within(&self, invocation: StagedFunction, timeout: u64) -> Result<String> {
+        let (sender, receiver) = channel();
+        let worker = self.worker.clone();
+        thread::spawn(move || {
+            let _ = sender.send(invoke_function(&worker, invocation));
+        });
+        let wait = Duration::from_secs(timeout.saturating_add(self.timeout_grace_period));
+        match receiver.recv_timeout(wait) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                log::warn!("Abandoned function invocation after {}s", timeout);
+                Err(TaskTimedOut(timeout).into())
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(TransientFailure::WorkerCrash.into()),
+        }
+    }
+
+    // Tests run against their sample inputs within the test limits, and
+    // their outputs are returned in the result instead of being uploaded.
+    // Their files are removed along with the file manager as usual.
+    fn invoke_function_test(
+        &mut self,
+        task: &StagedTask,
+        function_test: &FunctionTest,
+    ) -> Result<TaskOutputs> {
+        let file_mgr = TaskFileManager::new(
+            &self.tenant_dir(&task.creator),
+            &self.fusion_base,
+            &task.task_id,
+            &FunctionInputFiles::default(),
+            &FunctionOutputFiles::default(),
+            FUNCTION_TEST_DISK_QUOTA.min(self.disk_quota),
+        )?;
+        let input_files = file_mgr.stage_sample_inputs(&function_test.sample_inputs)?;
+        let output_files = file_mgr.stage_sample_outputs(&function_test.outputs)?;
+        let timeout = FunctionTest::resource_profile(task.resource_profile).timeout;
+        let deadline = TaskDeadline::new(Duration::from_secs(timeout), Duration::from_secs(0));
+        let invocation =
+            prepare_function_test(task, input_files, output_files.clone()).deadline(Some(deadline));
+
+        log::debug!("Invoke function test: {:?}", invocation);
+        // The whole chain of a failure is kept as the log of the test.
+        let summary = self
+            .worker
+            .invoke_function(invocation)
+            .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+
+        let sample_outputs = file_mgr.read_sample_outputs(
+            &output_files,
+            &function_test.outputs,
+            MAX_FUNCTION_TEST_FILE_SIZE,
+        )?;
+        let return_value = post_process(&task.post_processing, summary.as_bytes())?;
+        let task_outputs =
+            TaskOutputs::new(return_value, HashMap::new()).sample_outputs(sample_outputs);
         Ok(task_outputs)
     }
 
+    // Offers an ephemeral key of this enclave for the task, and returns the
+    // files of the task with the keys wrapped for the offer by participants
+    // in place of the registered ones.
+    fn exchange_task_keys(
+        &mut self,
+        task: &StagedTask,
+    ) -> Result<(FunctionInputFiles, FunctionOutputFiles)> {
+        let key_pair = NistP256KeyPair::new()?;
+        let public_key = key_pair.public_key_into_bytes();
+        let offer = {
+            let attested_tls_config = self
+                .attested_tls_config
+                .read()
+                .map_err(|_| anyhow::anyhow!("Cannot lock attested TLS config"))?;
+            let signing_key = EcdsaKeyPair::from_pkcs8(
+                &ECDSA_P256_SHA256_ASN1_SIGNING,
+                &attested_tls_config.private_key,
+            )
+            .map_err(|_| anyhow::anyhow!("Invalid attested TLS key"))?;
+            let message = TaskKeyOffer::message(&task.task_id, &public_key);
+            let signature = signing_key
+                .sign(&SystemRandom::new(), &message)
+                .map_err(|_| anyhow::anyhow!("Cannot sign task key offer"))?;
+            TaskKeyOffer::new(
+                public_key.clone(),
+                attested_tls_config.cert.clone(),
+                signature.as_ref().to_vec(),
+            )
+        };
+        let request = PutTaskKeyOfferRequest::new(task.task_id, offer);
+        self.scheduler_client
+            .clone()
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Cannot lock scheduler client"))?
+            .put_task_key_offer(request)?;
+
+        // Keys of brokered inputs are released by their brokers instead.
+        let fnames: HashSet<&String> = task
+            .input_data
+            .iter()
+            .filter(|(_, file)| file.key_broker.is_none())
+            .map(|(fname, _)| fname)
+            .chain(task.output_data.iter().map(|(fname, _)| fname))
+            .collect();
+        let mut keys: HashMap<String, FileCrypto> = HashMap::new();
+        let mut unwrapped: HashSet<Vec<u8>> = HashSet::new();
+        let mut elapsed = 0;
+        while !fnames.iter().all(|fname| keys.contains_key(*fname)) {
+            anyhow::ensure!(
+                elapsed < TASK_KEYS_TIMEOUT_SECS,
+                "Timed out waiting for the keys of the task"
+            );
+            std::thread::sleep(std::time::Duration::from_secs(TASK_KEYS_POLL_SECS));
+            elapsed += TASK_KEYS_POLL_SECS;
+
+            let request = GetTaskKeysRequest::new(task.task_id);
+            let key_exchange = self
+                .scheduler_client
+                .clone()
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Cannot lock scheduler client"))?
+                .get_task_keys(request)?
+                .key_exchange;
+            for wrapped in key_exchange.wrapped_keys.iter() {
+                if wrapped.offer_public_key != public_key || unwrapped.contains(&wrapped.public_key)
+                {
+                    continue;
+                }
+                let shared_key = key_pair.compute_shared_key(&wrapped.public_key)?;
+                for (fname, crypto) in wrapped.unwrap(&task.task_id, &shared_key)? {
+                    keys.entry(fname).or_insert(crypto);
+                }
+                unwrapped.insert(wrapped.public_key.clone());
+            }
+        }
+
+        let mut input_data = task.input_data.clone();
+        for (fname, file) in input_data.iter_mut() {
+            if file.key_broker.is_none() {
+                file.crypto_info = keys[fname];
+            }
+        }
+        let mut output_data = task.output_data.clone();
+        for (fname, file) in output_data.iter_mut() {
+            file.crypto_info = keys[fname];
+        }
+        Ok((input_data, output_data))
+    }
+
+    // Brokers release the keys of their files to this enclave once they have
+    // verified the quote in its attested certificate, presented as the client
+    // certificate. The brokers are not attested in turn: nobody else can
+    // complete the handshake for the enclave, and a wrong key fails the cmac
+    // check of the file.
+    fn release_brokered_keys(
+        &mut self,
+        task: &StagedTask,
+        mut input_data: FunctionInputFiles,
+    ) -> Result<FunctionInputFiles> {
+        for (fname, file) in input_data.iter_mut() {
+            let key_broker = match &file.key_broker {
+                Some(key_broker) => key_broker,
+                None => continue,
+            };
+            let config = SgxTrustedTlsClientConfig::from_attested_tls_config(
+                self.attested_tls_config.clone(),
+            )?;
+            let channel = Endpoint::new(&key_broker.address)
+                .config(config)
+                .connect()
+                .map_err(|e| {
+                    anyhow::anyhow!("Cannot connect to the key broker of {}: {:?}", fname, e)
+                })?;
+            let request = ReleaseKeyRequest::new(&key_broker.key_id, task.task_id);
+            let response = TeaclaveKeyBrokerClient::new(channel)?
+                .release_key(request)
+                .map_err(|e| anyhow::anyhow!("Key of {} is not released: {:?}", fname, e))?;
+            // Raw files are not checked against their cmacs.
+            if let FileCrypto::Raw = response.crypto_info {
+                anyhow::bail!("Key broker of {} released no key", fname);
+            }
+            file.crypto_info = response.crypto_info;
+        }
+        Ok(input_data)
+    }
+
+    // Opens a slot in the upload endpoint of the host for each input pushed by
+    // clients, and publishes the tokens of the slots to the owners of the
+    // inputs. The inputs are then downloaded from the slots.
+    fn open_upload_slots(
+        &mut self,
+        task: &StagedTask,
+        mut input_data: FunctionInputFiles,
+    ) -> Result<FunctionInputFiles> {
+        let endpoint = self
+            .push_inputs_endpoint
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Pushed inputs are not enabled on this worker"))?;
+        let mut tokens = HashMap::new();
+        for (fname, file) in input_data.iter_mut() {
+            if is_pushed(file) {
+                let token = TaskUploadSlots::new_token()?;
+                file.url = TaskUploadSlots::push_url(&token)?;
+                tokens.insert(fname.to_owned(), token);
+            }
+        }
+        open_push_slots(
+            &self.fusion_base,
+            input_data
+                .iter()
+                .filter(|(_, file)| is_pushed(file))
+                .map(|(_, file)| &file.url),
+        )?;
+
+        let upload_slots = TaskUploadSlots::new(task.task_id, endpoint, tokens);
+        let request = PutTaskUploadSlotsRequest::new(upload_slots);
+        self.scheduler_client
+            .clone()
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Cannot lock scheduler client"))?
+            .put_task_upload_slots(request)?;
+        Ok(input_data)
+    }
+
     fn update_task_result(
         &mut self,
         task_id: &Uuid,
         task_result: Result<TaskOutputs>,
+        usage: TaskUsage,
     ) -> Result<()> {
-        let request = UpdateTaskResultRequest::new(*task_id, task_result);
+        let task_result = match task_result {
+            Ok(outputs) if outputs.return_value.len() > RETURN_VALUE_CHUNK_SIZE => {
+                self.put_return_value_chunks(task_id, outputs)
+            }
+            task_result => task_result,
+        };
+        let request = UpdateTaskResultRequest::new(*task_id, task_result).usage(usage);
 
         let _response = self
             .scheduler_client
@@ -140,6 +666,30 @@ impl TeaclaveExecutionService {
         Ok(())
     }
 
+    // Large return values are put in chunks and replaced by their handles, the
+    // task fails if any chunk cannot be put.
+    fn put_return_value_chunks(
+        &mut self,
+        task_id: &Uuid,
+        outputs: TaskOutputs,
+    ) -> Result<TaskOutputs> {
+        let (handle, chunks) = ReturnValueChunk::split(*task_id, &outputs.return_value);
+        for chunk in chunks {
+            let request = PutReturnValueChunkRequest::new(chunk);
+            self.scheduler_client
+                .clone()
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Cannot lock scheduler client"))?
+                .put_return_value_chunk(request)?;
+        }
+
+        let outputs = TaskOutputs {
+            return_value: Vec::new(),
+            ..outputs
+        };
+        Ok(outputs.return_value_handle(Some(handle)))
+    }
+
     fn update_task_status(&mut self, task_id: &Uuid, task_status: TaskStatus) -> Result<()> {
         let request = UpdateTaskStatusRequest::new(task_id.to_owned(), task_status);
         let _response = self
@@ -153,10 +703,21 @@ impl TeaclaveExecutionService {
     }
 }
 
+// A panic of the worker fails the task transiently instead of killing the
+// execution service, so that the task may be retried.
+fn invoke_function(worker: &Worker, invocation: StagedFunction) -> Result<String> {
+    panic::catch_unwind(AssertUnwindSafe(|| worker.invoke_function(invocation)))
+        .unwrap_or_else(|_| Err(TransientFailure::WorkerCrash.into()))
+}
+
 fn prepare_task(task: &StagedTask, file_mgr: &TaskFileManager) -> Result<StagedFunction> {
     let input_files = file_mgr.prepare_staged_inputs()?;
     let output_files = file_mgr.prepare_staged_outputs()?;
     let function_payload = String::from_utf8_lossy(&task.function_payload).to_string();
+    let artifacts = task
+        .workflow_cache
+        .as_ref()
+        .map(|cache| file_mgr.workflow_artifacts(cache));
 
     let staged_function = StagedFunction::new()
         .executor_type(task.executor_type)
@@ -166,23 +727,116 @@ fn prepare_task(task: &StagedTask, file_mgr: &TaskFileManager) -> Result<StagedF
         .payload(function_payload)
         .input_files(input_files)
         .output_files(output_files)
+        .artifacts(artifacts)
+        .entry_point(task.entry_point.clone())
+        .tenant(task.creator.tenant_id())
+        .capabilities(task.capabilities)
         .runtime_name("default");
     Ok(staged_function)
 }
 
+// Tests are confined whatever the function declares, see `FunctionTest`.
+fn prepare_function_test(
+    task: &StagedTask,
+    input_files: StagedFiles,
+    output_files: StagedFiles,
+) -> StagedFunction {
+    let function_payload = String::from_utf8_lossy(&task.function_payload).to_string();
+    StagedFunction::new()
+        .executor_type(task.executor_type)
+        .executor(task.executor)
+        .name(&task.function_name)
+        .arguments(task.function_arguments.clone())
+        .payload(function_payload)
+        .input_files(input_files)
+        .output_files(output_files)
+        .entry_point(task.entry_point.clone())
+        .tenant(task.creator.tenant_id())
+        .capabilities(Some(FunctionTest::capabilities(task.capabilities)))
+        .runtime_name("default")
+}
+
+fn prewarm_task_on_worker(
+    worker: &Worker,
+    tenant_dir: &TenantDir,
+    fusion_base: impl AsRef<Path>,
+    prewarm_task: &PrewarmTask,
+    disk_quota: u64,
+) -> Result<()> {
+    prefetch_inputs(tenant_dir, fusion_base, prewarm_task, disk_quota)?;
+    let function_payload = String::from_utf8_lossy(&prewarm_task.function_payload).to_string();
+    worker.prepare_function(
+        &prewarm_task.creator.tenant_id(),
+        prewarm_task.executor_type,
+        prewarm_task.executor,
+        &prewarm_task.function_name,
+        &function_payload,
+    )
+}
+
 fn finalize_task(file_mgr: &TaskFileManager) -> Result<HashMap<String, FileAuthTag>> {
     file_mgr.upload_outputs()
 }
 
+// A task times out after the timeout declared by its function, or else the
+// timeout of the worker, or after the timeout of the task if that is earlier.
+// Tasks are not timed out if none is set.
+fn task_deadline(
+    task: &StagedTask,
+    capacity: &ResourceProfile,
+    timeout_grace_period: u64,
+) -> Option<TaskDeadline> {
+    let timeout = match task.resource_profile.timeout {
+        0 => capacity.timeout,
+        timeout => timeout,
+    };
+    let timeout = match (timeout, task.timeout) {
+        (0, Some(task_timeout)) => task_timeout,
+        (timeout, Some(task_timeout)) => timeout.min(task_timeout),
+        (timeout, None) => timeout,
+    };
+    if timeout == 0 {
+        return None;
+    }
+    Some(TaskDeadline::new(
+        Duration::from_secs(timeout),
+        Duration::from_secs(timeout_grace_period),
+    ))
+}
+
+// Uploads the outputs a timed out function closed in time as a partial
+// result. The task fails if there is no such output.
+fn salvage_task(
+    file_mgr: &TaskFileManager,
+    deadline: &TaskDeadline,
+    error: anyhow::Error,
+) -> Result<TaskOutputs> {
+    let closed_outputs = deadline.closed_outputs();
+    if closed_outputs.is_empty() {
+        return Err(error.context("Task timed out"));
+    }
+    log::warn!(
+        "Task timed out, salvaging outputs {:?}: {:?}",
+        closed_outputs,
+        error
+    );
+    let outputs_tag = file_mgr.upload_closed_outputs(&closed_outputs)?;
+    let task_outputs = TaskOutputs::new(format!("Task timed out: {}", error), outputs_tag);
+    Ok(task_outputs.partial(true))
+}
+
 #[cfg(feature = "enclave_unit_test")]
 pub mod tests {
     use super::*;
+    use anyhow::Context;
     use serde_json::json;
     use std::format;
     use teaclave_crypto::*;
     use url::Url;
     use uuid::Uuid;
 
+    const TEST_DISK_QUOTA: u64 = 64 * 1024 * 1024;
+
     pub fn test_invoke_echo() {
         let task_id = Uuid::new_v4();
         let function_arguments =
@@ -193,12 +847,18 @@ pub mod tests {
             .function_name("builtin-echo")
             .function_arguments(function_arguments);
 
-        let file_mgr = TaskFileManager::new(
+        let tenant_dir = TenantDir::new(
             WORKER_BASE_DIR,
+            &staged_task.creator.tenant_id(),
+            TEST_DISK_QUOTA,
+        );
+        let file_mgr = TaskFileManager::new(
+            &tenant_dir,
             "/tmp/fusion_base",
             &staged_task.task_id,
             &staged_task.input_data,
             &staged_task.output_data,
+            TEST_DISK_QUOTA,
         )
         .unwrap();
         let invocation = prepare_task(&staged_task, &file_mgr).unwrap();
@@ -252,12 +912,18 @@ pub mod tests {
             .input_data(input_data)
             .output_data(output_data);
 
-        let file_mgr = TaskFileManager::new(
+        let tenant_dir = TenantDir::new(
             WORKER_BASE_DIR,
+            &staged_task.creator.tenant_id(),
+            TEST_DISK_QUOTA,
+        );
+        let file_mgr = TaskFileManager::new(
+            &tenant_dir,
             "/tmp/fusion_base",
             &staged_task.task_id,
             &staged_task.input_data,
             &staged_task.output_data,
+            TEST_DISK_QUOTA,
         )
         .unwrap();
         let invocation = prepare_task(&staged_task, &file_mgr).unwrap();
@@ -270,4 +936,81 @@ pub mod tests {
         log::debug!("summary: {:?}", result);
         assert!(result.is_ok());
     }
+
+    pub fn test_check_resource_profile() {
+        let capacity = ResourceProfile::new()
+            .heap_size(256 * 1024 * 1024)
+            .input_size(TEST_DISK_QUOTA)
+            .timeout(3600);
+
+        let staged_task = StagedTask::new().resource_profile(
+            ResourceProfile::new()
+                .heap_size(64 * 1024 * 1024)
+                .timeout(60),
+        );
+        assert!(staged_task.resource_profile.check(&capacity).is_ok());
+
+        // Tasks without a profile run on any worker
+        let staged_task = StagedTask::new();
+        assert!(staged_task.resource_profile.check(&capacity).is_ok());
+
+        let staged_task = StagedTask::new()
+            .resource_profile(ResourceProfile::new().input_size(TEST_DISK_QUOTA + 1));
+        assert!(staged_task.resource_profile.check(&capacity).is_err());
+
+        let staged_task = StagedTask::new().resource_profile(ResourceProfile::new().timeout(3601));
+        assert!(staged_task.resource_profile.check(&capacity).is_err());
+
+        // A worker without limits accepts any profile
+        let staged_task = StagedTask::new().resource_profile(
+            ResourceProfile::new()
+                .heap_size(1024 * 1024 * 1024)
+                .timeout(86400),
+        );
+        assert!(staged_task
+            .resource_profile
+            .check(&ResourceProfile::new())
+            .is_ok());
+    }
+
+    pub fn test_check_resource_limits() {
+        let staged_task = StagedTask::new()
+            .resource_profile(ResourceProfile::new().heap_size(64 * 1024 * 1024))
+            .resource_limits(ResourceLimits::new().max_memory_bytes(32 * 1024 * 1024));
+        let result = staged_task
+            .resource_limits
+            .check_memory(&staged_task.resource_profile)
+            .map_err(anyhow::Error::from)
+            .context("Cannot run task");
+        let failure = TaskFailure::from_error(&result.unwrap_err());
+        assert_eq!(failure.kind, TaskFailureKind::ResourceExceeded);
+
+        // Functions without a declared heap are not limited by it
+        let staged_task = staged_task.resource_profile(ResourceProfile::new());
+        assert!(staged_task
+            .resource_limits
+            .check_memory(&staged_task.resource_profile)
+            .is_ok());
+    }
+
+    pub fn test_task_deadline() {
+        let capacity = ResourceProfile::new().timeout(3600);
+
+        // Tasks time out by the timeout of the worker if they declare none
+        let staged_task = StagedTask::new();
+        let deadline = task_deadline(&staged_task, &capacity, 0).unwrap();
+        assert!(!deadline.is_timed_out());
+
+        let staged_task = StagedTask::new().resource_profile(ResourceProfile::new().timeout(60));
+        let deadline = task_deadline(&staged_task, &capacity, 30).unwrap();
+        assert!(!deadline.is_timed_out());
+        assert!(!deadline.is_grace_period_over());
+
+        let staged_task = StagedTask::new();
+        assert!(task_deadline(&staged_task, &ResourceProfile::new(), 30).is_none());
+
+        // Tasks with a timeout time out by it even on workers without one
+        let staged_task = StagedTask::new().timeout(Some(60));
+        assert!(task_deadline(&staged_task, &ResourceProfile::new(), 30).is_some());
+    }
 }
@@ -17,7 +17,7 @@
 
 use crate::ocall::handle_file_request;
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::path::PathBuf;
 use std::prelude::v1::*;
@@ -31,6 +31,20 @@ pub(crate) struct TaskFileManager {
     inter_inputs: InterInputs,
     inter_outputs: InterOutputs,
     fusion_base: PathBuf,
+    tenant_dir: TenantDir,
+    task_id: Uuid,
+    task_dir: PathBuf,
+    disk_quota: u64,
+    priority: TaskPriority,
+}
+
+/// Scratch directory of a tenant, i.e., the creator of tasks, holding the files
+/// of its tasks and the inputs prefetched for them. Tenants are isolated in
+/// their own directories, and the total disk usage of a tenant is capped.
+#[derive(Debug, Clone)]
+pub(crate) struct TenantDir {
+    dir: PathBuf,
+    disk_quota: u64,
 }
 
 struct InterInputs {
@@ -46,6 +60,7 @@ pub(self) struct InterInput {
     pub(self) file: FunctionInputFile,
     pub(self) download_path: PathBuf,
     pub(self) staged_path: PathBuf,
+    pub(self) prefetched: bool,
 }
 
 pub(self) struct InterOutput {
@@ -57,13 +72,14 @@ pub(self) struct InterOutput {
 
 impl TaskFileManager {
     pub(crate) fn new(
-        inter_base: impl AsRef<Path>,
+        tenant_dir: &TenantDir,
         fusion_base: impl AsRef<Path>,
         task_id: &Uuid,
         inputs: &FunctionInputFiles,
         outputs: &FunctionOutputFiles,
+        disk_quota: u64,
     ) -> Result<Self> {
-        let cwd = Path::new(inter_base.as_ref()).join(task_id.to_string());
+        let cwd = tenant_dir.task_dir(task_id);
         let inputs_base = cwd.join("inputs");
         let outputs_base = cwd.join("outputs");
 
@@ -74,14 +90,55 @@ impl TaskFileManager {
             inter_inputs,
             inter_outputs,
             fusion_base: fusion_base.as_ref().to_owned(),
+            tenant_dir: tenant_dir.clone(),
+            task_id: *task_id,
+            task_dir: cwd,
+            disk_quota,
+            priority: TaskPriority::default(),
         };
 
         Ok(tfmgr)
     }
 
+    /// Reuses the inputs downloaded for the prewarm hint of the task. Inputs
+    /// which differ from the hint are downloaded again.
+    pub(crate) fn prefetched_inputs(mut self, prewarm_task: &PrewarmTask) -> Result<Self> {
+        for inter_input in self.inter_inputs.inner.iter_mut() {
+            let matched = prewarm_task
+                .input_files
+                .get(&inter_input.funiq_key)
+                .map_or(false, |file| file.matches(&inter_input.file));
+            if matched && inter_input.download_path.exists() {
+                inter_input.prefetched = true;
+            } else if inter_input.download_path.exists() {
+                std::untrusted::fs::remove_file(&inter_input.download_path)?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Transfers of the files are weighted by the priority of the task on the
+    /// worker host.
+    pub(crate) fn priority(self, priority: TaskPriority) -> Self {
+        Self { priority, ..self }
+    }
+
     pub(crate) fn prepare_staged_inputs(&self) -> Result<StagedFiles> {
-        self.inter_inputs.download(&self.fusion_base)?;
-        self.inter_inputs.convert_to_staged_files()
+        self.inter_inputs
+            .download(&self.fusion_base, self.priority)?;
+        self.check_disk_usage()?;
+        let staged_inputs = self.inter_inputs.convert_to_staged_files()?;
+        self.check_disk_usage()?;
+        Ok(staged_inputs)
+    }
+
+    /// Total size of the input files of the task as fetched.
+    pub(crate) fn input_size(&self) -> Result<u64> {
+        self.inter_inputs
+            .inner
+            .iter()
+            .map(|inter_input| disk_usage(&inter_input.download_path))
+            .sum()
     }
 
     pub(crate) fn prepare_staged_outputs(&self) -> Result<StagedFiles> {
@@ -90,10 +147,282 @@ impl TaskFileManager {
     }
 
     pub(crate) fn upload_outputs(&self) -> Result<HashMap<String, FileAuthTag>> {
-        let auth_tags = self.inter_outputs.convert_staged_files_for_upload()?;
-        self.inter_outputs.upload(&self.fusion_base)?;
+        self.upload_selected_outputs(|_| true)
+    }
+
+    /// Uploads only the outputs closed by a timed out function in its grace
+    /// period, which are the partial result of the task.
+    pub(crate) fn upload_closed_outputs(
+        &self,
+        closed_outputs: &HashSet<String>,
+    ) -> Result<HashMap<String, FileAuthTag>> {
+        self.upload_selected_outputs(|funiq_key| closed_outputs.contains(funiq_key))
+    }
+
+    fn upload_selected_outputs(
+        &self,
+        selected: impl Fn(&str) -> bool,
+    ) -> Result<HashMap<String, FileAuthTag>> {
+        // Outputs written by the function are counted before converting them.
+        self.check_disk_usage()?;
+        let auth_tags = self
+            .inter_outputs
+            .convert_staged_files_for_upload(&selected)?;
+        self.check_disk_usage()?;
+        self.inter_outputs
+            .upload(&self.fusion_base, self.priority, &selected)?;
         Ok(auth_tags)
     }
+
+    /// Writes the sample inputs of a function test to staged files directly,
+    /// as nothing is downloaded for a test.
+    pub(crate) fn stage_sample_inputs(
+        &self,
+        sample_inputs: &HashMap<String, Vec<u8>>,
+    ) -> Result<StagedFiles> {
+        let samples_base = self.task_dir.join("samples");
+        let staged_inputs = sample_inputs
+            .iter()
+            .map(|(funiq_key, sample)| {
+                let path = make_sample_path(&samples_base, funiq_key)?;
+                let staged_info = StagedFileInfo::create_with_bytes(path, sample)?;
+                Ok((funiq_key.to_owned(), staged_info))
+            })
+            .collect::<Result<StagedFiles>>()?;
+        self.check_disk_usage()?;
+        Ok(staged_inputs)
+    }
+
+    /// Stages the outputs of a function test, which are read back with
+    /// `read_sample_outputs` instead of being uploaded.
+    pub(crate) fn stage_sample_outputs(&self, outputs: &[String]) -> Result<StagedFiles> {
+        let outputs_base = self.task_dir.join("sample-outputs");
+        outputs
+            .iter()
+            .map(|funiq_key| {
+                let path = make_sample_path(&outputs_base, funiq_key)?;
+                let random_key = TeaclaveFile128Key::random();
+                let staged_info = StagedFileInfo::new(path, random_key, FileAuthTag::default());
+                Ok((funiq_key.to_owned(), staged_info))
+            })
+            .collect()
+    }
+
+    /// Reads the outputs written by a function test, leaving out the ones the
+    /// function did not write. Outputs larger than `max_size` fail the test.
+    pub(crate) fn read_sample_outputs(
+        &self,
+        staged_outputs: &StagedFiles,
+        outputs: &[String],
+        max_size: usize,
+    ) -> Result<HashMap<String, Vec<u8>>> {
+        self.check_disk_usage()?;
+        let mut sample_outputs = HashMap::new();
+        for funiq_key in outputs {
+            let staged_info = match staged_outputs.get(funiq_key) {
+                Some(staged_info) if staged_info.path.exists() => staged_info,
+                _ => continue,
+            };
+            let content = staged_info.get_plaintext()?;
+            anyhow::ensure!(
+                content.len() <= max_size,
+                "Output {} exceeds {} bytes",
+                funiq_key,
+                max_size
+            );
+            sample_outputs.insert(funiq_key.to_owned(), content);
+        }
+        Ok(sample_outputs)
+    }
+
+    // Artifacts are put in the fusion base, which is shared by all workers.
+    pub(crate) fn workflow_artifacts(&self, cache: &WorkflowCache) -> WorkflowArtifacts {
+        WorkflowArtifacts::new(&self.fusion_base, cache)
+    }
+
+    fn check_disk_usage(&self) -> Result<()> {
+        let usage = disk_usage(&self.task_dir)?;
+        anyhow::ensure!(
+            usage <= self.disk_quota,
+            "Task files exceed the disk quota: {} > {}",
+            usage,
+            self.disk_quota
+        );
+        self.tenant_dir.check_disk_usage()
+    }
+}
+
+impl TenantDir {
+    // Tenant files are put in $base_dir/$tenant_id
+    pub(crate) fn new(inter_base: impl AsRef<Path>, tenant: &str, disk_quota: u64) -> Self {
+        Self {
+            dir: Path::new(inter_base.as_ref()).join(tenant),
+            disk_quota,
+        }
+    }
+
+    // Task files are put in $base_dir/$tenant_id/$task_id
+    fn task_dir(&self, task_id: &Uuid) -> PathBuf {
+        self.dir.join(task_id.to_string())
+    }
+
+    fn check_disk_usage(&self) -> Result<()> {
+        let usage = disk_usage(&self.dir)?;
+        anyhow::ensure!(
+            usage <= self.disk_quota,
+            "Tenant files exceed the disk quota: {} > {}",
+            usage,
+            self.disk_quota
+        );
+        Ok(())
+    }
+
+    // The directory is removed along with the last task of the tenant.
+    fn remove_task_dir(&self, task_id: &Uuid) -> Result<()> {
+        remove_task_dir(&self.task_dir(task_id))?;
+        let is_empty =
+            self.dir.exists() && std::untrusted::fs::read_dir(&self.dir)?.next().is_none();
+        if is_empty {
+            std::untrusted::fs::remove_dir(&self.dir)?;
+        }
+        Ok(())
+    }
+}
+
+// All files of a task (inputs, staged files and outputs) are removed once the
+// manager goes out of scope, including on errors and panics.
+impl Drop for TaskFileManager {
+    fn drop(&mut self) {
+        if let Err(e) = self.tenant_dir.remove_task_dir(&self.task_id) {
+            log::error!("Failed to remove task files {:?}: {:?}", self.task_dir, e);
+        }
+    }
+}
+
+fn remove_task_dir(dir: &Path) -> Result<()> {
+    if dir.exists() {
+        std::untrusted::fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+// Symbolic links are not followed, so staged links to downloaded files are not
+// counted twice.
+fn disk_usage(path: &Path) -> Result<u64> {
+    if !path.exists() {
+        return Ok(0);
+    }
+    let metadata = std::untrusted::fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut usage = 0;
+    for entry in std::untrusted::fs::read_dir(path)? {
+        usage += disk_usage(&entry?.path())?;
+    }
+    Ok(usage)
+}
+
+/// Verifies that no file of the task is left in the intermediate directory
+/// after the task finished, and removes the remaining ones otherwise.
+pub(crate) fn sweep_task_files(tenant_dir: &TenantDir, task_id: &Uuid) -> Result<()> {
+    let dir = tenant_dir.task_dir(task_id);
+    if !dir.exists() {
+        return Ok(());
+    }
+    log::warn!("Task files are not cleaned up, sweeping: {:?}", dir);
+    tenant_dir.remove_task_dir(task_id)?;
+    anyhow::ensure!(!dir.exists(), "Cannot remove task files: {:?}", dir);
+    Ok(())
+}
+
+/// Downloads the inputs of a task before it is staged. Inputs are put where the
+/// file manager of the staged task expects them, and count towards the disk
+/// quota of the tenant.
+pub(crate) fn prefetch_inputs(
+    tenant_dir: &TenantDir,
+    fusion_base: impl AsRef<Path>,
+    prewarm_task: &PrewarmTask,
+    disk_quota: u64,
+) -> Result<()> {
+    let cwd = tenant_dir.task_dir(&prewarm_task.task_id);
+    let inputs_base = cwd.join("inputs");
+    // Pushed inputs are only uploaded once the task is running.
+    let req_info = prewarm_task
+        .input_files
+        .iter()
+        .filter(|(_, file)| file.url.scheme() != PUSH_URL_SCHEME)
+        .map(|(funiq_key, file)| {
+            make_intermediate_path(&inputs_base, funiq_key, &file.url)
+                .map(|download_path| HandleFileInfo::new(&download_path, &file.url))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let request =
+        FileAgentRequest::new(HandleFileCommand::Download, req_info, fusion_base.as_ref());
+    log::debug!("Ocall file prefetch request: {:?}", request);
+
+    let result = handle_file_request(request).and_then(|_| {
+        let usage = disk_usage(&cwd)?;
+        anyhow::ensure!(
+            usage <= disk_quota,
+            "Prefetched files exceed the disk quota: {} > {}",
+            usage,
+            disk_quota
+        );
+        tenant_dir.check_disk_usage()
+    });
+    if result.is_err() {
+        tenant_dir.remove_task_dir(&prewarm_task.task_id)?;
+    }
+    result
+}
+
+/// Whether the input is pushed by its owner to the upload endpoint of the host,
+/// rather than downloaded from its url.
+pub(crate) fn is_pushed(file: &FunctionInputFile) -> bool {
+    file.url.scheme() == PUSH_URL_SCHEME
+}
+
+/// Opens the slots of the `push:<token>` urls in the upload endpoint of the
+/// host, so that the inputs are accepted and then downloaded as usual.
+pub(crate) fn open_push_slots<'a>(
+    fusion_base: impl AsRef<Path>,
+    urls: impl IntoIterator<Item = &'a Url>,
+) -> Result<()> {
+    let req_info = urls
+        .into_iter()
+        .map(|url| HandleFileInfo::new(PathBuf::new(), url));
+    let request = FileAgentRequest::new(
+        HandleFileCommand::OpenPushSlots,
+        req_info,
+        fusion_base.as_ref(),
+    );
+    log::debug!("Ocall open push slots request: {:?}", request);
+    handle_file_request(request)
+}
+
+/// Removes the inputs prefetched for a task which is not staged on this
+/// worker.
+pub(crate) fn remove_prefetched_inputs(tenant_dir: &TenantDir, task_id: &Uuid) -> Result<()> {
+    tenant_dir.remove_task_dir(task_id)
+}
+
+/// Removes files left by tasks of a previous run, e.g., when the service was
+/// aborted in the middle of a task.
+pub(crate) fn sweep_stale_task_files(inter_base: impl AsRef<Path>) -> Result<()> {
+    if !inter_base.as_ref().exists() {
+        return Ok(());
+    }
+    for entry in std::untrusted::fs::read_dir(inter_base.as_ref())? {
+        let path = entry?.path();
+        log::warn!("Removing stale task files: {:?}", path);
+        if std::untrusted::fs::symlink_metadata(&path)?.is_dir() {
+            std::untrusted::fs::remove_dir_all(&path)?;
+        } else {
+            std::untrusted::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
 }
 
 impl InterInput {
@@ -110,6 +439,7 @@ impl InterInput {
             file,
             download_path,
             staged_path,
+            prefetched: false,
         })
     }
 
@@ -178,14 +508,24 @@ impl InterInputs {
             .collect()
     }
 
-    pub(crate) fn download(&self, fusion_base: impl AsRef<Path>) -> Result<()> {
-        let req_info = self.inner.iter().map(|inter_input| {
-            HandleFileInfo::new(&inter_input.download_path, &inter_input.file.url)
-        });
+    pub(crate) fn download(
+        &self,
+        fusion_base: impl AsRef<Path>,
+        priority: TaskPriority,
+    ) -> Result<()> {
+        let req_info = self
+            .inner
+            .iter()
+            .filter(|inter_input| !inter_input.prefetched)
+            .map(|inter_input| {
+                HandleFileInfo::new(&inter_input.download_path, &inter_input.file.url)
+            });
         let request =
-            FileAgentRequest::new(HandleFileCommand::Download, req_info, fusion_base.as_ref());
+            FileAgentRequest::new(HandleFileCommand::Download, req_info, fusion_base.as_ref())
+                .priority(priority);
         log::debug!("Ocall file download request: {:?}", request);
-        handle_file_request(request)?;
+        // The inputs may be fetched on a retry of the task.
+        handle_file_request(request).map_err(|e| TransientFailure::FileFetch(e.to_string()))?;
         Ok(())
     }
 
@@ -268,9 +608,13 @@ impl InterOutputs {
             .collect()
     }
 
-    pub fn convert_staged_files_for_upload(&self) -> Result<HashMap<String, FileAuthTag>> {
+    pub fn convert_staged_files_for_upload(
+        &self,
+        selected: impl Fn(&str) -> bool,
+    ) -> Result<HashMap<String, FileAuthTag>> {
         self.inner
             .iter()
+            .filter(|inter_output| selected(&inter_output.funiq_key))
             .map(|inter_output| {
                 inter_output
                     .convert_to_upload_file()
@@ -279,12 +623,23 @@ impl InterOutputs {
             .collect()
     }
 
-    pub(crate) fn upload(&self, fusion_base: impl AsRef<Path>) -> Result<()> {
-        let req_info = self.inner.iter().map(|inter_output| {
-            HandleFileInfo::new(&inter_output.upload_path, &inter_output.file.url)
-        });
+    pub(crate) fn upload(
+        &self,
+        fusion_base: impl AsRef<Path>,
+        priority: TaskPriority,
+        selected: impl Fn(&str) -> bool,
+    ) -> Result<()> {
+        let req_info = self
+            .inner
+            .iter()
+            .filter(|inter_output| selected(&inter_output.funiq_key))
+            .map(|inter_output| {
+                HandleFileInfo::new(&inter_output.upload_path, &inter_output.file.url)
+                    .credential(inter_output.file.credential.clone())
+            });
         let request =
-            FileAgentRequest::new(HandleFileCommand::Upload, req_info, fusion_base.as_ref());
+            FileAgentRequest::new(HandleFileCommand::Upload, req_info, fusion_base.as_ref())
+                .priority(priority);
         log::debug!("Ocall file upload request: {:?}", request);
         handle_file_request(request)?;
         Ok(())
@@ -322,12 +677,28 @@ fn make_intermediate_path(base: impl AsRef<Path>, funiq_key: &str, url: &Url) ->
     Ok(local_dest)
 }
 
+// Sample file is put in $base_dir/${funiq_key}-staged/sample
+fn make_sample_path(base: impl AsRef<Path>, funiq_key: &str) -> Result<PathBuf> {
+    let staged_dir = format!("{}-{}", funiq_key, "staged");
+    let file_dir = base.as_ref().to_owned().join(&staged_dir);
+    if !file_dir.exists() {
+        std::untrusted::fs::create_dir_all(&file_dir)?;
+    }
+    Ok(file_dir.join("sample"))
+}
+
 #[cfg(feature = "enclave_unit_test")]
 pub mod tests {
     use super::*;
     use teaclave_crypto::*;
     use url::Url;
 
+    const TEST_DISK_QUOTA: u64 = 64 * 1024 * 1024;
+
+    fn tenant_dir(tenant: &str, disk_quota: u64) -> TenantDir {
+        TenantDir::new("/tmp", &UserID::from(tenant).tenant_id(), disk_quota)
+    }
+
     pub fn test_input() {
         let key = [0; 16];
         let iv = [1; 12];
@@ -340,16 +711,125 @@ pub mod tests {
         let inputs = hashmap!("training_data" => input_file);
         let outputs = hashmap!();
         let task_id = Uuid::new_v4();
+        let tenant_dir = tenant_dir("test_input", TEST_DISK_QUOTA);
 
         let file_mgr = TaskFileManager::new(
-            "/tmp",
+            &tenant_dir,
             "/tmp/fusion_base",
             &task_id,
             &inputs.into(),
             &outputs.into(),
+            TEST_DISK_QUOTA,
         )
         .unwrap();
         file_mgr.prepare_staged_inputs().unwrap();
         file_mgr.prepare_staged_outputs().unwrap();
     }
+
+    pub fn test_disk_quota_and_cleanup() {
+        let key = [0; 16];
+        let iv = [1; 12];
+        let crypto = AesGcm128Key::new(&key, &iv).unwrap();
+        let input_url =
+            Url::parse("http://localhost:6789/fixtures/functions/gbdt_training/train.aes_gcm_128")
+                .unwrap();
+        let tag = FileAuthTag::from_hex("592f1e607649d89ff2aa8a2841a57cad").unwrap();
+        let input_file = FunctionInputFile::new(input_url, tag, crypto);
+        let inputs = hashmap!("training_data" => input_file);
+        let outputs = hashmap!();
+        let task_id = Uuid::new_v4();
+        let tenant_dir = tenant_dir("test_disk_quota", TEST_DISK_QUOTA);
+
+        let file_mgr = TaskFileManager::new(
+            &tenant_dir,
+            "/tmp/fusion_base",
+            &task_id,
+            &inputs.clone().into(),
+            &outputs.clone().into(),
+            1,
+        )
+        .unwrap();
+        assert!(file_mgr.prepare_staged_inputs().is_err());
+
+        let dir = tenant_dir.task_dir(&task_id);
+        assert!(dir.exists());
+        drop(file_mgr);
+        assert!(!dir.exists());
+        assert!(!tenant_dir.dir.exists());
+        assert!(sweep_task_files(&tenant_dir, &task_id).is_ok());
+
+        // The files of all tasks of a tenant count towards the tenant quota.
+        let tenant_dir = self::tenant_dir("test_tenant_quota", 1);
+        let file_mgr = TaskFileManager::new(
+            &tenant_dir,
+            "/tmp/fusion_base",
+            &task_id,
+            &inputs.into(),
+            &outputs.into(),
+            TEST_DISK_QUOTA,
+        )
+        .unwrap();
+        let err = file_mgr.prepare_staged_inputs().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Tenant files exceed the disk quota"));
+        drop(file_mgr);
+        assert!(!tenant_dir.dir.exists());
+    }
+
+    pub fn test_prefetch_inputs() {
+        let key = [0; 16];
+        let iv = [1; 12];
+        let crypto = AesGcm128Key::new(&key, &iv).unwrap();
+        let input_url =
+            Url::parse("http://localhost:6789/fixtures/functions/gbdt_training/train.aes_gcm_128")
+                .unwrap();
+        let tag = FileAuthTag::from_hex("592f1e607649d89ff2aa8a2841a57cad").unwrap();
+        let input_file = FunctionInputFile::new(input_url.clone(), tag, crypto);
+        let inputs = hashmap!("training_data" => input_file);
+        let outputs = hashmap!();
+        let task_id = Uuid::new_v4();
+        let creator = UserID::from("test_prefetch_inputs");
+        let tenant_dir = TenantDir::new("/tmp", &creator.tenant_id(), TEST_DISK_QUOTA);
+
+        let prewarm_task = PrewarmTask {
+            task_id,
+            creator,
+            input_files: hashmap!("training_data" => PrewarmInputFile {
+                url: input_url,
+                cmac: tag,
+            }),
+            ..Default::default()
+        };
+        prefetch_inputs(
+            &tenant_dir,
+            "/tmp/fusion_base",
+            &prewarm_task,
+            TEST_DISK_QUOTA,
+        )
+        .unwrap();
+        // Prefetched inputs are kept in the directory of the tenant only.
+        assert!(tenant_dir.task_dir(&task_id).exists());
+        assert!(!self::tenant_dir("another_tenant", TEST_DISK_QUOTA)
+            .task_dir(&task_id)
+            .exists());
+
+        let file_mgr = TaskFileManager::new(
+            &tenant_dir,
+            "/tmp/fusion_base",
+            &task_id,
+            &inputs.into(),
+            &outputs.into(),
+            TEST_DISK_QUOTA,
+        )
+        .unwrap()
+        .prefetched_inputs(&prewarm_task)
+        .unwrap();
+        // Prefetched inputs are not downloaded again.
+        file_mgr.prepare_staged_inputs().unwrap();
+
+        let dir = tenant_dir.task_dir(&task_id);
+        drop(file_mgr);
+        assert!(!dir.exists());
+    }
 }
@@ -17,7 +17,7 @@
 
 use std::prelude::v1::*;
 
-use teaclave_types::TeaclaveServiceResponseError;
+use teaclave_types::{TeaclaveErrorCode, TeaclaveServiceResponseError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -26,10 +26,40 @@ pub(crate) enum TeaclaveFrontendError {
     AuthenticationError,
     #[error("lock error")]
     LockError,
+    #[error("function payload too large")]
+    PayloadTooLarge,
+    #[error("too many function arguments")]
+    TooManyArguments,
+    #[error("function arguments too large")]
+    ArgumentsTooLarge,
+    #[error("executor not allowed")]
+    ExecutorNotAllowed,
+    #[error("connection error")]
+    ConnectionError,
+    #[error("too many tasks in a batch")]
+    TooManyTasks,
+    #[error("too many requests of the user")]
+    TooManyRequests,
+}
+
+impl TeaclaveFrontendError {
+    fn code(&self) -> TeaclaveErrorCode {
+        match self {
+            TeaclaveFrontendError::AuthenticationError => TeaclaveErrorCode::Auth,
+            TeaclaveFrontendError::LockError => TeaclaveErrorCode::Internal,
+            TeaclaveFrontendError::PayloadTooLarge => TeaclaveErrorCode::Quota,
+            TeaclaveFrontendError::TooManyArguments => TeaclaveErrorCode::Quota,
+            TeaclaveFrontendError::ArgumentsTooLarge => TeaclaveErrorCode::Quota,
+            TeaclaveFrontendError::ExecutorNotAllowed => TeaclaveErrorCode::Auth,
+            TeaclaveFrontendError::ConnectionError => TeaclaveErrorCode::Internal,
+            TeaclaveFrontendError::TooManyTasks => TeaclaveErrorCode::Quota,
+            TeaclaveFrontendError::TooManyRequests => TeaclaveErrorCode::Quota,
+        }
+    }
 }
 
 impl From<TeaclaveFrontendError> for TeaclaveServiceResponseError {
     fn from(error: TeaclaveFrontendError) -> Self {
-        TeaclaveServiceResponseError::RequestError(error.to_string())
+        TeaclaveServiceResponseError::request_error(error.code(), error)
     }
 }
@@ -35,7 +35,7 @@ use teaclave_binder::{handle_ecall, register_ecall_handler};
 use teaclave_config::build::AS_ROOT_CA_CERT;
 use teaclave_config::RuntimeConfig;
 use teaclave_proto::teaclave_frontend_service::{
-    TeaclaveFrontendRequest, TeaclaveFrontendResponse,
+    GetPlatformInfoResponse, TeaclaveFrontendApiRequest, TeaclaveFrontendApiResponse,
 };
 use teaclave_rpc::config::SgxTrustedTlsServerConfig;
 use teaclave_rpc::server::SgxTrustedTlsServer;
@@ -45,7 +45,10 @@ use teaclave_service_enclave_utils::{
 use teaclave_types::{TeeServiceError, TeeServiceResult};
 
 mod error;
+mod qos;
+mod scope;
 mod service;
+mod validator;
 
 fn start_service(config: &RuntimeConfig) -> Result<()> {
     let listen_address = config.api_endpoints.frontend.listen_address;
@@ -57,10 +60,11 @@ fn start_service(config: &RuntimeConfig) -> Result<()> {
     let server_config =
         SgxTrustedTlsServerConfig::from_attested_tls_config(attested_tls_config.clone())?;
 
-    let mut server = SgxTrustedTlsServer::<TeaclaveFrontendResponse, TeaclaveFrontendRequest>::new(
-        listen_address,
-        server_config,
-    );
+    let mut server =
+        SgxTrustedTlsServer::<TeaclaveFrontendApiResponse, TeaclaveFrontendApiRequest>::new(
+            listen_address,
+            server_config,
+        );
 
     let enclave_info = teaclave_types::EnclaveInfo::from_bytes(&config.audit.enclave_info_bytes);
     let authentication_service_endpoint = create_trusted_authentication_endpoint(
@@ -79,9 +83,17 @@ fn start_service(config: &RuntimeConfig) -> Result<()> {
         attested_tls_config,
     )?;
 
+    let platform_info = GetPlatformInfoResponse::new(
+        config.audit.enclave_info_bytes.clone(),
+        config.audit.auditor_signatures_bytes.clone(),
+    );
     let service = service::TeaclaveFrontendService::new(
         authentication_service_endpoint,
         management_service_endpoint,
+        config.limits.clone(),
+        &config.executors,
+        platform_info,
+        qos::Admission::new(config.qos.clone()),
     )?;
     match server.start(service) {
         Ok(_) => (),
@@ -125,6 +137,9 @@ register_ecall_handler!(
 #[cfg(feature = "enclave_unit_test")]
 pub mod tests {
     use super::*;
+    use teaclave_test_utils::*;
 
-    pub fn run_tests() -> bool {}
+    pub fn run_tests() -> bool {
+        run_tests!(qos::tests::test_admit_by_class)
+    }
 }
@@ -0,0 +1,121 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Admission of the requests of users by the rates of their QoS classes, so
+//! that the requests of batch tenants do not hold up those of interactive
+//! users at the frontend.
+
+use std::collections::HashMap;
+use std::prelude::v1::*;
+use std::sync::SgxMutex as Mutex;
+use teaclave_config::QosConfig;
+
+// Tokens are counted in thousandths, so that a class admitting r requests per
+// second refills r thousandths of a token each millisecond.
+const TOKEN: u64 = 1000;
+
+/// Token buckets of the users, each as large as the burst of the class of the
+/// user and refilled at the rate of the class.
+pub(crate) struct Admission {
+    qos: Option<QosConfig>,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    tokens: u64,
+    // Milliseconds since the Unix epoch the bucket was refilled at.
+    refilled_at: u64,
+}
+
+impl Admission {
+    pub(crate) fn new(qos: Option<QosConfig>) -> Self {
+        Self {
+            qos,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token of the user at the time in milliseconds since the Unix
+    /// epoch, and returns whether the request of the user is admitted. All
+    /// requests are admitted without QoS classes.
+    pub(crate) fn admit(&self, user_id: &str, now: u64) -> bool {
+        let class = match self.qos.as_ref().and_then(|qos| qos.class_of(user_id)) {
+            Some(class) => class,
+            None => return true,
+        };
+        let capacity = class.burst.saturating_mul(TOKEN);
+        let mut buckets = match self.buckets.lock() {
+            Ok(buckets) => buckets,
+            Err(_) => return false,
+        };
+        let bucket = buckets.entry(user_id.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            refilled_at: now,
+        });
+        let elapsed = now.saturating_sub(bucket.refilled_at);
+        let refill = elapsed.saturating_mul(class.requests_per_second);
+        bucket.tokens = bucket.tokens.saturating_add(refill).min(capacity);
+        bucket.refilled_at = bucket.refilled_at.max(now);
+        if bucket.tokens < TOKEN {
+            return false;
+        }
+        bucket.tokens -= TOKEN;
+        true
+    }
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use teaclave_config::QosClassConfig;
+
+    pub fn test_admit_by_class() {
+        let class = |requests_per_second, burst| QosClassConfig {
+            requests_per_second,
+            burst,
+            max_priority: "high".to_string(),
+        };
+        let mut classes = HashMap::new();
+        classes.insert("interactive".to_string(), class(10, 2));
+        classes.insert("batch".to_string(), class(1, 1));
+        let mut users = HashMap::new();
+        users.insert("batch_user".to_string(), "batch".to_string());
+        let admission = Admission::new(Some(QosConfig {
+            default_class: "interactive".to_string(),
+            classes,
+            users,
+        }));
+
+        let now = 1_000_000;
+        assert!(admission.admit("batch_user", now));
+        assert!(!admission.admit("batch_user", now));
+        // A burst of the default class is admitted regardless of others.
+        assert!(admission.admit("notebook_user", now));
+        assert!(admission.admit("notebook_user", now));
+        assert!(!admission.admit("notebook_user", now));
+
+        // Buckets refill at the rates of the classes.
+        assert!(admission.admit("notebook_user", now + 100));
+        assert!(!admission.admit("batch_user", now + 500));
+        assert!(admission.admit("batch_user", now + 1000));
+
+        let admission = Admission::new(None);
+        for _ in 0..10 {
+            assert!(admission.admit("batch_user", now));
+        }
+    }
+}
@@ -0,0 +1,125 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+use std::prelude::v1::*;
+
+use teaclave_proto::teaclave_frontend_service::{
+    ApproveTaskRequest, ApproveTaskScheduleRequest, ArchiveTaskRequest, AssignDataRequest,
+    CancelTaskRequest, CancelTaskScheduleRequest, CreateTaskFromTemplateRequest, CreateTaskRequest,
+    CreateTaskShareLinkRequest, CreateTasksBatchRequest, DeleteInputFileRequest,
+    DeleteOutputFileRequest, EstimateTaskRequest, ExportStorageSnapshotRequest,
+    GetActivityFeedRequest, GetDataLineageRequest, GetFunctionRequest, GetInputFileRequest,
+    GetModelRequest, GetOutputFileRequest, GetTaskKeyOfferRequest, GetTaskManifestRequest,
+    GetTaskRequest, GetTaskResultStreamRequest, GetTaskReturnValueRequest, GetTaskScheduleRequest,
+    GetTaskUploadSlotsRequest, InvokeTaskRequest, ListFilesRequest, ListFunctionsRequest,
+    ListPendingApprovalsRequest, ListTasksRequest, PromoteStorageReplicaRequest,
+    PutTaskKeysRequest, RegisterApprovalPolicyRequest, RegisterFunctionRequest,
+    RegisterFusionOutputRequest, RegisterInputFileRequest, RegisterInputFromOutputRequest,
+    RegisterModelRequest, RegisterOutputFileRequest, RegisterSigningKeyRequest,
+    RegisterTaskScheduleRequest, RegisterTaskTemplateRequest, RejectTaskRequest,
+    RestoreArchivedTaskRequest, RestoreStorageSnapshotRequest, RevokeApprovalPolicyRequest,
+    RotateInputFileKeyRequest, TestFunctionRequest, UpdateInputFileRequest,
+    UpdateOutputFileRequest, UpdateOutputUrlRequest,
+};
+use teaclave_types::ExternalID;
+
+/// Requests checked against the scope of a down-scoped token before they are
+/// forwarded to the management service.
+pub(crate) trait ScopedRequest {
+    /// External IDs of all resources the request names.
+    fn resources(&self) -> Vec<String>;
+}
+
+fn ids<'a>(ids: impl IntoIterator<Item = &'a ExternalID>) -> Vec<String> {
+    ids.into_iter().map(|id| id.to_string()).collect()
+}
+
+macro_rules! scoped_requests {
+    ($($request: ident => |$r: ident| $resources: expr;)*) => {
+        $(
+            impl ScopedRequest for $request {
+                fn resources(&self) -> Vec<String> {
+                    let $r = self;
+                    $resources
+                }
+            }
+        )*
+    };
+}
+
+scoped_requests! {
+    RegisterInputFileRequest => |_r| vec![];
+    UpdateInputFileRequest => |r| ids(vec![&r.data_id]);
+    RotateInputFileKeyRequest => |r| ids(vec![&r.data_id]);
+    RegisterOutputFileRequest => |_r| vec![];
+    UpdateOutputFileRequest => |r| ids(vec![&r.data_id]);
+    UpdateOutputUrlRequest => |r| ids(vec![&r.data_id]);
+    RegisterFusionOutputRequest => |_r| vec![];
+    RegisterInputFromOutputRequest => |r| ids(vec![&r.data_id]);
+    GetOutputFileRequest => |r| ids(vec![&r.data_id]);
+    GetInputFileRequest => |r| ids(vec![&r.data_id]);
+    DeleteInputFileRequest => |r| ids(vec![&r.data_id]);
+    DeleteOutputFileRequest => |r| ids(vec![&r.data_id]);
+    RegisterFunctionRequest => |_r| vec![];
+    GetFunctionRequest => |r| ids(vec![&r.function_id]);
+    CreateTaskRequest => |r| {
+        let upstream_tasks = r.input_dependencies.values().map(|d| &d.task_id);
+        ids(std::iter::once(&r.function_id).chain(upstream_tasks))
+    };
+    GetTaskRequest => |r| ids(vec![&r.task_id]);
+    ListTasksRequest => |_r| vec![];
+    ListPendingApprovalsRequest => |_r| vec![];
+    ListFunctionsRequest => |_r| vec![];
+    ListFilesRequest => |_r| vec![];
+    AssignDataRequest => |r| {
+        let files = r.inputs.values().chain(r.outputs.values());
+        ids(std::iter::once(&r.task_id).chain(files))
+    };
+    ApproveTaskRequest => |r| ids(vec![&r.task_id]);
+    InvokeTaskRequest => |r| ids(vec![&r.task_id]);
+    GetTaskManifestRequest => |r| ids(vec![&r.task_id]);
+    GetTaskReturnValueRequest => |r| ids(vec![&r.task_id]);
+    GetTaskKeyOfferRequest => |r| ids(vec![&r.task_id]);
+    PutTaskKeysRequest => |r| ids(vec![&r.task_id]);
+    GetDataLineageRequest => |r| ids(vec![&r.data_id]);
+    RegisterApprovalPolicyRequest => |r| ids(std::iter::once(&r.data_id).chain(&r.function_ids));
+    RevokeApprovalPolicyRequest => |r| vec![r.policy_id.to_string()];
+    RegisterModelRequest => |r| ids(vec![&r.task_id, &r.data_id]);
+    GetModelRequest => |_r| vec![];
+    CreateTaskShareLinkRequest => |r| ids(vec![&r.task_id]);
+    ArchiveTaskRequest => |r| ids(vec![&r.task_id]);
+    RestoreArchivedTaskRequest => |r| ids(vec![&r.task_id]);
+    ExportStorageSnapshotRequest => |_r| vec![];
+    RestoreStorageSnapshotRequest => |_r| vec![];
+    PromoteStorageReplicaRequest => |_r| vec![];
+    CancelTaskRequest => |r| ids(vec![&r.task_id]);
+    GetTaskUploadSlotsRequest => |r| ids(vec![&r.task_id]);
+    RejectTaskRequest => |r| ids(vec![&r.task_id]);
+    GetActivityFeedRequest => |_r| vec![];
+    GetTaskResultStreamRequest => |r| ids(vec![&r.task_id]);
+    RegisterTaskTemplateRequest => |r| ids(vec![&r.function_id]);
+    CreateTaskFromTemplateRequest => |r| ids(vec![&r.template_id]);
+    CreateTasksBatchRequest => |r| r.tasks.iter().flat_map(|t| t.resources()).collect();
+    TestFunctionRequest => |r| ids(vec![&r.function_id]);
+    RegisterTaskScheduleRequest => |r| {
+        ids(std::iter::once(&r.template_id).chain(r.inputs.values()))
+    };
+    ApproveTaskScheduleRequest => |r| ids(vec![&r.template_id]);
+    CancelTaskScheduleRequest => |r| ids(vec![&r.template_id]);
+    GetTaskScheduleRequest => |r| ids(vec![&r.template_id]);
+    EstimateTaskRequest => |r| ids(vec![&r.function_id]);
+    RegisterSigningKeyRequest => |_r| vec![];
+}
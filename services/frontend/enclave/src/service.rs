@@ -16,45 +16,87 @@
 // under the License.
 
 use crate::error::TeaclaveFrontendError;
+use crate::qos::Admission;
+use crate::scope::ScopedRequest;
+use crate::validator::{validate_request, validate_request_v2};
 
 use anyhow::Result;
+use std::convert::TryFrom;
 use std::prelude::v1::*;
 use std::sync::{Arc, SgxMutex as Mutex};
+use std::time::UNIX_EPOCH;
 
+use teaclave_config::{ExecutorsConfig, LimitsConfig};
 use teaclave_proto::teaclave_authentication_service::{
     TeaclaveAuthenticationInternalClient, UserAuthenticateRequest,
 };
 use teaclave_proto::teaclave_common::UserCredential;
 use teaclave_proto::teaclave_frontend_service::{
-    ApproveTaskRequest, ApproveTaskResponse, AssignDataRequest, AssignDataResponse,
-    CreateTaskRequest, CreateTaskResponse, GetFunctionRequest, GetFunctionResponse,
-    GetInputFileRequest, GetInputFileResponse, GetOutputFileRequest, GetOutputFileResponse,
-    GetTaskRequest, GetTaskResponse, InvokeTaskRequest, InvokeTaskResponse,
+    ApproveTaskRequest, ApproveTaskResponse, ApproveTaskScheduleRequest,
+    ApproveTaskScheduleResponse, ArchiveTaskRequest, ArchiveTaskResponse, AssignDataRequest,
+    AssignDataResponse, CancelTaskRequest, CancelTaskResponse, CancelTaskScheduleRequest,
+    CancelTaskScheduleResponse, CreateTaskFromTemplateRequest, CreateTaskFromTemplateResponse,
+    CreateTaskRequest, CreateTaskResponse, CreateTaskShareLinkRequest, CreateTaskShareLinkResponse,
+    CreateTasksBatchRequest, CreateTasksBatchResponse, DeleteInputFileRequest,
+    DeleteInputFileResponse, DeleteOutputFileRequest, DeleteOutputFileResponse,
+    EstimateTaskRequest, EstimateTaskResponse, ExportStorageSnapshotRequest,
+    ExportStorageSnapshotResponse, GetActivityFeedRequest, GetActivityFeedResponse,
+    GetDataLineageRequest, GetDataLineageResponse, GetFunctionRequest, GetFunctionResponse,
+    GetInputFileRequest, GetInputFileResponse, GetModelRequest, GetModelResponse,
+    GetOutputFileRequest, GetOutputFileResponse, GetPlatformInfoRequest, GetPlatformInfoResponse,
+    GetSharedTaskRequest, GetSharedTaskResponse, GetTaskKeyOfferRequest, GetTaskKeyOfferResponse,
+    GetTaskManifestRequest, GetTaskManifestResponse, GetTaskRequest, GetTaskResponse,
+    GetTaskResultStreamRequest, GetTaskResultStreamResponse, GetTaskReturnValueRequest,
+    GetTaskReturnValueResponse, GetTaskScheduleRequest, GetTaskScheduleResponse,
+    GetTaskUploadSlotsRequest, GetTaskUploadSlotsResponse, InvokeTaskRequest, InvokeTaskResponse,
+    ListFilesRequest, ListFilesResponse, ListFunctionsRequest, ListFunctionsResponse,
+    ListPendingApprovalsRequest, ListPendingApprovalsResponse, ListTasksRequest, ListTasksResponse,
+    PageRequest, PromoteStorageReplicaRequest, PromoteStorageReplicaResponse, PutTaskKeysRequest,
+    PutTaskKeysResponse, RegisterApprovalPolicyRequest, RegisterApprovalPolicyResponse,
     RegisterFunctionRequest, RegisterFunctionResponse, RegisterFusionOutputRequest,
     RegisterFusionOutputResponse, RegisterInputFileRequest, RegisterInputFileResponse,
-    RegisterInputFromOutputRequest, RegisterInputFromOutputResponse, RegisterOutputFileRequest,
-    RegisterOutputFileResponse, TeaclaveFrontend, UpdateInputFileRequest, UpdateInputFileResponse,
-    UpdateOutputFileRequest, UpdateOutputFileResponse,
+    RegisterInputFromOutputRequest, RegisterInputFromOutputResponse, RegisterModelRequest,
+    RegisterModelResponse, RegisterOutputFileRequest, RegisterOutputFileResponse,
+    RegisterSigningKeyRequest, RegisterSigningKeyResponse, RegisterTaskScheduleRequest,
+    RegisterTaskScheduleResponse, RegisterTaskTemplateRequest, RegisterTaskTemplateResponse,
+    RejectTaskRequest, RejectTaskResponse, RestoreArchivedTaskRequest, RestoreArchivedTaskResponse,
+    RestoreStorageSnapshotRequest, RestoreStorageSnapshotResponse, RevokeApprovalPolicyRequest,
+    RevokeApprovalPolicyResponse, RotateInputFileKeyRequest, RotateInputFileKeyResponse,
+    TeaclaveFrontend, TeaclaveFrontendApiRequest, TeaclaveFrontendApiResponse,
+    TeaclaveFrontendRequest, TestFunctionRequest, TestFunctionResponse, UpdateInputFileRequest,
+    UpdateInputFileResponse, UpdateOutputFileRequest, UpdateOutputFileResponse,
+    UpdateOutputUrlRequest, UpdateOutputUrlResponse,
 };
+use teaclave_proto::teaclave_frontend_service_v2::{TeaclaveFrontendV2, TeaclaveFrontendV2Request};
 use teaclave_proto::teaclave_management_service::TeaclaveManagementClient;
 use teaclave_rpc::endpoint::Endpoint;
-use teaclave_rpc::Request;
-use teaclave_service_enclave_utils::{bail, teaclave_service};
-use teaclave_types::TeaclaveServiceResponseResult;
+use teaclave_rpc::{Request, TeaclaveService};
+use teaclave_service_enclave_utils::{bail, ensure};
+use teaclave_types::{
+    Executor, ExecutorType, FunctionArguments, TeaclaveServiceResponseResult, TrustedTime,
+};
 
-#[teaclave_service(teaclave_frontend_service, TeaclaveFrontend, TeaclaveFrontendError)]
 #[derive(Clone)]
 pub(crate) struct TeaclaveFrontendService {
     authentication_client: Arc<Mutex<TeaclaveAuthenticationInternalClient>>,
     management_client: Arc<Mutex<TeaclaveManagementClient>>,
+    management_service_endpoint: Arc<Endpoint>,
+    limits: LimitsConfig,
+    allowed_executors: Vec<Executor>,
+    platform_info: GetPlatformInfoResponse,
+    admission: Arc<Admission>,
 }
 
 macro_rules! authentication_and_forward_to_management {
     ($service: ident, $request: ident, $func: ident) => {{
-        match $service.authenticate(&$request) {
+        match $service.authenticate(&$request, stringify!($func)) {
             Ok(true) => (),
             _ => bail!(TeaclaveFrontendError::AuthenticationError),
         }
+        ensure!(
+            $service.admit(&$request),
+            TeaclaveFrontendError::TooManyRequests
+        );
 
         let client = $service.management_client.clone();
         let mut client = client
@@ -75,7 +117,17 @@ impl TeaclaveFrontendService {
     pub(crate) fn new(
         authentication_service_endpoint: Endpoint,
         management_service_endpoint: Endpoint,
+        limits: LimitsConfig,
+        executors: &ExecutorsConfig,
+        platform_info: GetPlatformInfoResponse,
+        admission: Admission,
     ) -> Result<Self> {
+        let allowed_executors = executors
+            .allowed
+            .iter()
+            .map(|e| Executor::try_from(e.as_str()))
+            .collect::<Result<Vec<Executor>>>()?;
+
         let mut i = 0;
         let authentication_channel = loop {
             match authentication_service_endpoint.connect() {
@@ -111,8 +163,104 @@ impl TeaclaveFrontendService {
         Ok(Self {
             authentication_client,
             management_client,
+            management_service_endpoint: Arc::new(management_service_endpoint),
+            limits,
+            allowed_executors,
+            platform_info,
+            admission: Arc::new(admission),
         })
     }
+
+    // Lists are forwarded page by page, so that a response never holds more
+    // than the largest page regardless of how many tasks a user has.
+    fn limit_page(&self, page: &mut PageRequest) {
+        let max_page_size = self.limits.max_list_page_size;
+        if page.size == 0 || page.size > max_page_size {
+            page.size = max_page_size;
+        }
+    }
+
+    fn is_executor_allowed(&self, executor: Executor) -> bool {
+        self.allowed_executors.contains(&executor)
+    }
+
+    fn check_function_arguments(
+        &self,
+        function_arguments: &FunctionArguments,
+    ) -> TeaclaveServiceResponseResult<()> {
+        ensure!(
+            function_arguments.inner().len() <= self.limits.max_function_arguments,
+            TeaclaveFrontendError::TooManyArguments
+        );
+        ensure!(
+            function_arguments.clone().into_string().len()
+                <= self.limits.max_function_arguments_size,
+            TeaclaveFrontendError::ArgumentsTooLarge
+        );
+        Ok(())
+    }
+}
+
+// Requests are validated against the constraints of their endpoints before
+// dispatching, so that malformed fields are reported with their positions
+// instead of failing in the conversion or in the management service. Each
+// version of the API is dispatched to its own handlers.
+impl TeaclaveService<TeaclaveFrontendApiRequest, TeaclaveFrontendApiResponse>
+    for TeaclaveFrontendService
+{
+    fn handle_request(
+        &self,
+        request: Request<TeaclaveFrontendApiRequest>,
+    ) -> TeaclaveServiceResponseResult<TeaclaveFrontendApiResponse> {
+        let metadata = request.metadata;
+        match request.message {
+            TeaclaveFrontendApiRequest::V1(message) => {
+                validate_request(&message)?;
+                let request = Request { metadata, message };
+                TeaclaveFrontend::dispatch(self, request).map(TeaclaveFrontendApiResponse::V1)
+            }
+            TeaclaveFrontendApiRequest::V2(message) => {
+                validate_request_v2(&message)?;
+                let request = Request { metadata, message };
+                TeaclaveFrontendV2::dispatch(self, request).map(TeaclaveFrontendApiResponse::V2)
+            }
+        }
+    }
+
+    fn is_stream_request(&self, request: &Request<TeaclaveFrontendApiRequest>) -> bool {
+        match &request.message {
+            TeaclaveFrontendApiRequest::V1(message) => {
+                matches!(message, TeaclaveFrontendRequest::GetTaskResultStream(_))
+            }
+            TeaclaveFrontendApiRequest::V2(message) => {
+                matches!(message, TeaclaveFrontendV2Request::GetTaskResultStream(_))
+            }
+        }
+    }
+
+    fn handle_stream_request(
+        &self,
+        request: Request<TeaclaveFrontendApiRequest>,
+        sink: &mut dyn FnMut(TeaclaveFrontendApiResponse) -> TeaclaveServiceResponseResult<()>,
+    ) -> TeaclaveServiceResponseResult<()> {
+        let metadata = request.metadata;
+        match request.message {
+            TeaclaveFrontendApiRequest::V1(message) => {
+                validate_request(&message)?;
+                let request = Request { metadata, message };
+                TeaclaveFrontend::dispatch_stream(self, request, &mut |response| {
+                    sink(TeaclaveFrontendApiResponse::V1(response))
+                })
+            }
+            TeaclaveFrontendApiRequest::V2(message) => {
+                validate_request_v2(&message)?;
+                let request = Request { metadata, message };
+                TeaclaveFrontendV2::dispatch_stream(self, request, &mut |response| {
+                    sink(TeaclaveFrontendApiResponse::V2(response))
+                })
+            }
+        }
+    }
 }
 
 impl TeaclaveFrontend for TeaclaveFrontendService {
@@ -130,6 +278,13 @@ impl TeaclaveFrontend for TeaclaveFrontendService {
         authentication_and_forward_to_management!(self, request, update_input_file)
     }
 
+    fn rotate_input_file_key(
+        &self,
+        request: Request<RotateInputFileKeyRequest>,
+    ) -> TeaclaveServiceResponseResult<RotateInputFileKeyResponse> {
+        authentication_and_forward_to_management!(self, request, rotate_input_file_key)
+    }
+
     fn register_output_file(
         &self,
         request: Request<RegisterOutputFileRequest>,
@@ -144,6 +299,13 @@ impl TeaclaveFrontend for TeaclaveFrontendService {
         authentication_and_forward_to_management!(self, request, update_output_file)
     }
 
+    fn update_output_url(
+        &self,
+        request: Request<UpdateOutputUrlRequest>,
+    ) -> TeaclaveServiceResponseResult<UpdateOutputUrlResponse> {
+        authentication_and_forward_to_management!(self, request, update_output_url)
+    }
+
     fn register_fusion_output(
         &self,
         request: Request<RegisterFusionOutputRequest>,
@@ -171,10 +333,47 @@ impl TeaclaveFrontend for TeaclaveFrontendService {
         authentication_and_forward_to_management!(self, request, get_input_file)
     }
 
+    fn delete_input_file(
+        &self,
+        request: Request<DeleteInputFileRequest>,
+    ) -> TeaclaveServiceResponseResult<DeleteInputFileResponse> {
+        authentication_and_forward_to_management!(self, request, delete_input_file)
+    }
+
+    fn delete_output_file(
+        &self,
+        request: Request<DeleteOutputFileRequest>,
+    ) -> TeaclaveServiceResponseResult<DeleteOutputFileResponse> {
+        authentication_and_forward_to_management!(self, request, delete_output_file)
+    }
+
     fn register_function(
         &self,
         request: Request<RegisterFunctionRequest>,
     ) -> TeaclaveServiceResponseResult<RegisterFunctionResponse> {
+        let function = &request.message;
+        ensure!(
+            function.payload.len() <= self.limits.max_function_payload_size,
+            TeaclaveFrontendError::PayloadTooLarge
+        );
+        ensure!(
+            function.arguments.len() <= self.limits.max_function_arguments,
+            TeaclaveFrontendError::TooManyArguments
+        );
+        let arguments_size: usize = function.arguments.iter().map(|a| a.len()).sum();
+        ensure!(
+            arguments_size <= self.limits.max_function_arguments_size,
+            TeaclaveFrontendError::ArgumentsTooLarge
+        );
+        // Python functions can only be run by the MesaPy executor.
+        let executor = match function.executor_type {
+            ExecutorType::Builtin => Executor::Builtin,
+            ExecutorType::Python => Executor::MesaPy,
+        };
+        ensure!(
+            self.is_executor_allowed(executor),
+            TeaclaveFrontendError::ExecutorNotAllowed
+        );
         authentication_and_forward_to_management!(self, request, register_function)
     }
 
@@ -189,6 +388,11 @@ impl TeaclaveFrontend for TeaclaveFrontendService {
         &self,
         request: Request<CreateTaskRequest>,
     ) -> TeaclaveServiceResponseResult<CreateTaskResponse> {
+        self.check_function_arguments(&request.message.function_arguments)?;
+        ensure!(
+            self.is_executor_allowed(request.message.executor),
+            TeaclaveFrontendError::ExecutorNotAllowed
+        );
         authentication_and_forward_to_management!(self, request, create_task)
     }
 
@@ -199,6 +403,38 @@ impl TeaclaveFrontend for TeaclaveFrontendService {
         authentication_and_forward_to_management!(self, request, get_task)
     }
 
+    fn list_tasks(
+        &self,
+        mut request: Request<ListTasksRequest>,
+    ) -> TeaclaveServiceResponseResult<ListTasksResponse> {
+        self.limit_page(&mut request.message.page);
+        authentication_and_forward_to_management!(self, request, list_tasks)
+    }
+
+    fn list_pending_approvals(
+        &self,
+        mut request: Request<ListPendingApprovalsRequest>,
+    ) -> TeaclaveServiceResponseResult<ListPendingApprovalsResponse> {
+        self.limit_page(&mut request.message.page);
+        authentication_and_forward_to_management!(self, request, list_pending_approvals)
+    }
+
+    fn list_functions(
+        &self,
+        mut request: Request<ListFunctionsRequest>,
+    ) -> TeaclaveServiceResponseResult<ListFunctionsResponse> {
+        self.limit_page(&mut request.message.page);
+        authentication_and_forward_to_management!(self, request, list_functions)
+    }
+
+    fn list_files(
+        &self,
+        mut request: Request<ListFilesRequest>,
+    ) -> TeaclaveServiceResponseResult<ListFilesResponse> {
+        self.limit_page(&mut request.message.page);
+        authentication_and_forward_to_management!(self, request, list_files)
+    }
+
     fn assign_data(
         &self,
         request: Request<AssignDataRequest>,
@@ -219,10 +455,291 @@ impl TeaclaveFrontend for TeaclaveFrontendService {
     ) -> TeaclaveServiceResponseResult<InvokeTaskResponse> {
         authentication_and_forward_to_management!(self, request, invoke_task)
     }
+
+    fn get_task_manifest(
+        &self,
+        request: Request<GetTaskManifestRequest>,
+    ) -> TeaclaveServiceResponseResult<GetTaskManifestResponse> {
+        authentication_and_forward_to_management!(self, request, get_task_manifest)
+    }
+
+    fn get_task_return_value(
+        &self,
+        request: Request<GetTaskReturnValueRequest>,
+    ) -> TeaclaveServiceResponseResult<GetTaskReturnValueResponse> {
+        authentication_and_forward_to_management!(self, request, get_task_return_value)
+    }
+
+    fn get_task_key_offer(
+        &self,
+        request: Request<GetTaskKeyOfferRequest>,
+    ) -> TeaclaveServiceResponseResult<GetTaskKeyOfferResponse> {
+        authentication_and_forward_to_management!(self, request, get_task_key_offer)
+    }
+
+    fn put_task_keys(
+        &self,
+        request: Request<PutTaskKeysRequest>,
+    ) -> TeaclaveServiceResponseResult<PutTaskKeysResponse> {
+        authentication_and_forward_to_management!(self, request, put_task_keys)
+    }
+
+    fn get_data_lineage(
+        &self,
+        request: Request<GetDataLineageRequest>,
+    ) -> TeaclaveServiceResponseResult<GetDataLineageResponse> {
+        authentication_and_forward_to_management!(self, request, get_data_lineage)
+    }
+
+    fn register_approval_policy(
+        &self,
+        request: Request<RegisterApprovalPolicyRequest>,
+    ) -> TeaclaveServiceResponseResult<RegisterApprovalPolicyResponse> {
+        authentication_and_forward_to_management!(self, request, register_approval_policy)
+    }
+
+    fn revoke_approval_policy(
+        &self,
+        request: Request<RevokeApprovalPolicyRequest>,
+    ) -> TeaclaveServiceResponseResult<RevokeApprovalPolicyResponse> {
+        authentication_and_forward_to_management!(self, request, revoke_approval_policy)
+    }
+
+    fn register_model(
+        &self,
+        request: Request<RegisterModelRequest>,
+    ) -> TeaclaveServiceResponseResult<RegisterModelResponse> {
+        authentication_and_forward_to_management!(self, request, register_model)
+    }
+
+    fn get_model(
+        &self,
+        request: Request<GetModelRequest>,
+    ) -> TeaclaveServiceResponseResult<GetModelResponse> {
+        authentication_and_forward_to_management!(self, request, get_model)
+    }
+
+    fn create_task_share_link(
+        &self,
+        request: Request<CreateTaskShareLinkRequest>,
+    ) -> TeaclaveServiceResponseResult<CreateTaskShareLinkResponse> {
+        authentication_and_forward_to_management!(self, request, create_task_share_link)
+    }
+
+    fn archive_task(
+        &self,
+        request: Request<ArchiveTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<ArchiveTaskResponse> {
+        authentication_and_forward_to_management!(self, request, archive_task)
+    }
+
+    fn restore_archived_task(
+        &self,
+        request: Request<RestoreArchivedTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<RestoreArchivedTaskResponse> {
+        authentication_and_forward_to_management!(self, request, restore_archived_task)
+    }
+
+    fn export_storage_snapshot(
+        &self,
+        request: Request<ExportStorageSnapshotRequest>,
+    ) -> TeaclaveServiceResponseResult<ExportStorageSnapshotResponse> {
+        authentication_and_forward_to_management!(self, request, export_storage_snapshot)
+    }
+
+    fn restore_storage_snapshot(
+        &self,
+        request: Request<RestoreStorageSnapshotRequest>,
+    ) -> TeaclaveServiceResponseResult<RestoreStorageSnapshotResponse> {
+        authentication_and_forward_to_management!(self, request, restore_storage_snapshot)
+    }
+
+    fn promote_storage_replica(
+        &self,
+        request: Request<PromoteStorageReplicaRequest>,
+    ) -> TeaclaveServiceResponseResult<PromoteStorageReplicaResponse> {
+        authentication_and_forward_to_management!(self, request, promote_storage_replica)
+    }
+
+    fn cancel_task(
+        &self,
+        request: Request<CancelTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<CancelTaskResponse> {
+        authentication_and_forward_to_management!(self, request, cancel_task)
+    }
+
+    fn get_task_upload_slots(
+        &self,
+        request: Request<GetTaskUploadSlotsRequest>,
+    ) -> TeaclaveServiceResponseResult<GetTaskUploadSlotsResponse> {
+        authentication_and_forward_to_management!(self, request, get_task_upload_slots)
+    }
+
+    fn reject_task(
+        &self,
+        request: Request<RejectTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<RejectTaskResponse> {
+        authentication_and_forward_to_management!(self, request, reject_task)
+    }
+
+    fn get_activity_feed(
+        &self,
+        request: Request<GetActivityFeedRequest>,
+    ) -> TeaclaveServiceResponseResult<GetActivityFeedResponse> {
+        authentication_and_forward_to_management!(self, request, get_activity_feed)
+    }
+
+    // A stream is forwarded over a connection of its own to the management
+    // service, so that a slow client does not hold up the other requests.
+    fn get_task_result_stream(
+        &self,
+        request: Request<GetTaskResultStreamRequest>,
+        sink: &mut dyn FnMut(GetTaskResultStreamResponse) -> TeaclaveServiceResponseResult<()>,
+    ) -> TeaclaveServiceResponseResult<()> {
+        match self.authenticate(&request, "get_task_result_stream") {
+            Ok(true) => (),
+            _ => bail!(TeaclaveFrontendError::AuthenticationError),
+        }
+        ensure!(self.admit(&request), TeaclaveFrontendError::TooManyRequests);
+
+        let channel = self
+            .management_service_endpoint
+            .connect()
+            .map_err(|_| TeaclaveFrontendError::ConnectionError)?;
+        let mut client = TeaclaveManagementClient::new_with_metadata(channel, request.metadata)
+            .map_err(|_| TeaclaveFrontendError::ConnectionError)?;
+        for response in client.get_task_result_stream(request.message)? {
+            sink(response?)?;
+        }
+        Ok(())
+    }
+
+    fn register_task_template(
+        &self,
+        request: Request<RegisterTaskTemplateRequest>,
+    ) -> TeaclaveServiceResponseResult<RegisterTaskTemplateResponse> {
+        self.check_function_arguments(&request.message.function_arguments)?;
+        ensure!(
+            self.is_executor_allowed(request.message.executor),
+            TeaclaveFrontendError::ExecutorNotAllowed
+        );
+        authentication_and_forward_to_management!(self, request, register_task_template)
+    }
+
+    fn create_task_from_template(
+        &self,
+        request: Request<CreateTaskFromTemplateRequest>,
+    ) -> TeaclaveServiceResponseResult<CreateTaskFromTemplateResponse> {
+        self.check_function_arguments(&request.message.function_arguments)?;
+        authentication_and_forward_to_management!(self, request, create_task_from_template)
+    }
+
+    fn create_tasks_batch(
+        &self,
+        request: Request<CreateTasksBatchRequest>,
+    ) -> TeaclaveServiceResponseResult<CreateTasksBatchResponse> {
+        let tasks = &request.message.tasks;
+        ensure!(
+            tasks.len() <= self.limits.max_batch_tasks,
+            TeaclaveFrontendError::TooManyTasks
+        );
+        for task in tasks.iter() {
+            self.check_function_arguments(&task.function_arguments)?;
+            ensure!(
+                self.is_executor_allowed(task.executor),
+                TeaclaveFrontendError::ExecutorNotAllowed
+            );
+        }
+        authentication_and_forward_to_management!(self, request, create_tasks_batch)
+    }
+
+    fn test_function(
+        &self,
+        request: Request<TestFunctionRequest>,
+    ) -> TeaclaveServiceResponseResult<TestFunctionResponse> {
+        self.check_function_arguments(&request.message.function_arguments)?;
+        ensure!(
+            self.is_executor_allowed(request.message.executor),
+            TeaclaveFrontendError::ExecutorNotAllowed
+        );
+        authentication_and_forward_to_management!(self, request, test_function)
+    }
+
+    fn register_task_schedule(
+        &self,
+        request: Request<RegisterTaskScheduleRequest>,
+    ) -> TeaclaveServiceResponseResult<RegisterTaskScheduleResponse> {
+        authentication_and_forward_to_management!(self, request, register_task_schedule)
+    }
+
+    fn approve_task_schedule(
+        &self,
+        request: Request<ApproveTaskScheduleRequest>,
+    ) -> TeaclaveServiceResponseResult<ApproveTaskScheduleResponse> {
+        authentication_and_forward_to_management!(self, request, approve_task_schedule)
+    }
+
+    fn cancel_task_schedule(
+        &self,
+        request: Request<CancelTaskScheduleRequest>,
+    ) -> TeaclaveServiceResponseResult<CancelTaskScheduleResponse> {
+        authentication_and_forward_to_management!(self, request, cancel_task_schedule)
+    }
+
+    fn get_task_schedule(
+        &self,
+        request: Request<GetTaskScheduleRequest>,
+    ) -> TeaclaveServiceResponseResult<GetTaskScheduleResponse> {
+        authentication_and_forward_to_management!(self, request, get_task_schedule)
+    }
+
+    fn estimate_task(
+        &self,
+        request: Request<EstimateTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<EstimateTaskResponse> {
+        authentication_and_forward_to_management!(self, request, estimate_task)
+    }
+
+    fn register_signing_key(
+        &self,
+        request: Request<RegisterSigningKeyRequest>,
+    ) -> TeaclaveServiceResponseResult<RegisterSigningKeyResponse> {
+        authentication_and_forward_to_management!(self, request, register_signing_key)
+    }
+
+    // The token is the only credential of a shared task, so the request is
+    // forwarded without any user id for observers who are not registered.
+    fn get_shared_task(
+        &self,
+        request: Request<GetSharedTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<GetSharedTaskResponse> {
+        let client = self.management_client.clone();
+        let mut client = client
+            .lock()
+            .map_err(|_| TeaclaveFrontendError::LockError)?;
+        client.metadata_mut().clear();
+        let response = client.get_shared_task(request.message)?;
+        Ok(response)
+    }
+
+    // The enclave info is public, relying parties verify the signatures of
+    // the auditors over it before trusting the measurements.
+    fn get_platform_info(
+        &self,
+        _request: Request<GetPlatformInfoRequest>,
+    ) -> TeaclaveServiceResponseResult<GetPlatformInfoResponse> {
+        Ok(self.platform_info.clone())
+    }
 }
 
 impl TeaclaveFrontendService {
-    fn authenticate<T>(&self, request: &Request<T>) -> anyhow::Result<bool> {
+    // A down-scoped token is only accepted for the operations in its scope,
+    // and only for requests naming no resources outside of it.
+    fn authenticate<T: ScopedRequest>(
+        &self,
+        request: &Request<T>,
+        operation: &str,
+    ) -> anyhow::Result<bool> {
         use anyhow::anyhow;
         let id = request
             .metadata
@@ -239,7 +756,108 @@ impl TeaclaveFrontendService {
             .clone()
             .lock()
             .map_err(|_| anyhow!("Cannot lock authentication client"))?
-            .user_authenticate(auth_request);
-        Ok(auth_response?.accept)
+            .user_authenticate(auth_request)?;
+        let accept = match auth_response.scope {
+            Some(scope) => {
+                auth_response.accept && scope.permits(operation, &request.message.resources())
+            }
+            None => auth_response.accept,
+        };
+        Ok(accept)
+    }
+
+    // Requests are admitted by the QoS class of the user once authenticated.
+    fn admit<T>(&self, request: &Request<T>) -> bool {
+        let now = TrustedTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        match request.metadata.get("id") {
+            Some(id) => self.admission.admit(id, now),
+            None => false,
+        }
+    }
+}
+
+macro_rules! forward_to_v1 {
+    ($($func: ident($request: ident) -> $response: ident;)*) => {
+        $(
+            fn $func(
+                &self,
+                request: Request<$request>,
+            ) -> TeaclaveServiceResponseResult<$response> {
+                TeaclaveFrontend::$func(self, request)
+            }
+        )*
+    };
+}
+
+// Messages of both versions are converted to the same types, so the handlers
+// of version 2 forward them to those of version 1 until a method changes its
+// semantics in version 2.
+impl TeaclaveFrontendV2 for TeaclaveFrontendService {
+    forward_to_v1! {
+        register_input_file(RegisterInputFileRequest) -> RegisterInputFileResponse;
+        register_output_file(RegisterOutputFileRequest) -> RegisterOutputFileResponse;
+        update_input_file(UpdateInputFileRequest) -> UpdateInputFileResponse;
+        rotate_input_file_key(RotateInputFileKeyRequest) -> RotateInputFileKeyResponse;
+        update_output_file(UpdateOutputFileRequest) -> UpdateOutputFileResponse;
+        update_output_url(UpdateOutputUrlRequest) -> UpdateOutputUrlResponse;
+        register_fusion_output(RegisterFusionOutputRequest) -> RegisterFusionOutputResponse;
+        register_input_from_output(RegisterInputFromOutputRequest) -> RegisterInputFromOutputResponse;
+        get_output_file(GetOutputFileRequest) -> GetOutputFileResponse;
+        get_input_file(GetInputFileRequest) -> GetInputFileResponse;
+        delete_input_file(DeleteInputFileRequest) -> DeleteInputFileResponse;
+        delete_output_file(DeleteOutputFileRequest) -> DeleteOutputFileResponse;
+        register_function(RegisterFunctionRequest) -> RegisterFunctionResponse;
+        get_function(GetFunctionRequest) -> GetFunctionResponse;
+        create_task(CreateTaskRequest) -> CreateTaskResponse;
+        get_task(GetTaskRequest) -> GetTaskResponse;
+        list_tasks(ListTasksRequest) -> ListTasksResponse;
+        list_pending_approvals(ListPendingApprovalsRequest) -> ListPendingApprovalsResponse;
+        list_functions(ListFunctionsRequest) -> ListFunctionsResponse;
+        list_files(ListFilesRequest) -> ListFilesResponse;
+        assign_data(AssignDataRequest) -> AssignDataResponse;
+        approve_task(ApproveTaskRequest) -> ApproveTaskResponse;
+        invoke_task(InvokeTaskRequest) -> InvokeTaskResponse;
+        get_task_manifest(GetTaskManifestRequest) -> GetTaskManifestResponse;
+        get_task_return_value(GetTaskReturnValueRequest) -> GetTaskReturnValueResponse;
+        get_task_key_offer(GetTaskKeyOfferRequest) -> GetTaskKeyOfferResponse;
+        put_task_keys(PutTaskKeysRequest) -> PutTaskKeysResponse;
+        get_data_lineage(GetDataLineageRequest) -> GetDataLineageResponse;
+        register_approval_policy(RegisterApprovalPolicyRequest) -> RegisterApprovalPolicyResponse;
+        revoke_approval_policy(RevokeApprovalPolicyRequest) -> RevokeApprovalPolicyResponse;
+        register_model(RegisterModelRequest) -> RegisterModelResponse;
+        get_model(GetModelRequest) -> GetModelResponse;
+        create_task_share_link(CreateTaskShareLinkRequest) -> CreateTaskShareLinkResponse;
+        get_shared_task(GetSharedTaskRequest) -> GetSharedTaskResponse;
+        archive_task(ArchiveTaskRequest) -> ArchiveTaskResponse;
+        restore_archived_task(RestoreArchivedTaskRequest) -> RestoreArchivedTaskResponse;
+        export_storage_snapshot(ExportStorageSnapshotRequest) -> ExportStorageSnapshotResponse;
+        restore_storage_snapshot(RestoreStorageSnapshotRequest) -> RestoreStorageSnapshotResponse;
+        promote_storage_replica(PromoteStorageReplicaRequest) -> PromoteStorageReplicaResponse;
+        cancel_task(CancelTaskRequest) -> CancelTaskResponse;
+        get_task_upload_slots(GetTaskUploadSlotsRequest) -> GetTaskUploadSlotsResponse;
+        reject_task(RejectTaskRequest) -> RejectTaskResponse;
+        get_activity_feed(GetActivityFeedRequest) -> GetActivityFeedResponse;
+        register_task_template(RegisterTaskTemplateRequest) -> RegisterTaskTemplateResponse;
+        create_task_from_template(CreateTaskFromTemplateRequest) -> CreateTaskFromTemplateResponse;
+        create_tasks_batch(CreateTasksBatchRequest) -> CreateTasksBatchResponse;
+        test_function(TestFunctionRequest) -> TestFunctionResponse;
+        register_task_schedule(RegisterTaskScheduleRequest) -> RegisterTaskScheduleResponse;
+        approve_task_schedule(ApproveTaskScheduleRequest) -> ApproveTaskScheduleResponse;
+        cancel_task_schedule(CancelTaskScheduleRequest) -> CancelTaskScheduleResponse;
+        get_task_schedule(GetTaskScheduleRequest) -> GetTaskScheduleResponse;
+        estimate_task(EstimateTaskRequest) -> EstimateTaskResponse;
+        get_platform_info(GetPlatformInfoRequest) -> GetPlatformInfoResponse;
+        register_signing_key(RegisterSigningKeyRequest) -> RegisterSigningKeyResponse;
+    }
+
+    fn get_task_result_stream(
+        &self,
+        request: Request<GetTaskResultStreamRequest>,
+        sink: &mut dyn FnMut(GetTaskResultStreamResponse) -> TeaclaveServiceResponseResult<()>,
+    ) -> TeaclaveServiceResponseResult<()> {
+        TeaclaveFrontend::get_task_result_stream(self, request, sink)
     }
 }
@@ -0,0 +1,901 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::prelude::v1::*;
+
+use teaclave_proto::teaclave_common_proto::{FileCredentialInfo, FileCryptoInfo, KeyBrokerInfo};
+use teaclave_proto::teaclave_frontend_service::TeaclaveFrontendRequest;
+use teaclave_proto::teaclave_frontend_service_proto as proto;
+use teaclave_proto::teaclave_frontend_service_v2::TeaclaveFrontendV2Request;
+use teaclave_proto::teaclave_frontend_service_v2_proto as proto_v2;
+use teaclave_proto::teaclave_frontend_service_v2_proto::function_argument::Value;
+use teaclave_types::{
+    ArgumentTemplate, Executor, ExecutorType, ExternalID, FileAuthTag, FileCredential, FileCrypto,
+    Function, ModelReference, PostProcessingStep, Storable, TaskPriority, TaskState, TaskTemplate,
+    TeaclaveInputFile, TeaclaveOutputFile, TeaclaveServiceResponseError, CHUNKED_URL_SCHEME_PREFIX,
+    MAX_FUNCTION_TEST_FILE_SIZE, MAX_TASK_RETRIES, MIN_SCHEDULE_INTERVAL,
+    SIGNING_PUBLIC_KEY_LENGTH,
+};
+use url::Url;
+
+const INPUT_URL_SCHEMES: &[&str] = &[
+    "http",
+    "https",
+    "file",
+    "fusion",
+    "data",
+    "push",
+    "chunked+http",
+    "chunked+https",
+];
+const OUTPUT_URL_SCHEMES: &[&str] = &["http", "https", "file", "fusion", "s3"];
+// Share links expire in 30 days at most.
+const MAX_SHARE_LINK_EXPIRES_IN: u64 = 30 * 24 * 3600;
+// Tasks expire in a year at most.
+const MAX_TASK_EXPIRES_IN: u64 = 365 * 24 * 3600;
+// Schedules start running in a year at most.
+const MAX_SCHEDULE_START_IN: u64 = 365 * 24 * 3600;
+// Names of snapshots are at most 64 characters long at the storage services,
+// including the suffixes of the shards.
+const MAX_SNAPSHOT_NAME_LENGTH: usize = 64;
+const MAX_EXPORTED_SNAPSHOT_NAME_LENGTH: usize = 48;
+// Snapshots are sealed with 128-bit keys.
+const SNAPSHOT_KEY_LENGTH: usize = 16;
+// Requests are signed with Ed25519.
+const SIGNATURE_LENGTH: usize = 64;
+
+/// A constraint violation of a frontend request. The field is the path of the
+/// offending value in the request, e.g., `inputs_ownership[1].uids[0]`.
+#[derive(Debug)]
+pub(crate) struct FieldError {
+    field: String,
+    reason: String,
+}
+
+impl FieldError {
+    fn new(field: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            reason: reason.into(),
+        }
+    }
+
+    // Locates the error of a nested message in the request.
+    fn within(self, parent: &str) -> Self {
+        Self {
+            field: format!("{}.{}", parent, self.field),
+            reason: self.reason,
+        }
+    }
+}
+
+impl From<FieldError> for TeaclaveServiceResponseError {
+    fn from(error: FieldError) -> Self {
+        TeaclaveServiceResponseError::ValidationError {
+            field: error.field,
+            reason: error.reason,
+        }
+    }
+}
+
+type ValidationResult = std::result::Result<(), FieldError>;
+
+/// Checks a request against the constraints of its endpoint before it is
+/// converted and forwarded to the management service.
+pub(crate) fn validate_request(request: &TeaclaveFrontendRequest) -> ValidationResult {
+    use TeaclaveFrontendRequest::*;
+
+    match request {
+        RegisterInputFile(r) => validate_register_input_file(r),
+        RegisterOutputFile(r) => validate_register_output_file(r),
+        UpdateInputFile(r) => validate_update_input_file(r),
+        RotateInputFileKey(r) => validate_rotate_input_file_key(r),
+        UpdateOutputFile(r) => validate_update_output_file(r),
+        UpdateOutputUrl(r) => validate_update_output_url(r),
+        RegisterFusionOutput(r) => validate_user_list("owner_list", &r.owner_list),
+        RegisterInputFromOutput(r) => {
+            validate_id("data_id", &r.data_id, TeaclaveOutputFile::key_prefix())
+        }
+        GetOutputFile(r) => validate_id("data_id", &r.data_id, TeaclaveOutputFile::key_prefix()),
+        GetInputFile(r) => validate_id("data_id", &r.data_id, TeaclaveInputFile::key_prefix()),
+        DeleteInputFile(r) => validate_id("data_id", &r.data_id, TeaclaveInputFile::key_prefix()),
+        DeleteOutputFile(r) => validate_id("data_id", &r.data_id, TeaclaveOutputFile::key_prefix()),
+        RegisterFunction(r) => validate_register_function(r),
+        GetFunction(r) => validate_id("function_id", &r.function_id, Function::key_prefix()),
+        CreateTask(r) => validate_create_task(r),
+        GetTask(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        ListTasks(r) => validate_list_tasks(r),
+        ListPendingApprovals(_) => Ok(()),
+        ListFunctions(_) => Ok(()),
+        ListFiles(_) => Ok(()),
+        AssignData(r) => validate_assign_data(r),
+        ApproveTask(r) => validate_approve_task(r),
+        InvokeTask(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        GetTaskManifest(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        GetTaskReturnValue(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        GetTaskKeyOffer(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        PutTaskKeys(r) => validate_put_task_keys(r),
+        GetDataLineage(r) => validate_data_id("data_id", &r.data_id),
+        RegisterApprovalPolicy(r) => validate_register_approval_policy(r),
+        RevokeApprovalPolicy(r) => validate_not_empty("policy_id", &r.policy_id),
+        RegisterModel(r) => validate_register_model(r),
+        GetModel(r) => validate_not_empty("name", &r.name),
+        CreateTaskShareLink(r) => validate_create_task_share_link(r),
+        GetSharedTask(r) => validate_not_empty("token", &r.token),
+        GetPlatformInfo(_) => Ok(()),
+        ArchiveTask(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        RestoreArchivedTask(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        ExportStorageSnapshot(r) => validate_export_storage_snapshot(r),
+        RestoreStorageSnapshot(r) => validate_restore_storage_snapshot(r),
+        PromoteStorageReplica(r) => validate_not_empty("name", &r.name),
+        CancelTask(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        GetTaskUploadSlots(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        RejectTask(r) => validate_reject_task(r),
+        GetActivityFeed(_) => Ok(()),
+        GetTaskResultStream(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        RegisterTaskTemplate(r) => validate_register_task_template(r),
+        CreateTaskFromTemplate(r) => validate_create_task_from_template(r),
+        CreateTasksBatch(r) => validate_create_tasks_batch(r),
+        TestFunction(r) => validate_test_function(r),
+        RegisterTaskSchedule(r) => validate_register_task_schedule(r),
+        ApproveTaskSchedule(r) => {
+            validate_id("template_id", &r.template_id, TaskTemplate::key_prefix())
+        }
+        CancelTaskSchedule(r) => {
+            validate_id("template_id", &r.template_id, TaskTemplate::key_prefix())
+        }
+        GetTaskSchedule(r) => {
+            validate_id("template_id", &r.template_id, TaskTemplate::key_prefix())
+        }
+        EstimateTask(r) => validate_estimate_task(r),
+        RegisterSigningKey(r) => validate_signing_key("public_key", &r.public_key),
+    }
+}
+
+/// Checks a request of version 2. Requests other than those of the methods
+/// changed in the version are checked as in version 1.
+pub(crate) fn validate_request_v2(request: &TeaclaveFrontendV2Request) -> ValidationResult {
+    use TeaclaveFrontendV2Request::*;
+
+    match request {
+        RegisterInputFile(r) => validate_register_input_file(r),
+        RegisterOutputFile(r) => validate_register_output_file(r),
+        UpdateInputFile(r) => validate_update_input_file(r),
+        RotateInputFileKey(r) => validate_rotate_input_file_key(r),
+        UpdateOutputFile(r) => validate_update_output_file(r),
+        UpdateOutputUrl(r) => validate_update_output_url(r),
+        RegisterFusionOutput(r) => validate_user_list("owner_list", &r.owner_list),
+        RegisterInputFromOutput(r) => {
+            validate_id("data_id", &r.data_id, TeaclaveOutputFile::key_prefix())
+        }
+        GetOutputFile(r) => validate_id("data_id", &r.data_id, TeaclaveOutputFile::key_prefix()),
+        GetInputFile(r) => validate_id("data_id", &r.data_id, TeaclaveInputFile::key_prefix()),
+        DeleteInputFile(r) => validate_id("data_id", &r.data_id, TeaclaveInputFile::key_prefix()),
+        DeleteOutputFile(r) => validate_id("data_id", &r.data_id, TeaclaveOutputFile::key_prefix()),
+        RegisterFunction(r) => validate_register_function(r),
+        GetFunction(r) => validate_id("function_id", &r.function_id, Function::key_prefix()),
+        CreateTask(r) => validate_create_task_v2(r),
+        GetTask(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        ListTasks(r) => validate_list_tasks(r),
+        ListPendingApprovals(_) => Ok(()),
+        ListFunctions(_) => Ok(()),
+        ListFiles(_) => Ok(()),
+        AssignData(r) => validate_assign_data(r),
+        ApproveTask(r) => validate_approve_task(r),
+        InvokeTask(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        GetTaskManifest(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        GetTaskReturnValue(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        GetTaskKeyOffer(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        PutTaskKeys(r) => validate_put_task_keys(r),
+        GetDataLineage(r) => validate_data_id("data_id", &r.data_id),
+        RegisterApprovalPolicy(r) => validate_register_approval_policy(r),
+        RevokeApprovalPolicy(r) => validate_not_empty("policy_id", &r.policy_id),
+        RegisterModel(r) => validate_register_model(r),
+        GetModel(r) => validate_not_empty("name", &r.name),
+        CreateTaskShareLink(r) => validate_create_task_share_link(r),
+        GetSharedTask(r) => validate_not_empty("token", &r.token),
+        GetPlatformInfo(_) => Ok(()),
+        ArchiveTask(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        RestoreArchivedTask(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        ExportStorageSnapshot(r) => validate_export_storage_snapshot(r),
+        RestoreStorageSnapshot(r) => validate_restore_storage_snapshot(r),
+        PromoteStorageReplica(r) => validate_not_empty("name", &r.name),
+        CancelTask(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        GetTaskUploadSlots(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        RejectTask(r) => validate_reject_task(r),
+        GetActivityFeed(_) => Ok(()),
+        GetTaskResultStream(r) => validate_id("task_id", &r.task_id, TaskState::key_prefix()),
+        RegisterTaskTemplate(r) => validate_register_task_template(r),
+        CreateTaskFromTemplate(r) => validate_create_task_from_template(r),
+        CreateTasksBatch(r) => validate_create_tasks_batch(r),
+        TestFunction(r) => validate_test_function(r),
+        RegisterTaskSchedule(r) => validate_register_task_schedule(r),
+        ApproveTaskSchedule(r) => {
+            validate_id("template_id", &r.template_id, TaskTemplate::key_prefix())
+        }
+        CancelTaskSchedule(r) => {
+            validate_id("template_id", &r.template_id, TaskTemplate::key_prefix())
+        }
+        GetTaskSchedule(r) => {
+            validate_id("template_id", &r.template_id, TaskTemplate::key_prefix())
+        }
+        EstimateTask(r) => validate_estimate_task(r),
+        RegisterSigningKey(r) => validate_signing_key("public_key", &r.public_key),
+    }
+}
+
+fn validate_register_input_file(request: &proto::RegisterInputFileRequest) -> ValidationResult {
+    validate_url("url", &request.url, INPUT_URL_SCHEMES)?;
+    validate_cmac("cmac", &request.cmac)?;
+    // The key of a brokered file is never given to Teaclave.
+    match &request.key_broker {
+        Some(key_broker) => match request.crypto_info {
+            Some(_) => Err(FieldError::new(
+                "crypto_info",
+                "crypto info with key broker",
+            )),
+            None => validate_key_broker(key_broker).map_err(|e| e.within("key_broker")),
+        },
+        None => validate_crypto_info("crypto_info", &request.crypto_info),
+    }
+}
+
+fn validate_key_broker(key_broker: &KeyBrokerInfo) -> ValidationResult {
+    validate_not_empty("address", &key_broker.address)?;
+    if key_broker.address.contains("://") {
+        return Err(FieldError::new("address", "address with scheme"));
+    }
+    validate_not_empty("key_id", &key_broker.key_id)
+}
+
+fn validate_register_output_file(request: &proto::RegisterOutputFileRequest) -> ValidationResult {
+    validate_crypto_info("crypto_info", &request.crypto_info)?;
+    // The url of a deferred output is set with its credential later.
+    if request.url.is_empty() {
+        return match request.credential {
+            Some(_) => Err(FieldError::new("credential", "credential without url")),
+            None => Ok(()),
+        };
+    }
+    validate_url("url", &request.url, OUTPUT_URL_SCHEMES)?;
+    validate_credential("credential", &request.url, &request.credential)
+}
+
+fn validate_update_input_file(request: &proto::UpdateInputFileRequest) -> ValidationResult {
+    validate_id("data_id", &request.data_id, TeaclaveInputFile::key_prefix())?;
+    validate_url("url", &request.url, INPUT_URL_SCHEMES)
+}
+
+fn validate_rotate_input_file_key(request: &proto::RotateInputFileKeyRequest) -> ValidationResult {
+    validate_id("data_id", &request.data_id, TeaclaveInputFile::key_prefix())?;
+    validate_url("url", &request.url, INPUT_URL_SCHEMES)?;
+    validate_cmac("cmac", &request.cmac)?;
+    validate_crypto_info("crypto_info", &request.crypto_info)
+}
+
+fn validate_update_output_file(request: &proto::UpdateOutputFileRequest) -> ValidationResult {
+    validate_id(
+        "data_id",
+        &request.data_id,
+        TeaclaveOutputFile::key_prefix(),
+    )?;
+    validate_url("url", &request.url, OUTPUT_URL_SCHEMES)?;
+    validate_credential("credential", &request.url, &request.credential)
+}
+
+fn validate_update_output_url(request: &proto::UpdateOutputUrlRequest) -> ValidationResult {
+    validate_id(
+        "data_id",
+        &request.data_id,
+        TeaclaveOutputFile::key_prefix(),
+    )?;
+    validate_url("url", &request.url, OUTPUT_URL_SCHEMES)?;
+    validate_credential("credential", &request.url, &request.credential)
+}
+
+fn validate_register_function(request: &proto::RegisterFunctionRequest) -> ValidationResult {
+    validate_not_empty("name", &request.name)?;
+    let executor_type = ExecutorType::try_from(request.executor_type.as_str())
+        .map_err(|_| FieldError::new("executor_type", "unsupported executor type"))?;
+    validate_unique_names(
+        "inputs",
+        "name",
+        request.inputs.iter().map(|input| input.name.as_str()),
+    )?;
+    validate_unique_names(
+        "outputs",
+        "name",
+        request.outputs.iter().map(|output| output.name.as_str()),
+    )?;
+    // Builtin functions have a single entry point.
+    if executor_type == ExecutorType::Builtin && !request.entry_points.is_empty() {
+        return Err(FieldError::new(
+            "entry_points",
+            "not supported by builtin functions",
+        ));
+    }
+    let mut seen = HashSet::new();
+    for (i, entry_point) in request.entry_points.iter().enumerate() {
+        let field = format!("entry_points[{}]", i);
+        validate_not_empty(&field, entry_point)?;
+        if !seen.insert(entry_point) {
+            return Err(FieldError::new(field, "duplicated entry point"));
+        }
+    }
+    // Output files cannot be written without egress.
+    if let Some(capabilities) = &request.capabilities {
+        if !capabilities.egress && !request.outputs.is_empty() {
+            return Err(FieldError::new(
+                "capabilities.egress",
+                "required by the outputs of the function",
+            ));
+        }
+    }
+    for (i, measurement) in request.worker_measurements.iter().enumerate() {
+        validate_measurement(&format!("worker_measurements[{}]", i), measurement)?;
+    }
+    for (i, step) in request.post_processing.iter().enumerate() {
+        let field = format!("post_processing[{}]", i);
+        PostProcessingStep::try_from(step.clone())
+            .and_then(|step| step.check())
+            .map_err(|e| FieldError::new(field, e.to_string()))?;
+    }
+    Ok(())
+}
+
+fn validate_list_tasks(request: &proto::ListTasksRequest) -> ValidationResult {
+    if !request.function_id.is_empty() {
+        validate_id("function_id", &request.function_id, Function::key_prefix())?;
+    }
+    if request.created_after > 0
+        && request.created_before > 0
+        && request.created_after >= request.created_before
+    {
+        return Err(FieldError::new(
+            "created_before",
+            "must be later than created_after",
+        ));
+    }
+    Ok(())
+}
+
+fn validate_create_task(request: &proto::CreateTaskRequest) -> ValidationResult {
+    validate_id("function_id", &request.function_id, Function::key_prefix())?;
+    validate_function_arguments("function_arguments", &request.function_arguments)?;
+    Executor::try_from(request.executor.as_str())
+        .map_err(|_| FieldError::new("executor", "unsupported executor"))?;
+    validate_ownership("inputs_ownership", &request.inputs_ownership)?;
+    validate_ownership("outputs_ownership", &request.outputs_ownership)?;
+    validate_task_expires_in("expires_in", request.expires_in)?;
+    validate_task_expires_in("approval_window", request.approval_window)?;
+    validate_input_dependencies("input_dependencies", &request.input_dependencies)?;
+    validate_priority("priority", &request.priority)?;
+    validate_retry_policy("retry_policy", request.retry_policy.as_ref())?;
+    validate_worker_affinity(&request.worker_measurements, &request.worker_signers)?;
+    validate_signature("signature", &request.signature)
+}
+
+fn validate_create_task_v2(request: &proto_v2::CreateTaskRequest) -> ValidationResult {
+    validate_id("function_id", &request.function_id, Function::key_prefix())?;
+    validate_typed_arguments("function_arguments", &request.function_arguments)?;
+    Executor::try_from(request.executor.as_str())
+        .map_err(|_| FieldError::new("executor", "unsupported executor"))?;
+    validate_ownership("inputs_ownership", &request.inputs_ownership)?;
+    validate_ownership("outputs_ownership", &request.outputs_ownership)?;
+    validate_task_expires_in("expires_in", request.expires_in)?;
+    validate_task_expires_in("approval_window", request.approval_window)?;
+    validate_input_dependencies("input_dependencies", &request.input_dependencies)?;
+    validate_priority("priority", &request.priority)?;
+    validate_retry_policy("retry_policy", request.retry_policy.as_ref())?;
+    validate_worker_affinity(&request.worker_measurements, &request.worker_signers)?;
+    validate_signature("signature", &request.signature)
+}
+
+fn validate_create_tasks_batch(request: &proto::CreateTasksBatchRequest) -> ValidationResult {
+    if request.tasks.is_empty() {
+        return Err(FieldError::new("tasks", "must not be empty"));
+    }
+    for (i, task) in request.tasks.iter().enumerate() {
+        validate_create_task(task).map_err(|e| e.within(&format!("tasks[{}]", i)))?;
+    }
+    Ok(())
+}
+
+fn validate_register_task_template(
+    request: &proto::RegisterTaskTemplateRequest,
+) -> ValidationResult {
+    validate_id("function_id", &request.function_id, Function::key_prefix())?;
+    validate_function_arguments("function_arguments", &request.function_arguments)?;
+    Executor::try_from(request.executor.as_str())
+        .map_err(|_| FieldError::new("executor", "unsupported executor"))?;
+    validate_ownership("inputs_ownership", &request.inputs_ownership)?;
+    validate_ownership("outputs_ownership", &request.outputs_ownership)
+}
+
+fn validate_create_task_from_template(
+    request: &proto::CreateTaskFromTemplateRequest,
+) -> ValidationResult {
+    validate_id(
+        "template_id",
+        &request.template_id,
+        TaskTemplate::key_prefix(),
+    )?;
+    validate_function_arguments("function_arguments", &request.function_arguments)
+}
+
+fn validate_estimate_task(request: &proto::EstimateTaskRequest) -> ValidationResult {
+    validate_id("function_id", &request.function_id, Function::key_prefix())?;
+    validate_priority("priority", &request.priority)
+}
+
+fn validate_test_function(request: &proto::TestFunctionRequest) -> ValidationResult {
+    validate_id("function_id", &request.function_id, Function::key_prefix())?;
+    validate_function_arguments("function_arguments", &request.function_arguments)?;
+    Executor::try_from(request.executor.as_str())
+        .map_err(|_| FieldError::new("executor", "unsupported executor"))?;
+    for (fname, sample) in request.sample_inputs.iter() {
+        if sample.len() > MAX_FUNCTION_TEST_FILE_SIZE {
+            return Err(FieldError::new(
+                format!("sample_inputs[{}]", fname),
+                format!(
+                    "must not be larger than {} bytes",
+                    MAX_FUNCTION_TEST_FILE_SIZE
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_register_task_schedule(
+    request: &proto::RegisterTaskScheduleRequest,
+) -> ValidationResult {
+    validate_id(
+        "template_id",
+        &request.template_id,
+        TaskTemplate::key_prefix(),
+    )?;
+    if request.start_in > MAX_SCHEDULE_START_IN {
+        return Err(FieldError::new(
+            "start_in",
+            format!("must not be greater than {}", MAX_SCHEDULE_START_IN),
+        ));
+    }
+    if request.interval < MIN_SCHEDULE_INTERVAL {
+        return Err(FieldError::new(
+            "interval",
+            format!("must not be less than {}", MIN_SCHEDULE_INTERVAL),
+        ));
+    }
+    validate_data_map("inputs", &request.inputs, TeaclaveInputFile::key_prefix())
+}
+
+fn validate_input_dependencies(
+    field: &str,
+    dependencies: &HashMap<String, proto::TaskOutputReference>,
+) -> ValidationResult {
+    for (fname, reference) in dependencies.iter() {
+        validate_id(
+            &format!("{}[{}].task_id", field, fname),
+            &reference.task_id,
+            TaskState::key_prefix(),
+        )?;
+        validate_not_empty(&format!("{}[{}].output", field, fname), &reference.output)?;
+    }
+    Ok(())
+}
+
+// Zero means the task never expires.
+// Tasks are of the normal priority if empty.
+fn validate_priority(field: &str, priority: &str) -> ValidationResult {
+    if priority.is_empty() {
+        return Ok(());
+    }
+    TaskPriority::try_from(priority)
+        .map(|_| ())
+        .map_err(|_| FieldError::new(field, "unsupported priority"))
+}
+
+fn validate_task_expires_in(field: &str, expires_in: u64) -> ValidationResult {
+    if expires_in > MAX_TASK_EXPIRES_IN {
+        return Err(FieldError::new(
+            field,
+            format!("must not be greater than {}", MAX_TASK_EXPIRES_IN),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_retry_policy(field: &str, policy: Option<&proto::RetryPolicy>) -> ValidationResult {
+    match policy {
+        Some(policy) if policy.max_retries > MAX_TASK_RETRIES => Err(FieldError::new(
+            format!("{}.max_retries", field),
+            format!("must not be greater than {}", MAX_TASK_RETRIES),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn validate_reject_task(request: &proto::RejectTaskRequest) -> ValidationResult {
+    validate_id("task_id", &request.task_id, TaskState::key_prefix())?;
+    validate_not_empty("reason", &request.reason)
+}
+
+fn validate_assign_data(request: &proto::AssignDataRequest) -> ValidationResult {
+    validate_id("task_id", &request.task_id, TaskState::key_prefix())?;
+    validate_unique_names(
+        "inputs",
+        "data_name",
+        request.inputs.iter().map(|dm| dm.data_name.as_str()),
+    )?;
+    for (i, data) in request.inputs.iter().enumerate() {
+        let field = format!("inputs[{}].data_id", i);
+        // Inputs can also be assigned by references to registered models.
+        if ModelReference::is_model_reference(&data.data_id) {
+            ModelReference::try_from(data.data_id.as_str())
+                .map_err(|e| FieldError::new(field, e.to_string()))?;
+        } else {
+            validate_id(&field, &data.data_id, TeaclaveInputFile::key_prefix())?;
+        }
+    }
+    validate_data_map(
+        "outputs",
+        &request.outputs,
+        TeaclaveOutputFile::key_prefix(),
+    )?;
+    validate_signature("signature", &request.signature)
+}
+
+fn validate_approve_task(request: &proto::ApproveTaskRequest) -> ValidationResult {
+    validate_id("task_id", &request.task_id, TaskState::key_prefix())?;
+    validate_signature("signature", &request.signature)
+}
+
+fn validate_put_task_keys(request: &proto::PutTaskKeysRequest) -> ValidationResult {
+    validate_id("task_id", &request.task_id, TaskState::key_prefix())?;
+    validate_public_key("offer_public_key", &request.offer_public_key)?;
+    validate_public_key("public_key", &request.public_key)?;
+    if request.wrapped_keys.is_empty() {
+        return Err(FieldError::new("wrapped_keys", "must not be empty"));
+    }
+    validate_unique_names(
+        "wrapped_keys",
+        "data_name",
+        request.wrapped_keys.keys().map(String::as_str),
+    )
+}
+
+fn validate_register_model(request: &proto::RegisterModelRequest) -> ValidationResult {
+    validate_not_empty("name", &request.name)?;
+    if request.name.contains('@') {
+        return Err(FieldError::new("name", "must not contain @"));
+    }
+    validate_id("task_id", &request.task_id, TaskState::key_prefix())?;
+    validate_id(
+        "data_id",
+        &request.data_id,
+        TeaclaveOutputFile::key_prefix(),
+    )
+}
+
+fn validate_create_task_share_link(
+    request: &proto::CreateTaskShareLinkRequest,
+) -> ValidationResult {
+    validate_id("task_id", &request.task_id, TaskState::key_prefix())?;
+    if request.expires_in == 0 || request.expires_in > MAX_SHARE_LINK_EXPIRES_IN {
+        return Err(FieldError::new(
+            "expires_in",
+            format!("must be between 1 and {}", MAX_SHARE_LINK_EXPIRES_IN),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_register_approval_policy(
+    request: &proto::RegisterApprovalPolicyRequest,
+) -> ValidationResult {
+    // Policies cover both the inputs and the outputs of the owner.
+    validate_data_id("data_id", &request.data_id)?;
+    if request.function_ids.is_empty() {
+        return Err(FieldError::new("function_ids", "must not be empty"));
+    }
+    for (i, function_id) in request.function_ids.iter().enumerate() {
+        validate_id(
+            &format!("function_ids[{}]", i),
+            function_id,
+            Function::key_prefix(),
+        )?;
+    }
+    validate_user_list("creators", &request.creators)
+}
+
+fn validate_export_storage_snapshot(
+    request: &proto::ExportStorageSnapshotRequest,
+) -> ValidationResult {
+    validate_snapshot_name("name", &request.name, MAX_EXPORTED_SNAPSHOT_NAME_LENGTH)
+}
+
+fn validate_restore_storage_snapshot(
+    request: &proto::RestoreStorageSnapshotRequest,
+) -> ValidationResult {
+    if request.snapshots.is_empty() {
+        return Err(FieldError::new("snapshots", "must not be empty"));
+    }
+    for (i, snapshot) in request.snapshots.iter().enumerate() {
+        let field = format!("snapshots[{}]", i);
+        validate_snapshot_name("name", &snapshot.name, MAX_SNAPSHOT_NAME_LENGTH)
+            .and_then(|_| validate_cmac("cmac", &snapshot.cmac))
+            .map_err(|e| e.within(&field))?;
+        if snapshot.key.len() != SNAPSHOT_KEY_LENGTH {
+            return Err(FieldError::new(
+                format!("{}.key", field),
+                "invalid key length",
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Names of snapshots are file names at the storage services.
+fn validate_snapshot_name(field: &str, name: &str, max_length: usize) -> ValidationResult {
+    validate_not_empty(field, name)?;
+    if name.len() > max_length {
+        return Err(FieldError::new(
+            field,
+            format!("longer than {} characters", max_length),
+        ));
+    }
+    if !name
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+    {
+        return Err(FieldError::new(
+            field,
+            "expected ASCII letters, digits, '-' and '_' only",
+        ));
+    }
+    Ok(())
+}
+
+fn validate_id(field: &str, id: &str, expected_prefix: &str) -> ValidationResult {
+    let external_id = ExternalID::try_from(id).map_err(|_| {
+        FieldError::new(
+            field,
+            format!("expected an id in the form of {}-<uuid>", expected_prefix),
+        )
+    })?;
+    if external_id.prefix != expected_prefix {
+        return Err(FieldError::new(
+            field,
+            format!(
+                "expected prefix {}, found {}",
+                expected_prefix, external_id.prefix
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// Either an input or an output file
+fn validate_data_id(field: &str, id: &str) -> ValidationResult {
+    if validate_id(field, id, TeaclaveInputFile::key_prefix()).is_err() {
+        validate_id(field, id, TeaclaveOutputFile::key_prefix())?;
+    }
+    Ok(())
+}
+
+fn validate_url(field: &str, url: &str, schemes: &[&str]) -> ValidationResult {
+    let url = Url::parse(url).map_err(|e| FieldError::new(field, e.to_string()))?;
+    if !schemes.contains(&url.scheme()) {
+        return Err(FieldError::new(
+            field,
+            format!(
+                "unsupported scheme {}, expected one of {}",
+                url.scheme(),
+                schemes.join(", ")
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_cmac(field: &str, cmac: &[u8]) -> ValidationResult {
+    FileAuthTag::from_bytes(cmac)
+        .map(|_| ())
+        .map_err(|_| FieldError::new(field, "invalid cmac length"))
+}
+
+// MRENCLAVE is a SHA-256 digest in hex.
+fn validate_measurement(field: &str, measurement: &str) -> ValidationResult {
+    if measurement.len() != 64 || !measurement.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(FieldError::new(field, "expected a hex encoded MRENCLAVE"));
+    }
+    Ok(())
+}
+
+fn validate_worker_affinity(measurements: &[String], signers: &[String]) -> ValidationResult {
+    for (i, measurement) in measurements.iter().enumerate() {
+        validate_measurement(&format!("worker_measurements[{}]", i), measurement)?;
+    }
+    for (i, signer) in signers.iter().enumerate() {
+        let field = format!("worker_signers[{}]", i);
+        if signer.len() != 64 || !signer.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(FieldError::new(field, "expected a hex encoded MRSIGNER"));
+        }
+    }
+    Ok(())
+}
+
+// Ephemeral keys of the exchange are NIST P-256 keys in the uncompressed
+// encoding.
+fn validate_public_key(field: &str, public_key: &[u8]) -> ValidationResult {
+    if public_key.len() != 65 || public_key[0] != 4 {
+        return Err(FieldError::new(field, "expected an uncompressed P-256 key"));
+    }
+    Ok(())
+}
+
+fn validate_signing_key(field: &str, public_key: &[u8]) -> ValidationResult {
+    if public_key.len() != SIGNING_PUBLIC_KEY_LENGTH {
+        return Err(FieldError::new(field, "expected an Ed25519 public key"));
+    }
+    Ok(())
+}
+
+// Requests are unsigned if the signature is empty.
+fn validate_signature(field: &str, signature: &[u8]) -> ValidationResult {
+    if !signature.is_empty() && signature.len() != SIGNATURE_LENGTH {
+        return Err(FieldError::new(field, "expected an Ed25519 signature"));
+    }
+    Ok(())
+}
+
+fn validate_crypto_info(field: &str, crypto_info: &Option<FileCryptoInfo>) -> ValidationResult {
+    let crypto_info = crypto_info
+        .as_ref()
+        .ok_or_else(|| FieldError::new(field, "missing crypto info"))?;
+    FileCrypto::new(&crypto_info.schema, &crypto_info.key, &crypto_info.iv)
+        .map(|_| ())
+        .map_err(|e| FieldError::new(field, e.to_string()))
+}
+
+// The url is validated before the credential.
+fn validate_credential(
+    field: &str,
+    url: &str,
+    credential: &Option<FileCredentialInfo>,
+) -> ValidationResult {
+    // The credential of a chunked input is used for its manifest and chunks.
+    let url = url.trim_start_matches(CHUNKED_URL_SCHEME_PREFIX);
+    let url = Url::parse(url).map_err(|e| FieldError::new("url", e.to_string()))?;
+    match credential {
+        Some(info) => FileCredential::new(
+            &info.schema,
+            &info.access_key_id,
+            &info.secret,
+            &info.region,
+            &info.endpoint,
+        )
+        .and_then(|credential| credential.check_url(&url))
+        .map_err(|e| FieldError::new(field, e.to_string())),
+        None if url.scheme() == "s3" => Err(FieldError::new(field, "missing credential")),
+        None => Ok(()),
+    }
+}
+
+fn validate_function_arguments(field: &str, arguments: &str) -> ValidationResult {
+    // Report the line and column of malformed JSON from serde_json.
+    let value: serde_json::Value =
+        serde_json::from_str(arguments).map_err(|e| FieldError::new(field, e.to_string()))?;
+    let arguments = value
+        .as_object()
+        .ok_or_else(|| FieldError::new(field, "expected a JSON object"))?;
+    for (name, argument) in arguments.iter() {
+        validate_argument_template(&format!("{}.{}", field, name), argument)?;
+    }
+    Ok(())
+}
+
+fn validate_argument_template(field: &str, argument: &serde_json::Value) -> ValidationResult {
+    match ArgumentTemplate::parse(argument) {
+        Some(Err(e)) => Err(FieldError::new(field, e.to_string())),
+        _ => Ok(()),
+    }
+}
+
+fn validate_typed_arguments(
+    field: &str,
+    arguments: &HashMap<String, proto_v2::FunctionArgument>,
+) -> ValidationResult {
+    for (name, argument) in arguments.iter() {
+        let field = format!("{}.{}", field, name);
+        match &argument.value {
+            Some(Value::FloatValue(f)) if !f.is_finite() => {
+                return Err(FieldError::new(field, "expected a finite number"));
+            }
+            Some(Value::StringValue(s)) => {
+                validate_argument_template(&field, &serde_json::Value::String(s.to_owned()))?;
+            }
+            Some(Value::JsonValue(json)) => {
+                serde_json::from_str::<serde_json::Value>(json)
+                    .map_err(|e| FieldError::new(field, e.to_string()))?;
+            }
+            Some(_) => (),
+            None => return Err(FieldError::new(field, "missing value")),
+        }
+    }
+    Ok(())
+}
+
+fn validate_ownership(field: &str, ownership: &[proto::OwnerList]) -> ValidationResult {
+    validate_unique_names(
+        field,
+        "data_name",
+        ownership.iter().map(|ol| ol.data_name.as_str()),
+    )?;
+    for (i, owner_list) in ownership.iter().enumerate() {
+        validate_user_list(&format!("{}[{}].uids", field, i), &owner_list.uids)?;
+    }
+    Ok(())
+}
+
+fn validate_data_map(field: &str, data_map: &[proto::DataMap], prefix: &str) -> ValidationResult {
+    validate_unique_names(
+        field,
+        "data_name",
+        data_map.iter().map(|dm| dm.data_name.as_str()),
+    )?;
+    for (i, data) in data_map.iter().enumerate() {
+        validate_id(&format!("{}[{}].data_id", field, i), &data.data_id, prefix)?;
+    }
+    Ok(())
+}
+
+fn validate_user_list(field: &str, users: &[String]) -> ValidationResult {
+    if users.is_empty() {
+        return Err(FieldError::new(field, "must not be empty"));
+    }
+    for (i, user) in users.iter().enumerate() {
+        validate_not_empty(&format!("{}[{}]", field, i), user)?;
+    }
+    Ok(())
+}
+
+fn validate_unique_names<'a>(
+    field: &str,
+    key: &str,
+    names: impl Iterator<Item = &'a str>,
+) -> ValidationResult {
+    let mut seen = HashSet::new();
+    for (i, name) in names.enumerate() {
+        let name_field = format!("{}[{}].{}", field, i, key);
+        validate_not_empty(&name_field, name)?;
+        if !seen.insert(name) {
+            return Err(FieldError::new(name_field, "duplicated name"));
+        }
+    }
+    Ok(())
+}
+
+fn validate_not_empty(field: &str, value: &str) -> ValidationResult {
+    if value.is_empty() {
+        return Err(FieldError::new(field, "must not be empty"));
+    }
+    Ok(())
+}
@@ -0,0 +1,133 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::ocall::handle_file_request;
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use std::prelude::v1::*;
+use std::untrusted::fs;
+use std::untrusted::path::PathEx;
+use teaclave_config::ArchiveConfig;
+use teaclave_types::*;
+use url::Url;
+use uuid::Uuid;
+
+// Sealed bundles are staged in this directory of the fusion base on their way
+// to and from the archive storage.
+const ARCHIVE_STAGING_DIR: &str = "archive";
+
+/// Object storage of the archived tasks, accessed through the file agent.
+#[derive(Clone, Default)]
+pub(crate) struct TaskArchiveStorage {
+    base_url: Option<Url>,
+    credential: Option<FileCredential>,
+    admins: Vec<UserID>,
+    fusion_base: PathBuf,
+}
+
+impl TaskArchiveStorage {
+    pub(crate) fn new(config: &ArchiveConfig, fusion_base: impl AsRef<Path>) -> Result<Self> {
+        let base_url = match &config.url {
+            Some(url) => Some(Url::parse(url)?),
+            None => None,
+        };
+        let credential = match &config.credential {
+            Some(c) => Some(FileCredential::new(
+                &c.schema,
+                &c.access_key_id,
+                &c.secret,
+                &c.region,
+                &c.endpoint,
+            )?),
+            None => None,
+        };
+        if let (Some(url), Some(credential)) = (&base_url, &credential) {
+            credential.check_url(url)?;
+        }
+
+        Ok(Self {
+            base_url,
+            credential,
+            admins: config
+                .admins
+                .iter()
+                .map(|id| id.to_string().into())
+                .collect(),
+            fusion_base: fusion_base.as_ref().to_owned(),
+        })
+    }
+
+    pub(crate) fn is_admin(&self, user_id: &UserID) -> bool {
+        self.admins.contains(user_id)
+    }
+
+    /// Seals the archive and uploads it, returning the record of the archived
+    /// task to keep in the storage service. Every upload goes to a new object,
+    /// so that a restored task can be archived again.
+    pub(crate) fn upload(&self, archive: &TaskArchive, archived_at: u64) -> Result<ArchivedTask> {
+        let base_url = self
+            .base_url
+            .as_ref()
+            .ok_or_else(|| anyhow!("Archive storage is not configured"))?;
+        let object = format!(
+            "{}-{}.archive",
+            archive.task.external_id(),
+            Uuid::new_v4().to_simple()
+        );
+        let url = base_url.join(&object)?;
+
+        let path = self.staging_path(&object)?;
+        let sealed = archive.seal(&path)?;
+        let info = HandleFileInfo::new(&path, &url).credential(self.credential.clone());
+        let request =
+            FileAgentRequest::new(HandleFileCommand::Upload, vec![info], &self.fusion_base);
+        let result = handle_file_request(request);
+        fs::remove_file(&path)?;
+        result?;
+
+        Ok(ArchivedTask::new(archive, url, &sealed, archived_at))
+    }
+
+    /// Downloads the sealed archive of the task and opens it with the key and
+    /// cmac kept in the record.
+    pub(crate) fn download(&self, archived: &ArchivedTask) -> Result<TaskArchive> {
+        let object = format!("{}.restore", archived.external_id());
+        let path = self.staging_path(&object)?;
+        let info = HandleFileInfo::new(&path, &archived.url).credential(self.credential.clone());
+        let request =
+            FileAgentRequest::new(HandleFileCommand::Download, vec![info], &self.fusion_base);
+        let result = handle_file_request(request).and_then(|_| {
+            let sealed = StagedFileInfo::new(&path, archived.crypto_info, archived.cmac);
+            TaskArchive::unseal(&sealed)
+        });
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        result
+    }
+
+    // A file left by an interrupted request is removed.
+    fn staging_path(&self, name: &str) -> Result<PathBuf> {
+        let dir = self.fusion_base.join(ARCHIVE_STAGING_DIR);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(name);
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        Ok(path)
+    }
+}
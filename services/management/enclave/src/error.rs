@@ -16,7 +16,7 @@
 // under the License.
 
 use std::prelude::v1::*;
-use teaclave_types::TeaclaveServiceResponseError;
+use teaclave_types::{TeaclaveErrorCode, TeaclaveServiceResponseError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -31,10 +31,88 @@ pub(crate) enum TeaclaveManagementServiceError {
     PermissionDenied,
     #[error("bad task")]
     BadTask,
+    #[error("approval receipt error")]
+    ApprovalReceiptError,
+    #[error("task modified concurrently")]
+    TaskConflict,
+    #[error("task manifest not found")]
+    ManifestNotFound,
+    #[error("approval policy not found")]
+    PolicyNotFound,
+    #[error("output file url not set")]
+    OutputUrlNotSet,
+    #[error("model not found")]
+    ModelNotFound,
+    #[error("invalid or expired share link")]
+    ShareLinkInvalid,
+    #[error("data modified concurrently")]
+    DataConflict,
+    #[error("task key offer not found")]
+    KeyOfferNotFound,
+    #[error("task not found")]
+    TaskNotFound,
+    #[error("archived task not found")]
+    ArchiveNotFound,
+    #[error("archive storage error")]
+    ArchiveError,
+    #[error("task upload slots not found")]
+    UploadSlotsNotFound,
+    #[error("task expired")]
+    TaskExpired,
+    #[error("too many participants of the task")]
+    TooManyParticipants,
+    #[error("too many owners of a task file")]
+    TooManyFileOwners,
+    #[error("too many files of the task")]
+    TooManyTaskFiles,
+    #[error("task schedule not found")]
+    ScheduleNotFound,
+    #[error("federation peer not found")]
+    FederationPeerNotFound,
+    #[error("federation peer error")]
+    FederationError,
+    #[error("data assigned to unfinished tasks")]
+    DataInUse,
+    #[error("invalid request signature")]
+    InvalidSignature,
+}
+
+impl TeaclaveManagementServiceError {
+    fn code(&self) -> TeaclaveErrorCode {
+        match self {
+            TeaclaveManagementServiceError::InvalidRequest => TeaclaveErrorCode::Validation,
+            TeaclaveManagementServiceError::DataError => TeaclaveErrorCode::Internal,
+            TeaclaveManagementServiceError::StorageError => TeaclaveErrorCode::Internal,
+            TeaclaveManagementServiceError::PermissionDenied => TeaclaveErrorCode::Auth,
+            TeaclaveManagementServiceError::BadTask => TeaclaveErrorCode::Conflict,
+            TeaclaveManagementServiceError::ApprovalReceiptError => TeaclaveErrorCode::Internal,
+            TeaclaveManagementServiceError::TaskConflict => TeaclaveErrorCode::Conflict,
+            TeaclaveManagementServiceError::ManifestNotFound => TeaclaveErrorCode::NotFound,
+            TeaclaveManagementServiceError::PolicyNotFound => TeaclaveErrorCode::NotFound,
+            TeaclaveManagementServiceError::OutputUrlNotSet => TeaclaveErrorCode::Conflict,
+            TeaclaveManagementServiceError::ModelNotFound => TeaclaveErrorCode::NotFound,
+            TeaclaveManagementServiceError::ShareLinkInvalid => TeaclaveErrorCode::Auth,
+            TeaclaveManagementServiceError::DataConflict => TeaclaveErrorCode::Conflict,
+            TeaclaveManagementServiceError::KeyOfferNotFound => TeaclaveErrorCode::NotFound,
+            TeaclaveManagementServiceError::TaskNotFound => TeaclaveErrorCode::NotFound,
+            TeaclaveManagementServiceError::ArchiveNotFound => TeaclaveErrorCode::NotFound,
+            TeaclaveManagementServiceError::ArchiveError => TeaclaveErrorCode::Internal,
+            TeaclaveManagementServiceError::UploadSlotsNotFound => TeaclaveErrorCode::NotFound,
+            TeaclaveManagementServiceError::TaskExpired => TeaclaveErrorCode::Conflict,
+            TeaclaveManagementServiceError::TooManyParticipants => TeaclaveErrorCode::Validation,
+            TeaclaveManagementServiceError::TooManyFileOwners => TeaclaveErrorCode::Validation,
+            TeaclaveManagementServiceError::TooManyTaskFiles => TeaclaveErrorCode::Validation,
+            TeaclaveManagementServiceError::ScheduleNotFound => TeaclaveErrorCode::NotFound,
+            TeaclaveManagementServiceError::FederationPeerNotFound => TeaclaveErrorCode::NotFound,
+            TeaclaveManagementServiceError::FederationError => TeaclaveErrorCode::Internal,
+            TeaclaveManagementServiceError::DataInUse => TeaclaveErrorCode::Conflict,
+            TeaclaveManagementServiceError::InvalidSignature => TeaclaveErrorCode::Auth,
+        }
+    }
 }
 
 impl From<TeaclaveManagementServiceError> for TeaclaveServiceResponseError {
     fn from(error: TeaclaveManagementServiceError) -> Self {
-        TeaclaveServiceResponseError::RequestError(error.to_string())
+        TeaclaveServiceResponseError::request_error(error.code(), error)
     }
 }
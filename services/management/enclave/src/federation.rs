@@ -0,0 +1,98 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Teaclave deployments of other organizations, to which tasks are delegated
+//! when they run on the data held by the other deployment. The deployments
+//! run the management enclave of the same enclave info, so each accepts the
+//! other by mutual attestation, and the keys of the task files are only ever
+//! exchanged between the two management enclaves.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::prelude::v1::*;
+use teaclave_proto::teaclave_management_service::{
+    DelegateTaskRequest, GetDelegatedTaskRequest, TeaclaveManagementClient,
+};
+use teaclave_rpc::endpoint::Endpoint;
+use teaclave_types::{StagedTask, TaskState};
+use uuid::Uuid;
+
+/// Management services of the federated deployments by their names.
+#[derive(Default)]
+pub(crate) struct Federation {
+    name: String,
+    peers: HashMap<String, Endpoint>,
+    mr_enclave: String,
+}
+
+impl Federation {
+    pub(crate) fn new(
+        name: impl Into<String>,
+        peers: HashMap<String, Endpoint>,
+        mr_enclave: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            peers,
+            mr_enclave: mr_enclave.into(),
+        }
+    }
+
+    pub(crate) fn has_peer(&self, name: &str) -> bool {
+        self.peers.contains_key(name)
+    }
+
+    /// Whether a request of the peer `origin` is sent by a management enclave.
+    /// Frontend enclaves are accepted by the management service as well, but
+    /// never send the requests of the peers.
+    pub(crate) fn accepts(&self, origin: &str, peer_mr_enclave: Option<&str>) -> bool {
+        self.has_peer(origin)
+            && peer_mr_enclave.map_or(false, |mr_enclave| {
+                mr_enclave.eq_ignore_ascii_case(&self.mr_enclave)
+            })
+    }
+
+    /// Has the peer stage the task, which it runs with the keys of the files
+    /// in the staged task.
+    pub(crate) fn delegate(
+        &self,
+        peer: &str,
+        ts: TaskState,
+        staged_task: StagedTask,
+    ) -> Result<()> {
+        let mut client = self.connect(peer)?;
+        client.delegate_task(DelegateTaskRequest::new(&self.name, ts, staged_task))?;
+        Ok(())
+    }
+
+    /// The copy of the task run by the peer.
+    pub(crate) fn get_delegated(&self, peer: &str, task_id: Uuid) -> Result<TaskState> {
+        let mut client = self.connect(peer)?;
+        let response =
+            client.get_delegated_task(GetDelegatedTaskRequest::new(&self.name, task_id))?;
+        Ok(response.task_state)
+    }
+
+    fn connect(&self, peer: &str) -> Result<TeaclaveManagementClient> {
+        let endpoint = self
+            .peers
+            .get(peer)
+            .ok_or_else(|| anyhow!("unknown federation peer {}", peer))?;
+        let channel = endpoint.connect()?;
+        TeaclaveManagementClient::new(channel)
+    }
+}
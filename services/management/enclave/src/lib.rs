@@ -24,6 +24,7 @@ extern crate sgx_tstd as std;
 extern crate log;
 use anyhow::{anyhow, Result};
 
+use std::collections::HashMap;
 use std::prelude::v1::*;
 
 use teaclave_attestation::{verifier, AttestationConfig, RemoteAttestation};
@@ -39,10 +40,17 @@ use teaclave_proto::teaclave_management_service::{
 };
 use teaclave_rpc::config::SgxTrustedTlsServerConfig;
 use teaclave_rpc::server::SgxTrustedTlsServer;
-use teaclave_service_enclave_utils::{create_trusted_storage_endpoint, ServiceEnclave};
+use teaclave_service_enclave_utils::{
+    create_trusted_authentication_endpoint, create_trusted_management_endpoint,
+    create_trusted_storage_endpoint, ServiceEnclave,
+};
 use teaclave_types::{EnclaveInfo, TeeServiceError, TeeServiceResult};
 
+mod archive;
 mod error;
+mod federation;
+mod ocall;
+mod output;
 mod service;
 
 fn start_service(config: &RuntimeConfig) -> Result<()> {
@@ -82,10 +90,88 @@ fn start_service(config: &RuntimeConfig) -> Result<()> {
         &enclave_info,
         AS_ROOT_CA_CERT,
         verifier::universal_quote_verifier,
+        attested_tls_config.clone(),
+    )?;
+
+    let storage_shard_endpoints = config
+        .storage_shards
+        .iter()
+        .map(|shard| {
+            let endpoint = create_trusted_storage_endpoint(
+                &shard.advertised_address,
+                &enclave_info,
+                AS_ROOT_CA_CERT,
+                verifier::universal_quote_verifier,
+                attested_tls_config.clone(),
+            )?;
+            Ok((shard.key_prefixes.clone(), endpoint))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let storage_replica_endpoints = config
+        .storage_replicas
+        .iter()
+        .map(|replica| {
+            let endpoint = create_trusted_storage_endpoint(
+                &replica.advertised_address,
+                &enclave_info,
+                AS_ROOT_CA_CERT,
+                verifier::universal_quote_verifier,
+                attested_tls_config.clone(),
+            )?;
+            Ok((replica.name.clone(), endpoint))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // Peers are verified against the management enclave of the local enclave
+    // info, i.e., they run the same management service.
+    let federation = match &config.federation {
+        Some(federation) => {
+            let mr_enclave = enclave_info
+                .get_enclave_attr("teaclave_management_service")
+                .map(|attr| hex::encode(attr.measurement.mr_enclave))
+                .ok_or_else(|| anyhow!("cannot get enclave attribute of management"))?;
+            let peers = federation
+                .peers
+                .iter()
+                .map(|peer| {
+                    let endpoint = create_trusted_management_endpoint(
+                        &peer.advertised_address,
+                        &enclave_info,
+                        AS_ROOT_CA_CERT,
+                        verifier::universal_quote_verifier,
+                        attested_tls_config.clone(),
+                    )?;
+                    Ok((peer.name.clone(), endpoint))
+                })
+                .collect::<Result<HashMap<_, _>>>()?;
+            federation::Federation::new(&federation.name, peers, mr_enclave)
+        }
+        None => federation::Federation::default(),
+    };
+
+    let authentication_service_endpoint = create_trusted_authentication_endpoint(
+        &config.internal_endpoints.authentication.advertised_address,
+        &enclave_info,
+        AS_ROOT_CA_CERT,
+        verifier::universal_quote_verifier,
         attested_tls_config,
     )?;
 
-    let service = service::TeaclaveManagementService::new(storage_service_endpoint)?;
+    let archive_storage =
+        archive::TaskArchiveStorage::new(&config.archive, &config.mount.fusion_base_dir)?;
+
+    let service = service::TeaclaveManagementService::new(
+        storage_service_endpoint,
+        storage_shard_endpoints,
+        storage_replica_endpoints,
+        authentication_service_endpoint,
+    )?
+    .archive_storage(archive_storage)
+    .output_reader(output::OutputFileReader::new(&config.mount.fusion_base_dir))
+    .limits(config.limits.clone())
+    .federation(federation)
+    .qos(config.qos.clone());
     match server.start(service) {
         Ok(_) => (),
         Err(e) => {
@@ -136,6 +222,7 @@ pub mod tests {
             service::tests::handle_output_file,
             service::tests::handle_function,
             service::tests::handle_task,
+            service::tests::stage_task_with_changed_spec,
             service::tests::handle_staged_task,
         )
     }
@@ -0,0 +1,72 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::ocall::handle_file_request;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::prelude::v1::*;
+use std::untrusted::fs;
+use std::untrusted::path::PathEx;
+use teaclave_types::*;
+use uuid::Uuid;
+
+// Ranges of output files are staged in this directory of the fusion base on
+// their way to the clients.
+const OUTPUT_STAGING_DIR: &str = "outputs";
+
+/// Reads the output files of tasks in ranges through the file agent, so that
+/// a large file is never held in memory as a whole.
+#[derive(Clone, Default)]
+pub(crate) struct OutputFileReader {
+    fusion_base: PathBuf,
+}
+
+impl OutputFileReader {
+    pub(crate) fn new(fusion_base: impl AsRef<Path>) -> Self {
+        Self {
+            fusion_base: fusion_base.as_ref().to_owned(),
+        }
+    }
+
+    /// Reads the range of the file as it is stored, i.e., encrypted. The data
+    /// is shorter than `length` at the end of the file.
+    pub(crate) fn read_range(
+        &self,
+        file: &TeaclaveOutputFile,
+        offset: usize,
+        length: usize,
+    ) -> Result<Vec<u8>> {
+        let dir = self.fusion_base.join(OUTPUT_STAGING_DIR);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!(
+            "{}-{}.range",
+            file.uuid,
+            Uuid::new_v4().to_simple()
+        ));
+        let info = HandleFileInfo::new(&path, &file.url)
+            .credential(file.credential.clone())
+            .range(offset as u64, length as u64);
+        let request =
+            FileAgentRequest::new(HandleFileCommand::Download, vec![info], &self.fusion_base);
+        let result =
+            handle_file_request(request).and_then(|_| fs::read(&path).map_err(anyhow::Error::from));
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        result
+    }
+}
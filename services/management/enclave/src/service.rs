@@ -15,34 +15,77 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use crate::archive::TaskArchiveStorage;
 use crate::error::TeaclaveManagementServiceError;
+use crate::federation::Federation;
+use crate::output::OutputFileReader;
 use anyhow::{anyhow, Result};
-use std::collections::HashMap;
-use std::convert::TryInto;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::{TryFrom, TryInto};
 use std::prelude::v1::*;
 use std::sync::{Arc, SgxMutex as Mutex};
+use teaclave_config::{LimitsConfig, QosConfig};
+use teaclave_proto::teaclave_authentication_service::{
+    SignApprovalReceiptRequest, TeaclaveAuthenticationInternalClient,
+};
 use teaclave_proto::teaclave_frontend_service::{
-    ApproveTaskRequest, ApproveTaskResponse, AssignDataRequest, AssignDataResponse,
-    CreateTaskRequest, CreateTaskResponse, GetFunctionRequest, GetFunctionResponse,
-    GetInputFileRequest, GetInputFileResponse, GetOutputFileRequest, GetOutputFileResponse,
-    GetTaskRequest, GetTaskResponse, InvokeTaskRequest, InvokeTaskResponse,
+    ApproveTaskRequest, ApproveTaskResponse, ApproveTaskScheduleRequest,
+    ApproveTaskScheduleResponse, ArchiveTaskRequest, ArchiveTaskResponse, AssignDataRequest,
+    AssignDataResponse, CancelTaskRequest, CancelTaskResponse, CancelTaskScheduleRequest,
+    CancelTaskScheduleResponse, CreateTaskFromTemplateRequest, CreateTaskFromTemplateResponse,
+    CreateTaskRequest, CreateTaskResponse, CreateTaskShareLinkRequest, CreateTaskShareLinkResponse,
+    CreateTasksBatchRequest, CreateTasksBatchResponse, DeleteInputFileRequest,
+    DeleteInputFileResponse, DeleteOutputFileRequest, DeleteOutputFileResponse,
+    EstimateTaskRequest, EstimateTaskResponse, ExportStorageSnapshotRequest,
+    ExportStorageSnapshotResponse, GetActivityFeedRequest, GetActivityFeedResponse,
+    GetDataLineageRequest, GetDataLineageResponse, GetFunctionRequest, GetFunctionResponse,
+    GetInputFileRequest, GetInputFileResponse, GetModelRequest, GetModelResponse,
+    GetOutputFileRequest, GetOutputFileResponse, GetSharedTaskRequest, GetSharedTaskResponse,
+    GetTaskKeyOfferRequest, GetTaskKeyOfferResponse, GetTaskManifestRequest,
+    GetTaskManifestResponse, GetTaskRequest, GetTaskResponse, GetTaskResultStreamRequest,
+    GetTaskResultStreamResponse, GetTaskReturnValueRequest, GetTaskReturnValueResponse,
+    GetTaskScheduleRequest, GetTaskScheduleResponse, GetTaskUploadSlotsRequest,
+    GetTaskUploadSlotsResponse, InvokeTaskRequest, InvokeTaskResponse, ListFilesRequest,
+    ListFilesResponse, ListFunctionsRequest, ListFunctionsResponse, ListPendingApprovalsRequest,
+    ListPendingApprovalsResponse, ListTasksRequest, ListTasksResponse, PageRequest, PageResponse,
+    PromoteStorageReplicaRequest, PromoteStorageReplicaResponse, PutTaskKeysRequest,
+    PutTaskKeysResponse, RegisterApprovalPolicyRequest, RegisterApprovalPolicyResponse,
     RegisterFunctionRequest, RegisterFunctionResponse, RegisterFusionOutputRequest,
     RegisterFusionOutputResponse, RegisterInputFileRequest, RegisterInputFileResponse,
-    RegisterInputFromOutputRequest, RegisterInputFromOutputResponse, RegisterOutputFileRequest,
-    RegisterOutputFileResponse, UpdateInputFileRequest, UpdateInputFileResponse,
-    UpdateOutputFileRequest, UpdateOutputFileResponse,
+    RegisterInputFromOutputRequest, RegisterInputFromOutputResponse, RegisterModelRequest,
+    RegisterModelResponse, RegisterOutputFileRequest, RegisterOutputFileResponse,
+    RegisterSigningKeyRequest, RegisterSigningKeyResponse, RegisterTaskScheduleRequest,
+    RegisterTaskScheduleResponse, RegisterTaskTemplateRequest, RegisterTaskTemplateResponse,
+    RejectTaskRequest, RejectTaskResponse, RestoreArchivedTaskRequest, RestoreArchivedTaskResponse,
+    RestoreStorageSnapshotRequest, RestoreStorageSnapshotResponse, RevokeApprovalPolicyRequest,
+    RevokeApprovalPolicyResponse, RotateInputFileKeyRequest, RotateInputFileKeyResponse,
+    TestFunctionRequest, TestFunctionResponse, UpdateInputFileRequest, UpdateInputFileResponse,
+    UpdateOutputFileRequest, UpdateOutputFileResponse, UpdateOutputUrlRequest,
+    UpdateOutputUrlResponse,
+};
+use teaclave_proto::teaclave_management_service::{
+    DelegateTaskRequest, DelegateTaskResponse, GetDelegatedTaskRequest, GetDelegatedTaskResponse,
+    TeaclaveManagement,
 };
-use teaclave_proto::teaclave_management_service::TeaclaveManagement;
 use teaclave_proto::teaclave_storage_service::{
-    EnqueueRequest, GetRequest, PutRequest, TeaclaveStorageClient,
+    CompareAndSwapRequest, EnqueueRequest, ExportSnapshotRequest, GetRequest,
+    PromoteReplicaRequest, RestoreSnapshotRequest, ScanIndexRequest, Transaction,
 };
 use teaclave_rpc::endpoint::Endpoint;
 use teaclave_rpc::Request;
 use teaclave_service_enclave_utils::{ensure, teaclave_service};
+use teaclave_storage_client::{
+    connect_router, put_item_in_transaction, TeaclaveStorageRouter, TypedStorage,
+};
 use teaclave_types::*;
-use url::Url;
 use uuid::Uuid;
 
+// Lineages of more tasks are rejected rather than walked.
+const MAX_LINEAGE_STEPS: usize = 1024;
+// Output files are streamed in chunks of this size, each read through the
+// file agent.
+const OUTPUT_CHUNK_SIZE: usize = 1024 * 1024;
+
 #[teaclave_service(
     teaclave_management_service,
     TeaclaveManagement,
@@ -50,7 +93,13 @@ use uuid::Uuid;
 )]
 #[derive(Clone)]
 pub(crate) struct TeaclaveManagementService {
-    storage_client: Arc<Mutex<TeaclaveStorageClient>>,
+    storage_client: Arc<TeaclaveStorageRouter>,
+    authentication_client: Arc<Mutex<TeaclaveAuthenticationInternalClient>>,
+    archive_storage: Arc<TaskArchiveStorage>,
+    output_reader: OutputFileReader,
+    limits: LimitsConfig,
+    federation: Arc<Federation>,
+    qos: Option<QosConfig>,
 }
 
 impl TeaclaveManagement for TeaclaveManagementService {
@@ -61,16 +110,26 @@ impl TeaclaveManagement for TeaclaveManagementService {
     ) -> TeaclaveServiceResponseResult<RegisterInputFileResponse> {
         let user_id = self.get_request_user_id(request.metadata())?;
         let request = request.message;
-        let input_file = TeaclaveInputFile::new(
+        let mut input_file = TeaclaveInputFile::new(
             request.url,
             request.cmac,
             request.crypto_info,
-            vec![user_id],
+            vec![user_id.clone()],
         );
+        if let Some(key_broker) = request.key_broker {
+            input_file = input_file.key_broker(key_broker);
+        }
 
-        self.write_to_db(&input_file)
+        self.storage_client
+            .put_item(&input_file)
             .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
 
+        self.record_activity(
+            &user_id,
+            ActivityKind::DataRegistered,
+            input_file.external_id(),
+        );
+
         let response = RegisterInputFileResponse::new(input_file.external_id());
         Ok(response)
     }
@@ -86,7 +145,8 @@ impl TeaclaveManagement for TeaclaveManagementService {
         let request = request.message;
 
         let old_input_file: TeaclaveInputFile = self
-            .read_from_db(&request.data_id)
+            .storage_client
+            .get_item(&request.data_id)
             .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
 
         ensure!(
@@ -101,13 +161,72 @@ impl TeaclaveManagement for TeaclaveManagementService {
             old_input_file.owner,
         );
 
-        self.write_to_db(&input_file)
+        self.storage_client
+            .put_item(&input_file)
             .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
 
         let response = UpdateInputFileResponse::new(input_file.external_id());
         Ok(response)
     }
 
+    // access control:
+    // 1) exisiting_file.owner_list.len() == 1
+    // 2) user_id in existing_file.owner_list
+    // 3) existing_file.key_broker is none
+    fn rotate_input_file_key(
+        &self,
+        request: Request<RotateInputFileKeyRequest>,
+    ) -> TeaclaveServiceResponseResult<RotateInputFileKeyResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let (old_input_file, stored) = self
+            .read_input_file_from_db(&request.data_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+
+        ensure!(
+            old_input_file.owner == OwnerList::from(vec![user_id.clone()]),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        // Teaclave holds no key of a brokered file to replace.
+        ensure!(
+            old_input_file.key_broker.is_none(),
+            TeaclaveManagementServiceError::InvalidRequest
+        );
+
+        // Tasks keep the file as it was when assigned, so the re-encrypted
+        // file must not overwrite the one unfinished tasks are about to read.
+        ensure!(
+            request.url != old_input_file.url,
+            TeaclaveManagementServiceError::InvalidRequest
+        );
+
+        let in_flight_task_ids = self.read_in_flight_tasks(user_id, &request.data_id)?;
+
+        // The location, cmac and key are replaced at once, and only if the file
+        // is not changed since it was read.
+        let input_file = TeaclaveInputFile {
+            url: request.url,
+            cmac: request.cmac,
+            crypto_info: request.crypto_info,
+            ..old_input_file
+        };
+        let value = input_file
+            .to_vec()
+            .map_err(|_| TeaclaveManagementServiceError::DataError)?;
+        let cas_request = CompareAndSwapRequest::new(input_file.key(), stored, value);
+        self.storage_client
+            .compare_and_swap(cas_request)
+            .map_err(|e| match e.code() {
+                TeaclaveErrorCode::Conflict => TeaclaveManagementServiceError::DataConflict,
+                _ => TeaclaveManagementServiceError::StorageError,
+            })?;
+
+        let response = RotateInputFileKeyResponse::new(request.data_id, in_flight_task_ids);
+        Ok(response)
+    }
+
     // access control: none
     fn register_output_file(
         &self,
@@ -115,11 +234,22 @@ impl TeaclaveManagement for TeaclaveManagementService {
     ) -> TeaclaveServiceResponseResult<RegisterOutputFileResponse> {
         let user_id = self.get_request_user_id(request.metadata())?;
         let request = request.message;
-        let output_file = TeaclaveOutputFile::new(request.url, request.crypto_info, vec![user_id]);
+        let output_file = match request.url {
+            Some(url) => TeaclaveOutputFile::new(url, request.crypto_info, vec![user_id.clone()]),
+            None => TeaclaveOutputFile::new_deferred(request.crypto_info, vec![user_id.clone()]),
+        }
+        .credential(request.credential);
 
-        self.write_to_db(&output_file)
+        self.storage_client
+            .put_item(&output_file)
             .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
 
+        self.record_activity(
+            &user_id,
+            ActivityKind::DataRegistered,
+            output_file.external_id(),
+        );
+
         let response = RegisterOutputFileResponse::new(output_file.external_id());
         Ok(response)
     }
@@ -135,7 +265,8 @@ impl TeaclaveManagement for TeaclaveManagementService {
         let request = request.message;
 
         let old_output_file: TeaclaveOutputFile = self
-            .read_from_db(&request.data_id)
+            .storage_client
+            .get_item(&request.data_id)
             .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
 
         ensure!(
@@ -143,19 +274,56 @@ impl TeaclaveManagement for TeaclaveManagementService {
             TeaclaveManagementServiceError::PermissionDenied
         );
 
+        // The credential is bound to the destination, so it is replaced as well.
         let output_file = TeaclaveOutputFile::new(
             request.url,
             old_output_file.crypto_info,
             old_output_file.owner,
-        );
+        )
+        .credential(request.credential);
 
-        self.write_to_db(&output_file)
+        self.storage_client
+            .put_item(&output_file)
             .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
 
         let response = UpdateOutputFileResponse::new(output_file.external_id());
         Ok(response)
     }
 
+    // access control:
+    // 1) exisiting_file.owner_list.len() == 1
+    // 2) user_id in existing_file.owner_list
+    // the url can only be set once, on an output file registered without it
+    fn update_output_url(
+        &self,
+        request: Request<UpdateOutputUrlRequest>,
+    ) -> TeaclaveServiceResponseResult<UpdateOutputUrlResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let mut output_file: TeaclaveOutputFile = self
+            .storage_client
+            .get_item(&request.data_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+
+        ensure!(
+            output_file.owner == OwnerList::from(vec![user_id]),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        // Unlike UpdateOutputFile, the file is updated in place so that the
+        // tasks it is already assigned to upload to the new url.
+        output_file
+            .set_url(request.url, request.credential)
+            .map_err(|_| TeaclaveManagementServiceError::InvalidRequest)?;
+
+        self.storage_client
+            .put_item(&output_file)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+
+        Ok(UpdateOutputUrlResponse)
+    }
+
     // access control: user_id in owner_list
     fn register_fusion_output(
         &self,
@@ -173,9 +341,16 @@ impl TeaclaveManagement for TeaclaveManagementService {
             .create_fusion_data(owner_list)
             .map_err(|_| TeaclaveManagementServiceError::DataError)?;
 
-        self.write_to_db(&output_file)
+        self.storage_client
+            .put_item(&output_file)
             .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
 
+        self.record_activity(
+            &user_id,
+            ActivityKind::DataRegistered,
+            output_file.external_id(),
+        );
+
         let response = RegisterFusionOutputResponse::new(output_file.external_id());
         Ok(response)
     }
@@ -190,7 +365,8 @@ impl TeaclaveManagement for TeaclaveManagementService {
         let user_id = self.get_request_user_id(request.metadata())?;
 
         let output: TeaclaveOutputFile = self
-            .read_from_db(&request.message.data_id)
+            .storage_client
+            .get_item(&request.message.data_id)
             .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
 
         ensure!(
@@ -201,9 +377,12 @@ impl TeaclaveManagement for TeaclaveManagementService {
         let input = TeaclaveInputFile::from_output(output)
             .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
 
-        self.write_to_db(&input)
+        self.storage_client
+            .put_item(&input)
             .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
 
+        self.record_activity(&user_id, ActivityKind::DataRegistered, input.external_id());
+
         let response = RegisterInputFromOutputResponse::new(input.external_id());
         Ok(response)
     }
@@ -216,7 +395,8 @@ impl TeaclaveManagement for TeaclaveManagementService {
         let user_id = self.get_request_user_id(request.metadata())?;
 
         let output_file: TeaclaveOutputFile = self
-            .read_from_db(&request.message.data_id)
+            .storage_client
+            .get_item(&request.message.data_id)
             .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
 
         ensure!(
@@ -236,7 +416,8 @@ impl TeaclaveManagement for TeaclaveManagementService {
         let user_id = self.get_request_user_id(request.metadata())?;
 
         let input_file: TeaclaveInputFile = self
-            .read_from_db(&request.message.data_id)
+            .storage_client
+            .get_item(&request.message.data_id)
             .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
 
         ensure!(
@@ -248,6 +429,84 @@ impl TeaclaveManagement for TeaclaveManagementService {
         Ok(response)
     }
 
+    // access control:
+    // 1) exisiting_file.owner_list.len() == 1
+    // 2) user_id in existing_file.owner_list
+    // 3) no unfinished task is assigned the file
+    fn delete_input_file(
+        &self,
+        request: Request<DeleteInputFileRequest>,
+    ) -> TeaclaveServiceResponseResult<DeleteInputFileResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let input_file: TeaclaveInputFile = self
+            .storage_client
+            .get_item(&request.data_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+
+        ensure!(
+            input_file.owner == OwnerList::from(vec![user_id.clone()]),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        let in_flight_task_ids = self.read_in_flight_tasks(user_id, &request.data_id)?;
+        ensure!(
+            in_flight_task_ids.is_empty(),
+            TeaclaveManagementServiceError::DataInUse
+        );
+
+        // The record stays until the storage service purges it, indexed only
+        // by the time of deletion.
+        let input_file = TeaclaveInputFile {
+            deleted_at: Some(now_in_secs()),
+            ..input_file
+        };
+        self.storage_client
+            .put_item(&input_file)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+
+        Ok(DeleteInputFileResponse)
+    }
+
+    // access control:
+    // 1) exisiting_file.owner_list.len() == 1
+    // 2) user_id in existing_file.owner_list
+    // 3) no unfinished task is assigned the file
+    fn delete_output_file(
+        &self,
+        request: Request<DeleteOutputFileRequest>,
+    ) -> TeaclaveServiceResponseResult<DeleteOutputFileResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let output_file: TeaclaveOutputFile = self
+            .storage_client
+            .get_item(&request.data_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+
+        ensure!(
+            output_file.owner == OwnerList::from(vec![user_id.clone()]),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        let in_flight_task_ids = self.read_in_flight_tasks(user_id, &request.data_id)?;
+        ensure!(
+            in_flight_task_ids.is_empty(),
+            TeaclaveManagementServiceError::DataInUse
+        );
+
+        let output_file = TeaclaveOutputFile {
+            deleted_at: Some(now_in_secs()),
+            ..output_file
+        };
+        self.storage_client
+            .put_item(&output_file)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+
+        Ok(DeleteOutputFileResponse)
+    }
+
     // access_control: none
     fn register_function(
         &self,
@@ -257,11 +516,18 @@ impl TeaclaveManagement for TeaclaveManagementService {
 
         let function = Function::from(request.message)
             .id(Uuid::new_v4())
-            .owner(user_id);
+            .owner(user_id.clone());
 
-        self.write_to_db(&function)
+        self.storage_client
+            .put_item(&function)
             .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
 
+        self.record_activity(
+            &user_id,
+            ActivityKind::FunctionRegistered,
+            function.external_id(),
+        );
+
         let response = RegisterFunctionResponse::new(function.external_id());
         Ok(response)
     }
@@ -274,7 +540,8 @@ impl TeaclaveManagement for TeaclaveManagementService {
         let user_id = self.get_request_user_id(request.metadata())?;
 
         let function: Function = self
-            .read_from_db(&request.message.function_id)
+            .storage_client
+            .get_item_from_replica(&request.message.function_id)
             .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
 
         ensure!(
@@ -292,6 +559,11 @@ impl TeaclaveManagement for TeaclaveManagementService {
             arguments: function.arguments,
             inputs: function.inputs,
             outputs: function.outputs,
+            resource_profile: function.resource_profile,
+            entry_points: function.entry_points,
+            capabilities: function.capabilities,
+            worker_measurements: function.worker_measurements,
+            post_processing: function.post_processing,
         };
         Ok(response)
     }
@@ -301,35 +573,18 @@ impl TeaclaveManagement for TeaclaveManagementService {
     // 1) arugments match function definition
     // 2) input match function definition
     // 3) output match function definition
+    // 4) entry point is exposed by the function
+    // 5) participants, files and owners of each file are within the limits
+    // a signed request is verified with the signing key of the user, and the
+    // signature is stored along with the task
     fn create_task(
         &self,
         request: Request<CreateTaskRequest>,
     ) -> TeaclaveServiceResponseResult<CreateTaskResponse> {
         let user_id = self.get_request_user_id(request.metadata())?;
+        let task_id = self.create_task_for(user_id, request.message)?;
 
-        let request = request.message;
-
-        let function: Function = self
-            .read_from_db(&request.function_id)
-            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
-
-        let task = Task::<Create>::new(
-            user_id,
-            request.executor,
-            request.function_arguments,
-            request.inputs_ownership,
-            request.outputs_ownership,
-            function,
-        )
-        .map_err(|_| TeaclaveManagementServiceError::BadTask)?;
-
-        log::debug!("CreateTask: {:?}", task);
-
-        let ts: TaskState = task.into();
-        self.write_to_db(&ts)
-            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
-
-        let response = CreateTaskResponse::new(ts.external_id());
+        let response = CreateTaskResponse::new(task_id);
         Ok(response)
     }
 
@@ -341,16 +596,19 @@ impl TeaclaveManagement for TeaclaveManagementService {
         let user_id = self.get_request_user_id(request.metadata())?;
 
         let ts: TaskState = self
-            .read_from_db(&request.message.task_id)
+            .storage_client
+            .get_item(&request.message.task_id)
             .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
 
         ensure!(
             ts.has_participant(&user_id),
             TeaclaveManagementServiceError::PermissionDenied
         );
+        let ts = self.follow_delegated_task(ts);
 
         log::debug!("GetTask: {:?}", ts);
 
+        let spec_hash = ts.spec_hash();
         let response = GetTaskResponse {
             task_id: ts.external_id(),
             creator: ts.creator,
@@ -365,7 +623,126 @@ impl TeaclaveManagement for TeaclaveManagementService {
             assigned_outputs: ts.assigned_outputs.external_ids(),
             result: ts.result,
             status: ts.status,
+            labels: ts.labels,
+            approval_receipts: ts.approval_receipts,
+            entry_point: ts.entry_point,
+            reuse_result: ts.reuse_result,
+            function_capabilities: ts.function_capabilities,
+            key_exchange: ts.key_exchange,
+            worker_measurements: ts.worker_measurements,
+            expires_at: ts.expires_at,
+            rejected_by: ts.rejected_by,
+            rejection_reason: ts.rejection_reason,
+            approval_expires_at: ts.approval_expires_at,
+            input_dependencies: ts.input_dependencies,
+            staging_deferred: ts.staging_deferred,
+            priority: ts.priority,
+            resource_limits: ts.resource_limits,
+            timeout: ts.timeout,
+            retry_policy: ts.retry_policy,
+            retry_count: ts.retry_count,
+            worker_signers: ts.worker_signers,
+            federation_peer: ts.federation_peer,
+            function_post_processing: ts.function_post_processing,
+            spec_hash,
+            request_signatures: ts.request_signatures,
+        };
+        Ok(response)
+    }
+
+    // access control: task.participants.contains(&user_id)
+    // tasks are listed from the index of the user, and only those carrying
+    // all of the requested labels and matching the filter are returned
+    // the index entries and the index of the filtered participant narrow the
+    // tasks before they are read
+    fn list_tasks(
+        &self,
+        request: Request<ListTasksRequest>,
+    ) -> TeaclaveServiceResponseResult<ListTasksResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+        let filter = request.filter;
+
+        let participant_tasks: Option<HashSet<Uuid>> = filter.participant.as_ref().map(|p| {
+            self.storage_client
+                .get_item_from_replica(&UserTasks::external_id_of(p))
+                .map(|tasks: UserTasks| tasks.task_ids.into_iter().collect())
+                .unwrap_or_default()
+        });
+        let indexed = |task_id: &Uuid, entry: Option<&TaskIndexEntry>| {
+            participant_tasks
+                .as_ref()
+                .map_or(true, |tasks| tasks.contains(task_id))
+                && filter.may_match(entry)
+        };
+        let (task_ids, page) = self.list_user_tasks(user_id, &request.page, indexed, |ts| {
+            ts.has_labels(&request.labels) && filter.matches(ts)
+        })?;
+
+        log::debug!("ListTasks: {:?}", task_ids);
+
+        let response = ListTasksResponse::new(task_ids, page);
+        Ok(response)
+    }
+
+    // access control: task.participants.contains(&user_id)
+    // only tasks not yet approved by the user and still accepting approvals
+    // are returned
+    fn list_pending_approvals(
+        &self,
+        request: Request<ListPendingApprovalsRequest>,
+    ) -> TeaclaveServiceResponseResult<ListPendingApprovalsResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let (task_ids, page) = self.list_user_tasks(
+            user_id.clone(),
+            &request.page,
+            |_, _| true,
+            |ts| ts.awaits_approval_from(&user_id),
+        )?;
+
+        log::debug!("ListPendingApprovals: {:?}", task_ids);
+
+        let response = ListPendingApprovalsResponse::new(task_ids, page);
+        Ok(response)
+    }
+
+    // access control: function.owner == user_id
+    fn list_functions(
+        &self,
+        request: Request<ListFunctionsRequest>,
+    ) -> TeaclaveServiceResponseResult<ListFunctionsResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let (function_ids, page) =
+            self.scan_user_index(FUNCTION_OWNER_INDEX, &user_id, &request.page)?;
+
+        log::debug!("ListFunctions: {:?}", function_ids);
+
+        let response = ListFunctionsResponse::new(function_ids, page);
+        Ok(response)
+    }
+
+    // access control: file.owner.contains(&user_id)
+    fn list_files(
+        &self,
+        request: Request<ListFilesRequest>,
+    ) -> TeaclaveServiceResponseResult<ListFilesResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let index = if request.output {
+            OUTPUT_FILE_OWNER_INDEX
+        } else {
+            INPUT_FILE_OWNER_INDEX
         };
+        let (data_ids, page) = self.scan_user_index(index, &user_id, &request.page)?;
+
+        log::debug!("ListFiles: {:?}", data_ids);
+
+        let response = ListFilesResponse::new(data_ids, page);
         Ok(response)
     }
 
@@ -379,6 +756,8 @@ impl TeaclaveManagement for TeaclaveManagementService {
     //    * inputs_ownership or outputs_ownership contains the data name
     //    * input file: OwnerList match input_file.owner
     //    * output file: OwnerList match output_file.owner
+    // a signed request is verified with the signing key of the user, and the
+    // signature is stored along with the task
     fn assign_data(
         &self,
         request: Request<AssignDataRequest>,
@@ -386,24 +765,37 @@ impl TeaclaveManagement for TeaclaveManagementService {
         let user_id = self.get_request_user_id(request.metadata())?;
 
         let request = request.message;
+        let signature = self.verify_request_signature(
+            request.request_signature(&user_id),
+            request.signature.clone(),
+        )?;
 
-        let ts: TaskState = self
-            .read_from_db(&request.task_id)
+        let (ts, version) = self
+            .read_task_from_db(&request.task_id)
             .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
 
         ensure!(
             ts.has_participant(&user_id),
             TeaclaveManagementServiceError::PermissionDenied
         );
+        self.ensure_not_expired(&ts, version)?;
 
         let mut task: Task<Assign> = ts.try_into().map_err(|e| {
             log::warn!("Assign state error: {:?}", e);
             TeaclaveManagementServiceError::PermissionDenied
         })?;
 
-        for (data_name, data_id) in request.inputs.iter() {
+        // Models are assigned by the input files of the referenced versions.
+        let mut inputs = request.inputs;
+        for (data_name, reference) in request.input_models {
+            let model = self.read_model_version(&user_id, &reference.name, reference.version)?;
+            inputs.insert(data_name, model.data_id);
+        }
+
+        for (data_name, data_id) in inputs.iter() {
             let file: TeaclaveInputFile = self
-                .read_from_db(&data_id)
+                .storage_client
+                .get_item(&data_id)
                 .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
             task.assign_input(&user_id, data_name, file)
                 .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
@@ -411,7 +803,8 @@ impl TeaclaveManagement for TeaclaveManagementService {
 
         for (data_name, data_id) in request.outputs.iter() {
             let file: TeaclaveOutputFile = self
-                .read_from_db(&data_id)
+                .storage_client
+                .get_item(&data_id)
                 .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
             task.assign_output(&user_id, data_name, file)
                 .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
@@ -419,9 +812,20 @@ impl TeaclaveManagement for TeaclaveManagementService {
 
         log::debug!("AssignData: {:?}", task);
 
-        let ts: TaskState = task.into();
-        self.write_to_db(&ts)
-            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+        let mut ts: TaskState = task.into();
+        ts.request_signatures.extend(signature);
+        let data_assigned = ts.status == TaskStatus::DataAssigned;
+        if data_assigned {
+            ts = self.approve_by_policies(ts);
+        }
+        self.update_task_in_db(&mut ts, version)?;
+
+        // Workers can prepare the task while waiting for approvals.
+        if data_assigned {
+            if let Err(e) = self.publish_prewarm_task(&ts) {
+                log::warn!("Failed to publish prewarm task: {:?}", e);
+            }
+        }
 
         Ok(AssignDataResponse)
     }
@@ -429,6 +833,10 @@ impl TeaclaveManagement for TeaclaveManagementService {
     // access_control:
     // 1) task status == Ready
     // 2) user_id in task.participants
+    // a receipt of the approved task spec signed by the authentication service
+    // is stored along with the approval and returned to the user
+    // a signed request is verified with the signing key of the user, and the
+    // signature is stored along with the task
     fn approve_task(
         &self,
         request: Request<ApproveTaskRequest>,
@@ -436,9 +844,20 @@ impl TeaclaveManagement for TeaclaveManagementService {
         let user_id = self.get_request_user_id(request.metadata())?;
 
         let request = request.message;
-        let ts: TaskState = self
-            .read_from_db(&request.task_id)
+        let (ts, version) = self
+            .read_task_from_db(&request.task_id)
             .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+        ensure!(
+            ts.has_participant(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        self.ensure_not_expired(&ts, version)?;
+        let spec_hash = ts.spec_hash();
+        let signature = self.verify_request_signature(
+            request.request_signature(&user_id, &spec_hash),
+            request.signature,
+        )?;
+        let receipt = ApprovalReceipt::new(user_id.clone(), ts.task_id, spec_hash);
 
         let mut task: Task<Approve> = ts.try_into().map_err(|e| {
             log::warn!("Approve state error: {:?}", e);
@@ -448,18 +867,28 @@ impl TeaclaveManagement for TeaclaveManagementService {
         task.approve(&user_id)
             .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
 
+        let receipt = self
+            .sign_approval_receipt(receipt)
+            .map_err(|_| TeaclaveManagementServiceError::ApprovalReceiptError)?;
+        task.add_approval_receipt(receipt.clone())
+            .map_err(|_| TeaclaveManagementServiceError::ApprovalReceiptError)?;
+
         log::debug!("ApproveTask: approve:{:?}", task);
 
-        let ts: TaskState = task.into();
-        self.write_to_db(&ts)
-            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+        let mut ts: TaskState = task.into();
+        ts.request_signatures.extend(signature);
+        self.update_task_in_db(&mut ts, version)?;
 
-        Ok(ApproveTaskResponse)
+        self.record_activity(&user_id, ActivityKind::TaskApproved, ts.external_id());
+
+        Ok(ApproveTaskResponse::new(receipt))
     }
 
     // access_control:
     // 1) task status == Approved
     // 2) user_id == task.creator
+    // a task with inputs of upstream tasks which have not finished is staged
+    // by the scheduler once they have
     fn invoke_task(
         &self,
         request: Request<InvokeTaskRequest>,
@@ -467,8 +896,8 @@ impl TeaclaveManagement for TeaclaveManagementService {
         let user_id = self.get_request_user_id(request.metadata())?;
         let request = request.message;
 
-        let ts: TaskState = self
-            .read_from_db(&request.task_id)
+        let (mut ts, version) = self
+            .read_task_from_db(&request.task_id)
             .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
 
         // Early validation
@@ -476,13 +905,45 @@ impl TeaclaveManagement for TeaclaveManagementService {
             ts.has_creator(&user_id),
             TeaclaveManagementServiceError::PermissionDenied
         );
+        ensure!(
+            !ts.staging_deferred,
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        self.ensure_not_expired(&ts, version)?;
 
         let function: Function = self
-            .read_from_db(&ts.function_id)
+            .storage_client
+            .get_item(&ts.function_id)
             .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
 
         log::debug!("InvokeTask: get function: {:?}", function);
 
+        // Inputs of upstream tasks are assigned once the tasks have finished.
+        let dependencies_resolved = ts
+            .resolve_dependencies(|task_id| self.storage_client.get_item(task_id))
+            .map_err(|e| {
+                log::warn!("Resolve dependencies error: {:?}", e);
+                TeaclaveManagementServiceError::BadTask
+            })?;
+
+        // Tasks of the same workflow share the sealed cache of intermediate
+        // artifacts of their creator.
+        let workflow_cache = match &ts.workflow_id {
+            Some(workflow_id) => Some(
+                self.read_or_create_workflow_cache(&ts.creator, workflow_id)
+                    .map_err(|_| TeaclaveManagementServiceError::StorageError)?,
+            ),
+            None => None,
+        };
+
+        // Outputs registered without urls are staged with the urls set since.
+        ts.assigned_outputs
+            .resolve_deferred(|data_id| self.storage_client.get_item(data_id))
+            .map_err(|e| {
+                log::warn!("Resolve outputs error: {:?}", e);
+                TeaclaveManagementServiceError::OutputUrlNotSet
+            })?;
+
         let mut task: Task<Stage> = ts.try_into().map_err(|e| {
             log::warn!("Stage state error: {:?}", e);
             TeaclaveManagementServiceError::PermissionDenied
@@ -490,86 +951,2119 @@ impl TeaclaveManagement for TeaclaveManagementService {
 
         log::debug!("InvokeTask: get task: {:?}", task);
 
-        let staged_task = task.stage_for_running(&user_id, function)?;
+        let mut staged_task = task.stage_for_running(&user_id, function).map_err(|e| {
+            log::warn!("Stage error: {:?}", e);
+            TeaclaveManagementServiceError::BadTask
+        })?;
+        // Argument templates take the results of the tasks they reference,
+        // which have to be finished by now.
+        staged_task
+            .function_arguments
+            .resolve_templates(|task_id| self.storage_client.get_item(task_id))
+            .map_err(|e| {
+                log::warn!("Resolve argument templates error: {:?}", e);
+                TeaclaveManagementServiceError::BadTask
+            })?;
+        if let Some(workflow_cache) = workflow_cache {
+            staged_task = staged_task.workflow_cache(workflow_cache);
+        }
 
         log::debug!("InvokeTask: staged task: {:?}", staged_task);
 
-        self.enqueue_to_db(StagedTask::get_queue_key().as_bytes(), &staged_task)?;
+        if !dependencies_resolved {
+            let mut ts = task.defer();
+            self.update_task_in_db(&mut ts, version)?;
+            let deferred_task = DeferredTask::new(staged_task);
+            self.enqueue_to_db(DeferredTask::get_queue_key().as_bytes(), &deferred_task)?;
+            return Ok(InvokeTaskResponse);
+        }
 
-        let ts: TaskState = task.into();
-        self.write_to_db(&ts)
-            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+        // The task is only staged once even if it is invoked concurrently.
+        let mut ts: TaskState = task.into();
+
+        // A delegated task is staged by the peer, and only marked staged here
+        // once the peer has it, so that a failed delegation can be retried.
+        if let Some(peer) = &ts.federation_peer {
+            self.federation
+                .delegate(peer, ts.clone(), staged_task)
+                .map_err(|e| {
+                    log::warn!("Delegate task error: {:?}", e);
+                    TeaclaveManagementServiceError::FederationError
+                })?;
+            self.update_task_in_db(&mut ts, version)?;
+            return Ok(InvokeTaskResponse);
+        }
+
+        self.update_task_in_db(&mut ts, version)?;
+
+        self.enqueue_to_db(staged_task.queue_key().as_bytes(), &staged_task)?;
 
         Ok(InvokeTaskResponse)
     }
-}
 
-impl TeaclaveManagementService {
-    pub(crate) fn new(storage_service_endpoint: Endpoint) -> Result<Self> {
-        let mut i = 0;
-        let channel = loop {
-            match storage_service_endpoint.connect() {
-                Ok(channel) => break channel,
-                Err(_) => {
-                    anyhow::ensure!(i < 10, "failed to connect to storage service");
-                    log::debug!("Failed to connect to storage service, retry {}", i);
-                    i += 1;
-                }
-            }
-            std::thread::sleep(std::time::Duration::from_secs(3));
-        };
-        let storage_client = Arc::new(Mutex::new(TeaclaveStorageClient::new(channel)?));
-        let service = Self { storage_client };
+    // access control: task.participants.contains(&user_id)
+    // the manifest only exists after the task finished successfully
+    fn get_task_manifest(
+        &self,
+        request: Request<GetTaskManifestRequest>,
+    ) -> TeaclaveServiceResponseResult<GetTaskManifestResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
 
-        #[cfg(test_mode)]
-        service.add_mock_data()?;
+        let ts: TaskState = self
+            .storage_client
+            .get_item(&request.task_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
 
-        Ok(service)
-    }
+        ensure!(
+            ts.has_participant(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
 
-    pub fn create_fusion_data(&self, owners: impl Into<OwnerList>) -> Result<TeaclaveOutputFile> {
-        let uuid = Uuid::new_v4();
-        let url = format!("fusion:///TEACLAVE_FUSION_BASE/{}.fusion", uuid.to_string());
-        let url = Url::parse(&url).map_err(|_| anyhow!("invalid url"))?;
-        let crypto_info = FileCrypto::default();
+        let manifest: TaskManifest = self
+            .storage_client
+            .get_item(&TaskManifest::external_id_of(ts.task_id))
+            .map_err(|_| TeaclaveManagementServiceError::ManifestNotFound)?;
+
+        log::debug!("GetTaskManifest: {:?}", manifest);
 
-        Ok(TeaclaveOutputFile::new(url, crypto_info, owners))
+        let response = GetTaskManifestResponse::new(manifest);
+        Ok(response)
     }
 
-    fn get_request_user_id(
+    // access control: task.participants.contains(&user_id)
+    // only the return value of a successful task can be fetched
+    fn get_task_return_value(
         &self,
-        meta: &HashMap<String, String>,
-    ) -> TeaclaveServiceResponseResult<UserID> {
-        let user_id = meta
-            .get("id")
-            .ok_or_else(|| TeaclaveManagementServiceError::InvalidRequest)?;
-        Ok(user_id.to_string().into())
-    }
+        request: Request<GetTaskReturnValueRequest>,
+    ) -> TeaclaveServiceResponseResult<GetTaskReturnValueResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
 
-    fn write_to_db(&self, item: &impl Storable) -> Result<()> {
-        let k = item.key();
-        let v = item.to_vec()?;
-        let put_request = PutRequest::new(k.as_slice(), v.as_slice());
-        let _put_response = self
+        let ts: TaskState = self
             .storage_client
-            .clone()
-            .lock()
-            .map_err(|_| anyhow!("Cannot lock storage client"))?
-            .put(put_request)?;
-        Ok(())
+            .get_item(&request.task_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+
+        ensure!(
+            ts.has_participant(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        let outputs = match ts.result {
+            TaskResult::Ok(outputs) => outputs,
+            _ => return Err(TeaclaveManagementServiceError::BadTask.into()),
+        };
+        let limit = match request.limit {
+            0 => RETURN_VALUE_CHUNK_SIZE,
+            limit => limit.min(RETURN_VALUE_CHUNK_SIZE),
+        };
+        let (data, total_size) = match outputs.return_value_handle {
+            Some(handle) => {
+                let data = self
+                    .read_return_value(&handle, request.offset, limit)
+                    .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+                (data, handle.size as usize)
+            }
+            None => {
+                let return_value = outputs.return_value;
+                let start = request.offset.min(return_value.len());
+                let end = (start + limit).min(return_value.len());
+                (return_value[start..end].to_vec(), return_value.len())
+            }
+        };
+
+        let end = request.offset + data.len();
+        let next_offset = if end < total_size { Some(end) } else { None };
+        let response = GetTaskReturnValueResponse::new(data, total_size, next_offset);
+        Ok(response)
+    }
+
+    // access control: task.participants.contains(&user_id)
+    // the offer is made by the execution enclave once the task is running
+    fn get_task_key_offer(
+        &self,
+        request: Request<GetTaskKeyOfferRequest>,
+    ) -> TeaclaveServiceResponseResult<GetTaskKeyOfferResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let ts: TaskState = self
+            .storage_client
+            .get_item(&request.task_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+
+        ensure!(
+            ts.has_participant(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        ensure!(ts.key_exchange, TeaclaveManagementServiceError::BadTask);
+
+        let key_exchange: TaskKeyExchange = self
+            .storage_client
+            .get_item(&TaskKeyExchange::external_id_of(&ts.task_id))
+            .map_err(|_| TeaclaveManagementServiceError::KeyOfferNotFound)?;
+
+        let response = GetTaskKeyOfferResponse::new(key_exchange.offer);
+        Ok(response)
+    }
+
+    // access control:
+    // 1) task.participants.contains(&user_id)
+    // 2) user_id in the owner list of every file whose key is wrapped
+    // the keys are only accepted for the current offer of a running task, and
+    // the key of a file cannot be replaced once wrapped
+    fn put_task_keys(
+        &self,
+        request: Request<PutTaskKeysRequest>,
+    ) -> TeaclaveServiceResponseResult<PutTaskKeysResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let ts: TaskState = self
+            .storage_client
+            .get_item(&request.task_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+
+        ensure!(
+            ts.has_participant(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        for fname in request.keys.keys.keys() {
+            let owners = ts
+                .inputs_ownership
+                .get(fname)
+                .or_else(|| ts.outputs_ownership.get(fname));
+            ensure!(
+                owners.map_or(false, |owners| owners.contains(&user_id)),
+                TeaclaveManagementServiceError::PermissionDenied
+            );
+        }
+        ensure!(
+            ts.key_exchange && ts.status == TaskStatus::Running,
+            TeaclaveManagementServiceError::BadTask
+        );
+
+        let (mut key_exchange, stored) = self
+            .read_task_key_exchange_from_db(&ts.task_id)
+            .map_err(|_| TeaclaveManagementServiceError::KeyOfferNotFound)?;
+        ensure!(
+            key_exchange.offer.public_key == request.keys.offer_public_key,
+            TeaclaveManagementServiceError::BadTask
+        );
+        let wrapped_files = key_exchange.wrapped_files();
+        ensure!(
+            request
+                .keys
+                .keys
+                .keys()
+                .all(|fname| !wrapped_files.contains(&fname)),
+            TeaclaveManagementServiceError::InvalidRequest
+        );
+
+        key_exchange.wrapped_keys.push(request.keys);
+        let value = key_exchange
+            .to_vec()
+            .map_err(|_| TeaclaveManagementServiceError::DataError)?;
+        let cas_request = CompareAndSwapRequest::new(key_exchange.key(), stored, value);
+        self.storage_client
+            .compare_and_swap(cas_request)
+            .map_err(|e| match e.code() {
+                TeaclaveErrorCode::Conflict => TeaclaveManagementServiceError::DataConflict,
+                _ => TeaclaveManagementServiceError::StorageError,
+            })?;
+
+        Ok(PutTaskKeysResponse)
+    }
+
+    // access control: data.owner contains user_id
+    // the tasks deriving the data are disclosed to its owners for auditing
+    fn get_data_lineage(
+        &self,
+        request: Request<GetDataLineageRequest>,
+    ) -> TeaclaveServiceResponseResult<GetDataLineageResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let owner = self.read_data_owner(&request.data_id)?;
+        ensure!(
+            owner.contains(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        // Tasks are visited breadth first from the producer of the data, each
+        // task once even if several of the data it produced are used.
+        let mut steps = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending = VecDeque::new();
+        pending.push_back(request.data_id);
+        while let Some(data_id) = pending.pop_front() {
+            let producer = match self.read_data_producer(&data_id) {
+                Some(producer) => producer,
+                None => continue,
+            };
+            if !visited.insert(producer.uuid) {
+                continue;
+            }
+            ensure!(
+                steps.len() < MAX_LINEAGE_STEPS,
+                TeaclaveManagementServiceError::InvalidRequest
+            );
+
+            let ts: TaskState = self
+                .storage_client
+                .get_item(&producer)
+                .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+            let manifest: TaskManifest = self
+                .storage_client
+                .get_item(&TaskManifest::external_id_of(ts.task_id))
+                .map_err(|_| TeaclaveManagementServiceError::ManifestNotFound)?;
+            let step = LineageStep::new(manifest, &ts);
+            pending.extend(step.input_data_ids.values().cloned());
+            steps.push(step);
+        }
+
+        log::debug!("GetDataLineage: {:?}", steps);
+
+        let response = GetDataLineageResponse::new(steps);
+        Ok(response)
+    }
+
+    // access control: data.owner contains user_id
+    fn register_approval_policy(
+        &self,
+        request: Request<RegisterApprovalPolicyRequest>,
+    ) -> TeaclaveServiceResponseResult<RegisterApprovalPolicyResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let owner = self.read_data_owner(&request.data_id)?;
+        ensure!(
+            owner.contains(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        for function_id in request.function_ids.iter() {
+            let _: Function = self
+                .storage_client
+                .get_item(function_id)
+                .map_err(|_| TeaclaveManagementServiceError::InvalidRequest)?;
+        }
+
+        let policy = ApprovalPolicy::new(request.data_id, request.function_ids, request.creators);
+        let policy_id = policy.policy_id;
+        let mut policies = self.read_approval_policies(user_id);
+        policies.add(policy);
+        self.storage_client
+            .put_item(&policies)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+
+        log::debug!("RegisterApprovalPolicy: {:?}", policies);
+
+        let response = RegisterApprovalPolicyResponse::new(policy_id);
+        Ok(response)
+    }
+
+    // access control: only policies of the user can be revoked
+    fn revoke_approval_policy(
+        &self,
+        request: Request<RevokeApprovalPolicyRequest>,
+    ) -> TeaclaveServiceResponseResult<RevokeApprovalPolicyResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+
+        let mut policies = self.read_approval_policies(user_id);
+        ensure!(
+            policies.remove(&request.message.policy_id),
+            TeaclaveManagementServiceError::PolicyNotFound
+        );
+        self.storage_client
+            .put_item(&policies)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+
+        Ok(RevokeApprovalPolicyResponse)
+    }
+
+    // access control:
+    // 1) output_file.owner contains user_id
+    // 2) the output file is assigned to the task, which finished successfully
+    // the output is registered as an input file for tasks using the model
+    fn register_model(
+        &self,
+        request: Request<RegisterModelRequest>,
+    ) -> TeaclaveServiceResponseResult<RegisterModelResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let output: TeaclaveOutputFile = self
+            .storage_client
+            .get_item(&request.data_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+        ensure!(
+            output.owner.contains(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        let ts: TaskState = self
+            .storage_client
+            .get_item(&request.task_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+        ensure!(
+            ts.status == TaskStatus::Finished
+                && ts.result.is_ok()
+                && ts
+                    .assigned_outputs
+                    .external_ids()
+                    .values()
+                    .any(|data_id| data_id == &request.data_id),
+            TeaclaveManagementServiceError::BadTask
+        );
+
+        let input = TeaclaveInputFile::from_output(output)
+            .map_err(|_| TeaclaveManagementServiceError::BadTask)?;
+        let mut model = self
+            .storage_client
+            .get_item(&Model::external_id_of(&user_id, &request.name))
+            .unwrap_or_else(|_| Model::new(user_id, &request.name));
+        let version = model.add_version(
+            input.external_id(),
+            ts.external_id(),
+            ts.function_id,
+            request.metrics,
+        );
+
+        // A version never refers to an input file which is not stored.
+        let mut transaction = Transaction::begin();
+        put_item_in_transaction(&mut transaction, &input)
+            .and_then(|_| put_item_in_transaction(&mut transaction, &model))
+            .map_err(|_| TeaclaveManagementServiceError::DataError)?;
+        self.storage_client
+            .commit(transaction)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+
+        log::debug!("RegisterModel: {:?}", model);
+
+        let response = RegisterModelResponse::new(version);
+        Ok(response)
+    }
+
+    // access control: only models of the user can be got
+    fn get_model(
+        &self,
+        request: Request<GetModelRequest>,
+    ) -> TeaclaveServiceResponseResult<GetModelResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let model = self.read_model_version(&user_id, &request.name, request.version)?;
+
+        let response = GetModelResponse::new(request.name, model);
+        Ok(response)
+    }
+
+    // access control: task.creator == user_id
+    fn create_task_share_link(
+        &self,
+        request: Request<CreateTaskShareLinkRequest>,
+    ) -> TeaclaveServiceResponseResult<CreateTaskShareLinkResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let ts: TaskState = self
+            .storage_client
+            .get_item(&request.task_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+
+        ensure!(
+            ts.has_creator(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        let token = TaskShareLink::generate_token();
+        let expires_at = now_in_secs() + request.expires_in;
+        let link = TaskShareLink::new(&token, ts.external_id(), user_id, expires_at);
+        self.storage_client
+            .put_item(&link)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+
+        let response = CreateTaskShareLinkResponse::new(token, expires_at);
+        Ok(response)
+    }
+
+    // access control: the request carries no user id, anyone holding an
+    // unexpired token can get the progress of the task, but not its
+    // participants, data or result
+    fn get_shared_task(
+        &self,
+        request: Request<GetSharedTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<GetSharedTaskResponse> {
+        let request = request.message;
+
+        let link: TaskShareLink = self
+            .storage_client
+            .get_item(&TaskShareLink::external_id_of(&request.token))
+            .map_err(|_| TeaclaveManagementServiceError::ShareLinkInvalid)?;
+        ensure!(
+            !link.is_expired(now_in_secs()),
+            TeaclaveManagementServiceError::ShareLinkInvalid
+        );
+
+        let ts: TaskState = self
+            .storage_client
+            .get_item(&link.task_id)
+            .map_err(|_| TeaclaveManagementServiceError::ShareLinkInvalid)?;
+
+        let response =
+            GetSharedTaskResponse::new(ts.external_id(), TaskProgress::from(&ts), link.expires_at);
+        Ok(response)
+    }
+
+    // access control: user_id is an admin of the archive
+    fn archive_task(
+        &self,
+        request: Request<ArchiveTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<ArchiveTaskResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        ensure!(
+            self.archive_storage.is_admin(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        let request = request.message;
+
+        let ts: TaskState = self
+            .storage_client
+            .get_item(&request.task_id)
+            .map_err(|_| TeaclaveManagementServiceError::TaskNotFound)?;
+        ensure!(
+            ts.status == TaskStatus::Finished,
+            TeaclaveManagementServiceError::BadTask
+        );
+        let manifest: Option<TaskManifest> = self
+            .storage_client
+            .get_item(&TaskManifest::external_id_of(ts.task_id))
+            .ok();
+
+        let archive = TaskArchive::new(ts, manifest);
+        let archived = self
+            .archive_storage
+            .upload(&archive, now_in_secs())
+            .map_err(|e| {
+                log::error!("Failed to archive task {}: {:?}", request.task_id, e);
+                TeaclaveManagementServiceError::ArchiveError
+            })?;
+
+        // The records are removed in the same transaction as the archive is
+        // recorded, so that the task is either archived or left as it is.
+        let mut transaction = Transaction::begin();
+        put_item_in_transaction(&mut transaction, &archived)
+            .map_err(|_| TeaclaveManagementServiceError::DataError)?;
+        for participant in archive.task.participants.clone() {
+            self.remove_task_from_user_index(&mut transaction, participant, &archive.task.task_id)
+                .map_err(|_| TeaclaveManagementServiceError::DataError)?;
+        }
+        if let Some(manifest) = &archive.manifest {
+            transaction.delete(manifest.key());
+        }
+        transaction.delete(archive.task.key());
+        self.storage_client
+            .commit(transaction)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+
+        log::debug!("ArchiveTask: {:?}", archived);
+
+        let response = ArchiveTaskResponse::new(archived.url);
+        Ok(response)
+    }
+
+    // access control: user_id is an admin of the archive
+    fn restore_archived_task(
+        &self,
+        request: Request<RestoreArchivedTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<RestoreArchivedTaskResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        ensure!(
+            self.archive_storage.is_admin(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        let request = request.message;
+
+        let archived: ArchivedTask = self
+            .storage_client
+            .get_item(&ArchivedTask::external_id_of(&request.task_id.uuid))
+            .map_err(|_| TeaclaveManagementServiceError::ArchiveNotFound)?;
+        let archive = self.archive_storage.download(&archived).map_err(|e| {
+            log::error!("Failed to restore task {}: {:?}", request.task_id, e);
+            TeaclaveManagementServiceError::ArchiveError
+        })?;
+        ensure!(
+            archive.task.task_id == archived.task_id,
+            TeaclaveManagementServiceError::ArchiveError
+        );
+
+        let mut transaction = Transaction::begin();
+        put_item_in_transaction(&mut transaction, &archive.task)
+            .map_err(|_| TeaclaveManagementServiceError::DataError)?;
+        if let Some(manifest) = &archive.manifest {
+            put_item_in_transaction(&mut transaction, manifest)
+                .map_err(|_| TeaclaveManagementServiceError::DataError)?;
+        }
+        for participant in archive.task.participants.clone() {
+            self.add_task_to_user_index(&mut transaction, participant, &archive.task)
+                .map_err(|_| TeaclaveManagementServiceError::DataError)?;
+        }
+        transaction.delete(archived.key());
+        self.storage_client
+            .commit(transaction)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+
+        log::debug!("RestoreArchivedTask: {:?}", archived);
+
+        Ok(RestoreArchivedTaskResponse)
+    }
+
+    // access control: user_id is an admin of the archive
+    fn export_storage_snapshot(
+        &self,
+        request: Request<ExportStorageSnapshotRequest>,
+    ) -> TeaclaveServiceResponseResult<ExportStorageSnapshotResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        ensure!(
+            self.archive_storage.is_admin(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        let request = request.message;
+
+        let mut snapshots = Vec::new();
+        for storage in 0..self.storage_client.storages() {
+            let name = storage_snapshot_name(&request.name, storage);
+            let response = self
+                .storage_client
+                .export_snapshot(storage, ExportSnapshotRequest::new(name.as_str()))?;
+            snapshots.push(StorageSnapshot::new(
+                name,
+                response.crypto_info,
+                response.cmac,
+                response.entries,
+            ));
+        }
+
+        log::info!("ExportStorageSnapshot: {} by {}", request.name, user_id);
+
+        Ok(ExportStorageSnapshotResponse::new(snapshots))
+    }
+
+    // access control: user_id is an admin of the archive
+    //
+    // The storages are restored one by one, so a failed restore may leave some
+    // of them restored, and it is to be retried with the same snapshots.
+    fn restore_storage_snapshot(
+        &self,
+        request: Request<RestoreStorageSnapshotRequest>,
+    ) -> TeaclaveServiceResponseResult<RestoreStorageSnapshotResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        ensure!(
+            self.archive_storage.is_admin(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        let request = request.message;
+        ensure!(
+            request.snapshots.len() == self.storage_client.storages(),
+            TeaclaveManagementServiceError::InvalidRequest
+        );
+
+        for (storage, snapshot) in request.snapshots.into_iter().enumerate() {
+            let name = snapshot.name;
+            let request =
+                RestoreSnapshotRequest::new(name.as_str(), snapshot.crypto_info, snapshot.cmac);
+            self.storage_client
+                .restore_snapshot(storage, request)
+                .map_err(|e| {
+                    log::error!("Failed to restore snapshot {}: {:?}", name, e);
+                    e
+                })?;
+        }
+
+        log::info!("RestoreStorageSnapshot by {}", user_id);
+
+        Ok(RestoreStorageSnapshotResponse)
+    }
+
+    // access control: user_id is an admin of the archive
+    //
+    // The services keep sending requests to the default storage, so they are
+    // to be pointed to the promoted replica afterwards.
+    fn promote_storage_replica(
+        &self,
+        request: Request<PromoteStorageReplicaRequest>,
+    ) -> TeaclaveServiceResponseResult<PromoteStorageReplicaResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        ensure!(
+            self.archive_storage.is_admin(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        let name = request.message.name;
+        ensure!(
+            self.storage_client.has_replica(&name),
+            TeaclaveManagementServiceError::InvalidRequest
+        );
+
+        self.storage_client
+            .promote_replica(&name, PromoteReplicaRequest::new())
+            .map_err(|e| {
+                log::error!("Failed to promote storage replica {}: {:?}", name, e);
+                e
+            })?;
+
+        log::info!("PromoteStorageReplica: {} by {}", name, user_id);
+
+        Ok(PromoteStorageReplicaResponse)
+    }
+
+    // access control:
+    // 1) task.creator == user_id
+    // 2) task status is one of Created, DataAssigned, Approved and Staged
+    fn cancel_task(
+        &self,
+        request: Request<CancelTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<CancelTaskResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let (mut ts, version) = self
+            .read_task_from_db(&request.task_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+        ensure!(
+            ts.has_creator(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        ts.cancel().map_err(|e| {
+            log::warn!("Cancel state error: {:?}", e);
+            TeaclaveManagementServiceError::BadTask
+        })?;
+
+        log::debug!("CancelTask: {:?}", ts);
+
+        self.update_task_in_db(&mut ts, version)?;
+
+        Ok(CancelTaskResponse)
+    }
+
+    // access control:
+    // 1) task.participants.contains(&user_id)
+    // 2) task status is one of Created, DataAssigned and Approved
+    fn reject_task(
+        &self,
+        request: Request<RejectTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<RejectTaskResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let (mut ts, version) = self
+            .read_task_from_db(&request.task_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+        ensure!(
+            ts.has_participant(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        self.ensure_not_expired(&ts, version)?;
+
+        ts.reject(&user_id, &request.reason).map_err(|e| {
+            log::warn!("Reject state error: {:?}", e);
+            TeaclaveManagementServiceError::BadTask
+        })?;
+
+        log::debug!("RejectTask: {:?}", ts);
+
+        self.update_task_in_db(&mut ts, version)?;
+
+        Ok(RejectTaskResponse)
+    }
+
+    // access control: the feed of the user only
+    fn get_activity_feed(
+        &self,
+        request: Request<GetActivityFeedRequest>,
+    ) -> TeaclaveServiceResponseResult<GetActivityFeedResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let activity = self
+            .storage_client
+            .get_item_from_replica(&UserActivity::external_id_of(&user_id))
+            .unwrap_or_else(|_| UserActivity::new(user_id));
+        let events = activity.since(request.since).cloned().collect();
+
+        Ok(GetActivityFeedResponse::new(events))
+    }
+
+    // access control: task.participants.contains(&user_id) for the return
+    // value, and output_file.owner.contains(&user_id) for an output file
+    // the result is read and sent chunk by chunk, and the output files are
+    // sent as they are stored, i.e., encrypted
+    fn get_task_result_stream(
+        &self,
+        request: Request<GetTaskResultStreamRequest>,
+        sink: &mut dyn FnMut(GetTaskResultStreamResponse) -> TeaclaveServiceResponseResult<()>,
+    ) -> TeaclaveServiceResponseResult<()> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let ts: TaskState = self
+            .storage_client
+            .get_item(&request.task_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+
+        ensure!(
+            ts.has_participant(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        let outputs = match &ts.result {
+            TaskResult::Ok(outputs) => outputs,
+            _ => return Err(TeaclaveManagementServiceError::BadTask.into()),
+        };
+
+        let fname = match request.output {
+            Some(fname) => fname,
+            None => {
+                let total_size = match &outputs.return_value_handle {
+                    Some(handle) => handle.size as usize,
+                    None => outputs.return_value.len(),
+                };
+                let mut offset = 0;
+                while offset < total_size {
+                    let data = match &outputs.return_value_handle {
+                        Some(handle) => self
+                            .read_return_value(handle, offset, RETURN_VALUE_CHUNK_SIZE)
+                            .map_err(|_| TeaclaveManagementServiceError::StorageError)?,
+                        None => {
+                            let end = (offset + RETURN_VALUE_CHUNK_SIZE).min(total_size);
+                            outputs.return_value[offset..end].to_vec()
+                        }
+                    };
+                    let length = data.len();
+                    sink(GetTaskResultStreamResponse::new(data, offset))?;
+                    offset += length;
+                }
+                return Ok(());
+            }
+        };
+
+        let file_id = ts
+            .assigned_outputs
+            .external_ids()
+            .remove(&fname)
+            .ok_or(TeaclaveManagementServiceError::InvalidRequest)?;
+        let file: TeaclaveOutputFile = self
+            .storage_client
+            .get_item(&file_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+        ensure!(
+            file.owner.contains(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        let mut offset = 0;
+        loop {
+            let data = self
+                .output_reader
+                .read_range(&file, offset, OUTPUT_CHUNK_SIZE)
+                .map_err(|e| {
+                    log::warn!("GetTaskResultStream: failed to read {}: {:?}", file_id, e);
+                    TeaclaveManagementServiceError::StorageError
+                })?;
+            let length = data.len();
+            if length > 0 {
+                sink(GetTaskResultStreamResponse::new(data, offset))?;
+            }
+            if length < OUTPUT_CHUNK_SIZE {
+                return Ok(());
+            }
+            offset += length;
+        }
+    }
+
+    // access control: task.participants.contains(&user_id)
+    // the slots are opened by the execution enclave once the task is running,
+    // and only the slots of the inputs owned by the user are returned
+    fn get_task_upload_slots(
+        &self,
+        request: Request<GetTaskUploadSlotsRequest>,
+    ) -> TeaclaveServiceResponseResult<GetTaskUploadSlotsResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let ts: TaskState = self
+            .storage_client
+            .get_item(&request.task_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+
+        ensure!(
+            ts.has_participant(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        ensure!(
+            ts.status == TaskStatus::Running,
+            TeaclaveManagementServiceError::BadTask
+        );
+
+        let mut upload_slots: TaskUploadSlots = self
+            .storage_client
+            .get_item(&TaskUploadSlots::external_id_of(&ts.task_id))
+            .map_err(|_| TeaclaveManagementServiceError::UploadSlotsNotFound)?;
+        upload_slots.tokens.retain(|fname, _| {
+            ts.inputs_ownership
+                .get(fname)
+                .map_or(false, |owners| owners.contains(&user_id))
+        });
+
+        let response = GetTaskUploadSlotsResponse::new(upload_slots.endpoint, upload_slots.tokens);
+        Ok(response)
+    }
+
+    // access control: none
+    // the ownership of the files and the defaults of the arguments must match
+    // the function definition
+    fn register_task_template(
+        &self,
+        request: Request<RegisterTaskTemplateRequest>,
+    ) -> TeaclaveServiceResponseResult<RegisterTaskTemplateResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let function: Function = self
+            .storage_client
+            .get_item(&request.function_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+
+        let template = TaskTemplate::new(
+            user_id,
+            request.executor,
+            request.function_arguments,
+            request.inputs_ownership,
+            request.outputs_ownership,
+            &function,
+        )
+        .map_err(|_| TeaclaveManagementServiceError::BadTask)?;
+
+        log::debug!("RegisterTaskTemplate: {:?}", template);
+
+        self.storage_client
+            .put_item(&template)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+
+        let response = RegisterTaskTemplateResponse::new(template.external_id());
+        Ok(response)
+    }
+
+    // access control: template.participants.contains(&user_id)
+    // the task is checked as one created with CreateTask
+    fn create_task_from_template(
+        &self,
+        request: Request<CreateTaskFromTemplateRequest>,
+    ) -> TeaclaveServiceResponseResult<CreateTaskFromTemplateResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let template: TaskTemplate = self
+            .storage_client
+            .get_item(&request.template_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+
+        ensure!(
+            template.has_participant(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        let function_arguments = template.function_arguments_with(request.function_arguments);
+        let task_request = CreateTaskRequest::new()
+            .function_id(template.function_id)
+            .function_arguments(function_arguments)
+            .executor(template.executor)
+            .inputs_ownership(template.inputs_ownership)
+            .outputs_ownership(template.outputs_ownership)
+            .labels(request.labels);
+        let task_id = self.create_task_for(user_id, task_request)?;
+
+        let response = CreateTaskFromTemplateResponse::new(task_id);
+        Ok(response)
+    }
+
+    // access control: none
+    // every task is checked as one created with CreateTask, and no task is
+    // created unless all of them are
+    fn create_tasks_batch(
+        &self,
+        request: Request<CreateTasksBatchRequest>,
+    ) -> TeaclaveServiceResponseResult<CreateTasksBatchResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let mut prepared = Vec::with_capacity(request.tasks.len());
+        for task_request in request.tasks {
+            prepared.push(self.prepare_task(user_id.clone(), task_request)?);
+        }
+
+        if let Err(e) = self.store_tasks(prepared.iter().map(|(ts, _)| ts)) {
+            log::warn!("CreateTasksBatch: failed to store tasks: {:?}", e);
+            return Err(TeaclaveManagementServiceError::StorageError.into());
+        }
+
+        let mut task_ids = Vec::with_capacity(prepared.len());
+        for (ts, function_name) in prepared.iter() {
+            self.publish_task(ts, function_name);
+            task_ids.push(ts.external_id());
+        }
+
+        let response = CreateTasksBatchResponse::new(task_ids);
+        Ok(response)
+    }
+
+    // access control: function.owner == user_id
+    // the test has no participant but the owner, so it is staged right away
+    fn test_function(
+        &self,
+        request: Request<TestFunctionRequest>,
+    ) -> TeaclaveServiceResponseResult<TestFunctionResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let function: Function = self
+            .storage_client
+            .get_item(&request.function_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+        ensure!(
+            function.owner == user_id,
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        let function_test = FunctionTest::new(request.sample_inputs, &function)
+            .map_err(|_| TeaclaveManagementServiceError::BadTask)?;
+        let task = Task::<Create>::new_function_test(
+            user_id.clone(),
+            request.executor,
+            request.function_arguments,
+            function.clone(),
+            function_test,
+        )
+        .map_err(|_| TeaclaveManagementServiceError::BadTask)?
+        .created_at(now_in_secs());
+
+        log::debug!("TestFunction: {:?}", task);
+
+        let ts: TaskState = task.into();
+        let mut task: Task<Stage> = ts.try_into().map_err(|e| {
+            log::warn!("Stage state error: {:?}", e);
+            TeaclaveManagementServiceError::BadTask
+        })?;
+        let staged_task = task.stage_for_running(&user_id, function).map_err(|e| {
+            log::warn!("Stage error: {:?}", e);
+            TeaclaveManagementServiceError::BadTask
+        })?;
+
+        let ts: TaskState = task.into();
+        self.store_task(&ts)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+        self.enqueue_to_db(staged_task.queue_key().as_bytes(), &staged_task)?;
+
+        let response = TestFunctionResponse::new(ts.external_id());
+        Ok(response)
+    }
+
+    // access control:
+    // 1) template.creator == user_id
+    // 2) the template has no schedule yet
+    // the creator approves the schedule by registering it, and the tasks run
+    // once the other participants have approved it as well
+    fn register_task_schedule(
+        &self,
+        request: Request<RegisterTaskScheduleRequest>,
+    ) -> TeaclaveServiceResponseResult<RegisterTaskScheduleResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let template: TaskTemplate = self
+            .storage_client
+            .get_item(&request.template_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+        ensure!(
+            template.creator == user_id,
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        let schedule_id = TaskSchedule::external_id_of(&template.template_id);
+        ensure!(
+            self.storage_client
+                .get_item::<TaskSchedule>(&schedule_id)
+                .is_err(),
+            TeaclaveManagementServiceError::BadTask
+        );
+
+        let function: Function = self
+            .storage_client
+            .get_item(&template.function_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+        let mut inputs = HashMap::new();
+        for (fname, data_id) in request.inputs.iter() {
+            let file: TeaclaveInputFile = self
+                .storage_client
+                .get_item(data_id)
+                .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+            inputs.insert(fname.to_owned(), file);
+        }
+
+        let spec = ScheduleSpec::new(
+            now_in_secs() + request.start_in,
+            request.interval,
+            request.max_runs,
+        )
+        .map_err(|_| TeaclaveManagementServiceError::InvalidRequest)?;
+        let mut schedule = TaskSchedule::new(&template, &function, spec, &inputs)
+            .map_err(|_| TeaclaveManagementServiceError::BadTask)?;
+
+        let receipt = ApprovalReceipt::new(user_id, schedule.template_id, schedule.spec_hash());
+        let receipt = self
+            .sign_approval_receipt(receipt)
+            .map_err(|_| TeaclaveManagementServiceError::ApprovalReceiptError)?;
+        schedule
+            .approve(receipt)
+            .map_err(|_| TeaclaveManagementServiceError::ApprovalReceiptError)?;
+
+        log::debug!("RegisterTaskSchedule: {:?}", schedule);
+
+        self.storage_client
+            .put_item(&schedule)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+
+        // The scheduler creates the task of the first run once it is due,
+        // and queues the next run along with it.
+        let tick = TaskScheduleTick::new(schedule.template_id, 0, schedule.spec.start_at);
+        self.enqueue_to_db(TaskScheduleTick::get_queue_key().as_bytes(), &tick)?;
+
+        Ok(RegisterTaskScheduleResponse)
+    }
+
+    // access control: schedule.participants.contains(&user_id)
+    // the receipt binds the approval to the schedule and its inputs, which
+    // stands for the approvals of the tasks of all runs
+    fn approve_task_schedule(
+        &self,
+        request: Request<ApproveTaskScheduleRequest>,
+    ) -> TeaclaveServiceResponseResult<ApproveTaskScheduleResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let (mut schedule, stored) = self.read_task_schedule_from_db(&request.template_id)?;
+        ensure!(
+            schedule.has_participant(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        ensure!(!schedule.canceled, TeaclaveManagementServiceError::BadTask);
+
+        let receipt = ApprovalReceipt::new(user_id, schedule.template_id, schedule.spec_hash());
+        let receipt = self
+            .sign_approval_receipt(receipt)
+            .map_err(|_| TeaclaveManagementServiceError::ApprovalReceiptError)?;
+        schedule
+            .approve(receipt)
+            .map_err(|_| TeaclaveManagementServiceError::ApprovalReceiptError)?;
+
+        log::debug!("ApproveTaskSchedule: {:?}", schedule);
+
+        self.update_task_schedule_in_db(&schedule, stored)?;
+
+        Ok(ApproveTaskScheduleResponse)
+    }
+
+    // access control: schedule.participants.contains(&user_id)
+    // any participant can withdraw from the schedule, which stops it for good
+    fn cancel_task_schedule(
+        &self,
+        request: Request<CancelTaskScheduleRequest>,
+    ) -> TeaclaveServiceResponseResult<CancelTaskScheduleResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let (mut schedule, stored) = self.read_task_schedule_from_db(&request.template_id)?;
+        ensure!(
+            schedule.has_participant(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        ensure!(!schedule.canceled, TeaclaveManagementServiceError::BadTask);
+
+        schedule.canceled = true;
+
+        log::debug!("CancelTaskSchedule: {:?}", schedule);
+
+        self.update_task_schedule_in_db(&schedule, stored)?;
+
+        Ok(CancelTaskScheduleResponse)
+    }
+
+    // access control: schedule.participants.contains(&user_id)
+    fn get_task_schedule(
+        &self,
+        request: Request<GetTaskScheduleRequest>,
+    ) -> TeaclaveServiceResponseResult<GetTaskScheduleResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let (schedule, _) = self.read_task_schedule_from_db(&request.template_id)?;
+        ensure!(
+            schedule.has_participant(&user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        let response = GetTaskScheduleResponse {
+            template_id: request.template_id,
+            spec: schedule.spec,
+            inputs: schedule.inputs,
+            participants: schedule.participants,
+            approved_users: schedule.approved_users,
+            approval_receipts: schedule.approval_receipts,
+            task_ids: schedule.task_ids,
+            runs: schedule.runs,
+            canceled: schedule.canceled,
+        };
+        Ok(response)
+    }
+
+    // access control: function.public || function.owner == user_id
+    // The run time is estimated from the usage statistics of the function,
+    // and the queue wait from the backlog recorded by the scheduler on its
+    // last sweep.
+    fn estimate_task(
+        &self,
+        request: Request<EstimateTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<EstimateTaskResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let request = request.message;
+
+        let function: Function = self
+            .storage_client
+            .get_item_from_replica(&request.function_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+        ensure!(
+            (function.public || function.owner == user_id),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+
+        let usage = self
+            .storage_client
+            .get_item(&FunctionUsage::external_id_of(&request.function_id.uuid))
+            .unwrap_or_else(|_| FunctionUsage::new(request.function_id.uuid));
+        let backlog: QueueBacklog = self
+            .storage_client
+            .get_item(&QueueBacklog::external_id())
+            .unwrap_or_default();
+
+        let response = EstimateTaskResponse {
+            run_time: usage
+                .estimate_run_time(request.input_size)
+                .unwrap_or_default(),
+            queue_wait: backlog.queue_wait(request.priority),
+            runs: usage.runs,
+            live_workers: backlog.live_workers,
+            backlog_taken_at: backlog.taken_at,
+        };
+        Ok(response)
+    }
+
+    // access control: none
+    // the key replaces the one registered by the user before, and signatures
+    // stored with tasks keep the keys they were verified with
+    fn register_signing_key(
+        &self,
+        request: Request<RegisterSigningKeyRequest>,
+    ) -> TeaclaveServiceResponseResult<RegisterSigningKeyResponse> {
+        let user_id = self.get_request_user_id(request.metadata())?;
+        let signing_key = UserSigningKey::new(user_id, request.message.public_key, now_in_secs())
+            .map_err(|_| TeaclaveManagementServiceError::InvalidRequest)?;
+        self.storage_client
+            .put_item(&signing_key)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+
+        Ok(RegisterSigningKeyResponse)
+    }
+
+    // access control: the management service of a federation peer
+    // the copy of the task has no participants here, and is only read by the
+    // peer delegating it
+    fn delegate_task(
+        &self,
+        request: Request<DelegateTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<DelegateTaskResponse> {
+        ensure!(
+            self.federation
+                .accepts(&request.message.origin, request.peer_mr_enclave()),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        let request = request.message;
+        ensure!(
+            request.task_state.task_id == request.staged_task.task_id
+                && request.task_state.status == TaskStatus::Staged,
+            TeaclaveManagementServiceError::BadTask
+        );
+        // A task is delegated once, even if it is invoked concurrently.
+        ensure!(
+            self.storage_client
+                .get_item::<TaskState>(&request.task_state.external_id())
+                .is_err(),
+            TeaclaveManagementServiceError::TaskConflict
+        );
+
+        let ts = request.task_state.into_delegated(&request.origin);
+        let mut staged_task = request.staged_task;
+        staged_task.creator = ts.creator.clone();
+        self.storage_client
+            .put_item(&ts)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+        self.enqueue_to_db(staged_task.queue_key().as_bytes(), &staged_task)?;
+
+        Ok(DelegateTaskResponse)
+    }
+
+    // access control: the management service of the federation peer which
+    // delegated the task
+    fn get_delegated_task(
+        &self,
+        request: Request<GetDelegatedTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<GetDelegatedTaskResponse> {
+        ensure!(
+            self.federation
+                .accepts(&request.message.origin, request.peer_mr_enclave()),
+            TeaclaveManagementServiceError::PermissionDenied
+        );
+        let request = request.message;
+
+        let task_id = ExternalID::new(TaskState::key_prefix(), request.task_id);
+        let ts: TaskState = self
+            .storage_client
+            .get_item(&task_id)
+            .map_err(|_| TeaclaveManagementServiceError::TaskNotFound)?;
+        ensure!(
+            ts.delegated_from.as_ref() == Some(&request.origin),
+            TeaclaveManagementServiceError::TaskNotFound
+        );
+
+        Ok(GetDelegatedTaskResponse::new(ts))
+    }
+}
+
+impl TeaclaveManagementService {
+    pub(crate) fn new(
+        storage_service_endpoint: Endpoint,
+        storage_shard_endpoints: Vec<(Vec<String>, Endpoint)>,
+        storage_replica_endpoints: Vec<(String, Endpoint)>,
+        authentication_service_endpoint: Endpoint,
+    ) -> Result<Self> {
+        let storage_client = Arc::new(connect_router(
+            &storage_service_endpoint,
+            &storage_shard_endpoints,
+            &storage_replica_endpoints,
+        )?);
+
+        let mut i = 0;
+        let channel = loop {
+            match authentication_service_endpoint.connect() {
+                Ok(channel) => break channel,
+                Err(_) => {
+                    anyhow::ensure!(i < 10, "failed to connect to authentication service");
+                    log::debug!("Failed to connect to authentication service, retry {}", i);
+                    i += 1;
+                }
+            }
+            std::thread::sleep(std::time::Duration::from_secs(3));
+        };
+        let authentication_client = Arc::new(Mutex::new(
+            TeaclaveAuthenticationInternalClient::new(channel)?,
+        ));
+
+        let service = Self {
+            storage_client,
+            authentication_client,
+            archive_storage: Arc::new(TaskArchiveStorage::default()),
+            output_reader: OutputFileReader::default(),
+            limits: LimitsConfig::default(),
+            federation: Arc::new(Federation::default()),
+            qos: None,
+        };
+
+        #[cfg(test_mode)]
+        service.add_mock_data()?;
+
+        Ok(service)
+    }
+
+    pub(crate) fn archive_storage(self, archive_storage: TaskArchiveStorage) -> Self {
+        Self {
+            archive_storage: Arc::new(archive_storage),
+            ..self
+        }
+    }
+
+    pub(crate) fn output_reader(self, output_reader: OutputFileReader) -> Self {
+        Self {
+            output_reader,
+            ..self
+        }
+    }
+
+    pub(crate) fn limits(self, limits: LimitsConfig) -> Self {
+        Self { limits, ..self }
+    }
+
+    pub(crate) fn federation(self, federation: Federation) -> Self {
+        Self {
+            federation: Arc::new(federation),
+            ..self
+        }
+    }
+
+    pub(crate) fn qos(self, qos: Option<QosConfig>) -> Self {
+        Self { qos, ..self }
+    }
+
+    // Creates a task of the request for the user, shared by the tasks created
+    // from templates.
+    fn create_task_for(
+        &self,
+        user_id: UserID,
+        request: CreateTaskRequest,
+    ) -> TeaclaveServiceResponseResult<ExternalID> {
+        let (ts, function_name) = self.prepare_task(user_id, request)?;
+        self.store_task(&ts)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+        self.publish_task(&ts, &function_name);
+
+        Ok(ts.external_id())
+    }
+
+    // Checks the request and builds the task without writing anything, so
+    // that a batch of tasks is checked as a whole before any is stored.
+    fn prepare_task(
+        &self,
+        user_id: UserID,
+        request: CreateTaskRequest,
+    ) -> TeaclaveServiceResponseResult<(TaskState, String)> {
+        let function: Function = self
+            .storage_client
+            .get_item(&request.function_id)
+            .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+        let function_name = function.name.clone();
+        if let Some(entry_point) = &request.entry_point {
+            ensure!(
+                function.entry_points.contains(entry_point),
+                TeaclaveManagementServiceError::BadTask
+            );
+        }
+        self.ensure_input_dependencies(&user_id, &request)?;
+        self.ensure_argument_templates(&user_id, &request)?;
+        self.ensure_federation_peer(&request)?;
+        let priority = self.qos_priority(&user_id, request.priority);
+        let signature = self.verify_request_signature(
+            request.request_signature(&user_id),
+            request.signature.clone(),
+        )?;
+
+        let task = Task::<Create>::new(
+            user_id,
+            request.executor,
+            request.function_arguments,
+            request.inputs_ownership,
+            request.outputs_ownership,
+            function,
+        )
+        .map_err(|_| TeaclaveManagementServiceError::BadTask)?
+        .created_at(now_in_secs())
+        .labels(request.labels)
+        .workflow_id(request.workflow_id)
+        .entry_point(request.entry_point)
+        .reuse_result(request.reuse_result)
+        .key_exchange(request.key_exchange)
+        .priority(priority)
+        .resource_limits(request.resource_limits)
+        .timeout(request.timeout)
+        .retry_policy(request.retry_policy)
+        .expires_at(request.expires_in.map(|secs| now_in_secs() + secs))
+        .approval_expires_at(request.approval_window.map(|secs| now_in_secs() + secs))
+        .input_dependencies(request.input_dependencies)
+        .federation_peer(request.federation_peer)
+        .worker_affinity(request.worker_measurements, request.worker_signers)
+        .map_err(|_| TeaclaveManagementServiceError::BadTask)?;
+
+        log::debug!("CreateTask: {:?}", task);
+
+        let mut ts: TaskState = task.into();
+        ts.request_signatures.extend(signature);
+        self.ensure_task_complexity(&ts)?;
+        Ok((ts, function_name))
+    }
+
+    fn store_task(&self, ts: &TaskState) -> Result<()> {
+        self.store_tasks(std::iter::once(ts))
+    }
+
+    // The tasks are stored along with their entries in the indexes of their
+    // participants in one transaction, so that a failure midway leaves
+    // neither a task missing from the index of a participant nor an entry of
+    // a task which is not stored.
+    fn store_tasks<'a>(&self, tasks: impl Iterator<Item = &'a TaskState>) -> Result<()> {
+        let mut transaction = Transaction::begin();
+        let mut user_indexes: HashMap<UserID, UserTasks> = HashMap::new();
+        for ts in tasks {
+            put_item_in_transaction(&mut transaction, ts)?;
+            for participant in ts.participants.clone() {
+                user_indexes
+                    .entry(participant.clone())
+                    .or_insert_with(|| self.read_user_index(participant))
+                    .add_task(ts);
+            }
+        }
+        for user_tasks in user_indexes.values() {
+            put_item_in_transaction(&mut transaction, user_tasks)?;
+        }
+        self.storage_client.commit(transaction)?;
+        Ok(())
+    }
+
+    // Side effects of a stored task, which never fail the request.
+    fn publish_task(&self, ts: &TaskState, function_name: &str) {
+        // The scheduler expires the task at the end of the approval window
+        // unless everyone has approved it by then.
+        if let Some(expires_at) = ts.approval_expires_at {
+            let window = TaskApprovalWindow::new(ts.task_id, expires_at);
+            let queue_key = TaskApprovalWindow::get_queue_key();
+            if let Err(e) = self.enqueue_to_db(queue_key.as_bytes(), &window) {
+                log::warn!("CreateTask: failed to queue approval window: {:?}", e);
+            }
+        }
+
+        // Notify the other participants to kick off the approval workflow.
+        for notification in TaskNotification::for_participants(ts, function_name) {
+            let queue_key = notification.queue_key();
+            if let Err(e) = self.enqueue_to_db(queue_key.as_bytes(), &notification) {
+                log::warn!("CreateTask: failed to notify {}: {:?}", queue_key, e);
+            }
+        }
+
+        self.record_activity(&ts.creator, ActivityKind::TaskCreated, ts.external_id());
+    }
+
+    // Tasks are only delegated to the configured peers. The workflow cache,
+    // the upstream tasks and the key exchange of a task are local to this
+    // deployment, so a delegated task has none of them.
+    fn ensure_federation_peer(
+        &self,
+        request: &CreateTaskRequest,
+    ) -> TeaclaveServiceResponseResult<()> {
+        if let Some(peer) = &request.federation_peer {
+            ensure!(
+                self.federation.has_peer(peer),
+                TeaclaveManagementServiceError::FederationPeerNotFound
+            );
+            ensure!(
+                request.workflow_id.is_none()
+                    && request.input_dependencies.is_empty()
+                    && !request.key_exchange,
+                TeaclaveManagementServiceError::BadTask
+            );
+        }
+        Ok(())
+    }
+
+    // Inputs can only be outputs of upstream tasks the user participates in,
+    // and are owned by the owners of the outputs, who have to approve the
+    // task as well.
+    fn ensure_input_dependencies(
+        &self,
+        user_id: &UserID,
+        request: &CreateTaskRequest,
+    ) -> TeaclaveServiceResponseResult<()> {
+        for (fname, reference) in request.input_dependencies.iter() {
+            let upstream: TaskState = self
+                .storage_client
+                .get_item(&reference.task_id)
+                .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+            ensure!(
+                upstream.has_participant(user_id),
+                TeaclaveManagementServiceError::PermissionDenied
+            );
+            let output_owners = upstream
+                .outputs_ownership
+                .get(&reference.output)
+                .ok_or(TeaclaveManagementServiceError::BadTask)?;
+            ensure!(
+                request.inputs_ownership.get(fname) == Some(output_owners),
+                TeaclaveManagementServiceError::BadTask
+            );
+        }
+        Ok(())
+    }
+
+    // Arguments can only take values from the results of tasks the user
+    // participates in.
+    fn ensure_argument_templates(
+        &self,
+        user_id: &UserID,
+        request: &CreateTaskRequest,
+    ) -> TeaclaveServiceResponseResult<()> {
+        let templates = request
+            .function_arguments
+            .templates()
+            .map_err(|_| TeaclaveManagementServiceError::BadTask)?;
+        for (_, template) in templates.iter() {
+            let upstream: TaskState = self
+                .storage_client
+                .get_item(&template.task_id)
+                .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+            ensure!(
+                upstream.has_participant(user_id),
+                TeaclaveManagementServiceError::PermissionDenied
+            );
+        }
+        Ok(())
+    }
+
+    // Specifications with many parties or files slow down the approval and
+    // staging of the task, so their sizes are capped.
+    // Tasks of the users of a QoS class are staged at the highest priority of
+    // the class at most, so that batch tenants cannot crowd out interactive
+    // users by asking for high priorities.
+    fn qos_priority(&self, user_id: &UserID, priority: TaskPriority) -> TaskPriority {
+        let max_priority = self
+            .qos
+            .as_ref()
+            .and_then(|qos| qos.class_of(&user_id.to_string()))
+            .and_then(|class| TaskPriority::try_from(class.max_priority.as_str()).ok());
+        match max_priority {
+            Some(max_priority) => priority.min(max_priority),
+            None => priority,
+        }
+    }
+
+    fn ensure_task_complexity(&self, ts: &TaskState) -> TeaclaveServiceResponseResult<()> {
+        ensure!(
+            ts.participants.len() <= self.limits.max_task_participants,
+            TeaclaveManagementServiceError::TooManyParticipants
+        );
+        ensure!(
+            ts.inputs_ownership.len() + ts.outputs_ownership.len() <= self.limits.max_task_files,
+            TeaclaveManagementServiceError::TooManyTaskFiles
+        );
+        ensure!(
+            ts.inputs_ownership
+                .values()
+                .chain(ts.outputs_ownership.values())
+                .all(|owners| owners.len() <= self.limits.max_file_owners),
+            TeaclaveManagementServiceError::TooManyFileOwners
+        );
+        Ok(())
+    }
+
+    pub fn create_fusion_data(&self, owners: impl Into<OwnerList>) -> Result<TeaclaveOutputFile> {
+        Ok(TeaclaveOutputFile::new_fusion(owners))
+    }
+
+    fn get_request_user_id(
+        &self,
+        meta: &HashMap<String, String>,
+    ) -> TeaclaveServiceResponseResult<UserID> {
+        let user_id = meta
+            .get("id")
+            .ok_or_else(|| TeaclaveManagementServiceError::InvalidRequest)?;
+        Ok(user_id.to_string().into())
+    }
+
+    // Reads the range of a return value stored in chunks, which spans two
+    // chunks at most since the range is no larger than a chunk.
+    fn read_return_value(
+        &self,
+        handle: &ReturnValueHandle,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<u8>> {
+        let end = (offset + limit).min(handle.size as usize);
+        let mut data = Vec::new();
+        let mut position = offset;
+        while position < end {
+            let index = position / RETURN_VALUE_CHUNK_SIZE;
+            let chunk_id = ReturnValueChunk::external_id_of(&handle.task_id, index as u32);
+            let chunk: ReturnValueChunk = self.storage_client.get_item(&chunk_id)?;
+            let chunk_offset = index * RETURN_VALUE_CHUNK_SIZE;
+            let start = position - chunk_offset;
+            let stop = (end - chunk_offset).min(chunk.data.len());
+            anyhow::ensure!(start < stop, "Return value chunk is truncated.");
+            data.extend_from_slice(&chunk.data[start..stop]);
+            position = chunk_offset + stop;
+        }
+        Ok(data)
+    }
+
+    // The stored version of the task is returned along with it, which is
+    // expected to be unchanged when the task is written back.
+    fn read_task_from_db(&self, key: &ExternalID) -> Result<(TaskState, u64)> {
+        anyhow::ensure!(
+            TaskState::match_prefix(&key.prefix),
+            "Key prefix doesn't match."
+        );
+
+        let request = GetRequest::new(key.to_bytes());
+        let response = self.storage_client.get(request)?;
+        let ts = TaskState::from_slice(response.value.as_slice())?;
+        Ok((ts, response.version))
+    }
+
+    // Same as read_task_from_db for input files to be updated in place.
+    fn read_input_file_from_db(&self, key: &ExternalID) -> Result<(TeaclaveInputFile, Vec<u8>)> {
+        anyhow::ensure!(
+            TeaclaveInputFile::match_prefix(&key.prefix),
+            "Key prefix doesn't match."
+        );
+
+        let request = GetRequest::new(key.to_bytes());
+        let response = self.storage_client.get(request)?;
+        let file = TeaclaveInputFile::from_slice(response.value.as_slice())?;
+        anyhow::ensure!(!file.is_deleted(), "Record is deleted.");
+        Ok((file, response.value))
     }
 
-    fn read_from_db<T: Storable>(&self, key: &ExternalID) -> Result<T> {
-        anyhow::ensure!(T::match_prefix(&key.prefix), "Key prefix doesn't match.");
+    // Same as read_task_from_db for the key exchange of a task.
+    fn read_task_key_exchange_from_db(&self, task_id: &Uuid) -> Result<(TaskKeyExchange, Vec<u8>)> {
+        let key = TaskKeyExchange::external_id_of(task_id);
+        let request = GetRequest::new(key.to_bytes());
+        let response = self.storage_client.get(request)?;
+        let key_exchange = TaskKeyExchange::from_slice(response.value.as_slice())?;
+        Ok((key_exchange, response.value))
+    }
 
+    // Same as read_task_from_db for the schedule of a template. A template
+    // without a schedule is not told apart from one the user cannot see.
+    fn read_task_schedule_from_db(
+        &self,
+        template_id: &ExternalID,
+    ) -> TeaclaveServiceResponseResult<(TaskSchedule, Vec<u8>)> {
+        ensure!(
+            TaskTemplate::match_prefix(&template_id.prefix),
+            TeaclaveManagementServiceError::InvalidRequest
+        );
+        let key = TaskSchedule::external_id_of(&template_id.uuid);
         let request = GetRequest::new(key.to_bytes());
         let response = self
             .storage_client
+            .get(request)
+            .map_err(|_| TeaclaveManagementServiceError::ScheduleNotFound)?;
+        let schedule = TaskSchedule::from_slice(response.value.as_slice())
+            .map_err(|_| TeaclaveManagementServiceError::DataError)?;
+        Ok((schedule, response.value))
+    }
+
+    // Approvals and cancellations of a schedule, and the runs recorded by the
+    // scheduler, do not clobber each other.
+    fn update_task_schedule_in_db(
+        &self,
+        schedule: &TaskSchedule,
+        stored: Vec<u8>,
+    ) -> TeaclaveServiceResponseResult<()> {
+        let value = schedule
+            .to_vec()
+            .map_err(|_| TeaclaveManagementServiceError::DataError)?;
+        let request = CompareAndSwapRequest::new(schedule.key(), stored, value);
+        self.storage_client
+            .compare_and_swap(request)
+            .map_err(|e| match e.code() {
+                TeaclaveErrorCode::Conflict => TeaclaveManagementServiceError::DataConflict,
+                _ => TeaclaveManagementServiceError::StorageError,
+            })?;
+        Ok(())
+    }
+
+    // Writes the task only if it is still at the version it was read at, so
+    // that concurrent transitions, e.g., two approvals, do not clobber each
+    // other. Clients receive a conflict error and can retry with the latest
+    // task.
+    fn update_task_in_db(
+        &self,
+        ts: &mut TaskState,
+        version: u64,
+    ) -> TeaclaveServiceResponseResult<()> {
+        self.update_task_in_db_with(ts, version, Transaction::begin())
+    }
+
+    // Same as update_task_in_db, along with the writes of the transaction,
+    // e.g., of the files of the task, which are only written with the task.
+    // The task is written first, so that a conflict writes nothing even if
+    // the writes go to other storages.
+    fn update_task_in_db_with(
+        &self,
+        ts: &mut TaskState,
+        version: u64,
+        writes: Transaction,
+    ) -> TeaclaveServiceResponseResult<()> {
+        ts.revision += 1;
+        let value = ts
+            .to_vec()
+            .map_err(|_| TeaclaveManagementServiceError::DataError)?;
+        let mut transaction = Transaction::begin();
+        transaction.put_if_version(ts.key(), value, version);
+        transaction.writes.extend(writes.writes);
+        self.storage_client
+            .commit(transaction)
+            .map_err(|e| match e.code() {
+                TeaclaveErrorCode::Conflict => TeaclaveManagementServiceError::TaskConflict,
+                _ => TeaclaveManagementServiceError::StorageError,
+            })?;
+        Ok(())
+    }
+
+    // A task delegated to a federation peer follows the copy run by the peer
+    // when it is read, until it is finished. It is returned as it is if the
+    // peer cannot be reached.
+    fn follow_delegated_task(&self, ts: TaskState) -> TaskState {
+        let peer = match (&ts.federation_peer, &ts.status) {
+            (Some(peer), TaskStatus::Staged) | (Some(peer), TaskStatus::Running) => peer.clone(),
+            _ => return ts,
+        };
+        match self.read_delegated_task(&peer, &ts.external_id()) {
+            Ok(followed) => followed,
+            Err(e) => {
+                log::warn!("Follow delegated task {} error: {:?}", ts.task_id, e);
+                ts
+            }
+        }
+    }
+
+    fn read_delegated_task(&self, peer: &str, task_id: &ExternalID) -> Result<TaskState> {
+        let delegated = self.federation.get_delegated(peer, task_id.uuid)?;
+        let (mut ts, version) = self.read_task_from_db(task_id)?;
+        if delegated.status == ts.status {
+            return Ok(ts);
+        }
+        let mut transaction = Transaction::begin();
+        for outfile in ts.follow_delegated(delegated)? {
+            put_item_in_transaction(&mut transaction, &outfile)?;
+        }
+        self.update_task_in_db_with(&mut ts, version, transaction)?;
+        Ok(ts)
+    }
+
+    // An expired task is moved to the Expired state once it is touched, even
+    // if the scheduler has not swept it yet.
+    fn ensure_not_expired(
+        &self,
+        ts: &TaskState,
+        version: u64,
+    ) -> TeaclaveServiceResponseResult<()> {
+        if !ts.is_expired(now_in_secs()) {
+            return Ok(());
+        }
+        let mut expired = ts.clone();
+        if expired.expire().is_ok() {
+            if let Err(e) = self.update_task_in_db(&mut expired, version) {
+                log::warn!("Failed to expire task {}: {:?}", ts.task_id, e);
+            }
+        }
+        Err(TeaclaveManagementServiceError::TaskExpired.into())
+    }
+
+    fn sign_approval_receipt(&self, receipt: ApprovalReceipt) -> Result<ApprovalReceipt> {
+        let request = SignApprovalReceiptRequest::new(receipt);
+        let response = self
+            .authentication_client
             .clone()
             .lock()
-            .map_err(|_| anyhow!("Cannot lock storage client"))?
-            .get(request)?;
-        T::from_slice(response.value.as_slice())
+            .map_err(|_| anyhow!("Cannot lock authentication client"))?
+            .sign_approval_receipt(request)?;
+        Ok(response.receipt)
+    }
+
+    // Gets the given version of a model of the user, or the latest one.
+    fn read_model_version(
+        &self,
+        user_id: &UserID,
+        name: &str,
+        version: Option<u32>,
+    ) -> TeaclaveServiceResponseResult<ModelVersion> {
+        let model: Model = self
+            .storage_client
+            .get_item(&Model::external_id_of(user_id, name))
+            .map_err(|_| TeaclaveManagementServiceError::ModelNotFound)?;
+        let model_version = model
+            .get(version)
+            .cloned()
+            .ok_or_else(|| TeaclaveManagementServiceError::ModelNotFound)?;
+        Ok(model_version)
+    }
+
+    // A user without any task has no index in the storage yet.
+    fn read_user_index(&self, user_id: UserID) -> UserTasks {
+        self.storage_client
+            .get_item(&UserTasks::external_id_of(&user_id))
+            .unwrap_or_else(|_| UserTasks::new(user_id))
+    }
+
+    // Lists up to limit tasks of the user matching the filter, starting at
+    // offset in the index of the user. The offset of the next page is returned
+    // if the index is not exhausted. A limit of 0 lists all remaining tasks.
+    // Tasks are only read if their ids and index entries pass the index
+    // filter. Tasks are read from the replicas, so their states may be
+    // slightly stale.
+    // Tokens of the pages of tasks are offsets in the task index of the user.
+    fn list_user_tasks(
+        &self,
+        user_id: UserID,
+        page: &PageRequest,
+        index_filter: impl Fn(&Uuid, Option<&TaskIndexEntry>) -> bool,
+        filter: impl Fn(&TaskState) -> bool,
+    ) -> TeaclaveServiceResponseResult<(Vec<ExternalID>, PageResponse)> {
+        let offset = match &page.token {
+            Some(token) => token
+                .parse()
+                .map_err(|_| TeaclaveManagementServiceError::InvalidRequest)?,
+            None => 0,
+        };
+        let user_tasks = self
+            .storage_client
+            .get_item_from_replica(&UserTasks::external_id_of(&user_id))
+            .unwrap_or_else(|_| UserTasks::new(user_id));
+        let mut task_ids = Vec::new();
+        for (index, task_id) in user_tasks.task_ids.iter().enumerate().skip(offset) {
+            if page.size > 0 && task_ids.len() == page.size {
+                let page = PageResponse::new(Some(index.to_string()));
+                return Ok((task_ids, page));
+            }
+            if !index_filter(task_id, user_tasks.entry(task_id)) {
+                continue;
+            }
+            let task_id = ExternalID::new(TaskState::key_prefix(), *task_id);
+            let ts: TaskState = self
+                .storage_client
+                .get_item_from_replica(&task_id)
+                .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+            if filter(&ts) {
+                task_ids.push(task_id);
+            }
+        }
+
+        Ok((task_ids, PageResponse::default()))
+    }
+
+    // Reads a page of the records indexed under the user, e.g., the functions
+    // the user owns. Tokens of the pages are the hex of the key of the last
+    // record of the previous page.
+    fn scan_user_index(
+        &self,
+        index: &str,
+        user_id: &UserID,
+        page: &PageRequest,
+    ) -> TeaclaveServiceResponseResult<(Vec<ExternalID>, PageResponse)> {
+        let mut request = ScanIndexRequest::new(index, user_id.to_string()).limit(page.size as u32);
+        if let Some(token) = &page.token {
+            let key =
+                hex::decode(token).map_err(|_| TeaclaveManagementServiceError::InvalidRequest)?;
+            request = request.start_after(key);
+        }
+        let response = self
+            .storage_client
+            .scan_index(request)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+        let ids = response
+            .entries
+            .into_iter()
+            .map(|entry| -> Result<ExternalID> { String::from_utf8(entry.key)?.try_into() })
+            .collect::<Result<Vec<ExternalID>>>()
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+        let page = PageResponse::new(response.next_start_after.map(hex::encode));
+        Ok((ids, page))
+    }
+
+    // Unfinished tasks the input or output file is assigned to. Owners
+    // assigning data to a task participate in it, so the index of the owner
+    // covers them all.
+    fn read_in_flight_tasks(
+        &self,
+        user_id: UserID,
+        data_id: &ExternalID,
+    ) -> TeaclaveServiceResponseResult<Vec<ExternalID>> {
+        let mut task_ids = Vec::new();
+        for task_id in self.read_user_index(user_id).task_external_ids() {
+            let ts: TaskState = self
+                .storage_client
+                .get_item(&task_id)
+                .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+            let assigned = ts
+                .assigned_inputs
+                .external_ids()
+                .values()
+                .chain(ts.assigned_outputs.external_ids().values())
+                .any(|id| id == data_id);
+            let done = match ts.status {
+                TaskStatus::Finished
+                | TaskStatus::Canceled
+                | TaskStatus::Expired
+                | TaskStatus::Rejected => true,
+                _ => false,
+            };
+            if assigned && !done {
+                task_ids.push(task_id);
+            }
+        }
+        Ok(task_ids)
+    }
+
+    // The data is either an input or an output file.
+    fn read_data_owner(&self, data_id: &ExternalID) -> TeaclaveServiceResponseResult<OwnerList> {
+        let owner = if TeaclaveInputFile::match_prefix(&data_id.prefix) {
+            let file: TeaclaveInputFile = self
+                .storage_client
+                .get_item(data_id)
+                .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+            file.owner
+        } else {
+            let file: TeaclaveOutputFile = self
+                .storage_client
+                .get_item(data_id)
+                .map_err(|_| TeaclaveManagementServiceError::PermissionDenied)?;
+            file.owner
+        };
+        Ok(owner)
+    }
+
+    // Inputs registered from outputs share the uuid of the outputs, whose
+    // producer is recorded once the task finished. Data registered by users
+    // have no producer.
+    fn read_data_producer(&self, data_id: &ExternalID) -> Option<ExternalID> {
+        let output_id = ExternalID::new(TeaclaveOutputFile::key_prefix(), data_id.uuid);
+        let output: TeaclaveOutputFile = self.storage_client.get_item(&output_id).ok()?;
+        output.producer
+    }
+
+    // A user without any policy has no policies in the storage yet.
+    fn read_approval_policies(&self, user_id: UserID) -> ApprovalPolicies {
+        self.storage_client
+            .get_item(&ApprovalPolicies::external_id_of(&user_id))
+            .unwrap_or_else(|_| ApprovalPolicies::new(user_id))
+    }
+
+    // Verifies the signature on a request with the signing key registered by
+    // the user, and returns the signature to be stored with the task. Requests
+    // are unsigned if the signature is empty.
+    fn verify_request_signature(
+        &self,
+        request_signature: RequestSignature,
+        signature: Vec<u8>,
+    ) -> TeaclaveServiceResponseResult<Option<RequestSignature>> {
+        if signature.is_empty() {
+            return Ok(None);
+        }
+        let signing_key: UserSigningKey = self
+            .storage_client
+            .get_item(&UserSigningKey::external_id_of(&request_signature.user_id))
+            .map_err(|_| TeaclaveManagementServiceError::InvalidSignature)?;
+        let request_signature = request_signature
+            .signature(signature, signing_key.public_key)
+            .timestamp(now_in_secs());
+        request_signature
+            .verify()
+            .map_err(|_| TeaclaveManagementServiceError::InvalidSignature)?;
+        Ok(Some(request_signature))
+    }
+
+    // Participants whose approval policies cover all of their data in the task
+    // approve it as soon as all data are assigned. Approvals failing here are
+    // left to the participants.
+    fn approve_by_policies(&self, mut ts: TaskState) -> TaskState {
+        let pending_users: Vec<UserID> = ts
+            .participants
+            .uids
+            .iter()
+            .filter(|user_id| ts.awaits_approval_from(user_id))
+            .cloned()
+            .collect();
+        for user_id in pending_users {
+            if !self.read_approval_policies(user_id.clone()).approve(&ts) {
+                continue;
+            }
+            match self.approve_on_behalf_of(ts.clone(), &user_id) {
+                Ok(approved) => ts = approved,
+                Err(e) => log::warn!("Failed to approve by policies of {}: {:?}", user_id, e),
+            }
+        }
+        ts
+    }
+
+    fn approve_on_behalf_of(&self, ts: TaskState, user_id: &UserID) -> Result<TaskState> {
+        let receipt = ApprovalReceipt::new(user_id.clone(), ts.task_id, ts.spec_hash());
+        let mut task: Task<Approve> = ts.try_into()?;
+        task.approve(user_id)?;
+        let receipt = self.sign_approval_receipt(receipt)?;
+        task.add_approval_receipt(receipt)?;
+        log::debug!("ApproveByPolicies: {:?}", task);
+        Ok(task.into())
+    }
+
+    fn add_task_to_user_index(
+        &self,
+        transaction: &mut Transaction,
+        user_id: UserID,
+        ts: &TaskState,
+    ) -> Result<()> {
+        let mut user_tasks = self.read_user_index(user_id);
+        user_tasks.add_task(ts);
+        put_item_in_transaction(transaction, &user_tasks)
+    }
+
+    // The feed is best effort, failing to record an event never fails the
+    // request.
+    fn record_activity(&self, user_id: &UserID, kind: ActivityKind, object_id: ExternalID) {
+        let mut activity = self
+            .storage_client
+            .get_item(&UserActivity::external_id_of(user_id))
+            .unwrap_or_else(|_| UserActivity::new(user_id.clone()));
+        activity.record(ActivityEvent::new(kind, object_id, now_in_secs()));
+        if let Err(e) = self.storage_client.put_item(&activity) {
+            log::warn!("Failed to record activity of {}: {:?}", user_id, e);
+        }
+    }
+
+    fn remove_task_from_user_index(
+        &self,
+        transaction: &mut Transaction,
+        user_id: UserID,
+        task_id: &Uuid,
+    ) -> Result<()> {
+        let mut user_tasks = self.read_user_index(user_id);
+        user_tasks.remove_task(task_id);
+        put_item_in_transaction(transaction, &user_tasks)
+    }
+
+    fn read_or_create_workflow_cache(
+        &self,
+        creator: &UserID,
+        workflow_id: &str,
+    ) -> Result<WorkflowCache> {
+        if let Ok(cache) = self
+            .storage_client
+            .get_item(&WorkflowCache::external_id_of(creator, workflow_id))
+        {
+            return Ok(cache);
+        }
+        let cache = WorkflowCache::new(creator.clone(), workflow_id);
+        self.storage_client.put_item(&cache)?;
+        Ok(cache)
+    }
+
+    // Payloads of pinned tasks are not handed out before the task is pulled
+    // by a pinned execution enclave.
+    fn publish_prewarm_task(&self, ts: &TaskState) -> TeaclaveServiceResponseResult<()> {
+        if !ts.worker_measurements.is_empty() || !ts.worker_signers.is_empty() {
+            return Ok(());
+        }
+        let function: Function = self
+            .storage_client
+            .get_item(&ts.function_id)
+            .map_err(|_| TeaclaveManagementServiceError::StorageError)?;
+        let prewarm_task = PrewarmTask::new(ts, &function);
+        self.enqueue_to_db(PrewarmTask::get_queue_key().as_bytes(), &prewarm_task)
     }
 
     fn enqueue_to_db(&self, key: &[u8], item: &impl Storable) -> TeaclaveServiceResponseResult<()> {
@@ -577,12 +3071,7 @@ impl TeaclaveManagementService {
             .to_vec()
             .map_err(|_| TeaclaveManagementServiceError::DataError)?;
         let enqueue_request = EnqueueRequest::new(key, value);
-        let _enqueue_response = self
-            .storage_client
-            .clone()
-            .lock()
-            .map_err(|_| TeaclaveManagementServiceError::StorageError)?
-            .enqueue(enqueue_request)?;
+        let _enqueue_response = self.storage_client.enqueue(enqueue_request)?;
         Ok(())
     }
 
@@ -591,16 +3080,16 @@ impl TeaclaveManagementService {
         let mut output_file = self.create_fusion_data(vec!["mock_user1", "frontend_user"])?;
         output_file.uuid = Uuid::parse_str("00000000-0000-0000-0000-000000000001")?;
         output_file.cmac = Some(FileAuthTag::mock());
-        self.write_to_db(&output_file)?;
+        self.storage_client.put_item(&output_file)?;
 
         let mut output_file = self.create_fusion_data(vec!["mock_user2", "mock_user3"])?;
         output_file.uuid = Uuid::parse_str("00000000-0000-0000-0000-000000000002")?;
         output_file.cmac = Some(FileAuthTag::mock());
-        self.write_to_db(&output_file)?;
+        self.storage_client.put_item(&output_file)?;
 
         let mut input_file = TeaclaveInputFile::from_output(output_file)?;
         input_file.uuid = Uuid::parse_str("00000000-0000-0000-0000-000000000002")?;
-        self.write_to_db(&input_file)?;
+        self.storage_client.put_item(&input_file)?;
 
         let function_input = FunctionInput::new("input", "input_desc");
         let function_output = FunctionOutput::new("output", "output_desc");
@@ -618,7 +3107,7 @@ impl TeaclaveManagementService {
             .outputs(vec![function_output, function_output2])
             .owner("teaclave".to_string());
 
-        self.write_to_db(&function)?;
+        self.storage_client.put_item(&function)?;
 
         let function_output = FunctionOutput::new("output", "output_desc");
         let function = Function::new()
@@ -631,11 +3120,24 @@ impl TeaclaveManagementService {
             .outputs(vec![function_output])
             .owner("teaclave".to_string());
 
-        self.write_to_db(&function)?;
+        self.storage_client.put_item(&function)?;
         Ok(())
     }
 }
 
+fn now_in_secs() -> u64 {
+    TrustedTime::now_secs()
+}
+
+// The snapshots of the shards are named after the shards, see `storages` of
+// the storage router.
+fn storage_snapshot_name(name: &str, storage: usize) -> String {
+    match storage {
+        0 => name.to_string(),
+        shard => format!("{}-shard-{}", name, shard),
+    }
+}
+
 #[cfg(feature = "enclave_unit_test")]
 pub mod tests {
     use super::*;
@@ -713,6 +3215,46 @@ pub mod tests {
         debug!("task: {:?}", deserialized_task);
     }
 
+    pub fn stage_task_with_changed_spec() {
+        let function_id = Uuid::new_v4();
+        let function = |payload: &[u8]| {
+            Function::new()
+                .id(function_id)
+                .name("mock_function")
+                .payload(payload.to_vec())
+                .public(true)
+                .owner("mock_user")
+        };
+
+        let task = Task::<Create>::new(
+            UserID::from("mock_user"),
+            Executor::MesaPy,
+            FunctionArguments::default(),
+            HashMap::new(),
+            HashMap::new(),
+            function(b"python script"),
+        )
+        .unwrap();
+        let ts: TaskState = task.into();
+        assert!(!ts.creation_spec_hash.is_empty());
+
+        let mut task: Task<Stage> = ts.clone().try_into().unwrap();
+        let user_id = UserID::from("mock_user");
+        assert!(task
+            .stage_for_running(&user_id, function(b"python script"))
+            .is_ok());
+        assert!(task
+            .stage_for_running(&user_id, function(b"modified script"))
+            .is_err());
+
+        let mut ts = ts;
+        ts.function_arguments = FunctionArguments::from_json(json!({"arg": "data"})).unwrap();
+        let mut task: Task<Stage> = ts.try_into().unwrap();
+        assert!(task
+            .stage_for_running(&user_id, function(b"python script"))
+            .is_err());
+    }
+
     pub fn handle_staged_task() {
         let function = Function::new()
             .id(Uuid::new_v4())
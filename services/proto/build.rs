@@ -28,8 +28,11 @@ fn main() {
         "services/proto/src/proto/teaclave_common.proto",
         "services/proto/src/proto/teaclave_storage_service.proto",
         "services/proto/src/proto/teaclave_frontend_service.proto",
+        "services/proto/src/proto/teaclave_frontend_service_v2.proto",
         "services/proto/src/proto/teaclave_management_service.proto",
         "services/proto/src/proto/teaclave_scheduler_service.proto",
+        "services/proto/src/proto/teaclave_policy_decision_service.proto",
+        "services/proto/src/proto/teaclave_key_broker_service.proto",
     ];
 
     let out_dir = env::var("OUT_DIR").expect("$OUT_DIR not set. Please build with cargo");
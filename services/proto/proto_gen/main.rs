@@ -37,11 +37,14 @@ struct Method {
     impl_input_type: String,
     output_type: String,
     impl_output_type: String,
+    server_streaming: bool,
 }
 
 struct Service {
     proto_name: String,
+    api_version: String,
     methods: Vec<Method>,
+    has_streaming: bool,
 }
 
 impl Service {
@@ -55,6 +58,14 @@ impl Service {
         }
         let mut methods = vec![];
         let package_name = prost_service.package.trim_end_matches("_proto");
+        // Later versions of an API are in packages suffixed with the versions,
+        // e.g., teaclave_frontend_service_v2_proto.
+        let api_version = package_name
+            .rsplit('_')
+            .next()
+            .filter(|suffix| suffix.starts_with('v') && suffix[1..].parse::<u32>().is_ok())
+            .unwrap_or_default()
+            .to_string();
         for m in prost_service.methods.iter() {
             let impl_input_type = convert_to_impl_type(&package_name, &m.input_type);
             let impl_output_type = convert_to_impl_type(&package_name, &m.output_type);
@@ -66,12 +77,16 @@ impl Service {
                 impl_input_type,
                 output_type: m.output_type.clone(),
                 impl_output_type,
+                server_streaming: m.server_streaming,
             };
             methods.push(method);
         }
+        let has_streaming = methods.iter().any(|m| m.server_streaming);
         Self {
             proto_name: prost_service.proto_name.clone(),
+            api_version,
             methods,
+            has_streaming,
         }
     }
 }
@@ -94,6 +109,248 @@ pub fn get_default_config() -> prost_build::Config {
     let mut config = prost_build::Config::new();
     config.service_generator(Box::new(MesaTEEServiceGenerator));
     config.type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]");
+    // Optional fields for clients sending serialized requests.
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.labels",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.workflow_id",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.ListTasksRequest.labels",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.ListTasksRequest.page",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.ListTasksRequest.statuses",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.ListTasksRequest.function_id",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.ListTasksRequest.participant",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.ListTasksRequest.created_after",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.ListTasksRequest.created_before",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.ListPendingApprovalsRequest.page",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.ListFunctionsRequest.page",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.ListFilesRequest.output",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.ListFilesRequest.page",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_common_proto.PageRequest.page_token",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_common_proto.PageRequest.page_size",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.RegisterFunctionRequest.resource_profile",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.RegisterFunctionRequest.entry_points",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.RegisterFunctionRequest.capabilities",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.RegisterFunctionRequest.worker_measurements",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.RegisterFunctionRequest.post_processing",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.PostProcessingStep.decimals",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.PostProcessingStep.k",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.PostProcessingStep.schema",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.entry_point",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.reuse_result",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.key_exchange",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.expires_in",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.approval_window",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.priority",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.resource_limits",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.timeout",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.retry_policy",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.worker_measurements",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.worker_signers",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.federation_peer",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.labels",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.workflow_id",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.entry_point",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.reuse_result",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.key_exchange",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.expires_in",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.approval_window",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.priority",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.resource_limits",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.timeout",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.retry_policy",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.worker_measurements",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.worker_signers",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.federation_peer",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.CreateTaskRequest.signature",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.CreateTaskRequest.signature",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.AssignDataRequest.signature",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.ApproveTaskRequest.signature",
+        "#[serde(default)]",
+    );
+    // Typed arguments are serialized like {"int_value": 1}.
+    config.type_attribute(
+        ".teaclave_frontend_service_v2_proto.FunctionArgument.value",
+        "#[serde(rename_all = \"snake_case\")]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_v2_proto.FunctionArgument.value",
+        "#[serde(flatten)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.RegisterOutputFileRequest.credential",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.UpdateOutputFileRequest.credential",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.UpdateOutputUrlRequest.credential",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.RegisterModelRequest.metrics",
+        "#[serde(default)]",
+    );
+    config.field_attribute(
+        ".teaclave_frontend_service_proto.GetModelRequest.version",
+        "#[serde(default)]",
+    );
     config
 }
 
@@ -23,8 +23,12 @@ pub mod teaclave_access_control_service;
 pub mod teaclave_authentication_service;
 pub mod teaclave_common;
 pub mod teaclave_frontend_service;
+pub mod teaclave_frontend_service_v2;
+pub mod teaclave_key_broker_service;
 pub mod teaclave_management_service;
+pub mod teaclave_policy_decision_service;
 pub mod teaclave_scheduler_service;
+pub mod teaclave_storage_router;
 pub mod teaclave_storage_service;
 
 macro_rules! include_proto {
@@ -49,6 +53,10 @@ pub mod teaclave_frontend_service_proto {
     include_proto!("teaclave_frontend_service_proto");
 }
 
+pub mod teaclave_frontend_service_v2_proto {
+    include_proto!("teaclave_frontend_service_v2_proto");
+}
+
 pub mod teaclave_management_service_proto {
     include_proto!("teaclave_management_service_proto");
 }
@@ -60,3 +68,11 @@ pub mod teaclave_access_control_service_proto {
 pub mod teaclave_scheduler_service_proto {
     include_proto!("teaclave_scheduler_service_proto");
 }
+
+pub mod teaclave_policy_decision_service_proto {
+    include_proto!("teaclave_policy_decision_service_proto");
+}
+
+pub mod teaclave_key_broker_service_proto {
+    include_proto!("teaclave_key_broker_service_proto");
+}
@@ -20,6 +20,7 @@ use anyhow::{Error, Result};
 use core::convert::TryInto;
 use std::prelude::v1::*;
 use teaclave_rpc::into_request;
+use teaclave_types::{ApprovalReceipt, TokenScope};
 
 use crate::teaclave_authentication_service_proto as proto;
 use crate::teaclave_common;
@@ -37,6 +38,8 @@ pub use proto::TeaclaveAuthenticationInternalResponse;
 pub struct UserRegisterRequest {
     pub id: std::string::String,
     pub password: std::string::String,
+    /// Where the verification code is sent, e.g., an email address
+    pub contact: std::string::String,
 }
 
 impl UserRegisterRequest {
@@ -44,6 +47,14 @@ impl UserRegisterRequest {
         Self {
             id: id.into(),
             password: password.into(),
+            contact: String::new(),
+        }
+    }
+
+    pub fn contact(self, contact: impl Into<String>) -> Self {
+        Self {
+            contact: contact.into(),
+            ..self
         }
     }
 }
@@ -52,6 +63,26 @@ impl UserRegisterRequest {
 #[derive(Debug, Default)]
 pub struct UserRegisterResponse;
 
+#[into_request(TeaclaveAuthenticationApiRequest::ConfirmRegistration)]
+#[derive(Debug)]
+pub struct ConfirmRegistrationRequest {
+    pub id: std::string::String,
+    pub code: std::string::String,
+}
+
+impl ConfirmRegistrationRequest {
+    pub fn new(id: impl Into<String>, code: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            code: code.into(),
+        }
+    }
+}
+
+#[into_request(TeaclaveAuthenticationApiResponse::ConfirmRegistration)]
+#[derive(Debug, Default)]
+pub struct ConfirmRegistrationResponse;
+
 #[into_request(TeaclaveAuthenticationApiRequest::UserLogin)]
 #[derive(Debug)]
 pub struct UserLoginRequest {
@@ -82,6 +113,49 @@ impl UserLoginResponse {
     }
 }
 
+#[into_request(TeaclaveAuthenticationApiRequest::MintScopedToken)]
+#[derive(Debug)]
+pub struct MintScopedTokenRequest {
+    pub id: std::string::String,
+    pub token: std::string::String,
+    pub scope: TokenScope,
+    /// Seconds the token is valid for
+    pub expires_in: u64,
+}
+
+impl MintScopedTokenRequest {
+    pub fn new(
+        id: impl Into<String>,
+        token: impl Into<String>,
+        scope: TokenScope,
+        expires_in: u64,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            token: token.into(),
+            scope,
+            expires_in,
+        }
+    }
+}
+
+#[into_request(TeaclaveAuthenticationApiResponse::MintScopedToken)]
+#[derive(Debug)]
+pub struct MintScopedTokenResponse {
+    pub token: std::string::String,
+    /// Seconds since the Unix epoch
+    pub expires_at: u64,
+}
+
+impl MintScopedTokenResponse {
+    pub fn new(token: impl Into<String>, expires_at: u64) -> Self {
+        Self {
+            token: token.into(),
+            expires_at,
+        }
+    }
+}
+
 #[into_request(TeaclaveAuthenticationInternalRequest::UserAuthenticate)]
 #[derive(Debug)]
 pub struct UserAuthenticateRequest {
@@ -98,11 +172,44 @@ impl UserAuthenticateRequest {
 #[derive(Debug)]
 pub struct UserAuthenticateResponse {
     pub accept: bool,
+    /// Restrictions of an accepted token, if it is down-scoped
+    pub scope: Option<TokenScope>,
 }
 
 impl UserAuthenticateResponse {
     pub fn new(accept: bool) -> Self {
-        Self { accept }
+        Self {
+            accept,
+            scope: None,
+        }
+    }
+
+    pub fn scope(self, scope: Option<TokenScope>) -> Self {
+        Self { scope, ..self }
+    }
+}
+
+#[into_request(TeaclaveAuthenticationInternalRequest::SignApprovalReceipt)]
+#[derive(Debug)]
+pub struct SignApprovalReceiptRequest {
+    pub receipt: ApprovalReceipt,
+}
+
+impl SignApprovalReceiptRequest {
+    pub fn new(receipt: ApprovalReceipt) -> Self {
+        Self { receipt }
+    }
+}
+
+#[into_request(TeaclaveAuthenticationInternalResponse::SignApprovalReceipt)]
+#[derive(Debug)]
+pub struct SignApprovalReceiptResponse {
+    pub receipt: ApprovalReceipt,
+}
+
+impl SignApprovalReceiptResponse {
+    pub fn new(receipt: ApprovalReceipt) -> Self {
+        Self { receipt }
     }
 }
 
@@ -113,6 +220,7 @@ impl std::convert::TryFrom<proto::UserRegisterRequest> for UserRegisterRequest {
         let ret = Self {
             id: proto.id,
             password: proto.password,
+            contact: proto.contact,
         };
 
         Ok(ret)
@@ -124,6 +232,7 @@ impl From<UserRegisterRequest> for proto::UserRegisterRequest {
         Self {
             id: request.id,
             password: request.password,
+            contact: request.contact,
         }
     }
 }
@@ -142,6 +251,42 @@ impl From<UserRegisterResponse> for proto::UserRegisterResponse {
     }
 }
 
+impl std::convert::TryFrom<proto::ConfirmRegistrationRequest> for ConfirmRegistrationRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::ConfirmRegistrationRequest) -> Result<Self> {
+        let ret = Self {
+            id: proto.id,
+            code: proto.code,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<ConfirmRegistrationRequest> for proto::ConfirmRegistrationRequest {
+    fn from(request: ConfirmRegistrationRequest) -> Self {
+        Self {
+            id: request.id,
+            code: request.code,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ConfirmRegistrationResponse> for ConfirmRegistrationResponse {
+    type Error = Error;
+
+    fn try_from(_reponse: proto::ConfirmRegistrationResponse) -> Result<Self> {
+        Ok(Self {})
+    }
+}
+
+impl From<ConfirmRegistrationResponse> for proto::ConfirmRegistrationResponse {
+    fn from(_response: ConfirmRegistrationResponse) -> Self {
+        Self {}
+    }
+}
+
 impl std::convert::TryFrom<proto::UserLoginRequest> for UserLoginRequest {
     type Error = Error;
 
@@ -182,6 +327,55 @@ impl From<UserLoginResponse> for proto::UserLoginResponse {
     }
 }
 
+impl std::convert::TryFrom<proto::MintScopedTokenRequest> for MintScopedTokenRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::MintScopedTokenRequest) -> Result<Self> {
+        let ret = Self {
+            id: proto.id,
+            token: proto.token,
+            scope: TokenScope::new(proto.operations, proto.resources),
+            expires_in: proto.expires_in,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<MintScopedTokenRequest> for proto::MintScopedTokenRequest {
+    fn from(request: MintScopedTokenRequest) -> Self {
+        Self {
+            id: request.id,
+            token: request.token,
+            operations: request.scope.operations,
+            resources: request.scope.resources,
+            expires_in: request.expires_in,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::MintScopedTokenResponse> for MintScopedTokenResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::MintScopedTokenResponse) -> Result<Self> {
+        let ret = Self {
+            token: proto.token,
+            expires_at: proto.expires_at,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<MintScopedTokenResponse> for proto::MintScopedTokenResponse {
+    fn from(response: MintScopedTokenResponse) -> Self {
+        Self {
+            token: response.token,
+            expires_at: response.expires_at,
+        }
+    }
+}
+
 impl std::convert::TryFrom<proto::UserAuthenticateRequest> for UserAuthenticateRequest {
     type Error = Error;
 
@@ -211,6 +405,9 @@ impl std::convert::TryFrom<proto::UserAuthenticateResponse> for UserAuthenticate
     fn try_from(proto: proto::UserAuthenticateResponse) -> Result<Self> {
         let ret = Self {
             accept: proto.accept,
+            scope: proto
+                .scope
+                .map(|scope| TokenScope::new(scope.operations, scope.resources)),
         };
 
         Ok(ret)
@@ -221,6 +418,56 @@ impl From<UserAuthenticateResponse> for proto::UserAuthenticateResponse {
     fn from(response: UserAuthenticateResponse) -> Self {
         Self {
             accept: response.accept,
+            scope: response.scope.map(|scope| proto::TokenScope {
+                operations: scope.operations,
+                resources: scope.resources,
+            }),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::SignApprovalReceiptRequest> for SignApprovalReceiptRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::SignApprovalReceiptRequest) -> Result<Self> {
+        let ret = Self {
+            receipt: proto
+                .receipt
+                .ok_or_else(|| anyhow!("Missing receipt"))?
+                .try_into()?,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<SignApprovalReceiptRequest> for proto::SignApprovalReceiptRequest {
+    fn from(request: SignApprovalReceiptRequest) -> Self {
+        Self {
+            receipt: Some(request.receipt.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::SignApprovalReceiptResponse> for SignApprovalReceiptResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::SignApprovalReceiptResponse) -> Result<Self> {
+        let ret = Self {
+            receipt: proto
+                .receipt
+                .ok_or_else(|| anyhow!("Missing receipt"))?
+                .try_into()?,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<SignApprovalReceiptResponse> for proto::SignApprovalReceiptResponse {
+    fn from(response: SignApprovalReceiptResponse) -> Self {
+        Self {
+            receipt: Some(response.receipt.into()),
         }
     }
 }
@@ -22,7 +22,11 @@ use crate::teaclave_common_proto as proto;
 use anyhow::{bail, Error, Result};
 use std::convert::TryInto;
 use teaclave_crypto::TeaclaveFile128Key;
-use teaclave_types::{FileCrypto, TaskFailure, TaskOutputs, TaskResult, TaskStatus};
+use teaclave_types::{
+    ApprovalReceipt, FileCredential, FileCrypto, KeyBrokerReference, ReturnValueHandle,
+    TaskFailure, TaskFailureKind, TaskKeyOffer, TaskOutputs, TaskResult, TaskStatus,
+};
+use uuid::Uuid;
 
 #[derive(Debug)]
 pub struct UserCredential {
@@ -103,6 +107,61 @@ impl std::convert::From<TeaclaveFile128Key> for proto::FileCryptoInfo {
     }
 }
 
+impl std::convert::TryFrom<proto::FileCredentialInfo> for FileCredential {
+    type Error = Error;
+    fn try_from(proto: proto::FileCredentialInfo) -> Result<Self> {
+        FileCredential::new(
+            &proto.schema,
+            &proto.access_key_id,
+            &proto.secret,
+            &proto.region,
+            &proto.endpoint,
+        )
+    }
+}
+
+impl std::convert::From<FileCredential> for proto::FileCredentialInfo {
+    fn from(credential: FileCredential) -> Self {
+        let schema = credential.schema().to_owned();
+        match credential {
+            FileCredential::Bearer { token } | FileCredential::AzureSas { token } => {
+                proto::FileCredentialInfo {
+                    schema,
+                    secret: token,
+                    ..Default::default()
+                }
+            }
+            FileCredential::AwsS3 {
+                access_key_id,
+                secret_access_key,
+                region,
+                endpoint,
+            } => proto::FileCredentialInfo {
+                schema,
+                access_key_id,
+                secret: secret_access_key,
+                region,
+                endpoint: endpoint.map(|e| e.into_string()).unwrap_or_default(),
+            },
+        }
+    }
+}
+
+impl From<proto::KeyBrokerInfo> for KeyBrokerReference {
+    fn from(proto: proto::KeyBrokerInfo) -> Self {
+        KeyBrokerReference::new(proto.address, proto.key_id)
+    }
+}
+
+impl From<KeyBrokerReference> for proto::KeyBrokerInfo {
+    fn from(key_broker: KeyBrokerReference) -> Self {
+        proto::KeyBrokerInfo {
+            address: key_broker.address,
+            key_id: key_broker.key_id,
+        }
+    }
+}
+
 pub fn i32_to_task_status(status: i32) -> Result<TaskStatus> {
     let ret = match proto::TaskStatus::from_i32(status) {
         Some(proto::TaskStatus::Created) => TaskStatus::Created,
@@ -111,6 +170,9 @@ pub fn i32_to_task_status(status: i32) -> Result<TaskStatus> {
         Some(proto::TaskStatus::Staged) => TaskStatus::Staged,
         Some(proto::TaskStatus::Running) => TaskStatus::Running,
         Some(proto::TaskStatus::Finished) => TaskStatus::Finished,
+        Some(proto::TaskStatus::Canceled) => TaskStatus::Canceled,
+        Some(proto::TaskStatus::Expired) => TaskStatus::Expired,
+        Some(proto::TaskStatus::Rejected) => TaskStatus::Rejected,
         None => bail!("invalid task status"),
     };
     Ok(ret)
@@ -124,6 +186,9 @@ pub fn i32_from_task_status(status: TaskStatus) -> i32 {
         TaskStatus::Staged => proto::TaskStatus::Staged as i32,
         TaskStatus::Running => proto::TaskStatus::Running as i32,
         TaskStatus::Finished => proto::TaskStatus::Finished as i32,
+        TaskStatus::Canceled => proto::TaskStatus::Canceled as i32,
+        TaskStatus::Expired => proto::TaskStatus::Expired as i32,
+        TaskStatus::Rejected => proto::TaskStatus::Rejected as i32,
     }
 }
 
@@ -133,6 +198,12 @@ impl std::convert::TryFrom<proto::TaskOutputs> for TaskOutputs {
         let ret = TaskOutputs {
             return_value: proto.return_value,
             tags_map: proto.tags_map.try_into()?,
+            return_value_handle: proto
+                .return_value_handle
+                .map(TryInto::try_into)
+                .transpose()?,
+            partial: proto.partial,
+            sample_outputs: proto.sample_outputs,
         };
         Ok(ret)
     }
@@ -142,6 +213,30 @@ impl std::convert::From<TaskOutputs> for proto::TaskOutputs {
         proto::TaskOutputs {
             return_value: outputs.return_value,
             tags_map: outputs.tags_map.into(),
+            return_value_handle: outputs.return_value_handle.map(Into::into),
+            partial: outputs.partial,
+            sample_outputs: outputs.sample_outputs,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ReturnValueHandle> for ReturnValueHandle {
+    type Error = Error;
+    fn try_from(proto: proto::ReturnValueHandle) -> Result<Self> {
+        let ret = ReturnValueHandle {
+            task_id: Uuid::parse_str(&proto.task_id)?,
+            size: proto.size,
+            digest: proto.digest,
+        };
+        Ok(ret)
+    }
+}
+impl std::convert::From<ReturnValueHandle> for proto::ReturnValueHandle {
+    fn from(handle: ReturnValueHandle) -> Self {
+        proto::ReturnValueHandle {
+            task_id: handle.task_id.to_string(),
+            size: handle.size,
+            digest: handle.digest,
         }
     }
 }
@@ -149,8 +244,14 @@ impl std::convert::From<TaskOutputs> for proto::TaskOutputs {
 impl std::convert::TryFrom<proto::TaskFailure> for TaskFailure {
     type Error = Error;
     fn try_from(proto: proto::TaskFailure) -> Result<Self> {
+        let kind = if proto.kind.is_empty() {
+            TaskFailureKind::default()
+        } else {
+            proto.kind.as_str().try_into()?
+        };
         let ret = TaskFailure {
             reason: proto.reason,
+            kind,
         };
         Ok(ret)
     }
@@ -159,6 +260,7 @@ impl std::convert::From<TaskFailure> for proto::TaskFailure {
     fn from(outputs: TaskFailure) -> Self {
         proto::TaskFailure {
             reason: outputs.reason,
+            kind: outputs.kind.to_string(),
         }
     }
 }
@@ -194,3 +296,138 @@ impl std::convert::From<TaskResult> for proto::TaskResult {
         proto::TaskResult { result: opt_result }
     }
 }
+
+impl std::convert::TryFrom<proto::ApprovalReceipt> for ApprovalReceipt {
+    type Error = Error;
+    fn try_from(proto: proto::ApprovalReceipt) -> Result<Self> {
+        let ret = ApprovalReceipt {
+            user_id: proto.user_id.into(),
+            task_id: Uuid::parse_str(&proto.task_id)?,
+            task_spec_hash: proto.task_spec_hash,
+            timestamp: proto.timestamp,
+            signature: proto.signature,
+            public_key: proto.public_key,
+        };
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<ApprovalReceipt> for proto::ApprovalReceipt {
+    fn from(receipt: ApprovalReceipt) -> Self {
+        proto::ApprovalReceipt {
+            user_id: receipt.user_id.to_string(),
+            task_id: receipt.task_id.to_string(),
+            task_spec_hash: receipt.task_spec_hash,
+            timestamp: receipt.timestamp,
+            signature: receipt.signature,
+            public_key: receipt.public_key,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::TaskKeyOffer> for TaskKeyOffer {
+    type Error = Error;
+    fn try_from(proto: proto::TaskKeyOffer) -> Result<Self> {
+        let ret = TaskKeyOffer::new(proto.public_key, proto.cert, proto.signature);
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<TaskKeyOffer> for proto::TaskKeyOffer {
+    fn from(offer: TaskKeyOffer) -> Self {
+        proto::TaskKeyOffer {
+            public_key: offer.public_key,
+            cert: offer.cert,
+            signature: offer.signature,
+        }
+    }
+}
+
+/// Page of a list, requested with the token of the previous page, or none for
+/// the first page. A size of 0 requests the largest page allowed by the
+/// service.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PageRequest {
+    pub token: Option<String>,
+    pub size: usize,
+}
+
+impl PageRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn token(self, token: impl ToString) -> Self {
+        Self {
+            token: Some(token.to_string()),
+            ..self
+        }
+    }
+
+    pub fn size(self, size: usize) -> Self {
+        Self { size, ..self }
+    }
+
+    /// Page following a page of the list, or none after the last page.
+    pub fn next(&self, page: &PageResponse) -> Option<Self> {
+        page.next_token.as_ref().map(|token| Self {
+            token: Some(token.to_owned()),
+            size: self.size,
+        })
+    }
+}
+
+/// Token of the page following a page of a list, which is none for the last
+/// page.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PageResponse {
+    pub next_token: Option<String>,
+}
+
+impl PageResponse {
+    pub fn new(next_token: Option<String>) -> Self {
+        Self { next_token }
+    }
+}
+
+impl From<proto::PageRequest> for PageRequest {
+    fn from(proto: proto::PageRequest) -> Self {
+        let token = if proto.page_token.is_empty() {
+            None
+        } else {
+            Some(proto.page_token)
+        };
+        Self {
+            token,
+            size: proto.page_size as usize,
+        }
+    }
+}
+
+impl From<PageRequest> for proto::PageRequest {
+    fn from(page: PageRequest) -> Self {
+        Self {
+            page_token: page.token.unwrap_or_default(),
+            page_size: page.size as u32,
+        }
+    }
+}
+
+impl From<proto::PageResponse> for PageResponse {
+    fn from(proto: proto::PageResponse) -> Self {
+        let next_token = if proto.next_page_token.is_empty() {
+            None
+        } else {
+            Some(proto.next_page_token)
+        };
+        Self { next_token }
+    }
+}
+
+impl From<PageResponse> for proto::PageResponse {
+    fn from(page: PageResponse) -> Self {
+        Self {
+            next_page_token: page.next_token.unwrap_or_default(),
+        }
+    }
+}
@@ -17,34 +17,63 @@
 
 use crate::teaclave_common::{i32_from_task_status, i32_to_task_status};
 use crate::teaclave_frontend_service_proto as proto;
+use crate::teaclave_frontend_service_v2::{TeaclaveFrontendV2Request, TeaclaveFrontendV2Response};
 use crate::teaclave_management_service::TeaclaveManagementRequest;
 use crate::teaclave_management_service::TeaclaveManagementResponse;
-use anyhow::anyhow;
+use anyhow::{anyhow, bail, ensure};
 use anyhow::{Error, Result};
 use core::convert::TryInto;
 use std::collections::HashMap;
 use std::prelude::v1::*;
+use teaclave_crypto::TeaclaveFile128Key;
 use teaclave_rpc::into_request;
 use teaclave_types::{
-    Executor, ExecutorType, ExternalID, FileAuthTag, FileCrypto, Function, FunctionArguments,
-    FunctionInput, FunctionOutput, OwnerList, TaskFileOwners, TaskResult, TaskStatus, UserID,
-    UserList,
+    approve_task_content, assign_data_content, create_task_content, ActivityEvent, ActivityKind,
+    ApprovalReceipt, Executor, ExecutorType, ExternalID, FileAuthTag, FileCredential, FileCrypto,
+    Function, FunctionArguments, FunctionCapabilities, FunctionInput, FunctionOutput,
+    KeyBrokerReference, LineageStep, ManifestMeasurement, ModelReference, ModelVersion, OwnerList,
+    PostProcessingStep, RequestSignature, ResourceLimits, ResourceProfile, RetryPolicy,
+    ScheduleSpec, SignedAction, StorageSnapshot, TaskFileOwners, TaskFilter, TaskKeyOffer,
+    TaskManifest, TaskOutputReference, TaskPriority, TaskProgress, TaskResult, TaskStatus, UserID,
+    UserList, WrappedTaskKeys,
 };
 use url::Url;
 use uuid::Uuid;
 
+pub use crate::teaclave_common::{PageRequest, PageResponse};
 pub use proto::TeaclaveFrontend;
 pub use proto::TeaclaveFrontendClient;
 pub use proto::TeaclaveFrontendRequest;
 pub use proto::TeaclaveFrontendResponse;
 
+/// Requests of all versions of the frontend API served side by side. Methods
+/// of later versions are named with their versions, e.g., "v2/create_task",
+/// and the others are of version 1.
+#[derive(Clone, serde::Serialize, serde::Deserialize, Debug)]
+#[serde(untagged)]
+pub enum TeaclaveFrontendApiRequest {
+    V1(TeaclaveFrontendRequest),
+    V2(TeaclaveFrontendV2Request),
+}
+
+/// Responses in the versions of their requests.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, serde::Serialize, Debug)]
+#[serde(untagged)]
+pub enum TeaclaveFrontendApiResponse {
+    V1(TeaclaveFrontendResponse),
+    V2(TeaclaveFrontendV2Response),
+}
+
 #[into_request(TeaclaveFrontendRequest::RegisterInputFile)]
+#[into_request(TeaclaveFrontendV2Request::RegisterInputFile)]
 #[into_request(TeaclaveManagementRequest::RegisterInputFile)]
 #[derive(Debug, PartialEq)]
 pub struct RegisterInputFileRequest {
     pub url: Url,
     pub cmac: FileAuthTag,
     pub crypto_info: FileCrypto,
+    pub key_broker: Option<KeyBrokerReference>,
 }
 
 impl RegisterInputFileRequest {
@@ -53,11 +82,24 @@ impl RegisterInputFileRequest {
             url,
             cmac,
             crypto_info: crypto.into(),
+            key_broker: None,
+        }
+    }
+
+    /// Registers a file whose key is released by the broker to the execution
+    /// enclaves only, and never given to Teaclave.
+    pub fn with_key_broker(url: Url, cmac: FileAuthTag, key_broker: KeyBrokerReference) -> Self {
+        Self {
+            url,
+            cmac,
+            crypto_info: FileCrypto::Raw,
+            key_broker: Some(key_broker),
         }
     }
 }
 
 #[into_request(TeaclaveFrontendRequest::UpdateInputFile)]
+#[into_request(TeaclaveFrontendV2Request::UpdateInputFile)]
 #[into_request(TeaclaveManagementRequest::UpdateInputFile)]
 #[derive(Debug, PartialEq)]
 pub struct UpdateInputFileRequest {
@@ -71,6 +113,33 @@ impl UpdateInputFileRequest {
     }
 }
 
+#[into_request(TeaclaveFrontendRequest::RotateInputFileKey)]
+#[into_request(TeaclaveFrontendV2Request::RotateInputFileKey)]
+#[into_request(TeaclaveManagementRequest::RotateInputFileKey)]
+#[derive(Debug, PartialEq)]
+pub struct RotateInputFileKeyRequest {
+    pub data_id: ExternalID,
+    pub url: Url,
+    pub cmac: FileAuthTag,
+    pub crypto_info: FileCrypto,
+}
+
+impl RotateInputFileKeyRequest {
+    pub fn new(
+        data_id: ExternalID,
+        url: Url,
+        cmac: FileAuthTag,
+        crypto: impl Into<FileCrypto>,
+    ) -> Self {
+        Self {
+            data_id,
+            url,
+            cmac,
+            crypto_info: crypto.into(),
+        }
+    }
+}
+
 #[into_request(TeaclaveFrontendResponse::RegisterInputFile)]
 #[into_request(TeaclaveManagementResponse::RegisterInputFile)]
 #[derive(Debug, PartialEq)]
@@ -97,34 +166,111 @@ impl UpdateInputFileResponse {
     }
 }
 
+#[into_request(TeaclaveFrontendResponse::RotateInputFileKey)]
+#[into_request(TeaclaveManagementResponse::RotateInputFileKey)]
+#[derive(Debug, PartialEq)]
+pub struct RotateInputFileKeyResponse {
+    pub data_id: ExternalID,
+    pub in_flight_task_ids: Vec<ExternalID>,
+}
+
+impl RotateInputFileKeyResponse {
+    pub fn new(data_id: ExternalID, in_flight_task_ids: Vec<ExternalID>) -> Self {
+        Self {
+            data_id,
+            in_flight_task_ids,
+        }
+    }
+}
+
 #[into_request(TeaclaveFrontendRequest::RegisterOutputFile)]
+#[into_request(TeaclaveFrontendV2Request::RegisterOutputFile)]
 #[into_request(TeaclaveManagementRequest::RegisterOutputFile)]
 #[derive(Debug)]
 pub struct RegisterOutputFileRequest {
-    pub url: Url,
+    pub url: Option<Url>,
     pub crypto_info: FileCrypto,
+    pub credential: Option<FileCredential>,
 }
 
 impl RegisterOutputFileRequest {
     pub fn new(url: Url, crypto: impl Into<FileCrypto>) -> Self {
         Self {
-            url,
+            url: Some(url),
+            crypto_info: crypto.into(),
+            credential: None,
+        }
+    }
+
+    /// Registers an output file whose URL is set later with
+    /// `UpdateOutputUrlRequest`, before the task is invoked.
+    pub fn deferred(crypto: impl Into<FileCrypto>) -> Self {
+        Self {
+            url: None,
             crypto_info: crypto.into(),
+            credential: None,
+        }
+    }
+
+    pub fn credential(self, credential: FileCredential) -> Self {
+        Self {
+            credential: Some(credential),
+            ..self
         }
     }
 }
 
 #[into_request(TeaclaveFrontendRequest::UpdateOutputFile)]
+#[into_request(TeaclaveFrontendV2Request::UpdateOutputFile)]
 #[into_request(TeaclaveManagementRequest::UpdateOutputFile)]
 #[derive(Debug)]
 pub struct UpdateOutputFileRequest {
     pub data_id: ExternalID,
     pub url: Url,
+    pub credential: Option<FileCredential>,
 }
 
 impl UpdateOutputFileRequest {
     pub fn new(data_id: ExternalID, url: Url) -> Self {
-        Self { data_id, url }
+        Self {
+            data_id,
+            url,
+            credential: None,
+        }
+    }
+
+    pub fn credential(self, credential: FileCredential) -> Self {
+        Self {
+            credential: Some(credential),
+            ..self
+        }
+    }
+}
+
+#[into_request(TeaclaveFrontendRequest::UpdateOutputUrl)]
+#[into_request(TeaclaveFrontendV2Request::UpdateOutputUrl)]
+#[into_request(TeaclaveManagementRequest::UpdateOutputUrl)]
+#[derive(Debug)]
+pub struct UpdateOutputUrlRequest {
+    pub data_id: ExternalID,
+    pub url: Url,
+    pub credential: Option<FileCredential>,
+}
+
+impl UpdateOutputUrlRequest {
+    pub fn new(data_id: ExternalID, url: Url) -> Self {
+        Self {
+            data_id,
+            url,
+            credential: None,
+        }
+    }
+
+    pub fn credential(self, credential: FileCredential) -> Self {
+        Self {
+            credential: Some(credential),
+            ..self
+        }
     }
 }
 
@@ -154,7 +300,13 @@ impl UpdateOutputFileResponse {
     }
 }
 
+#[into_request(TeaclaveFrontendResponse::UpdateOutputUrl)]
+#[into_request(TeaclaveManagementResponse::UpdateOutputUrl)]
+#[derive(Debug)]
+pub struct UpdateOutputUrlResponse;
+
 #[into_request(TeaclaveFrontendRequest::RegisterFusionOutput)]
+#[into_request(TeaclaveFrontendV2Request::RegisterFusionOutput)]
 #[into_request(TeaclaveManagementRequest::RegisterFusionOutput)]
 #[derive(Debug)]
 pub struct RegisterFusionOutputRequest {
@@ -183,6 +335,7 @@ impl RegisterFusionOutputResponse {
 }
 
 #[into_request(TeaclaveFrontendRequest::RegisterInputFromOutput)]
+#[into_request(TeaclaveFrontendV2Request::RegisterInputFromOutput)]
 #[into_request(TeaclaveManagementRequest::RegisterInputFromOutput)]
 #[derive(Debug)]
 pub struct RegisterInputFromOutputRequest {
@@ -209,6 +362,7 @@ impl RegisterInputFromOutputResponse {
 }
 
 #[into_request(TeaclaveFrontendRequest::GetInputFile)]
+#[into_request(TeaclaveFrontendV2Request::GetInputFile)]
 #[into_request(TeaclaveManagementRequest::GetInputFile)]
 #[derive(Debug)]
 pub struct GetInputFileRequest {
@@ -236,6 +390,7 @@ impl GetInputFileResponse {
 }
 
 #[into_request(TeaclaveFrontendRequest::GetOutputFile)]
+#[into_request(TeaclaveFrontendV2Request::GetOutputFile)]
 #[into_request(TeaclaveManagementRequest::GetOutputFile)]
 #[derive(Debug)]
 pub struct GetOutputFileRequest {
@@ -262,8 +417,45 @@ impl GetOutputFileResponse {
     }
 }
 
+#[into_request(TeaclaveFrontendRequest::DeleteInputFile)]
+#[into_request(TeaclaveFrontendV2Request::DeleteInputFile)]
+#[into_request(TeaclaveManagementRequest::DeleteInputFile)]
+#[derive(Debug)]
+pub struct DeleteInputFileRequest {
+    pub data_id: ExternalID,
+}
+
+impl DeleteInputFileRequest {
+    pub fn new(data_id: ExternalID) -> Self {
+        Self { data_id }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::DeleteInputFile)]
+#[derive(Debug)]
+pub struct DeleteInputFileResponse;
+
+#[into_request(TeaclaveFrontendRequest::DeleteOutputFile)]
+#[into_request(TeaclaveFrontendV2Request::DeleteOutputFile)]
+#[into_request(TeaclaveManagementRequest::DeleteOutputFile)]
+#[derive(Debug)]
+pub struct DeleteOutputFileRequest {
+    pub data_id: ExternalID,
+}
+
+impl DeleteOutputFileRequest {
+    pub fn new(data_id: ExternalID) -> Self {
+        Self { data_id }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::DeleteOutputFile)]
+#[derive(Debug)]
+pub struct DeleteOutputFileResponse;
+
 #[into_request(TeaclaveManagementRequest::RegisterFunction)]
 #[into_request(TeaclaveFrontendRequest::RegisterFunction)]
+#[into_request(TeaclaveFrontendV2Request::RegisterFunction)]
 #[derive(Debug, Default)]
 pub struct RegisterFunctionRequest {
     pub name: String,
@@ -274,6 +466,11 @@ pub struct RegisterFunctionRequest {
     pub arguments: Vec<String>,
     pub inputs: Vec<FunctionInput>,
     pub outputs: Vec<FunctionOutput>,
+    pub resource_profile: ResourceProfile,
+    pub entry_points: Vec<String>,
+    pub capabilities: Option<FunctionCapabilities>,
+    pub worker_measurements: Vec<String>,
+    pub post_processing: Vec<PostProcessingStep>,
 }
 
 impl RegisterFunctionRequest {
@@ -331,6 +528,50 @@ impl RegisterFunctionRequest {
     pub fn outputs(self, outputs: Vec<FunctionOutput>) -> Self {
         Self { outputs, ..self }
     }
+
+    pub fn resource_profile(self, resource_profile: ResourceProfile) -> Self {
+        Self {
+            resource_profile,
+            ..self
+        }
+    }
+
+    pub fn entry_points<T: IntoIterator>(self, entry_points: T) -> Self
+    where
+        <T as IntoIterator>::Item: ToString,
+    {
+        Self {
+            entry_points: entry_points.into_iter().map(|x| x.to_string()).collect(),
+            ..self
+        }
+    }
+
+    pub fn capabilities(self, capabilities: FunctionCapabilities) -> Self {
+        Self {
+            capabilities: Some(capabilities),
+            ..self
+        }
+    }
+
+    pub fn worker_measurements<T: IntoIterator>(self, worker_measurements: T) -> Self
+    where
+        <T as IntoIterator>::Item: ToString,
+    {
+        Self {
+            worker_measurements: worker_measurements
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect(),
+            ..self
+        }
+    }
+
+    pub fn post_processing(self, post_processing: Vec<PostProcessingStep>) -> Self {
+        Self {
+            post_processing,
+            ..self
+        }
+    }
 }
 
 // We explicitly construct Function here in case of missing any field
@@ -347,6 +588,11 @@ impl From<RegisterFunctionRequest> for Function {
             arguments: request.arguments,
             inputs: request.inputs,
             outputs: request.outputs,
+            resource_profile: request.resource_profile,
+            entry_points: request.entry_points,
+            capabilities: request.capabilities,
+            worker_measurements: request.worker_measurements,
+            post_processing: request.post_processing,
         }
     }
 }
@@ -365,6 +611,7 @@ impl RegisterFunctionResponse {
 
 #[into_request(TeaclaveManagementRequest::GetFunction)]
 #[into_request(TeaclaveFrontendRequest::GetFunction)]
+#[into_request(TeaclaveFrontendV2Request::GetFunction)]
 #[derive(Debug)]
 pub struct GetFunctionRequest {
     pub function_id: ExternalID,
@@ -388,10 +635,16 @@ pub struct GetFunctionResponse {
     pub arguments: Vec<String>,
     pub inputs: Vec<FunctionInput>,
     pub outputs: Vec<FunctionOutput>,
+    pub resource_profile: ResourceProfile,
+    pub entry_points: Vec<String>,
+    pub capabilities: Option<FunctionCapabilities>,
+    pub worker_measurements: Vec<String>,
+    pub post_processing: Vec<PostProcessingStep>,
 }
 
 #[into_request(TeaclaveManagementRequest::CreateTask)]
 #[into_request(TeaclaveFrontendRequest::CreateTask)]
+#[into_request(TeaclaveFrontendV2Request::CreateTask)]
 #[derive(Default)]
 pub struct CreateTaskRequest {
     pub function_id: ExternalID,
@@ -399,6 +652,22 @@ pub struct CreateTaskRequest {
     pub executor: Executor,
     pub inputs_ownership: TaskFileOwners,
     pub outputs_ownership: TaskFileOwners,
+    pub labels: HashMap<String, String>,
+    pub workflow_id: Option<String>,
+    pub entry_point: Option<String>,
+    pub reuse_result: bool,
+    pub key_exchange: bool,
+    pub expires_in: Option<u64>,
+    pub approval_window: Option<u64>,
+    pub input_dependencies: HashMap<String, TaskOutputReference>,
+    pub priority: TaskPriority,
+    pub resource_limits: ResourceLimits,
+    pub timeout: Option<u64>,
+    pub retry_policy: RetryPolicy,
+    pub worker_measurements: Vec<String>,
+    pub worker_signers: Vec<String>,
+    pub federation_peer: Option<String>,
+    pub signature: Vec<u8>,
 }
 
 impl CreateTaskRequest {
@@ -437,6 +706,144 @@ impl CreateTaskRequest {
             ..self
         }
     }
+
+    pub fn labels(self, labels: HashMap<String, String>) -> Self {
+        Self { labels, ..self }
+    }
+
+    pub fn workflow_id(self, workflow_id: impl ToString) -> Self {
+        Self {
+            workflow_id: Some(workflow_id.to_string()),
+            ..self
+        }
+    }
+
+    pub fn entry_point(self, entry_point: impl ToString) -> Self {
+        Self {
+            entry_point: Some(entry_point.to_string()),
+            ..self
+        }
+    }
+
+    pub fn reuse_result(self, reuse_result: bool) -> Self {
+        Self {
+            reuse_result,
+            ..self
+        }
+    }
+
+    pub fn key_exchange(self, key_exchange: bool) -> Self {
+        Self {
+            key_exchange,
+            ..self
+        }
+    }
+
+    /// Seconds after which the task expires unless it has started running.
+    pub fn expires_in(self, expires_in: u64) -> Self {
+        Self {
+            expires_in: Some(expires_in),
+            ..self
+        }
+    }
+
+    /// Seconds after which the task expires unless every participant has
+    /// approved it.
+    pub fn approval_window(self, approval_window: u64) -> Self {
+        Self {
+            approval_window: Some(approval_window),
+            ..self
+        }
+    }
+
+    /// Assigns the input with the output of an upstream task once the
+    /// upstream task has finished.
+    pub fn input_dependency(
+        mut self,
+        data_name: impl ToString,
+        task_id: ExternalID,
+        output: impl ToString,
+    ) -> Self {
+        self.input_dependencies.insert(
+            data_name.to_string(),
+            TaskOutputReference::new(task_id, output),
+        );
+        self
+    }
+
+    pub fn priority(self, priority: TaskPriority) -> Self {
+        Self { priority, ..self }
+    }
+
+    pub fn resource_limits(self, resource_limits: ResourceLimits) -> Self {
+        Self {
+            resource_limits,
+            ..self
+        }
+    }
+
+    pub fn timeout(self, timeout: u64) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    pub fn retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy,
+            ..self
+        }
+    }
+
+    pub fn worker_measurements<T: IntoIterator>(self, worker_measurements: T) -> Self
+    where
+        <T as IntoIterator>::Item: ToString,
+    {
+        Self {
+            worker_measurements: worker_measurements
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect(),
+            ..self
+        }
+    }
+
+    pub fn worker_signers<T: IntoIterator>(self, worker_signers: T) -> Self
+    where
+        <T as IntoIterator>::Item: ToString,
+    {
+        Self {
+            worker_signers: worker_signers.into_iter().map(|x| x.to_string()).collect(),
+            ..self
+        }
+    }
+
+    pub fn federation_peer(self, federation_peer: impl ToString) -> Self {
+        Self {
+            federation_peer: Some(federation_peer.to_string()),
+            ..self
+        }
+    }
+
+    /// Signs the request with the signature of the creator on the message of
+    /// `request_signature`.
+    pub fn signature(self, signature: Vec<u8>) -> Self {
+        Self { signature, ..self }
+    }
+
+    /// The unsigned signature of the user on the request, whose message is
+    /// what the user signs.
+    pub fn request_signature(&self, user_id: &UserID) -> RequestSignature {
+        let content = create_task_content(
+            &self.function_id,
+            &self.function_arguments,
+            self.executor,
+            &self.inputs_ownership,
+            &self.outputs_ownership,
+        );
+        RequestSignature::new(user_id.clone(), SignedAction::CreateTask, &content)
+    }
 }
 
 #[into_request(TeaclaveManagementResponse::CreateTask)]
@@ -453,6 +860,7 @@ impl CreateTaskResponse {
 
 #[into_request(TeaclaveManagementRequest::GetTask)]
 #[into_request(TeaclaveFrontendRequest::GetTask)]
+#[into_request(TeaclaveFrontendV2Request::GetTask)]
 #[derive(Debug)]
 pub struct GetTaskRequest {
     pub task_id: ExternalID,
@@ -480,867 +888,4361 @@ pub struct GetTaskResponse {
     pub assigned_outputs: HashMap<String, ExternalID>,
     pub status: TaskStatus,
     pub result: TaskResult,
+    pub labels: HashMap<String, String>,
+    pub approval_receipts: Vec<ApprovalReceipt>,
+    pub entry_point: Option<String>,
+    pub reuse_result: bool,
+    pub function_capabilities: Option<FunctionCapabilities>,
+    pub key_exchange: bool,
+    pub worker_measurements: Vec<String>,
+    pub expires_at: Option<u64>,
+    pub rejected_by: Option<UserID>,
+    pub rejection_reason: String,
+    pub approval_expires_at: Option<u64>,
+    pub input_dependencies: HashMap<String, TaskOutputReference>,
+    pub staging_deferred: bool,
+    pub priority: TaskPriority,
+    pub resource_limits: ResourceLimits,
+    pub timeout: Option<u64>,
+    pub retry_policy: RetryPolicy,
+    pub retry_count: u32,
+    pub worker_signers: Vec<String>,
+    pub federation_peer: Option<String>,
+    pub function_post_processing: Vec<PostProcessingStep>,
+    pub spec_hash: String,
+    pub request_signatures: Vec<RequestSignature>,
 }
 
-#[into_request(TeaclaveManagementRequest::AssignData)]
-#[into_request(TeaclaveFrontendRequest::AssignData)]
-#[derive(Debug)]
-pub struct AssignDataRequest {
-    pub task_id: ExternalID,
-    pub inputs: HashMap<String, ExternalID>,
-    pub outputs: HashMap<String, ExternalID>,
+#[into_request(TeaclaveManagementRequest::ListTasks)]
+#[into_request(TeaclaveFrontendRequest::ListTasks)]
+#[into_request(TeaclaveFrontendV2Request::ListTasks)]
+#[derive(Debug, Default)]
+pub struct ListTasksRequest {
+    pub labels: HashMap<String, String>,
+    pub filter: TaskFilter,
+    pub page: PageRequest,
 }
 
-impl AssignDataRequest {
-    pub fn new(
-        task_id: ExternalID,
-        inputs: HashMap<String, ExternalID>,
-        outputs: HashMap<String, ExternalID>,
-    ) -> Self {
-        Self {
-            task_id,
-            inputs,
-            outputs,
-        }
+impl ListTasksRequest {
+    pub fn new() -> Self {
+        Self::default()
     }
-}
 
-#[derive(Debug)]
-pub struct AssignDataResponse;
+    pub fn labels(self, labels: HashMap<String, String>) -> Self {
+        Self { labels, ..self }
+    }
 
-#[into_request(TeaclaveManagementRequest::ApproveTask)]
-#[into_request(TeaclaveFrontendRequest::ApproveTask)]
-#[derive(Debug)]
-pub struct ApproveTaskRequest {
-    pub task_id: ExternalID,
-}
+    pub fn page(self, page: PageRequest) -> Self {
+        Self { page, ..self }
+    }
 
-impl ApproveTaskRequest {
-    pub fn new(task_id: ExternalID) -> Self {
-        Self { task_id }
+    pub fn filter(self, filter: TaskFilter) -> Self {
+        Self { filter, ..self }
     }
 }
 
+#[into_request(TeaclaveManagementResponse::ListTasks)]
 #[derive(Debug)]
-pub struct ApproveTaskResponse;
-
-#[into_request(TeaclaveManagementRequest::InvokeTask)]
-#[into_request(TeaclaveFrontendRequest::InvokeTask)]
-#[derive(Debug)]
-pub struct InvokeTaskRequest {
-    pub task_id: ExternalID,
+pub struct ListTasksResponse {
+    pub task_ids: Vec<ExternalID>,
+    pub page: PageResponse,
 }
 
-impl InvokeTaskRequest {
-    pub fn new(task_id: ExternalID) -> Self {
-        Self { task_id }
+impl ListTasksResponse {
+    pub fn new(task_ids: Vec<ExternalID>, page: PageResponse) -> Self {
+        Self { task_ids, page }
     }
 }
 
-#[derive(Debug)]
-pub struct InvokeTaskResponse;
+#[into_request(TeaclaveManagementRequest::ListPendingApprovals)]
+#[into_request(TeaclaveFrontendRequest::ListPendingApprovals)]
+#[into_request(TeaclaveFrontendV2Request::ListPendingApprovals)]
+#[derive(Debug, Default)]
+pub struct ListPendingApprovalsRequest {
+    pub page: PageRequest,
+}
 
-impl std::convert::TryFrom<proto::RegisterInputFileRequest> for RegisterInputFileRequest {
+impl ListPendingApprovalsRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn page(self, page: PageRequest) -> Self {
+        Self { page }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::ListPendingApprovals)]
+#[derive(Debug)]
+pub struct ListPendingApprovalsResponse {
+    pub task_ids: Vec<ExternalID>,
+    pub page: PageResponse,
+}
+
+impl ListPendingApprovalsResponse {
+    pub fn new(task_ids: Vec<ExternalID>, page: PageResponse) -> Self {
+        Self { task_ids, page }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::ListFunctions)]
+#[into_request(TeaclaveFrontendRequest::ListFunctions)]
+#[into_request(TeaclaveFrontendV2Request::ListFunctions)]
+#[derive(Debug, Default)]
+pub struct ListFunctionsRequest {
+    pub page: PageRequest,
+}
+
+impl ListFunctionsRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn page(self, page: PageRequest) -> Self {
+        Self { page }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::ListFunctions)]
+#[derive(Debug)]
+pub struct ListFunctionsResponse {
+    pub function_ids: Vec<ExternalID>,
+    pub page: PageResponse,
+}
+
+impl ListFunctionsResponse {
+    pub fn new(function_ids: Vec<ExternalID>, page: PageResponse) -> Self {
+        Self { function_ids, page }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::ListFiles)]
+#[into_request(TeaclaveFrontendRequest::ListFiles)]
+#[into_request(TeaclaveFrontendV2Request::ListFiles)]
+#[derive(Debug, Default)]
+pub struct ListFilesRequest {
+    /// Lists output files instead of input files.
+    pub output: bool,
+    pub page: PageRequest,
+}
+
+impl ListFilesRequest {
+    pub fn input() -> Self {
+        Self::default()
+    }
+
+    pub fn output() -> Self {
+        Self {
+            output: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn page(self, page: PageRequest) -> Self {
+        Self { page, ..self }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::ListFiles)]
+#[derive(Debug)]
+pub struct ListFilesResponse {
+    pub data_ids: Vec<ExternalID>,
+    pub page: PageResponse,
+}
+
+impl ListFilesResponse {
+    pub fn new(data_ids: Vec<ExternalID>, page: PageResponse) -> Self {
+        Self { data_ids, page }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::AssignData)]
+#[into_request(TeaclaveFrontendRequest::AssignData)]
+#[into_request(TeaclaveFrontendV2Request::AssignData)]
+#[derive(Debug)]
+pub struct AssignDataRequest {
+    pub task_id: ExternalID,
+    pub inputs: HashMap<String, ExternalID>,
+    pub outputs: HashMap<String, ExternalID>,
+    pub input_models: HashMap<String, ModelReference>,
+    pub signature: Vec<u8>,
+}
+
+impl AssignDataRequest {
+    pub fn new(
+        task_id: ExternalID,
+        inputs: HashMap<String, ExternalID>,
+        outputs: HashMap<String, ExternalID>,
+    ) -> Self {
+        Self {
+            task_id,
+            inputs,
+            outputs,
+            input_models: HashMap::new(),
+            signature: Vec::new(),
+        }
+    }
+
+    /// Assigns models of the user to inputs, which are resolved to the input
+    /// files of the referenced versions.
+    pub fn input_models(self, input_models: HashMap<String, ModelReference>) -> Self {
+        Self {
+            input_models,
+            ..self
+        }
+    }
+
+    /// Signs the request with the signature of the user on the message of
+    /// `request_signature`.
+    pub fn signature(self, signature: Vec<u8>) -> Self {
+        Self { signature, ..self }
+    }
+
+    /// The unsigned signature of the user on the request, whose message is
+    /// what the user signs.
+    pub fn request_signature(&self, user_id: &UserID) -> RequestSignature {
+        let content = assign_data_content(
+            &self.task_id,
+            &self.inputs,
+            &self.outputs,
+            &self.input_models,
+        );
+        RequestSignature::new(user_id.clone(), SignedAction::AssignData, &content)
+    }
+}
+
+#[derive(Debug)]
+pub struct AssignDataResponse;
+
+#[into_request(TeaclaveManagementRequest::ApproveTask)]
+#[into_request(TeaclaveFrontendRequest::ApproveTask)]
+#[into_request(TeaclaveFrontendV2Request::ApproveTask)]
+#[derive(Debug)]
+pub struct ApproveTaskRequest {
+    pub task_id: ExternalID,
+    pub signature: Vec<u8>,
+}
+
+impl ApproveTaskRequest {
+    pub fn new(task_id: ExternalID) -> Self {
+        Self {
+            task_id,
+            signature: Vec::new(),
+        }
+    }
+
+    /// Signs the request with the signature of the user on the message of
+    /// `request_signature`.
+    pub fn signature(self, signature: Vec<u8>) -> Self {
+        Self { signature, ..self }
+    }
+
+    /// The unsigned signature of the user approving the task of the spec
+    /// hash, whose message is what the user signs.
+    pub fn request_signature(&self, user_id: &UserID, spec_hash: &str) -> RequestSignature {
+        let content = approve_task_content(&self.task_id, spec_hash);
+        RequestSignature::new(user_id.clone(), SignedAction::ApproveTask, &content)
+    }
+}
+
+#[derive(Debug)]
+pub struct ApproveTaskResponse {
+    pub receipt: ApprovalReceipt,
+}
+
+impl ApproveTaskResponse {
+    pub fn new(receipt: ApprovalReceipt) -> Self {
+        Self { receipt }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::InvokeTask)]
+#[into_request(TeaclaveFrontendRequest::InvokeTask)]
+#[into_request(TeaclaveFrontendV2Request::InvokeTask)]
+#[derive(Debug)]
+pub struct InvokeTaskRequest {
+    pub task_id: ExternalID,
+}
+
+impl InvokeTaskRequest {
+    pub fn new(task_id: ExternalID) -> Self {
+        Self { task_id }
+    }
+}
+
+#[derive(Debug)]
+pub struct InvokeTaskResponse;
+
+#[into_request(TeaclaveManagementRequest::GetTaskManifest)]
+#[into_request(TeaclaveFrontendRequest::GetTaskManifest)]
+#[into_request(TeaclaveFrontendV2Request::GetTaskManifest)]
+#[derive(Debug)]
+pub struct GetTaskManifestRequest {
+    pub task_id: ExternalID,
+}
+
+impl GetTaskManifestRequest {
+    pub fn new(task_id: ExternalID) -> Self {
+        Self { task_id }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::GetTaskManifest)]
+#[derive(Debug)]
+pub struct GetTaskManifestResponse {
+    pub manifest: TaskManifest,
+}
+
+impl GetTaskManifestResponse {
+    pub fn new(manifest: TaskManifest) -> Self {
+        Self { manifest }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::GetTaskReturnValue)]
+#[into_request(TeaclaveFrontendRequest::GetTaskReturnValue)]
+#[into_request(TeaclaveFrontendV2Request::GetTaskReturnValue)]
+#[derive(Debug)]
+pub struct GetTaskReturnValueRequest {
+    pub task_id: ExternalID,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl GetTaskReturnValueRequest {
+    pub fn new(task_id: ExternalID) -> Self {
+        Self {
+            task_id,
+            offset: 0,
+            limit: 0,
+        }
+    }
+
+    pub fn page(self, offset: usize, limit: usize) -> Self {
+        Self {
+            offset,
+            limit,
+            ..self
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::GetTaskReturnValue)]
+#[derive(Debug)]
+pub struct GetTaskReturnValueResponse {
+    pub data: Vec<u8>,
+    pub total_size: usize,
+    pub next_offset: Option<usize>,
+}
+
+impl GetTaskReturnValueResponse {
+    pub fn new(data: Vec<u8>, total_size: usize, next_offset: Option<usize>) -> Self {
+        Self {
+            data,
+            total_size,
+            next_offset,
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::GetTaskKeyOffer)]
+#[into_request(TeaclaveFrontendRequest::GetTaskKeyOffer)]
+#[into_request(TeaclaveFrontendV2Request::GetTaskKeyOffer)]
+#[derive(Debug)]
+pub struct GetTaskKeyOfferRequest {
+    pub task_id: ExternalID,
+}
+
+impl GetTaskKeyOfferRequest {
+    pub fn new(task_id: ExternalID) -> Self {
+        Self { task_id }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::GetTaskKeyOffer)]
+#[derive(Debug)]
+pub struct GetTaskKeyOfferResponse {
+    pub offer: TaskKeyOffer,
+}
+
+impl GetTaskKeyOfferResponse {
+    pub fn new(offer: TaskKeyOffer) -> Self {
+        Self { offer }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::PutTaskKeys)]
+#[into_request(TeaclaveFrontendRequest::PutTaskKeys)]
+#[into_request(TeaclaveFrontendV2Request::PutTaskKeys)]
+#[derive(Debug)]
+pub struct PutTaskKeysRequest {
+    pub task_id: ExternalID,
+    pub keys: WrappedTaskKeys,
+}
+
+impl PutTaskKeysRequest {
+    pub fn new(task_id: ExternalID, keys: WrappedTaskKeys) -> Self {
+        Self { task_id, keys }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::PutTaskKeys)]
+#[derive(Debug)]
+pub struct PutTaskKeysResponse;
+
+#[into_request(TeaclaveManagementRequest::GetDataLineage)]
+#[into_request(TeaclaveFrontendRequest::GetDataLineage)]
+#[into_request(TeaclaveFrontendV2Request::GetDataLineage)]
+#[derive(Debug)]
+pub struct GetDataLineageRequest {
+    pub data_id: ExternalID,
+}
+
+impl GetDataLineageRequest {
+    pub fn new(data_id: ExternalID) -> Self {
+        Self { data_id }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::GetDataLineage)]
+#[derive(Debug)]
+pub struct GetDataLineageResponse {
+    pub steps: Vec<LineageStep>,
+}
+
+impl GetDataLineageResponse {
+    pub fn new(steps: Vec<LineageStep>) -> Self {
+        Self { steps }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::RegisterApprovalPolicy)]
+#[into_request(TeaclaveFrontendRequest::RegisterApprovalPolicy)]
+#[into_request(TeaclaveFrontendV2Request::RegisterApprovalPolicy)]
+#[derive(Debug)]
+pub struct RegisterApprovalPolicyRequest {
+    pub data_id: ExternalID,
+    pub function_ids: Vec<ExternalID>,
+    pub creators: UserList,
+}
+
+impl RegisterApprovalPolicyRequest {
+    pub fn new(
+        data_id: ExternalID,
+        function_ids: Vec<ExternalID>,
+        creators: impl Into<UserList>,
+    ) -> Self {
+        Self {
+            data_id,
+            function_ids,
+            creators: creators.into(),
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::RegisterApprovalPolicy)]
+#[derive(Debug)]
+pub struct RegisterApprovalPolicyResponse {
+    pub policy_id: Uuid,
+}
+
+impl RegisterApprovalPolicyResponse {
+    pub fn new(policy_id: Uuid) -> Self {
+        Self { policy_id }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::RevokeApprovalPolicy)]
+#[into_request(TeaclaveFrontendRequest::RevokeApprovalPolicy)]
+#[into_request(TeaclaveFrontendV2Request::RevokeApprovalPolicy)]
+#[derive(Debug)]
+pub struct RevokeApprovalPolicyRequest {
+    pub policy_id: Uuid,
+}
+
+impl RevokeApprovalPolicyRequest {
+    pub fn new(policy_id: Uuid) -> Self {
+        Self { policy_id }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::RevokeApprovalPolicy)]
+#[derive(Debug)]
+pub struct RevokeApprovalPolicyResponse;
+
+#[into_request(TeaclaveManagementRequest::RegisterModel)]
+#[into_request(TeaclaveFrontendRequest::RegisterModel)]
+#[into_request(TeaclaveFrontendV2Request::RegisterModel)]
+#[derive(Debug)]
+pub struct RegisterModelRequest {
+    pub name: String,
+    pub task_id: ExternalID,
+    pub data_id: ExternalID,
+    pub metrics: HashMap<String, f64>,
+}
+
+impl RegisterModelRequest {
+    pub fn new(name: impl ToString, task_id: ExternalID, data_id: ExternalID) -> Self {
+        Self {
+            name: name.to_string(),
+            task_id,
+            data_id,
+            metrics: HashMap::new(),
+        }
+    }
+
+    pub fn metrics(self, metrics: HashMap<String, f64>) -> Self {
+        Self { metrics, ..self }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::RegisterModel)]
+#[derive(Debug)]
+pub struct RegisterModelResponse {
+    pub version: u32,
+}
+
+impl RegisterModelResponse {
+    pub fn new(version: u32) -> Self {
+        Self { version }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::GetModel)]
+#[into_request(TeaclaveFrontendRequest::GetModel)]
+#[into_request(TeaclaveFrontendV2Request::GetModel)]
+#[derive(Debug)]
+pub struct GetModelRequest {
+    pub name: String,
+    pub version: Option<u32>,
+}
+
+impl GetModelRequest {
+    pub fn new(name: impl ToString, version: Option<u32>) -> Self {
+        Self {
+            name: name.to_string(),
+            version,
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::GetModel)]
+#[derive(Debug)]
+pub struct GetModelResponse {
+    pub name: String,
+    pub model: ModelVersion,
+}
+
+impl GetModelResponse {
+    pub fn new(name: impl ToString, model: ModelVersion) -> Self {
+        Self {
+            name: name.to_string(),
+            model,
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::CreateTaskShareLink)]
+#[into_request(TeaclaveFrontendRequest::CreateTaskShareLink)]
+#[into_request(TeaclaveFrontendV2Request::CreateTaskShareLink)]
+#[derive(Debug)]
+pub struct CreateTaskShareLinkRequest {
+    pub task_id: ExternalID,
+    /// Seconds the link is valid for
+    pub expires_in: u64,
+}
+
+impl CreateTaskShareLinkRequest {
+    pub fn new(task_id: ExternalID, expires_in: u64) -> Self {
+        Self {
+            task_id,
+            expires_in,
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::CreateTaskShareLink)]
+#[derive(Debug)]
+pub struct CreateTaskShareLinkResponse {
+    pub token: String,
+    /// Seconds since the Unix epoch
+    pub expires_at: u64,
+}
+
+impl CreateTaskShareLinkResponse {
+    pub fn new(token: impl ToString, expires_at: u64) -> Self {
+        Self {
+            token: token.to_string(),
+            expires_at,
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::GetSharedTask)]
+#[into_request(TeaclaveFrontendRequest::GetSharedTask)]
+#[into_request(TeaclaveFrontendV2Request::GetSharedTask)]
+#[derive(Debug)]
+pub struct GetSharedTaskRequest {
+    pub token: String,
+}
+
+impl GetSharedTaskRequest {
+    pub fn new(token: impl ToString) -> Self {
+        Self {
+            token: token.to_string(),
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::GetSharedTask)]
+#[derive(Debug)]
+pub struct GetSharedTaskResponse {
+    pub task_id: ExternalID,
+    pub progress: TaskProgress,
+    pub expires_at: u64,
+}
+
+impl GetSharedTaskResponse {
+    pub fn new(task_id: ExternalID, progress: TaskProgress, expires_at: u64) -> Self {
+        Self {
+            task_id,
+            progress,
+            expires_at,
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::ArchiveTask)]
+#[into_request(TeaclaveFrontendRequest::ArchiveTask)]
+#[into_request(TeaclaveFrontendV2Request::ArchiveTask)]
+#[derive(Debug)]
+pub struct ArchiveTaskRequest {
+    pub task_id: ExternalID,
+}
+
+impl ArchiveTaskRequest {
+    pub fn new(task_id: ExternalID) -> Self {
+        Self { task_id }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::ArchiveTask)]
+#[derive(Debug)]
+pub struct ArchiveTaskResponse {
+    pub url: Url,
+}
+
+impl ArchiveTaskResponse {
+    pub fn new(url: Url) -> Self {
+        Self { url }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::RestoreArchivedTask)]
+#[into_request(TeaclaveFrontendRequest::RestoreArchivedTask)]
+#[into_request(TeaclaveFrontendV2Request::RestoreArchivedTask)]
+#[derive(Debug)]
+pub struct RestoreArchivedTaskRequest {
+    pub task_id: ExternalID,
+}
+
+impl RestoreArchivedTaskRequest {
+    pub fn new(task_id: ExternalID) -> Self {
+        Self { task_id }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::RestoreArchivedTask)]
+#[derive(Debug)]
+pub struct RestoreArchivedTaskResponse;
+
+#[into_request(TeaclaveManagementRequest::ExportStorageSnapshot)]
+#[into_request(TeaclaveFrontendRequest::ExportStorageSnapshot)]
+#[into_request(TeaclaveFrontendV2Request::ExportStorageSnapshot)]
+#[derive(Debug)]
+pub struct ExportStorageSnapshotRequest {
+    pub name: String,
+}
+
+impl ExportStorageSnapshotRequest {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::ExportStorageSnapshot)]
+#[derive(Debug)]
+pub struct ExportStorageSnapshotResponse {
+    pub snapshots: Vec<StorageSnapshot>,
+}
+
+impl ExportStorageSnapshotResponse {
+    pub fn new(snapshots: Vec<StorageSnapshot>) -> Self {
+        Self { snapshots }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::RestoreStorageSnapshot)]
+#[into_request(TeaclaveFrontendRequest::RestoreStorageSnapshot)]
+#[into_request(TeaclaveFrontendV2Request::RestoreStorageSnapshot)]
+#[derive(Debug)]
+pub struct RestoreStorageSnapshotRequest {
+    pub snapshots: Vec<StorageSnapshot>,
+}
+
+impl RestoreStorageSnapshotRequest {
+    pub fn new(snapshots: Vec<StorageSnapshot>) -> Self {
+        Self { snapshots }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::RestoreStorageSnapshot)]
+#[derive(Debug)]
+pub struct RestoreStorageSnapshotResponse;
+
+#[into_request(TeaclaveManagementRequest::PromoteStorageReplica)]
+#[into_request(TeaclaveFrontendRequest::PromoteStorageReplica)]
+#[into_request(TeaclaveFrontendV2Request::PromoteStorageReplica)]
+#[derive(Debug)]
+pub struct PromoteStorageReplicaRequest {
+    pub name: String,
+}
+
+impl PromoteStorageReplicaRequest {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::PromoteStorageReplica)]
+#[derive(Debug)]
+pub struct PromoteStorageReplicaResponse;
+
+#[into_request(TeaclaveManagementRequest::CancelTask)]
+#[into_request(TeaclaveFrontendRequest::CancelTask)]
+#[into_request(TeaclaveFrontendV2Request::CancelTask)]
+#[derive(Debug)]
+pub struct CancelTaskRequest {
+    pub task_id: ExternalID,
+}
+
+impl CancelTaskRequest {
+    pub fn new(task_id: ExternalID) -> Self {
+        Self { task_id }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::CancelTask)]
+#[derive(Debug)]
+pub struct CancelTaskResponse;
+
+#[into_request(TeaclaveManagementRequest::GetTaskUploadSlots)]
+#[into_request(TeaclaveFrontendRequest::GetTaskUploadSlots)]
+#[into_request(TeaclaveFrontendV2Request::GetTaskUploadSlots)]
+#[derive(Debug)]
+pub struct GetTaskUploadSlotsRequest {
+    pub task_id: ExternalID,
+}
+
+impl GetTaskUploadSlotsRequest {
+    pub fn new(task_id: ExternalID) -> Self {
+        Self { task_id }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::GetTaskUploadSlots)]
+#[derive(Debug)]
+pub struct GetTaskUploadSlotsResponse {
+    pub endpoint: Url,
+    pub tokens: HashMap<String, String>,
+}
+
+impl GetTaskUploadSlotsResponse {
+    pub fn new(endpoint: Url, tokens: HashMap<String, String>) -> Self {
+        Self { endpoint, tokens }
+    }
+
+    /// The url to push the input to.
+    pub fn upload_url(&self, fname: &str) -> Result<Url> {
+        let token = self
+            .tokens
+            .get(fname)
+            .ok_or_else(|| anyhow!("no upload slot for {}", fname))?;
+        Ok(self.endpoint.join(token)?)
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::RejectTask)]
+#[into_request(TeaclaveFrontendRequest::RejectTask)]
+#[into_request(TeaclaveFrontendV2Request::RejectTask)]
+#[derive(Debug)]
+pub struct RejectTaskRequest {
+    pub task_id: ExternalID,
+    pub reason: String,
+}
+
+impl RejectTaskRequest {
+    pub fn new(task_id: ExternalID, reason: impl ToString) -> Self {
+        Self {
+            task_id,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::RejectTask)]
+#[derive(Debug)]
+pub struct RejectTaskResponse;
+
+#[into_request(TeaclaveManagementRequest::GetActivityFeed)]
+#[into_request(TeaclaveFrontendRequest::GetActivityFeed)]
+#[into_request(TeaclaveFrontendV2Request::GetActivityFeed)]
+#[derive(Debug)]
+pub struct GetActivityFeedRequest {
+    pub since: u64,
+}
+
+impl GetActivityFeedRequest {
+    pub fn new(since: u64) -> Self {
+        Self { since }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::GetActivityFeed)]
+#[derive(Debug)]
+pub struct GetActivityFeedResponse {
+    pub events: Vec<ActivityEvent>,
+}
+
+impl GetActivityFeedResponse {
+    pub fn new(events: Vec<ActivityEvent>) -> Self {
+        Self { events }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::GetTaskResultStream)]
+#[into_request(TeaclaveFrontendRequest::GetTaskResultStream)]
+#[into_request(TeaclaveFrontendV2Request::GetTaskResultStream)]
+#[derive(Debug)]
+pub struct GetTaskResultStreamRequest {
+    pub task_id: ExternalID,
+    pub output: Option<String>,
+}
+
+impl GetTaskResultStreamRequest {
+    pub fn new(task_id: ExternalID) -> Self {
+        Self {
+            task_id,
+            output: None,
+        }
+    }
+
+    pub fn output(self, output: impl ToString) -> Self {
+        Self {
+            output: Some(output.to_string()),
+            ..self
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::GetTaskResultStream)]
+#[derive(Debug)]
+pub struct GetTaskResultStreamResponse {
+    pub data: Vec<u8>,
+    pub offset: usize,
+}
+
+impl GetTaskResultStreamResponse {
+    pub fn new(data: Vec<u8>, offset: usize) -> Self {
+        Self { data, offset }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::RegisterTaskTemplate)]
+#[into_request(TeaclaveFrontendRequest::RegisterTaskTemplate)]
+#[into_request(TeaclaveFrontendV2Request::RegisterTaskTemplate)]
+#[derive(Default, Debug)]
+pub struct RegisterTaskTemplateRequest {
+    pub function_id: ExternalID,
+    pub function_arguments: FunctionArguments,
+    pub executor: Executor,
+    pub inputs_ownership: TaskFileOwners,
+    pub outputs_ownership: TaskFileOwners,
+}
+
+impl RegisterTaskTemplateRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn function_id(self, function_id: ExternalID) -> Self {
+        Self {
+            function_id,
+            ..self
+        }
+    }
+
+    pub fn function_arguments(self, function_arguments: impl Into<FunctionArguments>) -> Self {
+        Self {
+            function_arguments: function_arguments.into(),
+            ..self
+        }
+    }
+
+    pub fn executor(self, executor: Executor) -> Self {
+        Self { executor, ..self }
+    }
+
+    pub fn inputs_ownership(self, map: impl Into<TaskFileOwners>) -> Self {
+        Self {
+            inputs_ownership: map.into(),
+            ..self
+        }
+    }
+
+    pub fn outputs_ownership(self, map: impl Into<TaskFileOwners>) -> Self {
+        Self {
+            outputs_ownership: map.into(),
+            ..self
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::RegisterTaskTemplate)]
+#[derive(Debug)]
+pub struct RegisterTaskTemplateResponse {
+    pub template_id: ExternalID,
+}
+
+impl RegisterTaskTemplateResponse {
+    pub fn new(template_id: ExternalID) -> Self {
+        Self { template_id }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::CreateTaskFromTemplate)]
+#[into_request(TeaclaveFrontendRequest::CreateTaskFromTemplate)]
+#[into_request(TeaclaveFrontendV2Request::CreateTaskFromTemplate)]
+#[derive(Debug)]
+pub struct CreateTaskFromTemplateRequest {
+    pub template_id: ExternalID,
+    pub function_arguments: FunctionArguments,
+    pub labels: HashMap<String, String>,
+}
+
+impl CreateTaskFromTemplateRequest {
+    pub fn new(template_id: ExternalID) -> Self {
+        Self {
+            template_id,
+            function_arguments: FunctionArguments::default(),
+            labels: HashMap::new(),
+        }
+    }
+
+    pub fn function_arguments(self, function_arguments: impl Into<FunctionArguments>) -> Self {
+        Self {
+            function_arguments: function_arguments.into(),
+            ..self
+        }
+    }
+
+    pub fn labels(self, labels: HashMap<String, String>) -> Self {
+        Self { labels, ..self }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::CreateTaskFromTemplate)]
+#[derive(Debug)]
+pub struct CreateTaskFromTemplateResponse {
+    pub task_id: ExternalID,
+}
+
+impl CreateTaskFromTemplateResponse {
+    pub fn new(task_id: ExternalID) -> Self {
+        Self { task_id }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::CreateTasksBatch)]
+#[into_request(TeaclaveFrontendRequest::CreateTasksBatch)]
+#[into_request(TeaclaveFrontendV2Request::CreateTasksBatch)]
+#[derive(Debug)]
+pub struct CreateTasksBatchRequest {
+    pub tasks: Vec<CreateTaskRequest>,
+}
+
+impl CreateTasksBatchRequest {
+    pub fn new(tasks: Vec<CreateTaskRequest>) -> Self {
+        Self { tasks }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::CreateTasksBatch)]
+#[derive(Debug)]
+pub struct CreateTasksBatchResponse {
+    pub task_ids: Vec<ExternalID>,
+}
+
+impl CreateTasksBatchResponse {
+    pub fn new(task_ids: Vec<ExternalID>) -> Self {
+        Self { task_ids }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::TestFunction)]
+#[into_request(TeaclaveFrontendRequest::TestFunction)]
+#[into_request(TeaclaveFrontendV2Request::TestFunction)]
+#[derive(Debug)]
+pub struct TestFunctionRequest {
+    pub function_id: ExternalID,
+    pub function_arguments: FunctionArguments,
+    pub executor: Executor,
+    pub sample_inputs: HashMap<String, Vec<u8>>,
+}
+
+impl TestFunctionRequest {
+    pub fn new(function_id: ExternalID, executor: Executor) -> Self {
+        Self {
+            function_id,
+            function_arguments: FunctionArguments::default(),
+            executor,
+            sample_inputs: HashMap::new(),
+        }
+    }
+
+    pub fn function_arguments(self, function_arguments: impl Into<FunctionArguments>) -> Self {
+        Self {
+            function_arguments: function_arguments.into(),
+            ..self
+        }
+    }
+
+    pub fn sample_input(mut self, fname: impl ToString, content: impl Into<Vec<u8>>) -> Self {
+        self.sample_inputs.insert(fname.to_string(), content.into());
+        self
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::TestFunction)]
+#[derive(Debug)]
+pub struct TestFunctionResponse {
+    pub task_id: ExternalID,
+}
+
+impl TestFunctionResponse {
+    pub fn new(task_id: ExternalID) -> Self {
+        Self { task_id }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::RegisterTaskSchedule)]
+#[into_request(TeaclaveFrontendRequest::RegisterTaskSchedule)]
+#[into_request(TeaclaveFrontendV2Request::RegisterTaskSchedule)]
+#[derive(Debug)]
+pub struct RegisterTaskScheduleRequest {
+    pub template_id: ExternalID,
+    pub start_in: u64,
+    pub interval: u64,
+    pub max_runs: Option<u64>,
+    pub inputs: HashMap<String, ExternalID>,
+}
+
+impl RegisterTaskScheduleRequest {
+    pub fn new(template_id: ExternalID, interval: u64) -> Self {
+        Self {
+            template_id,
+            start_in: 0,
+            interval,
+            max_runs: None,
+            inputs: HashMap::new(),
+        }
+    }
+
+    pub fn start_in(self, start_in: u64) -> Self {
+        Self { start_in, ..self }
+    }
+
+    pub fn max_runs(self, max_runs: u64) -> Self {
+        Self {
+            max_runs: Some(max_runs),
+            ..self
+        }
+    }
+
+    pub fn input(mut self, data_name: impl ToString, data_id: ExternalID) -> Self {
+        self.inputs.insert(data_name.to_string(), data_id);
+        self
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::RegisterTaskSchedule)]
+#[derive(Debug)]
+pub struct RegisterTaskScheduleResponse;
+
+#[into_request(TeaclaveManagementRequest::ApproveTaskSchedule)]
+#[into_request(TeaclaveFrontendRequest::ApproveTaskSchedule)]
+#[into_request(TeaclaveFrontendV2Request::ApproveTaskSchedule)]
+#[derive(Debug)]
+pub struct ApproveTaskScheduleRequest {
+    pub template_id: ExternalID,
+}
+
+impl ApproveTaskScheduleRequest {
+    pub fn new(template_id: ExternalID) -> Self {
+        Self { template_id }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::ApproveTaskSchedule)]
+#[derive(Debug)]
+pub struct ApproveTaskScheduleResponse;
+
+#[into_request(TeaclaveManagementRequest::CancelTaskSchedule)]
+#[into_request(TeaclaveFrontendRequest::CancelTaskSchedule)]
+#[into_request(TeaclaveFrontendV2Request::CancelTaskSchedule)]
+#[derive(Debug)]
+pub struct CancelTaskScheduleRequest {
+    pub template_id: ExternalID,
+}
+
+impl CancelTaskScheduleRequest {
+    pub fn new(template_id: ExternalID) -> Self {
+        Self { template_id }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::CancelTaskSchedule)]
+#[derive(Debug)]
+pub struct CancelTaskScheduleResponse;
+
+#[into_request(TeaclaveManagementRequest::GetTaskSchedule)]
+#[into_request(TeaclaveFrontendRequest::GetTaskSchedule)]
+#[into_request(TeaclaveFrontendV2Request::GetTaskSchedule)]
+#[derive(Debug)]
+pub struct GetTaskScheduleRequest {
+    pub template_id: ExternalID,
+}
+
+impl GetTaskScheduleRequest {
+    pub fn new(template_id: ExternalID) -> Self {
+        Self { template_id }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::GetTaskSchedule)]
+#[derive(Debug)]
+pub struct GetTaskScheduleResponse {
+    pub template_id: ExternalID,
+    pub spec: ScheduleSpec,
+    pub inputs: HashMap<String, ExternalID>,
+    pub participants: UserList,
+    pub approved_users: UserList,
+    pub approval_receipts: Vec<ApprovalReceipt>,
+    pub task_ids: Vec<ExternalID>,
+    pub runs: u64,
+    pub canceled: bool,
+}
+
+#[into_request(TeaclaveManagementRequest::EstimateTask)]
+#[into_request(TeaclaveFrontendRequest::EstimateTask)]
+#[into_request(TeaclaveFrontendV2Request::EstimateTask)]
+#[derive(Debug)]
+pub struct EstimateTaskRequest {
+    pub function_id: ExternalID,
+    /// Total bytes of the input files
+    pub input_size: u64,
+    pub priority: TaskPriority,
+}
+
+impl EstimateTaskRequest {
+    pub fn new(function_id: ExternalID) -> Self {
+        Self {
+            function_id,
+            input_size: 0,
+            priority: TaskPriority::default(),
+        }
+    }
+
+    pub fn input_size(self, input_size: u64) -> Self {
+        Self { input_size, ..self }
+    }
+
+    pub fn priority(self, priority: TaskPriority) -> Self {
+        Self { priority, ..self }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::EstimateTask)]
+#[derive(Debug)]
+pub struct EstimateTaskResponse {
+    /// Milliseconds, zero if the function has never run
+    pub run_time: u64,
+    /// Milliseconds
+    pub queue_wait: u64,
+    /// Successful runs of the function the run time is estimated from
+    pub runs: u64,
+    pub live_workers: u32,
+    /// Seconds since the Unix epoch when the queues were last swept
+    pub backlog_taken_at: u64,
+}
+
+#[into_request(TeaclaveFrontendRequest::GetPlatformInfo)]
+#[into_request(TeaclaveFrontendV2Request::GetPlatformInfo)]
+#[derive(Debug, Default)]
+pub struct GetPlatformInfoRequest {}
+
+impl GetPlatformInfoRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[into_request(TeaclaveFrontendResponse::GetPlatformInfo)]
+#[derive(Debug, Clone)]
+pub struct GetPlatformInfoResponse {
+    /// Content of the enclave info TOML the signatures are over
+    pub enclave_info: Vec<u8>,
+    pub auditor_signatures: Vec<Vec<u8>>,
+}
+
+impl GetPlatformInfoResponse {
+    pub fn new(enclave_info: Vec<u8>, auditor_signatures: Vec<Vec<u8>>) -> Self {
+        Self {
+            enclave_info,
+            auditor_signatures,
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementRequest::RegisterSigningKey)]
+#[into_request(TeaclaveFrontendRequest::RegisterSigningKey)]
+#[into_request(TeaclaveFrontendV2Request::RegisterSigningKey)]
+#[derive(Debug)]
+pub struct RegisterSigningKeyRequest {
+    /// Ed25519 public key
+    pub public_key: Vec<u8>,
+}
+
+impl RegisterSigningKeyRequest {
+    pub fn new(public_key: Vec<u8>) -> Self {
+        Self { public_key }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::RegisterSigningKey)]
+#[derive(Debug)]
+pub struct RegisterSigningKeyResponse;
+
+impl std::convert::TryFrom<proto::RegisterInputFileRequest> for RegisterInputFileRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::RegisterInputFileRequest) -> Result<Self> {
+        let url = Url::parse(&proto.url)?;
+        let cmac = FileAuthTag::from_bytes(&proto.cmac)?;
+        let ret = match proto.key_broker {
+            Some(key_broker) => {
+                ensure!(proto.crypto_info.is_none(), "crypto_info with key_broker");
+                RegisterInputFileRequest::with_key_broker(url, cmac, key_broker.into())
+            }
+            None => {
+                let crypto_info: FileCrypto = proto
+                    .crypto_info
+                    .ok_or_else(|| anyhow!("missing crypto_info"))?
+                    .try_into()?;
+                RegisterInputFileRequest::new(url, cmac, crypto_info)
+            }
+        };
+        Ok(ret)
+    }
+}
+
+impl From<RegisterInputFileRequest> for proto::RegisterInputFileRequest {
+    fn from(request: RegisterInputFileRequest) -> Self {
+        let (crypto_info, key_broker) = match request.key_broker {
+            Some(key_broker) => (None, Some(key_broker.into())),
+            None => (Some(request.crypto_info.into()), None),
+        };
+        Self {
+            url: request.url.into_string(),
+            cmac: request.cmac.to_bytes(),
+            crypto_info,
+            key_broker,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::UpdateInputFileRequest> for UpdateInputFileRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::UpdateInputFileRequest) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        let url = Url::parse(&proto.url)?;
+
+        Ok(UpdateInputFileRequest { data_id, url })
+    }
+}
+
+impl From<UpdateInputFileRequest> for proto::UpdateInputFileRequest {
+    fn from(request: UpdateInputFileRequest) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+            url: request.url.into_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RotateInputFileKeyRequest> for RotateInputFileKeyRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::RotateInputFileKeyRequest) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        let url = Url::parse(&proto.url)?;
+        let cmac = FileAuthTag::from_bytes(&proto.cmac)?;
+        let crypto_info = proto
+            .crypto_info
+            .ok_or_else(|| anyhow!("missing crypto_info"))?
+            .try_into()?;
+        Ok(RotateInputFileKeyRequest {
+            data_id,
+            url,
+            cmac,
+            crypto_info,
+        })
+    }
+}
+
+impl From<RotateInputFileKeyRequest> for proto::RotateInputFileKeyRequest {
+    fn from(request: RotateInputFileKeyRequest) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+            url: request.url.into_string(),
+            cmac: request.cmac.to_bytes(),
+            crypto_info: Some(request.crypto_info.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterInputFileResponse> for RegisterInputFileResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::RegisterInputFileResponse) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        Ok(Self { data_id })
+    }
+}
+
+impl From<RegisterInputFileResponse> for proto::RegisterInputFileResponse {
+    fn from(request: RegisterInputFileResponse) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::UpdateInputFileResponse> for UpdateInputFileResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::UpdateInputFileResponse) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        Ok(Self { data_id })
+    }
+}
+
+impl From<UpdateInputFileResponse> for proto::UpdateInputFileResponse {
+    fn from(request: UpdateInputFileResponse) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RotateInputFileKeyResponse> for RotateInputFileKeyResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::RotateInputFileKeyResponse) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        let in_flight_task_ids = proto
+            .in_flight_task_ids
+            .into_iter()
+            .map(|task_id| task_id.try_into())
+            .collect::<Result<Vec<ExternalID>>>()?;
+        Ok(Self {
+            data_id,
+            in_flight_task_ids,
+        })
+    }
+}
+
+impl From<RotateInputFileKeyResponse> for proto::RotateInputFileKeyResponse {
+    fn from(response: RotateInputFileKeyResponse) -> Self {
+        Self {
+            data_id: response.data_id.to_string(),
+            in_flight_task_ids: response
+                .in_flight_task_ids
+                .into_iter()
+                .map(|task_id| task_id.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterOutputFileRequest> for RegisterOutputFileRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::RegisterOutputFileRequest) -> Result<Self> {
+        // An empty url defers it to UpdateOutputUrlRequest.
+        let url = match proto.url.as_str() {
+            "" => None,
+            url => Some(Url::parse(url)?),
+        };
+        let ret = Self {
+            url,
+            crypto_info: proto
+                .crypto_info
+                .ok_or_else(|| anyhow!("missing crypto_info"))?
+                .try_into()?,
+            credential: proto.credential.map(|c| c.try_into()).transpose()?,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<RegisterOutputFileRequest> for proto::RegisterOutputFileRequest {
+    fn from(request: RegisterOutputFileRequest) -> Self {
+        Self {
+            url: request.url.map_or_else(String::new, Url::into_string),
+            crypto_info: Some(request.crypto_info.into()),
+            credential: request.credential.map(|c| c.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::UpdateOutputFileRequest> for UpdateOutputFileRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::UpdateOutputFileRequest) -> Result<Self> {
+        let ret = Self {
+            data_id: proto.data_id.try_into()?,
+            url: Url::parse(&proto.url)?,
+            credential: proto.credential.map(|c| c.try_into()).transpose()?,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<UpdateOutputFileRequest> for proto::UpdateOutputFileRequest {
+    fn from(request: UpdateOutputFileRequest) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+            url: request.url.into_string(),
+            credential: request.credential.map(|c| c.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterOutputFileResponse> for RegisterOutputFileResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::RegisterOutputFileResponse) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        Ok(Self { data_id })
+    }
+}
+
+impl From<RegisterOutputFileResponse> for proto::RegisterOutputFileResponse {
+    fn from(request: RegisterOutputFileResponse) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::UpdateOutputFileResponse> for UpdateOutputFileResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::UpdateOutputFileResponse) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        Ok(Self { data_id })
+    }
+}
+
+impl From<UpdateOutputFileResponse> for proto::UpdateOutputFileResponse {
+    fn from(request: UpdateOutputFileResponse) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterFusionOutputRequest> for RegisterFusionOutputRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::RegisterFusionOutputRequest) -> Result<Self> {
+        let ret = Self {
+            owner_list: OwnerList::new(proto.owner_list),
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<RegisterFusionOutputRequest> for proto::RegisterFusionOutputRequest {
+    fn from(request: RegisterFusionOutputRequest) -> Self {
+        Self {
+            owner_list: request.owner_list.into(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterFusionOutputResponse> for RegisterFusionOutputResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::RegisterFusionOutputResponse) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        Ok(Self { data_id })
+    }
+}
+
+impl From<RegisterFusionOutputResponse> for proto::RegisterFusionOutputResponse {
+    fn from(request: RegisterFusionOutputResponse) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterInputFromOutputRequest>
+    for RegisterInputFromOutputRequest
+{
+    type Error = Error;
+
+    fn try_from(proto: proto::RegisterInputFromOutputRequest) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        let ret = Self { data_id };
+
+        Ok(ret)
+    }
+}
+
+impl From<RegisterInputFromOutputRequest> for proto::RegisterInputFromOutputRequest {
+    fn from(request: RegisterInputFromOutputRequest) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterInputFromOutputResponse>
+    for RegisterInputFromOutputResponse
+{
+    type Error = Error;
+
+    fn try_from(proto: proto::RegisterInputFromOutputResponse) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        Ok(Self { data_id })
+    }
+}
+
+impl From<RegisterInputFromOutputResponse> for proto::RegisterInputFromOutputResponse {
+    fn from(request: RegisterInputFromOutputResponse) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetInputFileRequest> for GetInputFileRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetInputFileRequest) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        let ret = Self { data_id };
+
+        Ok(ret)
+    }
+}
+
+impl From<GetInputFileRequest> for proto::GetInputFileRequest {
+    fn from(request: GetInputFileRequest) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetInputFileResponse> for GetInputFileResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetInputFileResponse) -> Result<Self> {
+        Ok(Self {
+            owner: OwnerList::new(proto.owner),
+            cmac: FileAuthTag::from_bytes(&proto.cmac)?,
+        })
+    }
+}
+
+impl From<GetInputFileResponse> for proto::GetInputFileResponse {
+    fn from(request: GetInputFileResponse) -> Self {
+        Self {
+            owner: request.owner.into(),
+            cmac: request.cmac.to_bytes(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::DeleteInputFileRequest> for DeleteInputFileRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::DeleteInputFileRequest) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        let ret = Self { data_id };
+
+        Ok(ret)
+    }
+}
+
+impl From<DeleteInputFileRequest> for proto::DeleteInputFileRequest {
+    fn from(request: DeleteInputFileRequest) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::DeleteInputFileResponse> for DeleteInputFileResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::DeleteInputFileResponse) -> Result<Self> {
+        Ok(DeleteInputFileResponse)
+    }
+}
+
+impl From<DeleteInputFileResponse> for proto::DeleteInputFileResponse {
+    fn from(_response: DeleteInputFileResponse) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::DeleteOutputFileRequest> for DeleteOutputFileRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::DeleteOutputFileRequest) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        let ret = Self { data_id };
+
+        Ok(ret)
+    }
+}
+
+impl From<DeleteOutputFileRequest> for proto::DeleteOutputFileRequest {
+    fn from(request: DeleteOutputFileRequest) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::DeleteOutputFileResponse> for DeleteOutputFileResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::DeleteOutputFileResponse) -> Result<Self> {
+        Ok(DeleteOutputFileResponse)
+    }
+}
+
+impl From<DeleteOutputFileResponse> for proto::DeleteOutputFileResponse {
+    fn from(_response: DeleteOutputFileResponse) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::GetOutputFileRequest> for GetOutputFileRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetOutputFileRequest) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        let ret = Self { data_id };
+
+        Ok(ret)
+    }
+}
+
+impl From<GetOutputFileRequest> for proto::GetOutputFileRequest {
+    fn from(request: GetOutputFileRequest) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetOutputFileResponse> for GetOutputFileResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetOutputFileResponse) -> Result<Self> {
+        let cmac = {
+            if proto.cmac.is_empty() {
+                None
+            } else {
+                Some(FileAuthTag::from_bytes(&proto.cmac)?)
+            }
+        };
+
+        Ok(Self {
+            owner: OwnerList::new(proto.owner),
+            cmac,
+        })
+    }
+}
+
+impl From<GetOutputFileResponse> for proto::GetOutputFileResponse {
+    fn from(request: GetOutputFileResponse) -> Self {
+        Self {
+            owner: request.owner.into(),
+            cmac: request.cmac.map_or_else(Vec::new, |cmac| cmac.to_bytes()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::FunctionInput> for FunctionInput {
+    type Error = Error;
+
+    fn try_from(proto: proto::FunctionInput) -> Result<Self> {
+        let ret = Self {
+            name: proto.name,
+            description: proto.description,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<FunctionInput> for proto::FunctionInput {
+    fn from(input: FunctionInput) -> Self {
+        Self {
+            name: input.name,
+            description: input.description,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::FunctionOutput> for FunctionOutput {
+    type Error = Error;
+
+    fn try_from(proto: proto::FunctionOutput) -> Result<Self> {
+        let ret = Self {
+            name: proto.name,
+            description: proto.description,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<FunctionOutput> for proto::FunctionOutput {
+    fn from(output: FunctionOutput) -> Self {
+        Self {
+            name: output.name,
+            description: output.description,
+        }
+    }
+}
+
+impl From<proto::ResourceProfile> for ResourceProfile {
+    fn from(proto: proto::ResourceProfile) -> Self {
+        Self {
+            heap_size: proto.heap_size,
+            input_size: proto.input_size,
+            timeout: proto.timeout,
+        }
+    }
+}
+
+impl From<ResourceProfile> for proto::ResourceProfile {
+    fn from(profile: ResourceProfile) -> Self {
+        Self {
+            heap_size: profile.heap_size,
+            input_size: profile.input_size,
+            timeout: profile.timeout,
+        }
+    }
+}
+
+impl From<proto::ResourceLimits> for ResourceLimits {
+    fn from(proto: proto::ResourceLimits) -> Self {
+        Self {
+            max_memory_bytes: proto.max_memory_bytes,
+            max_cpu_seconds: proto.max_cpu_seconds,
+            max_output_bytes: proto.max_output_bytes,
+        }
+    }
+}
+
+impl From<ResourceLimits> for proto::ResourceLimits {
+    fn from(limits: ResourceLimits) -> Self {
+        Self {
+            max_memory_bytes: limits.max_memory_bytes,
+            max_cpu_seconds: limits.max_cpu_seconds,
+            max_output_bytes: limits.max_output_bytes,
+        }
+    }
+}
+
+impl From<proto::RetryPolicy> for RetryPolicy {
+    fn from(proto: proto::RetryPolicy) -> Self {
+        Self {
+            max_retries: proto.max_retries,
+            backoff: proto.backoff,
+        }
+    }
+}
+
+impl From<RetryPolicy> for proto::RetryPolicy {
+    fn from(policy: RetryPolicy) -> Self {
+        Self {
+            max_retries: policy.max_retries,
+            backoff: policy.backoff,
+        }
+    }
+}
+
+impl From<proto::FunctionCapabilities> for FunctionCapabilities {
+    fn from(proto: proto::FunctionCapabilities) -> Self {
+        Self {
+            scratch_disk: proto.scratch_disk,
+            egress: proto.egress,
+            max_output_size: proto.max_output_size,
+        }
+    }
+}
+
+impl From<FunctionCapabilities> for proto::FunctionCapabilities {
+    fn from(capabilities: FunctionCapabilities) -> Self {
+        Self {
+            scratch_disk: capabilities.scratch_disk,
+            egress: capabilities.egress,
+            max_output_size: capabilities.max_output_size,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::PostProcessingStep> for PostProcessingStep {
+    type Error = Error;
+
+    fn try_from(proto: proto::PostProcessingStep) -> Result<Self> {
+        let step = match proto.step.as_str() {
+            "round" => PostProcessingStep::round(proto.decimals),
+            "top_k" => PostProcessingStep::top_k(proto.k),
+            "validate_schema" => PostProcessingStep::validate_schema(
+                serde_json::from_str(&proto.schema)
+                    .map_err(|_| anyhow!("Invalid post-processing schema"))?,
+            ),
+            _ => bail!("Invalid post-processing step: {}", proto.step),
+        };
+        Ok(step)
+    }
+}
+
+impl From<PostProcessingStep> for proto::PostProcessingStep {
+    fn from(step: PostProcessingStep) -> Self {
+        let mut proto = Self {
+            step: step.name().to_string(),
+            ..Default::default()
+        };
+        match step {
+            PostProcessingStep::Round { decimals } => proto.decimals = decimals,
+            PostProcessingStep::TopK { k } => proto.k = k,
+            PostProcessingStep::ValidateSchema { schema } => proto.schema = schema.to_string(),
+        }
+        proto
+    }
+}
+
+fn from_proto_post_processing(
+    proto: Vec<proto::PostProcessingStep>,
+) -> Result<Vec<PostProcessingStep>> {
+    proto.into_iter().map(TryInto::try_into).collect()
+}
+
+fn to_proto_post_processing(steps: Vec<PostProcessingStep>) -> Vec<proto::PostProcessingStep> {
+    steps.into_iter().map(Into::into).collect()
+}
+
+impl std::convert::TryFrom<proto::RequestSignature> for RequestSignature {
+    type Error = Error;
+
+    fn try_from(proto: proto::RequestSignature) -> Result<Self> {
+        let ret = RequestSignature {
+            user_id: proto.user_id.into(),
+            action: proto.action.as_str().try_into()?,
+            digest: proto.digest,
+            signature: proto.signature,
+            public_key: proto.public_key,
+            timestamp: proto.timestamp,
+        };
+        Ok(ret)
+    }
+}
+
+impl From<RequestSignature> for proto::RequestSignature {
+    fn from(signature: RequestSignature) -> Self {
+        proto::RequestSignature {
+            user_id: signature.user_id.to_string(),
+            action: signature.action.to_string(),
+            digest: signature.digest,
+            signature: signature.signature,
+            public_key: signature.public_key,
+            timestamp: signature.timestamp,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterFunctionRequest> for RegisterFunctionRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::RegisterFunctionRequest) -> Result<Self> {
+        let inputs: Result<Vec<FunctionInput>> = proto
+            .inputs
+            .into_iter()
+            .map(FunctionInput::try_from)
+            .collect();
+        let outputs: Result<Vec<FunctionOutput>> = proto
+            .outputs
+            .into_iter()
+            .map(FunctionOutput::try_from)
+            .collect();
+        let executor_type = proto.executor_type.try_into()?;
+
+        let ret = Self {
+            name: proto.name,
+            description: proto.description,
+            executor_type,
+            payload: proto.payload,
+            public: proto.public,
+            arguments: proto.arguments,
+            inputs: inputs?,
+            outputs: outputs?,
+            resource_profile: proto.resource_profile.map(Into::into).unwrap_or_default(),
+            entry_points: proto.entry_points,
+            capabilities: proto.capabilities.map(Into::into),
+            worker_measurements: proto.worker_measurements,
+            post_processing: from_proto_post_processing(proto.post_processing)?,
+        };
+        Ok(ret)
+    }
+}
+
+impl From<RegisterFunctionRequest> for proto::RegisterFunctionRequest {
+    fn from(request: RegisterFunctionRequest) -> Self {
+        let inputs: Vec<proto::FunctionInput> = request
+            .inputs
+            .into_iter()
+            .map(proto::FunctionInput::from)
+            .collect();
+        let outputs: Vec<proto::FunctionOutput> = request
+            .outputs
+            .into_iter()
+            .map(proto::FunctionOutput::from)
+            .collect();
+
+        Self {
+            name: request.name,
+            description: request.description,
+            executor_type: request.executor_type.into(),
+            payload: request.payload,
+            public: request.public,
+            arguments: request.arguments,
+            inputs,
+            outputs,
+            resource_profile: Some(request.resource_profile.into()),
+            entry_points: request.entry_points,
+            capabilities: request.capabilities.map(Into::into),
+            worker_measurements: request.worker_measurements,
+            post_processing: to_proto_post_processing(request.post_processing),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterFunctionResponse> for RegisterFunctionResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::RegisterFunctionResponse) -> Result<Self> {
+        let function_id = proto.function_id.try_into()?;
+        let ret = Self { function_id };
+
+        Ok(ret)
+    }
+}
+
+impl From<RegisterFunctionResponse> for proto::RegisterFunctionResponse {
+    fn from(response: RegisterFunctionResponse) -> Self {
+        Self {
+            function_id: response.function_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetFunctionRequest> for GetFunctionRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetFunctionRequest) -> Result<Self> {
+        let function_id = proto.function_id.try_into()?;
+        let ret = Self { function_id };
+
+        Ok(ret)
+    }
+}
+
+impl From<GetFunctionRequest> for proto::GetFunctionRequest {
+    fn from(request: GetFunctionRequest) -> Self {
+        Self {
+            function_id: request.function_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetFunctionResponse> for GetFunctionResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetFunctionResponse) -> Result<Self> {
+        let inputs: Result<Vec<FunctionInput>> = proto
+            .inputs
+            .into_iter()
+            .map(FunctionInput::try_from)
+            .collect();
+        let outputs: Result<Vec<FunctionOutput>> = proto
+            .outputs
+            .into_iter()
+            .map(FunctionOutput::try_from)
+            .collect();
+        let executor_type = proto.executor_type.try_into()?;
+
+        let ret = Self {
+            name: proto.name,
+            description: proto.description,
+            owner: proto.owner.into(),
+            executor_type,
+            payload: proto.payload,
+            public: proto.public,
+            arguments: proto.arguments,
+            inputs: inputs?,
+            outputs: outputs?,
+            resource_profile: proto.resource_profile.map(Into::into).unwrap_or_default(),
+            entry_points: proto.entry_points,
+            capabilities: proto.capabilities.map(Into::into),
+            worker_measurements: proto.worker_measurements,
+            post_processing: from_proto_post_processing(proto.post_processing)?,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<GetFunctionResponse> for proto::GetFunctionResponse {
+    fn from(response: GetFunctionResponse) -> Self {
+        let inputs: Vec<proto::FunctionInput> = response
+            .inputs
+            .into_iter()
+            .map(proto::FunctionInput::from)
+            .collect();
+        let outputs: Vec<proto::FunctionOutput> = response
+            .outputs
+            .into_iter()
+            .map(proto::FunctionOutput::from)
+            .collect();
+
+        Self {
+            name: response.name,
+            description: response.description,
+            owner: response.owner.into(),
+            executor_type: response.executor_type.into(),
+            payload: response.payload,
+            public: response.public,
+            arguments: response.arguments,
+            inputs,
+            outputs,
+            resource_profile: Some(response.resource_profile.into()),
+            entry_points: response.entry_points,
+            capabilities: response.capabilities.map(Into::into),
+            worker_measurements: response.worker_measurements,
+            post_processing: to_proto_post_processing(response.post_processing),
+        }
+    }
+}
+
+fn from_proto_ownership(proto: Vec<proto::OwnerList>) -> TaskFileOwners {
+    proto
+        .into_iter()
+        .map(|ol| (ol.data_name, ol.uids))
+        .collect()
+}
+
+fn to_proto_ownership(ownership: TaskFileOwners) -> Vec<proto::OwnerList> {
+    ownership
+        .into_iter()
+        .map(|(name, ol)| proto::OwnerList {
+            data_name: name,
+            uids: ol.into(),
+        })
+        .collect()
+}
+
+impl std::convert::TryFrom<proto::CreateTaskRequest> for CreateTaskRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::CreateTaskRequest) -> Result<Self> {
+        let function_arguments: FunctionArguments = proto.function_arguments.try_into()?;
+        let inputs_ownership = from_proto_ownership(proto.inputs_ownership);
+        let outputs_ownership = from_proto_ownership(proto.outputs_ownership);
+        let function_id = proto.function_id.try_into()?;
+        let executor = proto.executor.try_into()?;
+
+        let ret = Self {
+            function_id,
+            function_arguments,
+            executor,
+            inputs_ownership,
+            outputs_ownership,
+            labels: proto.labels,
+            workflow_id: Some(proto.workflow_id).filter(|id| !id.is_empty()),
+            entry_point: Some(proto.entry_point).filter(|name| !name.is_empty()),
+            reuse_result: proto.reuse_result,
+            key_exchange: proto.key_exchange,
+            expires_in: Some(proto.expires_in).filter(|secs| *secs > 0),
+            approval_window: Some(proto.approval_window).filter(|secs| *secs > 0),
+            input_dependencies: from_proto_dependencies(proto.input_dependencies)?,
+            priority: from_proto_priority(&proto.priority)?,
+            resource_limits: proto.resource_limits.map(Into::into).unwrap_or_default(),
+            timeout: Some(proto.timeout).filter(|secs| *secs > 0),
+            retry_policy: proto.retry_policy.map(Into::into).unwrap_or_default(),
+            worker_measurements: proto.worker_measurements,
+            worker_signers: proto.worker_signers,
+            federation_peer: Some(proto.federation_peer).filter(|name| !name.is_empty()),
+            signature: proto.signature,
+        };
+        Ok(ret)
+    }
+}
+
+impl From<CreateTaskRequest> for proto::CreateTaskRequest {
+    fn from(request: CreateTaskRequest) -> Self {
+        let function_arguments = request.function_arguments.into_string();
+        let inputs_ownership = to_proto_ownership(request.inputs_ownership);
+        let outputs_ownership = to_proto_ownership(request.outputs_ownership);
+
+        Self {
+            function_id: request.function_id.to_string(),
+            function_arguments,
+            executor: request.executor.to_string(),
+            inputs_ownership,
+            outputs_ownership,
+            labels: request.labels,
+            workflow_id: request.workflow_id.unwrap_or_default(),
+            entry_point: request.entry_point.unwrap_or_default(),
+            reuse_result: request.reuse_result,
+            key_exchange: request.key_exchange,
+            expires_in: request.expires_in.unwrap_or_default(),
+            approval_window: request.approval_window.unwrap_or_default(),
+            input_dependencies: to_proto_dependencies(request.input_dependencies),
+            priority: request.priority.to_string(),
+            resource_limits: Some(request.resource_limits.into()),
+            timeout: request.timeout.unwrap_or_default(),
+            retry_policy: Some(request.retry_policy.into()),
+            worker_measurements: request.worker_measurements,
+            worker_signers: request.worker_signers,
+            federation_peer: request.federation_peer.unwrap_or_default(),
+            signature: request.signature,
+        }
+    }
+}
+
+// Tasks are of the normal priority unless specified.
+fn from_proto_priority(priority: &str) -> Result<TaskPriority> {
+    if priority.is_empty() {
+        return Ok(TaskPriority::default());
+    }
+    priority.try_into()
+}
+
+impl std::convert::TryFrom<proto::CreateTaskResponse> for CreateTaskResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::CreateTaskResponse) -> Result<Self> {
+        let task_id = proto.task_id.try_into()?;
+        let ret = Self { task_id };
+
+        Ok(ret)
+    }
+}
+
+impl From<CreateTaskResponse> for proto::CreateTaskResponse {
+    fn from(response: CreateTaskResponse) -> Self {
+        Self {
+            task_id: response.task_id.to_string(),
+        }
+    }
+}
+
+fn to_proto_file_ids(map: HashMap<String, ExternalID>) -> Vec<proto::DataMap> {
+    map.into_iter()
+        .map(|(name, ext_id)| proto::DataMap {
+            data_name: name,
+            data_id: ext_id.to_string(),
+        })
+        .collect()
+}
+
+fn from_proto_file_ids(vector: Vec<proto::DataMap>) -> Result<HashMap<String, ExternalID>> {
+    vector
+        .into_iter()
+        .map(|item| {
+            item.data_id
+                .clone()
+                .try_into()
+                .map(|ext_id| (item.data_name, ext_id))
+        })
+        .collect()
+}
+
+fn to_proto_dependencies(
+    map: HashMap<String, TaskOutputReference>,
+) -> HashMap<String, proto::TaskOutputReference> {
+    map.into_iter()
+        .map(|(name, reference)| {
+            let reference = proto::TaskOutputReference {
+                task_id: reference.task_id.to_string(),
+                output: reference.output,
+            };
+            (name, reference)
+        })
+        .collect()
+}
+
+fn from_proto_dependencies(
+    map: HashMap<String, proto::TaskOutputReference>,
+) -> Result<HashMap<String, TaskOutputReference>> {
+    map.into_iter()
+        .map(|(name, reference)| {
+            let task_id = reference.task_id.try_into()?;
+            Ok((name, TaskOutputReference::new(task_id, reference.output)))
+        })
+        .collect()
+}
+
+impl std::convert::TryFrom<proto::GetTaskRequest> for GetTaskRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetTaskRequest) -> Result<Self> {
+        let task_id = proto.task_id.try_into()?;
+        let ret = Self { task_id };
+
+        Ok(ret)
+    }
+}
+
+impl From<GetTaskRequest> for proto::GetTaskRequest {
+    fn from(request: GetTaskRequest) -> Self {
+        Self {
+            task_id: request.task_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetTaskResponse> for GetTaskResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetTaskResponse) -> Result<Self> {
+        let function_arguments: FunctionArguments = proto.function_arguments.try_into()?;
+        let inputs_ownership = from_proto_ownership(proto.inputs_ownership);
+        let outputs_ownership = from_proto_ownership(proto.outputs_ownership);
+        let assigned_inputs = from_proto_file_ids(proto.assigned_inputs)?;
+        let assigned_outputs = from_proto_file_ids(proto.assigned_outputs)?;
+        let status = i32_to_task_status(proto.status)?;
+        let function_id = proto.function_id.try_into()?;
+        let task_id = proto.task_id.try_into()?;
+        let result = proto.result.try_into()?;
+        let approval_receipts = proto
+            .approval_receipts
+            .into_iter()
+            .map(|receipt| receipt.try_into())
+            .collect::<Result<Vec<ApprovalReceipt>>>()?;
+
+        let ret = Self {
+            task_id,
+            creator: proto.creator.into(),
+            function_id,
+            function_owner: proto.function_owner.into(),
+            function_arguments,
+            inputs_ownership,
+            outputs_ownership,
+            participants: UserList::new(proto.participants),
+            approved_users: UserList::new(proto.approved_users),
+            assigned_inputs,
+            assigned_outputs,
+            status,
+            result,
+            labels: proto.labels,
+            approval_receipts,
+            entry_point: Some(proto.entry_point).filter(|name| !name.is_empty()),
+            reuse_result: proto.reuse_result,
+            function_capabilities: proto.function_capabilities.map(Into::into),
+            key_exchange: proto.key_exchange,
+            worker_measurements: proto.worker_measurements,
+            expires_at: Some(proto.expires_at).filter(|secs| *secs > 0),
+            rejected_by: Some(proto.rejected_by)
+                .filter(|id| !id.is_empty())
+                .map(Into::into),
+            rejection_reason: proto.rejection_reason,
+            approval_expires_at: Some(proto.approval_expires_at).filter(|secs| *secs > 0),
+            input_dependencies: from_proto_dependencies(proto.input_dependencies)?,
+            staging_deferred: proto.staging_deferred,
+            priority: from_proto_priority(&proto.priority)?,
+            resource_limits: proto.resource_limits.map(Into::into).unwrap_or_default(),
+            timeout: Some(proto.timeout).filter(|secs| *secs > 0),
+            retry_policy: proto.retry_policy.map(Into::into).unwrap_or_default(),
+            retry_count: proto.retry_count,
+            worker_signers: proto.worker_signers,
+            federation_peer: Some(proto.federation_peer).filter(|name| !name.is_empty()),
+            function_post_processing: from_proto_post_processing(proto.function_post_processing)?,
+            spec_hash: proto.spec_hash,
+            request_signatures: proto
+                .request_signatures
+                .into_iter()
+                .map(|signature| signature.try_into())
+                .collect::<Result<_>>()?,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<GetTaskResponse> for proto::GetTaskResponse {
+    fn from(response: GetTaskResponse) -> Self {
+        let function_arguments = response.function_arguments.into_string();
+        let inputs_ownership = to_proto_ownership(response.inputs_ownership);
+        let outputs_ownership = to_proto_ownership(response.outputs_ownership);
+        let assigned_inputs = to_proto_file_ids(response.assigned_inputs);
+        let assigned_outputs = to_proto_file_ids(response.assigned_outputs);
+        let status = i32_from_task_status(response.status);
+        Self {
+            task_id: response.task_id.to_string(),
+            creator: response.creator.to_string(),
+            function_id: response.function_id.to_string(),
+            function_owner: response.function_owner.to_string(),
+            function_arguments,
+            inputs_ownership,
+            outputs_ownership,
+            participants: response.participants.into(),
+            approved_users: response.approved_users.into(),
+            assigned_inputs,
+            assigned_outputs,
+            status,
+            result: Some(response.result.into()),
+            labels: response.labels,
+            approval_receipts: response
+                .approval_receipts
+                .into_iter()
+                .map(|receipt| receipt.into())
+                .collect(),
+            entry_point: response.entry_point.unwrap_or_default(),
+            reuse_result: response.reuse_result,
+            function_capabilities: response.function_capabilities.map(Into::into),
+            key_exchange: response.key_exchange,
+            worker_measurements: response.worker_measurements,
+            expires_at: response.expires_at.unwrap_or_default(),
+            rejected_by: response
+                .rejected_by
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            rejection_reason: response.rejection_reason,
+            approval_expires_at: response.approval_expires_at.unwrap_or_default(),
+            input_dependencies: to_proto_dependencies(response.input_dependencies),
+            staging_deferred: response.staging_deferred,
+            priority: response.priority.to_string(),
+            resource_limits: Some(response.resource_limits.into()),
+            timeout: response.timeout.unwrap_or_default(),
+            retry_policy: Some(response.retry_policy.into()),
+            retry_count: response.retry_count,
+            worker_signers: response.worker_signers,
+            federation_peer: response.federation_peer.unwrap_or_default(),
+            function_post_processing: to_proto_post_processing(response.function_post_processing),
+            spec_hash: response.spec_hash,
+            request_signatures: response
+                .request_signatures
+                .into_iter()
+                .map(|signature| signature.into())
+                .collect(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ListTasksRequest> for ListTasksRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::ListTasksRequest) -> Result<Self> {
+        let statuses = proto
+            .statuses
+            .into_iter()
+            .map(i32_to_task_status)
+            .collect::<Result<Vec<_>>>()?;
+        let function_id = if proto.function_id.is_empty() {
+            None
+        } else {
+            Some(proto.function_id.try_into()?)
+        };
+        let participant = if proto.participant.is_empty() {
+            None
+        } else {
+            Some(proto.participant.into())
+        };
+        let filter = TaskFilter {
+            statuses,
+            function_id,
+            participant,
+            created_after: Some(proto.created_after).filter(|&secs| secs > 0),
+            created_before: Some(proto.created_before).filter(|&secs| secs > 0),
+        };
+        let ret = Self {
+            labels: proto.labels,
+            filter,
+            page: proto.page.map(Into::into).unwrap_or_default(),
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<ListTasksRequest> for proto::ListTasksRequest {
+    fn from(request: ListTasksRequest) -> Self {
+        let filter = request.filter;
+        Self {
+            labels: request.labels,
+            statuses: filter
+                .statuses
+                .into_iter()
+                .map(i32_from_task_status)
+                .collect(),
+            function_id: filter
+                .function_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            participant: filter
+                .participant
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            created_after: filter.created_after.unwrap_or(0),
+            created_before: filter.created_before.unwrap_or(0),
+            page: Some(request.page.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ListTasksResponse> for ListTasksResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::ListTasksResponse) -> Result<Self> {
+        let task_ids = proto
+            .task_ids
+            .into_iter()
+            .map(|task_id| task_id.try_into())
+            .collect::<Result<Vec<ExternalID>>>()?;
+        let page = proto.page.map(Into::into).unwrap_or_default();
+
+        Ok(Self { task_ids, page })
+    }
+}
+
+impl From<ListTasksResponse> for proto::ListTasksResponse {
+    fn from(response: ListTasksResponse) -> Self {
+        Self {
+            task_ids: response
+                .task_ids
+                .into_iter()
+                .map(|task_id| task_id.to_string())
+                .collect(),
+            page: Some(response.page.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ListPendingApprovalsRequest> for ListPendingApprovalsRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::ListPendingApprovalsRequest) -> Result<Self> {
+        let ret = Self {
+            page: proto.page.map(Into::into).unwrap_or_default(),
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<ListPendingApprovalsRequest> for proto::ListPendingApprovalsRequest {
+    fn from(request: ListPendingApprovalsRequest) -> Self {
+        Self {
+            page: Some(request.page.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ListPendingApprovalsResponse> for ListPendingApprovalsResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::ListPendingApprovalsResponse) -> Result<Self> {
+        let task_ids = proto
+            .task_ids
+            .into_iter()
+            .map(|task_id| task_id.try_into())
+            .collect::<Result<Vec<ExternalID>>>()?;
+        let page = proto.page.map(Into::into).unwrap_or_default();
+
+        Ok(Self { task_ids, page })
+    }
+}
+
+impl From<ListPendingApprovalsResponse> for proto::ListPendingApprovalsResponse {
+    fn from(response: ListPendingApprovalsResponse) -> Self {
+        Self {
+            task_ids: response
+                .task_ids
+                .into_iter()
+                .map(|task_id| task_id.to_string())
+                .collect(),
+            page: Some(response.page.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ListFunctionsRequest> for ListFunctionsRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::ListFunctionsRequest) -> Result<Self> {
+        let ret = Self {
+            page: proto.page.map(Into::into).unwrap_or_default(),
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<ListFunctionsRequest> for proto::ListFunctionsRequest {
+    fn from(request: ListFunctionsRequest) -> Self {
+        Self {
+            page: Some(request.page.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ListFunctionsResponse> for ListFunctionsResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::ListFunctionsResponse) -> Result<Self> {
+        let function_ids = proto
+            .function_ids
+            .into_iter()
+            .map(|function_id| function_id.try_into())
+            .collect::<Result<Vec<ExternalID>>>()?;
+        let page = proto.page.map(Into::into).unwrap_or_default();
+
+        Ok(Self { function_ids, page })
+    }
+}
+
+impl From<ListFunctionsResponse> for proto::ListFunctionsResponse {
+    fn from(response: ListFunctionsResponse) -> Self {
+        Self {
+            function_ids: response
+                .function_ids
+                .into_iter()
+                .map(|function_id| function_id.to_string())
+                .collect(),
+            page: Some(response.page.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ListFilesRequest> for ListFilesRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::ListFilesRequest) -> Result<Self> {
+        let ret = Self {
+            output: proto.output,
+            page: proto.page.map(Into::into).unwrap_or_default(),
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<ListFilesRequest> for proto::ListFilesRequest {
+    fn from(request: ListFilesRequest) -> Self {
+        Self {
+            output: request.output,
+            page: Some(request.page.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ListFilesResponse> for ListFilesResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::ListFilesResponse) -> Result<Self> {
+        let data_ids = proto
+            .data_ids
+            .into_iter()
+            .map(|data_id| data_id.try_into())
+            .collect::<Result<Vec<ExternalID>>>()?;
+        let page = proto.page.map(Into::into).unwrap_or_default();
+
+        Ok(Self { data_ids, page })
+    }
+}
+
+impl From<ListFilesResponse> for proto::ListFilesResponse {
+    fn from(response: ListFilesResponse) -> Self {
+        Self {
+            data_ids: response
+                .data_ids
+                .into_iter()
+                .map(|data_id| data_id.to_string())
+                .collect(),
+            page: Some(response.page.into()),
+        }
+    }
+}
+
+// The first page always starts at 0, so 0 marks the last page on the wire.
+fn next_offset_from_proto(next_offset: u64) -> Option<usize> {
+    match next_offset {
+        0 => None,
+        offset => Some(offset as usize),
+    }
+}
+
+impl std::convert::TryFrom<proto::AssignDataRequest> for AssignDataRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::AssignDataRequest) -> Result<Self> {
+        // Models are assigned to inputs by references instead of data ids.
+        let (models, inputs): (Vec<_>, Vec<_>) = proto
+            .inputs
+            .into_iter()
+            .partition(|item| ModelReference::is_model_reference(&item.data_id));
+        let input_models = models
+            .into_iter()
+            .map(|item| Ok((item.data_name, item.data_id.try_into()?)))
+            .collect::<Result<_>>()?;
+        let inputs = from_proto_file_ids(inputs)?;
+        let outputs = from_proto_file_ids(proto.outputs)?;
+        let task_id = proto.task_id.try_into()?;
+        let ret = Self {
+            task_id,
+            inputs,
+            outputs,
+            input_models,
+            signature: proto.signature,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<AssignDataRequest> for proto::AssignDataRequest {
+    fn from(request: AssignDataRequest) -> Self {
+        let mut inputs = to_proto_file_ids(request.inputs);
+        inputs.extend(
+            request
+                .input_models
+                .into_iter()
+                .map(|(name, reference)| proto::DataMap {
+                    data_name: name,
+                    data_id: reference.to_string(),
+                }),
+        );
+        let outputs = to_proto_file_ids(request.outputs);
+        Self {
+            task_id: request.task_id.to_string(),
+            inputs,
+            outputs,
+            signature: request.signature,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::AssignDataResponse> for AssignDataResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::AssignDataResponse) -> Result<Self> {
+        Ok(AssignDataResponse)
+    }
+}
+
+impl From<AssignDataResponse> for proto::AssignDataResponse {
+    fn from(_response: AssignDataResponse) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::ApproveTaskRequest> for ApproveTaskRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::ApproveTaskRequest) -> Result<Self> {
+        let task_id = proto.task_id.try_into()?;
+        let ret = Self {
+            task_id,
+            signature: proto.signature,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<ApproveTaskRequest> for proto::ApproveTaskRequest {
+    fn from(request: ApproveTaskRequest) -> Self {
+        Self {
+            task_id: request.task_id.to_string(),
+            signature: request.signature,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ApproveTaskResponse> for ApproveTaskResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::ApproveTaskResponse) -> Result<Self> {
+        let receipt = proto
+            .receipt
+            .ok_or_else(|| anyhow!("missing receipt"))?
+            .try_into()?;
+
+        Ok(Self { receipt })
+    }
+}
+
+impl From<ApproveTaskResponse> for proto::ApproveTaskResponse {
+    fn from(response: ApproveTaskResponse) -> Self {
+        Self {
+            receipt: Some(response.receipt.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::InvokeTaskRequest> for InvokeTaskRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::InvokeTaskRequest) -> Result<Self> {
+        let task_id = proto.task_id.try_into()?;
+        let ret = Self { task_id };
+
+        Ok(ret)
+    }
+}
+
+impl From<InvokeTaskRequest> for proto::InvokeTaskRequest {
+    fn from(request: InvokeTaskRequest) -> Self {
+        Self {
+            task_id: request.task_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::InvokeTaskResponse> for InvokeTaskResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::InvokeTaskResponse) -> Result<Self> {
+        Ok(InvokeTaskResponse)
+    }
+}
+
+impl From<InvokeTaskResponse> for proto::InvokeTaskResponse {
+    fn from(_response: InvokeTaskResponse) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::ManifestMeasurement> for ManifestMeasurement {
+    type Error = Error;
+
+    fn try_from(proto: proto::ManifestMeasurement) -> Result<Self> {
+        let ret = Self {
+            mr_enclave: proto.mr_enclave,
+            mr_signer: proto.mr_signer,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<ManifestMeasurement> for proto::ManifestMeasurement {
+    fn from(measurement: ManifestMeasurement) -> Self {
+        Self {
+            mr_enclave: measurement.mr_enclave,
+            mr_signer: measurement.mr_signer,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::TaskManifest> for TaskManifest {
+    type Error = Error;
+
+    fn try_from(proto: proto::TaskManifest) -> Result<Self> {
+        let enclave_measurements = proto
+            .enclave_measurements
+            .into_iter()
+            .map(|(name, measurement)| Ok((name, measurement.try_into()?)))
+            .collect::<Result<_>>()?;
+        let ret = Self {
+            task_id: Uuid::parse_str(&proto.task_id)?,
+            function_id: proto.function_id,
+            function_hash: proto.function_hash,
+            arguments_hash: proto.arguments_hash,
+            executor: proto.executor,
+            executor_version: proto.executor_version,
+            input_cmacs: proto.input_cmacs.into_iter().collect(),
+            output_cmacs: proto.output_cmacs.into_iter().collect(),
+            enclave_measurements,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<TaskManifest> for proto::TaskManifest {
+    fn from(manifest: TaskManifest) -> Self {
+        Self {
+            task_id: manifest.task_id.to_string(),
+            function_id: manifest.function_id,
+            function_hash: manifest.function_hash,
+            arguments_hash: manifest.arguments_hash,
+            executor: manifest.executor,
+            executor_version: manifest.executor_version,
+            input_cmacs: manifest.input_cmacs.into_iter().collect(),
+            output_cmacs: manifest.output_cmacs.into_iter().collect(),
+            enclave_measurements: manifest
+                .enclave_measurements
+                .into_iter()
+                .map(|(name, measurement)| (name, measurement.into()))
+                .collect(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetTaskManifestRequest> for GetTaskManifestRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetTaskManifestRequest) -> Result<Self> {
+        let task_id = proto.task_id.try_into()?;
+        let ret = Self { task_id };
+
+        Ok(ret)
+    }
+}
+
+impl From<GetTaskManifestRequest> for proto::GetTaskManifestRequest {
+    fn from(request: GetTaskManifestRequest) -> Self {
+        Self {
+            task_id: request.task_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetTaskManifestResponse> for GetTaskManifestResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetTaskManifestResponse) -> Result<Self> {
+        let manifest = proto
+            .manifest
+            .ok_or_else(|| anyhow!("missing manifest"))?
+            .try_into()?;
+
+        Ok(Self { manifest })
+    }
+}
+
+impl From<GetTaskManifestResponse> for proto::GetTaskManifestResponse {
+    fn from(response: GetTaskManifestResponse) -> Self {
+        Self {
+            manifest: Some(response.manifest.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetTaskReturnValueRequest> for GetTaskReturnValueRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetTaskReturnValueRequest) -> Result<Self> {
+        let ret = Self {
+            task_id: proto.task_id.try_into()?,
+            offset: proto.offset as usize,
+            limit: proto.limit as usize,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<GetTaskReturnValueRequest> for proto::GetTaskReturnValueRequest {
+    fn from(request: GetTaskReturnValueRequest) -> Self {
+        Self {
+            task_id: request.task_id.to_string(),
+            offset: request.offset as u64,
+            limit: request.limit as u64,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetTaskReturnValueResponse> for GetTaskReturnValueResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetTaskReturnValueResponse) -> Result<Self> {
+        Ok(Self {
+            data: proto.data,
+            total_size: proto.total_size as usize,
+            next_offset: next_offset_from_proto(proto.next_offset),
+        })
+    }
+}
+
+impl From<GetTaskReturnValueResponse> for proto::GetTaskReturnValueResponse {
+    fn from(response: GetTaskReturnValueResponse) -> Self {
+        Self {
+            data: response.data,
+            total_size: response.total_size as u64,
+            next_offset: response.next_offset.unwrap_or(0) as u64,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetTaskKeyOfferRequest> for GetTaskKeyOfferRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetTaskKeyOfferRequest) -> Result<Self> {
+        let task_id = proto.task_id.try_into()?;
+        let ret = Self { task_id };
+
+        Ok(ret)
+    }
+}
+
+impl From<GetTaskKeyOfferRequest> for proto::GetTaskKeyOfferRequest {
+    fn from(request: GetTaskKeyOfferRequest) -> Self {
+        Self {
+            task_id: request.task_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetTaskKeyOfferResponse> for GetTaskKeyOfferResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetTaskKeyOfferResponse) -> Result<Self> {
+        let offer = proto
+            .offer
+            .ok_or_else(|| anyhow!("missing offer"))?
+            .try_into()?;
+        let ret = Self { offer };
+
+        Ok(ret)
+    }
+}
+
+impl From<GetTaskKeyOfferResponse> for proto::GetTaskKeyOfferResponse {
+    fn from(response: GetTaskKeyOfferResponse) -> Self {
+        Self {
+            offer: Some(response.offer.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::PutTaskKeysRequest> for PutTaskKeysRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::PutTaskKeysRequest) -> Result<Self> {
+        let task_id = proto.task_id.try_into()?;
+        let keys = WrappedTaskKeys {
+            offer_public_key: proto.offer_public_key,
+            public_key: proto.public_key,
+            keys: proto.wrapped_keys,
+        };
+        let ret = Self { task_id, keys };
+
+        Ok(ret)
+    }
+}
+
+impl From<PutTaskKeysRequest> for proto::PutTaskKeysRequest {
+    fn from(request: PutTaskKeysRequest) -> Self {
+        Self {
+            task_id: request.task_id.to_string(),
+            offer_public_key: request.keys.offer_public_key,
+            public_key: request.keys.public_key,
+            wrapped_keys: request.keys.keys,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::PutTaskKeysResponse> for PutTaskKeysResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::PutTaskKeysResponse) -> Result<Self> {
+        Ok(PutTaskKeysResponse)
+    }
+}
+
+impl From<PutTaskKeysResponse> for proto::PutTaskKeysResponse {
+    fn from(_response: PutTaskKeysResponse) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::LineageStep> for LineageStep {
+    type Error = Error;
+
+    fn try_from(proto: proto::LineageStep) -> Result<Self> {
+        let manifest = proto
+            .manifest
+            .ok_or_else(|| anyhow!("missing manifest"))?
+            .try_into()?;
+        let input_data_ids = proto
+            .input_data_ids
+            .into_iter()
+            .map(|(name, data_id)| Ok((name, data_id.try_into()?)))
+            .collect::<Result<_>>()?;
+        let output_data_ids = proto
+            .output_data_ids
+            .into_iter()
+            .map(|(name, data_id)| Ok((name, data_id.try_into()?)))
+            .collect::<Result<_>>()?;
+        let ret = Self {
+            manifest,
+            input_data_ids,
+            output_data_ids,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<LineageStep> for proto::LineageStep {
+    fn from(step: LineageStep) -> Self {
+        Self {
+            manifest: Some(step.manifest.into()),
+            input_data_ids: step
+                .input_data_ids
+                .into_iter()
+                .map(|(name, data_id)| (name, data_id.to_string()))
+                .collect(),
+            output_data_ids: step
+                .output_data_ids
+                .into_iter()
+                .map(|(name, data_id)| (name, data_id.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetDataLineageRequest> for GetDataLineageRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetDataLineageRequest) -> Result<Self> {
+        let data_id = proto.data_id.try_into()?;
+        let ret = Self { data_id };
+
+        Ok(ret)
+    }
+}
+
+impl From<GetDataLineageRequest> for proto::GetDataLineageRequest {
+    fn from(request: GetDataLineageRequest) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetDataLineageResponse> for GetDataLineageResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetDataLineageResponse) -> Result<Self> {
+        let steps = proto
+            .steps
+            .into_iter()
+            .map(LineageStep::try_from)
+            .collect::<Result<_>>()?;
+
+        Ok(Self { steps })
+    }
+}
+
+impl From<GetDataLineageResponse> for proto::GetDataLineageResponse {
+    fn from(response: GetDataLineageResponse) -> Self {
+        Self {
+            steps: response.steps.into_iter().map(|step| step.into()).collect(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterApprovalPolicyRequest> for RegisterApprovalPolicyRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::RegisterApprovalPolicyRequest) -> Result<Self> {
+        let function_ids = proto
+            .function_ids
+            .into_iter()
+            .map(|function_id| function_id.try_into())
+            .collect::<Result<Vec<ExternalID>>>()?;
+        let ret = Self {
+            data_id: proto.data_id.try_into()?,
+            function_ids,
+            creators: UserList::new(proto.creators),
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<RegisterApprovalPolicyRequest> for proto::RegisterApprovalPolicyRequest {
+    fn from(request: RegisterApprovalPolicyRequest) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+            function_ids: request
+                .function_ids
+                .into_iter()
+                .map(|function_id| function_id.to_string())
+                .collect(),
+            creators: request.creators.into(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterApprovalPolicyResponse>
+    for RegisterApprovalPolicyResponse
+{
+    type Error = Error;
+
+    fn try_from(proto: proto::RegisterApprovalPolicyResponse) -> Result<Self> {
+        let policy_id = Uuid::parse_str(&proto.policy_id)?;
+
+        Ok(Self { policy_id })
+    }
+}
+
+impl From<RegisterApprovalPolicyResponse> for proto::RegisterApprovalPolicyResponse {
+    fn from(response: RegisterApprovalPolicyResponse) -> Self {
+        Self {
+            policy_id: response.policy_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RevokeApprovalPolicyRequest> for RevokeApprovalPolicyRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::RevokeApprovalPolicyRequest) -> Result<Self> {
+        let policy_id = Uuid::parse_str(&proto.policy_id)?;
+
+        Ok(Self { policy_id })
+    }
+}
+
+impl From<RevokeApprovalPolicyRequest> for proto::RevokeApprovalPolicyRequest {
+    fn from(request: RevokeApprovalPolicyRequest) -> Self {
+        Self {
+            policy_id: request.policy_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RevokeApprovalPolicyResponse> for RevokeApprovalPolicyResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::RevokeApprovalPolicyResponse) -> Result<Self> {
+        Ok(RevokeApprovalPolicyResponse)
+    }
+}
+
+impl From<RevokeApprovalPolicyResponse> for proto::RevokeApprovalPolicyResponse {
+    fn from(_response: RevokeApprovalPolicyResponse) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::UpdateOutputUrlRequest> for UpdateOutputUrlRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::UpdateOutputUrlRequest) -> Result<Self> {
+        let ret = Self {
+            data_id: proto.data_id.try_into()?,
+            url: Url::parse(&proto.url)?,
+            credential: proto.credential.map(|c| c.try_into()).transpose()?,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<UpdateOutputUrlRequest> for proto::UpdateOutputUrlRequest {
+    fn from(request: UpdateOutputUrlRequest) -> Self {
+        Self {
+            data_id: request.data_id.to_string(),
+            url: request.url.into_string(),
+            credential: request.credential.map(|c| c.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::UpdateOutputUrlResponse> for UpdateOutputUrlResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::UpdateOutputUrlResponse) -> Result<Self> {
+        Ok(UpdateOutputUrlResponse)
+    }
+}
+
+impl From<UpdateOutputUrlResponse> for proto::UpdateOutputUrlResponse {
+    fn from(_response: UpdateOutputUrlResponse) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterModelRequest> for RegisterModelRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::RegisterInputFileRequest) -> Result<Self> {
-        let url = Url::parse(&proto.url)?;
-        let cmac = FileAuthTag::from_bytes(&proto.cmac)?;
-        let crypto_info = proto
-            .crypto_info
-            .ok_or_else(|| anyhow!("missing crypto_info"))?
+    fn try_from(proto: proto::RegisterModelRequest) -> Result<Self> {
+        let ret = Self {
+            name: proto.name,
+            task_id: proto.task_id.try_into()?,
+            data_id: proto.data_id.try_into()?,
+            metrics: proto.metrics,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<RegisterModelRequest> for proto::RegisterModelRequest {
+    fn from(request: RegisterModelRequest) -> Self {
+        Self {
+            name: request.name,
+            task_id: request.task_id.to_string(),
+            data_id: request.data_id.to_string(),
+            metrics: request.metrics,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterModelResponse> for RegisterModelResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::RegisterModelResponse) -> Result<Self> {
+        Ok(Self {
+            version: proto.version,
+        })
+    }
+}
+
+impl From<RegisterModelResponse> for proto::RegisterModelResponse {
+    fn from(response: RegisterModelResponse) -> Self {
+        Self {
+            version: response.version,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetModelRequest> for GetModelRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetModelRequest) -> Result<Self> {
+        // Version 0 stands for the latest version.
+        let version = match proto.version {
+            0 => None,
+            version => Some(version),
+        };
+        let ret = Self {
+            name: proto.name,
+            version,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<GetModelRequest> for proto::GetModelRequest {
+    fn from(request: GetModelRequest) -> Self {
+        Self {
+            name: request.name,
+            version: request.version.unwrap_or_default(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ModelVersion> for ModelVersion {
+    type Error = Error;
+
+    fn try_from(proto: proto::ModelVersion) -> Result<Self> {
+        let ret = Self {
+            version: proto.version,
+            data_id: proto.data_id.try_into()?,
+            task_id: proto.task_id.try_into()?,
+            function_id: proto.function_id.try_into()?,
+            metrics: proto.metrics,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<ModelVersion> for proto::ModelVersion {
+    fn from(model: ModelVersion) -> Self {
+        Self {
+            version: model.version,
+            data_id: model.data_id.to_string(),
+            task_id: model.task_id.to_string(),
+            function_id: model.function_id.to_string(),
+            metrics: model.metrics,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetModelResponse> for GetModelResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetModelResponse) -> Result<Self> {
+        let model = proto
+            .model
+            .ok_or_else(|| anyhow!("missing model"))?
             .try_into()?;
-        Ok(RegisterInputFileRequest {
-            url,
-            cmac,
-            crypto_info,
+
+        Ok(Self {
+            name: proto.name,
+            model,
         })
     }
 }
 
-impl From<RegisterInputFileRequest> for proto::RegisterInputFileRequest {
-    fn from(request: RegisterInputFileRequest) -> Self {
+impl From<GetModelResponse> for proto::GetModelResponse {
+    fn from(response: GetModelResponse) -> Self {
         Self {
-            url: request.url.into_string(),
-            cmac: request.cmac.to_bytes(),
-            crypto_info: Some(request.crypto_info.into()),
+            name: response.name,
+            model: Some(response.model.into()),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::UpdateInputFileRequest> for UpdateInputFileRequest {
+impl std::convert::TryFrom<proto::CreateTaskShareLinkRequest> for CreateTaskShareLinkRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::UpdateInputFileRequest) -> Result<Self> {
-        let data_id = proto.data_id.try_into()?;
-        let url = Url::parse(&proto.url)?;
+    fn try_from(proto: proto::CreateTaskShareLinkRequest) -> Result<Self> {
+        let ret = Self {
+            task_id: proto.task_id.try_into()?,
+            expires_in: proto.expires_in,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<CreateTaskShareLinkRequest> for proto::CreateTaskShareLinkRequest {
+    fn from(request: CreateTaskShareLinkRequest) -> Self {
+        Self {
+            task_id: request.task_id.to_string(),
+            expires_in: request.expires_in,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::CreateTaskShareLinkResponse> for CreateTaskShareLinkResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::CreateTaskShareLinkResponse) -> Result<Self> {
+        Ok(Self {
+            token: proto.token,
+            expires_at: proto.expires_at,
+        })
+    }
+}
+
+impl From<CreateTaskShareLinkResponse> for proto::CreateTaskShareLinkResponse {
+    fn from(response: CreateTaskShareLinkResponse) -> Self {
+        Self {
+            token: response.token,
+            expires_at: response.expires_at,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetSharedTaskRequest> for GetSharedTaskRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetSharedTaskRequest) -> Result<Self> {
+        Ok(Self { token: proto.token })
+    }
+}
+
+impl From<GetSharedTaskRequest> for proto::GetSharedTaskRequest {
+    fn from(request: GetSharedTaskRequest) -> Self {
+        Self {
+            token: request.token,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::TaskProgress> for TaskProgress {
+    type Error = Error;
+
+    fn try_from(proto: proto::TaskProgress) -> Result<Self> {
+        let ret = Self {
+            status: i32_to_task_status(proto.status)?,
+            participants: proto.participants,
+            approvals: proto.approvals,
+            assigned_inputs: proto.assigned_inputs,
+            expected_inputs: proto.expected_inputs,
+            assigned_outputs: proto.assigned_outputs,
+            expected_outputs: proto.expected_outputs,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<TaskProgress> for proto::TaskProgress {
+    fn from(progress: TaskProgress) -> Self {
+        Self {
+            status: i32_from_task_status(progress.status),
+            participants: progress.participants,
+            approvals: progress.approvals,
+            assigned_inputs: progress.assigned_inputs,
+            expected_inputs: progress.expected_inputs,
+            assigned_outputs: progress.assigned_outputs,
+            expected_outputs: progress.expected_outputs,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetSharedTaskResponse> for GetSharedTaskResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetSharedTaskResponse) -> Result<Self> {
+        let progress = proto
+            .progress
+            .ok_or_else(|| anyhow!("missing progress"))?
+            .try_into()?;
+
+        Ok(Self {
+            task_id: proto.task_id.try_into()?,
+            progress,
+            expires_at: proto.expires_at,
+        })
+    }
+}
+
+impl From<GetSharedTaskResponse> for proto::GetSharedTaskResponse {
+    fn from(response: GetSharedTaskResponse) -> Self {
+        Self {
+            task_id: response.task_id.to_string(),
+            progress: Some(response.progress.into()),
+            expires_at: response.expires_at,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ArchiveTaskRequest> for ArchiveTaskRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::ArchiveTaskRequest) -> Result<Self> {
+        Ok(Self {
+            task_id: proto.task_id.try_into()?,
+        })
+    }
+}
+
+impl From<ArchiveTaskRequest> for proto::ArchiveTaskRequest {
+    fn from(request: ArchiveTaskRequest) -> Self {
+        Self {
+            task_id: request.task_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ArchiveTaskResponse> for ArchiveTaskResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::ArchiveTaskResponse) -> Result<Self> {
+        Ok(Self {
+            url: Url::parse(&proto.url)?,
+        })
+    }
+}
+
+impl From<ArchiveTaskResponse> for proto::ArchiveTaskResponse {
+    fn from(response: ArchiveTaskResponse) -> Self {
+        Self {
+            url: response.url.into_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RestoreArchivedTaskRequest> for RestoreArchivedTaskRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::RestoreArchivedTaskRequest) -> Result<Self> {
+        Ok(Self {
+            task_id: proto.task_id.try_into()?,
+        })
+    }
+}
+
+impl From<RestoreArchivedTaskRequest> for proto::RestoreArchivedTaskRequest {
+    fn from(request: RestoreArchivedTaskRequest) -> Self {
+        Self {
+            task_id: request.task_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RestoreArchivedTaskResponse> for RestoreArchivedTaskResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::RestoreArchivedTaskResponse) -> Result<Self> {
+        Ok(RestoreArchivedTaskResponse)
+    }
+}
+
+impl From<RestoreArchivedTaskResponse> for proto::RestoreArchivedTaskResponse {
+    fn from(_response: RestoreArchivedTaskResponse) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::StorageSnapshot> for StorageSnapshot {
+    type Error = Error;
+
+    fn try_from(proto: proto::StorageSnapshot) -> Result<Self> {
+        Ok(Self::new(
+            proto.name,
+            TeaclaveFile128Key::new(&proto.key)?,
+            FileAuthTag::from_bytes(&proto.cmac)?,
+            proto.entries,
+        ))
+    }
+}
+
+impl From<StorageSnapshot> for proto::StorageSnapshot {
+    fn from(snapshot: StorageSnapshot) -> Self {
+        Self {
+            name: snapshot.name,
+            key: snapshot.crypto_info.key.to_vec(),
+            cmac: snapshot.cmac.to_bytes(),
+            entries: snapshot.entries,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ExportStorageSnapshotRequest> for ExportStorageSnapshotRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::ExportStorageSnapshotRequest) -> Result<Self> {
+        Ok(Self { name: proto.name })
+    }
+}
+
+impl From<ExportStorageSnapshotRequest> for proto::ExportStorageSnapshotRequest {
+    fn from(request: ExportStorageSnapshotRequest) -> Self {
+        Self { name: request.name }
+    }
+}
+
+impl std::convert::TryFrom<proto::ExportStorageSnapshotResponse> for ExportStorageSnapshotResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::ExportStorageSnapshotResponse) -> Result<Self> {
+        let snapshots = proto
+            .snapshots
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<StorageSnapshot>>>()?;
+        Ok(Self { snapshots })
+    }
+}
+
+impl From<ExportStorageSnapshotResponse> for proto::ExportStorageSnapshotResponse {
+    fn from(response: ExportStorageSnapshotResponse) -> Self {
+        Self {
+            snapshots: response.snapshots.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RestoreStorageSnapshotRequest> for RestoreStorageSnapshotRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::RestoreStorageSnapshotRequest) -> Result<Self> {
+        let snapshots = proto
+            .snapshots
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<StorageSnapshot>>>()?;
+        Ok(Self { snapshots })
+    }
+}
+
+impl From<RestoreStorageSnapshotRequest> for proto::RestoreStorageSnapshotRequest {
+    fn from(request: RestoreStorageSnapshotRequest) -> Self {
+        Self {
+            snapshots: request.snapshots.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RestoreStorageSnapshotResponse>
+    for RestoreStorageSnapshotResponse
+{
+    type Error = Error;
+
+    fn try_from(_proto: proto::RestoreStorageSnapshotResponse) -> Result<Self> {
+        Ok(RestoreStorageSnapshotResponse)
+    }
+}
+
+impl From<RestoreStorageSnapshotResponse> for proto::RestoreStorageSnapshotResponse {
+    fn from(_response: RestoreStorageSnapshotResponse) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::PromoteStorageReplicaRequest> for PromoteStorageReplicaRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::PromoteStorageReplicaRequest) -> Result<Self> {
+        Ok(Self { name: proto.name })
+    }
+}
+
+impl From<PromoteStorageReplicaRequest> for proto::PromoteStorageReplicaRequest {
+    fn from(request: PromoteStorageReplicaRequest) -> Self {
+        Self { name: request.name }
+    }
+}
+
+impl std::convert::TryFrom<proto::PromoteStorageReplicaResponse> for PromoteStorageReplicaResponse {
+    type Error = Error;
 
-        Ok(UpdateInputFileRequest { data_id, url })
+    fn try_from(_proto: proto::PromoteStorageReplicaResponse) -> Result<Self> {
+        Ok(PromoteStorageReplicaResponse)
     }
 }
 
-impl From<UpdateInputFileRequest> for proto::UpdateInputFileRequest {
-    fn from(request: UpdateInputFileRequest) -> Self {
-        Self {
-            data_id: request.data_id.to_string(),
-            url: request.url.into_string(),
-        }
+impl From<PromoteStorageReplicaResponse> for proto::PromoteStorageReplicaResponse {
+    fn from(_response: PromoteStorageReplicaResponse) -> Self {
+        Self {}
     }
 }
 
-impl std::convert::TryFrom<proto::RegisterInputFileResponse> for RegisterInputFileResponse {
+impl std::convert::TryFrom<proto::CancelTaskRequest> for CancelTaskRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::RegisterInputFileResponse) -> Result<Self> {
-        let data_id = proto.data_id.try_into()?;
-        Ok(Self { data_id })
+    fn try_from(proto: proto::CancelTaskRequest) -> Result<Self> {
+        Ok(Self {
+            task_id: proto.task_id.try_into()?,
+        })
     }
 }
 
-impl From<RegisterInputFileResponse> for proto::RegisterInputFileResponse {
-    fn from(request: RegisterInputFileResponse) -> Self {
+impl From<CancelTaskRequest> for proto::CancelTaskRequest {
+    fn from(request: CancelTaskRequest) -> Self {
         Self {
-            data_id: request.data_id.to_string(),
+            task_id: request.task_id.to_string(),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::UpdateInputFileResponse> for UpdateInputFileResponse {
+impl std::convert::TryFrom<proto::CancelTaskResponse> for CancelTaskResponse {
     type Error = Error;
 
-    fn try_from(proto: proto::UpdateInputFileResponse) -> Result<Self> {
-        let data_id = proto.data_id.try_into()?;
-        Ok(Self { data_id })
+    fn try_from(_proto: proto::CancelTaskResponse) -> Result<Self> {
+        Ok(CancelTaskResponse)
     }
 }
 
-impl From<UpdateInputFileResponse> for proto::UpdateInputFileResponse {
-    fn from(request: UpdateInputFileResponse) -> Self {
-        Self {
-            data_id: request.data_id.to_string(),
-        }
+impl From<CancelTaskResponse> for proto::CancelTaskResponse {
+    fn from(_response: CancelTaskResponse) -> Self {
+        Self {}
     }
 }
 
-impl std::convert::TryFrom<proto::RegisterOutputFileRequest> for RegisterOutputFileRequest {
+impl std::convert::TryFrom<proto::GetTaskUploadSlotsRequest> for GetTaskUploadSlotsRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::RegisterOutputFileRequest) -> Result<Self> {
-        let ret = Self {
-            url: Url::parse(&proto.url)?,
-            crypto_info: proto
-                .crypto_info
-                .ok_or_else(|| anyhow!("missing crypto_info"))?
-                .try_into()?,
-        };
+    fn try_from(proto: proto::GetTaskUploadSlotsRequest) -> Result<Self> {
+        let task_id = proto.task_id.try_into()?;
+        let ret = Self { task_id };
 
         Ok(ret)
     }
 }
 
-impl From<RegisterOutputFileRequest> for proto::RegisterOutputFileRequest {
-    fn from(request: RegisterOutputFileRequest) -> Self {
+impl From<GetTaskUploadSlotsRequest> for proto::GetTaskUploadSlotsRequest {
+    fn from(request: GetTaskUploadSlotsRequest) -> Self {
         Self {
-            url: request.url.into_string(),
-            crypto_info: Some(request.crypto_info.into()),
+            task_id: request.task_id.to_string(),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::UpdateOutputFileRequest> for UpdateOutputFileRequest {
+impl std::convert::TryFrom<proto::GetTaskUploadSlotsResponse> for GetTaskUploadSlotsResponse {
     type Error = Error;
 
-    fn try_from(proto: proto::UpdateOutputFileRequest) -> Result<Self> {
+    fn try_from(proto: proto::GetTaskUploadSlotsResponse) -> Result<Self> {
+        let endpoint = Url::parse(&proto.endpoint)?;
         let ret = Self {
-            data_id: proto.data_id.try_into()?,
-            url: Url::parse(&proto.url)?,
+            endpoint,
+            tokens: proto.tokens,
         };
 
         Ok(ret)
     }
 }
 
-impl From<UpdateOutputFileRequest> for proto::UpdateOutputFileRequest {
-    fn from(request: UpdateOutputFileRequest) -> Self {
+impl From<GetTaskUploadSlotsResponse> for proto::GetTaskUploadSlotsResponse {
+    fn from(response: GetTaskUploadSlotsResponse) -> Self {
         Self {
-            data_id: request.data_id.to_string(),
-            url: request.url.into_string(),
+            endpoint: response.endpoint.into_string(),
+            tokens: response.tokens,
         }
     }
 }
 
-impl std::convert::TryFrom<proto::RegisterOutputFileResponse> for RegisterOutputFileResponse {
+impl std::convert::TryFrom<proto::RejectTaskRequest> for RejectTaskRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::RegisterOutputFileResponse) -> Result<Self> {
-        let data_id = proto.data_id.try_into()?;
-        Ok(Self { data_id })
+    fn try_from(proto: proto::RejectTaskRequest) -> Result<Self> {
+        let task_id = proto.task_id.try_into()?;
+        let ret = Self {
+            task_id,
+            reason: proto.reason,
+        };
+
+        Ok(ret)
     }
 }
 
-impl From<RegisterOutputFileResponse> for proto::RegisterOutputFileResponse {
-    fn from(request: RegisterOutputFileResponse) -> Self {
+impl From<RejectTaskRequest> for proto::RejectTaskRequest {
+    fn from(request: RejectTaskRequest) -> Self {
         Self {
-            data_id: request.data_id.to_string(),
+            task_id: request.task_id.to_string(),
+            reason: request.reason,
         }
     }
 }
 
-impl std::convert::TryFrom<proto::UpdateOutputFileResponse> for UpdateOutputFileResponse {
+impl std::convert::TryFrom<proto::RejectTaskResponse> for RejectTaskResponse {
     type Error = Error;
 
-    fn try_from(proto: proto::UpdateOutputFileResponse) -> Result<Self> {
-        let data_id = proto.data_id.try_into()?;
-        Ok(Self { data_id })
+    fn try_from(_proto: proto::RejectTaskResponse) -> Result<Self> {
+        Ok(RejectTaskResponse)
     }
 }
 
-impl From<UpdateOutputFileResponse> for proto::UpdateOutputFileResponse {
-    fn from(request: UpdateOutputFileResponse) -> Self {
-        Self {
-            data_id: request.data_id.to_string(),
-        }
+impl From<RejectTaskResponse> for proto::RejectTaskResponse {
+    fn from(_response: RejectTaskResponse) -> Self {
+        Self {}
     }
 }
 
-impl std::convert::TryFrom<proto::RegisterFusionOutputRequest> for RegisterFusionOutputRequest {
+impl std::convert::TryFrom<proto::GetActivityFeedRequest> for GetActivityFeedRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::RegisterFusionOutputRequest) -> Result<Self> {
-        let ret = Self {
-            owner_list: OwnerList::new(proto.owner_list),
-        };
+    fn try_from(proto: proto::GetActivityFeedRequest) -> Result<Self> {
+        let ret = Self { since: proto.since };
 
         Ok(ret)
     }
 }
 
-impl From<RegisterFusionOutputRequest> for proto::RegisterFusionOutputRequest {
-    fn from(request: RegisterFusionOutputRequest) -> Self {
+impl From<GetActivityFeedRequest> for proto::GetActivityFeedRequest {
+    fn from(request: GetActivityFeedRequest) -> Self {
         Self {
-            owner_list: request.owner_list.into(),
+            since: request.since,
         }
     }
 }
 
-impl std::convert::TryFrom<proto::RegisterFusionOutputResponse> for RegisterFusionOutputResponse {
+impl std::convert::TryFrom<proto::ActivityEvent> for ActivityEvent {
     type Error = Error;
 
-    fn try_from(proto: proto::RegisterFusionOutputResponse) -> Result<Self> {
-        let data_id = proto.data_id.try_into()?;
-        Ok(Self { data_id })
+    fn try_from(proto: proto::ActivityEvent) -> Result<Self> {
+        let kind = ActivityKind::try_from(proto.kind.as_str())?;
+        let object_id = proto.object_id.try_into()?;
+        let ret = Self::new(kind, object_id, proto.timestamp);
+
+        Ok(ret)
     }
 }
 
-impl From<RegisterFusionOutputResponse> for proto::RegisterFusionOutputResponse {
-    fn from(request: RegisterFusionOutputResponse) -> Self {
+impl From<ActivityEvent> for proto::ActivityEvent {
+    fn from(event: ActivityEvent) -> Self {
         Self {
-            data_id: request.data_id.to_string(),
+            kind: event.kind.to_string(),
+            object_id: event.object_id.to_string(),
+            timestamp: event.timestamp,
         }
     }
 }
 
-impl std::convert::TryFrom<proto::RegisterInputFromOutputRequest>
-    for RegisterInputFromOutputRequest
-{
+impl std::convert::TryFrom<proto::GetActivityFeedResponse> for GetActivityFeedResponse {
     type Error = Error;
 
-    fn try_from(proto: proto::RegisterInputFromOutputRequest) -> Result<Self> {
-        let data_id = proto.data_id.try_into()?;
-        let ret = Self { data_id };
+    fn try_from(proto: proto::GetActivityFeedResponse) -> Result<Self> {
+        let events = proto
+            .events
+            .into_iter()
+            .map(ActivityEvent::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let ret = Self { events };
 
         Ok(ret)
     }
 }
 
-impl From<RegisterInputFromOutputRequest> for proto::RegisterInputFromOutputRequest {
-    fn from(request: RegisterInputFromOutputRequest) -> Self {
+impl From<GetActivityFeedResponse> for proto::GetActivityFeedResponse {
+    fn from(response: GetActivityFeedResponse) -> Self {
         Self {
-            data_id: request.data_id.to_string(),
+            events: response.events.into_iter().map(Into::into).collect(),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::RegisterInputFromOutputResponse>
-    for RegisterInputFromOutputResponse
-{
+impl std::convert::TryFrom<proto::GetTaskResultStreamRequest> for GetTaskResultStreamRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::RegisterInputFromOutputResponse) -> Result<Self> {
-        let data_id = proto.data_id.try_into()?;
-        Ok(Self { data_id })
+    fn try_from(proto: proto::GetTaskResultStreamRequest) -> Result<Self> {
+        let output = if proto.output.is_empty() {
+            None
+        } else {
+            Some(proto.output)
+        };
+        let ret = Self {
+            task_id: proto.task_id.try_into()?,
+            output,
+        };
+
+        Ok(ret)
     }
 }
 
-impl From<RegisterInputFromOutputResponse> for proto::RegisterInputFromOutputResponse {
-    fn from(request: RegisterInputFromOutputResponse) -> Self {
+impl From<GetTaskResultStreamRequest> for proto::GetTaskResultStreamRequest {
+    fn from(request: GetTaskResultStreamRequest) -> Self {
         Self {
-            data_id: request.data_id.to_string(),
+            task_id: request.task_id.to_string(),
+            output: request.output.unwrap_or_default(),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::GetInputFileRequest> for GetInputFileRequest {
+impl std::convert::TryFrom<proto::GetTaskResultStreamResponse> for GetTaskResultStreamResponse {
     type Error = Error;
 
-    fn try_from(proto: proto::GetInputFileRequest) -> Result<Self> {
-        let data_id = proto.data_id.try_into()?;
-        let ret = Self { data_id };
-
-        Ok(ret)
+    fn try_from(proto: proto::GetTaskResultStreamResponse) -> Result<Self> {
+        Ok(Self {
+            data: proto.data,
+            offset: proto.offset as usize,
+        })
     }
 }
 
-impl From<GetInputFileRequest> for proto::GetInputFileRequest {
-    fn from(request: GetInputFileRequest) -> Self {
+impl From<GetTaskResultStreamResponse> for proto::GetTaskResultStreamResponse {
+    fn from(response: GetTaskResultStreamResponse) -> Self {
         Self {
-            data_id: request.data_id.to_string(),
+            data: response.data,
+            offset: response.offset as u64,
         }
     }
 }
 
-impl std::convert::TryFrom<proto::GetInputFileResponse> for GetInputFileResponse {
+impl std::convert::TryFrom<proto::RegisterTaskTemplateRequest> for RegisterTaskTemplateRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::GetInputFileResponse) -> Result<Self> {
-        Ok(Self {
-            owner: OwnerList::new(proto.owner),
-            cmac: FileAuthTag::from_bytes(&proto.cmac)?,
-        })
+    fn try_from(proto: proto::RegisterTaskTemplateRequest) -> Result<Self> {
+        let ret = Self {
+            function_id: proto.function_id.try_into()?,
+            function_arguments: proto.function_arguments.try_into()?,
+            executor: proto.executor.try_into()?,
+            inputs_ownership: from_proto_ownership(proto.inputs_ownership),
+            outputs_ownership: from_proto_ownership(proto.outputs_ownership),
+        };
+
+        Ok(ret)
     }
 }
 
-impl From<GetInputFileResponse> for proto::GetInputFileResponse {
-    fn from(request: GetInputFileResponse) -> Self {
+impl From<RegisterTaskTemplateRequest> for proto::RegisterTaskTemplateRequest {
+    fn from(request: RegisterTaskTemplateRequest) -> Self {
         Self {
-            owner: request.owner.into(),
-            cmac: request.cmac.to_bytes(),
+            function_id: request.function_id.to_string(),
+            function_arguments: request.function_arguments.into_string(),
+            executor: request.executor.to_string(),
+            inputs_ownership: to_proto_ownership(request.inputs_ownership),
+            outputs_ownership: to_proto_ownership(request.outputs_ownership),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::GetOutputFileRequest> for GetOutputFileRequest {
+impl std::convert::TryFrom<proto::RegisterTaskTemplateResponse> for RegisterTaskTemplateResponse {
     type Error = Error;
 
-    fn try_from(proto: proto::GetOutputFileRequest) -> Result<Self> {
-        let data_id = proto.data_id.try_into()?;
-        let ret = Self { data_id };
+    fn try_from(proto: proto::RegisterTaskTemplateResponse) -> Result<Self> {
+        let template_id = proto.template_id.try_into()?;
+        let ret = Self { template_id };
 
         Ok(ret)
     }
 }
 
-impl From<GetOutputFileRequest> for proto::GetOutputFileRequest {
-    fn from(request: GetOutputFileRequest) -> Self {
+impl From<RegisterTaskTemplateResponse> for proto::RegisterTaskTemplateResponse {
+    fn from(response: RegisterTaskTemplateResponse) -> Self {
         Self {
-            data_id: request.data_id.to_string(),
+            template_id: response.template_id.to_string(),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::GetOutputFileResponse> for GetOutputFileResponse {
+impl std::convert::TryFrom<proto::CreateTaskFromTemplateRequest> for CreateTaskFromTemplateRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::GetOutputFileResponse) -> Result<Self> {
-        let cmac = {
-            if proto.cmac.is_empty() {
-                None
-            } else {
-                Some(FileAuthTag::from_bytes(&proto.cmac)?)
-            }
+    fn try_from(proto: proto::CreateTaskFromTemplateRequest) -> Result<Self> {
+        let ret = Self {
+            template_id: proto.template_id.try_into()?,
+            function_arguments: proto.function_arguments.try_into()?,
+            labels: proto.labels,
         };
 
-        Ok(Self {
-            owner: OwnerList::new(proto.owner),
-            cmac,
-        })
+        Ok(ret)
     }
 }
 
-impl From<GetOutputFileResponse> for proto::GetOutputFileResponse {
-    fn from(request: GetOutputFileResponse) -> Self {
+impl From<CreateTaskFromTemplateRequest> for proto::CreateTaskFromTemplateRequest {
+    fn from(request: CreateTaskFromTemplateRequest) -> Self {
         Self {
-            owner: request.owner.into(),
-            cmac: request.cmac.map_or_else(Vec::new, |cmac| cmac.to_bytes()),
+            template_id: request.template_id.to_string(),
+            function_arguments: request.function_arguments.into_string(),
+            labels: request.labels,
         }
     }
 }
 
-impl std::convert::TryFrom<proto::FunctionInput> for FunctionInput {
+impl std::convert::TryFrom<proto::CreateTaskFromTemplateResponse>
+    for CreateTaskFromTemplateResponse
+{
     type Error = Error;
 
-    fn try_from(proto: proto::FunctionInput) -> Result<Self> {
-        let ret = Self {
-            name: proto.name,
-            description: proto.description,
-        };
+    fn try_from(proto: proto::CreateTaskFromTemplateResponse) -> Result<Self> {
+        let task_id = proto.task_id.try_into()?;
+        let ret = Self { task_id };
 
         Ok(ret)
     }
 }
 
-impl From<FunctionInput> for proto::FunctionInput {
-    fn from(input: FunctionInput) -> Self {
+impl From<CreateTaskFromTemplateResponse> for proto::CreateTaskFromTemplateResponse {
+    fn from(response: CreateTaskFromTemplateResponse) -> Self {
         Self {
-            name: input.name,
-            description: input.description,
+            task_id: response.task_id.to_string(),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::FunctionOutput> for FunctionOutput {
+impl std::convert::TryFrom<proto::CreateTasksBatchRequest> for CreateTasksBatchRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::FunctionOutput) -> Result<Self> {
-        let ret = Self {
-            name: proto.name,
-            description: proto.description,
-        };
+    fn try_from(proto: proto::CreateTasksBatchRequest) -> Result<Self> {
+        let tasks = proto
+            .tasks
+            .into_iter()
+            .map(CreateTaskRequest::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let ret = Self { tasks };
 
         Ok(ret)
     }
 }
 
-impl From<FunctionOutput> for proto::FunctionOutput {
-    fn from(output: FunctionOutput) -> Self {
+impl From<CreateTasksBatchRequest> for proto::CreateTasksBatchRequest {
+    fn from(request: CreateTasksBatchRequest) -> Self {
         Self {
-            name: output.name,
-            description: output.description,
+            tasks: request.tasks.into_iter().map(Into::into).collect(),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::RegisterFunctionRequest> for RegisterFunctionRequest {
+impl std::convert::TryFrom<proto::CreateTasksBatchResponse> for CreateTasksBatchResponse {
     type Error = Error;
 
-    fn try_from(proto: proto::RegisterFunctionRequest) -> Result<Self> {
-        let inputs: Result<Vec<FunctionInput>> = proto
-            .inputs
+    fn try_from(proto: proto::CreateTasksBatchResponse) -> Result<Self> {
+        let task_ids = proto
+            .task_ids
             .into_iter()
-            .map(FunctionInput::try_from)
-            .collect();
-        let outputs: Result<Vec<FunctionOutput>> = proto
-            .outputs
-            .into_iter()
-            .map(FunctionOutput::try_from)
-            .collect();
-        let executor_type = proto.executor_type.try_into()?;
+            .map(|task_id| task_id.try_into())
+            .collect::<Result<Vec<ExternalID>>>()?;
+        let ret = Self { task_ids };
 
-        let ret = Self {
-            name: proto.name,
-            description: proto.description,
-            executor_type,
-            payload: proto.payload,
-            public: proto.public,
-            arguments: proto.arguments,
-            inputs: inputs?,
-            outputs: outputs?,
-        };
         Ok(ret)
     }
 }
 
-impl From<RegisterFunctionRequest> for proto::RegisterFunctionRequest {
-    fn from(request: RegisterFunctionRequest) -> Self {
-        let inputs: Vec<proto::FunctionInput> = request
-            .inputs
-            .into_iter()
-            .map(proto::FunctionInput::from)
-            .collect();
-        let outputs: Vec<proto::FunctionOutput> = request
-            .outputs
-            .into_iter()
-            .map(proto::FunctionOutput::from)
-            .collect();
-
+impl From<CreateTasksBatchResponse> for proto::CreateTasksBatchResponse {
+    fn from(response: CreateTasksBatchResponse) -> Self {
         Self {
-            name: request.name,
-            description: request.description,
-            executor_type: request.executor_type.into(),
-            payload: request.payload,
-            public: request.public,
-            arguments: request.arguments,
-            inputs,
-            outputs,
+            task_ids: response
+                .task_ids
+                .into_iter()
+                .map(|task_id| task_id.to_string())
+                .collect(),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::RegisterFunctionResponse> for RegisterFunctionResponse {
+impl std::convert::TryFrom<proto::TestFunctionRequest> for TestFunctionRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::RegisterFunctionResponse) -> Result<Self> {
-        let function_id = proto.function_id.try_into()?;
-        let ret = Self { function_id };
+    fn try_from(proto: proto::TestFunctionRequest) -> Result<Self> {
+        let ret = Self {
+            function_id: proto.function_id.try_into()?,
+            function_arguments: proto.function_arguments.try_into()?,
+            executor: proto.executor.try_into()?,
+            sample_inputs: proto.sample_inputs,
+        };
 
         Ok(ret)
     }
 }
 
-impl From<RegisterFunctionResponse> for proto::RegisterFunctionResponse {
-    fn from(response: RegisterFunctionResponse) -> Self {
+impl From<TestFunctionRequest> for proto::TestFunctionRequest {
+    fn from(request: TestFunctionRequest) -> Self {
         Self {
-            function_id: response.function_id.to_string(),
+            function_id: request.function_id.to_string(),
+            function_arguments: request.function_arguments.into_string(),
+            executor: request.executor.to_string(),
+            sample_inputs: request.sample_inputs,
         }
     }
 }
 
-impl std::convert::TryFrom<proto::GetFunctionRequest> for GetFunctionRequest {
+impl std::convert::TryFrom<proto::TestFunctionResponse> for TestFunctionResponse {
     type Error = Error;
 
-    fn try_from(proto: proto::GetFunctionRequest) -> Result<Self> {
-        let function_id = proto.function_id.try_into()?;
-        let ret = Self { function_id };
+    fn try_from(proto: proto::TestFunctionResponse) -> Result<Self> {
+        let task_id = proto.task_id.try_into()?;
+        let ret = Self { task_id };
 
         Ok(ret)
     }
 }
 
-impl From<GetFunctionRequest> for proto::GetFunctionRequest {
-    fn from(request: GetFunctionRequest) -> Self {
+impl From<TestFunctionResponse> for proto::TestFunctionResponse {
+    fn from(response: TestFunctionResponse) -> Self {
         Self {
-            function_id: request.function_id.to_string(),
+            task_id: response.task_id.to_string(),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::GetFunctionResponse> for GetFunctionResponse {
+impl std::convert::TryFrom<proto::RegisterTaskScheduleRequest> for RegisterTaskScheduleRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::GetFunctionResponse) -> Result<Self> {
-        let inputs: Result<Vec<FunctionInput>> = proto
-            .inputs
-            .into_iter()
-            .map(FunctionInput::try_from)
-            .collect();
-        let outputs: Result<Vec<FunctionOutput>> = proto
-            .outputs
-            .into_iter()
-            .map(FunctionOutput::try_from)
-            .collect();
-        let executor_type = proto.executor_type.try_into()?;
-
+    fn try_from(proto: proto::RegisterTaskScheduleRequest) -> Result<Self> {
         let ret = Self {
-            name: proto.name,
-            description: proto.description,
-            owner: proto.owner.into(),
-            executor_type,
-            payload: proto.payload,
-            public: proto.public,
-            arguments: proto.arguments,
-            inputs: inputs?,
-            outputs: outputs?,
+            template_id: proto.template_id.try_into()?,
+            start_in: proto.start_in,
+            interval: proto.interval,
+            max_runs: Some(proto.max_runs).filter(|runs| *runs > 0),
+            inputs: from_proto_file_ids(proto.inputs)?,
         };
 
         Ok(ret)
     }
 }
 
-impl From<GetFunctionResponse> for proto::GetFunctionResponse {
-    fn from(response: GetFunctionResponse) -> Self {
-        let inputs: Vec<proto::FunctionInput> = response
-            .inputs
-            .into_iter()
-            .map(proto::FunctionInput::from)
-            .collect();
-        let outputs: Vec<proto::FunctionOutput> = response
-            .outputs
-            .into_iter()
-            .map(proto::FunctionOutput::from)
-            .collect();
-
+impl From<RegisterTaskScheduleRequest> for proto::RegisterTaskScheduleRequest {
+    fn from(request: RegisterTaskScheduleRequest) -> Self {
         Self {
-            name: response.name,
-            description: response.description,
-            owner: response.owner.into(),
-            executor_type: response.executor_type.into(),
-            payload: response.payload,
-            public: response.public,
-            arguments: response.arguments,
-            inputs,
-            outputs,
+            template_id: request.template_id.to_string(),
+            start_in: request.start_in,
+            interval: request.interval,
+            max_runs: request.max_runs.unwrap_or_default(),
+            inputs: to_proto_file_ids(request.inputs),
         }
     }
 }
 
-fn from_proto_ownership(proto: Vec<proto::OwnerList>) -> TaskFileOwners {
-    proto
-        .into_iter()
-        .map(|ol| (ol.data_name, ol.uids))
-        .collect()
+impl std::convert::TryFrom<proto::RegisterTaskScheduleResponse> for RegisterTaskScheduleResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::RegisterTaskScheduleResponse) -> Result<Self> {
+        Ok(RegisterTaskScheduleResponse)
+    }
 }
 
-fn to_proto_ownership(ownership: TaskFileOwners) -> Vec<proto::OwnerList> {
-    ownership
-        .into_iter()
-        .map(|(name, ol)| proto::OwnerList {
-            data_name: name,
-            uids: ol.into(),
-        })
-        .collect()
+impl From<RegisterTaskScheduleResponse> for proto::RegisterTaskScheduleResponse {
+    fn from(_response: RegisterTaskScheduleResponse) -> Self {
+        Self {}
+    }
 }
 
-impl std::convert::TryFrom<proto::CreateTaskRequest> for CreateTaskRequest {
+impl std::convert::TryFrom<proto::ApproveTaskScheduleRequest> for ApproveTaskScheduleRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::CreateTaskRequest) -> Result<Self> {
-        let function_arguments: FunctionArguments = proto.function_arguments.try_into()?;
-        let inputs_ownership = from_proto_ownership(proto.inputs_ownership);
-        let outputs_ownership = from_proto_ownership(proto.outputs_ownership);
-        let function_id = proto.function_id.try_into()?;
-        let executor = proto.executor.try_into()?;
-
-        let ret = Self {
-            function_id,
-            function_arguments,
-            executor,
-            inputs_ownership,
-            outputs_ownership,
-        };
-        Ok(ret)
+    fn try_from(proto: proto::ApproveTaskScheduleRequest) -> Result<Self> {
+        Ok(Self {
+            template_id: proto.template_id.try_into()?,
+        })
     }
 }
 
-impl From<CreateTaskRequest> for proto::CreateTaskRequest {
-    fn from(request: CreateTaskRequest) -> Self {
-        let function_arguments = request.function_arguments.into_string();
-        let inputs_ownership = to_proto_ownership(request.inputs_ownership);
-        let outputs_ownership = to_proto_ownership(request.outputs_ownership);
-
+impl From<ApproveTaskScheduleRequest> for proto::ApproveTaskScheduleRequest {
+    fn from(request: ApproveTaskScheduleRequest) -> Self {
         Self {
-            function_id: request.function_id.to_string(),
-            function_arguments,
-            executor: request.executor.to_string(),
-            inputs_ownership,
-            outputs_ownership,
+            template_id: request.template_id.to_string(),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::CreateTaskResponse> for CreateTaskResponse {
+impl std::convert::TryFrom<proto::ApproveTaskScheduleResponse> for ApproveTaskScheduleResponse {
     type Error = Error;
 
-    fn try_from(proto: proto::CreateTaskResponse) -> Result<Self> {
-        let task_id = proto.task_id.try_into()?;
-        let ret = Self { task_id };
+    fn try_from(_proto: proto::ApproveTaskScheduleResponse) -> Result<Self> {
+        Ok(ApproveTaskScheduleResponse)
+    }
+}
 
-        Ok(ret)
+impl From<ApproveTaskScheduleResponse> for proto::ApproveTaskScheduleResponse {
+    fn from(_response: ApproveTaskScheduleResponse) -> Self {
+        Self {}
     }
 }
 
-impl From<CreateTaskResponse> for proto::CreateTaskResponse {
-    fn from(response: CreateTaskResponse) -> Self {
+impl std::convert::TryFrom<proto::CancelTaskScheduleRequest> for CancelTaskScheduleRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::CancelTaskScheduleRequest) -> Result<Self> {
+        Ok(Self {
+            template_id: proto.template_id.try_into()?,
+        })
+    }
+}
+
+impl From<CancelTaskScheduleRequest> for proto::CancelTaskScheduleRequest {
+    fn from(request: CancelTaskScheduleRequest) -> Self {
         Self {
-            task_id: response.task_id.to_string(),
+            template_id: request.template_id.to_string(),
         }
     }
 }
 
-fn to_proto_file_ids(map: HashMap<String, ExternalID>) -> Vec<proto::DataMap> {
-    map.into_iter()
-        .map(|(name, ext_id)| proto::DataMap {
-            data_name: name,
-            data_id: ext_id.to_string(),
-        })
-        .collect()
+impl std::convert::TryFrom<proto::CancelTaskScheduleResponse> for CancelTaskScheduleResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::CancelTaskScheduleResponse) -> Result<Self> {
+        Ok(CancelTaskScheduleResponse)
+    }
 }
 
-fn from_proto_file_ids(vector: Vec<proto::DataMap>) -> Result<HashMap<String, ExternalID>> {
-    vector
-        .into_iter()
-        .map(|item| {
-            item.data_id
-                .clone()
-                .try_into()
-                .map(|ext_id| (item.data_name, ext_id))
-        })
-        .collect()
+impl From<CancelTaskScheduleResponse> for proto::CancelTaskScheduleResponse {
+    fn from(_response: CancelTaskScheduleResponse) -> Self {
+        Self {}
+    }
 }
 
-impl std::convert::TryFrom<proto::GetTaskRequest> for GetTaskRequest {
+impl std::convert::TryFrom<proto::GetTaskScheduleRequest> for GetTaskScheduleRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::GetTaskRequest) -> Result<Self> {
-        let task_id = proto.task_id.try_into()?;
-        let ret = Self { task_id };
-
-        Ok(ret)
+    fn try_from(proto: proto::GetTaskScheduleRequest) -> Result<Self> {
+        Ok(Self {
+            template_id: proto.template_id.try_into()?,
+        })
     }
 }
 
-impl From<GetTaskRequest> for proto::GetTaskRequest {
-    fn from(request: GetTaskRequest) -> Self {
+impl From<GetTaskScheduleRequest> for proto::GetTaskScheduleRequest {
+    fn from(request: GetTaskScheduleRequest) -> Self {
         Self {
-            task_id: request.task_id.to_string(),
+            template_id: request.template_id.to_string(),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::GetTaskResponse> for GetTaskResponse {
+impl std::convert::TryFrom<proto::GetTaskScheduleResponse> for GetTaskScheduleResponse {
     type Error = Error;
 
-    fn try_from(proto: proto::GetTaskResponse) -> Result<Self> {
-        let function_arguments: FunctionArguments = proto.function_arguments.try_into()?;
-        let inputs_ownership = from_proto_ownership(proto.inputs_ownership);
-        let outputs_ownership = from_proto_ownership(proto.outputs_ownership);
-        let assigned_inputs = from_proto_file_ids(proto.assigned_inputs)?;
-        let assigned_outputs = from_proto_file_ids(proto.assigned_outputs)?;
-        let status = i32_to_task_status(proto.status)?;
-        let function_id = proto.function_id.try_into()?;
-        let task_id = proto.task_id.try_into()?;
-        let result = proto.result.try_into()?;
+    fn try_from(proto: proto::GetTaskScheduleResponse) -> Result<Self> {
+        let approval_receipts = proto
+            .approval_receipts
+            .into_iter()
+            .map(|receipt| receipt.try_into())
+            .collect::<Result<Vec<ApprovalReceipt>>>()?;
+        let task_ids = proto
+            .task_ids
+            .into_iter()
+            .map(|task_id| task_id.try_into())
+            .collect::<Result<Vec<ExternalID>>>()?;
+        let spec = ScheduleSpec {
+            start_at: proto.start_at,
+            interval: proto.interval,
+            max_runs: Some(proto.max_runs).filter(|runs| *runs > 0),
+        };
 
         let ret = Self {
-            task_id,
-            creator: proto.creator.into(),
-            function_id,
-            function_owner: proto.function_owner.into(),
-            function_arguments,
-            inputs_ownership,
-            outputs_ownership,
+            template_id: proto.template_id.try_into()?,
+            spec,
+            inputs: from_proto_file_ids(proto.inputs)?,
             participants: UserList::new(proto.participants),
             approved_users: UserList::new(proto.approved_users),
-            assigned_inputs,
-            assigned_outputs,
-            status,
-            result,
+            approval_receipts,
+            task_ids,
+            runs: proto.runs,
+            canceled: proto.canceled,
         };
 
         Ok(ret)
     }
 }
 
-impl From<GetTaskResponse> for proto::GetTaskResponse {
-    fn from(response: GetTaskResponse) -> Self {
-        let function_arguments = response.function_arguments.into_string();
-        let inputs_ownership = to_proto_ownership(response.inputs_ownership);
-        let outputs_ownership = to_proto_ownership(response.outputs_ownership);
-        let assigned_inputs = to_proto_file_ids(response.assigned_inputs);
-        let assigned_outputs = to_proto_file_ids(response.assigned_outputs);
-        let status = i32_from_task_status(response.status);
+impl From<GetTaskScheduleResponse> for proto::GetTaskScheduleResponse {
+    fn from(response: GetTaskScheduleResponse) -> Self {
         Self {
-            task_id: response.task_id.to_string(),
-            creator: response.creator.to_string(),
-            function_id: response.function_id.to_string(),
-            function_owner: response.function_owner.to_string(),
-            function_arguments,
-            inputs_ownership,
-            outputs_ownership,
+            template_id: response.template_id.to_string(),
+            start_at: response.spec.start_at,
+            interval: response.spec.interval,
+            max_runs: response.spec.max_runs.unwrap_or_default(),
+            inputs: to_proto_file_ids(response.inputs),
             participants: response.participants.into(),
             approved_users: response.approved_users.into(),
-            assigned_inputs,
-            assigned_outputs,
-            status,
-            result: Some(response.result.into()),
+            approval_receipts: response
+                .approval_receipts
+                .into_iter()
+                .map(|receipt| receipt.into())
+                .collect(),
+            task_ids: response
+                .task_ids
+                .into_iter()
+                .map(|task_id| task_id.to_string())
+                .collect(),
+            runs: response.runs,
+            canceled: response.canceled,
         }
     }
 }
 
-impl std::convert::TryFrom<proto::AssignDataRequest> for AssignDataRequest {
+impl std::convert::TryFrom<proto::EstimateTaskRequest> for EstimateTaskRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::AssignDataRequest) -> Result<Self> {
-        let inputs = from_proto_file_ids(proto.inputs)?;
-        let outputs = from_proto_file_ids(proto.outputs)?;
-        let task_id = proto.task_id.try_into()?;
-        let ret = Self {
-            task_id,
-            inputs,
-            outputs,
-        };
-
-        Ok(ret)
+    fn try_from(proto: proto::EstimateTaskRequest) -> Result<Self> {
+        Ok(Self {
+            function_id: proto.function_id.try_into()?,
+            input_size: proto.input_size,
+            priority: from_proto_priority(&proto.priority)?,
+        })
     }
 }
 
-impl From<AssignDataRequest> for proto::AssignDataRequest {
-    fn from(request: AssignDataRequest) -> Self {
-        let inputs = to_proto_file_ids(request.inputs);
-        let outputs = to_proto_file_ids(request.outputs);
+impl From<EstimateTaskRequest> for proto::EstimateTaskRequest {
+    fn from(request: EstimateTaskRequest) -> Self {
         Self {
-            task_id: request.task_id.to_string(),
-            inputs,
-            outputs,
+            function_id: request.function_id.to_string(),
+            input_size: request.input_size,
+            priority: request.priority.to_string(),
         }
     }
 }
 
-impl std::convert::TryFrom<proto::AssignDataResponse> for AssignDataResponse {
+impl std::convert::TryFrom<proto::EstimateTaskResponse> for EstimateTaskResponse {
     type Error = Error;
 
-    fn try_from(_proto: proto::AssignDataResponse) -> Result<Self> {
-        Ok(AssignDataResponse)
+    fn try_from(proto: proto::EstimateTaskResponse) -> Result<Self> {
+        Ok(Self {
+            run_time: proto.run_time,
+            queue_wait: proto.queue_wait,
+            runs: proto.runs,
+            live_workers: proto.live_workers,
+            backlog_taken_at: proto.backlog_taken_at,
+        })
     }
 }
 
-impl From<AssignDataResponse> for proto::AssignDataResponse {
-    fn from(_response: AssignDataResponse) -> Self {
-        Self {}
+impl From<EstimateTaskResponse> for proto::EstimateTaskResponse {
+    fn from(response: EstimateTaskResponse) -> Self {
+        Self {
+            run_time: response.run_time,
+            queue_wait: response.queue_wait,
+            runs: response.runs,
+            live_workers: response.live_workers,
+            backlog_taken_at: response.backlog_taken_at,
+        }
     }
 }
 
-impl std::convert::TryFrom<proto::ApproveTaskRequest> for ApproveTaskRequest {
+impl std::convert::TryFrom<proto::GetPlatformInfoRequest> for GetPlatformInfoRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::ApproveTaskRequest) -> Result<Self> {
-        let task_id = proto.task_id.try_into()?;
-        let ret = Self { task_id };
-
-        Ok(ret)
+    fn try_from(_proto: proto::GetPlatformInfoRequest) -> Result<Self> {
+        Ok(Self {})
     }
 }
 
-impl From<ApproveTaskRequest> for proto::ApproveTaskRequest {
-    fn from(request: ApproveTaskRequest) -> Self {
-        Self {
-            task_id: request.task_id.to_string(),
-        }
+impl From<GetPlatformInfoRequest> for proto::GetPlatformInfoRequest {
+    fn from(_request: GetPlatformInfoRequest) -> Self {
+        Self {}
     }
 }
 
-impl std::convert::TryFrom<proto::ApproveTaskResponse> for ApproveTaskResponse {
+impl std::convert::TryFrom<proto::GetPlatformInfoResponse> for GetPlatformInfoResponse {
     type Error = Error;
 
-    fn try_from(_proto: proto::ApproveTaskResponse) -> Result<Self> {
-        Ok(ApproveTaskResponse)
+    fn try_from(proto: proto::GetPlatformInfoResponse) -> Result<Self> {
+        Ok(Self {
+            enclave_info: proto.enclave_info,
+            auditor_signatures: proto.auditor_signatures,
+        })
     }
 }
 
-impl From<ApproveTaskResponse> for proto::ApproveTaskResponse {
-    fn from(_response: ApproveTaskResponse) -> Self {
-        Self {}
+impl From<GetPlatformInfoResponse> for proto::GetPlatformInfoResponse {
+    fn from(response: GetPlatformInfoResponse) -> Self {
+        Self {
+            enclave_info: response.enclave_info,
+            auditor_signatures: response.auditor_signatures,
+        }
     }
 }
 
-impl std::convert::TryFrom<proto::InvokeTaskRequest> for InvokeTaskRequest {
+impl std::convert::TryFrom<proto::RegisterSigningKeyRequest> for RegisterSigningKeyRequest {
     type Error = Error;
 
-    fn try_from(proto: proto::InvokeTaskRequest) -> Result<Self> {
-        let task_id = proto.task_id.try_into()?;
-        let ret = Self { task_id };
-
-        Ok(ret)
+    fn try_from(proto: proto::RegisterSigningKeyRequest) -> Result<Self> {
+        Ok(Self {
+            public_key: proto.public_key,
+        })
     }
 }
 
-impl From<InvokeTaskRequest> for proto::InvokeTaskRequest {
-    fn from(request: InvokeTaskRequest) -> Self {
+impl From<RegisterSigningKeyRequest> for proto::RegisterSigningKeyRequest {
+    fn from(request: RegisterSigningKeyRequest) -> Self {
         Self {
-            task_id: request.task_id.to_string(),
+            public_key: request.public_key,
         }
     }
 }
 
-impl std::convert::TryFrom<proto::InvokeTaskResponse> for InvokeTaskResponse {
+impl std::convert::TryFrom<proto::RegisterSigningKeyResponse> for RegisterSigningKeyResponse {
     type Error = Error;
 
-    fn try_from(_proto: proto::InvokeTaskResponse) -> Result<Self> {
-        Ok(InvokeTaskResponse)
+    fn try_from(_proto: proto::RegisterSigningKeyResponse) -> Result<Self> {
+        Ok(RegisterSigningKeyResponse)
     }
 }
 
-impl From<InvokeTaskResponse> for proto::InvokeTaskResponse {
-    fn from(_response: InvokeTaskResponse) -> Self {
+impl From<RegisterSigningKeyResponse> for proto::RegisterSigningKeyResponse {
+    fn from(_response: RegisterSigningKeyResponse) -> Self {
         Self {}
     }
 }
@@ -0,0 +1,402 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::teaclave_common::i32_from_task_status;
+use crate::teaclave_frontend_service_proto as v1_proto;
+use crate::teaclave_frontend_service_v2_proto as proto;
+use anyhow::{anyhow, bail, Error, Result};
+use core::convert::TryInto;
+use proto::function_argument::Value;
+use std::collections::HashMap;
+use std::prelude::v1::*;
+use teaclave_types::{FunctionArguments, TaskStatus};
+
+pub use proto::TeaclaveFrontendV2;
+pub use proto::TeaclaveFrontendV2Client;
+pub use proto::TeaclaveFrontendV2Request;
+pub use proto::TeaclaveFrontendV2Response;
+
+// Messages of version 2 are converted from and to the same types as version 1,
+// so that the handlers of both versions forward them to the management
+// service alike.
+pub type RegisterInputFileRequest = crate::teaclave_frontend_service::RegisterInputFileRequest;
+pub type RegisterInputFileResponse = crate::teaclave_frontend_service::RegisterInputFileResponse;
+pub type RegisterOutputFileRequest = crate::teaclave_frontend_service::RegisterOutputFileRequest;
+pub type RegisterOutputFileResponse = crate::teaclave_frontend_service::RegisterOutputFileResponse;
+pub type UpdateInputFileRequest = crate::teaclave_frontend_service::UpdateInputFileRequest;
+pub type UpdateInputFileResponse = crate::teaclave_frontend_service::UpdateInputFileResponse;
+pub type RotateInputFileKeyRequest = crate::teaclave_frontend_service::RotateInputFileKeyRequest;
+pub type RotateInputFileKeyResponse = crate::teaclave_frontend_service::RotateInputFileKeyResponse;
+pub type UpdateOutputFileRequest = crate::teaclave_frontend_service::UpdateOutputFileRequest;
+pub type UpdateOutputFileResponse = crate::teaclave_frontend_service::UpdateOutputFileResponse;
+pub type UpdateOutputUrlRequest = crate::teaclave_frontend_service::UpdateOutputUrlRequest;
+pub type UpdateOutputUrlResponse = crate::teaclave_frontend_service::UpdateOutputUrlResponse;
+pub type RegisterFusionOutputRequest =
+    crate::teaclave_frontend_service::RegisterFusionOutputRequest;
+pub type RegisterFusionOutputResponse =
+    crate::teaclave_frontend_service::RegisterFusionOutputResponse;
+pub type RegisterInputFromOutputRequest =
+    crate::teaclave_frontend_service::RegisterInputFromOutputRequest;
+pub type RegisterInputFromOutputResponse =
+    crate::teaclave_frontend_service::RegisterInputFromOutputResponse;
+pub type GetOutputFileRequest = crate::teaclave_frontend_service::GetOutputFileRequest;
+pub type GetOutputFileResponse = crate::teaclave_frontend_service::GetOutputFileResponse;
+pub type GetInputFileRequest = crate::teaclave_frontend_service::GetInputFileRequest;
+pub type GetInputFileResponse = crate::teaclave_frontend_service::GetInputFileResponse;
+pub type DeleteInputFileRequest = crate::teaclave_frontend_service::DeleteInputFileRequest;
+pub type DeleteInputFileResponse = crate::teaclave_frontend_service::DeleteInputFileResponse;
+pub type DeleteOutputFileRequest = crate::teaclave_frontend_service::DeleteOutputFileRequest;
+pub type DeleteOutputFileResponse = crate::teaclave_frontend_service::DeleteOutputFileResponse;
+pub type RegisterFunctionRequest = crate::teaclave_frontend_service::RegisterFunctionRequest;
+pub type RegisterFunctionResponse = crate::teaclave_frontend_service::RegisterFunctionResponse;
+pub type GetFunctionRequest = crate::teaclave_frontend_service::GetFunctionRequest;
+pub type GetFunctionResponse = crate::teaclave_frontend_service::GetFunctionResponse;
+pub type CreateTaskRequest = crate::teaclave_frontend_service::CreateTaskRequest;
+pub type CreateTaskResponse = crate::teaclave_frontend_service::CreateTaskResponse;
+pub type GetTaskRequest = crate::teaclave_frontend_service::GetTaskRequest;
+pub type GetTaskResponse = crate::teaclave_frontend_service::GetTaskResponse;
+pub type ListTasksRequest = crate::teaclave_frontend_service::ListTasksRequest;
+pub type ListTasksResponse = crate::teaclave_frontend_service::ListTasksResponse;
+pub type ListPendingApprovalsRequest =
+    crate::teaclave_frontend_service::ListPendingApprovalsRequest;
+pub type ListPendingApprovalsResponse =
+    crate::teaclave_frontend_service::ListPendingApprovalsResponse;
+pub type ListFunctionsRequest = crate::teaclave_frontend_service::ListFunctionsRequest;
+pub type ListFunctionsResponse = crate::teaclave_frontend_service::ListFunctionsResponse;
+pub type ListFilesRequest = crate::teaclave_frontend_service::ListFilesRequest;
+pub type ListFilesResponse = crate::teaclave_frontend_service::ListFilesResponse;
+pub type AssignDataRequest = crate::teaclave_frontend_service::AssignDataRequest;
+pub type AssignDataResponse = crate::teaclave_frontend_service::AssignDataResponse;
+pub type ApproveTaskRequest = crate::teaclave_frontend_service::ApproveTaskRequest;
+pub type ApproveTaskResponse = crate::teaclave_frontend_service::ApproveTaskResponse;
+pub type InvokeTaskRequest = crate::teaclave_frontend_service::InvokeTaskRequest;
+pub type InvokeTaskResponse = crate::teaclave_frontend_service::InvokeTaskResponse;
+pub type GetTaskManifestRequest = crate::teaclave_frontend_service::GetTaskManifestRequest;
+pub type GetTaskManifestResponse = crate::teaclave_frontend_service::GetTaskManifestResponse;
+pub type GetTaskReturnValueRequest = crate::teaclave_frontend_service::GetTaskReturnValueRequest;
+pub type GetTaskReturnValueResponse = crate::teaclave_frontend_service::GetTaskReturnValueResponse;
+pub type GetTaskKeyOfferRequest = crate::teaclave_frontend_service::GetTaskKeyOfferRequest;
+pub type GetTaskKeyOfferResponse = crate::teaclave_frontend_service::GetTaskKeyOfferResponse;
+pub type PutTaskKeysRequest = crate::teaclave_frontend_service::PutTaskKeysRequest;
+pub type PutTaskKeysResponse = crate::teaclave_frontend_service::PutTaskKeysResponse;
+pub type GetDataLineageRequest = crate::teaclave_frontend_service::GetDataLineageRequest;
+pub type GetDataLineageResponse = crate::teaclave_frontend_service::GetDataLineageResponse;
+pub type RegisterApprovalPolicyRequest =
+    crate::teaclave_frontend_service::RegisterApprovalPolicyRequest;
+pub type RegisterApprovalPolicyResponse =
+    crate::teaclave_frontend_service::RegisterApprovalPolicyResponse;
+pub type RevokeApprovalPolicyRequest =
+    crate::teaclave_frontend_service::RevokeApprovalPolicyRequest;
+pub type RevokeApprovalPolicyResponse =
+    crate::teaclave_frontend_service::RevokeApprovalPolicyResponse;
+pub type RegisterModelRequest = crate::teaclave_frontend_service::RegisterModelRequest;
+pub type RegisterModelResponse = crate::teaclave_frontend_service::RegisterModelResponse;
+pub type GetModelRequest = crate::teaclave_frontend_service::GetModelRequest;
+pub type GetModelResponse = crate::teaclave_frontend_service::GetModelResponse;
+pub type CreateTaskShareLinkRequest = crate::teaclave_frontend_service::CreateTaskShareLinkRequest;
+pub type CreateTaskShareLinkResponse =
+    crate::teaclave_frontend_service::CreateTaskShareLinkResponse;
+pub type GetSharedTaskRequest = crate::teaclave_frontend_service::GetSharedTaskRequest;
+pub type GetSharedTaskResponse = crate::teaclave_frontend_service::GetSharedTaskResponse;
+pub type ArchiveTaskRequest = crate::teaclave_frontend_service::ArchiveTaskRequest;
+pub type ArchiveTaskResponse = crate::teaclave_frontend_service::ArchiveTaskResponse;
+pub type RestoreArchivedTaskRequest = crate::teaclave_frontend_service::RestoreArchivedTaskRequest;
+pub type RestoreArchivedTaskResponse =
+    crate::teaclave_frontend_service::RestoreArchivedTaskResponse;
+pub type ExportStorageSnapshotRequest =
+    crate::teaclave_frontend_service::ExportStorageSnapshotRequest;
+pub type ExportStorageSnapshotResponse =
+    crate::teaclave_frontend_service::ExportStorageSnapshotResponse;
+pub type RestoreStorageSnapshotRequest =
+    crate::teaclave_frontend_service::RestoreStorageSnapshotRequest;
+pub type RestoreStorageSnapshotResponse =
+    crate::teaclave_frontend_service::RestoreStorageSnapshotResponse;
+pub type PromoteStorageReplicaRequest =
+    crate::teaclave_frontend_service::PromoteStorageReplicaRequest;
+pub type PromoteStorageReplicaResponse =
+    crate::teaclave_frontend_service::PromoteStorageReplicaResponse;
+pub type CancelTaskRequest = crate::teaclave_frontend_service::CancelTaskRequest;
+pub type CancelTaskResponse = crate::teaclave_frontend_service::CancelTaskResponse;
+pub type GetTaskUploadSlotsRequest = crate::teaclave_frontend_service::GetTaskUploadSlotsRequest;
+pub type GetTaskUploadSlotsResponse = crate::teaclave_frontend_service::GetTaskUploadSlotsResponse;
+pub type RejectTaskRequest = crate::teaclave_frontend_service::RejectTaskRequest;
+pub type RejectTaskResponse = crate::teaclave_frontend_service::RejectTaskResponse;
+pub type GetActivityFeedRequest = crate::teaclave_frontend_service::GetActivityFeedRequest;
+pub type GetActivityFeedResponse = crate::teaclave_frontend_service::GetActivityFeedResponse;
+pub type GetTaskResultStreamRequest = crate::teaclave_frontend_service::GetTaskResultStreamRequest;
+pub type GetTaskResultStreamResponse =
+    crate::teaclave_frontend_service::GetTaskResultStreamResponse;
+pub type RegisterTaskTemplateRequest =
+    crate::teaclave_frontend_service::RegisterTaskTemplateRequest;
+pub type RegisterTaskTemplateResponse =
+    crate::teaclave_frontend_service::RegisterTaskTemplateResponse;
+pub type CreateTaskFromTemplateRequest =
+    crate::teaclave_frontend_service::CreateTaskFromTemplateRequest;
+pub type CreateTaskFromTemplateResponse =
+    crate::teaclave_frontend_service::CreateTaskFromTemplateResponse;
+pub type CreateTasksBatchRequest = crate::teaclave_frontend_service::CreateTasksBatchRequest;
+pub type CreateTasksBatchResponse = crate::teaclave_frontend_service::CreateTasksBatchResponse;
+pub type TestFunctionRequest = crate::teaclave_frontend_service::TestFunctionRequest;
+pub type TestFunctionResponse = crate::teaclave_frontend_service::TestFunctionResponse;
+pub type RegisterTaskScheduleRequest =
+    crate::teaclave_frontend_service::RegisterTaskScheduleRequest;
+pub type RegisterTaskScheduleResponse =
+    crate::teaclave_frontend_service::RegisterTaskScheduleResponse;
+pub type ApproveTaskScheduleRequest = crate::teaclave_frontend_service::ApproveTaskScheduleRequest;
+pub type ApproveTaskScheduleResponse =
+    crate::teaclave_frontend_service::ApproveTaskScheduleResponse;
+pub type CancelTaskScheduleRequest = crate::teaclave_frontend_service::CancelTaskScheduleRequest;
+pub type CancelTaskScheduleResponse = crate::teaclave_frontend_service::CancelTaskScheduleResponse;
+pub type GetTaskScheduleRequest = crate::teaclave_frontend_service::GetTaskScheduleRequest;
+pub type GetTaskScheduleResponse = crate::teaclave_frontend_service::GetTaskScheduleResponse;
+pub type EstimateTaskRequest = crate::teaclave_frontend_service::EstimateTaskRequest;
+pub type EstimateTaskResponse = crate::teaclave_frontend_service::EstimateTaskResponse;
+pub type GetPlatformInfoRequest = crate::teaclave_frontend_service::GetPlatformInfoRequest;
+pub type GetPlatformInfoResponse = crate::teaclave_frontend_service::GetPlatformInfoResponse;
+pub type RegisterSigningKeyRequest = crate::teaclave_frontend_service::RegisterSigningKeyRequest;
+pub type RegisterSigningKeyResponse = crate::teaclave_frontend_service::RegisterSigningKeyResponse;
+
+fn from_proto_arguments(
+    arguments: HashMap<String, proto::FunctionArgument>,
+) -> Result<FunctionArguments> {
+    let mut map = serde_json::Map::new();
+    for (name, argument) in arguments {
+        let value = match argument.value {
+            Some(Value::StringValue(s)) => s.into(),
+            Some(Value::IntValue(i)) => i.into(),
+            Some(Value::FloatValue(f)) => serde_json::Number::from_f64(f)
+                .ok_or_else(|| anyhow!("Invalid float argument: {}", name))?
+                .into(),
+            Some(Value::BoolValue(b)) => b.into(),
+            Some(Value::JsonValue(json)) => serde_json::from_str(&json)?,
+            None => bail!("Missing argument value: {}", name),
+        };
+        map.insert(name, value);
+    }
+    FunctionArguments::from_json(serde_json::Value::Object(map))
+}
+
+fn to_proto_arguments(arguments: &FunctionArguments) -> HashMap<String, proto::FunctionArgument> {
+    arguments
+        .inner()
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                serde_json::Value::String(s) => Value::StringValue(s.to_owned()),
+                serde_json::Value::Bool(b) => Value::BoolValue(*b),
+                serde_json::Value::Number(n) if n.is_i64() => {
+                    Value::IntValue(n.as_i64().unwrap_or_default())
+                }
+                serde_json::Value::Number(n) if n.is_f64() => {
+                    Value::FloatValue(n.as_f64().unwrap_or_default())
+                }
+                // Lists, objects, nulls and integers beyond i64
+                _ => Value::JsonValue(value.to_string()),
+            };
+            let argument = proto::FunctionArgument { value: Some(value) };
+            (name.to_owned(), argument)
+        })
+        .collect()
+}
+
+fn task_status_to_name(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Created => "Created",
+        TaskStatus::DataAssigned => "DataAssigned",
+        TaskStatus::Approved => "Approved",
+        TaskStatus::Staged => "Staged",
+        TaskStatus::Running => "Running",
+        TaskStatus::Finished => "Finished",
+        TaskStatus::Canceled => "Canceled",
+        TaskStatus::Expired => "Expired",
+        TaskStatus::Rejected => "Rejected",
+    }
+}
+
+fn task_status_from_name(name: &str) -> Result<TaskStatus> {
+    let status = match name {
+        "Created" => TaskStatus::Created,
+        "DataAssigned" => TaskStatus::DataAssigned,
+        "Approved" => TaskStatus::Approved,
+        "Staged" => TaskStatus::Staged,
+        "Running" => TaskStatus::Running,
+        "Finished" => TaskStatus::Finished,
+        "Canceled" => TaskStatus::Canceled,
+        "Expired" => TaskStatus::Expired,
+        "Rejected" => TaskStatus::Rejected,
+        _ => bail!("Invalid task status: {}", name),
+    };
+    Ok(status)
+}
+
+impl std::convert::TryFrom<proto::CreateTaskRequest> for CreateTaskRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::CreateTaskRequest) -> Result<Self> {
+        let function_arguments = from_proto_arguments(proto.function_arguments)?;
+        let request = v1_proto::CreateTaskRequest {
+            function_id: proto.function_id,
+            function_arguments: function_arguments.into_string(),
+            executor: proto.executor,
+            inputs_ownership: proto.inputs_ownership,
+            outputs_ownership: proto.outputs_ownership,
+            labels: proto.labels,
+            workflow_id: proto.workflow_id,
+            entry_point: proto.entry_point,
+            reuse_result: proto.reuse_result,
+            key_exchange: proto.key_exchange,
+            expires_in: proto.expires_in,
+            approval_window: proto.approval_window,
+            input_dependencies: proto.input_dependencies,
+            priority: proto.priority,
+            resource_limits: proto.resource_limits,
+            timeout: proto.timeout,
+            retry_policy: proto.retry_policy,
+            worker_measurements: proto.worker_measurements,
+            worker_signers: proto.worker_signers,
+            federation_peer: proto.federation_peer,
+            signature: proto.signature,
+        };
+        request.try_into()
+    }
+}
+
+impl From<CreateTaskRequest> for proto::CreateTaskRequest {
+    fn from(request: CreateTaskRequest) -> Self {
+        let function_arguments = to_proto_arguments(&request.function_arguments);
+        let request = v1_proto::CreateTaskRequest::from(request);
+
+        Self {
+            function_id: request.function_id,
+            function_arguments,
+            executor: request.executor,
+            inputs_ownership: request.inputs_ownership,
+            outputs_ownership: request.outputs_ownership,
+            labels: request.labels,
+            workflow_id: request.workflow_id,
+            entry_point: request.entry_point,
+            reuse_result: request.reuse_result,
+            key_exchange: request.key_exchange,
+            expires_in: request.expires_in,
+            approval_window: request.approval_window,
+            input_dependencies: request.input_dependencies,
+            priority: request.priority,
+            resource_limits: request.resource_limits,
+            timeout: request.timeout,
+            retry_policy: request.retry_policy,
+            worker_measurements: request.worker_measurements,
+            worker_signers: request.worker_signers,
+            federation_peer: request.federation_peer,
+            signature: request.signature,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetTaskResponse> for GetTaskResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetTaskResponse) -> Result<Self> {
+        let function_arguments = from_proto_arguments(proto.function_arguments)?;
+        let status = task_status_from_name(&proto.status)?;
+        let response = v1_proto::GetTaskResponse {
+            task_id: proto.task_id,
+            creator: proto.creator,
+            function_id: proto.function_id,
+            function_owner: proto.function_owner,
+            function_arguments: function_arguments.into_string(),
+            inputs_ownership: proto.inputs_ownership,
+            outputs_ownership: proto.outputs_ownership,
+            participants: proto.participants,
+            approved_users: proto.approved_users,
+            assigned_inputs: proto.assigned_inputs,
+            assigned_outputs: proto.assigned_outputs,
+            status: i32_from_task_status(status),
+            result: proto.result,
+            labels: proto.labels,
+            approval_receipts: proto.approval_receipts,
+            entry_point: proto.entry_point,
+            reuse_result: proto.reuse_result,
+            function_capabilities: proto.function_capabilities,
+            key_exchange: proto.key_exchange,
+            worker_measurements: proto.worker_measurements,
+            expires_at: proto.expires_at,
+            rejected_by: proto.rejected_by,
+            rejection_reason: proto.rejection_reason,
+            approval_expires_at: proto.approval_expires_at,
+            input_dependencies: proto.input_dependencies,
+            staging_deferred: proto.staging_deferred,
+            priority: proto.priority,
+            resource_limits: proto.resource_limits,
+            timeout: proto.timeout,
+            retry_policy: proto.retry_policy,
+            retry_count: proto.retry_count,
+            worker_signers: proto.worker_signers,
+            federation_peer: proto.federation_peer,
+            function_post_processing: proto.function_post_processing,
+            spec_hash: proto.spec_hash,
+            request_signatures: proto.request_signatures,
+        };
+        response.try_into()
+    }
+}
+
+impl From<GetTaskResponse> for proto::GetTaskResponse {
+    fn from(response: GetTaskResponse) -> Self {
+        let function_arguments = to_proto_arguments(&response.function_arguments);
+        let status = task_status_to_name(&response.status).to_string();
+        let response = v1_proto::GetTaskResponse::from(response);
+
+        Self {
+            task_id: response.task_id,
+            creator: response.creator,
+            function_id: response.function_id,
+            function_owner: response.function_owner,
+            function_arguments,
+            inputs_ownership: response.inputs_ownership,
+            outputs_ownership: response.outputs_ownership,
+            participants: response.participants,
+            approved_users: response.approved_users,
+            assigned_inputs: response.assigned_inputs,
+            assigned_outputs: response.assigned_outputs,
+            status,
+            result: response.result,
+            labels: response.labels,
+            approval_receipts: response.approval_receipts,
+            entry_point: response.entry_point,
+            reuse_result: response.reuse_result,
+            function_capabilities: response.function_capabilities,
+            key_exchange: response.key_exchange,
+            worker_measurements: response.worker_measurements,
+            expires_at: response.expires_at,
+            rejected_by: response.rejected_by,
+            rejection_reason: response.rejection_reason,
+            approval_expires_at: response.approval_expires_at,
+            input_dependencies: response.input_dependencies,
+            staging_deferred: response.staging_deferred,
+            priority: response.priority,
+            resource_limits: response.resource_limits,
+            timeout: response.timeout,
+            retry_policy: response.retry_policy,
+            retry_count: response.retry_count,
+            worker_signers: response.worker_signers,
+            federation_peer: response.federation_peer,
+            function_post_processing: response.function_post_processing,
+            spec_hash: response.spec_hash,
+            request_signatures: response.request_signatures,
+        }
+    }
+}
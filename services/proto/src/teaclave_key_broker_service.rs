@@ -0,0 +1,99 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+use crate::teaclave_key_broker_service_proto as proto;
+use anyhow::{anyhow, Error, Result};
+use core::convert::TryInto;
+use std::prelude::v1::*;
+use teaclave_rpc::into_request;
+use teaclave_types::FileCrypto;
+use uuid::Uuid;
+
+pub use proto::TeaclaveKeyBroker;
+pub use proto::TeaclaveKeyBrokerClient;
+pub use proto::TeaclaveKeyBrokerRequest;
+pub use proto::TeaclaveKeyBrokerResponse;
+
+#[into_request(TeaclaveKeyBrokerRequest::ReleaseKey)]
+#[derive(Debug)]
+pub struct ReleaseKeyRequest {
+    pub key_id: String,
+    pub task_id: Uuid,
+}
+
+impl ReleaseKeyRequest {
+    pub fn new(key_id: impl Into<String>, task_id: Uuid) -> Self {
+        Self {
+            key_id: key_id.into(),
+            task_id,
+        }
+    }
+}
+
+#[into_request(TeaclaveKeyBrokerResponse::ReleaseKey)]
+#[derive(Debug)]
+pub struct ReleaseKeyResponse {
+    pub crypto_info: FileCrypto,
+}
+
+impl ReleaseKeyResponse {
+    pub fn new(crypto_info: FileCrypto) -> Self {
+        Self { crypto_info }
+    }
+}
+
+impl std::convert::TryFrom<proto::ReleaseKeyRequest> for ReleaseKeyRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::ReleaseKeyRequest) -> Result<Self> {
+        let ret = Self {
+            key_id: proto.key_id,
+            task_id: Uuid::parse_str(&proto.task_id)?,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<ReleaseKeyRequest> for proto::ReleaseKeyRequest {
+    fn from(request: ReleaseKeyRequest) -> Self {
+        Self {
+            key_id: request.key_id,
+            task_id: request.task_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ReleaseKeyResponse> for ReleaseKeyResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::ReleaseKeyResponse) -> Result<Self> {
+        let crypto_info = proto
+            .crypto_info
+            .ok_or_else(|| anyhow!("missing crypto_info"))?
+            .try_into()?;
+
+        Ok(Self { crypto_info })
+    }
+}
+
+impl From<ReleaseKeyResponse> for proto::ReleaseKeyResponse {
+    fn from(response: ReleaseKeyResponse) -> Self {
+        Self {
+            crypto_info: Some(response.crypto_info.into()),
+        }
+    }
+}
@@ -16,6 +16,11 @@
 // under the License.
 
 use crate::teaclave_management_service_proto as proto;
+use anyhow::{Error, Result};
+use std::prelude::v1::*;
+use teaclave_rpc::into_request;
+use teaclave_types::{StagedTask, Storable, TaskState};
+use uuid::Uuid;
 
 pub use proto::TeaclaveManagement;
 pub use proto::TeaclaveManagementClient;
@@ -26,10 +31,14 @@ pub type RegisterInputFileRequest = crate::teaclave_frontend_service::RegisterIn
 pub type UpdateInputFileRequest = crate::teaclave_frontend_service::UpdateInputFileRequest;
 pub type RegisterInputFileResponse = crate::teaclave_frontend_service::RegisterInputFileResponse;
 pub type UpdateInputFileResponse = crate::teaclave_frontend_service::UpdateInputFileResponse;
+pub type RotateInputFileKeyRequest = crate::teaclave_frontend_service::RotateInputFileKeyRequest;
+pub type RotateInputFileKeyResponse = crate::teaclave_frontend_service::RotateInputFileKeyResponse;
 pub type RegisterOutputFileRequest = crate::teaclave_frontend_service::RegisterOutputFileRequest;
 pub type UpdateOutputFileRequest = crate::teaclave_frontend_service::UpdateOutputFileRequest;
 pub type RegisterOutputFileResponse = crate::teaclave_frontend_service::RegisterOutputFileResponse;
 pub type UpdateOutputFileResponse = crate::teaclave_frontend_service::UpdateOutputFileResponse;
+pub type UpdateOutputUrlRequest = crate::teaclave_frontend_service::UpdateOutputUrlRequest;
+pub type UpdateOutputUrlResponse = crate::teaclave_frontend_service::UpdateOutputUrlResponse;
 pub type RegisterFusionOutputRequest =
     crate::teaclave_frontend_service::RegisterFusionOutputRequest;
 pub type RegisterFusionOutputResponse =
@@ -40,6 +49,10 @@ pub type RegisterInputFromOutputResponse =
     crate::teaclave_frontend_service::RegisterInputFromOutputResponse;
 pub type GetInputFileRequest = crate::teaclave_frontend_service::GetInputFileRequest;
 pub type GetInputFileResponse = crate::teaclave_frontend_service::GetInputFileResponse;
+pub type DeleteInputFileRequest = crate::teaclave_frontend_service::DeleteInputFileRequest;
+pub type DeleteInputFileResponse = crate::teaclave_frontend_service::DeleteInputFileResponse;
+pub type DeleteOutputFileRequest = crate::teaclave_frontend_service::DeleteOutputFileRequest;
+pub type DeleteOutputFileResponse = crate::teaclave_frontend_service::DeleteOutputFileResponse;
 pub type GetOutputFileRequest = crate::teaclave_frontend_service::GetOutputFileRequest;
 pub type GetOutputFileResponse = crate::teaclave_frontend_service::GetOutputFileResponse;
 pub type RegisterFunctionRequest = crate::teaclave_frontend_service::RegisterFunctionRequest;
@@ -50,9 +63,228 @@ pub type CreateTaskRequest = crate::teaclave_frontend_service::CreateTaskRequest
 pub type CreateTaskResponse = crate::teaclave_frontend_service::CreateTaskResponse;
 pub type GetTaskRequest = crate::teaclave_frontend_service::GetTaskRequest;
 pub type GetTaskResponse = crate::teaclave_frontend_service::GetTaskResponse;
+pub type ListTasksRequest = crate::teaclave_frontend_service::ListTasksRequest;
+pub type ListTasksResponse = crate::teaclave_frontend_service::ListTasksResponse;
+pub type ListPendingApprovalsRequest =
+    crate::teaclave_frontend_service::ListPendingApprovalsRequest;
+pub type ListPendingApprovalsResponse =
+    crate::teaclave_frontend_service::ListPendingApprovalsResponse;
+pub type ListFunctionsRequest = crate::teaclave_frontend_service::ListFunctionsRequest;
+pub type ListFunctionsResponse = crate::teaclave_frontend_service::ListFunctionsResponse;
+pub type ListFilesRequest = crate::teaclave_frontend_service::ListFilesRequest;
+pub type ListFilesResponse = crate::teaclave_frontend_service::ListFilesResponse;
 pub type AssignDataRequest = crate::teaclave_frontend_service::AssignDataRequest;
 pub type AssignDataResponse = crate::teaclave_frontend_service::AssignDataResponse;
 pub type ApproveTaskRequest = crate::teaclave_frontend_service::ApproveTaskRequest;
 pub type ApproveTaskResponse = crate::teaclave_frontend_service::ApproveTaskResponse;
 pub type InvokeTaskRequest = crate::teaclave_frontend_service::InvokeTaskRequest;
 pub type InvokeTaskResponse = crate::teaclave_frontend_service::InvokeTaskResponse;
+pub type GetTaskManifestRequest = crate::teaclave_frontend_service::GetTaskManifestRequest;
+pub type GetTaskManifestResponse = crate::teaclave_frontend_service::GetTaskManifestResponse;
+pub type GetTaskReturnValueRequest = crate::teaclave_frontend_service::GetTaskReturnValueRequest;
+pub type GetTaskReturnValueResponse = crate::teaclave_frontend_service::GetTaskReturnValueResponse;
+pub type GetTaskKeyOfferRequest = crate::teaclave_frontend_service::GetTaskKeyOfferRequest;
+pub type GetTaskKeyOfferResponse = crate::teaclave_frontend_service::GetTaskKeyOfferResponse;
+pub type PutTaskKeysRequest = crate::teaclave_frontend_service::PutTaskKeysRequest;
+pub type PutTaskKeysResponse = crate::teaclave_frontend_service::PutTaskKeysResponse;
+pub type GetDataLineageRequest = crate::teaclave_frontend_service::GetDataLineageRequest;
+pub type GetDataLineageResponse = crate::teaclave_frontend_service::GetDataLineageResponse;
+pub type RegisterApprovalPolicyRequest =
+    crate::teaclave_frontend_service::RegisterApprovalPolicyRequest;
+pub type RegisterApprovalPolicyResponse =
+    crate::teaclave_frontend_service::RegisterApprovalPolicyResponse;
+pub type RevokeApprovalPolicyRequest =
+    crate::teaclave_frontend_service::RevokeApprovalPolicyRequest;
+pub type RevokeApprovalPolicyResponse =
+    crate::teaclave_frontend_service::RevokeApprovalPolicyResponse;
+pub type RegisterModelRequest = crate::teaclave_frontend_service::RegisterModelRequest;
+pub type RegisterModelResponse = crate::teaclave_frontend_service::RegisterModelResponse;
+pub type GetModelRequest = crate::teaclave_frontend_service::GetModelRequest;
+pub type GetModelResponse = crate::teaclave_frontend_service::GetModelResponse;
+pub type CreateTaskShareLinkRequest = crate::teaclave_frontend_service::CreateTaskShareLinkRequest;
+pub type CreateTaskShareLinkResponse =
+    crate::teaclave_frontend_service::CreateTaskShareLinkResponse;
+pub type GetSharedTaskRequest = crate::teaclave_frontend_service::GetSharedTaskRequest;
+pub type GetSharedTaskResponse = crate::teaclave_frontend_service::GetSharedTaskResponse;
+pub type ArchiveTaskRequest = crate::teaclave_frontend_service::ArchiveTaskRequest;
+pub type ArchiveTaskResponse = crate::teaclave_frontend_service::ArchiveTaskResponse;
+pub type RestoreArchivedTaskRequest = crate::teaclave_frontend_service::RestoreArchivedTaskRequest;
+pub type RestoreArchivedTaskResponse =
+    crate::teaclave_frontend_service::RestoreArchivedTaskResponse;
+pub type ExportStorageSnapshotRequest =
+    crate::teaclave_frontend_service::ExportStorageSnapshotRequest;
+pub type ExportStorageSnapshotResponse =
+    crate::teaclave_frontend_service::ExportStorageSnapshotResponse;
+pub type RestoreStorageSnapshotRequest =
+    crate::teaclave_frontend_service::RestoreStorageSnapshotRequest;
+pub type RestoreStorageSnapshotResponse =
+    crate::teaclave_frontend_service::RestoreStorageSnapshotResponse;
+pub type PromoteStorageReplicaRequest =
+    crate::teaclave_frontend_service::PromoteStorageReplicaRequest;
+pub type PromoteStorageReplicaResponse =
+    crate::teaclave_frontend_service::PromoteStorageReplicaResponse;
+pub type CancelTaskRequest = crate::teaclave_frontend_service::CancelTaskRequest;
+pub type CancelTaskResponse = crate::teaclave_frontend_service::CancelTaskResponse;
+pub type GetTaskUploadSlotsRequest = crate::teaclave_frontend_service::GetTaskUploadSlotsRequest;
+pub type GetTaskUploadSlotsResponse = crate::teaclave_frontend_service::GetTaskUploadSlotsResponse;
+pub type RejectTaskRequest = crate::teaclave_frontend_service::RejectTaskRequest;
+pub type RejectTaskResponse = crate::teaclave_frontend_service::RejectTaskResponse;
+pub type GetActivityFeedRequest = crate::teaclave_frontend_service::GetActivityFeedRequest;
+pub type GetActivityFeedResponse = crate::teaclave_frontend_service::GetActivityFeedResponse;
+pub type GetTaskResultStreamRequest = crate::teaclave_frontend_service::GetTaskResultStreamRequest;
+pub type GetTaskResultStreamResponse =
+    crate::teaclave_frontend_service::GetTaskResultStreamResponse;
+pub type RegisterTaskTemplateRequest =
+    crate::teaclave_frontend_service::RegisterTaskTemplateRequest;
+pub type RegisterTaskTemplateResponse =
+    crate::teaclave_frontend_service::RegisterTaskTemplateResponse;
+pub type CreateTaskFromTemplateRequest =
+    crate::teaclave_frontend_service::CreateTaskFromTemplateRequest;
+pub type CreateTaskFromTemplateResponse =
+    crate::teaclave_frontend_service::CreateTaskFromTemplateResponse;
+pub type CreateTasksBatchRequest = crate::teaclave_frontend_service::CreateTasksBatchRequest;
+pub type CreateTasksBatchResponse = crate::teaclave_frontend_service::CreateTasksBatchResponse;
+pub type TestFunctionRequest = crate::teaclave_frontend_service::TestFunctionRequest;
+pub type TestFunctionResponse = crate::teaclave_frontend_service::TestFunctionResponse;
+pub type RegisterTaskScheduleRequest =
+    crate::teaclave_frontend_service::RegisterTaskScheduleRequest;
+pub type RegisterTaskScheduleResponse =
+    crate::teaclave_frontend_service::RegisterTaskScheduleResponse;
+pub type ApproveTaskScheduleRequest = crate::teaclave_frontend_service::ApproveTaskScheduleRequest;
+pub type ApproveTaskScheduleResponse =
+    crate::teaclave_frontend_service::ApproveTaskScheduleResponse;
+pub type CancelTaskScheduleRequest = crate::teaclave_frontend_service::CancelTaskScheduleRequest;
+pub type CancelTaskScheduleResponse = crate::teaclave_frontend_service::CancelTaskScheduleResponse;
+pub type GetTaskScheduleRequest = crate::teaclave_frontend_service::GetTaskScheduleRequest;
+pub type GetTaskScheduleResponse = crate::teaclave_frontend_service::GetTaskScheduleResponse;
+pub type EstimateTaskRequest = crate::teaclave_frontend_service::EstimateTaskRequest;
+pub type EstimateTaskResponse = crate::teaclave_frontend_service::EstimateTaskResponse;
+pub type RegisterSigningKeyRequest = crate::teaclave_frontend_service::RegisterSigningKeyRequest;
+pub type RegisterSigningKeyResponse = crate::teaclave_frontend_service::RegisterSigningKeyResponse;
+
+#[into_request(TeaclaveManagementRequest::DelegateTask)]
+#[derive(Debug)]
+pub struct DelegateTaskRequest {
+    pub origin: String,
+    pub task_state: TaskState,
+    pub staged_task: StagedTask,
+}
+
+impl DelegateTaskRequest {
+    pub fn new(origin: impl Into<String>, task_state: TaskState, staged_task: StagedTask) -> Self {
+        Self {
+            origin: origin.into(),
+            task_state,
+            staged_task,
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::DelegateTask)]
+#[derive(Debug)]
+pub struct DelegateTaskResponse;
+
+#[into_request(TeaclaveManagementRequest::GetDelegatedTask)]
+#[derive(Debug)]
+pub struct GetDelegatedTaskRequest {
+    pub origin: String,
+    pub task_id: Uuid,
+}
+
+impl GetDelegatedTaskRequest {
+    pub fn new(origin: impl Into<String>, task_id: Uuid) -> Self {
+        Self {
+            origin: origin.into(),
+            task_id,
+        }
+    }
+}
+
+#[into_request(TeaclaveManagementResponse::GetDelegatedTask)]
+#[derive(Debug)]
+pub struct GetDelegatedTaskResponse {
+    pub task_state: TaskState,
+}
+
+impl GetDelegatedTaskResponse {
+    pub fn new(task_state: TaskState) -> Self {
+        Self { task_state }
+    }
+}
+
+impl std::convert::TryFrom<proto::DelegateTaskRequest> for DelegateTaskRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::DelegateTaskRequest) -> Result<Self> {
+        let ret = Self {
+            origin: proto.origin,
+            task_state: TaskState::from_slice(&proto.task_state)?,
+            staged_task: StagedTask::from_slice(&proto.staged_task)?,
+        };
+        Ok(ret)
+    }
+}
+
+impl From<DelegateTaskRequest> for proto::DelegateTaskRequest {
+    fn from(request: DelegateTaskRequest) -> Self {
+        Self {
+            origin: request.origin,
+            task_state: request.task_state.to_vec().unwrap(),
+            staged_task: request.staged_task.to_vec().unwrap(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::DelegateTaskResponse> for DelegateTaskResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::DelegateTaskResponse) -> Result<Self> {
+        Ok(Self)
+    }
+}
+
+impl From<DelegateTaskResponse> for proto::DelegateTaskResponse {
+    fn from(_response: DelegateTaskResponse) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::GetDelegatedTaskRequest> for GetDelegatedTaskRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetDelegatedTaskRequest) -> Result<Self> {
+        let ret = Self {
+            origin: proto.origin,
+            task_id: Uuid::parse_str(&proto.task_id)?,
+        };
+        Ok(ret)
+    }
+}
+
+impl From<GetDelegatedTaskRequest> for proto::GetDelegatedTaskRequest {
+    fn from(request: GetDelegatedTaskRequest) -> Self {
+        Self {
+            origin: request.origin,
+            task_id: request.task_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetDelegatedTaskResponse> for GetDelegatedTaskResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetDelegatedTaskResponse) -> Result<Self> {
+        let ret = Self {
+            task_state: TaskState::from_slice(&proto.task_state)?,
+        };
+        Ok(ret)
+    }
+}
+
+impl From<GetDelegatedTaskResponse> for proto::GetDelegatedTaskResponse {
+    fn from(response: GetDelegatedTaskResponse) -> Self {
+        Self {
+            task_state: response.task_state.to_vec().unwrap(),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+use crate::teaclave_policy_decision_service_proto as proto;
+use anyhow::{Error, Result};
+use std::prelude::v1::*;
+use teaclave_rpc::into_request;
+
+pub use proto::TeaclavePolicyDecision;
+pub use proto::TeaclavePolicyDecisionClient;
+pub use proto::TeaclavePolicyDecisionRequest;
+pub use proto::TeaclavePolicyDecisionResponse;
+
+#[into_request(TeaclavePolicyDecisionRequest::Decide)]
+#[derive(Debug)]
+pub struct DecideRequest {
+    pub decision: String,
+    pub subject: String,
+    pub object: String,
+}
+
+impl DecideRequest {
+    pub fn new(
+        decision: impl Into<String>,
+        subject: impl Into<String>,
+        object: impl Into<String>,
+    ) -> Self {
+        Self {
+            decision: decision.into(),
+            subject: subject.into(),
+            object: object.into(),
+        }
+    }
+}
+
+#[into_request(TeaclavePolicyDecisionResponse::Decide)]
+#[derive(Debug)]
+pub struct DecideResponse {
+    pub accept: bool,
+}
+
+impl DecideResponse {
+    pub fn new(accept: bool) -> Self {
+        Self { accept }
+    }
+}
+
+impl std::convert::TryFrom<proto::DecideRequest> for DecideRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::DecideRequest) -> Result<Self> {
+        let ret = Self {
+            decision: proto.decision,
+            subject: proto.subject,
+            object: proto.object,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<DecideRequest> for proto::DecideRequest {
+    fn from(request: DecideRequest) -> Self {
+        Self {
+            decision: request.decision,
+            subject: request.subject,
+            object: request.object,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::DecideResponse> for DecideResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::DecideResponse) -> Result<Self> {
+        Ok(Self {
+            accept: proto.accept,
+        })
+    }
+}
+
+impl From<DecideResponse> for proto::DecideResponse {
+    fn from(response: DecideResponse) -> Self {
+        Self {
+            accept: response.accept,
+        }
+    }
+}
@@ -30,7 +30,10 @@ pub use proto::TeaclaveSchedulerClient;
 pub use proto::TeaclaveSchedulerRequest;
 pub use proto::TeaclaveSchedulerResponse;
 use teaclave_rpc::into_request;
-use teaclave_types::{StagedTask, TaskFailure, TaskOutputs, TaskResult, TaskStatus};
+use teaclave_types::{
+    PrewarmTask, ReturnValueChunk, StagedTask, TaskFailure, TaskKeyExchange, TaskKeyOffer,
+    TaskOutputs, TaskResult, TaskStatus, TaskUploadSlots, TaskUsage, WorkerCapabilities,
+};
 use uuid::Uuid;
 
 #[into_request(TeaclaveSchedulerRequest::Subscribe)]
@@ -42,7 +45,22 @@ pub struct SubscribeResponse {
 }
 
 #[into_request(TeaclaveSchedulerRequest::PullTask)]
-pub struct PullTaskRequest {}
+#[derive(Default)]
+pub struct PullTaskRequest {
+    pub worker_id: Option<Uuid>,
+}
+
+impl PullTaskRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn worker_id(self, worker_id: Uuid) -> Self {
+        Self {
+            worker_id: Some(worker_id),
+        }
+    }
+}
 
 #[into_request(TeaclaveSchedulerResponse::PullTask)]
 #[derive(Debug)]
@@ -56,23 +74,45 @@ impl PullTaskResponse {
     }
 }
 
+#[into_request(TeaclaveSchedulerRequest::PullPrewarmTask)]
+pub struct PullPrewarmTaskRequest {}
+
+#[into_request(TeaclaveSchedulerResponse::PullPrewarmTask)]
+#[derive(Debug)]
+pub struct PullPrewarmTaskResponse {
+    pub prewarm_task: PrewarmTask,
+}
+
+impl PullPrewarmTaskResponse {
+    pub fn new(prewarm_task: PrewarmTask) -> Self {
+        Self { prewarm_task }
+    }
+}
+
 #[into_request(TeaclaveSchedulerRequest::UpdateTaskResult)]
 pub struct UpdateTaskResultRequest {
     pub task_id: Uuid,
     pub task_result: TaskResult,
+    pub usage: Option<TaskUsage>,
 }
 
 impl UpdateTaskResultRequest {
     pub fn new(task_id: Uuid, task_result: Result<TaskOutputs>) -> Self {
         let result = match task_result {
             Ok(task_output) => TaskResult::Ok(task_output),
-            Err(e) => TaskResult::Err(TaskFailure {
-                reason: e.to_string(),
-            }),
+            Err(e) => TaskResult::Err(TaskFailure::from_error(&e)),
         };
         Self {
             task_id,
             task_result: result,
+            usage: None,
+        }
+    }
+
+    pub fn usage(self, usage: TaskUsage) -> Self {
+        Self {
+            usage: Some(usage),
+            ..self
         }
     }
 }
@@ -80,6 +120,71 @@ impl UpdateTaskResultRequest {
 #[into_request(TeaclaveSchedulerResponse::UpdateTaskResult)]
 pub struct UpdateTaskResultResponse {}
 
+#[into_request(TeaclaveSchedulerRequest::PutReturnValueChunk)]
+pub struct PutReturnValueChunkRequest {
+    pub chunk: ReturnValueChunk,
+}
+
+impl PutReturnValueChunkRequest {
+    pub fn new(chunk: ReturnValueChunk) -> Self {
+        Self { chunk }
+    }
+}
+
+#[into_request(TeaclaveSchedulerResponse::PutReturnValueChunk)]
+pub struct PutReturnValueChunkResponse {}
+
+#[into_request(TeaclaveSchedulerRequest::PutTaskKeyOffer)]
+pub struct PutTaskKeyOfferRequest {
+    pub task_id: Uuid,
+    pub offer: TaskKeyOffer,
+}
+
+impl PutTaskKeyOfferRequest {
+    pub fn new(task_id: Uuid, offer: TaskKeyOffer) -> Self {
+        Self { task_id, offer }
+    }
+}
+
+#[into_request(TeaclaveSchedulerResponse::PutTaskKeyOffer)]
+pub struct PutTaskKeyOfferResponse {}
+
+#[into_request(TeaclaveSchedulerRequest::GetTaskKeys)]
+pub struct GetTaskKeysRequest {
+    pub task_id: Uuid,
+}
+
+impl GetTaskKeysRequest {
+    pub fn new(task_id: Uuid) -> Self {
+        Self { task_id }
+    }
+}
+
+#[into_request(TeaclaveSchedulerResponse::GetTaskKeys)]
+pub struct GetTaskKeysResponse {
+    pub key_exchange: TaskKeyExchange,
+}
+
+impl GetTaskKeysResponse {
+    pub fn new(key_exchange: TaskKeyExchange) -> Self {
+        Self { key_exchange }
+    }
+}
+
+#[into_request(TeaclaveSchedulerRequest::PutTaskUploadSlots)]
+pub struct PutTaskUploadSlotsRequest {
+    pub upload_slots: TaskUploadSlots,
+}
+
+impl PutTaskUploadSlotsRequest {
+    pub fn new(upload_slots: TaskUploadSlots) -> Self {
+        Self { upload_slots }
+    }
+}
+
+#[into_request(TeaclaveSchedulerResponse::PutTaskUploadSlots)]
+pub struct PutTaskUploadSlotsResponse {}
+
 #[into_request(TeaclaveSchedulerRequest::UpdateTaskStatus)]
 pub struct UpdateTaskStatusRequest {
     pub task_id: Uuid,
@@ -97,6 +202,50 @@ impl UpdateTaskStatusRequest {
 #[into_request(TeaclaveSchedulerResponse::UpdateTaskStatus)]
 pub struct UpdateTaskStatusResponse {}
 
+#[into_request(TeaclaveSchedulerRequest::RegisterWorker)]
+pub struct RegisterWorkerRequest {
+    pub capabilities: WorkerCapabilities,
+}
+
+impl RegisterWorkerRequest {
+    pub fn new(capabilities: WorkerCapabilities) -> Self {
+        Self { capabilities }
+    }
+}
+
+#[into_request(TeaclaveSchedulerResponse::RegisterWorker)]
+#[derive(Debug)]
+pub struct RegisterWorkerResponse {
+    pub worker_id: Uuid,
+    /// Seconds between the heartbeats the worker sends
+    pub heartbeat_interval: u64,
+}
+
+impl RegisterWorkerResponse {
+    pub fn new(worker_id: Uuid, heartbeat_interval: u64) -> Self {
+        Self {
+            worker_id,
+            heartbeat_interval,
+        }
+    }
+}
+
+#[into_request(TeaclaveSchedulerRequest::Heartbeat)]
+pub struct HeartbeatRequest {
+    pub worker_id: Uuid,
+    /// Tasks running on the worker
+    pub load: u32,
+}
+
+impl HeartbeatRequest {
+    pub fn new(worker_id: Uuid, load: u32) -> Self {
+        Self { worker_id, load }
+    }
+}
+
+#[into_request(TeaclaveSchedulerResponse::Heartbeat)]
+pub struct HeartbeatResponse {}
+
 #[into_request(TeaclaveSchedulerRequest::PublishTask)]
 pub struct PublishTaskRequest {
     pub staged_task: StagedTask,
@@ -140,14 +289,21 @@ impl std::convert::From<SubscribeResponse> for proto::SubscribeResponse {
 impl std::convert::TryFrom<proto::PullTaskRequest> for PullTaskRequest {
     type Error = Error;
     fn try_from(proto: proto::PullTaskRequest) -> Result<Self> {
-        let ret = Self {};
+        let worker_id = if proto.worker_id.is_empty() {
+            None
+        } else {
+            Some(Uuid::parse_str(&proto.worker_id)?)
+        };
+        let ret = Self { worker_id };
         Ok(ret)
     }
 }
 
 impl std::convert::From<PullTaskRequest> for proto::PullTaskRequest {
     fn from(req: PullTaskRequest) -> Self {
-        proto::PullTaskRequest {}
+        proto::PullTaskRequest {
+            worker_id: req.worker_id.map(|id| id.to_string()).unwrap_or_default(),
+        }
     }
 }
 
@@ -168,12 +324,46 @@ impl std::convert::From<PullTaskResponse> for proto::PullTaskResponse {
     }
 }
 
+impl std::convert::TryFrom<proto::PullPrewarmTaskRequest> for PullPrewarmTaskRequest {
+    type Error = Error;
+    fn try_from(proto: proto::PullPrewarmTaskRequest) -> Result<Self> {
+        let ret = Self {};
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<PullPrewarmTaskRequest> for proto::PullPrewarmTaskRequest {
+    fn from(req: PullPrewarmTaskRequest) -> Self {
+        proto::PullPrewarmTaskRequest {}
+    }
+}
+
+impl std::convert::TryFrom<proto::PullPrewarmTaskResponse> for PullPrewarmTaskResponse {
+    type Error = Error;
+    fn try_from(proto: proto::PullPrewarmTaskResponse) -> Result<Self> {
+        let prewarm_task = PrewarmTask::from_slice(&proto.prewarm_task)?;
+        let ret = Self { prewarm_task };
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<PullPrewarmTaskResponse> for proto::PullPrewarmTaskResponse {
+    fn from(req: PullPrewarmTaskResponse) -> Self {
+        proto::PullPrewarmTaskResponse {
+            prewarm_task: req.prewarm_task.to_vec().unwrap(),
+        }
+    }
+}
+
 impl std::convert::TryFrom<proto::UpdateTaskResultRequest> for UpdateTaskResultRequest {
     type Error = Error;
     fn try_from(proto: proto::UpdateTaskResultRequest) -> Result<Self> {
         let ret = Self {
             task_id: Uuid::parse_str(&proto.task_id)?,
             task_result: proto.result.try_into()?,
+            usage: proto
+                .usage
+                .map(|usage| TaskUsage::new(usage.run_time, usage.input_size)),
         };
         Ok(ret)
     }
@@ -184,6 +374,10 @@ impl std::convert::From<UpdateTaskResultRequest> for proto::UpdateTaskResultRequ
         proto::UpdateTaskResultRequest {
             task_id: req.task_id.to_string(),
             result: Some(req.task_result.into()),
+            usage: req.usage.map(|usage| proto::TaskUsage {
+                run_time: usage.run_time,
+                input_size: usage.input_size,
+            }),
         }
     }
 }
@@ -202,6 +396,141 @@ impl std::convert::From<UpdateTaskResultResponse> for proto::UpdateTaskResultRes
     }
 }
 
+impl std::convert::TryFrom<proto::PutReturnValueChunkRequest> for PutReturnValueChunkRequest {
+    type Error = Error;
+    fn try_from(proto: proto::PutReturnValueChunkRequest) -> Result<Self> {
+        let chunk = ReturnValueChunk::from_slice(&proto.chunk)?;
+        let ret = Self { chunk };
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<PutReturnValueChunkRequest> for proto::PutReturnValueChunkRequest {
+    fn from(req: PutReturnValueChunkRequest) -> Self {
+        proto::PutReturnValueChunkRequest {
+            chunk: req.chunk.to_vec().unwrap(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::PutReturnValueChunkResponse> for PutReturnValueChunkResponse {
+    type Error = Error;
+    fn try_from(proto: proto::PutReturnValueChunkResponse) -> Result<Self> {
+        let ret = Self {};
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<PutReturnValueChunkResponse> for proto::PutReturnValueChunkResponse {
+    fn from(req: PutReturnValueChunkResponse) -> Self {
+        proto::PutReturnValueChunkResponse {}
+    }
+}
+
+impl std::convert::TryFrom<proto::PutTaskKeyOfferRequest> for PutTaskKeyOfferRequest {
+    type Error = Error;
+    fn try_from(proto: proto::PutTaskKeyOfferRequest) -> Result<Self> {
+        let offer = proto
+            .offer
+            .ok_or_else(|| anyhow::anyhow!("Missing offer"))?
+            .try_into()?;
+        let ret = Self {
+            task_id: Uuid::parse_str(&proto.task_id)?,
+            offer,
+        };
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<PutTaskKeyOfferRequest> for proto::PutTaskKeyOfferRequest {
+    fn from(req: PutTaskKeyOfferRequest) -> Self {
+        proto::PutTaskKeyOfferRequest {
+            task_id: req.task_id.to_string(),
+            offer: Some(req.offer.into()),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::PutTaskKeyOfferResponse> for PutTaskKeyOfferResponse {
+    type Error = Error;
+    fn try_from(proto: proto::PutTaskKeyOfferResponse) -> Result<Self> {
+        let ret = Self {};
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<PutTaskKeyOfferResponse> for proto::PutTaskKeyOfferResponse {
+    fn from(req: PutTaskKeyOfferResponse) -> Self {
+        proto::PutTaskKeyOfferResponse {}
+    }
+}
+
+impl std::convert::TryFrom<proto::GetTaskKeysRequest> for GetTaskKeysRequest {
+    type Error = Error;
+    fn try_from(proto: proto::GetTaskKeysRequest) -> Result<Self> {
+        let ret = Self {
+            task_id: Uuid::parse_str(&proto.task_id)?,
+        };
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<GetTaskKeysRequest> for proto::GetTaskKeysRequest {
+    fn from(req: GetTaskKeysRequest) -> Self {
+        proto::GetTaskKeysRequest {
+            task_id: req.task_id.to_string(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetTaskKeysResponse> for GetTaskKeysResponse {
+    type Error = Error;
+    fn try_from(proto: proto::GetTaskKeysResponse) -> Result<Self> {
+        let key_exchange = TaskKeyExchange::from_slice(&proto.key_exchange)?;
+        let ret = Self { key_exchange };
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<GetTaskKeysResponse> for proto::GetTaskKeysResponse {
+    fn from(req: GetTaskKeysResponse) -> Self {
+        proto::GetTaskKeysResponse {
+            key_exchange: req.key_exchange.to_vec().unwrap(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::PutTaskUploadSlotsRequest> for PutTaskUploadSlotsRequest {
+    type Error = Error;
+    fn try_from(proto: proto::PutTaskUploadSlotsRequest) -> Result<Self> {
+        let upload_slots = TaskUploadSlots::from_slice(&proto.upload_slots)?;
+        let ret = Self { upload_slots };
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<PutTaskUploadSlotsRequest> for proto::PutTaskUploadSlotsRequest {
+    fn from(req: PutTaskUploadSlotsRequest) -> Self {
+        proto::PutTaskUploadSlotsRequest {
+            upload_slots: req.upload_slots.to_vec().unwrap(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::PutTaskUploadSlotsResponse> for PutTaskUploadSlotsResponse {
+    type Error = Error;
+    fn try_from(proto: proto::PutTaskUploadSlotsResponse) -> Result<Self> {
+        let ret = Self {};
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<PutTaskUploadSlotsResponse> for proto::PutTaskUploadSlotsResponse {
+    fn from(req: PutTaskUploadSlotsResponse) -> Self {
+        proto::PutTaskUploadSlotsResponse {}
+    }
+}
+
 impl std::convert::TryFrom<proto::UpdateTaskStatusRequest> for UpdateTaskStatusRequest {
     type Error = Error;
     fn try_from(proto: proto::UpdateTaskStatusRequest) -> Result<Self> {
@@ -238,6 +567,77 @@ impl std::convert::From<UpdateTaskStatusResponse> for proto::UpdateTaskStatusRes
     }
 }
 
+impl std::convert::TryFrom<proto::RegisterWorkerRequest> for RegisterWorkerRequest {
+    type Error = Error;
+    fn try_from(proto: proto::RegisterWorkerRequest) -> Result<Self> {
+        let capabilities = serde_json::from_slice(&proto.capabilities)?;
+        let ret = Self { capabilities };
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<RegisterWorkerRequest> for proto::RegisterWorkerRequest {
+    fn from(req: RegisterWorkerRequest) -> Self {
+        proto::RegisterWorkerRequest {
+            capabilities: serde_json::to_vec(&req.capabilities).unwrap(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RegisterWorkerResponse> for RegisterWorkerResponse {
+    type Error = Error;
+    fn try_from(proto: proto::RegisterWorkerResponse) -> Result<Self> {
+        let ret = Self {
+            worker_id: Uuid::parse_str(&proto.worker_id)?,
+            heartbeat_interval: proto.heartbeat_interval,
+        };
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<RegisterWorkerResponse> for proto::RegisterWorkerResponse {
+    fn from(req: RegisterWorkerResponse) -> Self {
+        proto::RegisterWorkerResponse {
+            worker_id: req.worker_id.to_string(),
+            heartbeat_interval: req.heartbeat_interval,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::HeartbeatRequest> for HeartbeatRequest {
+    type Error = Error;
+    fn try_from(proto: proto::HeartbeatRequest) -> Result<Self> {
+        let ret = Self {
+            worker_id: Uuid::parse_str(&proto.worker_id)?,
+            load: proto.load,
+        };
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<HeartbeatRequest> for proto::HeartbeatRequest {
+    fn from(req: HeartbeatRequest) -> Self {
+        proto::HeartbeatRequest {
+            worker_id: req.worker_id.to_string(),
+            load: req.load,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::HeartbeatResponse> for HeartbeatResponse {
+    type Error = Error;
+    fn try_from(proto: proto::HeartbeatResponse) -> Result<Self> {
+        let ret = Self {};
+        Ok(ret)
+    }
+}
+
+impl std::convert::From<HeartbeatResponse> for proto::HeartbeatResponse {
+    fn from(req: HeartbeatResponse) -> Self {
+        proto::HeartbeatResponse {}
+    }
+}
+
 use teaclave_types::Storable;
 impl std::convert::TryFrom<proto::PublishTaskRequest> for PublishTaskRequest {
     type Error = Error;
@@ -0,0 +1,277 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::prelude::v1::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(not(feature = "mesalock_sgx"))]
+use std::sync::{Mutex, MutexGuard};
+#[cfg(feature = "mesalock_sgx")]
+use std::sync::{SgxMutex as Mutex, SgxMutexGuard as MutexGuard};
+
+use crate::teaclave_storage_service::*;
+use teaclave_types::{TeaclaveServiceResponseError, TeaclaveServiceResponseResult};
+
+struct StorageShard {
+    key_prefixes: Vec<Vec<u8>>,
+    client: Mutex<TeaclaveStorageClient>,
+}
+
+struct StorageReplica {
+    name: String,
+    client: Mutex<TeaclaveStorageClient>,
+}
+
+/// Client of storage services sharded by key prefix. A request is sent to the
+/// shard with the longest key prefix of its key, or to the default storage if
+/// no prefix matches. Each shard has its own connection, so requests to
+/// different shards do not wait for each other.
+///
+/// Queries tolerating stale values can be read from the read replicas of the
+/// default storage, which are taken in turn.
+pub struct TeaclaveStorageRouter {
+    default: Mutex<TeaclaveStorageClient>,
+    shards: Vec<StorageShard>,
+    replicas: Vec<StorageReplica>,
+    next_replica: AtomicUsize,
+}
+
+impl TeaclaveStorageRouter {
+    pub fn new(default: TeaclaveStorageClient) -> Self {
+        Self {
+            default: Mutex::new(default),
+            shards: Vec::new(),
+            replicas: Vec::new(),
+            next_replica: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn shard(mut self, key_prefixes: &[String], client: TeaclaveStorageClient) -> Self {
+        self.shards.push(StorageShard {
+            key_prefixes: key_prefixes
+                .iter()
+                .map(|prefix| prefix.as_bytes().to_vec())
+                .collect(),
+            client: Mutex::new(client),
+        });
+        self
+    }
+
+    pub fn replica(mut self, name: impl Into<String>, client: TeaclaveStorageClient) -> Self {
+        self.replicas.push(StorageReplica {
+            name: name.into(),
+            client: Mutex::new(client),
+        });
+        self
+    }
+
+    pub fn has_replica(&self, name: &str) -> bool {
+        self.replicas.iter().any(|replica| replica.name == name)
+    }
+
+    fn route(&self, key: &[u8]) -> &Mutex<TeaclaveStorageClient> {
+        self.shards
+            .iter()
+            .filter_map(|shard| {
+                shard
+                    .key_prefixes
+                    .iter()
+                    .filter(|prefix| key.starts_with(prefix))
+                    .map(|prefix| (prefix.len(), &shard.client))
+                    .max_by_key(|(len, _)| *len)
+            })
+            .max_by_key(|(len, _)| *len)
+            .map_or(&self.default, |(_, client)| client)
+    }
+
+    fn client(
+        &self,
+        key: &[u8],
+    ) -> TeaclaveServiceResponseResult<MutexGuard<TeaclaveStorageClient>> {
+        self.route(key)
+            .lock()
+            .map_err(|_| TeaclaveServiceResponseError::InternalError("storage".to_string()))
+    }
+
+    /// Number of the storages, i.e., the default storage followed by the
+    /// shards in the order they are added.
+    pub fn storages(&self) -> usize {
+        1 + self.shards.len()
+    }
+
+    fn storage(
+        &self,
+        index: usize,
+    ) -> TeaclaveServiceResponseResult<MutexGuard<TeaclaveStorageClient>> {
+        let client = match index {
+            0 => &self.default,
+            _ => match self.shards.get(index - 1) {
+                Some(shard) => &shard.client,
+                None => {
+                    return Err(TeaclaveServiceResponseError::InternalError(
+                        "storage".to_string(),
+                    ))
+                }
+            },
+        };
+        client
+            .lock()
+            .map_err(|_| TeaclaveServiceResponseError::InternalError("storage".to_string()))
+    }
+
+    pub fn get(&self, request: GetRequest) -> TeaclaveServiceResponseResult<GetResponse> {
+        self.client(&request.key)?.get(request)
+    }
+
+    /// Gets the value from a read replica, which may lag behind the default
+    /// storage. The value is read from the storage instead if the key belongs
+    /// to a shard, or if the replica fails or does not have the key yet.
+    pub fn get_from_replica(
+        &self,
+        request: GetRequest,
+    ) -> TeaclaveServiceResponseResult<GetResponse> {
+        if self.replicas.is_empty() || !std::ptr::eq(self.route(&request.key), &self.default) {
+            return self.get(request);
+        }
+        let index = self.next_replica.fetch_add(1, Ordering::Relaxed) % self.replicas.len();
+        let response = match self.replicas[index].client.lock() {
+            Ok(mut client) => client.get(GetRequest::new(request.key.as_slice())),
+            Err(_) => return self.get(request),
+        };
+        response.or_else(|_| self.get(request))
+    }
+
+    pub fn put(&self, request: PutRequest) -> TeaclaveServiceResponseResult<PutResponse> {
+        self.client(&request.key)?.put(request)
+    }
+
+    pub fn compare_and_swap(
+        &self,
+        request: CompareAndSwapRequest,
+    ) -> TeaclaveServiceResponseResult<CompareAndSwapResponse> {
+        self.client(&request.key)?.compare_and_swap(request)
+    }
+
+    pub fn put_if_version(
+        &self,
+        request: PutIfVersionRequest,
+    ) -> TeaclaveServiceResponseResult<PutIfVersionResponse> {
+        self.client(&request.key)?.put_if_version(request)
+    }
+
+    pub fn put_with_ttl(
+        &self,
+        request: PutWithTtlRequest,
+    ) -> TeaclaveServiceResponseResult<PutWithTtlResponse> {
+        self.client(&request.key)?.put_with_ttl(request)
+    }
+
+    pub fn delete(&self, request: DeleteRequest) -> TeaclaveServiceResponseResult<DeleteResponse> {
+        self.client(&request.key)?.delete(request)
+    }
+
+    pub fn put_indexed(
+        &self,
+        request: PutIndexedRequest,
+    ) -> TeaclaveServiceResponseResult<PutIndexedResponse> {
+        self.client(&request.key)?.put_indexed(request)
+    }
+
+    /// Commits the transaction at the storage of its writes. Writes routed to
+    /// different storages are committed one storage after another, in the
+    /// order of their first writes, so they are only atomic within each
+    /// storage: a failed commit leaves the storages committed before it.
+    pub fn commit(
+        &self,
+        transaction: Transaction,
+    ) -> TeaclaveServiceResponseResult<CommitTransactionResponse> {
+        let mut storages: Vec<(&Mutex<TeaclaveStorageClient>, Vec<TransactionWrite>)> = Vec::new();
+        for write in transaction.writes {
+            let client = self.route(write.key());
+            match storages.iter_mut().find(|(c, _)| std::ptr::eq(*c, client)) {
+                Some((_, writes)) => writes.push(write),
+                None => storages.push((client, vec![write])),
+            }
+        }
+        for (client, writes) in storages {
+            client
+                .lock()
+                .map_err(|_| TeaclaveServiceResponseError::InternalError("storage".to_string()))?
+                .commit_transaction(CommitTransactionRequest::new(writes))?;
+        }
+        Ok(CommitTransactionResponse)
+    }
+
+    /// Scans the index at the storage keeping its records, whose key prefix
+    /// the name of the index starts with.
+    pub fn scan_index(
+        &self,
+        request: ScanIndexRequest,
+    ) -> TeaclaveServiceResponseResult<ScanIndexResponse> {
+        self.client(request.index.as_bytes())?.scan_index(request)
+    }
+
+    pub fn enqueue(
+        &self,
+        request: EnqueueRequest,
+    ) -> TeaclaveServiceResponseResult<EnqueueResponse> {
+        self.client(&request.key)?.enqueue(request)
+    }
+
+    pub fn dequeue(
+        &self,
+        request: DequeueRequest,
+    ) -> TeaclaveServiceResponseResult<DequeueResponse> {
+        self.client(&request.key)?.dequeue(request)
+    }
+
+    /// Exports a snapshot of the storage of the index, see `storages`.
+    pub fn export_snapshot(
+        &self,
+        storage: usize,
+        request: ExportSnapshotRequest,
+    ) -> TeaclaveServiceResponseResult<ExportSnapshotResponse> {
+        self.storage(storage)?.export_snapshot(request)
+    }
+
+    /// Restores a snapshot to the storage of the index, see `storages`.
+    pub fn restore_snapshot(
+        &self,
+        storage: usize,
+        request: RestoreSnapshotRequest,
+    ) -> TeaclaveServiceResponseResult<RestoreSnapshotResponse> {
+        self.storage(storage)?.restore_snapshot(request)
+    }
+
+    /// Promotes the read replica of the name, which stands by for the default
+    /// storage.
+    pub fn promote_replica(
+        &self,
+        name: &str,
+        request: PromoteReplicaRequest,
+    ) -> TeaclaveServiceResponseResult<PromoteReplicaResponse> {
+        let replica = self
+            .replicas
+            .iter()
+            .find(|replica| replica.name == name)
+            .ok_or_else(|| TeaclaveServiceResponseError::InternalError("storage".to_string()))?;
+        replica
+            .client
+            .lock()
+            .map_err(|_| TeaclaveServiceResponseError::InternalError("storage".to_string()))?
+            .promote_replica(request)
+    }
+}
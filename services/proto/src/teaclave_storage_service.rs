@@ -15,7 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use anyhow::{Error, Result};
+use anyhow::{bail, Error, Result};
 use std::prelude::v1::*;
 
 use crate::teaclave_storage_service_proto as proto;
@@ -23,7 +23,9 @@ pub use proto::TeaclaveStorage;
 pub use proto::TeaclaveStorageClient;
 pub use proto::TeaclaveStorageRequest;
 pub use proto::TeaclaveStorageResponse;
+use teaclave_crypto::TeaclaveFile128Key;
 use teaclave_rpc::into_request;
+use teaclave_types::{FileAuthTag, IndexTerm};
 
 #[into_request(TeaclaveStorageRequest::Get)]
 #[derive(Debug)]
@@ -41,12 +43,14 @@ impl GetRequest {
 #[derive(Debug)]
 pub struct GetResponse {
     pub value: Vec<u8>,
+    pub version: u64,
 }
 
 impl GetResponse {
-    pub fn new(value: impl Into<Vec<u8>>) -> Self {
+    pub fn new(value: impl Into<Vec<u8>>, version: u64) -> Self {
         Self {
             value: value.into(),
+            version,
         }
     }
 }
@@ -71,6 +75,79 @@ impl PutRequest {
 #[derive(Debug, Default)]
 pub struct PutResponse;
 
+#[into_request(TeaclaveStorageRequest::CompareAndSwap)]
+#[derive(Debug)]
+pub struct CompareAndSwapRequest {
+    pub key: Vec<u8>,
+    pub expected: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+impl CompareAndSwapRequest {
+    pub fn new(
+        key: impl Into<Vec<u8>>,
+        expected: impl Into<Vec<u8>>,
+        value: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self {
+            key: key.into(),
+            expected: expected.into(),
+            value: value.into(),
+        }
+    }
+}
+
+#[into_request(TeaclaveStorageResponse::CompareAndSwap)]
+#[derive(Debug, Default)]
+pub struct CompareAndSwapResponse;
+
+#[into_request(TeaclaveStorageRequest::PutIfVersion)]
+#[derive(Debug)]
+pub struct PutIfVersionRequest {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    pub version: u64,
+}
+
+impl PutIfVersionRequest {
+    pub fn new(key: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>, version: u64) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            version,
+        }
+    }
+}
+
+#[into_request(TeaclaveStorageResponse::PutIfVersion)]
+#[derive(Debug)]
+pub struct PutIfVersionResponse {
+    pub version: u64,
+}
+
+#[into_request(TeaclaveStorageRequest::PutWithTtl)]
+#[derive(Debug)]
+pub struct PutWithTtlRequest {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    /// Seconds the value is kept for
+    pub ttl: u64,
+}
+
+impl PutWithTtlRequest {
+    pub fn new(key: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>, ttl: u64) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            ttl,
+        }
+    }
+}
+
+#[into_request(TeaclaveStorageResponse::PutWithTtl)]
+#[derive(Debug, Default)]
+pub struct PutWithTtlResponse;
+
 #[into_request(TeaclaveStorageRequest::Delete)]
 #[derive(Debug)]
 pub struct DeleteRequest {
@@ -87,6 +164,185 @@ impl DeleteRequest {
 #[derive(Debug, Default)]
 pub struct DeleteResponse;
 
+#[into_request(TeaclaveStorageRequest::PutIndexed)]
+#[derive(Debug)]
+pub struct PutIndexedRequest {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    pub terms: Vec<IndexTerm>,
+}
+
+impl PutIndexedRequest {
+    pub fn new(key: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>, terms: Vec<IndexTerm>) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            terms,
+        }
+    }
+}
+
+#[into_request(TeaclaveStorageResponse::PutIndexed)]
+#[derive(Debug, Default)]
+pub struct PutIndexedResponse;
+
+/// A write of a transaction, as the request of the same name would write it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransactionWrite {
+    Put {
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    PutIfVersion {
+        key: Vec<u8>,
+        value: Vec<u8>,
+        version: u64,
+    },
+    PutIndexed {
+        key: Vec<u8>,
+        value: Vec<u8>,
+        terms: Vec<IndexTerm>,
+    },
+    Delete {
+        key: Vec<u8>,
+    },
+}
+
+impl TransactionWrite {
+    pub fn key(&self) -> &[u8] {
+        match self {
+            TransactionWrite::Put { key, .. }
+            | TransactionWrite::PutIfVersion { key, .. }
+            | TransactionWrite::PutIndexed { key, .. }
+            | TransactionWrite::Delete { key } => key,
+        }
+    }
+}
+
+/// Writes buffered by the client from `begin` until they are committed with
+/// `CommitTransaction`, which writes either all of them or none.
+#[derive(Debug, Default)]
+pub struct Transaction {
+    pub writes: Vec<TransactionWrite>,
+}
+
+impl Transaction {
+    pub fn begin() -> Self {
+        Self::default()
+    }
+
+    pub fn put(&mut self, key: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>) {
+        self.writes.push(TransactionWrite::Put {
+            key: key.into(),
+            value: value.into(),
+        });
+    }
+
+    pub fn put_if_version(
+        &mut self,
+        key: impl Into<Vec<u8>>,
+        value: impl Into<Vec<u8>>,
+        version: u64,
+    ) {
+        self.writes.push(TransactionWrite::PutIfVersion {
+            key: key.into(),
+            value: value.into(),
+            version,
+        });
+    }
+
+    pub fn put_indexed(
+        &mut self,
+        key: impl Into<Vec<u8>>,
+        value: impl Into<Vec<u8>>,
+        terms: Vec<IndexTerm>,
+    ) {
+        self.writes.push(TransactionWrite::PutIndexed {
+            key: key.into(),
+            value: value.into(),
+            terms,
+        });
+    }
+
+    pub fn delete(&mut self, key: impl Into<Vec<u8>>) {
+        self.writes
+            .push(TransactionWrite::Delete { key: key.into() });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+}
+
+#[into_request(TeaclaveStorageRequest::CommitTransaction)]
+#[derive(Debug)]
+pub struct CommitTransactionRequest {
+    pub writes: Vec<TransactionWrite>,
+}
+
+impl CommitTransactionRequest {
+    pub fn new(writes: Vec<TransactionWrite>) -> Self {
+        Self { writes }
+    }
+}
+
+impl From<Transaction> for CommitTransactionRequest {
+    fn from(transaction: Transaction) -> Self {
+        Self::new(transaction.writes)
+    }
+}
+
+#[into_request(TeaclaveStorageResponse::CommitTransaction)]
+#[derive(Debug, Default)]
+pub struct CommitTransactionResponse;
+
+#[into_request(TeaclaveStorageRequest::ScanIndex)]
+#[derive(Debug)]
+pub struct ScanIndexRequest {
+    pub index: String,
+    pub term: Vec<u8>,
+    pub start_after: Option<Vec<u8>>,
+    /// Max number of records in the response, or the default of the storage
+    /// service if zero.
+    pub limit: u32,
+}
+
+impl ScanIndexRequest {
+    pub fn new(index: impl ToString, term: impl Into<Vec<u8>>) -> Self {
+        Self {
+            index: index.to_string(),
+            term: term.into(),
+            start_after: None,
+            limit: 0,
+        }
+    }
+
+    pub fn start_after(self, key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            start_after: Some(key.into()),
+            ..self
+        }
+    }
+
+    pub fn limit(self, limit: u32) -> Self {
+        Self { limit, ..self }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexEntry {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+#[into_request(TeaclaveStorageResponse::ScanIndex)]
+#[derive(Debug, Default)]
+pub struct ScanIndexResponse {
+    pub entries: Vec<IndexEntry>,
+    /// Key to continue the scan after, if more records remain.
+    pub next_start_after: Option<Vec<u8>>,
+}
+
 #[into_request(TeaclaveStorageRequest::Enqueue)]
 #[derive(Debug)]
 pub struct EnqueueRequest {
@@ -153,7 +409,10 @@ impl std::convert::TryFrom<proto::GetResponse> for GetResponse {
     type Error = Error;
 
     fn try_from(proto: proto::GetResponse) -> Result<Self> {
-        let ret = Self { value: proto.value };
+        let ret = Self {
+            value: proto.value,
+            version: proto.version,
+        };
 
         Ok(ret)
     }
@@ -163,6 +422,7 @@ impl From<GetResponse> for proto::GetResponse {
     fn from(response: GetResponse) -> Self {
         Self {
             value: response.value,
+            version: response.version,
         }
     }
 }
@@ -203,6 +463,124 @@ impl From<PutResponse> for proto::PutResponse {
     }
 }
 
+impl std::convert::TryFrom<proto::CompareAndSwapRequest> for CompareAndSwapRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::CompareAndSwapRequest) -> Result<Self> {
+        let ret = Self {
+            key: proto.key,
+            expected: proto.expected,
+            value: proto.value,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<CompareAndSwapRequest> for proto::CompareAndSwapRequest {
+    fn from(request: CompareAndSwapRequest) -> Self {
+        Self {
+            key: request.key,
+            expected: request.expected,
+            value: request.value,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::CompareAndSwapResponse> for CompareAndSwapResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::CompareAndSwapResponse) -> Result<Self> {
+        Ok(Self {})
+    }
+}
+
+impl From<CompareAndSwapResponse> for proto::CompareAndSwapResponse {
+    fn from(_response: CompareAndSwapResponse) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::PutIfVersionRequest> for PutIfVersionRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::PutIfVersionRequest) -> Result<Self> {
+        let ret = Self {
+            key: proto.key,
+            value: proto.value,
+            version: proto.version,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<PutIfVersionRequest> for proto::PutIfVersionRequest {
+    fn from(request: PutIfVersionRequest) -> Self {
+        Self {
+            key: request.key,
+            value: request.value,
+            version: request.version,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::PutIfVersionResponse> for PutIfVersionResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::PutIfVersionResponse) -> Result<Self> {
+        Ok(Self {
+            version: proto.version,
+        })
+    }
+}
+
+impl From<PutIfVersionResponse> for proto::PutIfVersionResponse {
+    fn from(response: PutIfVersionResponse) -> Self {
+        Self {
+            version: response.version,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::PutWithTtlRequest> for PutWithTtlRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::PutWithTtlRequest) -> Result<Self> {
+        let ret = Self {
+            key: proto.key,
+            value: proto.value,
+            ttl: proto.ttl,
+        };
+
+        Ok(ret)
+    }
+}
+
+impl From<PutWithTtlRequest> for proto::PutWithTtlRequest {
+    fn from(request: PutWithTtlRequest) -> Self {
+        Self {
+            key: request.key,
+            value: request.value,
+            ttl: request.ttl,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::PutWithTtlResponse> for PutWithTtlResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::PutWithTtlResponse) -> Result<Self> {
+        Ok(Self {})
+    }
+}
+
+impl From<PutWithTtlResponse> for proto::PutWithTtlResponse {
+    fn from(_response: PutWithTtlResponse) -> Self {
+        Self {}
+    }
+}
+
 impl std::convert::TryFrom<proto::DeleteRequest> for DeleteRequest {
     type Error = Error;
 
@@ -233,6 +611,239 @@ impl From<DeleteResponse> for proto::DeleteResponse {
     }
 }
 
+impl std::convert::TryFrom<proto::IndexTerm> for IndexTerm {
+    type Error = Error;
+
+    fn try_from(proto: proto::IndexTerm) -> Result<Self> {
+        Ok(Self {
+            index: proto.index,
+            term: proto.term,
+        })
+    }
+}
+
+impl From<IndexTerm> for proto::IndexTerm {
+    fn from(term: IndexTerm) -> Self {
+        Self {
+            index: term.index,
+            term: term.term,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::PutIndexedRequest> for PutIndexedRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::PutIndexedRequest) -> Result<Self> {
+        let terms = proto
+            .terms
+            .into_iter()
+            .map(std::convert::TryInto::try_into)
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            key: proto.key,
+            value: proto.value,
+            terms,
+        })
+    }
+}
+
+impl From<PutIndexedRequest> for proto::PutIndexedRequest {
+    fn from(request: PutIndexedRequest) -> Self {
+        Self {
+            key: request.key,
+            value: request.value,
+            terms: request.terms.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::PutIndexedResponse> for PutIndexedResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::PutIndexedResponse) -> Result<Self> {
+        Ok(Self {})
+    }
+}
+
+impl From<PutIndexedResponse> for proto::PutIndexedResponse {
+    fn from(_response: PutIndexedResponse) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::TransactionWrite> for TransactionWrite {
+    type Error = Error;
+
+    fn try_from(proto: proto::TransactionWrite) -> Result<Self> {
+        let write = match proto::TransactionWriteKind::from_i32(proto.kind) {
+            Some(proto::TransactionWriteKind::Put) => TransactionWrite::Put {
+                key: proto.key,
+                value: proto.value,
+            },
+            Some(proto::TransactionWriteKind::PutIfVersion) => TransactionWrite::PutIfVersion {
+                key: proto.key,
+                value: proto.value,
+                version: proto.version,
+            },
+            Some(proto::TransactionWriteKind::PutIndexed) => TransactionWrite::PutIndexed {
+                key: proto.key,
+                value: proto.value,
+                terms: proto
+                    .terms
+                    .into_iter()
+                    .map(std::convert::TryInto::try_into)
+                    .collect::<Result<_>>()?,
+            },
+            Some(proto::TransactionWriteKind::Delete) => {
+                TransactionWrite::Delete { key: proto.key }
+            }
+            None => bail!("invalid transaction write kind"),
+        };
+        Ok(write)
+    }
+}
+
+impl From<TransactionWrite> for proto::TransactionWrite {
+    fn from(write: TransactionWrite) -> Self {
+        match write {
+            TransactionWrite::Put { key, value } => Self {
+                kind: proto::TransactionWriteKind::Put as i32,
+                key,
+                value,
+                ..Default::default()
+            },
+            TransactionWrite::PutIfVersion {
+                key,
+                value,
+                version,
+            } => Self {
+                kind: proto::TransactionWriteKind::PutIfVersion as i32,
+                key,
+                value,
+                version,
+                ..Default::default()
+            },
+            TransactionWrite::PutIndexed { key, value, terms } => Self {
+                kind: proto::TransactionWriteKind::PutIndexed as i32,
+                key,
+                value,
+                terms: terms.into_iter().map(Into::into).collect(),
+                ..Default::default()
+            },
+            TransactionWrite::Delete { key } => Self {
+                kind: proto::TransactionWriteKind::Delete as i32,
+                key,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::CommitTransactionRequest> for CommitTransactionRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::CommitTransactionRequest) -> Result<Self> {
+        let writes = proto
+            .writes
+            .into_iter()
+            .map(std::convert::TryInto::try_into)
+            .collect::<Result<_>>()?;
+        Ok(Self { writes })
+    }
+}
+
+impl From<CommitTransactionRequest> for proto::CommitTransactionRequest {
+    fn from(request: CommitTransactionRequest) -> Self {
+        Self {
+            writes: request.writes.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::CommitTransactionResponse> for CommitTransactionResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::CommitTransactionResponse) -> Result<Self> {
+        Ok(Self {})
+    }
+}
+
+impl From<CommitTransactionResponse> for proto::CommitTransactionResponse {
+    fn from(_response: CommitTransactionResponse) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::ScanIndexRequest> for ScanIndexRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::ScanIndexRequest) -> Result<Self> {
+        let start_after = if proto.start_after.is_empty() {
+            None
+        } else {
+            Some(proto.start_after)
+        };
+        Ok(Self {
+            index: proto.index,
+            term: proto.term,
+            start_after,
+            limit: proto.limit,
+        })
+    }
+}
+
+impl From<ScanIndexRequest> for proto::ScanIndexRequest {
+    fn from(request: ScanIndexRequest) -> Self {
+        Self {
+            index: request.index,
+            term: request.term,
+            start_after: request.start_after.unwrap_or_default(),
+            limit: request.limit,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ScanIndexResponse> for ScanIndexResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::ScanIndexResponse) -> Result<Self> {
+        let entries = proto
+            .entries
+            .into_iter()
+            .map(|entry| IndexEntry {
+                key: entry.key,
+                value: entry.value,
+            })
+            .collect();
+        let next_start_after = if proto.next_start_after.is_empty() {
+            None
+        } else {
+            Some(proto.next_start_after)
+        };
+        Ok(Self {
+            entries,
+            next_start_after,
+        })
+    }
+}
+
+impl From<ScanIndexResponse> for proto::ScanIndexResponse {
+    fn from(response: ScanIndexResponse) -> Self {
+        Self {
+            entries: response
+                .entries
+                .into_iter()
+                .map(|entry| proto::IndexEntry {
+                    key: entry.key,
+                    value: entry.value,
+                })
+                .collect(),
+            next_start_after: response.next_start_after.unwrap_or_default(),
+        }
+    }
+}
+
 impl std::convert::TryFrom<proto::EnqueueRequest> for EnqueueRequest {
     type Error = Error;
 
@@ -300,3 +911,365 @@ impl From<DequeueResponse> for proto::DequeueResponse {
         }
     }
 }
+
+/// A put of the value, or a delete of the key if the value is none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicationLogEntry {
+    pub sequence: u64,
+    pub key: Vec<u8>,
+    pub value: Option<Vec<u8>>,
+}
+
+#[into_request(TeaclaveStorageRequest::ReadReplicationLog)]
+#[derive(Debug)]
+pub struct ReadReplicationLogRequest {
+    pub from_sequence: u64,
+    pub snapshot_cursor: Option<Vec<u8>>,
+}
+
+impl ReadReplicationLogRequest {
+    pub fn new(from_sequence: u64) -> Self {
+        Self {
+            from_sequence,
+            snapshot_cursor: None,
+        }
+    }
+
+    /// Reads the page of the snapshot after the cursor, which is empty for
+    /// the first page.
+    pub fn snapshot(from_sequence: u64, cursor: impl Into<Vec<u8>>) -> Self {
+        Self {
+            from_sequence,
+            snapshot_cursor: Some(cursor.into()),
+        }
+    }
+}
+
+#[into_request(TeaclaveStorageResponse::ReadReplicationLog)]
+#[derive(Debug, Default)]
+pub struct ReadReplicationLogResponse {
+    pub entries: Vec<ReplicationLogEntry>,
+    pub next_sequence: u64,
+    pub snapshot_required: bool,
+    pub snapshot_cursor: Option<Vec<u8>>,
+}
+
+impl std::convert::TryFrom<proto::ReplicationLogEntry> for ReplicationLogEntry {
+    type Error = Error;
+
+    fn try_from(proto: proto::ReplicationLogEntry) -> Result<Self> {
+        let value = if proto.deleted {
+            None
+        } else {
+            Some(proto.value)
+        };
+        Ok(Self {
+            sequence: proto.sequence,
+            key: proto.key,
+            value,
+        })
+    }
+}
+
+impl From<ReplicationLogEntry> for proto::ReplicationLogEntry {
+    fn from(entry: ReplicationLogEntry) -> Self {
+        Self {
+            sequence: entry.sequence,
+            key: entry.key,
+            deleted: entry.value.is_none(),
+            value: entry.value.unwrap_or_default(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ReadReplicationLogRequest> for ReadReplicationLogRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::ReadReplicationLogRequest) -> Result<Self> {
+        let snapshot_cursor = if proto.snapshot {
+            Some(proto.snapshot_cursor)
+        } else {
+            None
+        };
+        Ok(Self {
+            from_sequence: proto.from_sequence,
+            snapshot_cursor,
+        })
+    }
+}
+
+impl From<ReadReplicationLogRequest> for proto::ReadReplicationLogRequest {
+    fn from(request: ReadReplicationLogRequest) -> Self {
+        Self {
+            from_sequence: request.from_sequence,
+            snapshot: request.snapshot_cursor.is_some(),
+            snapshot_cursor: request.snapshot_cursor.unwrap_or_default(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::ReadReplicationLogResponse> for ReadReplicationLogResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::ReadReplicationLogResponse) -> Result<Self> {
+        let entries = proto
+            .entries
+            .into_iter()
+            .map(std::convert::TryInto::try_into)
+            .collect::<Result<_>>()?;
+        let snapshot_cursor = if proto.snapshot_cursor.is_empty() {
+            None
+        } else {
+            Some(proto.snapshot_cursor)
+        };
+        Ok(Self {
+            entries,
+            next_sequence: proto.next_sequence,
+            snapshot_required: proto.snapshot_required,
+            snapshot_cursor,
+        })
+    }
+}
+
+impl From<ReadReplicationLogResponse> for proto::ReadReplicationLogResponse {
+    fn from(response: ReadReplicationLogResponse) -> Self {
+        Self {
+            entries: response.entries.into_iter().map(Into::into).collect(),
+            next_sequence: response.next_sequence,
+            snapshot_required: response.snapshot_required,
+            snapshot_cursor: response.snapshot_cursor.unwrap_or_default(),
+        }
+    }
+}
+
+#[into_request(TeaclaveStorageRequest::ExportSnapshot)]
+#[derive(Debug)]
+pub struct ExportSnapshotRequest {
+    pub name: String,
+}
+
+impl ExportSnapshotRequest {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+#[into_request(TeaclaveStorageResponse::ExportSnapshot)]
+#[derive(Debug)]
+pub struct ExportSnapshotResponse {
+    pub crypto_info: TeaclaveFile128Key,
+    pub cmac: FileAuthTag,
+    pub entries: u64,
+}
+
+#[into_request(TeaclaveStorageRequest::RestoreSnapshot)]
+#[derive(Debug)]
+pub struct RestoreSnapshotRequest {
+    pub name: String,
+    pub crypto_info: TeaclaveFile128Key,
+    pub cmac: FileAuthTag,
+}
+
+impl RestoreSnapshotRequest {
+    pub fn new(
+        name: impl Into<String>,
+        crypto_info: TeaclaveFile128Key,
+        cmac: FileAuthTag,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            crypto_info,
+            cmac,
+        }
+    }
+}
+
+#[into_request(TeaclaveStorageResponse::RestoreSnapshot)]
+#[derive(Debug)]
+pub struct RestoreSnapshotResponse {
+    pub entries: u64,
+}
+
+#[into_request(TeaclaveStorageRequest::GetWalStats)]
+#[derive(Debug, Default)]
+pub struct GetWalStatsRequest {}
+
+impl GetWalStatsRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Bytes of the write-ahead log of a storage service, of which those not
+/// taken by a checkpoint of the current keys are dead.
+#[into_request(TeaclaveStorageResponse::GetWalStats)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GetWalStatsResponse {
+    pub enabled: bool,
+    pub generation: u64,
+    pub log_bytes: u64,
+    pub live_bytes: u64,
+    pub dead_bytes: u64,
+    /// Compactions since the storage service started
+    pub compactions: u64,
+}
+
+#[into_request(TeaclaveStorageRequest::PromoteReplica)]
+#[derive(Debug, Default)]
+pub struct PromoteReplicaRequest {}
+
+impl PromoteReplicaRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[into_request(TeaclaveStorageResponse::PromoteReplica)]
+#[derive(Debug)]
+pub struct PromoteReplicaResponse;
+
+impl std::convert::TryFrom<proto::ExportSnapshotRequest> for ExportSnapshotRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::ExportSnapshotRequest) -> Result<Self> {
+        Ok(Self { name: proto.name })
+    }
+}
+
+impl From<ExportSnapshotRequest> for proto::ExportSnapshotRequest {
+    fn from(request: ExportSnapshotRequest) -> Self {
+        Self { name: request.name }
+    }
+}
+
+impl std::convert::TryFrom<proto::ExportSnapshotResponse> for ExportSnapshotResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::ExportSnapshotResponse) -> Result<Self> {
+        Ok(Self {
+            crypto_info: TeaclaveFile128Key::new(&proto.key)?,
+            cmac: FileAuthTag::from_bytes(&proto.cmac)?,
+            entries: proto.entries,
+        })
+    }
+}
+
+impl From<ExportSnapshotResponse> for proto::ExportSnapshotResponse {
+    fn from(response: ExportSnapshotResponse) -> Self {
+        Self {
+            key: response.crypto_info.key.to_vec(),
+            cmac: response.cmac.to_bytes(),
+            entries: response.entries,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RestoreSnapshotRequest> for RestoreSnapshotRequest {
+    type Error = Error;
+
+    fn try_from(proto: proto::RestoreSnapshotRequest) -> Result<Self> {
+        Ok(Self {
+            name: proto.name,
+            crypto_info: TeaclaveFile128Key::new(&proto.key)?,
+            cmac: FileAuthTag::from_bytes(&proto.cmac)?,
+        })
+    }
+}
+
+impl From<RestoreSnapshotRequest> for proto::RestoreSnapshotRequest {
+    fn from(request: RestoreSnapshotRequest) -> Self {
+        Self {
+            name: request.name,
+            key: request.crypto_info.key.to_vec(),
+            cmac: request.cmac.to_bytes(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::RestoreSnapshotResponse> for RestoreSnapshotResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::RestoreSnapshotResponse) -> Result<Self> {
+        Ok(Self {
+            entries: proto.entries,
+        })
+    }
+}
+
+impl From<RestoreSnapshotResponse> for proto::RestoreSnapshotResponse {
+    fn from(response: RestoreSnapshotResponse) -> Self {
+        Self {
+            entries: response.entries,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::GetWalStatsRequest> for GetWalStatsRequest {
+    type Error = Error;
+
+    fn try_from(_proto: proto::GetWalStatsRequest) -> Result<Self> {
+        Ok(Self {})
+    }
+}
+
+impl From<GetWalStatsRequest> for proto::GetWalStatsRequest {
+    fn from(_request: GetWalStatsRequest) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::GetWalStatsResponse> for GetWalStatsResponse {
+    type Error = Error;
+
+    fn try_from(proto: proto::GetWalStatsResponse) -> Result<Self> {
+        Ok(Self {
+            enabled: proto.enabled,
+            generation: proto.generation,
+            log_bytes: proto.log_bytes,
+            live_bytes: proto.live_bytes,
+            dead_bytes: proto.dead_bytes,
+            compactions: proto.compactions,
+        })
+    }
+}
+
+impl From<GetWalStatsResponse> for proto::GetWalStatsResponse {
+    fn from(response: GetWalStatsResponse) -> Self {
+        Self {
+            enabled: response.enabled,
+            generation: response.generation,
+            log_bytes: response.log_bytes,
+            live_bytes: response.live_bytes,
+            dead_bytes: response.dead_bytes,
+            compactions: response.compactions,
+        }
+    }
+}
+
+impl std::convert::TryFrom<proto::PromoteReplicaRequest> for PromoteReplicaRequest {
+    type Error = Error;
+
+    fn try_from(_proto: proto::PromoteReplicaRequest) -> Result<Self> {
+        Ok(Self {})
+    }
+}
+
+impl From<PromoteReplicaRequest> for proto::PromoteReplicaRequest {
+    fn from(_request: PromoteReplicaRequest) -> Self {
+        Self {}
+    }
+}
+
+impl std::convert::TryFrom<proto::PromoteReplicaResponse> for PromoteReplicaResponse {
+    type Error = Error;
+
+    fn try_from(_proto: proto::PromoteReplicaResponse) -> Result<Self> {
+        Ok(PromoteReplicaResponse)
+    }
+}
+
+impl From<PromoteReplicaResponse> for proto::PromoteReplicaResponse {
+    fn from(_response: PromoteReplicaResponse) -> Self {
+        Self {}
+    }
+}
@@ -17,7 +17,7 @@
 
 use std::prelude::v1::*;
 
-use teaclave_types::TeaclaveServiceResponseError;
+use teaclave_types::{TeaclaveErrorCode, TeaclaveServiceResponseError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -30,8 +30,18 @@ pub enum TeaclaveSchedulerError {
     StorageError,
 }
 
+impl TeaclaveSchedulerError {
+    fn code(&self) -> TeaclaveErrorCode {
+        match self {
+            TeaclaveSchedulerError::SchedulerServiceErr => TeaclaveErrorCode::Internal,
+            TeaclaveSchedulerError::DataError => TeaclaveErrorCode::Internal,
+            TeaclaveSchedulerError::StorageError => TeaclaveErrorCode::Internal,
+        }
+    }
+}
+
 impl From<TeaclaveSchedulerError> for TeaclaveServiceResponseError {
     fn from(error: TeaclaveSchedulerError) -> Self {
-        TeaclaveServiceResponseError::RequestError(error.to_string())
+        TeaclaveServiceResponseError::request_error(error.code(), error)
     }
 }
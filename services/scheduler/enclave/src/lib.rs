@@ -26,6 +26,8 @@ use std::prelude::v1::*;
 #[macro_use]
 extern crate log;
 use anyhow::{anyhow, Result};
+use std::thread;
+use std::time::Duration;
 
 use teaclave_attestation::{verifier, AttestationConfig, RemoteAttestation};
 use teaclave_binder::proto::{
@@ -48,6 +50,10 @@ mod error;
 mod publisher;
 mod service;
 
+// Staged tasks and approval windows are checked for expiration, deferred tasks
+// for their upstream tasks, and task schedules for due runs, every minute.
+const EXPIRY_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
 fn start_service(config: &RuntimeConfig) -> Result<()> {
     let listen_address = config.internal_endpoints.scheduler.listen_address;
     let attestation_config = AttestationConfig::from_teaclave_config(&config)?;
@@ -87,10 +93,42 @@ fn start_service(config: &RuntimeConfig) -> Result<()> {
         &enclave_info,
         AS_ROOT_CA_CERT,
         verifier::universal_quote_verifier,
-        attested_tls_config,
+        attested_tls_config.clone(),
+    )?;
+
+    let storage_shard_endpoints = config
+        .storage_shards
+        .iter()
+        .map(|shard| {
+            let endpoint = create_trusted_storage_endpoint(
+                &shard.advertised_address,
+                &enclave_info,
+                AS_ROOT_CA_CERT,
+                verifier::universal_quote_verifier,
+                attested_tls_config.clone(),
+            )?;
+            Ok((shard.key_prefixes.clone(), endpoint))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let service = service::TeaclaveSchedulerService::new(
+        storage_service_endpoint,
+        storage_shard_endpoints,
+        enclave_info.measurements.clone(),
     )?;
 
-    let service = service::TeaclaveSchedulerService::new(storage_service_endpoint)?;
+    let sweeper = service.clone();
+    thread::spawn(move || loop {
+        thread::sleep(EXPIRY_SWEEP_INTERVAL);
+        sweeper.sweep_expired_tasks();
+        sweeper.sweep_approval_windows();
+        sweeper.sweep_timed_out_tasks();
+        sweeper.sweep_lost_workers();
+        sweeper.stage_task_retries();
+        sweeper.stage_deferred_tasks();
+        sweeper.run_task_schedules();
+    });
+
     match server.start(service) {
         Ok(_) => (),
         Err(e) => {
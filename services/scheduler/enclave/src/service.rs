@@ -17,9 +17,10 @@
 
 use crate::error::TeaclaveSchedulerError;
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::convert::TryInto;
 use std::prelude::v1::*;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, SgxMutex as Mutex};
 
 use teaclave_proto::teaclave_scheduler_service::*;
@@ -27,6 +28,7 @@ use teaclave_proto::teaclave_storage_service::*;
 use teaclave_rpc::endpoint::Endpoint;
 use teaclave_rpc::Request;
 use teaclave_service_enclave_utils::teaclave_service;
+use teaclave_storage_client::{connect_router, TeaclaveStorageRouter, TypedStorage};
 use teaclave_types::*;
 use uuid::Uuid;
 
@@ -36,29 +38,31 @@ use anyhow::Result;
 #[teaclave_service(teaclave_scheduler_service, TeaclaveScheduler, TeaclaveSchedulerError)]
 #[derive(Clone)]
 pub(crate) struct TeaclaveSchedulerService {
-    storage_client: Arc<Mutex<TeaclaveStorageClient>>,
+    storage_client: Arc<TeaclaveStorageRouter>,
     task_queue: Arc<Mutex<VecDeque<StagedTask>>>,
+    measurements: Arc<HashMap<String, EnclaveMeasurement>>,
+    pulls: Arc<AtomicU64>,
+    workers: Arc<Mutex<HashMap<Uuid, WorkerRegistration>>>,
 }
 
 impl TeaclaveSchedulerService {
-    pub(crate) fn new(storage_service_endpoint: Endpoint) -> Result<Self> {
-        let mut i = 0;
-        let channel = loop {
-            match storage_service_endpoint.connect() {
-                Ok(channel) => break channel,
-                Err(_) => {
-                    anyhow::ensure!(i < 10, "failed to connect to storage service");
-                    log::debug!("Failed to connect to storage service, retry {}", i);
-                    i += 1;
-                }
-            }
-            std::thread::sleep(std::time::Duration::from_secs(3));
-        };
-        let storage_client = Arc::new(Mutex::new(TeaclaveStorageClient::new(channel)?));
+    pub(crate) fn new(
+        storage_service_endpoint: Endpoint,
+        storage_shard_endpoints: Vec<(Vec<String>, Endpoint)>,
+        measurements: HashMap<String, EnclaveMeasurement>,
+    ) -> Result<Self> {
+        let storage_client = Arc::new(connect_router(
+            &storage_service_endpoint,
+            &storage_shard_endpoints,
+            &[],
+        )?);
         let task_queue = Arc::new(Mutex::new(VecDeque::new()));
         let service = Self {
             storage_client,
             task_queue,
+            measurements: Arc::new(measurements),
+            pulls: Arc::new(AtomicU64::new(0)),
+            workers: Arc::new(Mutex::new(HashMap::new())),
         };
 
         Ok(service)
@@ -66,45 +70,873 @@ impl TeaclaveSchedulerService {
 
     fn pull_staged_task<T: Storable>(&self, key: &[u8]) -> TeaclaveServiceResponseResult<T> {
         let dequeue_request = DequeueRequest::new(key);
-        let dequeue_response = self
-            .storage_client
-            .clone()
-            .lock()
-            .map_err(|_| TeaclaveSchedulerError::StorageError)?
-            .dequeue(dequeue_request)?;
+        let dequeue_response = self.storage_client.dequeue(dequeue_request)?;
         T::from_slice(dequeue_response.value.as_slice())
             .map_err(|_| TeaclaveSchedulerError::DataError.into())
     }
 
-    fn get_task_state(&self, task_id: &Uuid) -> Result<TaskState> {
-        let key = ExternalID::new(TaskState::key_prefix(), task_id.to_owned());
-        self.get_from_db(&key)
+    fn enqueue_staged_task(
+        &self,
+        key: &[u8],
+        staged_task: &StagedTask,
+    ) -> TeaclaveServiceResponseResult<()> {
+        let value = staged_task
+            .to_vec()
+            .map_err(|_| TeaclaveSchedulerError::DataError)?;
+        let enqueue_request = EnqueueRequest::new(key, value);
+        let _enqueue_response = self.storage_client.enqueue(enqueue_request)?;
+        Ok(())
     }
 
-    fn get_from_db<T: Storable>(&self, key: &ExternalID) -> Result<T> {
-        anyhow::ensure!(T::match_prefix(&key.prefix), "Key prefix doesn't match.");
-        let get_request = GetRequest::new(key.to_bytes());
-        let response = self
+    // Finishes a staged task with the result of an identical task if both of
+    // them consent to reuse, returns whether the task is finished.
+    fn reuse_result(&self, ts: TaskState, version: u64) -> Result<bool> {
+        if !ts.reuse_result {
+            return Ok(false);
+        }
+        let function: Function = self.storage_client.get_item(&ts.function_id)?;
+        let fingerprint = match ts.result_fingerprint(&function) {
+            Some(fingerprint) => fingerprint,
+            None => return Ok(false),
+        };
+        let reusable: ReusableResult = match self
             .storage_client
-            .clone()
+            .get_item(&ReusableResult::external_id_of(&fingerprint))
+        {
+            Ok(reusable) => reusable,
+            Err(_) => return Ok(false),
+        };
+
+        let task: Task<Run> = ts.try_into()?;
+        let mut task: Task<Finish> = TaskState::from(task).try_into()?;
+        let outputs = TaskOutputs::new(reusable.return_value, HashMap::new())
+            .return_value_handle(reusable.return_value_handle);
+        task.update_result(TaskResult::Ok(outputs))?;
+        let mut ts = TaskState::from(task);
+        self.update_task_in_db(&mut ts, version)?;
+        self.record_task_finished(&ts);
+
+        log::info!(
+            "PullTask: task {} served from the result of task {}",
+            ts.external_id(),
+            reusable.task_id
+        );
+        Ok(true)
+    }
+
+    // Expires the staged tasks which have not started running in time. The
+    // staged queues are drained, and the tasks which have not expired are put
+    // back in their order. The backlog of the queues is recorded on the way.
+    pub(crate) fn sweep_expired_tasks(&self) {
+        let mut queued = Vec::new();
+        for priority in TaskPriority::levels() {
+            let mut function_ids = Vec::new();
+            for key in queue_keys(*priority, Executor::all()) {
+                function_ids.extend(self.sweep_expired_tasks_of(key.as_bytes()));
+            }
+            queued.push((*priority, function_ids));
+        }
+        self.record_queue_backlog(&queued);
+    }
+
+    // Returns the functions of the tasks put back.
+    fn sweep_expired_tasks_of(&self, key: &[u8]) -> Vec<Uuid> {
+        let now = now_in_secs();
+        let mut staged_tasks = Vec::new();
+        while let Ok(staged_task) = self.pull_staged_task::<StagedTask>(key) {
+            staged_tasks.push(staged_task);
+        }
+        let mut function_ids = Vec::new();
+        for staged_task in staged_tasks.iter() {
+            match self.read_task_from_db(&staged_task.task_id) {
+                Ok((ts, version)) if ts.is_expired(now) => match self.expire_task(ts, version) {
+                    Ok(()) => {
+                        log::info!("SweepExpiredTasks: dropped task {}", staged_task.task_id);
+                        continue;
+                    }
+                    Err(e) => log::warn!("SweepExpiredTasks: cannot expire task: {:?}", e),
+                },
+                Ok(_) => (),
+                Err(e) => log::warn!("SweepExpiredTasks: cannot read task: {:?}", e),
+            }
+            match self.enqueue_staged_task(key, staged_task) {
+                Ok(()) => function_ids.push(staged_task.function_id),
+                Err(e) => log::error!(
+                    "SweepExpiredTasks: lost staged task {}: {:?}",
+                    staged_task.task_id,
+                    e
+                ),
+            }
+        }
+        function_ids
+    }
+
+    // Records the backlog of the staged queues, from which the queue wait of
+    // a task is estimated. Tasks of functions which have never run are taken
+    // to run as long as the other queued tasks on average.
+    fn record_queue_backlog(&self, queued: &[(TaskPriority, Vec<Uuid>)]) {
+        let mut run_times: HashMap<Uuid, Option<u64>> = HashMap::new();
+        for function_id in queued.iter().flat_map(|(_, function_ids)| function_ids) {
+            if !run_times.contains_key(function_id) {
+                let run_time = self
+                    .storage_client
+                    .get_item::<FunctionUsage>(&FunctionUsage::external_id_of(function_id))
+                    .ok()
+                    .and_then(|usage| usage.mean_run_time());
+                run_times.insert(*function_id, run_time);
+            }
+        }
+        let known: Vec<u64> = queued
+            .iter()
+            .flat_map(|(_, function_ids)| function_ids)
+            .filter_map(|function_id| run_times[function_id])
+            .collect();
+        let default_run_time = match known.len() {
+            0 => 0,
+            n => known.iter().sum::<u64>() / n as u64,
+        };
+        let priorities = queued
+            .iter()
+            .map(|(priority, function_ids)| {
+                let work = function_ids
+                    .iter()
+                    .map(|function_id| run_times[function_id].unwrap_or(default_run_time))
+                    .sum();
+                PriorityBacklog::new(*priority, function_ids.len() as u64, work)
+            })
+            .collect();
+        let live_workers = self
+            .workers
             .lock()
-            .map_err(|_| anyhow!("Cannot lock storage client"))?
-            .get(get_request)?;
-        T::from_slice(response.value.as_slice())
+            .map(|workers| workers.len() as u32)
+            .unwrap_or_default();
+        let backlog = QueueBacklog::new(priorities, live_workers, now_in_secs());
+        if let Err(e) = self.storage_client.put_item(&backlog) {
+            log::warn!("SweepExpiredTasks: cannot record queue backlog: {:?}", e);
+        }
     }
 
-    fn put_into_db(&self, item: &impl Storable) -> Result<()> {
-        let k = item.key();
-        let v = item.to_vec()?;
-        let put_request = PutRequest::new(k.as_slice(), v.as_slice());
-        let _put_response = self
+    // Adds the run of a task to the usage statistics of its function, which
+    // are best effort.
+    fn record_function_usage(&self, function_id: &Uuid, usage: &TaskUsage) {
+        let mut function_usage = self
             .storage_client
-            .clone()
+            .get_item(&FunctionUsage::external_id_of(function_id))
+            .unwrap_or_else(|_| FunctionUsage::new(*function_id));
+        function_usage.record(usage);
+        if let Err(e) = self.storage_client.put_item(&function_usage) {
+            log::warn!(
+                "Failed to record usage of function {}: {:?}",
+                function_id,
+                e
+            );
+        }
+    }
+
+    // Expires the tasks whose approval windows elapsed before everyone
+    // approved them, and notifies the participants. The approval window queue
+    // is drained, and the windows which have not elapsed are put back.
+    pub(crate) fn sweep_approval_windows(&self) {
+        let key = TaskApprovalWindow::get_queue_key().as_bytes();
+        let now = now_in_secs();
+        let mut windows = Vec::new();
+        while let Ok(window) = self.pull_staged_task::<TaskApprovalWindow>(key) {
+            windows.push(window);
+        }
+        for window in windows.iter() {
+            if !window.has_elapsed(now) {
+                if let Err(e) = self.storage_client.enqueue_item(key, window) {
+                    log::error!(
+                        "SweepApprovalWindows: lost approval window of task {}: {:?}",
+                        window.task_id,
+                        e
+                    );
+                }
+                continue;
+            }
+            let (ts, version) = match self.read_task_from_db(&window.task_id) {
+                Ok(task) => task,
+                Err(e) => {
+                    log::warn!("SweepApprovalWindows: cannot read task: {:?}", e);
+                    continue;
+                }
+            };
+            // The task may have been expired by the management service when
+            // it was touched after the window elapsed.
+            if ts.everyone_approved() || !ts.is_expired(now) {
+                continue;
+            }
+            if let Err(e) = self.expire_task(ts.clone(), version) {
+                log::warn!("SweepApprovalWindows: cannot expire task: {:?}", e);
+                continue;
+            }
+            log::info!("SweepApprovalWindows: expired task {}", window.task_id);
+            self.notify_approval_expired(&ts);
+        }
+    }
+
+    // Fails the dispatched tasks which have not finished in time. The dispatch
+    // queue is drained, and the dispatches of tasks still running in time are
+    // put back.
+    pub(crate) fn sweep_timed_out_tasks(&self) {
+        let key = TaskDispatch::get_queue_key().as_bytes();
+        let now = now_in_secs();
+        let mut dispatches = Vec::new();
+        while let Ok(dispatch) = self.pull_staged_task::<TaskDispatch>(key) {
+            dispatches.push(dispatch);
+        }
+        for dispatch in dispatches.iter() {
+            let (mut ts, version) = match self.read_task_from_db(&dispatch.task_id) {
+                Ok(task) => task,
+                Err(e) => {
+                    log::warn!("SweepTimedOutTasks: cannot read task: {:?}", e);
+                    continue;
+                }
+            };
+            if ts.status != TaskStatus::Staged && ts.status != TaskStatus::Running {
+                continue;
+            }
+            // Dispatched before the task was retried
+            if ts.retry_count != dispatch.attempt {
+                continue;
+            }
+            if !dispatch.is_overdue(now) {
+                if let Err(e) = self.storage_client.enqueue_item(key, dispatch) {
+                    log::error!(
+                        "SweepTimedOutTasks: lost dispatch of task {}: {:?}",
+                        dispatch.task_id,
+                        e
+                    );
+                }
+                continue;
+            }
+            let timed_out = ts
+                .time_out(dispatch.timeout)
+                .and_then(|_| self.update_task_in_db(&mut ts, version));
+            match timed_out {
+                Ok(()) => {
+                    log::info!("SweepTimedOutTasks: failed task {}", dispatch.task_id);
+                    self.record_task_finished(&ts);
+                    self.forget_staged_task(&ts);
+                }
+                Err(e) => log::warn!("SweepTimedOutTasks: cannot fail task: {:?}", e),
+            }
+        }
+    }
+
+    // Stages the tasks failed transiently again once their backoff is over.
+    // The retry queue is drained, and the retries which are not due are put
+    // back. Retries of tasks canceled or expired in the meantime are dropped.
+    pub(crate) fn stage_task_retries(&self) {
+        let key = TaskRetry::get_queue_key().as_bytes();
+        let now = now_in_secs();
+        let mut retries = Vec::new();
+        while let Ok(retry) = self.pull_staged_task::<TaskRetry>(key) {
+            retries.push(retry);
+        }
+        for retry in retries.iter() {
+            let task_id = retry.staged_task.task_id;
+            let (ts, version) = match self.read_task_from_db(&task_id) {
+                Ok(task) => task,
+                Err(e) => {
+                    log::warn!("StageTaskRetries: cannot read task: {:?}", e);
+                    self.requeue_task_retry(retry);
+                    continue;
+                }
+            };
+            if ts.status != TaskStatus::Staged || ts.retry_count != retry.staged_task.retry_count {
+                continue;
+            }
+            if ts.is_expired(now) {
+                if let Err(e) = self.expire_task(ts, version) {
+                    log::warn!("StageTaskRetries: cannot expire task: {:?}", e);
+                }
+                continue;
+            }
+            if !retry.is_due(now) {
+                self.requeue_task_retry(retry);
+                continue;
+            }
+            let staged_task = &retry.staged_task;
+            match self
+                .storage_client
+                .enqueue_item(staged_task.queue_key().as_bytes(), staged_task)
+            {
+                Ok(()) => log::info!("StageTaskRetries: staged task {}", task_id),
+                Err(e) => {
+                    log::warn!("StageTaskRetries: cannot stage task: {:?}", e);
+                    self.requeue_task_retry(retry);
+                }
+            }
+        }
+    }
+
+    fn requeue_task_retry(&self, retry: &TaskRetry) {
+        let key = TaskRetry::get_queue_key().as_bytes();
+        if let Err(e) = self.storage_client.enqueue_item(key, retry) {
+            log::error!(
+                "StageTaskRetries: lost retry of task {}: {:?}",
+                retry.staged_task.task_id,
+                e
+            );
+        }
+    }
+
+    // Moves a running task failed transiently back to the Staged state and
+    // queues its retry if it has retries left, returns whether the task is
+    // retried. The retry is queued first, so that it is dropped by the sweep
+    // if the task cannot be updated.
+    fn retry_task(&self, task_id: &Uuid, failure: &TaskFailure) -> Result<bool> {
+        let (mut ts, version) = self.read_task_from_db(task_id)?;
+        if !ts.is_retryable(failure) {
+            return Ok(false);
+        }
+        let staged_task: StagedTask = match self
+            .storage_client
+            .get_item(&ExternalID::new(StagedTask::key_prefix(), *task_id))
+        {
+            Ok(staged_task) => staged_task,
+            Err(e) => {
+                log::warn!("UpdateTaskResult: cannot retry task: {:?}", e);
+                return Ok(false);
+            }
+        };
+        let retry_at = now_in_secs() + ts.retry_policy.backoff_of(ts.retry_count);
+        ts.retry()?;
+        self.untrack_task(task_id);
+        let staged_task = StagedTask {
+            retry_count: ts.retry_count,
+            ..staged_task
+        };
+        let retry = TaskRetry::new(staged_task, retry_at);
+        self.storage_client
+            .enqueue_item(TaskRetry::get_queue_key().as_bytes(), &retry)?;
+        self.update_task_in_db(&mut ts, version)?;
+        log::info!(
+            "UpdateTaskResult: retrying task {} at {}: {}",
+            task_id,
+            retry_at,
+            failure
+        );
+        Ok(true)
+    }
+
+    // The staged task kept for retries, or for requeues if the worker is lost,
+    // is no longer needed once the task has finished. Deleting it is best
+    // effort, and the task may not have been kept at all.
+    fn forget_staged_task(&self, ts: &TaskState) {
+        self.untrack_task(&ts.task_id);
+        let key = ExternalID::new(StagedTask::key_prefix(), ts.task_id);
+        if let Err(e) = self.storage_client.delete_item(&key) {
+            log::warn!("Failed to delete staged task {}: {:?}", ts.task_id, e);
+        }
+    }
+
+    // Heartbeat records only let other services see the live workers, so
+    // writing one is best effort. The record of a lost worker expires by
+    // itself.
+    fn record_heartbeat(&self, heartbeat: &WorkerHeartbeat) {
+        if let Err(e) = self
+            .storage_client
+            .put_item_with_ttl(heartbeat, WORKER_LIVENESS_TIMEOUT)
+        {
+            log::warn!(
+                "Failed to record heartbeat of worker {}: {:?}",
+                heartbeat.worker_id,
+                e
+            );
+        }
+    }
+
+    // Queues the tasks pulled by workers which stopped heartbeating again, and
+    // forgets the workers. A lost worker which comes back registers again.
+    pub(crate) fn sweep_lost_workers(&self) {
+        let now = now_in_secs();
+        let lost = match self.workers.lock() {
+            Ok(mut workers) => {
+                let lost_ids: Vec<Uuid> = workers
+                    .values()
+                    .filter(|worker| !worker.is_live(now))
+                    .map(|worker| worker.worker_id)
+                    .collect();
+                lost_ids
+                    .iter()
+                    .filter_map(|worker_id| workers.remove(worker_id))
+                    .collect::<Vec<_>>()
+            }
+            Err(_) => {
+                log::error!("SweepLostWorkers: cannot lock workers");
+                return;
+            }
+        };
+        for worker in lost.iter() {
+            log::warn!(
+                "SweepLostWorkers: lost worker {} with {} pulled tasks",
+                worker.worker_id,
+                worker.pulled_tasks.len()
+            );
+            for (task_id, retry_count) in worker.pulled_tasks.iter() {
+                if let Err(e) = self.requeue_lost_task(task_id, *retry_count) {
+                    log::warn!("SweepLostWorkers: cannot requeue task {}: {:?}", task_id, e);
+                }
+            }
+        }
+    }
+
+    // Moves a task pulled by a lost worker back to the Staged state and queues
+    // its retry right away, or fails it once it has been requeued too many
+    // times. Tasks finished or run again in the meantime are left alone. The
+    // retry is queued first, so that it is dropped by the sweep if the task
+    // cannot be updated.
+    fn requeue_lost_task(&self, task_id: &Uuid, retry_count: u32) -> Result<()> {
+        let (mut ts, version) = self.read_task_from_db(task_id)?;
+        if ts.status != TaskStatus::Staged && ts.status != TaskStatus::Running {
+            return Ok(());
+        }
+        if ts.retry_count != retry_count {
+            return Ok(());
+        }
+        if !ts.is_requeueable() {
+            ts.fail_transiently(&TransientFailure::WorkerLost)?;
+            self.update_task_in_db(&mut ts, version)?;
+            log::info!("SweepLostWorkers: failed task {}", task_id);
+            self.record_task_finished(&ts);
+            self.forget_staged_task(&ts);
+            return Ok(());
+        }
+        let staged_task: StagedTask = self
+            .storage_client
+            .get_item(&ExternalID::new(StagedTask::key_prefix(), *task_id))?;
+        ts.requeue()?;
+        let staged_task = StagedTask {
+            retry_count: ts.retry_count,
+            ..staged_task
+        };
+        let retry = TaskRetry::new(staged_task, now_in_secs());
+        self.storage_client
+            .enqueue_item(TaskRetry::get_queue_key().as_bytes(), &retry)?;
+        self.update_task_in_db(&mut ts, version)?;
+        log::info!("SweepLostWorkers: requeued task {}", task_id);
+        Ok(())
+    }
+
+    // Records the task pulled by a registered worker, returns whether the
+    // worker is known to the scheduler.
+    fn track_task(
+        &self,
+        worker_id: &Uuid,
+        mr_enclave: Option<&str>,
+        staged_task: &StagedTask,
+    ) -> Result<bool> {
+        let mut workers = self
+            .workers
             .lock()
-            .map_err(|_| anyhow!("Cannot lock storage client"))?
-            .put(put_request)?;
+            .map_err(|_| anyhow!("Cannot lock workers"))?;
+        match workers.get_mut(worker_id) {
+            Some(worker) if worker.mr_enclave.as_deref() == mr_enclave => {
+                worker.pull(staged_task.task_id, staged_task.retry_count);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    // Forgets the task on the worker which pulled it, if any.
+    fn untrack_task(&self, task_id: &Uuid) {
+        if let Ok(mut workers) = self.workers.lock() {
+            for worker in workers.values_mut() {
+                worker.pulled_tasks.remove(task_id);
+            }
+        }
+    }
+
+    // Stages the deferred tasks whose upstream tasks have all finished, and
+    // fails those with an upstream task which will never produce the output.
+    // The deferred task queue is drained, and the tasks still waiting are put
+    // back. Failing a task may fail its own downstream tasks in turn.
+    pub(crate) fn stage_deferred_tasks(&self) {
+        let key = DeferredTask::get_queue_key().as_bytes();
+        let now = now_in_secs();
+        let mut deferred_tasks = Vec::new();
+        while let Ok(deferred_task) = self.pull_staged_task::<DeferredTask>(key) {
+            deferred_tasks.push(deferred_task);
+        }
+        let mut failed_any = false;
+        for mut deferred_task in deferred_tasks {
+            let task_id = deferred_task.staged_task.task_id;
+            let (mut ts, version) = match self.read_task_from_db(&task_id) {
+                Ok(task) => task,
+                Err(e) => {
+                    log::warn!("StageDeferredTasks: cannot read task: {:?}", e);
+                    self.requeue_deferred_task(&deferred_task);
+                    continue;
+                }
+            };
+            // Canceled, rejected or expired in the meantime
+            if ts.status != TaskStatus::Approved || !ts.staging_deferred {
+                continue;
+            }
+            if ts.is_expired(now) {
+                if let Err(e) = self.expire_task(ts, version) {
+                    log::warn!("StageDeferredTasks: cannot expire task: {:?}", e);
+                }
+                continue;
+            }
+            match ts.resolve_dependencies(|task_id| self.storage_client.get_item(task_id)) {
+                Ok(true) => match self.stage_deferred_task(ts, version, &mut deferred_task) {
+                    Ok(()) => log::info!("StageDeferredTasks: staged task {}", task_id),
+                    Err(e) => {
+                        log::warn!("StageDeferredTasks: cannot stage task: {:?}", e);
+                        self.requeue_deferred_task(&deferred_task);
+                    }
+                },
+                Ok(false) => self.requeue_deferred_task(&deferred_task),
+                Err(e) => match self.fail_deferred_task(ts, version, e) {
+                    Ok(()) => failed_any = true,
+                    Err(e) => log::warn!("StageDeferredTasks: cannot fail task: {:?}", e),
+                },
+            }
+        }
+        if failed_any {
+            self.stage_deferred_tasks();
+        }
+    }
+
+    // Completes the staged task with the inputs of the upstream tasks, and
+    // queues it like the management service does for other tasks.
+    fn stage_deferred_task(
+        &self,
+        ts: TaskState,
+        version: u64,
+        deferred_task: &mut DeferredTask,
+    ) -> Result<()> {
+        let mut task: Task<Stage> = ts.try_into()?;
+        deferred_task.staged_task.input_data = task.stage_deferred()?;
+        let mut ts = TaskState::from(task);
+        self.update_task_in_db(&mut ts, version)?;
+        self.storage_client.enqueue_item(
+            deferred_task.staged_task.queue_key().as_bytes(),
+            &deferred_task.staged_task,
+        )
+    }
+
+    // Requeued tasks are checked again from their states on the next pass.
+    fn requeue_deferred_task(&self, deferred_task: &DeferredTask) {
+        let key = DeferredTask::get_queue_key().as_bytes();
+        if let Err(e) = self.storage_client.enqueue_item(key, deferred_task) {
+            log::error!(
+                "StageDeferredTasks: lost deferred task {}: {:?}",
+                deferred_task.staged_task.task_id,
+                e
+            );
+        }
+    }
+
+    fn fail_deferred_task(
+        &self,
+        mut ts: TaskState,
+        version: u64,
+        reason: anyhow::Error,
+    ) -> Result<()> {
+        ts.fail_deferred(reason)?;
+        self.update_task_in_db(&mut ts, version)?;
+        self.record_task_finished(&ts);
+        log::info!("StageDeferredTasks: failed task {}", ts.task_id);
+        Ok(())
+    }
+
+    // Creates and stages the tasks of the schedule runs which are due. The
+    // tick queue is drained, and the ticks which are not due are put back.
+    // The tick of the next run is queued along with a run, and the ticks of
+    // canceled schedules are dropped.
+    pub(crate) fn run_task_schedules(&self) {
+        let key = TaskScheduleTick::get_queue_key().as_bytes();
+        let now = now_in_secs();
+        let mut ticks = Vec::new();
+        while let Ok(tick) = self.pull_staged_task::<TaskScheduleTick>(key) {
+            ticks.push(tick);
+        }
+        for tick in ticks.iter() {
+            if !tick.is_due(now) {
+                if let Err(e) = self.storage_client.enqueue_item(key, tick) {
+                    log::error!(
+                        "RunTaskSchedules: lost tick of schedule {}: {:?}",
+                        tick.template_id,
+                        e
+                    );
+                }
+                continue;
+            }
+            if let Err(e) = self.run_task_schedule(tick) {
+                log::warn!(
+                    "RunTaskSchedules: cannot run schedule {}: {:?}",
+                    tick.template_id,
+                    e
+                );
+            }
+        }
+    }
+
+    // The run is recorded before its task is created, so that a run is never
+    // created twice. A run whose task cannot be created, e.g., before every
+    // participant has approved the schedule, is skipped.
+    fn run_task_schedule(&self, tick: &TaskScheduleTick) -> Result<()> {
+        let (mut schedule, stored) = self.read_task_schedule_from_db(&tick.template_id)?;
+        if schedule.canceled || schedule.runs != tick.run {
+            return Ok(());
+        }
+        let template: TaskTemplate = self.storage_client.get_item(&ExternalID::new(
+            TaskTemplate::key_prefix(),
+            tick.template_id,
+        ))?;
+
+        let created = self.create_scheduled_task(&schedule, &template, tick.run);
+        schedule.runs += 1;
+        match &created {
+            Ok((ts, _)) => schedule.task_ids.push(ts.external_id()),
+            Err(e) => log::warn!(
+                "RunTaskSchedules: skipped run {} of schedule {}: {:?}",
+                tick.run,
+                tick.template_id,
+                e
+            ),
+        }
+        self.update_task_schedule_in_db(&schedule, stored)?;
+
+        if let Some(run_at) = schedule.spec.run_at(schedule.runs) {
+            let next_tick = TaskScheduleTick::new(schedule.template_id, schedule.runs, run_at);
+            self.storage_client
+                .enqueue_item(TaskScheduleTick::get_queue_key().as_bytes(), &next_tick)?;
+        }
+
+        if let Ok((ts, staged_task)) = created {
+            self.stage_scheduled_task(&ts, &staged_task)?;
+            log::info!(
+                "RunTaskSchedules: staged task {} of schedule {}",
+                ts.task_id,
+                tick.template_id
+            );
+        }
+        Ok(())
+    }
+
+    // Outputs of the task are new fusion data of their owners, which is
+    // written along with the task.
+    fn create_scheduled_task(
+        &self,
+        schedule: &TaskSchedule,
+        template: &TaskTemplate,
+        run: u64,
+    ) -> Result<(TaskState, StagedTask)> {
+        let function: Function = self.storage_client.get_item(&template.function_id)?;
+        let mut inputs = HashMap::new();
+        for (fname, data_id) in schedule.inputs.iter() {
+            let file: TeaclaveInputFile = self.storage_client.get_item(data_id)?;
+            inputs.insert(fname.to_owned(), file);
+        }
+        let outputs: HashMap<String, TeaclaveOutputFile> = template
+            .outputs_ownership
+            .clone()
+            .into_iter()
+            .map(|(fname, owners)| (fname, TeaclaveOutputFile::new_fusion(owners)))
+            .collect();
+
+        let (mut ts, staged_task) =
+            schedule.instantiate(template, function, inputs, outputs.clone(), run)?;
+        ts.created_at = Some(now_in_secs());
+        for file in outputs.values() {
+            self.storage_client.put_item(file)?;
+        }
+        Ok((ts, staged_task))
+    }
+
+    // Stores the task like the management service does for created tasks,
+    // and queues it for the execution service.
+    fn stage_scheduled_task(&self, ts: &TaskState, staged_task: &StagedTask) -> Result<()> {
+        self.storage_client.put_item(ts)?;
+        for user_id in ts.participants.clone() {
+            let mut user_tasks = self
+                .storage_client
+                .get_item(&UserTasks::external_id_of(&user_id))
+                .unwrap_or_else(|_| UserTasks::new(user_id.clone()));
+            user_tasks.add_task(ts);
+            self.storage_client.put_item(&user_tasks)?;
+        }
+        self.storage_client
+            .enqueue_item(staged_task.queue_key().as_bytes(), staged_task)?;
+        self.record_activity(ts, ActivityKind::TaskCreated);
         Ok(())
     }
+
+    // Notifications are best effort, like those of newly created tasks.
+    fn notify_approval_expired(&self, ts: &TaskState) {
+        let function_name = self
+            .storage_client
+            .get_item::<Function>(&ts.function_id)
+            .map(|function| function.name)
+            .unwrap_or_default();
+        for notification in TaskNotification::for_approval_expired(ts, &function_name) {
+            let queue_key = notification.queue_key();
+            if let Err(e) = self
+                .storage_client
+                .enqueue_item(queue_key.as_bytes(), &notification)
+            {
+                log::warn!(
+                    "SweepApprovalWindows: failed to notify {}: {:?}",
+                    queue_key,
+                    e
+                );
+            }
+        }
+    }
+
+    // Moves an expired task to the Expired state unless it is there already.
+    fn expire_task(&self, mut ts: TaskState, version: u64) -> Result<()> {
+        if ts.status != TaskStatus::Expired {
+            ts.expire()?;
+            self.update_task_in_db(&mut ts, version)?;
+        }
+        Ok(())
+    }
+
+    fn record_task_finished(&self, ts: &TaskState) {
+        self.record_activity(ts, ActivityKind::TaskFinished);
+    }
+
+    // Adds the event of the task to the activity feeds of the participants,
+    // which are best effort.
+    fn record_activity(&self, ts: &TaskState, kind: ActivityKind) {
+        let now = now_in_secs();
+        for user_id in ts.participants.clone() {
+            let mut activity = self
+                .storage_client
+                .get_item(&UserActivity::external_id_of(&user_id))
+                .unwrap_or_else(|_| UserActivity::new(user_id.clone()));
+            activity.record(ActivityEvent::new(kind, ts.external_id(), now));
+            if let Err(e) = self.storage_client.put_item(&activity) {
+                log::warn!("Failed to record activity of {}: {:?}", user_id, e);
+            }
+        }
+    }
+
+    // Executors whose queues a worker pulls from, its home queue first.
+    // Workers which have not registered support all executors, and start
+    // from the queue of each executor in turn.
+    fn queue_executors(&self, worker_id: Option<Uuid>, pull: u64) -> Vec<Executor> {
+        let registered = worker_id.and_then(|worker_id| {
+            let workers = self.workers.lock().ok()?;
+            workers.get(&worker_id).map(|w| w.queue_executors())
+        });
+        registered.unwrap_or_else(|| {
+            let mut executors = Executor::all().to_vec();
+            let home = (pull % executors.len() as u64) as usize;
+            executors.rotate_left(home);
+            executors
+        })
+    }
+
+    // Pulls the first task of the queue which the execution enclave of the
+    // measurement and the signer is allowed to run with one of the executors.
+    // Canceled and expired tasks, and tasks served from the result of an
+    // identical task are never handed to the execution service.
+    fn pull_task_of(
+        &self,
+        key: &[u8],
+        mr_enclave: Option<&str>,
+        mr_signer: Option<&str>,
+        executors: &[Executor],
+        passed_over: &mut Vec<StagedTask>,
+    ) -> TeaclaveServiceResponseResult<StagedTask> {
+        loop {
+            let staged_task: StagedTask = match self.pull_staged_task(key) {
+                Ok(staged_task) => staged_task,
+                Err(e) => break Err(e),
+            };
+            if !staged_task.accepts_worker(mr_enclave, mr_signer)
+                || !executors.contains(&staged_task.executor)
+            {
+                passed_over.push(staged_task);
+                continue;
+            }
+            let (ts, version) = match self.read_task_from_db(&staged_task.task_id) {
+                Ok(task) => task,
+                Err(e) => {
+                    log::warn!("PullTask: cannot read task: {:?}", e);
+                    break Ok(staged_task);
+                }
+            };
+            if ts.status == TaskStatus::Canceled {
+                log::info!("PullTask: dropped canceled task {}", ts.external_id());
+                continue;
+            }
+            if ts.is_expired(now_in_secs()) {
+                log::info!("PullTask: dropped expired task {}", ts.external_id());
+                if let Err(e) = self.expire_task(ts, version) {
+                    log::warn!("PullTask: cannot expire task: {:?}", e);
+                }
+                continue;
+            }
+            match self.reuse_result(ts, version) {
+                Ok(true) => continue,
+                Ok(false) => break Ok(staged_task),
+                Err(e) => {
+                    log::warn!("PullTask: cannot reuse result: {:?}", e);
+                    break Ok(staged_task);
+                }
+            }
+        }
+    }
+
+    fn get_task_state(&self, task_id: &Uuid) -> Result<TaskState> {
+        let key = ExternalID::new(TaskState::key_prefix(), task_id.to_owned());
+        self.storage_client.get_item(&key)
+    }
+
+    // The stored version of the task is returned along with it, which is
+    // expected to be unchanged when the task is written back.
+    fn read_task_from_db(&self, task_id: &Uuid) -> Result<(TaskState, u64)> {
+        let key = ExternalID::new(TaskState::key_prefix(), task_id.to_owned());
+        self.storage_client.get_versioned_item(&key)
+    }
+
+    // Writes the task only if it is still at the version it was read at, so
+    // that a task canceled in the meantime is not brought back to life.
+    fn update_task_in_db(&self, ts: &mut TaskState, version: u64) -> Result<()> {
+        ts.revision += 1;
+        self.storage_client.put_item_if_version(ts, version)
+    }
+
+    // Same as read_task_from_db for the schedule of a template.
+    fn read_task_schedule_from_db(&self, template_id: &Uuid) -> Result<(TaskSchedule, Vec<u8>)> {
+        let key = TaskSchedule::external_id_of(template_id);
+        let get_request = GetRequest::new(key.to_bytes());
+        let response = self.storage_client.get(get_request)?;
+        let schedule = TaskSchedule::from_slice(response.value.as_slice())?;
+        Ok((schedule, response.value))
+    }
+
+    // A schedule canceled in the meantime is not brought back to life.
+    fn update_task_schedule_in_db(&self, schedule: &TaskSchedule, stored: Vec<u8>) -> Result<()> {
+        let request = CompareAndSwapRequest::new(schedule.key(), stored, schedule.to_vec()?);
+        let _response = self.storage_client.compare_and_swap(request)?;
+        Ok(())
+    }
+}
+
+fn now_in_secs() -> u64 {
+    TrustedTime::now_secs()
+}
+
+// The queues of a priority in the order they are searched on a pull: those of
+// the executors, the home queue of the worker first, and then the shared queue
+// of tasks staged before the executors had queues of their own.
+fn queue_keys(priority: TaskPriority, executors: &[Executor]) -> Vec<String> {
+    let mut keys: Vec<String> = executors
+        .iter()
+        .map(|executor| priority.executor_queue_key(*executor))
+        .collect();
+    keys.push(priority.queue_key().to_string());
+    keys
 }
 
 impl TeaclaveScheduler for TeaclaveSchedulerService {
@@ -134,27 +966,84 @@ impl TeaclaveScheduler for TeaclaveSchedulerService {
 
     fn pull_task(
         &self,
-        _request: Request<PullTaskRequest>,
+        request: Request<PullTaskRequest>,
     ) -> TeaclaveServiceResponseResult<PullTaskResponse> {
-        let key = StagedTask::get_queue_key().as_bytes();
-        let staged_task = self.pull_staged_task(key)?;
+        let mr_enclave = request.peer_mr_enclave();
+        let mr_signer = request.peer_mr_signer();
+        let worker_id = request.message.worker_id;
+        let pull = self.pulls.fetch_add(1, Ordering::Relaxed);
+        let executors = self.queue_executors(worker_id, pull);
+        // Tasks pinned to other execution enclaves, or of executors the worker
+        // does not support, are put back to the queues of their executors once
+        // the queues are searched.
+        let mut passed_over = Vec::new();
+        let mut staged_task = Err(TeaclaveSchedulerError::DataError.into());
+        'priorities: for priority in TaskPriority::pull_order(pull) {
+            for key in queue_keys(priority, &executors) {
+                staged_task = self.pull_task_of(
+                    key.as_bytes(),
+                    mr_enclave,
+                    mr_signer,
+                    &executors,
+                    &mut passed_over,
+                );
+                if staged_task.is_ok() {
+                    break 'priorities;
+                }
+            }
+        }
+        for passed in passed_over.iter() {
+            self.enqueue_staged_task(passed.queue_key().as_bytes(), passed)?;
+        }
+        let staged_task = staged_task?;
+        // The dispatch is recorded so that the task does not stay running
+        // forever if the execution service never reports its result.
+        if let Some(timeout) = staged_task.timeout {
+            let dispatch = TaskDispatch::new(staged_task.task_id, now_in_secs(), timeout)
+                .attempt(staged_task.retry_count);
+            self.storage_client
+                .enqueue_item(TaskDispatch::get_queue_key().as_bytes(), &dispatch)
+                .map_err(|_| TeaclaveSchedulerError::DataError)?;
+        }
+        // The staged task is kept to be staged again if the task fails
+        // transiently, or if the registered worker pulling it is lost.
+        let tracked = match worker_id {
+            Some(worker_id) => self.track_task(&worker_id, mr_enclave, &staged_task)?,
+            None => false,
+        };
+        if tracked || staged_task.retry_count < staged_task.retry_policy.max_retries {
+            self.storage_client
+                .put_item(&staged_task)
+                .map_err(|_| TeaclaveSchedulerError::DataError)?;
+        }
         let response = PullTaskResponse::new(staged_task);
         Ok(response)
     }
 
+    fn pull_prewarm_task(
+        &self,
+        _request: Request<PullPrewarmTaskRequest>,
+    ) -> TeaclaveServiceResponseResult<PullPrewarmTaskResponse> {
+        let key = PrewarmTask::get_queue_key().as_bytes();
+        let prewarm_task = self.pull_staged_task(key)?;
+        let response = PullPrewarmTaskResponse::new(prewarm_task);
+        Ok(response)
+    }
+
     fn update_task_status(
         &self,
         request: Request<UpdateTaskStatusRequest>,
     ) -> TeaclaveServiceResponseResult<UpdateTaskStatusResponse> {
         let request = request.message;
-        let ts = self.get_task_state(&request.task_id)?;
+        let (ts, version) = self.read_task_from_db(&request.task_id)?;
         let task: Task<Run> = ts.try_into()?;
 
         log::debug!("UpdateTaskStatus: Task {:?}", task);
-        // Only TaskStatus::Running is implicitly allowed here.
+        // Only TaskStatus::Running is implicitly allowed here. A task canceled
+        // after it was pulled fails here and is never run.
 
-        let ts = TaskState::from(task);
-        self.put_into_db(&ts)?;
+        let mut ts = TaskState::from(task);
+        self.update_task_in_db(&mut ts, version)?;
         Ok(UpdateTaskStatusResponse {})
     }
 
@@ -163,13 +1052,23 @@ impl TeaclaveScheduler for TeaclaveSchedulerService {
         request: Request<UpdateTaskResultRequest>,
     ) -> TeaclaveServiceResponseResult<UpdateTaskResultResponse> {
         let request = request.message;
+        if let TaskResult::Err(failure) = &request.task_result {
+            if self.retry_task(&request.task_id, failure)? {
+                return Ok(UpdateTaskResultResponse {});
+            }
+        }
         let ts = self.get_task_state(&request.task_id)?;
+        // The outputs of a delegated task are registered by the deployment
+        // delegating it, which takes them from the result.
+        let delegated = ts.delegated_from.is_some();
         let mut task: Task<Finish> = ts.try_into()?;
 
         if let TaskResult::Ok(outputs) = &request.task_result {
             for (key, auth_tag) in outputs.tags_map.iter() {
                 let outfile = task.update_output_cmac(key, auth_tag)?;
-                self.put_into_db(outfile)?;
+                if !delegated {
+                    self.storage_client.put_item(outfile)?;
+                }
             }
         };
 
@@ -177,10 +1076,152 @@ impl TeaclaveScheduler for TeaclaveSchedulerService {
         task.update_result(request.task_result)?;
         log::debug!("UpdateTaskResult: Task {:?}", task);
 
-        let ts = TaskState::from(task);
-        self.put_into_db(&ts)?;
+        let mut ts = TaskState::from(task);
+        ts.revision += 1;
+        self.storage_client.put_item(&ts)?;
+        self.record_task_finished(&ts);
+        self.forget_staged_task(&ts);
+
+        // Downstream tasks waiting for the outputs are staged right away
+        // rather than on the next sweep.
+        self.stage_deferred_tasks();
+
+        // Tests produce no file, and their results are never reused. Neither
+        // are the results of delegated tasks, whose functions are not stored
+        // here.
+        if ts.function_test.is_some() || delegated {
+            return Ok(UpdateTaskResultResponse {});
+        }
+
+        // Record what produced the outputs so that the result can be reproduced
+        if let TaskResult::Ok(outputs) = &ts.result {
+            // Partial results of timed out tasks tell nothing of the run time.
+            if let Some(usage) = request.usage.filter(|_| !outputs.partial) {
+                self.record_function_usage(&ts.function_id.uuid, &usage);
+            }
+
+            let function: Function = self.storage_client.get_item(&ts.function_id)?;
+            let manifest = TaskManifest::new(&ts, &function, &outputs.tags_map, &self.measurements);
+            self.storage_client.put_item(&manifest)?;
+
+            // Partial results of timed out tasks are never reused.
+            let fingerprint = if outputs.partial {
+                None
+            } else {
+                ts.result_fingerprint(&function)
+            };
+            if let Some(fingerprint) = fingerprint {
+                let reusable = ReusableResult::new(
+                    fingerprint,
+                    ts.external_id(),
+                    outputs.return_value.clone(),
+                )
+                .return_value_handle(outputs.return_value_handle.clone());
+                self.storage_client.put_item(&reusable)?;
+            }
+        }
         Ok(UpdateTaskResultResponse {})
     }
+
+    // Chunks are only accepted for running tasks, before their results.
+    fn put_return_value_chunk(
+        &self,
+        request: Request<PutReturnValueChunkRequest>,
+    ) -> TeaclaveServiceResponseResult<PutReturnValueChunkResponse> {
+        let chunk = request.message.chunk;
+        let ts = self.get_task_state(&chunk.task_id)?;
+        let _task: Task<Finish> = ts.try_into()?;
+        self.storage_client.put_item(&chunk)?;
+        Ok(PutReturnValueChunkResponse {})
+    }
+
+    // Offers are only accepted for running tasks exchanging keys.
+    fn put_task_key_offer(
+        &self,
+        request: Request<PutTaskKeyOfferRequest>,
+    ) -> TeaclaveServiceResponseResult<PutTaskKeyOfferResponse> {
+        let request = request.message;
+        let ts = self.get_task_state(&request.task_id)?;
+        if !ts.key_exchange {
+            return Err(anyhow!("Task does not exchange keys").into());
+        }
+        let _task: Task<Finish> = ts.try_into()?;
+        let key_exchange = TaskKeyExchange::new(request.task_id, request.offer);
+        self.storage_client.put_item(&key_exchange)?;
+        Ok(PutTaskKeyOfferResponse {})
+    }
+
+    fn get_task_keys(
+        &self,
+        request: Request<GetTaskKeysRequest>,
+    ) -> TeaclaveServiceResponseResult<GetTaskKeysResponse> {
+        let task_id = request.message.task_id;
+        let key_exchange = self
+            .storage_client
+            .get_item(&TaskKeyExchange::external_id_of(&task_id))?;
+        Ok(GetTaskKeysResponse::new(key_exchange))
+    }
+
+    // Slots are only accepted for running tasks.
+    fn put_task_upload_slots(
+        &self,
+        request: Request<PutTaskUploadSlotsRequest>,
+    ) -> TeaclaveServiceResponseResult<PutTaskUploadSlotsResponse> {
+        let upload_slots = request.message.upload_slots;
+        let ts = self.get_task_state(&upload_slots.task_id)?;
+        let _task: Task<Finish> = ts.try_into()?;
+        self.storage_client.put_item(&upload_slots)?;
+        Ok(PutTaskUploadSlotsResponse {})
+    }
+
+    fn register_worker(
+        &self,
+        request: Request<RegisterWorkerRequest>,
+    ) -> TeaclaveServiceResponseResult<RegisterWorkerResponse> {
+        let mr_enclave = request.peer_mr_enclave().map(ToOwned::to_owned);
+        let capabilities = request.message.capabilities;
+        let worker = WorkerRegistration::new(mr_enclave, capabilities, now_in_secs());
+        let worker_id = worker.worker_id;
+        log::info!(
+            "RegisterWorker: worker {} registered with {:?}",
+            worker_id,
+            worker.capabilities
+        );
+        let heartbeat = WorkerHeartbeat::from(&worker);
+        self.workers
+            .lock()
+            .map_err(|_| anyhow!("Cannot lock workers"))?
+            .insert(worker_id, worker);
+        self.record_heartbeat(&heartbeat);
+        Ok(RegisterWorkerResponse::new(
+            worker_id,
+            WORKER_HEARTBEAT_INTERVAL,
+        ))
+    }
+
+    // Heartbeats are only accepted from the enclave which registered the
+    // worker.
+    fn heartbeat(
+        &self,
+        request: Request<HeartbeatRequest>,
+    ) -> TeaclaveServiceResponseResult<HeartbeatResponse> {
+        let mr_enclave = request.peer_mr_enclave().map(ToOwned::to_owned);
+        let request = request.message;
+        let heartbeat = {
+            let mut workers = self
+                .workers
+                .lock()
+                .map_err(|_| anyhow!("Cannot lock workers"))?;
+            let worker = workers
+                .get_mut(&request.worker_id)
+                .filter(|worker| worker.mr_enclave == mr_enclave)
+                .ok_or_else(|| anyhow!("Unknown worker"))?;
+            worker.heartbeat(request.load, now_in_secs());
+            WorkerHeartbeat::from(&*worker)
+        };
+        self.record_heartbeat(&heartbeat);
+        Ok(HeartbeatResponse {})
+    }
 }
 
 #[cfg(test_mode)]
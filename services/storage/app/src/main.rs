@@ -16,11 +16,16 @@
 // under the License.
 
 use anyhow::{Context, Result};
+use std::env;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
+use teaclave_config::RuntimeConfig;
 use teaclave_service_app_utils::{register_signals, TeaclaveServiceLauncher};
 
+// Use to import ocall
+pub use teaclave_file_agent::ocall_handle_file_request;
+
 const PACKAGE_NAME: &str = env!("CARGO_PKG_NAME");
 
 fn main() -> Result<()> {
@@ -30,10 +35,39 @@ fn main() -> Result<()> {
             .write_style_or("TEACLAVE_LOG_STYLE", "RUST_LOG_STYLE"),
     );
 
-    let launcher = Arc::new(TeaclaveServiceLauncher::new(
-        PACKAGE_NAME,
-        "runtime.config.toml",
-    )?);
+    let mut config =
+        RuntimeConfig::from_toml("runtime.config.toml").context("Failed to load config file.")?;
+    // Serve one of the storage shards instead of the default storage.
+    if let Ok(name) = env::var("TEACLAVE_STORAGE_SHARD") {
+        let shard = config
+            .storage_shards
+            .iter()
+            .find(|shard| shard.name == name)
+            .with_context(|| format!("Cannot find storage shard {}", name))?;
+        config.internal_endpoints.storage.listen_address = shard.listen_address;
+        config.internal_endpoints.storage.advertised_address = shard.advertised_address.clone();
+        scope_storage(&mut config, &name);
+    }
+    // Serve one of the read replicas following the storage service.
+    if let Ok(name) = env::var("TEACLAVE_STORAGE_REPLICA") {
+        let replica = config
+            .storage_replicas
+            .iter()
+            .find(|replica| replica.name == name)
+            .with_context(|| format!("Cannot find storage replica {}", name))?;
+        let listen_address = replica.listen_address;
+        let advertised_address = replica.advertised_address.clone();
+        let storage = &mut config.internal_endpoints.storage;
+        config.storage_replica_primary = Some(std::mem::replace(
+            &mut storage.advertised_address,
+            advertised_address,
+        ));
+        storage.listen_address = listen_address;
+        // Log and backend of the replica once it is promoted.
+        scope_storage(&mut config, &name);
+    }
+
+    let launcher = Arc::new(TeaclaveServiceLauncher::with_config(PACKAGE_NAME, config)?);
     let launcher_ref = launcher.clone();
     thread::spawn(move || {
         let _ = launcher_ref.start();
@@ -54,3 +88,17 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+// Keeps the log and the backend of a shard or a replica apart from the others.
+fn scope_storage(config: &mut RuntimeConfig, name: &str) {
+    if let Some(wal) = config.storage_wal.as_mut() {
+        wal.dir = wal.dir.join(name);
+    }
+    let backend = &mut config.storage_backend;
+    if let Some(dir) = backend.dir.as_mut() {
+        *dir = dir.join(name);
+    }
+    if let Some(url) = backend.url.as_mut() {
+        *url = format!("{}/{}/", url.trim_end_matches('/'), name);
+    }
+}
@@ -0,0 +1,200 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Typed client of the storage service for services built on its persistence
+//! layer.
+//!
+//! The storage service only knows about bytes; records are encoded, keyed and
+//! indexed by their `Storable` implementations. `TypedStorage` reads and
+//! writes them through a `TeaclaveStorageRouter`, which is connected to the
+//! storage services over attested channels with `connect_router`:
+//!
+//! ```ignore
+//! let storage = connect_router(&endpoint, &[], &[])?;
+//! storage.put_item(&function)?;
+//! let function: Function = storage.get_item(&function.external_id())?;
+//! ```
+//!
+//! Requests not covered by the typed helpers, e.g. transactions and index
+//! scans, are sent with the router itself.
+
+#![cfg_attr(feature = "mesalock_sgx", no_std)]
+#[cfg(feature = "mesalock_sgx")]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+use anyhow::{ensure, Result};
+use std::prelude::v1::*;
+use std::time::Duration;
+use teaclave_rpc::endpoint::Endpoint;
+use teaclave_types::{ExternalID, Storable};
+
+pub use teaclave_proto::teaclave_storage_router::TeaclaveStorageRouter;
+pub use teaclave_proto::teaclave_storage_service::{TeaclaveStorageClient, Transaction};
+
+use teaclave_proto::teaclave_storage_service::{
+    DeleteRequest, DequeueRequest, EnqueueRequest, GetRequest, PutIfVersionRequest,
+    PutIndexedRequest, PutRequest, PutWithTtlRequest,
+};
+
+// The storage service may still be starting when the services depending on it
+// are, so a connection is retried for half a minute.
+const CONNECT_RETRIES: usize = 10;
+const CONNECT_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Connects to the storage service at the endpoint.
+pub fn connect(endpoint: &Endpoint) -> Result<TeaclaveStorageClient> {
+    let mut i = 0;
+    let channel = loop {
+        match endpoint.connect() {
+            Ok(channel) => break channel,
+            Err(_) => {
+                ensure!(i < CONNECT_RETRIES, "failed to connect to storage service");
+                log::debug!("Failed to connect to storage service, retry {}", i);
+                i += 1;
+            }
+        }
+        std::thread::sleep(CONNECT_INTERVAL);
+    };
+    TeaclaveStorageClient::new(channel)
+}
+
+/// Connects to the default storage service, its shards by key prefixes and
+/// its named read replicas.
+pub fn connect_router(
+    default: &Endpoint,
+    shards: &[(Vec<String>, Endpoint)],
+    replicas: &[(String, Endpoint)],
+) -> Result<TeaclaveStorageRouter> {
+    let mut router = TeaclaveStorageRouter::new(connect(default)?);
+    for (key_prefixes, endpoint) in shards.iter() {
+        router = router.shard(key_prefixes, connect(endpoint)?);
+    }
+    for (name, endpoint) in replicas.iter() {
+        router = router.replica(name, connect(endpoint)?);
+    }
+    Ok(router)
+}
+
+/// Reads and writes `Storable` records.
+pub trait TypedStorage {
+    /// Reads the record of the id. Records marked as deleted are not found.
+    fn get_item<T: Storable>(&self, id: &ExternalID) -> Result<T>;
+
+    /// Same as `get_item`, along with the stored version of the record.
+    fn get_versioned_item<T: Storable>(&self, id: &ExternalID) -> Result<(T, u64)>;
+
+    /// Reads from a read replica if any, for queries tolerating values slightly
+    /// behind the storage. Never read a record to be written back this way.
+    fn get_item_from_replica<T: Storable>(&self, id: &ExternalID) -> Result<T>;
+
+    /// Writes the record along with the entries of its secondary indexes,
+    /// which replace the entries it was written with.
+    fn put_item(&self, item: &impl Storable) -> Result<()>;
+
+    /// Writes the record only if it is still at the version it was read at.
+    fn put_item_if_version(&self, item: &impl Storable, version: u64) -> Result<()>;
+
+    /// Writes the record, which expires `ttl` seconds later unless it is
+    /// written again. Records with secondary indexes are not supported.
+    fn put_item_with_ttl(&self, item: &impl Storable, ttl: u64) -> Result<()>;
+
+    fn delete_item(&self, id: &ExternalID) -> Result<()>;
+
+    fn enqueue_item(&self, queue: &[u8], item: &impl Storable) -> Result<()>;
+
+    /// Takes the record at the head of the queue, fails if it is empty.
+    fn dequeue_item<T: Storable>(&self, queue: &[u8]) -> Result<T>;
+}
+
+impl TypedStorage for TeaclaveStorageRouter {
+    fn get_item<T: Storable>(&self, id: &ExternalID) -> Result<T> {
+        self.get_versioned_item(id).map(|(item, _)| item)
+    }
+
+    fn get_versioned_item<T: Storable>(&self, id: &ExternalID) -> Result<(T, u64)> {
+        ensure!(T::match_prefix(&id.prefix), "Key prefix doesn't match.");
+        let response = self.get(GetRequest::new(id.to_bytes()))?;
+        let item = T::from_slice(response.value.as_slice())?;
+        ensure!(!item.is_deleted(), "Record is deleted.");
+        Ok((item, response.version))
+    }
+
+    fn get_item_from_replica<T: Storable>(&self, id: &ExternalID) -> Result<T> {
+        ensure!(T::match_prefix(&id.prefix), "Key prefix doesn't match.");
+        let response = self.get_from_replica(GetRequest::new(id.to_bytes()))?;
+        let item = T::from_slice(response.value.as_slice())?;
+        ensure!(!item.is_deleted(), "Record is deleted.");
+        Ok(item)
+    }
+
+    fn put_item(&self, item: &impl Storable) -> Result<()> {
+        let k = item.key();
+        let v = item.to_vec()?;
+        let terms = item.index_terms();
+        if terms.is_empty() {
+            self.put(PutRequest::new(k.as_slice(), v.as_slice()))?;
+        } else {
+            self.put_indexed(PutIndexedRequest::new(k.as_slice(), v.as_slice(), terms))?;
+        }
+        Ok(())
+    }
+
+    fn put_item_if_version(&self, item: &impl Storable, version: u64) -> Result<()> {
+        let request = PutIfVersionRequest::new(item.key(), item.to_vec()?, version);
+        self.put_if_version(request)?;
+        Ok(())
+    }
+
+    fn put_item_with_ttl(&self, item: &impl Storable, ttl: u64) -> Result<()> {
+        ensure!(
+            item.index_terms().is_empty(),
+            "Indexed records cannot expire."
+        );
+        let request = PutWithTtlRequest::new(item.key(), item.to_vec()?, ttl);
+        self.put_with_ttl(request)?;
+        Ok(())
+    }
+
+    fn delete_item(&self, id: &ExternalID) -> Result<()> {
+        self.delete(DeleteRequest::new(id.to_bytes()))?;
+        Ok(())
+    }
+
+    fn enqueue_item(&self, queue: &[u8], item: &impl Storable) -> Result<()> {
+        self.enqueue(EnqueueRequest::new(queue, item.to_vec()?))?;
+        Ok(())
+    }
+
+    fn dequeue_item<T: Storable>(&self, queue: &[u8]) -> Result<T> {
+        let response = self.dequeue(DequeueRequest::new(queue))?;
+        T::from_slice(response.value.as_slice())
+    }
+}
+
+/// Adds the write of the record to the transaction, as `put_item` writes it.
+pub fn put_item_in_transaction(transaction: &mut Transaction, item: &impl Storable) -> Result<()> {
+    let k = item.key();
+    let v = item.to_vec()?;
+    let terms = item.index_terms();
+    if terms.is_empty() {
+        transaction.put(k, v);
+    } else {
+        transaction.put_indexed(k, v, terms);
+    }
+    Ok(())
+}
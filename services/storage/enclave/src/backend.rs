@@ -0,0 +1,357 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Persistence layers of the storage, selected by `StorageBackendConfig`:
+//!
+//!   leveldb -> LevelDB, in files sealed with the protected file system of
+//!              SGX, or in memory
+//!   memory  -> sorted map in memory, for tests
+//!   remote  -> keys in memory, checkpointed to an object storage, see `remote`
+//!
+//! The replication log, the write-ahead log, indexes and versions are kept on
+//! top of the backend, so they work the same on any of them.
+
+use crate::remote::RemoteBackend;
+use anyhow::Result;
+use protected_fs::ProtectedFile;
+use rusty_leveldb::{LdbIterator, Options, Status, WriteBatch, DB};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::prelude::v1::*;
+use std::untrusted::fs;
+use std::untrusted::path::PathEx;
+use teaclave_config::{StorageBackendConfig, StorageBackendKind};
+use teaclave_crypto::TeaclaveFile128Key;
+
+pub(crate) const DB_NAME: &str = "teaclave_db";
+// The key of the sealed files of LevelDB is created along with them, and kept
+// in this file next to them, sealed by the enclave.
+const KEY_FILE: &str = "teaclave_db.key";
+
+/// Keys and values of the storage, sorted by key.
+pub(crate) trait StorageBackend {
+    fn get(&mut self, key: &[u8]) -> Option<Vec<u8>>;
+
+    fn put(&mut self, key: &[u8], value: &[u8]) -> std::result::Result<(), Status>;
+
+    fn delete(&mut self, key: &[u8]) -> std::result::Result<(), Status>;
+
+    /// Writes the puts and deletes in order, either all of them or none.
+    fn write(&mut self, writes: &[(Vec<u8>, Option<Vec<u8>>)]) -> std::result::Result<(), Status>;
+
+    /// Iterates over the keys in order, as they are when the iterator is
+    /// created.
+    fn new_iter(&mut self) -> std::result::Result<Box<dyn LdbIterator>, Status>;
+
+    /// Persists the writes so far, for a backend not persisting each of them.
+    fn sync(&mut self) -> std::result::Result<(), Status> {
+        Ok(())
+    }
+
+    /// Replaces all keys with the entries in one write.
+    fn replace_all(&mut self, entries: &[(Vec<u8>, Vec<u8>)]) -> std::result::Result<(), Status> {
+        let mut writes = Vec::new();
+        let mut iter = self.new_iter()?;
+        iter.seek_to_first();
+        let (mut key, mut value) = (Vec::new(), Vec::new());
+        while iter.current(&mut key, &mut value) {
+            writes.push((key.clone(), None));
+            if !iter.advance() {
+                break;
+            }
+        }
+        writes.extend(
+            entries
+                .iter()
+                .map(|(key, value)| (key.clone(), Some(value.clone()))),
+        );
+        self.write(&writes)
+    }
+}
+
+impl<B: StorageBackend + ?Sized> StorageBackend for Box<B> {
+    fn get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        self.as_mut().get(key)
+    }
+
+    fn put(&mut self, key: &[u8], value: &[u8]) -> std::result::Result<(), Status> {
+        self.as_mut().put(key, value)
+    }
+
+    fn delete(&mut self, key: &[u8]) -> std::result::Result<(), Status> {
+        self.as_mut().delete(key)
+    }
+
+    fn write(&mut self, writes: &[(Vec<u8>, Option<Vec<u8>>)]) -> std::result::Result<(), Status> {
+        self.as_mut().write(writes)
+    }
+
+    fn new_iter(&mut self) -> std::result::Result<Box<dyn LdbIterator>, Status> {
+        self.as_mut().new_iter()
+    }
+
+    fn sync(&mut self) -> std::result::Result<(), Status> {
+        self.as_mut().sync()
+    }
+}
+
+// Every write is synced, which flushes it to the sealed files on disk.
+impl StorageBackend for DB {
+    fn get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        DB::get(self, key)
+    }
+
+    fn put(&mut self, key: &[u8], value: &[u8]) -> std::result::Result<(), Status> {
+        let mut batch = WriteBatch::new();
+        batch.put(key, value);
+        DB::write(self, batch, true)
+    }
+
+    fn delete(&mut self, key: &[u8]) -> std::result::Result<(), Status> {
+        let mut batch = WriteBatch::new();
+        batch.delete(key);
+        DB::write(self, batch, true)
+    }
+
+    fn write(&mut self, writes: &[(Vec<u8>, Option<Vec<u8>>)]) -> std::result::Result<(), Status> {
+        let mut batch = WriteBatch::new();
+        for (key, value) in writes {
+            match value {
+                Some(value) => batch.put(key, value),
+                None => batch.delete(key),
+            }
+        }
+        DB::write(self, batch, true)
+    }
+
+    fn new_iter(&mut self) -> std::result::Result<Box<dyn LdbIterator>, Status> {
+        Ok(Box::new(DB::new_iter(self)?))
+    }
+}
+
+/// Sorted map in memory, which is simple enough to test the layers on top of
+/// the backend with. Each iterator copies all keys.
+#[derive(Default)]
+pub(crate) struct MemoryBackend {
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl StorageBackend for MemoryBackend {
+    fn get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        self.entries.get(key).cloned()
+    }
+
+    fn put(&mut self, key: &[u8], value: &[u8]) -> std::result::Result<(), Status> {
+        self.entries.insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &[u8]) -> std::result::Result<(), Status> {
+        self.entries.remove(key);
+        Ok(())
+    }
+
+    fn write(&mut self, writes: &[(Vec<u8>, Option<Vec<u8>>)]) -> std::result::Result<(), Status> {
+        for (key, value) in writes {
+            match value {
+                Some(value) => self.entries.insert(key.clone(), value.clone()),
+                None => self.entries.remove(key),
+            };
+        }
+        Ok(())
+    }
+
+    fn new_iter(&mut self) -> std::result::Result<Box<dyn LdbIterator>, Status> {
+        Ok(Box::new(MemoryIter {
+            entries: self
+                .entries
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            position: None,
+        }))
+    }
+}
+
+struct MemoryIter {
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+    // None before the first key, as after a reset.
+    position: Option<usize>,
+}
+
+impl LdbIterator for MemoryIter {
+    fn advance(&mut self) -> bool {
+        let next = self.position.map_or(0, |position| position + 1);
+        self.position = Some(next).filter(|next| *next < self.entries.len());
+        self.position.is_some()
+    }
+
+    fn current(&self, key: &mut Vec<u8>, val: &mut Vec<u8>) -> bool {
+        match self.position {
+            Some(position) => {
+                let (k, v) = &self.entries[position];
+                key.clear();
+                key.extend_from_slice(k);
+                val.clear();
+                val.extend_from_slice(v);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        let position = match self
+            .entries
+            .binary_search_by(|(k, _)| k.as_slice().cmp(key))
+        {
+            Ok(position) | Err(position) => position,
+        };
+        self.position = Some(position).filter(|position| *position < self.entries.len());
+    }
+
+    fn reset(&mut self) {
+        self.position = None;
+    }
+
+    fn valid(&self) -> bool {
+        self.position.is_some()
+    }
+
+    fn prev(&mut self) -> bool {
+        self.position = self.position.and_then(|position| position.checked_sub(1));
+        self.position.is_some()
+    }
+}
+
+/// Opens the backend of the config. The sealed files of LevelDB are kept in
+/// its directory, and the remote backend stages its checkpoints in the fusion
+/// base.
+pub(crate) fn open(
+    config: &StorageBackendConfig,
+    fusion_base: &Path,
+) -> Result<Box<dyn StorageBackend>> {
+    let backend: Box<dyn StorageBackend> = match config.kind {
+        StorageBackendKind::Leveldb => match &config.dir {
+            Some(dir) => Box::new(open_sealed_leveldb(dir)?),
+            None => Box::new(DB::open(DB_NAME, rusty_leveldb::in_memory())?),
+        },
+        StorageBackendKind::Memory => Box::new(MemoryBackend::default()),
+        StorageBackendKind::Remote => Box::new(RemoteBackend::open(config, fusion_base)?),
+    };
+    Ok(backend)
+}
+
+fn open_sealed_leveldb(dir: &Path) -> Result<DB> {
+    fs::create_dir_all(dir)?;
+    let key_path = dir.join(KEY_FILE);
+    let key = if key_path.exists() {
+        let mut key = [0; 16];
+        ProtectedFile::open(&key_path)?.read_exact(&mut key)?;
+        key
+    } else {
+        let key = TeaclaveFile128Key::random().key;
+        let mut file = ProtectedFile::create(&key_path)?;
+        file.write_all(&key)?;
+        file.flush()?;
+        key
+    };
+    let db = DB::open(dir.join(DB_NAME), Options::new_disk_db_with(key))?;
+    info!("Opened the storage in {:?}", dir);
+    Ok(db)
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+
+    fn current_key_val(iter: &dyn LdbIterator) -> Option<(Vec<u8>, Vec<u8>)> {
+        let (mut key, mut value) = (vec![], vec![]);
+        if iter.current(&mut key, &mut value) {
+            Some((key, value))
+        } else {
+            None
+        }
+    }
+
+    pub fn test_memory_backend() {
+        let mut db = MemoryBackend::default();
+        db.put(b"b", b"2").unwrap();
+        db.write(&[
+            (b"a".to_vec(), Some(b"1".to_vec())),
+            (b"c".to_vec(), Some(b"3".to_vec())),
+            (b"b".to_vec(), None),
+        ])
+        .unwrap();
+        assert_eq!(db.get(b"a"), Some(b"1".to_vec()));
+        assert_eq!(db.get(b"b"), None);
+
+        // Iterators do not see the writes after they are created.
+        let mut iter = db.new_iter().unwrap();
+        db.put(b"d", b"4").unwrap();
+        assert!(!iter.valid());
+        assert_eq!(iter.next(), Some((b"a".to_vec(), b"1".to_vec())));
+        assert_eq!(iter.next(), Some((b"c".to_vec(), b"3".to_vec())));
+        assert_eq!(iter.next(), None);
+        iter.seek(b"b");
+        assert_eq!(
+            current_key_val(iter.as_ref()),
+            Some((b"c".to_vec(), b"3".to_vec()))
+        );
+        assert!(iter.prev());
+        assert_eq!(
+            current_key_val(iter.as_ref()),
+            Some((b"a".to_vec(), b"1".to_vec()))
+        );
+        assert!(!iter.prev());
+        iter.seek(b"d");
+        assert!(!iter.valid());
+
+        db.replace_all(&[(b"e".to_vec(), b"5".to_vec())]).unwrap();
+        assert_eq!(db.get(b"a"), None);
+        assert_eq!(db.get(b"d"), None);
+        assert_eq!(db.get(b"e"), Some(b"5".to_vec()));
+    }
+
+    pub fn test_sealed_leveldb_backend() {
+        let dir = Path::new("/tmp/storage_backend_test");
+        if dir.exists() {
+            fs::remove_dir_all(dir).unwrap();
+        }
+        let config = StorageBackendConfig {
+            dir: Some(dir.to_owned()),
+            ..Default::default()
+        };
+        let mut db = open(&config, dir).unwrap();
+        db.put(b"a", b"1").unwrap();
+        db.write(&[(b"b".to_vec(), Some(b"2".to_vec())), (b"a".to_vec(), None)])
+            .unwrap();
+        drop(db);
+
+        let mut db = open(&config, dir).unwrap();
+        assert_eq!(db.get(b"a"), None);
+        assert_eq!(db.get(b"b"), Some(b"2".to_vec()));
+        drop(db);
+
+        // The files are not opened with a key not sealed by the enclave.
+        fs::write(dir.join(KEY_FILE), [0; 16]).unwrap();
+        assert!(open(&config, dir).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -17,7 +17,7 @@
 
 use std::prelude::v1::*;
 
-use teaclave_types::TeaclaveServiceResponseError;
+use teaclave_types::{TeaclaveErrorCode, TeaclaveServiceResponseError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -28,10 +28,41 @@ pub(crate) enum TeaclaveStorageError {
     LevelDb(#[from] rusty_leveldb::Status),
     #[error("none error")]
     None,
+    #[error("value changed")]
+    Conflict,
+    #[error("read-only replica")]
+    ReadOnly,
+    #[error("invalid index name")]
+    InvalidIndex,
+    #[error("invalid ttl")]
+    InvalidTtl,
+    #[error("snapshot error")]
+    Snapshot,
+    #[error("invalid snapshot")]
+    InvalidSnapshot,
+    #[error("not a replica")]
+    NotReplica,
+}
+
+impl TeaclaveStorageError {
+    fn code(&self) -> TeaclaveErrorCode {
+        match self {
+            TeaclaveStorageError::Connection => TeaclaveErrorCode::Internal,
+            TeaclaveStorageError::LevelDb(_) => TeaclaveErrorCode::Internal,
+            TeaclaveStorageError::None => TeaclaveErrorCode::NotFound,
+            TeaclaveStorageError::Conflict => TeaclaveErrorCode::Conflict,
+            TeaclaveStorageError::ReadOnly => TeaclaveErrorCode::Validation,
+            TeaclaveStorageError::InvalidIndex => TeaclaveErrorCode::Validation,
+            TeaclaveStorageError::InvalidTtl => TeaclaveErrorCode::Validation,
+            TeaclaveStorageError::Snapshot => TeaclaveErrorCode::Internal,
+            TeaclaveStorageError::InvalidSnapshot => TeaclaveErrorCode::Validation,
+            TeaclaveStorageError::NotReplica => TeaclaveErrorCode::Validation,
+        }
+    }
 }
 
 impl From<TeaclaveStorageError> for TeaclaveServiceResponseError {
     fn from(error: TeaclaveStorageError) -> Self {
-        TeaclaveServiceResponseError::RequestError(error.to_string())
+        TeaclaveServiceResponseError::request_error(error.code(), error)
     }
 }
@@ -0,0 +1,138 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Purging of the soft-deleted records. A record is deleted softly by
+//! indexing it under the time it is deleted at in one of the
+//! `DELETED_RECORD_INDEXES`, e.g., a deleted input file, and the record is
+//! purged along with its index entries once the retention window passed.
+//! Keys of files are kept in the records, so the keys are purged as well.
+//! Records put with a TTL are purged the same way once they expire, see `ttl`.
+
+use crate::error::TeaclaveStorageError;
+use crate::index;
+use crate::replication::ReplicatedDB;
+use crate::ttl;
+use crate::version;
+use std::prelude::v1::*;
+use teaclave_types::DELETED_RECORD_INDEXES;
+
+// Records purged per index in a run, so that requests are not held up for
+// long by the single thread serving them.
+const PURGE_BATCH_SIZE: usize = 1000;
+
+/// Purges the records deleted at least `retention` seconds before `now`, and
+/// returns the number of the records purged.
+pub(crate) fn purge_deleted(
+    db: &mut ReplicatedDB,
+    now: u64,
+    retention: u64,
+) -> Result<usize, TeaclaveStorageError> {
+    // Terms are ordered before the bound if deleted at now - retention at
+    // the latest.
+    let bound = now.saturating_sub(retention).saturating_add(1);
+    let mut purged = 0;
+    for deleted_index in DELETED_RECORD_INDEXES {
+        let keys = index::keys_before(db, deleted_index, &bound.to_be_bytes(), PURGE_BATCH_SIZE)?;
+        for key in keys {
+            purge(db, &key)?;
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
+
+/// Purges the records expired at `now`, and returns the number of the records
+/// purged.
+pub(crate) fn purge_expired(
+    db: &mut ReplicatedDB,
+    now: u64,
+) -> Result<usize, TeaclaveStorageError> {
+    let keys = ttl::expired_keys(db, now, PURGE_BATCH_SIZE)?;
+    for key in &keys {
+        purge(db, key)?;
+    }
+    Ok(keys.len())
+}
+
+fn purge(db: &mut ReplicatedDB, key: &[u8]) -> Result<(), TeaclaveStorageError> {
+    index::remove_terms(db, key)?;
+    version::remove(db, key)?;
+    ttl::remove(db, key)?;
+    db.delete(key)?;
+    Ok(())
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use rusty_leveldb::DB;
+    use teaclave_proto::teaclave_storage_service::PutIndexedRequest;
+    use teaclave_types::{IndexTerm, INPUT_FILE_DELETED_INDEX, INPUT_FILE_OWNER_INDEX};
+
+    pub fn test_purge_deleted() {
+        let opt = rusty_leveldb::in_memory();
+        let mut db = ReplicatedDB::new(DB::open("mock_db", opt).unwrap());
+        let records = vec![
+            (
+                "input-1",
+                IndexTerm::deleted_at(INPUT_FILE_DELETED_INDEX, 100),
+            ),
+            (
+                "input-2",
+                IndexTerm::deleted_at(INPUT_FILE_DELETED_INDEX, 200),
+            ),
+            ("input-3", IndexTerm::new(INPUT_FILE_OWNER_INDEX, "alice")),
+        ];
+        for (key, term) in records {
+            let request = PutIndexedRequest::new(key, key, vec![term]);
+            index::put_indexed(&mut db, &request).unwrap();
+        }
+
+        // input-2 is still within the window
+        assert_eq!(purge_deleted(&mut db, 249, 50).unwrap(), 1);
+        assert_eq!(db.get(b"input-1"), None);
+        assert_eq!(db.get(b"input-2"), Some(b"input-2".to_vec()));
+        assert_eq!(db.get(b"input-3"), Some(b"input-3".to_vec()));
+
+        assert_eq!(purge_deleted(&mut db, 250, 50).unwrap(), 1);
+        assert_eq!(db.get(b"input-2"), None);
+        assert_eq!(purge_deleted(&mut db, 1000, 50).unwrap(), 0);
+        assert_eq!(db.get(b"input-3"), Some(b"input-3".to_vec()));
+    }
+
+    pub fn test_purge_expired() {
+        let opt = rusty_leveldb::in_memory();
+        let mut db = ReplicatedDB::new(DB::open("mock_db", opt).unwrap());
+        for (key, expires_at) in &[("token-1", 100), ("token-2", 200)] {
+            db.put(key.as_bytes(), b"token").unwrap();
+            version::bump(&mut db, key.as_bytes()).unwrap();
+            ttl::set(&mut db, key.as_bytes(), *expires_at).unwrap();
+        }
+        db.put(b"token-3", b"token").unwrap();
+
+        assert_eq!(purge_expired(&mut db, 99).unwrap(), 0);
+        assert_eq!(purge_expired(&mut db, 100).unwrap(), 1);
+        assert_eq!(db.get(b"token-1"), None);
+        assert_eq!(version::get(&mut db, b"token-1"), 0);
+        assert_eq!(ttl::get(&mut db, b"token-1"), None);
+        assert_eq!(db.get(b"token-2"), Some(b"token".to_vec()));
+
+        assert_eq!(purge_expired(&mut db, 1000).unwrap(), 1);
+        assert_eq!(db.get(b"token-2"), None);
+        assert_eq!(db.get(b"token-3"), Some(b"token".to_vec()));
+    }
+}
@@ -0,0 +1,276 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Secondary indexes of the stored records. An index maps a term, e.g., the
+//! id of a user, to the keys of the records indexed under it, which are kept
+//! in entries sorted by the term and the key:
+//!
+//!   index/<index>/<hex of term>/<key> -> empty
+//!
+//! The terms a key is indexed under are kept along with the entries, so that
+//! stale entries are removed when the key is indexed again or deleted:
+//!
+//!   index-terms/<key> -> a line of <index>/<hex of term> per term
+//!
+//! Entries are written through the replicated database like the records, so
+//! read replicas serve scans of the same indexes.
+
+use crate::error::TeaclaveStorageError;
+use crate::replication::ReplicatedDB;
+use rusty_leveldb::LdbIterator;
+use std::collections::BTreeSet;
+use std::prelude::v1::*;
+use teaclave_proto::teaclave_storage_service::{
+    IndexEntry, PutIndexedRequest, ScanIndexRequest, ScanIndexResponse,
+};
+
+const INDEX_PREFIX: &[u8] = b"index/";
+const INDEX_TERMS_PREFIX: &[u8] = b"index-terms/";
+const DEFAULT_SCAN_LIMIT: u32 = 100;
+const MAX_SCAN_LIMIT: u32 = 1000;
+
+type Result<T> = std::result::Result<T, TeaclaveStorageError>;
+
+fn term_path(index: &str, term: &[u8]) -> Vec<u8> {
+    format!("{}/{}", index, hex::encode(term)).into_bytes()
+}
+
+fn entry_prefix(path: &[u8]) -> Vec<u8> {
+    let mut prefix = INDEX_PREFIX.to_vec();
+    prefix.extend_from_slice(path);
+    prefix.push(b'/');
+    prefix
+}
+
+fn entry_key(path: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut entry_key = entry_prefix(path);
+    entry_key.extend_from_slice(key);
+    entry_key
+}
+
+fn terms_key(key: &[u8]) -> Vec<u8> {
+    let mut terms_key = INDEX_TERMS_PREFIX.to_vec();
+    terms_key.extend_from_slice(key);
+    terms_key
+}
+
+fn read_terms(db: &mut ReplicatedDB, key: &[u8]) -> BTreeSet<Vec<u8>> {
+    match db.get(&terms_key(key)) {
+        Some(paths) => paths
+            .split(|b| *b == b'\n')
+            .filter(|path| !path.is_empty())
+            .map(|path| path.to_vec())
+            .collect(),
+        None => BTreeSet::new(),
+    }
+}
+
+/// Puts the value of the key, and replaces the terms the key is indexed
+/// under. Only the entries of changed terms are written.
+pub(crate) fn put_indexed(db: &mut ReplicatedDB, request: &PutIndexedRequest) -> Result<()> {
+    let mut paths = BTreeSet::new();
+    for term in &request.terms {
+        if term.index.is_empty() || term.index.contains('/') {
+            return Err(TeaclaveStorageError::InvalidIndex);
+        }
+        paths.insert(term_path(&term.index, &term.term));
+    }
+
+    let key = &request.key;
+    let stale = read_terms(db, key);
+    for path in stale.difference(&paths) {
+        db.delete(&entry_key(path, key))?;
+    }
+    for path in paths.difference(&stale) {
+        db.put(&entry_key(path, key), b"")?;
+    }
+    if paths != stale {
+        if paths.is_empty() {
+            db.delete(&terms_key(key))?;
+        } else {
+            let paths: Vec<Vec<u8>> = paths.into_iter().collect();
+            db.put(&terms_key(key), &paths.join(&b'\n'))?;
+        }
+    }
+    db.put(key, &request.value)?;
+    Ok(())
+}
+
+/// Removes the key from the indexes it is indexed under, before the key is
+/// deleted.
+pub(crate) fn remove_terms(db: &mut ReplicatedDB, key: &[u8]) -> Result<()> {
+    let stale = read_terms(db, key);
+    if stale.is_empty() {
+        return Ok(());
+    }
+    for path in &stale {
+        db.delete(&entry_key(path, key))?;
+    }
+    db.delete(&terms_key(key))?;
+    Ok(())
+}
+
+/// Reads a page of the records indexed under the term, in the order of their
+/// keys.
+pub(crate) fn scan(db: &mut ReplicatedDB, request: &ScanIndexRequest) -> Result<ScanIndexResponse> {
+    let limit = match request.limit {
+        0 => DEFAULT_SCAN_LIMIT,
+        limit => std::cmp::min(limit, MAX_SCAN_LIMIT),
+    } as usize;
+    let path = term_path(&request.index, &request.term);
+    let prefix = entry_prefix(&path);
+    let start_after = request.start_after.as_deref();
+
+    let mut iter = db.new_iter()?;
+    iter.seek(&entry_key(&path, start_after.unwrap_or_default()));
+    let mut keys = Vec::new();
+    let mut more = false;
+    let (mut entry, mut value) = (Vec::new(), Vec::new());
+    while iter.current(&mut entry, &mut value) {
+        if !entry.starts_with(&prefix) {
+            break;
+        }
+        let key = &entry[prefix.len()..];
+        if Some(key) != start_after {
+            if keys.len() >= limit {
+                more = true;
+                break;
+            }
+            keys.push(key.to_vec());
+        }
+        if !iter.advance() {
+            break;
+        }
+    }
+
+    let next_start_after = if more { keys.last().cloned() } else { None };
+    let entries = keys
+        .into_iter()
+        .filter_map(|key| db.get(&key).map(|value| IndexEntry { key, value }))
+        .collect();
+    Ok(ScanIndexResponse {
+        entries,
+        next_start_after,
+    })
+}
+
+/// Reads up to `limit` keys indexed under terms of the index ordered before
+/// `bound`, in the order of the terms.
+pub(crate) fn keys_before(
+    db: &mut ReplicatedDB,
+    index: &str,
+    bound: &[u8],
+    limit: usize,
+) -> Result<Vec<Vec<u8>>> {
+    let prefix = entry_prefix(index.as_bytes());
+    let bound = entry_prefix(&term_path(index, bound));
+
+    let mut iter = db.new_iter()?;
+    iter.seek(&prefix);
+    let mut keys = Vec::new();
+    let (mut entry, mut value) = (Vec::new(), Vec::new());
+    while keys.len() < limit && iter.current(&mut entry, &mut value) {
+        if !entry.starts_with(&prefix) || entry >= bound {
+            break;
+        }
+        // Hex terms never contain a slash, the key follows the first one.
+        let path = &entry[prefix.len()..];
+        if let Some(at) = path.iter().position(|b| *b == b'/') {
+            keys.push(path[at + 1..].to_vec());
+        }
+        if !iter.advance() {
+            break;
+        }
+    }
+    Ok(keys)
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use rusty_leveldb::DB;
+    use teaclave_types::IndexTerm;
+
+    fn get_mock_database() -> ReplicatedDB {
+        let opt = rusty_leveldb::in_memory();
+        ReplicatedDB::new(DB::open("mock_db", opt).unwrap())
+    }
+
+    fn scan_keys(db: &mut ReplicatedDB, request: &ScanIndexRequest) -> Vec<Vec<u8>> {
+        let response = scan(db, request).unwrap();
+        response
+            .entries
+            .into_iter()
+            .map(|entry| entry.key)
+            .collect()
+    }
+
+    pub fn test_reindex_and_remove_terms() {
+        let mut db = get_mock_database();
+        let terms = vec![
+            IndexTerm::new("task-participant", "alice"),
+            IndexTerm::new("task-participant", "bob"),
+        ];
+        let request = PutIndexedRequest::new("task-1", "1", terms);
+        put_indexed(&mut db, &request).unwrap();
+        let request = PutIndexedRequest::new("task-2", "2", vec![]);
+        put_indexed(&mut db, &request).unwrap();
+
+        let alice = ScanIndexRequest::new("task-participant", "alice");
+        let bob = ScanIndexRequest::new("task-participant", "bob");
+        assert_eq!(scan_keys(&mut db, &alice), vec![b"task-1".to_vec()]);
+        assert_eq!(scan_keys(&mut db, &bob), vec![b"task-1".to_vec()]);
+
+        let terms = vec![IndexTerm::new("task-participant", "alice")];
+        let request = PutIndexedRequest::new("task-1", "1", terms);
+        put_indexed(&mut db, &request).unwrap();
+        assert!(scan_keys(&mut db, &bob).is_empty());
+
+        remove_terms(&mut db, b"task-1").unwrap();
+        assert!(scan_keys(&mut db, &alice).is_empty());
+        assert_eq!(db.get(&terms_key(b"task-1")), None);
+
+        let terms = vec![IndexTerm::new("task/participant", "alice")];
+        let request = PutIndexedRequest::new("task-1", "1", terms);
+        assert!(put_indexed(&mut db, &request).is_err());
+    }
+
+    pub fn test_scan_index_pages() {
+        let mut db = get_mock_database();
+        for key in &["function-1", "function-2", "function-3"] {
+            let terms = vec![IndexTerm::new("function-owner", "alice")];
+            let request = PutIndexedRequest::new(*key, *key, terms);
+            put_indexed(&mut db, &request).unwrap();
+        }
+        // A term which the term scanned is a prefix of.
+        let terms = vec![IndexTerm::new("function-owner", "alice2")];
+        let request = PutIndexedRequest::new("function-4", "4", terms);
+        put_indexed(&mut db, &request).unwrap();
+
+        let request = ScanIndexRequest::new("function-owner", "alice").limit(2);
+        let response = scan(&mut db, &request).unwrap();
+        assert_eq!(response.entries.len(), 2);
+        assert_eq!(response.entries[0].value, b"function-1");
+        assert_eq!(response.next_start_after, Some(b"function-2".to_vec()));
+
+        let request = request.start_after(response.next_start_after.unwrap());
+        let response = scan(&mut db, &request).unwrap();
+        assert_eq!(response.entries.len(), 1);
+        assert_eq!(response.entries[0].key, b"function-3");
+        assert_eq!(response.next_start_after, None);
+    }
+}
@@ -22,14 +22,13 @@ extern crate sgx_tstd as std;
 #[macro_use]
 extern crate log;
 
-use std::cell::RefCell;
 use std::format;
 use std::prelude::v1::*;
 use std::sync::mpsc::channel;
 use std::thread;
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
-use rusty_leveldb::DB;
 
 use teaclave_attestation::{verifier, AttestationConfig, RemoteAttestation};
 use teaclave_binder::proto::{
@@ -38,16 +37,26 @@ use teaclave_binder::proto::{
 };
 use teaclave_binder::{handle_ecall, register_ecall_handler};
 use teaclave_config::build::{AS_ROOT_CA_CERT, AUDITOR_PUBLIC_KEYS, STORAGE_INBOUND_SERVICES};
-use teaclave_config::RuntimeConfig;
+use teaclave_config::{RuntimeConfig, StorageBackendKind};
 use teaclave_proto::teaclave_storage_service::{TeaclaveStorageRequest, TeaclaveStorageResponse};
 use teaclave_rpc::config::SgxTrustedTlsServerConfig;
 use teaclave_rpc::server::SgxTrustedTlsServer;
-use teaclave_service_enclave_utils::ServiceEnclave;
-use teaclave_types::{EnclaveInfo, TeeServiceError, TeeServiceResult};
+use teaclave_service_enclave_utils::{create_trusted_storage_endpoint, ServiceEnclave};
+use teaclave_types::{EnclaveInfo, TeeServiceError, TeeServiceResult, TrustedTime};
 
+mod backend;
 mod error;
+mod gc;
+mod index;
+mod ocall;
 mod proxy;
+mod remote;
+mod replication;
 mod service;
+mod snapshot;
+mod ttl;
+mod version;
+mod wal;
 
 fn start_service(config: &RuntimeConfig) -> Result<()> {
     let listen_address = config.internal_endpoints.storage.listen_address;
@@ -68,22 +77,92 @@ fn start_service(config: &RuntimeConfig) -> Result<()> {
             None => Err(anyhow!("cannot get enclave attribute of {}", service)),
         })
         .collect::<Result<_>>()?;
-    let server_config = SgxTrustedTlsServerConfig::from_attested_tls_config(attested_tls_config)?
-        .attestation_report_verifier(
-        accepted_enclave_attrs,
-        AS_ROOT_CA_CERT,
-        verifier::universal_quote_verifier,
-    )?;
+    let server_config =
+        SgxTrustedTlsServerConfig::from_attested_tls_config(attested_tls_config.clone())?
+            .attestation_report_verifier(
+                accepted_enclave_attrs,
+                AS_ROOT_CA_CERT,
+                verifier::universal_quote_verifier,
+            )?;
 
     let (sender, receiver) = channel();
+    let read_only = config.storage_replica_primary.is_some();
+    let fusion_base = config.mount.fusion_base_dir.clone();
+    let wal_config = config.storage_wal.clone();
+    let wal_dir = wal_config.as_ref().map(|wal| wal.dir.clone());
+    let backend_config = config.storage_backend.clone();
     thread::spawn(move || {
-        let opt = rusty_leveldb::in_memory();
-        let storage = DB::open("teaclave_db", opt).expect("cannot open teaclave_db");
+        let mut storage =
+            backend::open(&backend_config, &fusion_base).expect("cannot open teaclave_db");
+        // Replicas copy the primary instead of recovering from a log, and
+        // start their own log when they are promoted.
+        let wal = wal_dir.clone().filter(|_| !read_only).map(|dir| {
+            wal::WriteAheadLog::recover(dir, storage.as_mut()).expect("cannot recover teaclave_db")
+        });
         let mut storage_service =
-            service::TeaclaveStorageService::new(RefCell::new(storage), receiver);
+            service::TeaclaveStorageService::new(storage, receiver, read_only)
+                .fusion_base(fusion_base);
+        match (wal, wal_dir) {
+            (Some(wal), _) => storage_service = storage_service.wal(wal),
+            (None, Some(dir)) => storage_service = storage_service.promotion_wal(dir),
+            (None, None) => (),
+        }
         storage_service.start();
     });
 
+    // Replicas skip the purges until they are promoted.
+    {
+        let sender = sender.clone();
+        let storage_gc = config.storage_gc.clone();
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(storage_gc.interval));
+            let request = proxy::PurgeRequest {
+                now: TrustedTime::now_secs(),
+                retention: storage_gc.retention,
+            };
+            if sender.send(proxy::ProxyMessage::Purge(request)).is_err() {
+                break;
+            }
+        });
+    }
+
+    if let Some(wal) = wal_config {
+        let sender = sender.clone();
+        let compaction = wal.compaction;
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(compaction.interval));
+            if sender
+                .send(proxy::ProxyMessage::Compact(compaction.clone()))
+                .is_err()
+            {
+                break;
+            }
+        });
+    }
+
+    if config.storage_backend.kind == StorageBackendKind::Remote {
+        let sender = sender.clone();
+        let sync_interval = config.storage_backend.sync_interval;
+        thread::spawn(move || loop {
+            thread::sleep(Duration::from_secs(sync_interval));
+            if sender.send(proxy::ProxyMessage::Sync).is_err() {
+                break;
+            }
+        });
+    }
+
+    if let Some(primary_address) = &config.storage_replica_primary {
+        let primary_endpoint = create_trusted_storage_endpoint(
+            primary_address,
+            &enclave_info,
+            AS_ROOT_CA_CERT,
+            verifier::universal_quote_verifier,
+            attested_tls_config,
+        )?;
+        let sender = sender.clone();
+        thread::spawn(move || replication::follow_primary(primary_endpoint, sender));
+    }
+
     let mut server = SgxTrustedTlsServer::<TeaclaveStorageResponse, TeaclaveStorageRequest>::new(
         listen_address,
         server_config,
@@ -137,11 +216,35 @@ pub mod tests {
 
     pub fn run_tests() -> bool {
         run_tests!(
+            backend::tests::test_memory_backend,
+            backend::tests::test_sealed_leveldb_backend,
             service::tests::test_get_key,
             service::tests::test_put_key,
+            service::tests::test_compare_and_swap,
+            service::tests::test_put_if_version,
+            service::tests::test_put_with_ttl,
             service::tests::test_delete_key,
             service::tests::test_enqueue,
             service::tests::test_dequeue,
+            service::tests::test_read_only_replica,
+            service::tests::test_promote_replica,
+            service::tests::test_read_replication_log_of_writes,
+            service::tests::test_delete_indexed_key,
+            service::tests::test_commit_transaction,
+            gc::tests::test_purge_deleted,
+            gc::tests::test_purge_expired,
+            index::tests::test_reindex_and_remove_terms,
+            index::tests::test_scan_index_pages,
+            replication::tests::test_read_replication_log,
+            replication::tests::test_replicate_snapshot,
+            replication::tests::test_commit_and_rollback,
+            snapshot::tests::test_export_and_restore,
+            version::tests::test_bump_and_remove,
+            ttl::tests::test_set_and_remove_expiry,
+            wal::tests::test_recover_writes,
+            wal::tests::test_recover_torn_record,
+            wal::tests::test_recover_random_crashes,
+            wal::tests::test_compact_log,
         )
     }
 }
@@ -18,17 +18,20 @@
 use crate::error::TeaclaveStorageError;
 use std::prelude::v1::*;
 use std::sync::mpsc::{channel, Sender};
-use teaclave_proto::teaclave_storage_service::{TeaclaveStorageRequest, TeaclaveStorageResponse};
+use teaclave_config::StorageCompactionConfig;
+use teaclave_proto::teaclave_storage_service::{
+    ReplicationLogEntry, TeaclaveStorageRequest, TeaclaveStorageResponse,
+};
 use teaclave_rpc::Request;
 use teaclave_types::TeaclaveServiceResponseResult;
 
 #[derive(Clone)]
 pub(crate) struct ProxyService {
-    sender: Sender<ProxyRequest>,
+    sender: Sender<ProxyMessage>,
 }
 
 impl ProxyService {
-    pub(crate) fn new(sender: Sender<ProxyRequest>) -> Self {
+    pub(crate) fn new(sender: Sender<ProxyMessage>) -> Self {
         Self { sender }
     }
 }
@@ -42,7 +45,7 @@ impl teaclave_rpc::TeaclaveService<TeaclaveStorageRequest, TeaclaveStorageRespon
     ) -> TeaclaveServiceResponseResult<TeaclaveStorageResponse> {
         let (sender, receiver) = channel();
         self.sender
-            .send(ProxyRequest { sender, request })
+            .send(ProxyMessage::Request(ProxyRequest { sender, request }))
             .map_err(|_| TeaclaveStorageError::Connection)?;
         receiver
             .recv()
@@ -55,3 +58,30 @@ pub(crate) struct ProxyRequest {
     pub sender: Sender<TeaclaveServiceResponseResult<TeaclaveStorageResponse>>,
     pub request: Request<TeaclaveStorageRequest>,
 }
+
+/// Writes of the primary storage read by a replica, which are applied in turn
+/// with the requests of clients. The replica is cleared first if reset is set.
+pub(crate) struct ReplicateRequest {
+    pub sender: Sender<Result<(), TeaclaveStorageError>>,
+    pub entries: Vec<ReplicationLogEntry>,
+    pub reset: bool,
+}
+
+/// Purge of the soft-deleted records, deleted at least `retention` seconds
+/// before `now`, and of the records expired at `now`, which is run in turn
+/// with the requests of clients.
+pub(crate) struct PurgeRequest {
+    pub now: u64,
+    pub retention: u64,
+}
+
+/// Compaction of the write-ahead log, if its dead bytes pass the thresholds
+/// of the config, and syncs of the backend, e.g., checkpoints of the remote
+/// one, are run in turn with the requests of clients as well.
+pub(crate) enum ProxyMessage {
+    Request(ProxyRequest),
+    Replicate(ReplicateRequest),
+    Purge(PurgeRequest),
+    Compact(StorageCompactionConfig),
+    Sync,
+}
@@ -0,0 +1,253 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Remote backend of the storage, which keeps the keys in memory and
+//! checkpoints them to an object storage through the file agent, so that
+//! they are kept off the node. A checkpoint is a snapshot of all keys sealed
+//! with a random key, see `snapshot`:
+//!
+//!   <url>/checkpoint-<generation> -> sealed snapshot of all keys
+//!   <url>/manifest                -> generation, key and cmac of the latest
+//!
+//! Keys written since the last checkpoint are lost on restart unless the
+//! write-ahead log is on as well. The manifest is sealed with the protected
+//! file system of SGX, so that only the enclave on the same platform opens it,
+//! and a copy of it is kept in the directory of the backend, or in the fusion
+//! base if not set. The remote manifest is only
+//! read when the copy is lost, and a storage failing to download it starts
+//! empty. Nothing prevents the host from rolling the manifest back to an older
+//! checkpoint, and older checkpoints are left to the object storage to expire.
+
+use crate::backend::{StorageBackend, DB_NAME};
+use crate::ocall::handle_file_request;
+use crate::snapshot;
+use anyhow::{anyhow, ensure, Result};
+use protected_fs::ProtectedFile;
+use rusty_leveldb::{LdbIterator, Status, StatusCode, DB};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::prelude::v1::*;
+use std::untrusted::fs;
+use std::untrusted::path::PathEx;
+use teaclave_config::StorageBackendConfig;
+use teaclave_crypto::TeaclaveFile128Key;
+use teaclave_types::{
+    FileAgentRequest, FileAuthTag, FileCredential, HandleFileCommand, HandleFileInfo,
+    StagedFileInfo,
+};
+use url::Url;
+
+// Checkpoints and the manifest are staged in this directory of the fusion
+// base on their way to and from the object storage.
+const REMOTE_STAGING_DIR: &str = "storage-remote";
+const MANIFEST: &str = "manifest";
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    generation: u64,
+    crypto_info: TeaclaveFile128Key,
+    cmac: FileAuthTag,
+}
+
+pub(crate) struct RemoteBackend {
+    db: DB,
+    base_url: Url,
+    credential: Option<FileCredential>,
+    fusion_base: PathBuf,
+    // Directory of the manifest and of the checkpoints being transferred.
+    staging_dir: PathBuf,
+    generation: u64,
+    // Whether there are writes since the last checkpoint.
+    dirty: bool,
+}
+
+impl RemoteBackend {
+    /// Opens the backend with the keys of the latest checkpoint, if any.
+    pub(crate) fn open(config: &StorageBackendConfig, fusion_base: &Path) -> Result<Self> {
+        let url = config
+            .url
+            .as_ref()
+            .ok_or_else(|| anyhow!("URL of the remote storage backend is not set"))?;
+        let base_url = Url::parse(url)?;
+        ensure!(
+            base_url.path().ends_with('/'),
+            "URL of the remote storage backend does not end with /"
+        );
+        let credential = match &config.credential {
+            Some(c) => Some(FileCredential::new(
+                &c.schema,
+                &c.access_key_id,
+                &c.secret,
+                &c.region,
+                &c.endpoint,
+            )?),
+            None => None,
+        };
+        if let Some(credential) = &credential {
+            credential.check_url(&base_url)?;
+        }
+
+        let mut backend = Self {
+            db: DB::open(DB_NAME, rusty_leveldb::in_memory())?,
+            base_url,
+            credential,
+            fusion_base: fusion_base.to_owned(),
+            staging_dir: match &config.dir {
+                Some(dir) => dir.to_owned(),
+                None => fusion_base.join(REMOTE_STAGING_DIR),
+            },
+            generation: 0,
+            dirty: false,
+        };
+        match backend.read_manifest()? {
+            Some(manifest) => {
+                let entries = backend.download_checkpoint(&manifest)?;
+                for (key, value) in &entries {
+                    backend.db.put(key, value)?;
+                }
+                backend.generation = manifest.generation;
+                info!(
+                    "Recovered {} keys from checkpoint {} of {}",
+                    entries.len(),
+                    manifest.generation,
+                    backend.base_url
+                );
+            }
+            None => info!("Found no checkpoint of {}", backend.base_url),
+        }
+        Ok(backend)
+    }
+
+    // The checkpoint is uploaded before the manifest pointing to it, so that
+    // a failure in between leaves the older checkpoint in use.
+    fn checkpoint(&mut self) -> Result<()> {
+        let generation = self.generation + 1;
+        let object = format!("checkpoint-{}", generation);
+        let (bytes, entries) = snapshot::encode(&mut self.db.new_iter()?);
+        let path = self.staging_path(&object)?;
+        let file = snapshot::seal(&path, &bytes)?;
+        let result = self.transfer(HandleFileCommand::Upload, &path, &object);
+        fs::remove_file(&path)?;
+        result?;
+
+        let manifest = Manifest {
+            generation,
+            crypto_info: file.crypto_info,
+            cmac: file.cmac,
+        };
+        let path = self.staging_path(MANIFEST)?;
+        let mut file = ProtectedFile::create(&path)?;
+        file.write_all(&serde_json::to_vec(&manifest)?)?;
+        file.flush()?;
+        drop(file);
+        self.generation = generation;
+        self.transfer(HandleFileCommand::Upload, &path, MANIFEST)?;
+        info!(
+            "Checkpointed {} keys to checkpoint {} of {}",
+            entries, generation, self.base_url
+        );
+        Ok(())
+    }
+
+    fn read_manifest(&self) -> Result<Option<Manifest>> {
+        let path = self.staging_dir.join(MANIFEST);
+        if !path.exists() {
+            fs::create_dir_all(&self.staging_dir)?;
+            if let Err(e) = self.transfer(HandleFileCommand::Download, &path, MANIFEST) {
+                warn!("Cannot download the manifest of the checkpoints: {:?}", e);
+                if path.exists() {
+                    fs::remove_file(&path)?;
+                }
+                return Ok(None);
+            }
+        }
+        let mut bytes = Vec::new();
+        ProtectedFile::open(&path)?.read_to_end(&mut bytes)?;
+        Ok(Some(serde_json::from_slice(&bytes)?))
+    }
+
+    fn download_checkpoint(&self, manifest: &Manifest) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let object = format!("checkpoint-{}", manifest.generation);
+        let path = self.staging_path(&object)?;
+        let result = self
+            .transfer(HandleFileCommand::Download, &path, &object)
+            .and_then(|_| {
+                let file = StagedFileInfo::new(&path, manifest.crypto_info, manifest.cmac);
+                snapshot::unseal(&file)
+            });
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        result
+    }
+
+    fn transfer(&self, command: HandleFileCommand, path: &Path, object: &str) -> Result<()> {
+        let url = self.base_url.join(object)?;
+        let info = HandleFileInfo::new(path, &url).credential(self.credential.clone());
+        handle_file_request(FileAgentRequest::new(
+            command,
+            vec![info],
+            &self.fusion_base,
+        ))
+    }
+
+    // A file left by an interrupted transfer is removed.
+    fn staging_path(&self, name: &str) -> Result<PathBuf> {
+        fs::create_dir_all(&self.staging_dir)?;
+        let path = self.staging_dir.join(name);
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        Ok(path)
+    }
+}
+
+impl StorageBackend for RemoteBackend {
+    fn get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        self.db.get(key)
+    }
+
+    fn put(&mut self, key: &[u8], value: &[u8]) -> std::result::Result<(), Status> {
+        self.dirty = true;
+        self.db.put(key, value)
+    }
+
+    fn delete(&mut self, key: &[u8]) -> std::result::Result<(), Status> {
+        self.dirty = true;
+        self.db.delete(key)
+    }
+
+    fn write(&mut self, writes: &[(Vec<u8>, Option<Vec<u8>>)]) -> std::result::Result<(), Status> {
+        self.dirty = true;
+        StorageBackend::write(&mut self.db, writes)
+    }
+
+    fn new_iter(&mut self) -> std::result::Result<Box<dyn LdbIterator>, Status> {
+        StorageBackend::new_iter(&mut self.db)
+    }
+
+    fn sync(&mut self) -> std::result::Result<(), Status> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.checkpoint()
+            .map_err(|e| Status::new(StatusCode::IOError, &format!("{:?}", e)))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,522 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Replication log of the primary storage, which read replicas follow to
+//! apply the same writes. The log keeps the latest writes in memory only; a
+//! replica falling behind the log, e.g., a new one or one of a restarted
+//! primary, copies a snapshot of all keys first.
+//!
+//! Writes of a transaction are buffered until they are committed in one
+//! batch, which the backend writes atomically, and then recorded in the log in
+//! order. A replica applies them as they are read from the log, so it may
+//! briefly serve a part of a transaction.
+//!
+//! The writes of a primary with a write-ahead log are appended to it before
+//! they are applied, see `wal`. Replicas copy the primary instead.
+//!
+//! A replica stands by for its primary: once promoted, it stops following the
+//! primary and serves writes on top of the keys it has copied, which lack the
+//! writes of the primary it has not read yet.
+
+use crate::backend::StorageBackend;
+use crate::error::TeaclaveStorageError;
+use crate::proxy::{ProxyMessage, ReplicateRequest};
+use crate::wal::WriteAheadLog;
+use anyhow::{anyhow, Result};
+use rusty_leveldb::{LdbIterator, Status};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::prelude::v1::*;
+use std::sync::mpsc::{channel, Sender};
+use std::time::Duration;
+use teaclave_config::StorageCompactionConfig;
+use teaclave_proto::teaclave_storage_service::{
+    GetWalStatsResponse, ReadReplicationLogRequest, ReadReplicationLogResponse,
+    ReplicationLogEntry, TeaclaveStorageClient,
+};
+use teaclave_rpc::endpoint::Endpoint;
+
+// Total size of the keys and values of the writes kept in the log.
+const MAX_LOG_SIZE: usize = 64 * 1024 * 1024;
+// Size of the entries of a response, which has at least one entry anyway, so
+// that the response stays below the max message size of RPC.
+const MAX_READ_SIZE: usize = 128 * 1024;
+// Interval of polling the log once a replica has caught up with the primary.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(3);
+
+pub(crate) struct ReplicatedDB {
+    db: Box<dyn StorageBackend>,
+    log: VecDeque<ReplicationLogEntry>,
+    log_size: usize,
+    next_sequence: u64,
+    // Writes of the open transaction, which are read before the database.
+    transaction: Option<Vec<(Vec<u8>, Option<Vec<u8>>)>>,
+    wal: Option<WriteAheadLog>,
+}
+
+impl ReplicatedDB {
+    pub(crate) fn new(db: impl StorageBackend + 'static) -> Self {
+        Self {
+            db: Box::new(db),
+            log: VecDeque::new(),
+            log_size: 0,
+            next_sequence: 0,
+            transaction: None,
+            wal: None,
+        }
+    }
+
+    /// Appends the writes to the log from now on, which the database is
+    /// recovered from.
+    pub(crate) fn wal(self, wal: WriteAheadLog) -> Self {
+        Self {
+            wal: Some(wal),
+            ..self
+        }
+    }
+
+    /// Starts a write-ahead log with a checkpoint of the keys copied by a
+    /// promoted replica, which its writes are appended to from now on.
+    pub(crate) fn start_wal(&mut self, dir: &Path) -> std::result::Result<(), Status> {
+        self.wal = Some(WriteAheadLog::start(dir, self.db.as_mut())?);
+        Ok(())
+    }
+
+    pub(crate) fn get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        let buffered = self
+            .transaction
+            .as_ref()
+            .and_then(|writes| writes.iter().rev().find(|(k, _)| k.as_slice() == key));
+        match buffered {
+            Some((_, value)) => value.clone(),
+            None => self.db.get(key),
+        }
+    }
+
+    pub(crate) fn put(&mut self, key: &[u8], value: &[u8]) -> std::result::Result<(), Status> {
+        if let Some(writes) = self.transaction.as_mut() {
+            writes.push((key.to_vec(), Some(value.to_vec())));
+            return Ok(());
+        }
+        if let Some(wal) = self.wal.as_mut() {
+            wal.append(self.db.as_mut(), vec![(key, Some(value))])?;
+        }
+        self.db.put(key, value)?;
+        self.record(key, Some(value));
+        Ok(())
+    }
+
+    pub(crate) fn delete(&mut self, key: &[u8]) -> std::result::Result<(), Status> {
+        if let Some(writes) = self.transaction.as_mut() {
+            writes.push((key.to_vec(), None));
+            return Ok(());
+        }
+        if let Some(wal) = self.wal.as_mut() {
+            wal.append(self.db.as_mut(), vec![(key, None)])?;
+        }
+        self.db.delete(key)?;
+        self.record(key, None);
+        Ok(())
+    }
+
+    /// Buffers the writes from now on until the transaction is committed or
+    /// rolled back. Iterators do not see the buffered writes.
+    pub(crate) fn begin(&mut self) {
+        self.transaction = Some(Vec::new());
+    }
+
+    /// Writes the buffered writes in one batch, so that either all of them
+    /// are written or none is, even if the enclave crashes in between.
+    pub(crate) fn commit(&mut self) -> std::result::Result<(), Status> {
+        let writes = match self.transaction.take() {
+            Some(writes) => writes,
+            None => return Ok(()),
+        };
+        if let Some(wal) = self.wal.as_mut() {
+            wal.append(
+                self.db.as_mut(),
+                writes.iter().map(|(k, v)| (k.as_slice(), v.as_deref())),
+            )?;
+        }
+        self.db.write(&writes)?;
+        for (key, value) in &writes {
+            self.record(key, value.as_deref());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn rollback(&mut self) {
+        self.transaction = None;
+    }
+
+    /// Compacts the write-ahead log if its dead bytes pass the thresholds,
+    /// and returns whether it did.
+    pub(crate) fn compact_wal(
+        &mut self,
+        config: &StorageCompactionConfig,
+    ) -> std::result::Result<bool, Status> {
+        match self.wal.as_mut() {
+            Some(wal) if wal.needs_compaction(config) => {
+                wal.compact(self.db.as_mut())?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    pub(crate) fn wal_stats(&self) -> GetWalStatsResponse {
+        self.wal
+            .as_ref()
+            .map(WriteAheadLog::stats)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn new_iter(&mut self) -> std::result::Result<Box<dyn LdbIterator>, Status> {
+        self.db.new_iter()
+    }
+
+    /// Persists the writes so far, for a backend not persisting each of them.
+    pub(crate) fn sync(&mut self) -> std::result::Result<(), Status> {
+        self.db.sync()
+    }
+
+    fn record(&mut self, key: &[u8], value: Option<&[u8]>) {
+        let entry = ReplicationLogEntry {
+            sequence: self.next_sequence,
+            key: key.to_vec(),
+            value: value.map(|v| v.to_vec()),
+        };
+        self.log_size += entry_size(&entry);
+        self.log.push_back(entry);
+        self.next_sequence += 1;
+        while self.log_size > MAX_LOG_SIZE {
+            match self.log.pop_front() {
+                Some(entry) => self.log_size -= entry_size(&entry),
+                None => break,
+            }
+        }
+    }
+
+    fn first_sequence(&self) -> u64 {
+        self.next_sequence - self.log.len() as u64
+    }
+
+    pub(crate) fn read_log(
+        &mut self,
+        request: &ReadReplicationLogRequest,
+    ) -> std::result::Result<ReadReplicationLogResponse, Status> {
+        if let Some(cursor) = &request.snapshot_cursor {
+            return self.read_snapshot(request.from_sequence, cursor);
+        }
+
+        // A sequence beyond the log is of the log before the primary
+        // restarted.
+        let from = request.from_sequence;
+        if from < self.first_sequence() || from > self.next_sequence {
+            return Ok(ReadReplicationLogResponse {
+                next_sequence: self.next_sequence,
+                snapshot_required: true,
+                ..Default::default()
+            });
+        }
+
+        let mut entries = Vec::new();
+        let mut size = 0;
+        for entry in self
+            .log
+            .iter()
+            .skip((from - self.first_sequence()) as usize)
+        {
+            if size >= MAX_READ_SIZE {
+                break;
+            }
+            size += entry_size(entry);
+            entries.push(entry.clone());
+        }
+        Ok(ReadReplicationLogResponse {
+            next_sequence: from + entries.len() as u64,
+            entries,
+            ..Default::default()
+        })
+    }
+
+    // Pages of the snapshot are read while writes go on. Those writes are in
+    // the log after the sequence the snapshot started at, which the replica
+    // applies after the snapshot, so that it ends up with the same values.
+    fn read_snapshot(
+        &mut self,
+        sequence: u64,
+        cursor: &[u8],
+    ) -> std::result::Result<ReadReplicationLogResponse, Status> {
+        let mut iter = self.db.new_iter()?;
+        let sequence = if cursor.is_empty() {
+            iter.seek_to_first();
+            self.next_sequence
+        } else {
+            iter.seek(cursor);
+            sequence
+        };
+
+        let mut entries = Vec::new();
+        let mut size = 0;
+        let (mut key, mut value) = (Vec::new(), Vec::new());
+        while iter.current(&mut key, &mut value) {
+            if key.as_slice() != cursor {
+                if size >= MAX_READ_SIZE {
+                    break;
+                }
+                let entry = ReplicationLogEntry {
+                    sequence,
+                    key: key.clone(),
+                    value: Some(value.clone()),
+                };
+                size += entry_size(&entry);
+                entries.push(entry);
+            }
+            if !iter.advance() {
+                break;
+            }
+        }
+        let snapshot_cursor = if iter.valid() {
+            entries.last().map(|entry| entry.key.clone())
+        } else {
+            None
+        };
+
+        Ok(ReadReplicationLogResponse {
+            entries,
+            next_sequence: sequence,
+            snapshot_required: false,
+            snapshot_cursor,
+        })
+    }
+
+    /// Applies the writes of the primary to a replica, which are not recorded
+    /// in its own log.
+    pub(crate) fn apply(
+        &mut self,
+        entries: &[ReplicationLogEntry],
+    ) -> std::result::Result<(), Status> {
+        for entry in entries {
+            match &entry.value {
+                Some(value) => self.db.put(&entry.key, value)?,
+                None => self.db.delete(&entry.key)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Clears a replica before copying a snapshot of the primary.
+    pub(crate) fn reset(&mut self) -> std::result::Result<(), Status> {
+        self.db.replace_all(&[])?;
+        self.log.clear();
+        self.log_size = 0;
+        Ok(())
+    }
+
+    /// Replaces all keys with those of a snapshot. The log is cleared and its
+    /// sequence skipped ahead, so that the replicas copy a snapshot of the
+    /// restored keys instead of following the log.
+    pub(crate) fn restore(
+        &mut self,
+        entries: &[(Vec<u8>, Vec<u8>)],
+    ) -> std::result::Result<(), Status> {
+        if let Some(wal) = self.wal.as_mut() {
+            wal.checkpoint(entries)?;
+        }
+        self.db.replace_all(entries)?;
+        self.log.clear();
+        self.log_size = 0;
+        self.next_sequence += 1;
+        Ok(())
+    }
+}
+
+fn entry_size(entry: &ReplicationLogEntry) -> usize {
+    entry.key.len() + entry.value.as_ref().map_or(0, |v| v.len())
+}
+
+/// Keeps a replica up to date with the primary storage at the endpoint, until
+/// the replica is promoted. The replica copies a snapshot of the primary
+/// whenever it (re)connects, since a lost connection may be of a restarted
+/// primary.
+pub(crate) fn follow_primary(endpoint: Endpoint, sender: Sender<ProxyMessage>) {
+    loop {
+        let result = endpoint
+            .connect()
+            .and_then(TeaclaveStorageClient::new)
+            .and_then(|client| follow(client, &sender));
+        if let Err(e) = result {
+            if let Some(TeaclaveStorageError::NotReplica) = e.downcast_ref() {
+                info!("Stopped following the primary storage on promotion");
+                return;
+            }
+            warn!("Failed to follow the primary storage: {:?}", e);
+        }
+        std::thread::sleep(RECONNECT_INTERVAL);
+    }
+}
+
+fn follow(mut primary: TeaclaveStorageClient, sender: &Sender<ProxyMessage>) -> Result<()> {
+    let mut next_request = None;
+    loop {
+        let request = match next_request.take() {
+            Some(request) => request,
+            None => {
+                replicate(sender, Vec::new(), true)?;
+                ReadReplicationLogRequest::snapshot(0, Vec::new())
+            }
+        };
+        let snapshot = request.snapshot_cursor.is_some();
+        let response = primary.read_replication_log(request)?;
+        if response.snapshot_required {
+            continue;
+        }
+
+        let caught_up = !snapshot && response.entries.is_empty();
+        replicate(sender, response.entries, false)?;
+        next_request = match response.snapshot_cursor {
+            Some(cursor) if snapshot => Some(ReadReplicationLogRequest::snapshot(
+                response.next_sequence,
+                cursor,
+            )),
+            _ => Some(ReadReplicationLogRequest::new(response.next_sequence)),
+        };
+        if caught_up {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+fn replicate(
+    sender: &Sender<ProxyMessage>,
+    entries: Vec<ReplicationLogEntry>,
+    reset: bool,
+) -> Result<()> {
+    let (result_sender, receiver) = channel();
+    let request = ReplicateRequest {
+        sender: result_sender,
+        entries,
+        reset,
+    };
+    sender
+        .send(ProxyMessage::Replicate(request))
+        .map_err(|_| anyhow!("storage service stopped"))?;
+    receiver
+        .recv()
+        .map_err(|_| anyhow!("storage service stopped"))??;
+    Ok(())
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use rusty_leveldb::DB;
+
+    fn get_mock_database() -> ReplicatedDB {
+        let opt = rusty_leveldb::in_memory();
+        ReplicatedDB::new(DB::open("mock_db", opt).unwrap())
+    }
+
+    pub fn test_read_replication_log() {
+        let mut primary = get_mock_database();
+        primary.put(b"a", b"1").unwrap();
+        primary.put(b"b", b"2").unwrap();
+        primary.delete(b"a").unwrap();
+
+        let response = primary
+            .read_log(&ReadReplicationLogRequest::new(1))
+            .unwrap();
+        assert!(!response.snapshot_required);
+        assert_eq!(response.next_sequence, 3);
+        assert_eq!(response.entries.len(), 2);
+        assert_eq!(response.entries[1].key, b"a");
+        assert_eq!(response.entries[1].value, None);
+
+        let response = primary
+            .read_log(&ReadReplicationLogRequest::new(3))
+            .unwrap();
+        assert!(response.entries.is_empty());
+        assert_eq!(response.next_sequence, 3);
+
+        // The primary restarted.
+        let response = primary
+            .read_log(&ReadReplicationLogRequest::new(4))
+            .unwrap();
+        assert!(response.snapshot_required);
+        assert_eq!(response.next_sequence, 3);
+    }
+
+    pub fn test_replicate_snapshot() {
+        let mut primary = get_mock_database();
+        primary.put(b"a", b"1").unwrap();
+        primary.put(b"b", b"2").unwrap();
+
+        let mut replica = get_mock_database();
+        replica.put(b"stale", b"0").unwrap();
+        replica.reset().unwrap();
+
+        let response = primary
+            .read_log(&ReadReplicationLogRequest::snapshot(2, vec![]))
+            .unwrap();
+        assert_eq!(response.next_sequence, 2);
+        assert_eq!(response.snapshot_cursor, None);
+        replica.apply(&response.entries).unwrap();
+
+        primary.put(b"c", b"3").unwrap();
+        let response = primary
+            .read_log(&ReadReplicationLogRequest::new(2))
+            .unwrap();
+        replica.apply(&response.entries).unwrap();
+
+        assert_eq!(replica.get(b"a"), Some(b"1".to_vec()));
+        assert_eq!(replica.get(b"c"), Some(b"3".to_vec()));
+        assert_eq!(replica.get(b"stale"), None);
+    }
+
+    pub fn test_commit_and_rollback() {
+        let mut primary = get_mock_database();
+        primary.put(b"a", b"1").unwrap();
+
+        primary.begin();
+        primary.put(b"a", b"2").unwrap();
+        primary.delete(b"a").unwrap();
+        primary.put(b"b", b"1").unwrap();
+        // Buffered writes are read back before they are committed.
+        assert_eq!(primary.get(b"a"), None);
+        assert_eq!(primary.get(b"b"), Some(b"1".to_vec()));
+        primary.rollback();
+        assert_eq!(primary.get(b"a"), Some(b"1".to_vec()));
+        assert_eq!(primary.get(b"b"), None);
+
+        primary.begin();
+        primary.delete(b"a").unwrap();
+        primary.put(b"b", b"2").unwrap();
+        primary.commit().unwrap();
+        assert_eq!(primary.get(b"a"), None);
+        assert_eq!(primary.get(b"b"), Some(b"2".to_vec()));
+
+        // The committed writes are replicated in order.
+        let response = primary
+            .read_log(&ReadReplicationLogRequest::new(1))
+            .unwrap();
+        assert_eq!(response.entries.len(), 2);
+        assert_eq!(response.entries[0].value, None);
+        assert_eq!(response.entries[1].value, Some(b"2".to_vec()));
+    }
+}
@@ -15,32 +15,89 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use crate::backend::StorageBackend;
 use crate::error::TeaclaveStorageError;
-use crate::proxy::ProxyRequest;
-use rusty_leveldb::DB;
-use std::cell::RefCell;
+use crate::gc;
+use crate::index;
+use crate::proxy::{ProxyMessage, PurgeRequest, ReplicateRequest};
+use crate::replication::ReplicatedDB;
+use crate::snapshot;
+use crate::ttl;
+use crate::version;
+use crate::wal::WriteAheadLog;
+use std::cell::{Cell, RefCell};
+use std::path::{Path, PathBuf};
 use std::prelude::v1::*;
 use std::sync::mpsc::Receiver;
+use teaclave_config::StorageCompactionConfig;
 use teaclave_proto::teaclave_storage_service::{
-    DeleteRequest, DeleteResponse, DequeueRequest, DequeueResponse, EnqueueRequest,
-    EnqueueResponse, GetRequest, GetResponse, PutRequest, PutResponse, TeaclaveStorage,
+    CommitTransactionRequest, CommitTransactionResponse, CompareAndSwapRequest,
+    CompareAndSwapResponse, DeleteRequest, DeleteResponse, DequeueRequest, DequeueResponse,
+    EnqueueRequest, EnqueueResponse, ExportSnapshotRequest, ExportSnapshotResponse, GetRequest,
+    GetResponse, GetWalStatsRequest, GetWalStatsResponse, PromoteReplicaRequest,
+    PromoteReplicaResponse, PutIfVersionRequest, PutIfVersionResponse, PutIndexedRequest,
+    PutIndexedResponse, PutRequest, PutResponse, PutWithTtlRequest, PutWithTtlResponse,
+    ReadReplicationLogRequest, ReadReplicationLogResponse, RestoreSnapshotRequest,
+    RestoreSnapshotResponse, ScanIndexRequest, ScanIndexResponse, TeaclaveStorage,
+    TransactionWrite,
 };
 use teaclave_rpc::Request;
-use teaclave_service_enclave_utils::{bail, teaclave_service};
-use teaclave_types::TeaclaveServiceResponseResult;
+use teaclave_service_enclave_utils::{bail, ensure, teaclave_service};
+use teaclave_types::{StagedFileInfo, TeaclaveServiceResponseResult, TrustedTime};
 
 #[teaclave_service(teaclave_storage_service, TeaclaveStorage, TeaclaveStorageError)]
 pub(crate) struct TeaclaveStorageService {
-    // Current LevelDB implementation is not concurrent, so we need to wrap the
-    // DB with RefCell. This service is running in a single thread, it's safe to
+    // The backends are not concurrent, so we need to wrap the database with
+    // RefCell. This service is running in a single thread, it's safe to
     // use RefCell.
-    database: RefCell<DB>,
-    receiver: Receiver<ProxyRequest>,
+    database: RefCell<ReplicatedDB>,
+    receiver: Receiver<ProxyMessage>,
+    // A read replica only applies the writes of the primary storage, until it
+    // is promoted.
+    read_only: Cell<bool>,
+    // Directory of the write-ahead log a replica starts when it is promoted.
+    promotion_wal_dir: Option<PathBuf>,
+    // Snapshots are kept in the fusion base, without which they are not
+    // supported.
+    fusion_base: Option<PathBuf>,
 }
 
 impl TeaclaveStorageService {
-    pub(crate) fn new(database: RefCell<DB>, receiver: Receiver<ProxyRequest>) -> Self {
-        Self { database, receiver }
+    pub(crate) fn new(
+        database: impl StorageBackend + 'static,
+        receiver: Receiver<ProxyMessage>,
+        read_only: bool,
+    ) -> Self {
+        Self {
+            database: RefCell::new(ReplicatedDB::new(database)),
+            receiver,
+            read_only: Cell::new(read_only),
+            promotion_wal_dir: None,
+            fusion_base: None,
+        }
+    }
+
+    pub(crate) fn fusion_base(mut self, fusion_base: impl AsRef<Path>) -> Self {
+        self.fusion_base = Some(fusion_base.as_ref().to_owned());
+        self
+    }
+
+    pub(crate) fn wal(mut self, wal: WriteAheadLog) -> Self {
+        self.database = RefCell::new(self.database.into_inner().wal(wal));
+        self
+    }
+
+    pub(crate) fn promotion_wal(mut self, dir: impl AsRef<Path>) -> Self {
+        self.promotion_wal_dir = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    fn snapshot_path(&self, name: &str) -> Result<PathBuf, TeaclaveStorageError> {
+        let fusion_base = self
+            .fusion_base
+            .as_ref()
+            .ok_or(TeaclaveStorageError::Snapshot)?;
+        snapshot::path(fusion_base, name).ok_or(TeaclaveStorageError::InvalidSnapshot)
     }
 }
 
@@ -49,7 +106,7 @@ impl TeaclaveStorageService {
 // queue-key-index: Vec<u8>; elements
 // Todo: what if there are errors when doing get_tail and get_head
 struct DBQueue<'a> {
-    database: &'a mut DB,
+    database: &'a mut ReplicatedDB,
     key: &'a [u8],
 }
 
@@ -97,7 +154,7 @@ impl<'a> DBQueue<'a> {
         Some(u32::from_le_bytes(bytes))
     }
 
-    pub fn open(database: &'a mut DB, key: &'a [u8]) -> Self {
+    pub fn open(database: &'a mut ReplicatedDB, key: &'a [u8]) -> Self {
         DBQueue { database, key }
     }
 
@@ -151,7 +208,23 @@ impl TeaclaveStorageService {
 
         loop {
             let request = match self.receiver.recv() {
-                Ok(req) => req,
+                Ok(ProxyMessage::Request(req)) => req,
+                Ok(ProxyMessage::Replicate(req)) => {
+                    self.replicate(req);
+                    continue;
+                }
+                Ok(ProxyMessage::Purge(req)) => {
+                    self.purge(req);
+                    continue;
+                }
+                Ok(ProxyMessage::Compact(config)) => {
+                    self.compact(&config);
+                    continue;
+                }
+                Ok(ProxyMessage::Sync) => {
+                    self.sync();
+                    continue;
+                }
                 Err(e) => {
                     error!("mspc receive error: {}", e);
                     break;
@@ -167,41 +240,262 @@ impl TeaclaveStorageService {
             }
         }
     }
+
+    // Writes of the old primary still in flight are rejected once promoted,
+    // which stops following it.
+    fn replicate(&self, request: ReplicateRequest) {
+        let result = if !self.read_only.get() {
+            Err(TeaclaveStorageError::NotReplica)
+        } else {
+            let mut db = self.database.borrow_mut();
+            let result = if request.reset {
+                db.reset()
+            } else {
+                db.apply(&request.entries)
+            };
+            result.map_err(TeaclaveStorageError::LevelDb)
+        };
+        if let Err(e) = request.sender.send(result) {
+            error!("mpsc send error: {}", e);
+        }
+    }
+
+    // Purges are written through the replication log, so replicas purge the
+    // same records without running any purge of their own.
+    fn purge(&self, request: PurgeRequest) {
+        if self.read_only.get() {
+            return;
+        }
+        let mut db = self.database.borrow_mut();
+        match gc::purge_deleted(&mut db, request.now, request.retention) {
+            Ok(0) => (),
+            Ok(purged) => info!("Purged {} deleted records", purged),
+            Err(e) => error!("Failed to purge deleted records: {:?}", e),
+        }
+        match gc::purge_expired(&mut db, request.now) {
+            Ok(0) => (),
+            Ok(purged) => info!("Purged {} expired records", purged),
+            Err(e) => error!("Failed to purge expired records: {:?}", e),
+        }
+    }
+
+    // Requests wait for the compaction, which writes all keys to a new
+    // generation of the log.
+    fn compact(&self, config: &StorageCompactionConfig) {
+        if let Err(e) = self.database.borrow_mut().compact_wal(config) {
+            error!("Failed to compact the write-ahead log: {:?}", e);
+        }
+    }
+
+    // Replicas keep nothing of their own until they are promoted.
+    fn sync(&self) {
+        if self.read_only.get() {
+            return;
+        }
+        if let Err(e) = self.database.borrow_mut().sync() {
+            error!("Failed to sync the storage backend: {:?}", e);
+        }
+    }
+}
+
+// Expired keys are absent to readers until they are purged.
+fn get_live(db: &mut ReplicatedDB, key: &[u8]) -> Option<Vec<u8>> {
+    if ttl::is_expired(db, key, TrustedTime::now_secs()) {
+        return None;
+    }
+    db.get(key)
 }
+
+// Writes as the request of the same kind does, into the open transaction.
+fn write_in_transaction(
+    db: &mut ReplicatedDB,
+    write: &TransactionWrite,
+) -> Result<(), TeaclaveStorageError> {
+    match write {
+        TransactionWrite::Put { key, value } => {
+            db.put(key, value)?;
+            version::bump(db, key)?;
+            ttl::remove(db, key)?;
+        }
+        TransactionWrite::PutIfVersion {
+            key,
+            value,
+            version,
+        } => {
+            if version::get(db, key) != *version {
+                return Err(TeaclaveStorageError::Conflict);
+            }
+            db.put(key, value)?;
+            version::bump(db, key)?;
+            ttl::remove(db, key)?;
+        }
+        TransactionWrite::PutIndexed { key, value, terms } => {
+            let request = PutIndexedRequest::new(key.as_slice(), value.as_slice(), terms.clone());
+            index::put_indexed(db, &request)?;
+            version::bump(db, key)?;
+            ttl::remove(db, key)?;
+        }
+        TransactionWrite::Delete { key } => {
+            index::remove_terms(db, key)?;
+            version::remove(db, key)?;
+            ttl::remove(db, key)?;
+            db.delete(key)?;
+        }
+    }
+    Ok(())
+}
+
 impl TeaclaveStorage for TeaclaveStorageService {
     fn get(&self, request: Request<GetRequest>) -> TeaclaveServiceResponseResult<GetResponse> {
         let request = request.message;
-        match self.database.borrow_mut().get(&request.key) {
-            Some(value) => Ok(GetResponse { value }),
+        let mut db = self.database.borrow_mut();
+        match get_live(&mut db, &request.key) {
+            Some(value) => Ok(GetResponse::new(value, version::get(&mut db, &request.key))),
             None => Err(TeaclaveStorageError::None.into()),
         }
     }
 
     fn put(&self, request: Request<PutRequest>) -> TeaclaveServiceResponseResult<PutResponse> {
+        ensure!(!self.read_only.get(), TeaclaveStorageError::ReadOnly);
         let request = request.message;
-        self.database
-            .borrow_mut()
-            .put(&request.key, &request.value)
+        let mut db = self.database.borrow_mut();
+        db.put(&request.key, &request.value)
             .map_err(TeaclaveStorageError::LevelDb)?;
+        version::bump(&mut db, &request.key)?;
+        ttl::remove(&mut db, &request.key)?;
         Ok(PutResponse)
     }
 
+    // Requests are served one by one, so the value cannot be changed between
+    // the comparison and the put.
+    fn compare_and_swap(
+        &self,
+        request: Request<CompareAndSwapRequest>,
+    ) -> TeaclaveServiceResponseResult<CompareAndSwapResponse> {
+        ensure!(!self.read_only.get(), TeaclaveStorageError::ReadOnly);
+        let request = request.message;
+        let mut db = self.database.borrow_mut();
+        match get_live(&mut db, &request.key) {
+            Some(value) if value == request.expected => (),
+            Some(_) => bail!(TeaclaveStorageError::Conflict),
+            None => bail!(TeaclaveStorageError::None),
+        }
+        db.put(&request.key, &request.value)
+            .map_err(TeaclaveStorageError::LevelDb)?;
+        version::bump(&mut db, &request.key)?;
+        ttl::remove(&mut db, &request.key)?;
+        Ok(CompareAndSwapResponse)
+    }
+
+    // Same as compare_and_swap on the version of the value instead of the
+    // value itself, which also tells apart writes of the same value.
+    fn put_if_version(
+        &self,
+        request: Request<PutIfVersionRequest>,
+    ) -> TeaclaveServiceResponseResult<PutIfVersionResponse> {
+        ensure!(!self.read_only.get(), TeaclaveStorageError::ReadOnly);
+        let request = request.message;
+        let mut db = self.database.borrow_mut();
+        ensure!(
+            version::get(&mut db, &request.key) == request.version,
+            TeaclaveStorageError::Conflict
+        );
+        db.put(&request.key, &request.value)
+            .map_err(TeaclaveStorageError::LevelDb)?;
+        let version = version::bump(&mut db, &request.key)?;
+        ttl::remove(&mut db, &request.key)?;
+        Ok(PutIfVersionResponse { version })
+    }
+
+    // The value and its expiry are written in turn with the other requests, so
+    // no reader sees the value without its expiry.
+    fn put_with_ttl(
+        &self,
+        request: Request<PutWithTtlRequest>,
+    ) -> TeaclaveServiceResponseResult<PutWithTtlResponse> {
+        ensure!(!self.read_only.get(), TeaclaveStorageError::ReadOnly);
+        let request = request.message;
+        ensure!(request.ttl > 0, TeaclaveStorageError::InvalidTtl);
+        let expires_at = TrustedTime::now_secs().saturating_add(request.ttl);
+        let mut db = self.database.borrow_mut();
+        db.put(&request.key, &request.value)
+            .map_err(TeaclaveStorageError::LevelDb)?;
+        version::bump(&mut db, &request.key)?;
+        ttl::set(&mut db, &request.key, expires_at)?;
+        Ok(PutWithTtlResponse)
+    }
+
     fn delete(
         &self,
         request: Request<DeleteRequest>,
     ) -> TeaclaveServiceResponseResult<DeleteResponse> {
+        ensure!(!self.read_only.get(), TeaclaveStorageError::ReadOnly);
         let request = request.message;
-        self.database
-            .borrow_mut()
-            .delete(&request.key)
+        let mut db = self.database.borrow_mut();
+        index::remove_terms(&mut db, &request.key)?;
+        version::remove(&mut db, &request.key)?;
+        ttl::remove(&mut db, &request.key)?;
+        db.delete(&request.key)
             .map_err(TeaclaveStorageError::LevelDb)?;
         Ok(DeleteResponse)
     }
 
+    fn put_indexed(
+        &self,
+        request: Request<PutIndexedRequest>,
+    ) -> TeaclaveServiceResponseResult<PutIndexedResponse> {
+        ensure!(!self.read_only.get(), TeaclaveStorageError::ReadOnly);
+        let mut db = self.database.borrow_mut();
+        index::put_indexed(&mut db, &request.message)?;
+        version::bump(&mut db, &request.message.key)?;
+        ttl::remove(&mut db, &request.message.key)?;
+        Ok(PutIndexedResponse)
+    }
+
+    // The writes are buffered until all of them succeed, and then written in
+    // one batch, so that a stale version or a crash midway leaves none of
+    // them. Later writes see the earlier ones, e.g., a PutIfVersion after a
+    // Put of the same key expects the bumped version.
+    fn commit_transaction(
+        &self,
+        request: Request<CommitTransactionRequest>,
+    ) -> TeaclaveServiceResponseResult<CommitTransactionResponse> {
+        ensure!(!self.read_only.get(), TeaclaveStorageError::ReadOnly);
+        let mut db = self.database.borrow_mut();
+        db.begin();
+        let result = request
+            .message
+            .writes
+            .iter()
+            .try_for_each(|write| write_in_transaction(&mut db, write));
+        if let Err(e) = result {
+            db.rollback();
+            return Err(e.into());
+        }
+        db.commit().map_err(TeaclaveStorageError::LevelDb)?;
+        Ok(CommitTransactionResponse)
+    }
+
+    // Replicas serve scans as well, which may lag behind the primary. Expired
+    // records are left out of the page.
+    fn scan_index(
+        &self,
+        request: Request<ScanIndexRequest>,
+    ) -> TeaclaveServiceResponseResult<ScanIndexResponse> {
+        let mut db = self.database.borrow_mut();
+        let mut response = index::scan(&mut db, &request.message)?;
+        let now = TrustedTime::now_secs();
+        response
+            .entries
+            .retain(|entry| !ttl::is_expired(&mut db, &entry.key, now));
+        Ok(response)
+    }
+
     fn enqueue(
         &self,
         request: Request<EnqueueRequest>,
     ) -> TeaclaveServiceResponseResult<EnqueueResponse> {
+        ensure!(!self.read_only.get(), TeaclaveStorageError::ReadOnly);
         let request = request.message;
         let mut db = self.database.borrow_mut();
         let mut queue = DBQueue::open(&mut db, &request.key);
@@ -212,16 +506,111 @@ impl TeaclaveStorage for TeaclaveStorageService {
         &self,
         request: Request<DequeueRequest>,
     ) -> TeaclaveServiceResponseResult<DequeueResponse> {
+        ensure!(!self.read_only.get(), TeaclaveStorageError::ReadOnly);
         let request = request.message;
         let mut db = self.database.borrow_mut();
         let mut queue = DBQueue::open(&mut db, &request.key);
         queue.dequeue().map(|value| DequeueResponse { value })
     }
+
+    // Replicas do not serve their own log, which does not record the writes
+    // applied from the primary.
+    fn read_replication_log(
+        &self,
+        request: Request<ReadReplicationLogRequest>,
+    ) -> TeaclaveServiceResponseResult<ReadReplicationLogResponse> {
+        ensure!(!self.read_only.get(), TeaclaveStorageError::ReadOnly);
+        let response = self
+            .database
+            .borrow_mut()
+            .read_log(&request.message)
+            .map_err(TeaclaveStorageError::LevelDb)?;
+        Ok(response)
+    }
+
+    // Replicas export snapshots as well, which may lag behind the primary.
+    fn export_snapshot(
+        &self,
+        request: Request<ExportSnapshotRequest>,
+    ) -> TeaclaveServiceResponseResult<ExportSnapshotResponse> {
+        let request = request.message;
+        let path = self.snapshot_path(&request.name)?;
+        let mut iter = self
+            .database
+            .borrow_mut()
+            .new_iter()
+            .map_err(TeaclaveStorageError::LevelDb)?;
+        let (bytes, entries) = snapshot::encode(iter.as_mut());
+        let file = snapshot::seal(&path, &bytes).map_err(|e| {
+            error!("Failed to export snapshot {}: {:?}", request.name, e);
+            TeaclaveStorageError::Snapshot
+        })?;
+        info!("Exported snapshot {} of {} keys", request.name, entries);
+        Ok(ExportSnapshotResponse {
+            crypto_info: file.crypto_info,
+            cmac: file.cmac,
+            entries,
+        })
+    }
+
+    fn restore_snapshot(
+        &self,
+        request: Request<RestoreSnapshotRequest>,
+    ) -> TeaclaveServiceResponseResult<RestoreSnapshotResponse> {
+        ensure!(!self.read_only.get(), TeaclaveStorageError::ReadOnly);
+        let request = request.message;
+        let path = self.snapshot_path(&request.name)?;
+        let file = StagedFileInfo::new(path, request.crypto_info, request.cmac);
+        let entries = snapshot::unseal(&file).map_err(|e| {
+            warn!("Failed to open snapshot {}: {:?}", request.name, e);
+            TeaclaveStorageError::InvalidSnapshot
+        })?;
+        self.database
+            .borrow_mut()
+            .restore(&entries)
+            .map_err(TeaclaveStorageError::LevelDb)?;
+        info!(
+            "Restored snapshot {} of {} keys",
+            request.name,
+            entries.len()
+        );
+        Ok(RestoreSnapshotResponse {
+            entries: entries.len() as u64,
+        })
+    }
+
+    fn get_wal_stats(
+        &self,
+        _request: Request<GetWalStatsRequest>,
+    ) -> TeaclaveServiceResponseResult<GetWalStatsResponse> {
+        Ok(self.database.borrow().wal_stats())
+    }
+
+    // The keys are served as replicated so far. A replica with a directory for
+    // its write-ahead log starts the log with them first, so that the promoted
+    // storage keeps them across restarts.
+    fn promote_replica(
+        &self,
+        _request: Request<PromoteReplicaRequest>,
+    ) -> TeaclaveServiceResponseResult<PromoteReplicaResponse> {
+        ensure!(self.read_only.get(), TeaclaveStorageError::NotReplica);
+        let mut db = self.database.borrow_mut();
+        if let Some(dir) = &self.promotion_wal_dir {
+            db.start_wal(dir).map_err(|e| {
+                error!("Failed to start the write-ahead log on promotion: {:?}", e);
+                TeaclaveStorageError::LevelDb(e)
+            })?;
+        }
+        self.read_only.set(false);
+        info!("Promoted the replica to a primary storage");
+        Ok(PromoteReplicaResponse)
+    }
 }
 
 #[cfg(test_mode)]
 mod test_mode {
     use super::*;
+    use rusty_leveldb::DB;
     pub(crate) fn repalce_with_mock_database(service: &mut TeaclaveStorageService) {
         let opt = rusty_leveldb::in_memory();
         let mut database = DB::open("mock_db", opt).unwrap();
@@ -229,15 +618,18 @@ mod test_mode {
         database
             .put(b"test_delete_key", b"test_delete_value")
             .unwrap();
-        service.database.replace(database);
+        service.database.replace(ReplicatedDB::new(database));
     }
 }
 
 #[cfg(feature = "enclave_unit_test")]
 pub mod tests {
     use super::*;
+    use rusty_leveldb::DB;
     use std::sync::mpsc::channel;
+    use teaclave_proto::teaclave_storage_service::Transaction;
     use teaclave_rpc::IntoRequest;
+    use teaclave_types::{IndexTerm, TeaclaveErrorCode};
 
     fn get_mock_service() -> TeaclaveStorageService {
         let (_sender, receiver) = channel();
@@ -247,10 +639,7 @@ pub mod tests {
         database
             .put(b"test_delete_key", b"test_delete_value")
             .unwrap();
-        TeaclaveStorageService {
-            database: RefCell::new(database),
-            receiver,
-        }
+        TeaclaveStorageService::new(database, receiver, false)
     }
 
     pub fn test_get_key() {
@@ -267,6 +656,83 @@ pub mod tests {
         assert!(service.get(request).is_ok());
     }
 
+    pub fn test_compare_and_swap() {
+        let service = get_mock_service();
+        let request =
+            CompareAndSwapRequest::new("test_get_key", "test_get_value", "test_swap_value")
+                .into_request();
+        assert!(service.compare_and_swap(request).is_ok());
+        let request = GetRequest::new("test_get_key").into_request();
+        assert_eq!(service.get(request).unwrap().value, b"test_swap_value");
+
+        let request =
+            CompareAndSwapRequest::new("test_get_key", "test_get_value", "test_stale_value")
+                .into_request();
+        let error = service.compare_and_swap(request).unwrap_err();
+        assert_eq!(error.code(), TeaclaveErrorCode::Conflict);
+        let request = GetRequest::new("test_get_key").into_request();
+        assert_eq!(service.get(request).unwrap().value, b"test_swap_value");
+    }
+
+    pub fn test_put_if_version() {
+        let service = get_mock_service();
+        // Keys written before versions were introduced are at version 0.
+        let request = GetRequest::new("test_get_key").into_request();
+        assert_eq!(service.get(request).unwrap().version, 0);
+        let request = PutIfVersionRequest::new("test_get_key", "1", 0).into_request();
+        assert_eq!(service.put_if_version(request).unwrap().version, 1);
+        let request = PutRequest::new("test_get_key", "2").into_request();
+        assert!(service.put(request).is_ok());
+
+        // The write of a stale version is rejected even with the same value.
+        let request = PutIfVersionRequest::new("test_get_key", "2", 1).into_request();
+        let error = service.put_if_version(request).unwrap_err();
+        assert_eq!(error.code(), TeaclaveErrorCode::Conflict);
+        let request = GetRequest::new("test_get_key").into_request();
+        let response = service.get(request).unwrap();
+        assert_eq!(
+            (response.value.as_slice(), response.version),
+            (&b"2"[..], 2)
+        );
+
+        let request = PutIfVersionRequest::new("test_new_key", "1", 0).into_request();
+        assert_eq!(service.put_if_version(request).unwrap().version, 1);
+        let request = DeleteRequest::new("test_new_key").into_request();
+        assert!(service.delete(request).is_ok());
+        let request = PutIfVersionRequest::new("test_new_key", "1", 0).into_request();
+        assert_eq!(service.put_if_version(request).unwrap().version, 1);
+    }
+
+    pub fn test_put_with_ttl() {
+        let service = get_mock_service();
+        let request = PutWithTtlRequest::new("test_ttl_key", "1", 0).into_request();
+        let error = service.put_with_ttl(request).unwrap_err();
+        assert_eq!(error.code(), TeaclaveErrorCode::Validation);
+
+        let request = PutWithTtlRequest::new("test_ttl_key", "1", 60).into_request();
+        assert!(service.put_with_ttl(request).is_ok());
+        let request = GetRequest::new("test_ttl_key").into_request();
+        assert_eq!(service.get(request).unwrap().version, 1);
+
+        // An expired key is absent before it is purged.
+        ttl::set(&mut service.database.borrow_mut(), b"test_ttl_key", 1).unwrap();
+        let request = GetRequest::new("test_ttl_key").into_request();
+        let error = service.get(request).unwrap_err();
+        assert_eq!(error.code(), TeaclaveErrorCode::NotFound);
+        let request = CompareAndSwapRequest::new("test_ttl_key", "1", "2").into_request();
+        assert!(service.compare_and_swap(request).is_err());
+
+        // A put without a TTL keeps the key.
+        let request = PutRequest::new("test_ttl_key", "2").into_request();
+        assert!(service.put(request).is_ok());
+        let request = GetRequest::new("test_ttl_key").into_request();
+        assert_eq!(service.get(request).unwrap().value, b"2");
+        assert_eq!(
+            ttl::get(&mut service.database.borrow_mut(), b"test_ttl_key"),
+            None
+        );
+    }
+
     pub fn test_delete_key() {
         let service = get_mock_service();
         let request = DeleteRequest::new("test_delete_key").into_request();
@@ -296,4 +762,125 @@ pub mod tests {
         let request = DequeueRequest::new("test_dequeue_key").into_request();
         assert_eq!(service.dequeue(request).unwrap().value, b"2");
     }
+
+    pub fn test_read_only_replica() {
+        let (_sender, receiver) = channel();
+        let opt = rusty_leveldb::in_memory();
+        let mut database = DB::open("mock_db", opt).unwrap();
+        database.put(b"test_get_key", b"test_get_value").unwrap();
+        let service = TeaclaveStorageService::new(database, receiver, true);
+
+        let request = GetRequest::new("test_get_key").into_request();
+        assert!(service.get(request).is_ok());
+        let request = PutRequest::new("test_put_key", "test_put_value").into_request();
+        let error = service.put(request).unwrap_err();
+        assert_eq!(error.code(), TeaclaveErrorCode::Validation);
+        let request = PutIfVersionRequest::new("test_get_key", "1", 0).into_request();
+        assert!(service.put_if_version(request).is_err());
+        let request = EnqueueRequest::new("test_enqueue_key", "1").into_request();
+        assert!(service.enqueue(request).is_err());
+        let request = ReadReplicationLogRequest::new(0).into_request();
+        assert!(service.read_replication_log(request).is_err());
+        let request = RestoreSnapshotRequest::new("test", Default::default(), Default::default())
+            .into_request();
+        let error = service.restore_snapshot(request).unwrap_err();
+        assert_eq!(error.code(), TeaclaveErrorCode::Validation);
+    }
+
+    pub fn test_promote_replica() {
+        let (_sender, receiver) = channel();
+        let opt = rusty_leveldb::in_memory();
+        let mut database = DB::open("mock_db", opt).unwrap();
+        database.put(b"test_get_key", b"test_get_value").unwrap();
+        let service = TeaclaveStorageService::new(database, receiver, true);
+
+        let request = PromoteReplicaRequest::new().into_request();
+        assert!(service.promote_replica(request).is_ok());
+        let request = PutRequest::new("test_put_key", "test_put_value").into_request();
+        assert!(service.put(request).is_ok());
+        let request = GetRequest::new("test_get_key").into_request();
+        assert_eq!(service.get(request).unwrap().value, b"test_get_value");
+
+        // Writes of the old primary are no longer applied.
+        let (sender, receiver) = channel();
+        service.replicate(ReplicateRequest {
+            sender,
+            entries: Vec::new(),
+            reset: true,
+        });
+        assert!(receiver.recv().unwrap().is_err());
+        let request = GetRequest::new("test_put_key").into_request();
+        assert!(service.get(request).is_ok());
+
+        let request = PromoteReplicaRequest::new().into_request();
+        let error = service.promote_replica(request).unwrap_err();
+        assert_eq!(error.code(), TeaclaveErrorCode::Validation);
+    }
+
+    pub fn test_read_replication_log_of_writes() {
+        let service = get_mock_service();
+        let request = PutRequest::new("test_put_key", "test_put_value").into_request();
+        assert!(service.put(request).is_ok());
+        let request = EnqueueRequest::new("test_enqueue_key", "1").into_request();
+        assert!(service.enqueue(request).is_ok());
+
+        // The mock database is written before the service is created, and the
+        // put is followed by the bump of its version.
+        let request = ReadReplicationLogRequest::new(0).into_request();
+        let response = service.read_replication_log(request).unwrap();
+        assert_eq!(response.next_sequence, 4);
+        assert_eq!(response.entries[0].key, b"test_put_key");
+        assert_eq!(response.entries[1].key, b"version/test_put_key");
+        assert_eq!(response.entries.len(), 4);
+    }
+
+    pub fn test_commit_transaction() {
+        let service = get_mock_service();
+        let terms = vec![IndexTerm::new("input-owner", "alice")];
+        let mut transaction = Transaction::begin();
+        transaction.put_indexed("input-1", "1", terms);
+        transaction.put_if_version("task-1", "1", 0);
+        transaction.delete("test_delete_key");
+        let request = CommitTransactionRequest::from(transaction).into_request();
+        assert!(service.commit_transaction(request).is_ok());
+        let request = ScanIndexRequest::new("input-owner", "alice").into_request();
+        assert_eq!(service.scan_index(request).unwrap().entries.len(), 1);
+        let request = GetRequest::new("task-1").into_request();
+        assert_eq!(service.get(request).unwrap().version, 1);
+        let request = GetRequest::new("test_delete_key").into_request();
+        assert!(service.get(request).is_err());
+
+        // A stale version fails the transaction without writing any of it.
+        let mut transaction = Transaction::begin();
+        transaction.delete("input-1");
+        transaction.put("test_get_key", "2");
+        transaction.put_if_version("task-1", "2", 0);
+        let request = CommitTransactionRequest::from(transaction).into_request();
+        let error = service.commit_transaction(request).unwrap_err();
+        assert_eq!(error.code(), TeaclaveErrorCode::Conflict);
+        let request = ScanIndexRequest::new("input-owner", "alice").into_request();
+        assert_eq!(service.scan_index(request).unwrap().entries.len(), 1);
+        let request = GetRequest::new("test_get_key").into_request();
+        assert_eq!(service.get(request).unwrap().value, b"test_get_value");
+        let request = GetRequest::new("task-1").into_request();
+        assert_eq!(service.get(request).unwrap().value, b"1");
+    }
+
+    pub fn test_delete_indexed_key() {
+        let service = get_mock_service();
+        let terms = vec![IndexTerm::new("input-owner", "alice")];
+        let request = PutIndexedRequest::new("input-1", "1", terms).into_request();
+        assert!(service.put_indexed(request).is_ok());
+        // A put keeps the terms of the key.
+        let request = PutRequest::new("input-1", "2").into_request();
+        assert!(service.put(request).is_ok());
+        let request = ScanIndexRequest::new("input-owner", "alice").into_request();
+        let response = service.scan_index(request).unwrap();
+        assert_eq!(response.entries[0].value, b"2");
+
+        let request = DeleteRequest::new("input-1").into_request();
+        assert!(service.delete(request).is_ok());
+        let request = ScanIndexRequest::new("input-owner", "alice").into_request();
+        assert!(service.scan_index(request).unwrap().entries.is_empty());
+    }
 }
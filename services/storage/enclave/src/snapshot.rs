@@ -0,0 +1,159 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Sealed snapshots of all keys of the storage, which back the storage up to
+//! a file in the fusion base and restore it on the same or another node. A
+//! snapshot is sealed with a random key, which is only returned to the caller
+//! along with the cmac verifying the snapshot.
+
+use anyhow::{ensure, Result};
+use rusty_leveldb::LdbIterator;
+use std::convert::TryInto;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::prelude::v1::*;
+use std::untrusted::fs;
+use teaclave_types::StagedFileInfo;
+
+// Snapshots are kept in this directory of the fusion base.
+const SNAPSHOT_DIR: &str = "storage-snapshots";
+const SNAPSHOT_HEADER: &[u8] = b"teaclave-storage-snapshot-1";
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Path of the snapshot of the name in the fusion base, or none if the name
+/// is not made of 1 to 64 ASCII letters, digits, `-` and `_`.
+pub(crate) fn path(fusion_base: &Path, name: &str) -> Option<PathBuf> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if !valid {
+        return None;
+    }
+    Some(
+        fusion_base
+            .join(SNAPSHOT_DIR)
+            .join(format!("{}.snapshot", name)),
+    )
+}
+
+/// Encodes all keys of the iterator in the order of the keys, each key and
+/// value prefixed with its length, and returns the number of the keys too.
+/// The iterator sees the keys as they are when it is created, so the snapshot
+/// is consistent.
+pub(crate) fn encode(iter: &mut dyn LdbIterator) -> (Vec<u8>, u64) {
+    let mut bytes = SNAPSHOT_HEADER.to_vec();
+    let mut entries = 0;
+    iter.seek_to_first();
+    let (mut key, mut value) = (Vec::new(), Vec::new());
+    while iter.current(&mut key, &mut value) {
+        put_field(&mut bytes, &key);
+        put_field(&mut bytes, &value);
+        entries += 1;
+        if !iter.advance() {
+            break;
+        }
+    }
+    (bytes, entries)
+}
+
+/// Seals the encoded keys into the snapshot at the path, replacing an older
+/// snapshot of the same name.
+pub(crate) fn seal(path: &Path, bytes: &[u8]) -> Result<StagedFileInfo> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    StagedFileInfo::create_with_bytes(path, bytes)
+}
+
+/// Opens the sealed snapshot and decodes all of its keys, so that a corrupted
+/// snapshot is rejected before any key is replaced.
+pub(crate) fn unseal(file: &StagedFileInfo) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut bytes = Vec::new();
+    file.create_readable_io()?.read_to_end(&mut bytes)?;
+    ensure!(
+        bytes.starts_with(SNAPSHOT_HEADER),
+        "Not a storage snapshot: {:?}",
+        file.path
+    );
+    let mut rest = &bytes[SNAPSHOT_HEADER.len()..];
+    let mut entries = Vec::new();
+    while !rest.is_empty() {
+        let key = take_field(&mut rest)?;
+        let value = take_field(&mut rest)?;
+        entries.push((key, value));
+    }
+    Ok(entries)
+}
+
+fn put_field(bytes: &mut Vec<u8>, field: &[u8]) {
+    bytes.extend_from_slice(&(field.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(field);
+}
+
+fn take_field(bytes: &mut &[u8]) -> Result<Vec<u8>> {
+    ensure!(bytes.len() >= 4, "Truncated storage snapshot");
+    let (length, rest) = bytes.split_at(4);
+    let length = u32::from_be_bytes(length.try_into()?) as usize;
+    ensure!(rest.len() >= length, "Truncated storage snapshot");
+    let (field, rest) = rest.split_at(length);
+    *bytes = rest;
+    Ok(field.to_vec())
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use crate::replication::ReplicatedDB;
+    use rusty_leveldb::DB;
+    use teaclave_proto::teaclave_storage_service::ReadReplicationLogRequest;
+    use teaclave_types::FileAuthTag;
+
+    pub fn test_export_and_restore() {
+        let opt = rusty_leveldb::in_memory();
+        let mut db = ReplicatedDB::new(DB::open("mock_db", opt).unwrap());
+        db.put(b"a", b"1").unwrap();
+        db.put(b"b", b"").unwrap();
+
+        assert!(path(Path::new("/tmp/fusion_base"), "../a").is_none());
+        assert!(path(Path::new("/tmp/fusion_base"), "").is_none());
+        let snapshot = path(Path::new("/tmp/fusion_base"), "test_snapshot").unwrap();
+        let (bytes, entries) = encode(db.new_iter().unwrap().as_mut());
+        assert_eq!(entries, 2);
+        let file = seal(&snapshot, &bytes).unwrap();
+
+        db.put(b"a", b"2").unwrap();
+        db.put(b"c", b"3").unwrap();
+        let tampered = StagedFileInfo::new(&snapshot, file.crypto_info, FileAuthTag::default());
+        assert!(unseal(&tampered).is_err());
+
+        let entries = unseal(&file).unwrap();
+        db.restore(&entries).unwrap();
+        assert_eq!(db.get(b"a"), Some(b"1".to_vec()));
+        assert_eq!(db.get(b"b"), Some(Vec::new()));
+        assert_eq!(db.get(b"c"), None);
+        // Replicas of the database before the restore copy a snapshot.
+        let response = db.read_log(&ReadReplicationLogRequest::new(4)).unwrap();
+        assert!(response.snapshot_required);
+
+        fs::remove_file(&snapshot).unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Expiration of the records put with a TTL. The time a key expires at is
+//! kept along with an entry ordered by the time, so that the periodic purge
+//! finds the expired keys without reading all of them:
+//!
+//!   expiry/<key>                       -> expiry as a big-endian u64
+//!   expiry-at/<big-endian expiry><key> -> empty
+//!
+//! Times are seconds since the Unix epoch of the trusted time. A key is read
+//! as absent once expired, even before it is purged, and writes without a TTL
+//! remove its expiry. Expiries are written through the replicated database
+//! like the records, so replicas hide the same keys by their own clock.
+
+use crate::error::TeaclaveStorageError;
+use crate::replication::ReplicatedDB;
+use rusty_leveldb::LdbIterator;
+use std::convert::TryInto;
+use std::prelude::v1::*;
+
+const EXPIRY_PREFIX: &[u8] = b"expiry/";
+const EXPIRY_AT_PREFIX: &[u8] = b"expiry-at/";
+
+type Result<T> = std::result::Result<T, TeaclaveStorageError>;
+
+fn expiry_key(key: &[u8]) -> Vec<u8> {
+    let mut expiry_key = EXPIRY_PREFIX.to_vec();
+    expiry_key.extend_from_slice(key);
+    expiry_key
+}
+
+fn expiry_at_key(expires_at: u64, key: &[u8]) -> Vec<u8> {
+    let mut expiry_at_key = EXPIRY_AT_PREFIX.to_vec();
+    expiry_at_key.extend_from_slice(&expires_at.to_be_bytes());
+    expiry_at_key.extend_from_slice(key);
+    expiry_at_key
+}
+
+/// Time the key expires at, if it is put with a TTL.
+pub(crate) fn get(db: &mut ReplicatedDB, key: &[u8]) -> Option<u64> {
+    db.get(&expiry_key(key))
+        .and_then(|bytes| bytes.as_slice().try_into().ok())
+        .map(u64::from_be_bytes)
+}
+
+pub(crate) fn is_expired(db: &mut ReplicatedDB, key: &[u8], now: u64) -> bool {
+    get(db, key).map_or(false, |expires_at| expires_at <= now)
+}
+
+/// Sets the time the key expires at, replacing the former one.
+pub(crate) fn set(db: &mut ReplicatedDB, key: &[u8], expires_at: u64) -> Result<()> {
+    if let Some(former) = get(db, key) {
+        db.delete(&expiry_at_key(former, key))?;
+    }
+    db.put(&expiry_key(key), &expires_at.to_be_bytes())?;
+    db.put(&expiry_at_key(expires_at, key), b"")?;
+    Ok(())
+}
+
+/// Keeps the key from expiring, once it is written without a TTL or deleted.
+pub(crate) fn remove(db: &mut ReplicatedDB, key: &[u8]) -> Result<()> {
+    if let Some(expires_at) = get(db, key) {
+        db.delete(&expiry_at_key(expires_at, key))?;
+        db.delete(&expiry_key(key))?;
+    }
+    Ok(())
+}
+
+/// Reads up to `limit` keys expired at `now`, the earliest first.
+pub(crate) fn expired_keys(db: &mut ReplicatedDB, now: u64, limit: usize) -> Result<Vec<Vec<u8>>> {
+    let bound = expiry_at_key(now.saturating_add(1), b"");
+    let prefix_len = EXPIRY_AT_PREFIX.len() + std::mem::size_of::<u64>();
+
+    let mut iter = db.new_iter()?;
+    iter.seek(EXPIRY_AT_PREFIX);
+    let mut keys = Vec::new();
+    let (mut entry, mut value) = (Vec::new(), Vec::new());
+    while keys.len() < limit && iter.current(&mut entry, &mut value) {
+        if !entry.starts_with(EXPIRY_AT_PREFIX) || entry >= bound {
+            break;
+        }
+        keys.push(entry[prefix_len..].to_vec());
+        if !iter.advance() {
+            break;
+        }
+    }
+    Ok(keys)
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use rusty_leveldb::DB;
+
+    pub fn test_set_and_remove_expiry() {
+        let opt = rusty_leveldb::in_memory();
+        let mut db = ReplicatedDB::new(DB::open("mock_db", opt).unwrap());
+        assert_eq!(get(&mut db, b"token-1"), None);
+        assert!(!is_expired(&mut db, b"token-1", 100));
+
+        set(&mut db, b"token-1", 200).unwrap();
+        set(&mut db, b"token-2", 150).unwrap();
+        assert!(!is_expired(&mut db, b"token-1", 199));
+        assert!(is_expired(&mut db, b"token-1", 200));
+        assert_eq!(
+            expired_keys(&mut db, 200, 10).unwrap(),
+            vec![b"token-2".to_vec(), b"token-1".to_vec()]
+        );
+        assert_eq!(expired_keys(&mut db, 200, 1).unwrap().len(), 1);
+
+        // An extended TTL leaves no entry at the former expiry.
+        set(&mut db, b"token-1", 300).unwrap();
+        assert_eq!(expired_keys(&mut db, 200, 10).unwrap().len(), 1);
+        remove(&mut db, b"token-2").unwrap();
+        assert_eq!(get(&mut db, b"token-2"), None);
+        assert!(expired_keys(&mut db, 299, 10).unwrap().is_empty());
+        assert_eq!(
+            expired_keys(&mut db, u64::max_value(), 10).unwrap(),
+            vec![b"token-1".to_vec()]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Versions of the stored records. Every write of a record through the
+//! service bumps its version, so that a record read along with its version is
+//! written back with `PutIfVersion` only if nobody wrote it in between:
+//!
+//!   version/<key> -> version as a big-endian u64
+//!
+//! Absent keys, and keys not written since versions were introduced, are at
+//! version 0. The version is removed along with the key, so a key written
+//! again after it is deleted starts over at version 1. Versions are written
+//! through the replicated database like the records.
+
+use crate::error::TeaclaveStorageError;
+use crate::replication::ReplicatedDB;
+use std::convert::TryInto;
+use std::prelude::v1::*;
+
+const VERSION_PREFIX: &[u8] = b"version/";
+
+type Result<T> = std::result::Result<T, TeaclaveStorageError>;
+
+fn version_key(key: &[u8]) -> Vec<u8> {
+    let mut version_key = VERSION_PREFIX.to_vec();
+    version_key.extend_from_slice(key);
+    version_key
+}
+
+/// Current version of the key.
+pub(crate) fn get(db: &mut ReplicatedDB, key: &[u8]) -> u64 {
+    db.get(&version_key(key))
+        .and_then(|bytes| bytes.as_slice().try_into().ok())
+        .map(u64::from_be_bytes)
+        .unwrap_or(0)
+}
+
+/// Bumps the version of the key once it is written, and returns the new one.
+pub(crate) fn bump(db: &mut ReplicatedDB, key: &[u8]) -> Result<u64> {
+    let version = get(db, key) + 1;
+    db.put(&version_key(key), &version.to_be_bytes())?;
+    Ok(version)
+}
+
+pub(crate) fn remove(db: &mut ReplicatedDB, key: &[u8]) -> Result<()> {
+    db.delete(&version_key(key))?;
+    Ok(())
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use rusty_leveldb::DB;
+
+    pub fn test_bump_and_remove() {
+        let opt = rusty_leveldb::in_memory();
+        let mut db = ReplicatedDB::new(DB::open("mock_db", opt).unwrap());
+        assert_eq!(get(&mut db, b"task-1"), 0);
+        assert_eq!(bump(&mut db, b"task-1").unwrap(), 1);
+        assert_eq!(bump(&mut db, b"task-1").unwrap(), 2);
+        assert_eq!(get(&mut db, b"task-1"), 2);
+        assert_eq!(get(&mut db, b"task-2"), 0);
+
+        remove(&mut db, b"task-1").unwrap();
+        assert_eq!(get(&mut db, b"task-1"), 0);
+    }
+}
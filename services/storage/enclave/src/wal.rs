@@ -0,0 +1,601 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Write-ahead log of the storage, which keeps the keys of an in-memory
+//! backend across restarts of the enclave. Every write is appended to the
+//! log and flushed before it is applied to the database, so that a write
+//! acknowledged to a client is never lost, and the writes of a transaction
+//! are appended as one record, so that they are recovered all or none.
+//!
+//! The log is sealed with the protected file system of SGX, and kept in
+//! generations:
+//!
+//!   <dir>/<generation>.wal -> header, checkpoint of all keys, records
+//!
+//! On start, the keys are recovered from the latest generation whose
+//! checkpoint is complete, dropping a record torn by a crash at its end, and
+//! replace those of the backend. A new generation is then started with a
+//! checkpoint of the recovered keys, and the older ones are removed. Without
+//! any generation, the log starts with the keys the backend already has. A log which fails to unseal is not recovered at
+//! all, so that a corrupted or tampered log is never served. Sealing does not
+//! prevent the host from rolling the log back to an older generation.
+//!
+//! Overwritten and deleted keys leave dead bytes in the log, which are
+//! reclaimed by compacting the log into a new generation once they pass the
+//! thresholds of `StorageCompactionConfig`. The live bytes are those a
+//! checkpoint of the current keys takes, which are counted as keys are
+//! written rather than scanned.
+
+use crate::backend::StorageBackend;
+use anyhow::{bail, Result};
+use protected_fs::ProtectedFile;
+use rusty_leveldb::LdbIterator;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::prelude::v1::*;
+use std::untrusted::fs;
+use teaclave_config::StorageCompactionConfig;
+use teaclave_proto::teaclave_storage_service::GetWalStatsResponse;
+
+const WAL_HEADER: &[u8] = b"teaclave-storage-wal-1";
+const WAL_EXTENSION: &str = "wal";
+const CHECKPOINT: u8 = 0;
+const WRITES: u8 = 1;
+const DELETE: u8 = 0;
+const PUT: u8 = 1;
+
+pub(crate) struct WriteAheadLog {
+    dir: PathBuf,
+    generation: u64,
+    file: ProtectedFile,
+    // Bytes written to the generation, including its checkpoint.
+    log_bytes: u64,
+    // Bytes a checkpoint of the current keys takes.
+    live_bytes: u64,
+    compactions: u64,
+}
+
+impl WriteAheadLog {
+    /// Recovers the keys from the log in the directory into the backend, and
+    /// starts a new generation of the log with them.
+    pub(crate) fn recover(dir: impl AsRef<Path>, db: &mut dyn StorageBackend) -> Result<Self> {
+        let dir = dir.as_ref().to_owned();
+        fs::create_dir_all(&dir)?;
+        let mut generations = generations(&dir)?;
+        generations.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut recovered = None;
+        for generation in &generations {
+            let path = generation_path(&dir, *generation);
+            match read_generation(&path)? {
+                Some(entries) => {
+                    info!("Recovered {} keys from {:?}", entries.len(), path);
+                    recovered = Some(entries);
+                    break;
+                }
+                // Crashed while starting the generation, whose predecessor
+                // is then still there.
+                None => warn!("Skipped the incomplete checkpoint of {:?}", path),
+            }
+        }
+
+        let recovered = match recovered {
+            Some(entries) => {
+                db.replace_all(&entries)?;
+                entries
+            }
+            None => entries(db)?,
+        };
+        let next_generation = generations.first().map_or(0, |g| g + 1);
+        let wal = Self::start_generation(dir, next_generation, &recovered)?;
+        Ok(wal)
+    }
+
+    /// Starts a log in the directory with a checkpoint of the keys of the
+    /// database, e.g., those a promoted replica has copied from its primary.
+    /// Generations already in the directory are superseded.
+    pub(crate) fn start(dir: impl AsRef<Path>, db: &mut dyn StorageBackend) -> io::Result<Self> {
+        let dir = dir.as_ref().to_owned();
+        fs::create_dir_all(&dir)?;
+        let next_generation = generations(&dir)?.into_iter().max().map_or(0, |g| g + 1);
+        Self::start_generation(dir, next_generation, &entries(db)?)
+    }
+
+    /// Appends the writes as one record, which is recovered all or none.
+    /// The database is read for the values the writes replace, which are not
+    /// written to it yet.
+    pub(crate) fn append<'a>(
+        &mut self,
+        db: &mut dyn StorageBackend,
+        writes: impl IntoIterator<Item = (&'a [u8], Option<&'a [u8]>)>,
+    ) -> io::Result<()> {
+        let mut body = vec![WRITES];
+        // Live sizes of the keys written by the earlier writes of the record.
+        let mut written: HashMap<&[u8], u64> = HashMap::new();
+        let mut live_bytes = self.live_bytes;
+        for (key, value) in writes {
+            let replaced = match written.get(key) {
+                Some(size) => *size,
+                None => db.get(key).map_or(0, |value| entry_size(key, &value)),
+            };
+            let size = value.map_or(0, |value| entry_size(key, value));
+            live_bytes = live_bytes.saturating_sub(replaced) + size;
+            written.insert(key, size);
+            match value {
+                Some(value) => {
+                    body.push(PUT);
+                    put_field(&mut body, key);
+                    put_field(&mut body, value);
+                }
+                None => {
+                    body.push(DELETE);
+                    put_field(&mut body, key);
+                }
+            }
+        }
+        self.write_record(&body)?;
+        self.live_bytes = live_bytes;
+        Ok(())
+    }
+
+    /// Replaces the log with a new generation holding only the keys, e.g.,
+    /// those of a restored snapshot.
+    pub(crate) fn checkpoint(&mut self, entries: &[(Vec<u8>, Vec<u8>)]) -> io::Result<()> {
+        let mut wal = Self::start_generation(self.dir.clone(), self.generation + 1, entries)?;
+        wal.compactions = self.compactions;
+        *self = wal;
+        Ok(())
+    }
+
+    /// Bytes of the log which a compaction reclaims.
+    pub(crate) fn dead_bytes(&self) -> u64 {
+        let checkpoint_size = (WAL_HEADER.len() + 5) as u64 + self.live_bytes;
+        self.log_bytes.saturating_sub(checkpoint_size)
+    }
+
+    /// Whether the dead bytes pass both thresholds of the config.
+    pub(crate) fn needs_compaction(&self, config: &StorageCompactionConfig) -> bool {
+        let dead_bytes = self.dead_bytes();
+        dead_bytes > 0
+            && dead_bytes >= config.min_dead_bytes
+            && dead_bytes as f64 >= config.min_dead_ratio * self.log_bytes as f64
+    }
+
+    /// Compacts the log into a new generation with a checkpoint of the keys
+    /// of the database, which holds all writes of the log.
+    pub(crate) fn compact(&mut self, db: &mut dyn StorageBackend) -> io::Result<()> {
+        let entries = entries(db)?;
+        let dead_bytes = self.dead_bytes();
+        self.checkpoint(&entries)?;
+        self.compactions += 1;
+        info!(
+            "Compacted the storage write-ahead log into generation {}, reclaiming {} bytes",
+            self.generation, dead_bytes
+        );
+        Ok(())
+    }
+
+    pub(crate) fn stats(&self) -> GetWalStatsResponse {
+        GetWalStatsResponse {
+            enabled: true,
+            generation: self.generation,
+            log_bytes: self.log_bytes,
+            live_bytes: self.live_bytes,
+            dead_bytes: self.dead_bytes(),
+            compactions: self.compactions,
+        }
+    }
+
+    // The older generations are only removed once the checkpoint of the new
+    // one is flushed, so that a crash in between leaves one to recover from.
+    fn start_generation(
+        dir: PathBuf,
+        generation: u64,
+        entries: &[(Vec<u8>, Vec<u8>)],
+    ) -> io::Result<Self> {
+        let path = generation_path(&dir, generation);
+        let mut file = ProtectedFile::create(&path)?;
+        file.write_all(WAL_HEADER)?;
+        let mut wal = Self {
+            dir,
+            generation,
+            file,
+            log_bytes: WAL_HEADER.len() as u64,
+            live_bytes: 0,
+            compactions: 0,
+        };
+        let mut body = vec![CHECKPOINT];
+        for (key, value) in entries {
+            put_field(&mut body, key);
+            put_field(&mut body, value);
+            wal.live_bytes += entry_size(key, value);
+        }
+        wal.write_record(&body)?;
+
+        for older in generations(&wal.dir)? {
+            if older < generation {
+                fs::remove_file(generation_path(&wal.dir, older))?;
+            }
+        }
+        Ok(wal)
+    }
+
+    fn write_record(&mut self, body: &[u8]) -> io::Result<()> {
+        let mut record = Vec::with_capacity(body.len() + 4);
+        put_field(&mut record, body);
+        self.file.write_all(&record)?;
+        self.file.flush()?;
+        self.log_bytes += record.len() as u64;
+        Ok(())
+    }
+}
+
+fn entries(db: &mut dyn StorageBackend) -> io::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let mut entries = Vec::new();
+    let mut iter = db.new_iter()?;
+    iter.seek_to_first();
+    let (mut key, mut value) = (Vec::new(), Vec::new());
+    while iter.current(&mut key, &mut value) {
+        entries.push((key.clone(), value.clone()));
+        if !iter.advance() {
+            break;
+        }
+    }
+    Ok(entries)
+}
+
+// Size of the key and value in a checkpoint.
+fn entry_size(key: &[u8], value: &[u8]) -> u64 {
+    (key.len() + value.len() + 8) as u64
+}
+
+fn generation_path(dir: &Path, generation: u64) -> PathBuf {
+    dir.join(format!("{:020}.{}", generation, WAL_EXTENSION))
+}
+
+fn generations(dir: &Path) -> io::Result<Vec<u64>> {
+    let mut generations = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(WAL_EXTENSION) {
+            continue;
+        }
+        if let Some(generation) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse().ok())
+        {
+            generations.push(generation);
+        }
+    }
+    Ok(generations)
+}
+
+/// Replays the generation into the keys it holds, or returns none if its
+/// checkpoint is incomplete. A torn record at the end is dropped, but the
+/// generation fails to recover if it cannot be unsealed or decoded.
+fn read_generation(path: &Path) -> Result<Option<Vec<(Vec<u8>, Vec<u8>)>>> {
+    // Nothing is written to a protected file before it is first flushed.
+    if fs::metadata(path)?.len() == 0 {
+        return Ok(None);
+    }
+    let mut bytes = Vec::new();
+    ProtectedFile::open(path)?.read_to_end(&mut bytes)?;
+    if bytes.len() < WAL_HEADER.len() {
+        return Ok(None);
+    }
+    if !bytes.starts_with(WAL_HEADER) {
+        bail!("Not a storage write-ahead log: {:?}", path);
+    }
+    let mut rest = &bytes[WAL_HEADER.len()..];
+    let mut keys = std::collections::BTreeMap::new();
+    let mut checkpointed = false;
+    while !rest.is_empty() {
+        let mut body = match take_field(&mut rest) {
+            Some(body) => body,
+            None => {
+                warn!("Dropped a torn record at the end of {:?}", path);
+                break;
+            }
+        };
+        let kind = take_byte(&mut body)?;
+        match (kind, checkpointed) {
+            (CHECKPOINT, false) => {
+                while !body.is_empty() {
+                    let key = take_complete_field(&mut body)?;
+                    let value = take_complete_field(&mut body)?;
+                    keys.insert(key.to_vec(), value.to_vec());
+                }
+                checkpointed = true;
+            }
+            (WRITES, true) => {
+                while !body.is_empty() {
+                    let op = take_byte(&mut body)?;
+                    let key = take_complete_field(&mut body)?.to_vec();
+                    match op {
+                        PUT => {
+                            let value = take_complete_field(&mut body)?;
+                            keys.insert(key, value.to_vec());
+                        }
+                        DELETE => {
+                            keys.remove(&key);
+                        }
+                        _ => bail!("Invalid write in {:?}", path),
+                    }
+                }
+            }
+            _ => bail!("Invalid record in {:?}", path),
+        }
+    }
+    if !checkpointed {
+        return Ok(None);
+    }
+    Ok(Some(keys.into_iter().collect()))
+}
+
+fn put_field(bytes: &mut Vec<u8>, field: &[u8]) {
+    bytes.extend_from_slice(&(field.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(field);
+}
+
+// Returns none if the field is cut short, i.e., torn.
+fn take_field<'a>(bytes: &mut &'a [u8]) -> Option<&'a [u8]> {
+    if bytes.len() < 4 {
+        return None;
+    }
+    let (length, rest) = bytes.split_at(4);
+    let length = u32::from_be_bytes(length.try_into().ok()?) as usize;
+    if rest.len() < length {
+        return None;
+    }
+    let (field, rest) = rest.split_at(length);
+    *bytes = rest;
+    Some(field)
+}
+
+// Fields within a complete record are never torn.
+fn take_complete_field<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8]> {
+    match take_field(bytes) {
+        Some(field) => Ok(field),
+        None => bail!("Truncated field in a storage write-ahead log record"),
+    }
+}
+
+fn take_byte(bytes: &mut &[u8]) -> Result<u8> {
+    match bytes.split_first() {
+        Some((byte, rest)) => {
+            *bytes = rest;
+            Ok(*byte)
+        }
+        None => bail!("Empty storage write-ahead log record"),
+    }
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use crate::replication::ReplicatedDB;
+    use rusty_leveldb::DB;
+    use std::collections::BTreeMap;
+
+    const WAL_DIR: &str = "/tmp/storage_wal_test";
+
+    fn clean(dir: &str) -> PathBuf {
+        let dir = PathBuf::from(dir);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        dir
+    }
+
+    fn open(dir: &Path) -> Result<(WriteAheadLog, DB)> {
+        let mut db = DB::open("mock_db", rusty_leveldb::in_memory())?;
+        let wal = WriteAheadLog::recover(dir, &mut db)?;
+        Ok((wal, db))
+    }
+
+    fn recover(dir: &Path) -> ReplicatedDB {
+        let (wal, db) = open(dir).unwrap();
+        ReplicatedDB::new(db).wal(wal)
+    }
+
+    // Appends the first bytes of a record only, as a crash in the middle of
+    // writing it does.
+    fn append_torn(wal: &mut WriteAheadLog, key: &[u8], value: &[u8], length: usize) {
+        let mut body = vec![WRITES, PUT];
+        put_field(&mut body, key);
+        put_field(&mut body, value);
+        let mut record = Vec::new();
+        put_field(&mut record, &body);
+        wal.file.write_all(&record[..length]).unwrap();
+        wal.file.flush().unwrap();
+    }
+
+    pub fn test_recover_writes() {
+        let dir = clean(WAL_DIR);
+        let mut db = recover(&dir);
+        db.put(b"a", b"1").unwrap();
+        db.put(b"b", b"2").unwrap();
+        db.delete(b"a").unwrap();
+        db.begin();
+        db.put(b"c", b"3").unwrap();
+        db.put(b"d", b"4").unwrap();
+        db.commit().unwrap();
+        // Rolled back writes are not logged.
+        db.begin();
+        db.put(b"e", b"5").unwrap();
+        db.rollback();
+        drop(db);
+
+        let mut db = recover(&dir);
+        assert_eq!(db.get(b"a"), None);
+        assert_eq!(db.get(b"b"), Some(b"2".to_vec()));
+        assert_eq!(db.get(b"c"), Some(b"3".to_vec()));
+        assert_eq!(db.get(b"d"), Some(b"4".to_vec()));
+        assert_eq!(db.get(b"e"), None);
+        // Only the generation started by the recovery is left.
+        assert_eq!(generations(&dir).unwrap(), vec![1]);
+
+        db.restore(&[(b"f".to_vec(), b"6".to_vec())]).unwrap();
+        drop(db);
+        let mut db = recover(&dir);
+        assert_eq!(db.get(b"b"), None);
+        assert_eq!(db.get(b"f"), Some(b"6".to_vec()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    pub fn test_recover_torn_record() {
+        let dir = clean(WAL_DIR);
+        let (mut wal, mut db) = open(&dir).unwrap();
+        wal.append(&mut db, vec![(&b"a"[..], Some(&b"1"[..]))])
+            .unwrap();
+        append_torn(&mut wal, b"b", b"2", 9);
+        drop(wal);
+
+        let (mut wal, mut db) = open(&dir).unwrap();
+        assert_eq!(db.get(b"a"), Some(b"1".to_vec()));
+        assert_eq!(db.get(b"b"), None);
+        // The new generation does not carry the torn record on.
+        wal.append(&mut db, vec![(&b"c"[..], Some(&b"3"[..]))])
+            .unwrap();
+        drop(wal);
+        let (_, mut db) = open(&dir).unwrap();
+        assert_eq!(db.get(b"a"), Some(b"1".to_vec()));
+        assert_eq!(db.get(b"c"), Some(b"3".to_vec()));
+
+        // A generation crashed in its checkpoint falls back to the older one.
+        let mut file = ProtectedFile::create(generation_path(&dir, 9)).unwrap();
+        file.write_all(WAL_HEADER).unwrap();
+        file.write_all(&[0, 0, 1]).unwrap();
+        drop(file);
+        let (_, mut db) = open(&dir).unwrap();
+        assert_eq!(db.get(b"c"), Some(b"3".to_vec()));
+        assert_eq!(generations(&dir).unwrap(), vec![10]);
+
+        // A log which is not sealed by the enclave is not recovered.
+        fs::write(generation_path(&dir, 11), b"teaclave-storage-wal-1").unwrap();
+        assert!(open(&dir).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    pub fn test_compact_log() {
+        let dir = clean(WAL_DIR);
+        let mut db = recover(&dir);
+        let config = StorageCompactionConfig {
+            interval: 60,
+            min_dead_bytes: 100,
+            min_dead_ratio: 0.5,
+        };
+        let stats = db.wal_stats();
+        assert!(stats.enabled);
+        assert_eq!((stats.live_bytes, stats.dead_bytes), (0, 0));
+
+        // Each record takes 6 bytes besides the key and value.
+        db.put(b"a", b"1").unwrap();
+        db.put(b"b", b"2").unwrap();
+        let stats = db.wal_stats();
+        assert_eq!((stats.live_bytes, stats.dead_bytes), (20, 12));
+        db.delete(b"b").unwrap();
+        let stats = db.wal_stats();
+        assert_eq!((stats.live_bytes, stats.dead_bytes), (10, 33));
+
+        db.begin();
+        db.put(b"a", b"22").unwrap();
+        db.put(b"a", b"333").unwrap();
+        db.commit().unwrap();
+        assert_eq!(db.wal_stats().live_bytes, 12);
+        assert!(!db.compact_wal(&config).unwrap());
+
+        while db.wal_stats().dead_bytes < config.min_dead_bytes {
+            db.put(b"a", b"4444").unwrap();
+        }
+        let stats = db.wal_stats();
+        assert!(db.compact_wal(&config).unwrap());
+        let compacted = db.wal_stats();
+        assert_eq!(compacted.generation, stats.generation + 1);
+        assert_eq!(compacted.live_bytes, stats.live_bytes);
+        assert_eq!(compacted.dead_bytes, 0);
+        assert_eq!(compacted.compactions, 1);
+        assert!(!db.compact_wal(&config).unwrap());
+        drop(db);
+
+        let mut db = recover(&dir);
+        assert_eq!(db.get(b"a"), Some(b"4444".to_vec()));
+        assert_eq!(db.get(b"b"), None);
+        assert_eq!(db.wal_stats().live_bytes, 13);
+
+        let opt = rusty_leveldb::in_memory();
+        let db = ReplicatedDB::new(DB::open("mock_db", opt).unwrap());
+        assert_eq!(db.wal_stats(), GetWalStatsResponse::default());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // Crashes the log at random points of random writes, and checks that the
+    // recovered keys are exactly those written before the crash.
+    pub fn test_recover_random_crashes() {
+        let dir = clean(WAL_DIR);
+        let mut seed: u64 = 0x5eed;
+        let mut random = move |bound: u64| {
+            seed = seed
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (seed >> 33) % bound
+        };
+        let mut expected = BTreeMap::new();
+        for _ in 0..20 {
+            let (mut wal, mut db) = open(&dir).unwrap();
+            let mut keys = Vec::new();
+            let (mut key, mut value) = (Vec::new(), Vec::new());
+            let mut iter = db.new_iter().unwrap();
+            iter.seek_to_first();
+            while iter.current(&mut key, &mut value) {
+                keys.push((key.clone(), value.clone()));
+                if !iter.advance() {
+                    break;
+                }
+            }
+            assert_eq!(keys, expected.clone().into_iter().collect::<Vec<_>>());
+
+            for _ in 0..random(10) {
+                let key = format!("key-{}", random(8)).into_bytes();
+                if random(3) == 0 {
+                    wal.append(&mut db, vec![(key.as_slice(), None)]).unwrap();
+                    expected.remove(&key);
+                } else {
+                    let value = vec![b'v'; random(64) as usize];
+                    wal.append(&mut db, vec![(key.as_slice(), Some(value.as_slice()))])
+                        .unwrap();
+                    expected.insert(key, value);
+                }
+            }
+            let value = vec![b't'; random(64) as usize];
+            append_torn(
+                &mut wal,
+                b"torn",
+                &value,
+                1 + random(8 + value.len() as u64) as usize,
+            );
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -33,6 +33,10 @@ impl TeaclaveServiceLauncher {
     pub fn new<P: AsRef<Path>>(package_name: &str, config_path: P) -> Result<Self> {
         let config = RuntimeConfig::from_toml(config_path.as_ref())
             .context("Failed to load config file.")?;
+        Self::with_config(package_name, config)
+    }
+
+    pub fn with_config(package_name: &str, config: RuntimeConfig) -> Result<Self> {
         let tee = TeeBinder::new(package_name).context("Failed to new the enclave.")?;
         Ok(Self { tee, config })
     }
@@ -51,6 +51,25 @@ pub fn teaclave_service(attr: TokenStream, input: TokenStream) -> TokenStream {
                 trace!("Dispatching request.");
                 self.dispatch(request)
             }
+
+            fn is_stream_request(
+                &self,
+                request: &teaclave_rpc::Request<teaclave_proto::#crate_name_proto::#request>,
+            ) -> bool {
+                use teaclave_proto::#crate_name_proto::#trait_name_ident;
+                self.is_stream(request)
+            }
+
+            fn handle_stream_request(
+                &self,
+                request: teaclave_rpc::Request<teaclave_proto::#crate_name_proto::#request>,
+                sink: &mut dyn FnMut(teaclave_proto::#crate_name_proto::#response) -> std::result::Result<(), teaclave_types::TeaclaveServiceResponseError>,
+            ) -> std::result::Result<(), teaclave_types::TeaclaveServiceResponseError> {
+                use teaclave_proto::#crate_name_proto::#trait_name_ident;
+                use log::trace;
+                trace!("Dispatching stream request.");
+                self.dispatch_stream(request, sink)
+            }
         }
     );
     q.into()
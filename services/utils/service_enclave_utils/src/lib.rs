@@ -60,6 +60,7 @@ impl ServiceEnclave {
             error!("Cannot enable backtrace");
             return Err(teaclave_types::TeeServiceError::SgxError);
         }
+        teaclave_rpc::crash::install_panic_hook();
 
         Ok(())
     }
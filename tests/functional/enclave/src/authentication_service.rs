@@ -24,7 +24,7 @@ use teaclave_proto::teaclave_common::*;
 use teaclave_rpc::config::SgxTrustedTlsClientConfig;
 use teaclave_rpc::endpoint::Endpoint;
 use teaclave_test_utils::test_case;
-use teaclave_types::EnclaveInfo;
+use teaclave_types::{EnclaveInfo, TeaclaveErrorCode};
 
 fn get_api_client() -> TeaclaveAuthenticationApiClient {
     let runtime_config = RuntimeConfig::from_toml("runtime.config.toml").expect("runtime");
@@ -147,5 +147,20 @@ fn test_register_fail() {
     let request = UserRegisterRequest::new("test_register_id2", "test_password");
     let response_result = client.user_register(request);
     debug!("{:?}", response_result);
-    assert!(response_result.is_err());
+    assert_eq!(
+        response_result.unwrap_err().code(),
+        TeaclaveErrorCode::Conflict
+    );
+}
+
+#[test_case]
+fn test_confirm_registration_fail() {
+    let mut client = get_api_client();
+    let request = UserRegisterRequest::new("test_confirm_id1", "test_password");
+    assert!(client.user_register(request).is_ok());
+    // Users registered without verification are not pending.
+    let request = ConfirmRegistrationRequest::new("test_confirm_id1", "00000000");
+    let response_result = client.confirm_registration(request);
+    debug!("{:?}", response_result);
+    assert_eq!(response_result.unwrap_err().code(), TeaclaveErrorCode::Auth);
 }
@@ -18,8 +18,13 @@
 use crate::utils::*;
 use std::convert::TryFrom;
 use std::prelude::v1::*;
+use teaclave_config::RuntimeConfig;
+use teaclave_proto::teaclave_authentication_service::MintScopedTokenRequest;
 use teaclave_proto::teaclave_common::*;
 use teaclave_proto::teaclave_frontend_service::*;
+use teaclave_proto::teaclave_frontend_service_v2::{
+    TeaclaveFrontendV2Client, TeaclaveFrontendV2Request,
+};
 use teaclave_proto::teaclave_scheduler_service::*;
 use teaclave_test_utils::test_case;
 use teaclave_types::*;
@@ -32,6 +37,13 @@ fn authorized_client() -> TeaclaveFrontendClient {
     create_frontend_client(shared_enclave_info(), FRONTEND_SERVICE_ADDR, cred).unwrap()
 }
 
+fn authorized_v2_client() -> TeaclaveFrontendV2Client {
+    let mut api_client =
+        create_authentication_api_client(shared_enclave_info(), AUTH_SERVICE_ADDR).unwrap();
+    let cred = login(&mut api_client, USERNAME, TEST_PASSWORD).unwrap();
+    create_frontend_v2_client(shared_enclave_info(), FRONTEND_SERVICE_ADDR, cred).unwrap()
+}
+
 fn unauthorized_client() -> TeaclaveFrontendClient {
     let cred = UserCredential::new(USERNAME, "InvalidToken");
     create_frontend_client(shared_enclave_info(), FRONTEND_SERVICE_ADDR, cred).unwrap()
@@ -301,6 +313,55 @@ fn test_approve_task() {
     let request = ApproveTaskRequest::new(task_id);
     let response = client.approve_task(request);
     assert!(response.is_ok());
+
+    let receipt = response.unwrap().receipt;
+    assert_eq!(receipt.user_id, UserID::from(USERNAME));
+    assert!(receipt.verify().is_ok());
+}
+
+#[test_case]
+fn test_scoped_token() {
+    let mut client = authorized_client();
+    let function_id =
+        ExternalID::try_from("function-00000000-0000-0000-0000-000000000002").unwrap();
+    let request = CreateTaskRequest::new()
+        .function_id(function_id.clone())
+        .function_arguments(hashmap!("arg1" => "arg1_value"))
+        .executor(Executor::MesaPy)
+        .outputs_ownership(hashmap!("output" => vec!["frontend_user"]));
+    let task_id = client.create_task(request).unwrap().task_id;
+    let request = CreateTaskRequest::new()
+        .function_id(function_id)
+        .function_arguments(hashmap!("arg1" => "arg1_value"))
+        .executor(Executor::MesaPy)
+        .outputs_ownership(hashmap!("output" => vec!["frontend_user"]));
+    let other_task_id = client.create_task(request).unwrap().task_id;
+
+    let mut api_client =
+        create_authentication_api_client(shared_enclave_info(), AUTH_SERVICE_ADDR).unwrap();
+    let cred = login(&mut api_client, USERNAME, TEST_PASSWORD).unwrap();
+    let scope = TokenScope::new(vec!["get_task".to_string()], vec![task_id.to_string()]);
+    let request = MintScopedTokenRequest::new(USERNAME, &cred.token, scope, 60);
+    let token = api_client.mint_scoped_token(request).unwrap().token;
+    let scoped_cred = UserCredential::new(USERNAME, &token);
+    let mut scoped_client =
+        create_frontend_client(shared_enclave_info(), FRONTEND_SERVICE_ADDR, scoped_cred).unwrap();
+
+    let request = GetTaskRequest::new(task_id.clone());
+    assert!(scoped_client.get_task(request).is_ok());
+
+    // Other resources and operations are out of the scope.
+    let request = GetTaskRequest::new(other_task_id);
+    let response = scoped_client.get_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+    let request = CancelTaskRequest::new(task_id);
+    let response = scoped_client.cancel_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    // A scoped token cannot mint further tokens.
+    let scope = TokenScope::new(vec!["get_task".to_string()], vec![]);
+    let request = MintScopedTokenRequest::new(USERNAME, &token, scope, 60);
+    assert!(api_client.mint_scoped_token(request).is_err());
 }
 
 #[test_case]
@@ -343,8 +404,132 @@ fn test_invoke_task() {
     let response = client.get_task(request).unwrap();
     assert_eq!(response.status, TaskStatus::Staged);
 
-    let request = PullTaskRequest {};
+    let request = PullTaskRequest::new();
     let mut scheduler_client = get_scheduler_client();
     let response = scheduler_client.pull_task(request);
     assert!(response.is_ok());
 }
+
+#[test_case]
+fn test_request_validation() {
+    use teaclave_proto::teaclave_frontend_service_proto as proto;
+
+    let mut client = authorized_client();
+
+    let request = TeaclaveFrontendRequest::GetTask(proto::GetTaskRequest {
+        task_id: "function-00000000-0000-0000-0000-000000000002".to_string(),
+    });
+    let response = client.get_task(request);
+    assert_eq!(
+        response.unwrap_err(),
+        TeaclaveServiceResponseError::ValidationError {
+            field: "task_id".to_string(),
+            reason: "expected prefix task, found function".to_string(),
+        }
+    );
+
+    let request = TeaclaveFrontendRequest::CreateTask(proto::CreateTaskRequest {
+        function_id: "function-00000000-0000-0000-0000-000000000002".to_string(),
+        function_arguments: "{}".to_string(),
+        executor: "mesapy".to_string(),
+        outputs_ownership: vec![proto::OwnerList {
+            data_name: "output".to_string(),
+            uids: vec![],
+        }],
+        ..Default::default()
+    });
+    let response = client.create_task(request);
+    assert_eq!(
+        response.unwrap_err(),
+        TeaclaveServiceResponseError::ValidationError {
+            field: "outputs_ownership[0].uids".to_string(),
+            reason: "must not be empty".to_string(),
+        }
+    );
+
+    let request = TeaclaveFrontendRequest::RegisterOutputFile(proto::RegisterOutputFileRequest {
+        url: "ftp://localhost/output".to_string(),
+        crypto_info: None,
+    });
+    let response = client.register_output_file(request);
+    match response.unwrap_err() {
+        TeaclaveServiceResponseError::ValidationError { field, .. } => assert_eq!(field, "url"),
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
+
+#[test_case]
+fn test_api_v2() {
+    use teaclave_proto::teaclave_frontend_service_proto::OwnerList;
+    use teaclave_proto::teaclave_frontend_service_v2_proto as proto;
+    use teaclave_proto::teaclave_frontend_service_v2_proto::function_argument::Value;
+
+    let mut client = authorized_v2_client();
+    let argument = |value| proto::FunctionArgument { value: Some(value) };
+
+    let request = TeaclaveFrontendV2Request::CreateTask(proto::CreateTaskRequest {
+        function_id: "function-00000000-0000-0000-0000-000000000002".to_string(),
+        function_arguments: hashmap!(
+            "arg1" => argument(Value::StringValue("arg1_value".to_string())),
+            "arg2" => argument(Value::IntValue(2))
+        ),
+        executor: "mesapy".to_string(),
+        outputs_ownership: vec![OwnerList {
+            data_name: "output".to_string(),
+            uids: vec!["frontend_user".to_string()],
+        }],
+        ..Default::default()
+    });
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    // tasks are shared by both versions
+    let request = GetTaskRequest::new(task_id.clone());
+    let response = authorized_client().get_task(request).unwrap();
+    assert_eq!(
+        response.function_arguments.get("arg2").unwrap().as_i64(),
+        Some(2)
+    );
+
+    let request = GetTaskRequest::new(task_id);
+    let response = client.get_task(request).unwrap();
+    assert_eq!(response.status, TaskStatus::Created);
+    assert_eq!(
+        response.function_arguments.get("arg1").unwrap().as_str(),
+        Some("arg1_value")
+    );
+
+    let request = TeaclaveFrontendV2Request::CreateTask(proto::CreateTaskRequest {
+        function_id: "function-00000000-0000-0000-0000-000000000002".to_string(),
+        function_arguments: hashmap!("arg1" => argument(Value::JsonValue("[1,".to_string()))),
+        executor: "mesapy".to_string(),
+        ..Default::default()
+    });
+    match client.create_task(request).unwrap_err() {
+        TeaclaveServiceResponseError::ValidationError { field, .. } => {
+            assert_eq!(field, "function_arguments.arg1")
+        }
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
+
+#[test_case]
+fn test_get_platform_info() {
+    // the enclave info is public, so an invalid credential does not matter
+    let response = unauthorized_client()
+        .get_platform_info(GetPlatformInfoRequest::new())
+        .unwrap();
+
+    let runtime_config = RuntimeConfig::from_toml(CONFIG_FILE).unwrap();
+    assert_eq!(
+        response.enclave_info,
+        runtime_config.audit.enclave_info_bytes
+    );
+    assert_eq!(
+        response.auditor_signatures,
+        runtime_config.audit.auditor_signatures_bytes
+    );
+    let enclave_info = EnclaveInfo::from_bytes(&response.enclave_info);
+    assert!(enclave_info
+        .get_enclave_attr("teaclave_frontend_service")
+        .is_some());
+}
@@ -20,9 +20,11 @@ use std::convert::TryFrom;
 use std::prelude::v1::*;
 use teaclave_proto::teaclave_management_service::*;
 use teaclave_proto::teaclave_scheduler_service::*;
+use teaclave_proto::teaclave_storage_service::*;
 use teaclave_test_utils::test_case;
 use teaclave_types::*;
 use url::Url;
+use uuid::Uuid;
 
 fn authorized_client(user_id: &str) -> TeaclaveManagementClient {
     get_management_client(user_id)
@@ -123,6 +125,118 @@ fn test_get_input_file() {
     assert!(response.is_err());
 }
 
+#[test_case]
+fn test_rotate_input_file_key() {
+    let url = Url::parse("https://external-storage.com/filepath?presigned_token").unwrap();
+    let cmac = FileAuthTag::mock();
+
+    let mut client = authorized_client("mock_user");
+    let request = RegisterInputFileRequest::new(url.clone(), cmac, FileCrypto::default());
+    let data_id = client.register_input_file(request).unwrap().data_id;
+
+    // The re-encrypted file cannot overwrite the old one.
+    let new_cmac = FileAuthTag::from_bytes(&[0x12u8; 16]).unwrap();
+    let crypto_info = FileCrypto::new("aes-gcm-128", &[0x90u8; 16], &[0x89u8; 12]).unwrap();
+    let request = RotateInputFileKeyRequest::new(data_id.clone(), url, new_cmac, crypto_info);
+    let response = client.rotate_input_file_key(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Validation);
+
+    let new_url = Url::parse("https://external-storage.com/filepath-v2?presigned_token").unwrap();
+    let request =
+        RotateInputFileKeyRequest::new(data_id.clone(), new_url.clone(), new_cmac, crypto_info);
+    let response = client.rotate_input_file_key(request).unwrap();
+    assert_eq!(response.data_id, data_id);
+    assert!(response.in_flight_task_ids.is_empty());
+
+    let request = GetInputFileRequest::new(data_id.clone());
+    let response = client.get_input_file(request).unwrap();
+    assert_eq!(response.cmac, new_cmac);
+
+    let request = RotateInputFileKeyRequest::new(data_id, new_url, new_cmac, crypto_info);
+    let response = authorized_client("mock_another_user").rotate_input_file_key(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+}
+
+#[test_case]
+fn test_delete_files() {
+    let url = Url::parse("https://external-storage.com/filepath?presigned_token").unwrap();
+    let mut client = authorized_client("mock_user_delete");
+    let request =
+        RegisterInputFileRequest::new(url.clone(), FileAuthTag::mock(), FileCrypto::default());
+    let input_id = client.register_input_file(request).unwrap().data_id;
+    let request = RegisterOutputFileRequest::new(url.clone(), FileCrypto::default());
+    let output_id = client.register_output_file(request).unwrap().data_id;
+
+    let request = DeleteInputFileRequest::new(input_id.clone());
+    let response = authorized_client("mock_another_user").delete_input_file(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let request = DeleteInputFileRequest::new(input_id.clone());
+    client.delete_input_file(request).unwrap();
+    let request = DeleteOutputFileRequest::new(output_id.clone());
+    client.delete_output_file(request).unwrap();
+
+    // Deleted files are gone for their owners as well.
+    let request = GetInputFileRequest::new(input_id.clone());
+    assert!(client.get_input_file(request).is_err());
+    let request = GetOutputFileRequest::new(output_id);
+    assert!(client.get_output_file(request).is_err());
+    let response = client.list_files(ListFilesRequest::input()).unwrap();
+    assert!(response.data_ids.is_empty());
+    let response = client.list_files(ListFilesRequest::output()).unwrap();
+    assert!(response.data_ids.is_empty());
+    let request = DeleteInputFileRequest::new(input_id);
+    assert!(client.delete_input_file(request).is_err());
+
+    // Files assigned to an unfinished task cannot be deleted.
+    let mut client1 = authorized_client("mock_user1");
+    let request =
+        RegisterInputFileRequest::new(url.clone(), FileAuthTag::mock(), FileCrypto::default());
+    let input_id = client1.register_input_file(request).unwrap().data_id;
+    let request = RegisterOutputFileRequest::new(url, FileCrypto::default());
+    let output_id = client1.register_output_file(request).unwrap().data_id;
+    let request = create_valid_task_request();
+    let task_id = authorized_client("mock_user")
+        .create_task(request)
+        .unwrap()
+        .task_id;
+    let request = AssignDataRequest::new(
+        task_id,
+        hashmap!("input" => input_id.clone()),
+        hashmap!("output" => output_id.clone()),
+    );
+    client1.assign_data(request).unwrap();
+
+    let request = DeleteInputFileRequest::new(input_id);
+    let response = client1.delete_input_file(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+    let request = DeleteOutputFileRequest::new(output_id);
+    let response = client1.delete_output_file(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+}
+
+#[test_case]
+fn test_register_brokered_input_file() {
+    let url = Url::parse("https://external-storage.com/filepath?presigned_token").unwrap();
+    let cmac = FileAuthTag::mock();
+    let key_broker = KeyBrokerReference::new("localhost:7700", "mock_key");
+
+    let mut client = authorized_client("mock_user");
+    let request = RegisterInputFileRequest::with_key_broker(url, cmac, key_broker);
+    let data_id = client.register_input_file(request).unwrap().data_id;
+
+    let request = GetInputFileRequest::new(data_id.clone());
+    let response = client.get_input_file(request).unwrap();
+    assert_eq!(response.cmac, cmac);
+
+    // Teaclave holds no key of the file to rotate.
+    let new_url = Url::parse("https://external-storage.com/filepath-v2?presigned_token").unwrap();
+    let crypto_info = FileCrypto::new("aes-gcm-128", &[0x90u8; 16], &[0x89u8; 12]).unwrap();
+    let request = RotateInputFileKeyRequest::new(data_id, new_url, cmac, crypto_info);
+    let response = client.rotate_input_file_key(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Validation);
+}
+
 #[test_case]
 fn test_register_function() {
     let function_input = FunctionInput::new("input", "input_desc");
@@ -227,6 +341,141 @@ fn test_create_task() {
     assert!(response.is_err());
 }
 
+#[test_case]
+fn test_create_task_exceeding_limits() {
+    let mut client = authorized_client("mock_user");
+
+    let owners: Vec<String> = (0..9).map(|i| format!("mock_owner{}", i)).collect();
+    let request = create_valid_task_request().inputs_ownership(hashmap!(
+        "input" => vec!["mock_user1"],
+        "input2" => owners
+    ));
+    let response = client.create_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Validation);
+
+    let owners1: Vec<String> = (0..8).map(|i| format!("mock_owner{}", i)).collect();
+    let owners2: Vec<String> = (8..16).map(|i| format!("mock_owner{}", i)).collect();
+    let request = create_valid_task_request().inputs_ownership(hashmap!(
+        "input" => owners1,
+        "input2" => owners2
+    ));
+    let response = client.create_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Validation);
+}
+
+#[test_case]
+fn test_create_task_with_entry_point() {
+    let mut client = authorized_client("mock_user");
+    let request = RegisterFunctionRequest::new()
+        .name("mock_function")
+        .executor_type(ExecutorType::Python)
+        .payload(b"def train(argv):\n\treturn\ndef predict(argv):\n\treturn".to_vec())
+        .public(true)
+        .entry_points(vec!["train", "predict"]);
+    let function_id = client.register_function(request).unwrap().function_id;
+
+    let request = CreateTaskRequest::new()
+        .function_id(function_id.clone())
+        .executor(Executor::MesaPy)
+        .entry_point("evaluate");
+    let response = client.create_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    let request = CreateTaskRequest::new()
+        .function_id(function_id)
+        .executor(Executor::MesaPy)
+        .entry_point("predict");
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    let request = GetTaskRequest::new(task_id);
+    let response = client.get_task(request).unwrap();
+    assert_eq!(response.entry_point, Some("predict".to_string()));
+}
+
+#[test_case]
+fn test_function_capabilities() {
+    let mut client = authorized_client("mock_user");
+    let capabilities = FunctionCapabilities::new()
+        .egress(true)
+        .max_output_size(1024);
+    let request = RegisterFunctionRequest::new()
+        .name("mock_function")
+        .executor_type(ExecutorType::Python)
+        .payload(b"def entrypoint(argv):\n\treturn".to_vec())
+        .public(true)
+        .capabilities(capabilities);
+    let function_id = client.register_function(request).unwrap().function_id;
+
+    let request = GetFunctionRequest::new(function_id.clone());
+    let response = client.get_function(request).unwrap();
+    assert_eq!(response.capabilities, Some(capabilities));
+
+    // Capabilities of the function are shown to the approvers of the task.
+    let request = CreateTaskRequest::new()
+        .function_id(function_id)
+        .executor(Executor::MesaPy);
+    let task_id = client.create_task(request).unwrap().task_id;
+    let request = GetTaskRequest::new(task_id);
+    let response = client.get_task(request).unwrap();
+    assert_eq!(response.function_capabilities, Some(capabilities));
+}
+
+#[test_case]
+fn test_function_post_processing() {
+    let mut client = authorized_client("mock_user");
+    let post_processing = vec![
+        PostProcessingStep::round(2),
+        PostProcessingStep::top_k(10),
+        PostProcessingStep::validate_schema(serde_json::json!({"type": "array"})),
+    ];
+    let request = RegisterFunctionRequest::new()
+        .name("mock_function")
+        .executor_type(ExecutorType::Python)
+        .payload(b"def entrypoint(argv):\n\treturn".to_vec())
+        .public(true)
+        .post_processing(post_processing.clone());
+    let function_id = client.register_function(request).unwrap().function_id;
+
+    let request = GetFunctionRequest::new(function_id.clone());
+    let response = client.get_function(request).unwrap();
+    assert_eq!(response.post_processing, post_processing);
+
+    // The steps are shown to the approvers of the task.
+    let request = CreateTaskRequest::new()
+        .function_id(function_id)
+        .executor(Executor::MesaPy);
+    let task_id = client.create_task(request).unwrap().task_id;
+    let request = GetTaskRequest::new(task_id);
+    let response = client.get_task(request).unwrap();
+    assert_eq!(response.function_post_processing, post_processing);
+}
+
+#[test_case]
+fn test_function_worker_measurements() {
+    let mut client = authorized_client("mock_user");
+    let measurement = "0".repeat(64);
+    let request = RegisterFunctionRequest::new()
+        .name("mock_function")
+        .executor_type(ExecutorType::Python)
+        .payload(b"def entrypoint(argv):\n\treturn".to_vec())
+        .public(true)
+        .worker_measurements(vec![&measurement]);
+    let function_id = client.register_function(request).unwrap().function_id;
+
+    let request = GetFunctionRequest::new(function_id.clone());
+    let response = client.get_function(request).unwrap();
+    assert_eq!(response.worker_measurements, vec![measurement.clone()]);
+
+    // The pinned measurements are shown to the approvers of the task.
+    let request = CreateTaskRequest::new()
+        .function_id(function_id)
+        .executor(Executor::MesaPy);
+    let task_id = client.create_task(request).unwrap().task_id;
+    let request = GetTaskRequest::new(task_id);
+    let response = client.get_task(request).unwrap();
+    assert_eq!(response.worker_measurements, vec![measurement]);
+}
+
 #[test_case]
 fn test_get_task() {
     let mut client = authorized_client("mock_user");
@@ -245,6 +494,189 @@ fn test_get_task() {
     }
 }
 
+#[test_case]
+fn test_list_tasks() {
+    let mut client = authorized_client("mock_user");
+
+    let labels = hashmap!(
+        "project" => "list_tasks",
+        "experiment" => "exp-1"
+    );
+    let request = create_valid_task_request().labels(labels.clone());
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    let request = GetTaskRequest::new(task_id.clone());
+    let response = client.get_task(request).unwrap();
+    assert_eq!(response.labels, labels);
+
+    let request = ListTasksRequest::new().labels(hashmap!("experiment" => "exp-1"));
+    let response = client.list_tasks(request).unwrap();
+    assert!(response.task_ids.contains(&task_id));
+
+    let request = ListTasksRequest::new().labels(hashmap!("experiment" => "exp-2"));
+    let response = client.list_tasks(request).unwrap();
+    assert!(!response.task_ids.contains(&task_id));
+
+    // participants can list the task as well
+    let request = ListTasksRequest::new();
+    let response = authorized_client("mock_user2").list_tasks(request).unwrap();
+    assert!(response.task_ids.contains(&task_id));
+
+    let request = ListTasksRequest::new();
+    let response = authorized_client("mock_user_c")
+        .list_tasks(request)
+        .unwrap();
+    assert!(!response.task_ids.contains(&task_id));
+}
+
+#[test_case]
+fn test_list_tasks_with_filter() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+    let function_id =
+        ExternalID::try_from("function-00000000-0000-0000-0000-000000000001").unwrap();
+    let other_function_id =
+        ExternalID::try_from("function-00000000-0000-0000-0000-000000000002").unwrap();
+
+    let mut list = |filter: TaskFilter| {
+        let request = ListTasksRequest::new().filter(filter);
+        client.list_tasks(request).unwrap().task_ids
+    };
+    let filter = TaskFilter::new()
+        .statuses(vec![TaskStatus::Created])
+        .function_id(function_id)
+        .participant(UserID::from("mock_user2"));
+    assert!(list(filter).contains(&task_id));
+
+    let filter = TaskFilter::new().statuses(vec![TaskStatus::Finished]);
+    assert!(!list(filter).contains(&task_id));
+    let filter = TaskFilter::new().function_id(other_function_id);
+    assert!(!list(filter).contains(&task_id));
+    let filter = TaskFilter::new().participant(UserID::from("mock_user_c"));
+    assert!(!list(filter).contains(&task_id));
+
+    // the task is created within the last day
+    let now = TrustedTime::now_secs();
+    let filter = TaskFilter::new().created_between(Some(now - 86400), Some(now + 60));
+    assert!(list(filter).contains(&task_id));
+    let filter = TaskFilter::new().created_between(None, Some(now - 86400));
+    assert!(!list(filter).contains(&task_id));
+}
+
+#[test_case]
+fn test_list_tasks_in_pages() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request();
+    let first_task_id = client.create_task(request).unwrap().task_id;
+    let request = create_valid_task_request();
+    let second_task_id = client.create_task(request).unwrap().task_id;
+
+    let mut task_ids = Vec::new();
+    let mut page = PageRequest::new().size(1);
+    loop {
+        let request = ListTasksRequest::new().page(page.clone());
+        let response = client.list_tasks(request).unwrap();
+        assert!(response.task_ids.len() <= 1);
+        task_ids.extend(response.task_ids);
+        match page.next(&response.page) {
+            Some(next_page) => page = next_page,
+            None => break,
+        }
+    }
+    assert!(task_ids.contains(&first_task_id));
+    assert!(task_ids.contains(&second_task_id));
+}
+
+#[test_case]
+fn test_list_functions_and_files_in_pages() {
+    let mut client = authorized_client("mock_user_list");
+    let mut function_ids = Vec::new();
+    for name in &["first_function", "second_function"] {
+        let request = RegisterFunctionRequest::new()
+            .name(*name)
+            .executor_type(ExecutorType::Python)
+            .payload(b"def entrypoint:\n\treturn".to_vec());
+        function_ids.push(client.register_function(request).unwrap().function_id);
+    }
+    let url = Url::parse("https://external-storage.com/filepath?presigned_token").unwrap();
+    let request = RegisterInputFileRequest::new(url, FileAuthTag::mock(), FileCrypto::default());
+    let data_id = client.register_input_file(request).unwrap().data_id;
+
+    let mut listed_ids = Vec::new();
+    let mut page = PageRequest::new().size(1);
+    loop {
+        let request = ListFunctionsRequest::new().page(page.clone());
+        let response = client.list_functions(request).unwrap();
+        assert!(response.function_ids.len() <= 1);
+        listed_ids.extend(response.function_ids);
+        match page.next(&response.page) {
+            Some(next_page) => page = next_page,
+            None => break,
+        }
+    }
+    assert_eq!(listed_ids, {
+        function_ids.sort_by_key(|id| id.to_string());
+        function_ids
+    });
+
+    let response = client.list_files(ListFilesRequest::input()).unwrap();
+    assert_eq!(response.data_ids, vec![data_id.clone()]);
+    let response = client.list_files(ListFilesRequest::output()).unwrap();
+    assert!(response.data_ids.is_empty());
+
+    let response = authorized_client("mock_user")
+        .list_files(ListFilesRequest::input())
+        .unwrap();
+    assert!(!response.data_ids.contains(&data_id));
+}
+
+#[test_case]
+fn test_list_pending_approvals() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    let request = ListPendingApprovalsRequest::new();
+    let response = authorized_client("mock_user2")
+        .list_pending_approvals(request)
+        .unwrap();
+    assert!(response.task_ids.contains(&task_id));
+
+    let request = ListPendingApprovalsRequest::new();
+    let response = authorized_client("mock_user_c")
+        .list_pending_approvals(request)
+        .unwrap();
+    assert!(!response.task_ids.contains(&task_id));
+}
+
+#[test_case]
+fn test_create_task_notification() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    let mut storage_client = get_storage_client();
+    let queue_key = TaskNotification::get_queue_key(&UserID::from("mock_user2"));
+    // notifications of tasks created by other tests may be queued ahead
+    let notification = loop {
+        let request = DequeueRequest::new(queue_key.as_bytes());
+        let response = storage_client.dequeue(request).unwrap();
+        let notification = TaskNotification::from_slice(&response.value).unwrap();
+        if notification.task_id == task_id.uuid {
+            break notification;
+        }
+    };
+    assert_eq!(notification.creator, UserID::from("mock_user"));
+    assert_eq!(notification.requested_inputs, vec!["input2".to_string()]);
+    assert_eq!(notification.requested_outputs, vec!["output2".to_string()]);
+
+    // the creator is not notified
+    let queue_key = TaskNotification::get_queue_key(&UserID::from("mock_user"));
+    let request = DequeueRequest::new(queue_key.as_bytes());
+    assert!(storage_client.dequeue(request).is_err());
+}
+
 #[test_case]
 fn test_assign_data() {
     let mut client = authorized_client("mock_user");
@@ -587,8 +1019,959 @@ fn test_invoke_task() {
     let response = client2.get_task(request).unwrap();
     assert_eq!(response.status, TaskStatus::Staged);
 
-    let request = PullTaskRequest {};
+    let request = PullTaskRequest::new();
     let mut scheduler_client = get_scheduler_client();
     let response = scheduler_client.pull_task(request);
     assert!(response.is_ok());
 }
+
+#[test_case]
+fn test_invoke_task_with_deferred_output() {
+    let mut client = authorized_client("mock_user");
+    let mut client1 = authorized_client("mock_user1");
+    let mut client2 = authorized_client("mock_user2");
+    let mut client3 = authorized_client("mock_user3");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    let url = Url::parse("input://path").unwrap();
+    let request = RegisterInputFileRequest::new(url, FileAuthTag::mock(), FileCrypto::default());
+    let input_file_id_user1 = client1.register_input_file(request).unwrap().data_id;
+
+    // the destination of the output is not provisioned yet
+    let request = RegisterOutputFileRequest::deferred(FileCrypto::default());
+    let output_file_id_user1 = client1.register_output_file(request).unwrap().data_id;
+
+    let request = AssignDataRequest::new(
+        task_id.clone(),
+        hashmap!("input" => input_file_id_user1),
+        hashmap!("output" => output_file_id_user1.clone()),
+    );
+    client1.assign_data(request).unwrap();
+
+    let input_file_id_user2 =
+        ExternalID::try_from("input-00000000-0000-0000-0000-000000000002").unwrap();
+    let request = AssignDataRequest::new(
+        task_id.clone(),
+        hashmap!("input2" => input_file_id_user2),
+        hashmap!(),
+    );
+    client2.assign_data(request).unwrap();
+
+    let request = RegisterFusionOutputRequest::new(vec!["mock_user2", "mock_user3"]);
+    let fusion_output = client3.register_fusion_output(request).unwrap().data_id;
+    let request = AssignDataRequest::new(
+        task_id.clone(),
+        hashmap!(),
+        hashmap!("output2" => fusion_output),
+    );
+    client3.assign_data(request).unwrap();
+
+    let request = ApproveTaskRequest::new(task_id.clone());
+    client.approve_task(request).unwrap();
+    let request = ApproveTaskRequest::new(task_id.clone());
+    client1.approve_task(request).unwrap();
+    let request = ApproveTaskRequest::new(task_id.clone());
+    client2.approve_task(request).unwrap();
+    let request = ApproveTaskRequest::new(task_id.clone());
+    client3.approve_task(request).unwrap();
+
+    // the url of the output is not set
+    let request = InvokeTaskRequest::new(task_id.clone());
+    let response = client.invoke_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    // only the owner sets the url
+    let url = Url::parse("https://output_file_path").unwrap();
+    let request = UpdateOutputUrlRequest::new(output_file_id_user1.clone(), url.clone());
+    let response = client2.update_output_url(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let request = UpdateOutputUrlRequest::new(output_file_id_user1.clone(), url.clone());
+    client1.update_output_url(request).unwrap();
+
+    // the url is only set once
+    let request = UpdateOutputUrlRequest::new(output_file_id_user1, url);
+    let response = client1.update_output_url(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Validation);
+
+    let request = InvokeTaskRequest::new(task_id.clone());
+    client.invoke_task(request).unwrap();
+
+    let request = GetTaskRequest::new(task_id);
+    let response = client.get_task(request).unwrap();
+    assert_eq!(response.status, TaskStatus::Staged);
+}
+
+#[test_case]
+fn test_get_task_manifest() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    // The manifest is only generated after the task finished.
+    let request = GetTaskManifestRequest::new(task_id.clone());
+    let response = client.get_task_manifest(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::NotFound);
+
+    let request = GetTaskManifestRequest::new(task_id);
+    let response = authorized_client("mock_user_c").get_task_manifest(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+}
+
+#[test_case]
+fn test_get_task_return_value() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    // The return value can only be fetched after the task finished.
+    let request = GetTaskReturnValueRequest::new(task_id.clone()).page(0, 16);
+    let response = client.get_task_return_value(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    let request = GetTaskReturnValueRequest::new(task_id);
+    let response = authorized_client("mock_user_c").get_task_return_value(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+}
+
+#[test_case]
+fn test_get_data_lineage() {
+    // Data registered by users are not produced by any task.
+    let data_id = ExternalID::try_from("output-00000000-0000-0000-0000-000000000001").unwrap();
+    let request = GetDataLineageRequest::new(data_id.clone());
+    let response = authorized_client("mock_user1")
+        .get_data_lineage(request)
+        .unwrap();
+    assert!(response.steps.is_empty());
+
+    let request = GetDataLineageRequest::new(data_id);
+    let response = authorized_client("mock_user_c").get_data_lineage(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+}
+
+#[test_case]
+fn test_approve_task_by_policies() {
+    let mut client = authorized_client("mock_user");
+    let mut client1 = authorized_client("mock_user1");
+    let mut client2 = authorized_client("mock_user2");
+    let mut client3 = authorized_client("mock_user3");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+    let function_id =
+        ExternalID::try_from("function-00000000-0000-0000-0000-000000000001").unwrap();
+
+    let url = Url::parse("input://path").unwrap();
+    let request = RegisterInputFileRequest::new(url, FileAuthTag::mock(), FileCrypto::default());
+    let input_file_id = client1.register_input_file(request).unwrap().data_id;
+    let url = Url::parse("https://output_file_path").unwrap();
+    let request = RegisterOutputFileRequest::new(url, FileCrypto::default());
+    let output_file_id = client1.register_output_file(request).unwrap().data_id;
+
+    // only owners of the data can register policies
+    let request = RegisterApprovalPolicyRequest::new(
+        input_file_id.clone(),
+        vec![function_id.clone()],
+        vec!["mock_user"],
+    );
+    let response = client2.register_approval_policy(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let mut policy_ids = Vec::new();
+    for data_id in vec![input_file_id.clone(), output_file_id.clone()] {
+        let request = RegisterApprovalPolicyRequest::new(
+            data_id,
+            vec![function_id.clone()],
+            vec!["mock_user"],
+        );
+        let response = client1.register_approval_policy(request).unwrap();
+        policy_ids.push(response.policy_id);
+    }
+
+    let request = AssignDataRequest::new(
+        task_id.clone(),
+        hashmap!("input" => input_file_id),
+        hashmap!("output" => output_file_id),
+    );
+    client1.assign_data(request).unwrap();
+    let input_file_id_user2 =
+        ExternalID::try_from("input-00000000-0000-0000-0000-000000000002").unwrap();
+    let request = AssignDataRequest::new(
+        task_id.clone(),
+        hashmap!("input2" => input_file_id_user2),
+        hashmap!(),
+    );
+    client2.assign_data(request).unwrap();
+    let request = RegisterFusionOutputRequest::new(vec!["mock_user2", "mock_user3"]);
+    let fusion_output = client3.register_fusion_output(request).unwrap().data_id;
+    let request = AssignDataRequest::new(
+        task_id.clone(),
+        hashmap!(),
+        hashmap!("output2" => fusion_output),
+    );
+    client3.assign_data(request).unwrap();
+
+    // mock_user1 approved by the policies once all data are assigned
+    let request = GetTaskRequest::new(task_id);
+    let response = client.get_task(request).unwrap();
+    assert!(response
+        .approved_users
+        .contains(&UserID::from("mock_user1")));
+    assert!(!response
+        .approved_users
+        .contains(&UserID::from("mock_user2")));
+    assert_eq!(response.approval_receipts.len(), 1);
+
+    for policy_id in policy_ids {
+        let request = RevokeApprovalPolicyRequest::new(policy_id);
+        client1.revoke_approval_policy(request).unwrap();
+        let request = RevokeApprovalPolicyRequest::new(policy_id);
+        let response = client1.revoke_approval_policy(request);
+        assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::NotFound);
+    }
+}
+
+#[test_case]
+fn test_register_model() {
+    let mut client = authorized_client("mock_user");
+    let mut client1 = authorized_client("mock_user1");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+    let output_file_id =
+        ExternalID::try_from("output-00000000-0000-0000-0000-000000000001").unwrap();
+
+    // only the owner of the output registers it as a model
+    let request = RegisterModelRequest::new("fraud", task_id.clone(), output_file_id.clone());
+    let response = client.register_model(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    // the task producing the model has not finished
+    let request = RegisterModelRequest::new("fraud", task_id.clone(), output_file_id);
+    let response = client1.register_model(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    let request = GetModelRequest::new("fraud", None);
+    let response = client1.get_model(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::NotFound);
+
+    let request = AssignDataRequest::new(task_id, hashmap!(), hashmap!())
+        .input_models(hashmap!("input" => ModelReference::new("fraud", Some(1))));
+    let response = client1.assign_data(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::NotFound);
+}
+
+#[test_case]
+fn test_task_share_link() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    // only the creator shares the task
+    let request = CreateTaskShareLinkRequest::new(task_id.clone(), 3600);
+    let response = authorized_client("mock_user1").create_task_share_link(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let request = CreateTaskShareLinkRequest::new(task_id.clone(), 3600);
+    let token = client.create_task_share_link(request).unwrap().token;
+
+    // observers are not participants of the task
+    let mut observer = authorized_client("observer");
+    let response = observer
+        .get_shared_task(GetSharedTaskRequest::new(&token))
+        .unwrap();
+    assert_eq!(response.task_id, task_id);
+    assert_eq!(response.progress.status, TaskStatus::Created);
+    assert_eq!(response.progress.assigned_inputs, 0);
+    assert_eq!(response.progress.expected_inputs, 2);
+
+    let response = observer.get_shared_task(GetSharedTaskRequest::new("invalid-token"));
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+}
+
+#[test_case]
+fn test_task_key_exchange() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    // keys of the task are registered along with the files
+    let request = GetTaskKeyOfferRequest::new(task_id);
+    let response = client.get_task_key_offer(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    let request = create_valid_task_request().key_exchange(true);
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    // no offer until the task is running
+    let request = GetTaskKeyOfferRequest::new(task_id.clone());
+    let response = client.get_task_key_offer(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::NotFound);
+
+    let request = GetTaskKeyOfferRequest::new(task_id.clone());
+    let response = authorized_client("mock_user_c").get_task_key_offer(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    // only owners hand the keys of the files
+    let keys = WrappedTaskKeys {
+        offer_public_key: vec![4; 65],
+        public_key: vec![4; 65],
+        keys: hashmap!("input" => vec![0; 32]),
+    };
+    let request = PutTaskKeysRequest::new(task_id.clone(), keys.clone());
+    let response = authorized_client("mock_user2").put_task_keys(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let request = PutTaskKeysRequest::new(task_id, keys);
+    let response = authorized_client("mock_user1").put_task_keys(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+}
+
+#[test_case]
+fn test_archive_task() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    // only admins archive and restore tasks
+    let request = ArchiveTaskRequest::new(task_id.clone());
+    let response = client.archive_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let request = RestoreArchivedTaskRequest::new(task_id.clone());
+    let response = client.restore_archived_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    // the task is left in place
+    let request = GetTaskRequest::new(task_id);
+    assert!(client.get_task(request).is_ok());
+}
+
+#[test_case]
+fn test_storage_snapshot() {
+    let mut client = authorized_client("mock_user");
+
+    // only admins export and restore snapshots of the storage
+    let request = ExportStorageSnapshotRequest::new("functional_test");
+    let response = client.export_storage_snapshot(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let request = RestoreStorageSnapshotRequest::new(vec![]);
+    let response = client.restore_storage_snapshot(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+}
+
+#[test_case]
+fn test_promote_storage_replica() {
+    let mut client = authorized_client("mock_user");
+
+    // only admins promote replicas of the storage
+    let request = PromoteStorageReplicaRequest::new("replica-1");
+    let response = client.promote_storage_replica(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+}
+
+#[test_case]
+fn test_cancel_task() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    // only the creator cancels the task
+    let request = CancelTaskRequest::new(task_id.clone());
+    let response = authorized_client("mock_user1").cancel_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let request = CancelTaskRequest::new(task_id.clone());
+    assert!(client.cancel_task(request).is_ok());
+
+    let request = GetTaskRequest::new(task_id.clone());
+    let response = client.get_task(request).unwrap();
+    assert_eq!(response.status, TaskStatus::Canceled);
+    assert!(matches!(response.result, TaskResult::Err(_)));
+
+    // canceled tasks stay canceled
+    let request = CancelTaskRequest::new(task_id);
+    let response = client.cancel_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+}
+
+#[test_case]
+fn test_get_task_upload_slots() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    let request = GetTaskUploadSlotsRequest::new(task_id.clone());
+    let response = authorized_client("mock_user_c").get_task_upload_slots(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    // no slots until the task is running
+    let request = GetTaskUploadSlotsRequest::new(task_id);
+    let response = client.get_task_upload_slots(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+}
+
+#[test_case]
+fn test_expire_task() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request().expires_in(1);
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    let request = GetTaskRequest::new(task_id.clone());
+    let response = client.get_task(request).unwrap();
+    assert!(response.expires_at.is_some());
+    assert_eq!(response.status, TaskStatus::Created);
+
+    std::thread::sleep(std::time::Duration::from_secs(2));
+
+    // expired tasks are not assigned, approved or invoked
+    let request = AssignDataRequest::new(task_id.clone(), hashmap!(), hashmap!());
+    let response = client.assign_data(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    let request = GetTaskRequest::new(task_id.clone());
+    let response = client.get_task(request).unwrap();
+    assert_eq!(response.status, TaskStatus::Expired);
+    assert!(matches!(response.result, TaskResult::Err(_)));
+
+    let request = InvokeTaskRequest::new(task_id);
+    let response = client.invoke_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+}
+
+#[test_case]
+fn test_reject_task() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    // only the participants reject the task
+    let request = RejectTaskRequest::new(task_id.clone(), "not agreed");
+    let response = authorized_client("mock_user_c").reject_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let request = RejectTaskRequest::new(task_id.clone(), "not agreed");
+    assert!(authorized_client("mock_user1").reject_task(request).is_ok());
+
+    let request = GetTaskRequest::new(task_id.clone());
+    let response = client.get_task(request).unwrap();
+    assert_eq!(response.status, TaskStatus::Rejected);
+    assert_eq!(response.rejected_by, Some(UserID::from("mock_user1")));
+    assert_eq!(response.rejection_reason, "not agreed");
+    assert!(matches!(response.result, TaskResult::Err(_)));
+
+    // rejected tasks stay rejected
+    let request = RejectTaskRequest::new(task_id, "not agreed");
+    let response = authorized_client("mock_user2").reject_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+}
+
+#[test_case]
+fn test_get_activity_feed() {
+    let mut client = authorized_client("mock_activity_user");
+    let url = Url::parse("https://external-storage.com/filepath?presigned_token").unwrap();
+    let request = RegisterInputFileRequest::new(url, FileAuthTag::mock(), FileCrypto::default());
+    let data_id = client.register_input_file(request).unwrap().data_id;
+
+    let request = GetActivityFeedRequest::new(0);
+    let response = client.get_activity_feed(request).unwrap();
+    let event = &response.events[0];
+    assert_eq!(event.kind, ActivityKind::DataRegistered);
+    assert_eq!(event.object_id, data_id);
+
+    // the feed of other users is left untouched
+    let request = GetActivityFeedRequest::new(0);
+    let response = authorized_client("mock_another_user").get_activity_feed(request);
+    assert!(response
+        .unwrap()
+        .events
+        .iter()
+        .all(|event| event.object_id != data_id));
+}
+
+#[test_case]
+fn test_get_task_result_stream() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request();
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    // The result can only be streamed after the task finished.
+    let request = GetTaskResultStreamRequest::new(task_id.clone());
+    let response = client
+        .get_task_result_stream(request)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>();
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    // The client is usable again once the stream ended.
+    let request = GetTaskRequest::new(task_id.clone());
+    assert!(client.get_task(request).is_ok());
+
+    let request = GetTaskResultStreamRequest::new(task_id).output("output");
+    let response = authorized_client("mock_user_c")
+        .get_task_result_stream(request)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>();
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+}
+
+#[test_case]
+fn test_create_task_from_template() {
+    let valid_request = create_valid_task_request();
+    let request = RegisterTaskTemplateRequest::new()
+        .function_id(valid_request.function_id)
+        .function_arguments(hashmap!("arg1" => "data1"))
+        .executor(Executor::MesaPy)
+        .inputs_ownership(valid_request.inputs_ownership)
+        .outputs_ownership(valid_request.outputs_ownership);
+    let response = authorized_client("mock_user").register_task_template(request);
+    let template_id = response.unwrap().template_id;
+
+    // owners of the files can create tasks from the template
+    let mut client = authorized_client("mock_user2");
+    let request = CreateTaskFromTemplateRequest::new(template_id.clone())
+        .function_arguments(hashmap!("arg2" => "data2"));
+    let task_id = client.create_task_from_template(request).unwrap().task_id;
+
+    let response = client.get_task(GetTaskRequest::new(task_id)).unwrap();
+    assert_eq!(response.creator, UserID::from("mock_user2"));
+    assert_eq!(response.function_arguments.get("arg1").unwrap(), "data1");
+    assert_eq!(response.function_arguments.get("arg2").unwrap(), "data2");
+    assert!(response.participants.contains(&UserID::from("mock_user3")));
+
+    // the arguments without defaults are required
+    let request = CreateTaskFromTemplateRequest::new(template_id.clone());
+    let response = client.create_task_from_template(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    let request = CreateTaskFromTemplateRequest::new(template_id)
+        .function_arguments(hashmap!("arg2" => "data2"));
+    let response = authorized_client("mock_another_user").create_task_from_template(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+}
+
+#[test_case]
+fn test_expire_task_approval_window() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request().approval_window(1);
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    let request = GetTaskRequest::new(task_id.clone());
+    let response = client.get_task(request).unwrap();
+    assert!(response.approval_expires_at.is_some());
+
+    let request = ApproveTaskRequest::new(task_id.clone());
+    authorized_client("mock_user1")
+        .approve_task(request)
+        .unwrap();
+
+    std::thread::sleep(std::time::Duration::from_secs(2));
+
+    // the other participants can no longer approve the task
+    let request = ApproveTaskRequest::new(task_id.clone());
+    let response = authorized_client("mock_user2").approve_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    let request = GetTaskRequest::new(task_id);
+    let response = client.get_task(request).unwrap();
+    assert_eq!(response.status, TaskStatus::Expired);
+}
+
+#[test_case]
+fn test_create_tasks_batch() {
+    let mut client = authorized_client("mock_user");
+    let request = CreateTasksBatchRequest::new(vec![
+        create_valid_task_request(),
+        create_valid_task_request(),
+    ]);
+    let response = client.create_tasks_batch(request).unwrap();
+    assert_eq!(response.task_ids.len(), 2);
+    assert_ne!(response.task_ids[0], response.task_ids[1]);
+
+    for task_id in response.task_ids {
+        let response = client.get_task(GetTaskRequest::new(task_id)).unwrap();
+        assert_eq!(response.creator, UserID::from("mock_user"));
+    }
+
+    // no task is created if any of them is invalid
+    let labels = hashmap!("batch" => "invalid-batch");
+    let mut invalid_request = create_valid_task_request();
+    invalid_request
+        .function_arguments
+        .inner_mut()
+        .remove("arg1");
+    let request = CreateTasksBatchRequest::new(vec![
+        create_valid_task_request().labels(labels.clone()),
+        invalid_request.labels(labels.clone()),
+    ]);
+    let response = client.create_tasks_batch(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    let request = ListTasksRequest::new().labels(labels);
+    let response = client.list_tasks(request).unwrap();
+    assert!(response.task_ids.is_empty());
+}
+
+#[test_case]
+fn test_test_function() {
+    let function_input = FunctionInput::new("input", "input_desc");
+    let function_output = FunctionOutput::new("output", "output_desc");
+    let request = RegisterFunctionRequest::new()
+        .name("mock_function")
+        .executor_type(ExecutorType::Python)
+        .payload(b"def entrypoint:\n\treturn".to_vec())
+        .public(true)
+        .arguments(vec!["arg"])
+        .inputs(vec![function_input])
+        .outputs(vec![function_output]);
+    let mut client = authorized_client("mock_user");
+    let function_id = client.register_function(request).unwrap().function_id;
+
+    let request = TestFunctionRequest::new(function_id.clone(), Executor::MesaPy)
+        .function_arguments(hashmap!("arg" => "data"))
+        .sample_input("input", b"sample".to_vec());
+    let task_id = client.test_function(request).unwrap().task_id;
+
+    // the test is only visible to the owner of the function
+    let response = client.get_task(GetTaskRequest::new(task_id)).unwrap();
+    assert_eq!(response.participants.len(), 1);
+    assert!(response.participants.contains(&UserID::from("mock_user")));
+
+    let request = TestFunctionRequest::new(function_id.clone(), Executor::MesaPy)
+        .function_arguments(hashmap!("arg" => "data"))
+        .sample_input("unknown_input", b"sample".to_vec());
+    let response = client.test_function(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    // even public functions are only tested by their owners
+    let request = TestFunctionRequest::new(function_id, Executor::MesaPy)
+        .function_arguments(hashmap!("arg" => "data"));
+    let response = authorized_client("mock_user1").test_function(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+}
+
+#[test_case]
+fn test_task_dependencies() {
+    let mut client = authorized_client("mock_user");
+    let mut client1 = authorized_client("mock_user1");
+    let mut client2 = authorized_client("mock_user2");
+    let mut client3 = authorized_client("mock_user3");
+    let upstream_id = client
+        .create_task(create_valid_task_request())
+        .unwrap()
+        .task_id;
+
+    // the input and the upstream output must have the same owners
+    let request =
+        create_valid_task_request().input_dependency("input", upstream_id.clone(), "output2");
+    let response = client.create_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    // only participants of the upstream task can consume its outputs
+    let request =
+        create_valid_task_request().input_dependency("input", upstream_id.clone(), "output");
+    let response = authorized_client("mock_user4").create_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let request =
+        create_valid_task_request().input_dependency("input", upstream_id.clone(), "output");
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    // the input is assigned with the upstream output instead
+    let url = Url::parse("input://path").unwrap();
+    let request = RegisterInputFileRequest::new(url, FileAuthTag::mock(), FileCrypto::default());
+    let input_file_id_user1 = client1.register_input_file(request).unwrap().data_id;
+    let request = AssignDataRequest::new(
+        task_id.clone(),
+        hashmap!("input" => input_file_id_user1),
+        hashmap!(),
+    );
+    assert!(client1.assign_data(request).is_err());
+
+    let url = Url::parse("https://output_file_path").unwrap();
+    let request = RegisterOutputFileRequest::new(url, FileCrypto::default());
+    let output_file_id_user1 = client1.register_output_file(request).unwrap().data_id;
+    let request = AssignDataRequest::new(
+        task_id.clone(),
+        hashmap!(),
+        hashmap!("output" => output_file_id_user1),
+    );
+    client1.assign_data(request).unwrap();
+
+    let input_file_id_user2 =
+        ExternalID::try_from("input-00000000-0000-0000-0000-000000000002").unwrap();
+    let request = AssignDataRequest::new(
+        task_id.clone(),
+        hashmap!("input2" => input_file_id_user2),
+        hashmap!(),
+    );
+    client2.assign_data(request).unwrap();
+
+    let request = RegisterFusionOutputRequest::new(vec!["mock_user2", "mock_user3"]);
+    let fusion_output = client3.register_fusion_output(request).unwrap().data_id;
+    let request = AssignDataRequest::new(
+        task_id.clone(),
+        hashmap!(),
+        hashmap!("output2" => fusion_output),
+    );
+    client3.assign_data(request).unwrap();
+
+    let request = ApproveTaskRequest::new(task_id.clone());
+    client.approve_task(request).unwrap();
+    let request = ApproveTaskRequest::new(task_id.clone());
+    client1.approve_task(request).unwrap();
+    let request = ApproveTaskRequest::new(task_id.clone());
+    client2.approve_task(request).unwrap();
+    let request = ApproveTaskRequest::new(task_id.clone());
+    client3.approve_task(request).unwrap();
+
+    // staging waits for the upstream task to finish
+    let request = InvokeTaskRequest::new(task_id.clone());
+    client.invoke_task(request).unwrap();
+    let response = client
+        .get_task(GetTaskRequest::new(task_id.clone()))
+        .unwrap();
+    assert_eq!(response.status, TaskStatus::Approved);
+    assert!(response.staging_deferred);
+    assert_eq!(
+        response.input_dependencies["input"],
+        TaskOutputReference::new(upstream_id, "output")
+    );
+
+    let request = InvokeTaskRequest::new(task_id);
+    let response = client.invoke_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+}
+
+#[test_case]
+fn test_create_task_with_argument_templates() {
+    let mut client = authorized_client("mock_user");
+    let upstream_id = client
+        .create_task(create_valid_task_request())
+        .unwrap()
+        .task_id;
+    let template = format!("{{{{task:{}:result.threshold}}}}", upstream_id.to_string());
+
+    // only participants of the upstream task can take its result
+    let request =
+        create_valid_task_request().function_arguments(hashmap!("threshold" => template.as_str()));
+    let response = authorized_client("mock_user4").create_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let request = create_valid_task_request().function_arguments(hashmap!(
+        "threshold" => "{{task:task-00000000-0000-0000-0000-000000000009:result}}"
+    ));
+    let response = client.create_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let request =
+        create_valid_task_request().function_arguments(hashmap!("threshold" => template.as_str()));
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    // the template is kept in the task until it is staged
+    let response = client.get_task(GetTaskRequest::new(task_id)).unwrap();
+    assert_eq!(
+        response.function_arguments.inner()["threshold"],
+        serde_json::Value::String(template)
+    );
+}
+
+#[test_case]
+fn test_create_task_with_resource_limits() {
+    let mut client = authorized_client("mock_user");
+    let limits = ResourceLimits::new()
+        .max_memory_bytes(64 * 1024 * 1024)
+        .max_cpu_seconds(60)
+        .max_output_bytes(1024);
+    let request = create_valid_task_request()
+        .resource_limits(limits)
+        .timeout(600)
+        .retry_policy(RetryPolicy::new(3, 10));
+    let task_id = client.create_task(request).unwrap().task_id;
+
+    let response = client.get_task(GetTaskRequest::new(task_id)).unwrap();
+    assert_eq!(response.resource_limits, limits);
+    assert_eq!(response.timeout, Some(600));
+    assert_eq!(response.retry_policy, RetryPolicy::new(3, 10));
+    assert_eq!(response.retry_count, 0);
+
+    // tasks are not limited by default
+    let task_id = client
+        .create_task(create_valid_task_request())
+        .unwrap()
+        .task_id;
+    let response = client.get_task(GetTaskRequest::new(task_id)).unwrap();
+    assert!(response.resource_limits.is_unlimited());
+    assert_eq!(response.timeout, None);
+    assert_eq!(response.retry_policy.max_retries, 0);
+}
+
+#[test_case]
+fn test_create_task_with_federation_peer() {
+    let mut client = authorized_client("mock_user");
+    let request = create_valid_task_request().federation_peer("unknown_peer");
+    let response = client.create_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::NotFound);
+
+    // only management services of the peers delegate tasks
+    let mut ts = TaskState::default();
+    ts.status = TaskStatus::Staged;
+    let request = DelegateTaskRequest::new("unknown_peer", ts, StagedTask::default());
+    let response = client.delegate_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let request = GetDelegatedTaskRequest::new("unknown_peer", Uuid::new_v4());
+    let response = client.get_delegated_task(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+}
+
+#[test_case]
+fn test_estimate_task() {
+    let request = RegisterFunctionRequest::new()
+        .name("mock_function")
+        .executor_type(ExecutorType::Python)
+        .payload(b"def entrypoint:\n\treturn".to_vec())
+        .public(false);
+    let mut client = authorized_client("mock_user");
+    let function_id = client.register_function(request).unwrap().function_id;
+
+    // a function which has never run has no estimated run time
+    let request = EstimateTaskRequest::new(function_id.clone());
+    let response = client.estimate_task(request).unwrap();
+    assert_eq!(response.runs, 0);
+    assert_eq!(response.run_time, 0);
+
+    // the run time grows with the input size
+    let mut usage = FunctionUsage::new(function_id.uuid);
+    usage.record(&TaskUsage::new(1000, 1024));
+    usage.record(&TaskUsage::new(3000, 3072));
+    let mut storage_client = get_storage_client();
+    let put_request = PutRequest::new(usage.key().as_slice(), usage.to_vec().unwrap().as_slice());
+    storage_client.put(put_request).unwrap();
+
+    let request = EstimateTaskRequest::new(function_id.clone())
+        .input_size(2048)
+        .priority(TaskPriority::High);
+    let response = client.estimate_task(request).unwrap();
+    assert_eq!(response.runs, 2);
+    assert_eq!(response.run_time, 2000);
+
+    // only the owner of a private function can estimate its tasks
+    let request = EstimateTaskRequest::new(function_id);
+    let response = authorized_client("mock_user_c").estimate_task(request);
+    assert!(response.is_err());
+}
+
+#[test_case]
+fn test_signed_requests() {
+    let rng = ring::rand::SystemRandom::new();
+    let pkcs8 = ring::signature::Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+    let key_pair = ring::signature::Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+    let public_key = ring::signature::KeyPair::public_key(&key_pair)
+        .as_ref()
+        .to_vec();
+    let user_id = UserID::from("mock_signing_user");
+    let mut client = authorized_client("mock_signing_user");
+
+    // requests cannot be signed before a key is registered
+    let request = create_valid_task_request();
+    let message = request.request_signature(&user_id).message();
+    let signature = key_pair.sign(&message).as_ref().to_vec();
+    let response = client.create_task(request.signature(signature.clone()));
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let request_key = RegisterSigningKeyRequest::new(public_key.clone());
+    assert!(client.register_signing_key(request_key).is_ok());
+    let request_key = RegisterSigningKeyRequest::new(vec![0; 16]);
+    assert!(client.register_signing_key(request_key).is_err());
+
+    // the signature is bound to the content of the request
+    let tampered = create_valid_task_request()
+        .function_arguments(hashmap!("arg1" => "data3"))
+        .signature(signature.clone());
+    let response = client.create_task(tampered);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    let task_id = client
+        .create_task(create_valid_task_request().signature(signature))
+        .unwrap()
+        .task_id;
+    let request = GetTaskRequest::new(task_id);
+    let response = client.get_task(request).unwrap();
+    assert_eq!(response.request_signatures.len(), 1);
+    let request_signature = &response.request_signatures[0];
+    assert_eq!(request_signature.user_id, user_id);
+    assert_eq!(request_signature.action, SignedAction::CreateTask);
+    assert_eq!(request_signature.public_key, public_key);
+    assert!(request_signature.verify().is_ok());
+
+    // unsigned requests are still accepted
+    let response = client.create_task(create_valid_task_request());
+    assert!(response.is_ok());
+}
+
+#[test_case]
+fn test_task_schedule() {
+    let valid_request = create_valid_task_request();
+    let request = RegisterTaskTemplateRequest::new()
+        .function_id(valid_request.function_id)
+        .function_arguments(valid_request.function_arguments)
+        .executor(Executor::MesaPy)
+        .inputs_ownership(valid_request.inputs_ownership)
+        .outputs_ownership(valid_request.outputs_ownership);
+    let mut client = authorized_client("mock_user");
+    let template_id = client.register_task_template(request).unwrap().template_id;
+
+    let mut client1 = authorized_client("mock_user1");
+    let url = Url::parse("input://path").unwrap();
+    let request = RegisterInputFileRequest::new(url, FileAuthTag::mock(), FileCrypto::default());
+    let input_file_id_user1 = client1.register_input_file(request).unwrap().data_id;
+    let input_file_id_user2 =
+        ExternalID::try_from("input-00000000-0000-0000-0000-000000000002").unwrap();
+    let schedule_request = || {
+        RegisterTaskScheduleRequest::new(template_id.clone(), 3600)
+            .input("input", input_file_id_user1.clone())
+            .input("input2", input_file_id_user2.clone())
+    };
+
+    // only the creator of the template schedules it
+    let response = client1.register_task_schedule(schedule_request());
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    // the scheduler does not keep intervals shorter than a minute
+    let request = RegisterTaskScheduleRequest::new(template_id.clone(), 30)
+        .input("input", input_file_id_user1.clone())
+        .input("input2", input_file_id_user2.clone());
+    let response = client.register_task_schedule(request);
+    assert!(response.is_err());
+
+    client.register_task_schedule(schedule_request()).unwrap();
+    let response = client.register_task_schedule(schedule_request());
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    let request = GetTaskScheduleRequest::new(template_id.clone());
+    let response = client.get_task_schedule(request).unwrap();
+    assert_eq!(response.spec.interval, 3600);
+    assert_eq!(response.runs, 0);
+    assert!(response.task_ids.is_empty());
+    assert_eq!(response.approved_users.len(), 1);
+    assert!(response.approved_users.contains(&UserID::from("mock_user")));
+
+    let request = ApproveTaskScheduleRequest::new(template_id.clone());
+    client1.approve_task_schedule(request).unwrap();
+    let request = ApproveTaskScheduleRequest::new(template_id.clone());
+    let response = authorized_client("mock_user4").approve_task_schedule(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Auth);
+
+    // any participant can cancel the schedule
+    let request = CancelTaskScheduleRequest::new(template_id.clone());
+    client1.cancel_task_schedule(request).unwrap();
+    let request = ApproveTaskScheduleRequest::new(template_id.clone());
+    let response = authorized_client("mock_user2").approve_task_schedule(request);
+    assert_eq!(response.unwrap_err().code(), TeaclaveErrorCode::Conflict);
+
+    let request = GetTaskScheduleRequest::new(template_id);
+    let response = client.get_task_schedule(request).unwrap();
+    assert!(response.canceled);
+    assert_eq!(response.approved_users.len(), 2);
+}
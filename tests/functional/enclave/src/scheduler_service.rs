@@ -41,13 +41,113 @@ fn test_pull_task() {
     let _enqueue_response = storage_client.enqueue(enqueue_request).unwrap();
 
     let mut client = get_scheduler_client();
-    let request = PullTaskRequest {};
+    let request = PullTaskRequest::new();
     let response = client.pull_task(request);
     log::debug!("response: {:?}", response);
     assert!(response.is_ok());
     assert_eq!(response.unwrap().staged_task.function_id, function_id);
 }
 
+#[test_case]
+fn test_pull_task_pinned_to_other_workers() {
+    let pinned = StagedTask::new()
+        .task_id(Uuid::new_v4())
+        .function_name("builtin-echo")
+        .executor(Executor::Builtin)
+        .worker_measurements(vec!["0".repeat(64)]);
+    let unpinned = StagedTask::new()
+        .task_id(Uuid::new_v4())
+        .function_name("builtin-echo")
+        .executor(Executor::Builtin);
+
+    let mut storage_client = get_storage_client();
+    for staged_task in [&pinned, &unpinned].iter() {
+        let enqueue_request = EnqueueRequest::new(
+            StagedTask::get_queue_key().as_bytes(),
+            staged_task.to_vec().unwrap(),
+        );
+        storage_client.enqueue(enqueue_request).unwrap();
+    }
+
+    // the task pinned to another enclave is left for its workers
+    let mut client = get_scheduler_client();
+    let response = client.pull_task(PullTaskRequest::new()).unwrap();
+    assert_eq!(response.staged_task.task_id, unpinned.task_id);
+}
+
+#[test_case]
+fn test_pull_task_pinned_to_other_signers() {
+    let pinned = StagedTask::new()
+        .task_id(Uuid::new_v4())
+        .function_name("builtin-echo")
+        .executor(Executor::Builtin)
+        .worker_signers(vec!["0".repeat(64)]);
+    let unpinned = StagedTask::new()
+        .task_id(Uuid::new_v4())
+        .function_name("builtin-echo")
+        .executor(Executor::Builtin);
+
+    let mut storage_client = get_storage_client();
+    for staged_task in [&pinned, &unpinned].iter() {
+        let enqueue_request = EnqueueRequest::new(
+            staged_task.queue_key().as_bytes(),
+            staged_task.to_vec().unwrap(),
+        );
+        storage_client.enqueue(enqueue_request).unwrap();
+    }
+
+    // the task pinned to another signer is left for its workers
+    let mut client = get_scheduler_client();
+    let response = client.pull_task(PullTaskRequest::new()).unwrap();
+    assert_eq!(response.staged_task.task_id, unpinned.task_id);
+}
+
+#[test_case]
+fn test_pull_task_priority() {
+    let new_task = |priority| {
+        StagedTask::new()
+            .task_id(Uuid::new_v4())
+            .function_name("builtin-echo")
+            .executor(Executor::Builtin)
+            .priority(priority)
+    };
+    let low = new_task(TaskPriority::Low);
+    let high: Vec<StagedTask> = (0..16).map(|_| new_task(TaskPriority::High)).collect();
+
+    let mut storage_client = get_storage_client();
+    for staged_task in std::iter::once(&low).chain(high.iter()) {
+        let enqueue_request = EnqueueRequest::new(
+            staged_task.queue_key().as_bytes(),
+            staged_task.to_vec().unwrap(),
+        );
+        storage_client.enqueue(enqueue_request).unwrap();
+    }
+
+    // tasks staged by other tests may be pulled along with them
+    let mut client = get_scheduler_client();
+    let mut pulled = Vec::new();
+    while pulled.len() < high.len() + 1 {
+        let task_id = client
+            .pull_task(PullTaskRequest::new())
+            .unwrap()
+            .staged_task
+            .task_id;
+        if task_id == low.task_id || high.iter().any(|task| task.task_id == task_id) {
+            pulled.push(task_id);
+        }
+    }
+    let high_ids: Vec<Uuid> = high.iter().map(|task| task.task_id).collect();
+    let pulled_high: Vec<Uuid> = pulled
+        .iter()
+        .filter(|task_id| **task_id != low.task_id)
+        .cloned()
+        .collect();
+    assert_eq!(pulled_high, high_ids);
+
+    // the low priority task is not starved by the high priority ones
+    assert_ne!(pulled.last(), Some(&low.task_id));
+}
+
 #[test_case]
 fn test_update_task_status_result() {
     let task_id = Uuid::new_v4();
@@ -75,7 +175,7 @@ fn test_update_task_status_result() {
     let _put_response = storage_client.put(put_request).unwrap();
 
     let mut client = get_scheduler_client();
-    let request = PullTaskRequest {};
+    let request = PullTaskRequest::new();
     let response = client.pull_task(request).unwrap();
     log::debug!("response: {:?}", response);
     let task_id = response.staged_task.task_id;
@@ -90,3 +190,262 @@ fn test_update_task_status_result() {
 
     assert!(response.is_ok());
 }
+
+#[test_case]
+fn test_pull_task_with_timeout() {
+    let task_id = Uuid::new_v4();
+    let staged_task = StagedTask::new()
+        .task_id(task_id)
+        .function_name("builtin-echo")
+        .executor(Executor::Builtin)
+        .timeout(Some(60));
+
+    let mut storage_client = get_storage_client();
+    let ts = TaskState {
+        task_id,
+        status: TaskStatus::Staged,
+        timeout: Some(60),
+        ..Default::default()
+    };
+    let put_request = PutRequest::new(ts.key().as_slice(), ts.to_vec().unwrap().as_slice());
+    storage_client.put(put_request).unwrap();
+    let enqueue_request = EnqueueRequest::new(
+        StagedTask::get_queue_key().as_bytes(),
+        staged_task.to_vec().unwrap(),
+    );
+    storage_client.enqueue(enqueue_request).unwrap();
+
+    let mut client = get_scheduler_client();
+    let response = client.pull_task(PullTaskRequest::new()).unwrap();
+    assert_eq!(response.staged_task.task_id, task_id);
+
+    // the dispatch is recorded for the task to fail if it does not finish in
+    // time
+    let dequeue_request = DequeueRequest::new(TaskDispatch::get_queue_key().as_bytes());
+    let response = storage_client.dequeue(dequeue_request).unwrap();
+    let dispatch = TaskDispatch::from_slice(&response.value).unwrap();
+    assert_eq!(dispatch.task_id, task_id);
+    assert_eq!(dispatch.timeout, 60);
+    assert!(!dispatch.is_overdue(dispatch.dispatched_at + 60));
+}
+
+#[test_case]
+fn test_update_task_result_retry() {
+    let task_id = Uuid::new_v4();
+    let retry_policy = RetryPolicy::new(1, 0);
+    let staged_task = StagedTask::new()
+        .task_id(task_id)
+        .function_name("builtin-echo")
+        .executor(Executor::Builtin)
+        .retry_policy(retry_policy);
+
+    let mut storage_client = get_storage_client();
+    let ts = TaskState {
+        task_id,
+        status: TaskStatus::Staged,
+        retry_policy,
+        ..Default::default()
+    };
+    let put_request = PutRequest::new(ts.key().as_slice(), ts.to_vec().unwrap().as_slice());
+    storage_client.put(put_request).unwrap();
+    let enqueue_request = EnqueueRequest::new(
+        StagedTask::get_queue_key().as_bytes(),
+        staged_task.to_vec().unwrap(),
+    );
+    storage_client.enqueue(enqueue_request).unwrap();
+
+    let mut client = get_scheduler_client();
+    let run_and_fail = |client: &mut TeaclaveSchedulerClient,
+                        storage_client: &mut TeaclaveStorageClient| {
+        let response = client.pull_task(PullTaskRequest::new()).unwrap();
+        assert_eq!(response.staged_task.task_id, task_id);
+        let request = UpdateTaskStatusRequest::new(task_id, TaskStatus::Running);
+        client.update_task_status(request).unwrap();
+        let failure = TransientFailure::FileFetch("connection refused".to_string());
+        let request = UpdateTaskResultRequest::new(task_id, Err(failure.into()));
+        client.update_task_result(request).unwrap();
+
+        let get_request = GetRequest::new(ts.key().as_slice());
+        let response = storage_client.get(get_request).unwrap();
+        TaskState::from_slice(&response.value).unwrap()
+    };
+
+    // the task failed transiently is staged again after the backoff
+    let retried = run_and_fail(&mut client, &mut storage_client);
+    assert_eq!(retried.status, TaskStatus::Staged);
+    assert_eq!(retried.retry_count, 1);
+    let dequeue_request = DequeueRequest::new(TaskRetry::get_queue_key().as_bytes());
+    let response = storage_client.dequeue(dequeue_request).unwrap();
+    let retry = TaskRetry::from_slice(&response.value).unwrap();
+    assert_eq!(retry.staged_task.task_id, task_id);
+    assert_eq!(retry.staged_task.retry_count, 1);
+    assert!(retry.is_due(retry.retry_at));
+
+    // and fails once it runs out of retries
+    let enqueue_request = EnqueueRequest::new(
+        StagedTask::get_queue_key().as_bytes(),
+        retry.staged_task.to_vec().unwrap(),
+    );
+    storage_client.enqueue(enqueue_request).unwrap();
+    let failed = run_and_fail(&mut client, &mut storage_client);
+    assert_eq!(failed.status, TaskStatus::Finished);
+    match failed.result {
+        TaskResult::Err(failure) => assert_eq!(failure.kind, TaskFailureKind::Transient),
+        _ => panic!("task not failed"),
+    }
+}
+
+#[test_case]
+fn test_register_worker_heartbeat() {
+    let mut client = get_scheduler_client();
+    let executors = vec![WorkerExecutor::new(
+        ExecutorType::Builtin,
+        Executor::Builtin,
+    )];
+    let capabilities = WorkerCapabilities::new(executors, ResourceProfile::new());
+    let request = RegisterWorkerRequest::new(capabilities);
+    let response = client.register_worker(request).unwrap();
+    assert_eq!(response.heartbeat_interval, WORKER_HEARTBEAT_INTERVAL);
+    let worker_id = response.worker_id;
+
+    let request = HeartbeatRequest::new(worker_id, 1);
+    assert!(client.heartbeat(request).is_ok());
+
+    // a worker unknown to the scheduler registers again
+    let request = HeartbeatRequest::new(Uuid::new_v4(), 0);
+    assert!(client.heartbeat(request).is_err());
+
+    // the task pulled by a registered worker is kept to be requeued
+    let task_id = Uuid::new_v4();
+    let staged_task = StagedTask::new()
+        .task_id(task_id)
+        .function_name("builtin-echo")
+        .executor(Executor::Builtin);
+    let mut storage_client = get_storage_client();
+    let enqueue_request = EnqueueRequest::new(
+        StagedTask::get_queue_key().as_bytes(),
+        staged_task.to_vec().unwrap(),
+    );
+    storage_client.enqueue(enqueue_request).unwrap();
+
+    let request = PullTaskRequest::new().worker_id(worker_id);
+    let response = client.pull_task(request).unwrap();
+    assert_eq!(response.staged_task.task_id, task_id);
+    let get_request = GetRequest::new(response.staged_task.key().as_slice());
+    let response = storage_client.get(get_request).unwrap();
+    let kept = StagedTask::from_slice(&response.value).unwrap();
+    assert_eq!(kept.task_id, task_id);
+}
+
+#[test_case]
+fn test_pull_task_of_worker_executors() {
+    let mut client = get_scheduler_client();
+    let executors = vec![WorkerExecutor::new(
+        ExecutorType::Builtin,
+        Executor::Builtin,
+    )];
+    let capabilities = WorkerCapabilities::new(executors, ResourceProfile::new());
+    let request = RegisterWorkerRequest::new(capabilities);
+    let worker_id = client.register_worker(request).unwrap().worker_id;
+
+    let mesapy = StagedTask::new()
+        .task_id(Uuid::new_v4())
+        .function_name("mesapy-echo")
+        .executor(Executor::MesaPy);
+    let builtin = StagedTask::new()
+        .task_id(Uuid::new_v4())
+        .function_name("builtin-echo")
+        .executor(Executor::Builtin);
+    let mut storage_client = get_storage_client();
+    for staged_task in [&mesapy, &builtin].iter() {
+        let enqueue_request = EnqueueRequest::new(
+            staged_task.queue_key().as_bytes(),
+            staged_task.to_vec().unwrap(),
+        );
+        storage_client.enqueue(enqueue_request).unwrap();
+    }
+
+    // the worker only pulls tasks of the executors it supports, while tasks
+    // staged by other tests may be pulled along with them
+    loop {
+        let request = PullTaskRequest::new().worker_id(worker_id);
+        let task_id = client.pull_task(request).unwrap().staged_task.task_id;
+        assert_ne!(task_id, mesapy.task_id);
+        if task_id == builtin.task_id {
+            break;
+        }
+    }
+
+    // the task is left in its queue for workers supporting its executor
+    loop {
+        let request = PullTaskRequest::new();
+        let task_id = client.pull_task(request).unwrap().staged_task.task_id;
+        if task_id == mesapy.task_id {
+            break;
+        }
+    }
+}
+
+#[test_case]
+fn test_pull_task_reuse_result() {
+    let mut storage_client = get_storage_client();
+    let function = Function::new()
+        .id(Uuid::new_v4())
+        .name("builtin-echo")
+        .arguments(vec!["message".to_string()]);
+    let put_request = PutRequest::new(
+        function.key().as_slice(),
+        function.to_vec().unwrap().as_slice(),
+    );
+    storage_client.put(put_request).unwrap();
+
+    let mut new_task = |reuse_result| {
+        let ts = TaskState {
+            task_id: Uuid::new_v4(),
+            function_id: function.external_id(),
+            function_arguments: FunctionArguments::from(hashmap!("message" => "Hello")),
+            executor: Executor::Builtin,
+            status: TaskStatus::Staged,
+            reuse_result,
+            ..Default::default()
+        };
+        let put_request = PutRequest::new(ts.key().as_slice(), ts.to_vec().unwrap().as_slice());
+        storage_client.put(put_request).unwrap();
+        let staged_task = StagedTask::new()
+            .task_id(ts.task_id)
+            .function_name("builtin-echo")
+            .executor(Executor::Builtin);
+        let enqueue_request = EnqueueRequest::new(
+            StagedTask::get_queue_key().as_bytes(),
+            staged_task.to_vec().unwrap(),
+        );
+        storage_client.enqueue(enqueue_request).unwrap();
+        ts
+    };
+
+    // the first task runs and its result is recorded
+    let first = new_task(true);
+    let mut client = get_scheduler_client();
+    let response = client.pull_task(PullTaskRequest::new()).unwrap();
+    assert_eq!(response.staged_task.task_id, first.task_id);
+    let request = UpdateTaskStatusRequest::new(first.task_id, TaskStatus::Running);
+    client.update_task_status(request).unwrap();
+    let task_outputs = TaskOutputs::new("Hello", hashmap!());
+    let request = UpdateTaskResultRequest::new(first.task_id, Ok(task_outputs));
+    client.update_task_result(request).unwrap();
+
+    // an identical task is served from the result, the one not consenting runs
+    let identical = new_task(true);
+    let not_consenting = new_task(false);
+    let response = client.pull_task(PullTaskRequest::new()).unwrap();
+    assert_eq!(response.staged_task.task_id, not_consenting.task_id);
+
+    let get_request = GetRequest::new(identical.key().as_slice());
+    let value = storage_client.get(get_request).unwrap().value;
+    let ts = TaskState::from_slice(&value).unwrap();
+    assert_eq!(ts.status, TaskStatus::Finished);
+    match ts.result {
+        TaskResult::Ok(outputs) => assert_eq!(outputs.return_value, b"Hello"),
+        _ => panic!("result not reused"),
+    }
+}
@@ -20,6 +20,9 @@ use teaclave_config::RuntimeConfig;
 use teaclave_proto::teaclave_storage_service::*;
 use teaclave_rpc::endpoint::Endpoint;
 use teaclave_test_utils::test_case;
+use teaclave_types::{
+    FileAuthTag, IndexTerm, ReturnValueChunk, Storable, StorageCodec, TeaclaveErrorCode,
+};
 
 fn get_client() -> TeaclaveStorageClient {
     let runtime_config = RuntimeConfig::from_toml("runtime.config.toml").expect("runtime");
@@ -61,6 +64,60 @@ fn test_put_success() {
     assert_eq!(response_result.unwrap().value, b"test_put_value");
 }
 
+#[test_case]
+fn test_put_if_version() {
+    let mut client = get_client();
+    let request = PutIfVersionRequest::new("test_version_key", "1", 0);
+    let version = client.put_if_version(request).unwrap().version;
+    assert_eq!(version, 1);
+    let request = GetRequest::new("test_version_key");
+    assert_eq!(client.get(request).unwrap().version, version);
+
+    // A second write based on the same version is rejected.
+    let request = PutIfVersionRequest::new("test_version_key", "2", version);
+    assert!(client.put_if_version(request).is_ok());
+    let request = PutIfVersionRequest::new("test_version_key", "3", version);
+    assert!(client.put_if_version(request).is_err());
+    let request = GetRequest::new("test_version_key");
+    assert_eq!(client.get(request).unwrap().value, b"2");
+}
+
+#[test_case]
+fn test_put_with_ttl() {
+    let mut client = get_client();
+    let request = PutWithTtlRequest::new("test_ttl_key", "1", 1);
+    assert!(client.put_with_ttl(request).is_ok());
+    let request = GetRequest::new("test_ttl_key");
+    assert_eq!(client.get(request).unwrap().value, b"1");
+
+    // The key is absent once expired, whether it is purged yet or not.
+    std::thread::sleep(std::time::Duration::from_secs(2));
+    let request = GetRequest::new("test_ttl_key");
+    let error = client.get(request).unwrap_err();
+    assert_eq!(error.code(), TeaclaveErrorCode::NotFound);
+}
+
+#[test_case]
+fn test_commit_transaction() {
+    let mut client = get_client();
+    let mut transaction = Transaction::begin();
+    transaction.put("test_transaction_key", "1");
+    transaction.put_if_version("test_transaction_version_key", "1", 0);
+    let request = CommitTransactionRequest::from(transaction);
+    assert!(client.commit_transaction(request).is_ok());
+    let request = GetRequest::new("test_transaction_version_key");
+    assert_eq!(client.get(request).unwrap().version, 1);
+
+    // None of the writes is written if any fails.
+    let mut transaction = Transaction::begin();
+    transaction.delete("test_transaction_key");
+    transaction.put_if_version("test_transaction_version_key", "2", 0);
+    let request = CommitTransactionRequest::from(transaction);
+    assert!(client.commit_transaction(request).is_err());
+    let request = GetRequest::new("test_transaction_key");
+    assert_eq!(client.get(request).unwrap().value, b"1");
+}
+
 #[test_case]
 fn test_delete_success() {
     let mut client = get_client();
@@ -123,3 +180,154 @@ fn test_dequeue_fail() {
     let response_result = client.dequeue(request);
     assert!(response_result.is_err());
 }
+
+#[test_case]
+fn test_fault_injection() {
+    use teaclave_rpc::fault::{Fault, FaultRule, FaultScope};
+
+    let mut client = get_client();
+    let _faults = FaultScope::new(vec![
+        FaultRule::new(Fault::Drop).request("put").times(1),
+        FaultRule::new(Fault::Corrupt).request("delete").times(1),
+        FaultRule::new(Fault::Duplicate).request("enqueue").times(1),
+    ]);
+
+    // a dropped request never reaches the service
+    let request = PutRequest::new("test_fault_put_key", "test_fault_put_value");
+    assert!(client.put(request).is_err());
+    let request = GetRequest::new("test_fault_put_key");
+    assert!(client.get(request).is_err());
+    let request = PutRequest::new("test_fault_put_key", "test_fault_put_value");
+    assert!(client.put(request).is_ok());
+
+    // a corrupted response hides that the request has been handled
+    let request = DeleteRequest::new("test_fault_put_key");
+    assert!(client.delete(request).is_err());
+    let request = GetRequest::new("test_fault_put_key");
+    assert!(client.get(request).is_err());
+
+    // a duplicated request is handled twice
+    let request = EnqueueRequest::new("test_fault_enqueue_key", "test_fault_enqueue_value");
+    assert!(client.enqueue(request).is_ok());
+    let request = DequeueRequest::new("test_fault_enqueue_key");
+    let response = client.dequeue(request).unwrap();
+    assert_eq!(response.value, b"test_fault_enqueue_value");
+    let request = DequeueRequest::new("test_fault_enqueue_key");
+    let response = client.dequeue(request).unwrap();
+    assert_eq!(response.value, b"test_fault_enqueue_value");
+    let request = DequeueRequest::new("test_fault_enqueue_key");
+    assert!(client.dequeue(request).is_err());
+}
+
+#[test_case]
+fn test_put_indexed_and_scan_index() {
+    let mut client = get_client();
+    for key in &["test_indexed_key_1", "test_indexed_key_2"] {
+        let terms = vec![IndexTerm::new("test_indexed-owner", "test_owner")];
+        let request = PutIndexedRequest::new(*key, "test_indexed_value", terms);
+        assert!(client.put_indexed(request).is_ok());
+    }
+
+    let request = ScanIndexRequest::new("test_indexed-owner", "test_owner").limit(1);
+    let response = client.scan_index(request).unwrap();
+    assert_eq!(response.entries[0].key, b"test_indexed_key_1");
+    let start_after = response.next_start_after.unwrap();
+    let request =
+        ScanIndexRequest::new("test_indexed-owner", "test_owner").start_after(start_after);
+    let response = client.scan_index(request).unwrap();
+    assert_eq!(response.entries.len(), 1);
+    assert_eq!(response.entries[0].key, b"test_indexed_key_2");
+    assert!(response.next_start_after.is_none());
+
+    let request = DeleteRequest::new("test_indexed_key_2");
+    assert!(client.delete(request).is_ok());
+    let request = ScanIndexRequest::new("test_indexed-owner", "test_owner");
+    let response = client.scan_index(request).unwrap();
+    assert_eq!(response.entries.len(), 1);
+}
+
+#[test_case]
+fn test_read_replication_log() {
+    let mut client = get_client();
+    let request = PutRequest::new("test_replicated_key", "test_replicated_value");
+    assert!(client.put(request).is_ok());
+
+    let mut entries = Vec::new();
+    let mut request = ReadReplicationLogRequest::new(0);
+    loop {
+        let response = client.read_replication_log(request).unwrap();
+        assert!(!response.snapshot_required);
+        if response.entries.is_empty() {
+            break;
+        }
+        entries.extend(response.entries);
+        request = ReadReplicationLogRequest::new(response.next_sequence);
+    }
+    assert!(entries
+        .iter()
+        .any(|entry| entry.key == b"test_replicated_key"
+            && entry.value == Some(b"test_replicated_value".to_vec())));
+}
+
+#[test_case]
+fn test_migrate_stored_record() {
+    let (_, chunks) = ReturnValueChunk::split(uuid::Uuid::new_v4(), b"return value");
+    let chunk = &chunks[0];
+
+    // a chunk written in JSON before its type switched formats
+    let mut client = get_client();
+    let json = StorageCodec::Json.encode(chunk).unwrap();
+    let request = PutRequest::new(chunk.key(), json);
+    client.put(request).unwrap();
+
+    let stored = client.get(GetRequest::new(chunk.key())).unwrap().value;
+    assert_eq!(
+        ReturnValueChunk::from_slice(&stored).unwrap().data,
+        chunk.data
+    );
+    let migrated = ReturnValueChunk::migrate(&stored).unwrap().unwrap();
+    assert_eq!(StorageCodec::of(&migrated), StorageCodec::Bincode);
+    assert!(migrated.len() < stored.len());
+    client.put(PutRequest::new(chunk.key(), migrated)).unwrap();
+
+    let stored = client.get(GetRequest::new(chunk.key())).unwrap().value;
+    assert_eq!(
+        ReturnValueChunk::from_slice(&stored).unwrap().data,
+        chunk.data
+    );
+    assert!(ReturnValueChunk::migrate(&stored).unwrap().is_none());
+}
+
+#[test_case]
+fn test_export_snapshot() {
+    let mut client = get_client();
+    let request = PutRequest::new("test_snapshot_key", "test_snapshot_value");
+    client.put(request).unwrap();
+
+    let request = ExportSnapshotRequest::new("functional_test");
+    let response = client.export_snapshot(request).unwrap();
+    assert!(response.entries > 0);
+
+    // the snapshot is verified before any key is replaced
+    let request = RestoreSnapshotRequest::new(
+        "functional_test",
+        response.crypto_info,
+        FileAuthTag::default(),
+    );
+    let error = client.restore_snapshot(request).unwrap_err();
+    assert_eq!(error.code(), TeaclaveErrorCode::Validation);
+    let request = GetRequest::new("test_snapshot_key");
+    assert!(client.get(request).is_ok());
+
+    let request = ExportSnapshotRequest::new("../functional_test");
+    let error = client.export_snapshot(request).unwrap_err();
+    assert_eq!(error.code(), TeaclaveErrorCode::Validation);
+}
+
+#[test_case]
+fn test_get_wal_stats() {
+    let mut client = get_client();
+    let response = client.get_wal_stats(GetWalStatsRequest::new()).unwrap();
+    // The storage service of the tests keeps its keys in memory only.
+    assert_eq!(response, GetWalStatsResponse::default());
+}
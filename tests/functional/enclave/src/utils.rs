@@ -26,6 +26,7 @@ use teaclave_proto::teaclave_access_control_service::*;
 use teaclave_proto::teaclave_authentication_service::*;
 use teaclave_proto::teaclave_common::*;
 use teaclave_proto::teaclave_frontend_service::*;
+use teaclave_proto::teaclave_frontend_service_v2::TeaclaveFrontendV2Client;
 use teaclave_proto::teaclave_management_service::*;
 use teaclave_proto::teaclave_scheduler_service::*;
 use teaclave_proto::teaclave_storage_service::*;
@@ -122,6 +123,22 @@ pub fn create_frontend_client(
     Ok(client)
 }
 
+pub fn create_frontend_v2_client(
+    enclave_info: &EnclaveInfo,
+    service_addr: &str,
+    cred: UserCredential,
+) -> Result<TeaclaveFrontendV2Client> {
+    let tls_config = create_client_config(&enclave_info, "teaclave_frontend_service")?;
+    let channel = Endpoint::new(service_addr).config(tls_config).connect()?;
+
+    let mut metadata = HashMap::new();
+    metadata.insert("id".to_string(), cred.id);
+    metadata.insert("token".to_string(), cred.token);
+
+    let client = TeaclaveFrontendV2Client::new_with_metadata(channel, metadata)?;
+    Ok(client)
+}
+
 pub fn create_authentication_api_client(
     enclave_info: &EnclaveInfo,
     service_addr: &str,
@@ -20,7 +20,10 @@ use rustls::internal::pemfile;
 use serde::{Deserialize, Serialize};
 use std::io;
 use std::prelude::v1::*;
+use std::sync::{Arc, SgxRwLock as RwLock};
 use std::untrusted::fs;
+use teaclave_attestation::report::AttestationReport;
+use teaclave_attestation::AttestedTlsConfig;
 use teaclave_rpc::channel::*;
 use teaclave_rpc::config::*;
 use teaclave_rpc::endpoint::*;
@@ -28,6 +31,7 @@ use teaclave_rpc::server::*;
 use teaclave_rpc::*;
 use teaclave_types::TeaclaveServiceResponseError;
 use teaclave_types::TeaclaveServiceResponseResult;
+use teaclave_types::{EnclaveAttr, EnclaveMeasurement, TrustedTime};
 
 const END_FULLCHAIN: &str = "./fixtures/end_fullchain.pem";
 const END_KEY: &str = "./fixtures/end_key.pem";
@@ -36,6 +40,7 @@ const END_KEY: &str = "./fixtures/end_key.pem";
 #[serde(tag = "request", rename_all = "snake_case")]
 enum EchoRequest {
     Say(SayRequest),
+    Repeat(RepeatRequest),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -43,6 +48,12 @@ struct SayRequest {
     message: String,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+struct RepeatRequest {
+    message: String,
+    times: usize,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "response", rename_all = "snake_case")]
 enum EchoResponse {
@@ -65,9 +76,34 @@ impl TeaclaveService<EchoRequest, EchoResponse> for EchoService {
         debug!("handle request: {:?}", request);
         let message = match request.message {
             EchoRequest::Say(s) => s.message,
+            EchoRequest::Repeat(_) => {
+                return Err(TeaclaveServiceResponseError::InternalError(
+                    "streaming".to_string(),
+                ))
+            }
         };
         Ok(EchoResponse::Say(SayResponse { message }))
     }
+
+    fn is_stream_request(&self, request: &teaclave_rpc::Request<EchoRequest>) -> bool {
+        matches!(request.message, EchoRequest::Repeat(_))
+    }
+
+    fn handle_stream_request(
+        &self,
+        request: teaclave_rpc::Request<EchoRequest>,
+        sink: &mut dyn FnMut(EchoResponse) -> TeaclaveServiceResponseResult<()>,
+    ) -> TeaclaveServiceResponseResult<()> {
+        debug!("handle stream request: {:?}", request);
+        if let EchoRequest::Repeat(r) = request.message {
+            for _ in 0..r.times {
+                sink(EchoResponse::Say(SayResponse {
+                    message: r.message.clone(),
+                }))?;
+            }
+        }
+        Ok(())
+    }
 }
 
 struct EchoClient {
@@ -79,6 +115,11 @@ impl EchoClient {
         Ok(Self { channel })
     }
 
+    fn try_clone(&self) -> Result<Self> {
+        let channel = self.channel.try_clone()?;
+        Ok(Self { channel })
+    }
+
     fn say(&mut self, request: SayRequest) -> TeaclaveServiceResponseResult<SayResponse> {
         let request = EchoRequest::Say(request);
         let request = Request {
@@ -97,6 +138,22 @@ impl EchoClient {
             EchoResponse::Say(r) => Ok(r),
         }
     }
+
+    fn repeat(
+        &mut self,
+        request: RepeatRequest,
+    ) -> TeaclaveServiceResponseResult<Vec<SayResponse>> {
+        let request = Request {
+            metadata: std::collections::HashMap::<String, String>::new(),
+            message: EchoRequest::Repeat(request),
+        };
+        self.channel
+            .invoke_stream(request)?
+            .map(|response| match response? {
+                EchoResponse::Say(r) => Ok(r),
+            })
+            .collect()
+    }
 }
 
 pub fn run_tests() -> bool {
@@ -104,7 +161,24 @@ pub fn run_tests() -> bool {
 
     start_echo_service();
 
-    run_tests!(echo_success)
+    run_tests!(
+        echo_success,
+        echo_stream_success,
+        echo_multiplexed_success,
+        session_cache_partitioned_by_identity,
+        refresh_drops_server_sessions,
+    )
+}
+
+fn load_end_cert() -> (Vec<u8>, Vec<u8>) {
+    let cert = pemfile::certs(&mut io::BufReader::new(
+        fs::File::open(END_FULLCHAIN).unwrap(),
+    ))
+    .unwrap();
+    let private_key =
+        pemfile::pkcs8_private_keys(&mut io::BufReader::new(fs::File::open(END_KEY).unwrap()))
+            .unwrap();
+    (cert[0].0.clone(), private_key[0].0.clone())
 }
 
 fn start_echo_service() {
@@ -112,16 +186,10 @@ fn start_echo_service() {
     use std::thread;
     use std::time::Duration;
     thread::spawn(move || {
-        let cert = pemfile::certs(&mut io::BufReader::new(
-            fs::File::open(END_FULLCHAIN).unwrap(),
-        ))
-        .unwrap();
-        let private_key =
-            &pemfile::pkcs8_private_keys(&mut io::BufReader::new(fs::File::open(END_KEY).unwrap()))
-                .unwrap()[0];
+        let (cert, private_key) = load_end_cert();
         let addr = "127.0.0.1:12345".parse().unwrap();
         let config = SgxTrustedTlsServerConfig::new()
-            .server_cert(&cert[0].as_ref(), &private_key.0)
+            .server_cert(&cert, &private_key)
             .unwrap();
         let mut server = SgxTrustedTlsServer::<EchoResponse, EchoRequest>::new(addr, config);
         server.start(EchoService).unwrap();
@@ -143,3 +211,137 @@ fn echo_success() {
     assert!(response_result.is_ok());
     assert!(response_result.unwrap().message == "Hello, World!");
 }
+
+fn echo_stream_success() {
+    use super::*;
+
+    let channel = Endpoint::new("localhost:12345").connect().unwrap();
+    let mut client = EchoClient::new(channel).unwrap();
+    let request = RepeatRequest {
+        message: "Hello, World!".to_string(),
+        times: 3,
+    };
+    let responses = client.repeat(request).unwrap();
+    assert_eq!(responses.len(), 3);
+    assert!(responses.iter().all(|r| r.message == "Hello, World!"));
+
+    // The channel serves other requests once the stream is read.
+    let request = SayRequest {
+        message: "Hello, World!".to_string(),
+    };
+    assert!(client.say(request).is_ok());
+}
+
+fn echo_multiplexed_success() {
+    use super::*;
+    use std::thread;
+
+    let channel = Endpoint::new("localhost:12345")
+        .connect_multiplexed()
+        .unwrap();
+    let mut client = EchoClient::new(channel).unwrap();
+
+    // The clients share the connection, each waiting for its own response.
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let mut client = client.try_clone().unwrap();
+            thread::spawn(move || {
+                let message = format!("Hello, {}!", i);
+                let request = SayRequest {
+                    message: message.clone(),
+                };
+                client.say(request).unwrap().message == message
+            })
+        })
+        .collect();
+    assert!(handles.into_iter().all(|handle| handle.join().unwrap()));
+
+    let request = RepeatRequest {
+        message: "Hello, World!".to_string(),
+        times: 3,
+    };
+    assert!(client.repeat(request).is_err());
+
+    let channel = Endpoint::new("localhost:12345").connect().unwrap();
+    let client = EchoClient::new(channel).unwrap();
+    assert!(client.try_clone().is_err());
+}
+
+fn accept_report(_: &AttestationReport) -> bool {
+    true
+}
+
+fn reject_report(_: &AttestationReport) -> bool {
+    false
+}
+
+fn cached_client_config(
+    cache: &SgxTrustedTlsSessionCache,
+    mr_enclave: u8,
+    root_ca: &[u8],
+    verifier: fn(&AttestationReport) -> bool,
+) -> rustls::ClientConfig {
+    let attrs = vec![EnclaveAttr {
+        measurement: EnclaveMeasurement::new([mr_enclave; 32], [0; 32]),
+    }];
+    SgxTrustedTlsClientConfig::new()
+        .attestation_report_verifier(attrs, root_ca, verifier)
+        .session_cache(cache)
+        .unwrap()
+        .client_config
+}
+
+fn session_cache_partitioned_by_identity() {
+    let cache = SgxTrustedTlsSessionCache::new();
+    let verified = cached_client_config(&cache, 1, b"root_ca", accept_report);
+    assert!(verified
+        .session_persistence
+        .put(b"server".to_vec(), b"session".to_vec()));
+
+    // Configs verifying the same identity resume the session.
+    let same = cached_client_config(&cache, 1, b"root_ca", accept_report);
+    assert!(same.session_persistence.get(b"server").is_some());
+
+    // A session is never resumed with other enclave attributes, root CA or
+    // verification function.
+    let other_attrs = cached_client_config(&cache, 2, b"root_ca", accept_report);
+    assert!(other_attrs.session_persistence.get(b"server").is_none());
+    let other_root_ca = cached_client_config(&cache, 1, b"other_root_ca", accept_report);
+    assert!(other_root_ca.session_persistence.get(b"server").is_none());
+    let other_verifier = cached_client_config(&cache, 1, b"root_ca", reject_report);
+    assert!(other_verifier.session_persistence.get(b"server").is_none());
+
+    // Sessions are only cached for attested servers.
+    assert!(SgxTrustedTlsClientConfig::new()
+        .session_cache(&cache)
+        .is_err());
+}
+
+fn refresh_drops_server_sessions() {
+    let (cert, private_key) = load_end_cert();
+    let attested_tls_config = Arc::new(RwLock::new(AttestedTlsConfig {
+        cert,
+        private_key,
+        time: TrustedTime::now(),
+        validity: std::time::Duration::from_secs(3600),
+    }));
+    let mut config =
+        SgxTrustedTlsServerConfig::from_attested_tls_config(attested_tls_config).unwrap();
+    assert!(config
+        .server_config()
+        .session_storage
+        .put(b"client".to_vec(), b"session".to_vec()));
+    assert!(config
+        .server_config()
+        .session_storage
+        .get(b"client")
+        .is_some());
+
+    // Clients have to verify the refreshed report in a full handshake.
+    config.refresh_server_config().unwrap();
+    assert!(config
+        .server_config()
+        .session_storage
+        .get(b"client")
+        .is_none());
+}
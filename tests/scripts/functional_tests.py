@@ -116,7 +116,7 @@ class TestAuthenticationService(unittest.TestCase):
 
         response = read_message(self.socket)
         self.assertEqual(
-            response, b'{"result":"err","request_error":"invalid request"}')
+            response, b'{"result":"err","code":"validation","request_error":"invalid request"}')
 
     def test_login_permission_denied(self):
         user_id = "invalid_id"
@@ -131,7 +131,9 @@ class TestAuthenticationService(unittest.TestCase):
 
         response = read_message(self.socket)
         self.assertEqual(
-            response, b'{"result":"err","request_error":"permission denied"}')
+            response,
+            b'{"result":"err","code":"auth","request_error":"permission denied"}'
+        )
 
 
 if __name__ == '__main__':
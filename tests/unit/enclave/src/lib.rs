@@ -33,6 +33,7 @@ use teaclave_types::{self, TeeServiceResult};
 #[handle_ecall]
 fn handle_run_test(_: &RunTestInput) -> TeeServiceResult<RunTestOutput> {
     let ret = check_all_passed!(
+        teaclave_frontend_service_enclave::tests::run_tests(),
         teaclave_management_service_enclave::tests::run_tests(),
         teaclave_storage_service_enclave::tests::run_tests(),
         teaclave_access_control_service_enclave::tests::run_tests(),
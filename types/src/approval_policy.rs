@@ -0,0 +1,119 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const APPROVAL_POLICIES_PREFIX: &str = "approvalpolicies";
+
+/// Pre-registered consent of a data owner to tasks running one of the
+/// functions on the data, created by one of the creators.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApprovalPolicy {
+    pub policy_id: Uuid,
+    pub data_id: ExternalID,
+    pub function_ids: Vec<ExternalID>,
+    pub creators: UserList,
+}
+
+impl ApprovalPolicy {
+    pub fn new(data_id: ExternalID, function_ids: Vec<ExternalID>, creators: UserList) -> Self {
+        Self {
+            policy_id: Uuid::new_v4(),
+            data_id,
+            function_ids,
+            creators,
+        }
+    }
+
+    pub fn matches(&self, data_id: &ExternalID, ts: &TaskState) -> bool {
+        &self.data_id == data_id
+            && self.function_ids.contains(&ts.function_id)
+            && self.creators.contains(&ts.creator)
+    }
+}
+
+/// Approval policies registered by a user, keyed by the user id.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ApprovalPolicies {
+    pub user_id: UserID,
+    pub policies: Vec<ApprovalPolicy>,
+}
+
+impl Storable for ApprovalPolicies {
+    fn key_prefix() -> &'static str {
+        APPROVAL_POLICIES_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        user_uuid(&self.user_id)
+    }
+}
+
+impl ApprovalPolicies {
+    pub fn new(user_id: UserID) -> Self {
+        Self {
+            user_id,
+            policies: Vec::new(),
+        }
+    }
+
+    pub fn external_id_of(user_id: &UserID) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), user_uuid(user_id))
+    }
+
+    pub fn add(&mut self, policy: ApprovalPolicy) {
+        self.policies.push(policy);
+    }
+
+    pub fn remove(&mut self, policy_id: &Uuid) -> bool {
+        let len = self.policies.len();
+        self.policies
+            .retain(|policy| &policy.policy_id != policy_id);
+        self.policies.len() != len
+    }
+
+    /// The task is approved on behalf of the user only if every file of the
+    /// task owned by the user is covered by a matching policy. Users owning
+    /// no file of the task, e.g., the creator, always approve by themselves.
+    pub fn approve(&self, ts: &TaskState) -> bool {
+        let inputs = ts
+            .assigned_inputs
+            .clone()
+            .into_iter()
+            .map(|(_, file)| (file.external_id(), file.owner));
+        let outputs = ts
+            .assigned_outputs
+            .clone()
+            .into_iter()
+            .map(|(_, file)| (file.external_id(), file.owner));
+        let owned_data: Vec<ExternalID> = inputs
+            .chain(outputs)
+            .filter(|(_, owner)| owner.contains(&self.user_id))
+            .map(|(data_id, _)| data_id)
+            .collect();
+
+        !owned_data.is_empty()
+            && owned_data.iter().all(|data_id| {
+                self.policies
+                    .iter()
+                    .any(|policy| policy.matches(data_id, ts))
+            })
+    }
+}
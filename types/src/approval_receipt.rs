@@ -0,0 +1,61 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+/// Evidence of a user approving a task, signed by the authentication service.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ApprovalReceipt {
+    pub user_id: UserID,
+    pub task_id: Uuid,
+    pub task_spec_hash: String,
+    pub timestamp: u64,
+    pub signature: Vec<u8>,
+    pub public_key: Vec<u8>,
+}
+
+impl ApprovalReceipt {
+    pub fn new(user_id: UserID, task_id: Uuid, task_spec_hash: impl ToString) -> Self {
+        Self {
+            user_id,
+            task_id,
+            task_spec_hash: task_spec_hash.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// The signed content: user id, task id, task spec hash and timestamp.
+    pub fn message(&self) -> Vec<u8> {
+        format!(
+            "{}|{}|{}|{}",
+            self.user_id, self.task_id, self.task_spec_hash, self.timestamp
+        )
+        .into_bytes()
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        let public_key =
+            ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, &self.public_key);
+        public_key
+            .verify(&self.message(), &self.signature)
+            .map_err(|_| anyhow!("invalid approval receipt signature"))
+    }
+}
@@ -0,0 +1,148 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+use crate::*;
+use anyhow::{anyhow, bail, ensure, Result};
+use serde_json::Value;
+use std::convert::TryFrom;
+use std::prelude::v1::*;
+
+const TEMPLATE_PREFIX: &str = "{{task:";
+const TEMPLATE_SUFFIX: &str = "}}";
+const RESULT_FIELD: &str = "result";
+
+/// A function argument taking its value from the return value of a finished
+/// task, written as `{{task:<task id>:result.<path>}}`, e.g.,
+/// `{{task:task-<uuid>:result.threshold}}`. The return value is parsed as
+/// JSON, and the path selects fields of objects and indices of arrays in it.
+/// Without a path, i.e., `{{task:<task id>:result}}`, the argument is the
+/// whole return value, or the return value as a string if it is not JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgumentTemplate {
+    pub task_id: ExternalID,
+    pub path: Vec<String>,
+}
+
+impl ArgumentTemplate {
+    /// Parses an argument, which is a template if it is a string starting
+    /// with `{{task:`.
+    pub fn parse(argument: &Value) -> Option<Result<Self>> {
+        match argument {
+            Value::String(s) if s.starts_with(TEMPLATE_PREFIX) => Some(Self::parse_str(s)),
+            _ => None,
+        }
+    }
+
+    fn parse_str(s: &str) -> Result<Self> {
+        ensure!(
+            s.ends_with(TEMPLATE_SUFFIX),
+            "Unterminated argument template: {}",
+            s
+        );
+        let body = &s[TEMPLATE_PREFIX.len()..s.len() - TEMPLATE_SUFFIX.len()];
+        let pos = body
+            .rfind(':')
+            .ok_or_else(|| anyhow!("Invalid argument template: {}", s))?;
+        let (task_id, selector) = (&body[..pos], &body[pos + 1..]);
+        let task_id = ExternalID::try_from(task_id)?;
+        ensure!(
+            task_id.prefix == TaskState::key_prefix(),
+            "Not a task in argument template: {}",
+            s
+        );
+        let mut segments = selector.split('.');
+        ensure!(
+            segments.next() == Some(RESULT_FIELD),
+            "Argument template does not select the result: {}",
+            s
+        );
+        let path: Vec<String> = segments.map(ToOwned::to_owned).collect();
+        ensure!(
+            path.iter().all(|segment| !segment.is_empty()),
+            "Empty field in argument template: {}",
+            s
+        );
+        Ok(Self { task_id, path })
+    }
+
+    /// Selects the value of the template from the state of the task, which
+    /// must have finished successfully with its return value in the result.
+    pub fn resolve(&self, ts: &TaskState) -> Result<Value> {
+        ensure!(
+            ts.status == TaskStatus::Finished,
+            "Task {} has not finished",
+            self.task_id.to_string()
+        );
+        let return_value = match &ts.result {
+            TaskResult::Ok(outputs) if outputs.return_value_handle.is_none() => {
+                &outputs.return_value
+            }
+            TaskResult::Ok(_) => bail!(
+                "Return value of task {} is too large",
+                self.task_id.to_string()
+            ),
+            _ => bail!("Task {} failed", self.task_id.to_string()),
+        };
+
+        if self.path.is_empty() {
+            let value = serde_json::from_slice(return_value).unwrap_or_else(|_| {
+                Value::String(String::from_utf8_lossy(return_value).into_owned())
+            });
+            return Ok(value);
+        }
+        let root: Value = serde_json::from_slice(return_value)
+            .map_err(|_| anyhow!("Result of task {} is not JSON", self.task_id.to_string()))?;
+        let mut value = &root;
+        for segment in self.path.iter() {
+            let selected = match value {
+                Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => value.get(segment.as_str()),
+            };
+            value = selected.ok_or_else(|| {
+                anyhow!(
+                    "Field not found in the result of task {}: {}",
+                    self.task_id.to_string(),
+                    segment
+                )
+            })?;
+        }
+        Ok(value.to_owned())
+    }
+}
+
+impl FunctionArguments {
+    /// Templates of the arguments by their names.
+    pub fn templates(&self) -> Result<Vec<(String, ArgumentTemplate)>> {
+        self.inner()
+            .iter()
+            .filter_map(|(name, argument)| {
+                ArgumentTemplate::parse(argument).map(|template| Ok((name.to_owned(), template?)))
+            })
+            .collect()
+    }
+
+    /// Replaces the templates with the values they select from the tasks.
+    pub fn resolve_templates<F>(&mut self, mut task: F) -> Result<()>
+    where
+        F: FnMut(&ExternalID) -> Result<TaskState>,
+    {
+        for (name, template) in self.templates()? {
+            let value = template.resolve(&task(&template.task_id)?)?;
+            self.inner_mut().insert(name, value);
+        }
+        Ok(())
+    }
+}
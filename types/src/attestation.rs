@@ -21,7 +21,7 @@ use std::prelude::v1::*;
 use std::collections::HashMap;
 
 use anyhow::{bail, ensure, Result};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 
 pub type SgxMeasurement = [u8; sgx_types::SGX_HASH_SIZE];
 
@@ -62,13 +62,38 @@ pub struct EnclaveAttr {
     pub measurement: EnclaveMeasurement,
 }
 
+/// Build metadata of an enclave recorded next to its measurement, so that a
+/// measurement can be traced back to the source and toolchain it is built
+/// from. Fields unknown to the build are left empty.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Eq, PartialEq)]
+#[serde(default)]
+pub struct EnclaveBuildInfo {
+    pub git_commit: String,
+    pub rustc: String,
+    pub rust_sgx_sdk: String,
+    pub sgx_sdk: String,
+    pub sgx_mode: String,
+    pub build_type: String,
+    pub build_flags: String,
+}
+
 pub struct EnclaveInfo {
     pub measurements: HashMap<String, EnclaveMeasurement>,
+    pub builds: HashMap<String, EnclaveBuildInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnclaveInfoEntry {
+    #[serde(deserialize_with = "from_hex")]
+    mr_signer: SgxMeasurement,
+    #[serde(deserialize_with = "from_hex")]
+    mr_enclave: SgxMeasurement,
+    build: Option<EnclaveBuildInfo>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(transparent)]
-struct EnclaveInfoToml(HashMap<String, EnclaveMeasurement>);
+struct EnclaveInfoToml(HashMap<String, EnclaveInfoEntry>);
 
 impl EnclaveInfo {
     pub fn verify_and_new<T, U>(
@@ -101,12 +126,17 @@ impl EnclaveInfo {
         let config: EnclaveInfoToml = toml::from_slice(enclave_info)
             .expect("Content not correct, unable to load enclave info.");
         let mut info_map = std::collections::HashMap::new();
+        let mut build_map = std::collections::HashMap::new();
         for (k, v) in config.0 {
+            if let Some(build) = v.build {
+                build_map.insert(k.clone(), build);
+            }
             info_map.insert(k, EnclaveMeasurement::new(v.mr_enclave, v.mr_signer));
         }
 
         Self {
             measurements: info_map,
+            builds: build_map,
         }
     }
 
@@ -135,6 +165,12 @@ impl EnclaveInfo {
         true
     }
 
+    /// Returns the build metadata of a service, if its enclave info records
+    /// one.
+    pub fn get_enclave_build(&self, service_name: &str) -> Option<&EnclaveBuildInfo> {
+        self.builds.get(service_name)
+    }
+
     pub fn get_enclave_attr(&self, service_name: &str) -> Option<EnclaveAttr> {
         if let Some(measurement) = self.measurements.get(service_name) {
             Some(EnclaveAttr {
@@ -66,20 +66,152 @@ pub enum TeeServiceError {
 
 pub type TeeServiceResult<T> = std::result::Result<T, TeeServiceError>;
 
-#[derive(Error, Debug, Serialize, Deserialize, PartialEq)]
+/// Kind of a service error. The code is serialized in every RPC error, so that
+/// clients can branch on it without parsing the error message.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
+pub enum TeaclaveErrorCode {
+    /// The user is not authenticated or not permitted to do the request.
+    Auth,
+    /// The request is malformed or has invalid arguments.
+    Validation,
+    /// The request exceeds a configured limit.
+    Quota,
+    /// The requested resource does not exist.
+    NotFound,
+    /// The request conflicts with the current state of the resource.
+    Conflict,
+    /// The service failed to handle a valid request.
+    Internal,
+    /// The remote enclave cannot be attested.
+    Attestation,
+}
+
+impl fmt::Display for TeaclaveErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = match self {
+            TeaclaveErrorCode::Auth => "auth",
+            TeaclaveErrorCode::Validation => "validation",
+            TeaclaveErrorCode::Quota => "quota",
+            TeaclaveErrorCode::NotFound => "not_found",
+            TeaclaveErrorCode::Conflict => "conflict",
+            TeaclaveErrorCode::Internal => "internal",
+            TeaclaveErrorCode::Attestation => "attestation",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+#[derive(Error, Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(into = "ServiceResponseErrorRepr", from = "ServiceResponseErrorRepr")]
 pub enum TeaclaveServiceResponseError {
-    #[error("Request error: {0}")]
-    RequestError(String),
+    #[error("Request error: {message}")]
+    RequestError {
+        code: TeaclaveErrorCode,
+        message: String,
+    },
     #[error("Connection error: {0}")]
     ConnectionError(String),
+    #[error("Attestation error: {0}")]
+    AttestationError(String),
     #[error("Internal error: {0}")]
     InternalError(String),
+    #[error("Validation error: {field}: {reason}")]
+    ValidationError { field: String, reason: String },
 }
 
+impl TeaclaveServiceResponseError {
+    pub fn request_error(code: TeaclaveErrorCode, message: impl ToString) -> Self {
+        TeaclaveServiceResponseError::RequestError {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn code(&self) -> TeaclaveErrorCode {
+        match self {
+            TeaclaveServiceResponseError::RequestError { code, .. } => *code,
+            TeaclaveServiceResponseError::ConnectionError(_) => TeaclaveErrorCode::Internal,
+            TeaclaveServiceResponseError::AttestationError(_) => TeaclaveErrorCode::Attestation,
+            TeaclaveServiceResponseError::InternalError(_) => TeaclaveErrorCode::Internal,
+            TeaclaveServiceResponseError::ValidationError { .. } => TeaclaveErrorCode::Validation,
+        }
+    }
+}
+
+// Errors not raised by the services themselves are internal errors, and their
+// messages are kept for debugging.
 impl From<anyhow::Error> for TeaclaveServiceResponseError {
     fn from(error: anyhow::Error) -> Self {
-        TeaclaveServiceResponseError::RequestError(error.to_string())
+        TeaclaveServiceResponseError::request_error(TeaclaveErrorCode::Internal, error)
+    }
+}
+
+// Wire format of the errors, e.g.,
+// {"code":"auth","request_error":"permission denied"}.
+#[derive(Serialize, Deserialize)]
+struct ServiceResponseErrorRepr {
+    code: TeaclaveErrorCode,
+    #[serde(flatten)]
+    error: ServiceResponseErrorKind,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ServiceResponseErrorKind {
+    RequestError(String),
+    ConnectionError(String),
+    AttestationError(String),
+    InternalError(String),
+    ValidationError { field: String, reason: String },
+}
+
+impl From<TeaclaveServiceResponseError> for ServiceResponseErrorRepr {
+    fn from(error: TeaclaveServiceResponseError) -> Self {
+        let code = error.code();
+        let error = match error {
+            TeaclaveServiceResponseError::RequestError { message, .. } => {
+                ServiceResponseErrorKind::RequestError(message)
+            }
+            TeaclaveServiceResponseError::ConnectionError(message) => {
+                ServiceResponseErrorKind::ConnectionError(message)
+            }
+            TeaclaveServiceResponseError::AttestationError(message) => {
+                ServiceResponseErrorKind::AttestationError(message)
+            }
+            TeaclaveServiceResponseError::InternalError(message) => {
+                ServiceResponseErrorKind::InternalError(message)
+            }
+            TeaclaveServiceResponseError::ValidationError { field, reason } => {
+                ServiceResponseErrorKind::ValidationError { field, reason }
+            }
+        };
+        Self { code, error }
+    }
+}
+
+impl From<ServiceResponseErrorRepr> for TeaclaveServiceResponseError {
+    fn from(repr: ServiceResponseErrorRepr) -> Self {
+        match repr.error {
+            ServiceResponseErrorKind::RequestError(message) => {
+                TeaclaveServiceResponseError::RequestError {
+                    code: repr.code,
+                    message,
+                }
+            }
+            ServiceResponseErrorKind::ConnectionError(message) => {
+                TeaclaveServiceResponseError::ConnectionError(message)
+            }
+            ServiceResponseErrorKind::AttestationError(message) => {
+                TeaclaveServiceResponseError::AttestationError(message)
+            }
+            ServiceResponseErrorKind::InternalError(message) => {
+                TeaclaveServiceResponseError::InternalError(message)
+            }
+            ServiceResponseErrorKind::ValidationError { field, reason } => {
+                TeaclaveServiceResponseError::ValidationError { field, reason }
+            }
+        }
     }
 }
 
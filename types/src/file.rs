@@ -15,8 +15,8 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use crate::storage::Storable;
-use crate::{FileAuthTag, FileCrypto, OwnerList};
+use crate::storage::{IndexTerm, Storable};
+use crate::{ExternalID, FileAuthTag, FileCredential, FileCrypto, OwnerList};
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::prelude::v1::*;
@@ -25,6 +25,24 @@ use uuid::Uuid;
 
 const INPUT_FILE_PREFIX: &str = "input";
 const OUTPUT_FILE_PREFIX: &str = "output";
+/// Indexes of the input and output files by their owners.
+pub const INPUT_FILE_OWNER_INDEX: &str = "input-owner";
+pub const OUTPUT_FILE_OWNER_INDEX: &str = "output-owner";
+/// Indexes of the deleted input and output files by the time they are
+/// deleted at.
+pub const INPUT_FILE_DELETED_INDEX: &str = "input-deleted";
+pub const OUTPUT_FILE_DELETED_INDEX: &str = "output-deleted";
+// Placeholder of the URL of an output file registered before its destination
+// is provisioned.
+const DEFERRED_OUTPUT_URL: &str = "teaclave:deferred";
+
+fn owner_terms(index: &str, owner: &OwnerList) -> Vec<IndexTerm> {
+    owner
+        .uids
+        .iter()
+        .map(|uid| IndexTerm::new(index, uid.to_string()))
+        .collect()
+}
 
 fn create_uuid() -> Uuid {
     Uuid::new_v4()
@@ -37,6 +55,34 @@ pub struct TeaclaveInputFile {
     pub crypto_info: FileCrypto,
     pub owner: OwnerList,
     pub uuid: Uuid,
+    /// Broker releasing the key of the file, which is then not held by
+    /// Teaclave at all.
+    #[serde(default)]
+    pub key_broker: Option<KeyBrokerReference>,
+    /// Seconds since the Unix epoch the file is deleted at. A deleted file is
+    /// purged with its key after the retention window of the storage.
+    #[serde(default)]
+    pub deleted_at: Option<u64>,
+}
+
+/// A key broker outside of Teaclave holding the key of an input file. The
+/// broker attests the execution enclave running a task before it releases the
+/// key to it.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct KeyBrokerReference {
+    /// Address of the broker, e.g., `broker.example.com:7700`
+    pub address: String,
+    /// Id of the key in the broker
+    pub key_id: String,
+}
+
+impl KeyBrokerReference {
+    pub fn new(address: impl Into<String>, key_id: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            key_id: key_id.into(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -46,6 +92,14 @@ pub struct TeaclaveOutputFile {
     pub crypto_info: FileCrypto,
     pub owner: OwnerList,
     pub uuid: Uuid,
+    #[serde(default)]
+    pub credential: Option<FileCredential>,
+    /// Task producing the file, set once the task finished.
+    #[serde(default)]
+    pub producer: Option<ExternalID>,
+    /// Seconds since the Unix epoch the file is deleted at.
+    #[serde(default)]
+    pub deleted_at: Option<u64>,
 }
 
 impl TeaclaveInputFile {
@@ -61,6 +115,19 @@ impl TeaclaveInputFile {
             crypto_info,
             owner: owner.into(),
             uuid: create_uuid(),
+            key_broker: None,
+            deleted_at: None,
+        }
+    }
+
+    /// Sets the broker of the key of the file. The registered crypto info is
+    /// raw, i.e., no key, until the execution service replaces it with the
+    /// key released by the broker.
+    pub fn key_broker(self, key_broker: KeyBrokerReference) -> Self {
+        Self {
+            crypto_info: FileCrypto::Raw,
+            key_broker: Some(key_broker),
+            ..self
         }
     }
 
@@ -73,6 +140,8 @@ impl TeaclaveInputFile {
             crypto_info: output.crypto_info,
             owner: output.owner,
             uuid: output.uuid,
+            key_broker: None,
+            deleted_at: None,
         };
         Ok(input)
     }
@@ -86,6 +155,18 @@ impl Storable for TeaclaveInputFile {
     fn uuid(&self) -> Uuid {
         self.uuid
     }
+
+    // A deleted file is no longer listed by its owners.
+    fn index_terms(&self) -> Vec<IndexTerm> {
+        match self.deleted_at {
+            Some(deleted_at) => vec![IndexTerm::deleted_at(INPUT_FILE_DELETED_INDEX, deleted_at)],
+            None => owner_terms(INPUT_FILE_OWNER_INDEX, &self.owner),
+        }
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
 }
 
 impl TeaclaveOutputFile {
@@ -100,12 +181,47 @@ impl TeaclaveOutputFile {
             crypto_info,
             owner: owner.into(),
             uuid: create_uuid(),
+            credential: None,
+            producer: None,
+            deleted_at: None,
         }
     }
 
-    pub fn assign_cmac(&mut self, cmac: &FileAuthTag) -> Result<()> {
+    /// Output file whose URL is set later with `set_url`, before the task it
+    /// is assigned to is invoked.
+    pub fn new_deferred(crypto_info: FileCrypto, owner: impl Into<OwnerList>) -> Self {
+        let url = Url::parse(DEFERRED_OUTPUT_URL).expect("valid placeholder url");
+        Self::new(url, crypto_info, owner)
+    }
+
+    /// Output file kept by the platform for its owners, which they can only
+    /// use as the input of other tasks.
+    pub fn new_fusion(owner: impl Into<OwnerList>) -> Self {
+        let uuid = create_uuid();
+        let url = format!("fusion:///TEACLAVE_FUSION_BASE/{}.fusion", uuid);
+        let url = Url::parse(&url).expect("valid fusion url");
+        Self::new(url, FileCrypto::default(), owner)
+    }
+
+    pub fn credential(self, credential: Option<FileCredential>) -> Self {
+        Self { credential, ..self }
+    }
+
+    pub fn is_deferred(&self) -> bool {
+        self.url.as_str() == DEFERRED_OUTPUT_URL
+    }
+
+    pub fn set_url(&mut self, url: Url, credential: Option<FileCredential>) -> Result<()> {
+        anyhow::ensure!(self.is_deferred(), "Output file url already set");
+        self.url = url;
+        self.credential = credential;
+        Ok(())
+    }
+
+    pub fn assign_cmac(&mut self, cmac: &FileAuthTag, producer: ExternalID) -> Result<()> {
         anyhow::ensure!(self.cmac.is_none(), "Cannot overwrite output file cmac");
         self.cmac = Some(cmac.to_owned());
+        self.producer = Some(producer);
         Ok(())
     }
 }
@@ -118,4 +234,15 @@ impl Storable for TeaclaveOutputFile {
     fn uuid(&self) -> Uuid {
         self.uuid
     }
+
+    fn index_terms(&self) -> Vec<IndexTerm> {
+        match self.deleted_at {
+            Some(deleted_at) => vec![IndexTerm::deleted_at(OUTPUT_FILE_DELETED_INDEX, deleted_at)],
+            None => owner_terms(OUTPUT_FILE_OWNER_INDEX, &self.owner),
+        }
+    }
+
+    fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
 }
@@ -15,14 +15,23 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use crate::{FileCredential, TaskPriority};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::prelude::v1::*;
 
+/// Prefix of the scheme of inputs uploaded in chunks, e.g.,
+/// `chunked+https://host/dataset/v2.manifest`, which the file agent assembles
+/// from the chunks listed in the manifest at the url without the prefix.
+pub const CHUNKED_URL_SCHEME_PREFIX: &str = "chunked+";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum HandleFileCommand {
     Download,
     Upload,
+    /// Accepts the inputs pushed by clients to the upload endpoint of the
+    /// worker host for the `push:` urls, which are then downloaded as usual.
+    OpenPushSlots,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -30,6 +39,10 @@ pub struct FileAgentRequest {
     pub cmd: HandleFileCommand,
     pub info: Vec<HandleFileInfo>,
     pub fusion_base: PathBuf,
+    /// Priority of the task requesting the files, whose cgroup the transfer
+    /// is weighted by on the worker host.
+    #[serde(default)]
+    pub priority: Option<TaskPriority>,
 }
 
 impl FileAgentRequest {
@@ -45,6 +58,14 @@ impl FileAgentRequest {
             cmd,
             info: info.into_iter().map(|x| x.into()).collect(),
             fusion_base: fusion_base.as_ref().to_owned(),
+            priority: None,
+        }
+    }
+
+    pub fn priority(self, priority: TaskPriority) -> Self {
+        Self {
+            priority: Some(priority),
+            ..self
         }
     }
 }
@@ -53,6 +74,18 @@ impl FileAgentRequest {
 pub struct HandleFileInfo {
     pub local: PathBuf,
     pub remote: url::Url,
+    #[serde(default)]
+    pub credential: Option<FileCredential>,
+    /// Only the range is downloaded if set.
+    #[serde(default)]
+    pub range: Option<FileRange>,
+}
+
+/// Range of bytes of a file, which is cut short at the end of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FileRange {
+    pub offset: u64,
+    pub length: u64,
 }
 
 impl HandleFileInfo {
@@ -60,6 +93,19 @@ impl HandleFileInfo {
         HandleFileInfo {
             local: local.as_ref().to_owned(),
             remote: remote.to_owned(),
+            credential: None,
+            range: None,
+        }
+    }
+
+    pub fn credential(self, credential: Option<FileCredential>) -> Self {
+        Self { credential, ..self }
+    }
+
+    pub fn range(self, offset: u64, length: u64) -> Self {
+        Self {
+            range: Some(FileRange { offset, length }),
+            ..self
         }
     }
 }
@@ -0,0 +1,116 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use anyhow::{bail, ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::prelude::v1::*;
+use url::Url;
+
+/// Credential used by the file agent to upload an output file to its
+/// destination, or to download a file from a storage backend requiring one.
+/// Each output file carries its own credential, so the results of a task can
+/// be written to storage backends owned by different parties.
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub enum FileCredential {
+    /// Bearer token sent in the `Authorization` header of an HTTP(S) PUT.
+    Bearer { token: String },
+    /// AWS access key of an `s3://bucket/key` destination. Requests are
+    /// signed with Signature Version 4. An endpoint can be given for
+    /// S3-compatible services, which are accessed with path-style URLs.
+    AwsS3 {
+        access_key_id: String,
+        secret_access_key: String,
+        region: String,
+        endpoint: Option<Url>,
+    },
+    /// Shared access signature of an Azure Blob Storage container.
+    AzureSas { token: String },
+}
+
+impl fmt::Debug for FileCredential {
+    // Never log secrets.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FileCredential({})", self.schema())
+    }
+}
+
+impl FileCredential {
+    pub const BEARER_SCHEMA: &'static str = "bearer";
+    pub const AWS_S3_SCHEMA: &'static str = "aws_s3";
+    pub const AZURE_SAS_SCHEMA: &'static str = "azure_sas";
+
+    pub fn new(
+        schema: &str,
+        access_key_id: &str,
+        secret: &str,
+        region: &str,
+        endpoint: &str,
+    ) -> Result<Self> {
+        ensure!(!secret.is_empty(), "Empty secret of credential");
+        let credential = match schema {
+            Self::BEARER_SCHEMA => FileCredential::Bearer {
+                token: secret.to_string(),
+            },
+            Self::AWS_S3_SCHEMA => {
+                ensure!(!access_key_id.is_empty(), "Empty access key id");
+                ensure!(!region.is_empty(), "Empty region");
+                let endpoint = if endpoint.is_empty() {
+                    None
+                } else {
+                    Some(Url::parse(endpoint)?)
+                };
+                FileCredential::AwsS3 {
+                    access_key_id: access_key_id.to_string(),
+                    secret_access_key: secret.to_string(),
+                    region: region.to_string(),
+                    endpoint,
+                }
+            }
+            Self::AZURE_SAS_SCHEMA => FileCredential::AzureSas {
+                token: secret.trim_start_matches('?').to_string(),
+            },
+            _ => bail!("Invalid credential schema: {}", schema),
+        };
+
+        Ok(credential)
+    }
+
+    pub fn schema(&self) -> &str {
+        match self {
+            FileCredential::Bearer { .. } => Self::BEARER_SCHEMA,
+            FileCredential::AwsS3 { .. } => Self::AWS_S3_SCHEMA,
+            FileCredential::AzureSas { .. } => Self::AZURE_SAS_SCHEMA,
+        }
+    }
+
+    /// Checks that the credential can be used to upload to the url.
+    pub fn check_url(&self, url: &Url) -> Result<()> {
+        let supported = match self {
+            FileCredential::Bearer { .. } => url.scheme() == "https" || url.scheme() == "http",
+            FileCredential::AwsS3 { .. } => url.scheme() == "s3" && url.host_str().is_some(),
+            FileCredential::AzureSas { .. } => url.scheme() == "https",
+        };
+        ensure!(
+            supported,
+            "Credential {} cannot be used for url scheme {}",
+            self.schema(),
+            url.scheme()
+        );
+        Ok(())
+    }
+}
@@ -15,7 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use crate::{ExecutorType, Storable, UserID};
+use crate::{ExecutorType, IndexTerm, PostProcessingStep, ResourceProfile, Storable, UserID};
 use serde::{Deserialize, Serialize};
 use std::prelude::v1::*;
 use uuid::Uuid;
@@ -50,7 +50,46 @@ impl FunctionOutput {
     }
 }
 
+/// Capabilities a function needs in the sandbox, declared when it is
+/// registered and shown to the participants approving its tasks. The worker
+/// denies the function anything it did not declare.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct FunctionCapabilities {
+    /// Writing intermediate artifacts to the scratch disk of the workflow
+    pub scratch_disk: bool,
+    /// Writing data out of the enclave to the output files of the task
+    pub egress: bool,
+    /// Maximum size of each output file in bytes, zero means unlimited
+    pub max_output_size: u64,
+}
+
+impl FunctionCapabilities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn scratch_disk(self, scratch_disk: bool) -> Self {
+        Self {
+            scratch_disk,
+            ..self
+        }
+    }
+
+    pub fn egress(self, egress: bool) -> Self {
+        Self { egress, ..self }
+    }
+
+    pub fn max_output_size(self, max_output_size: u64) -> Self {
+        Self {
+            max_output_size,
+            ..self
+        }
+    }
+}
+
 const FUNCION_PREFIX: &str = "function";
+/// Index of the functions by their owners.
+pub const FUNCTION_OWNER_INDEX: &str = "function-owner";
 
 #[derive(Default, Debug, Deserialize, Serialize)]
 pub struct Function {
@@ -64,6 +103,32 @@ pub struct Function {
     pub inputs: Vec<FunctionInput>,
     pub outputs: Vec<FunctionOutput>,
     pub owner: UserID,
+    /// Resources the function is expected to need. The field is left out
+    /// when nothing is specified to keep the definition, and thus the version
+    /// of existing functions unchanged.
+    #[serde(default, skip_serializing_if = "ResourceProfile::is_default")]
+    pub resource_profile: ResourceProfile,
+    /// Named entry points exposed by the payload, one of which can be chosen
+    /// when a task is created. The default entry point of the executor is
+    /// used otherwise. Left out when empty for the same reason as the
+    /// resource profile.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entry_points: Vec<String>,
+    /// Functions registered without capabilities are not confined. The field
+    /// is left out when absent to keep the definition, and thus the version
+    /// of existing functions unchanged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<FunctionCapabilities>,
+    /// Hex encoded MRENCLAVE of the execution enclaves allowed to run the
+    /// function, any of them when empty. Left out when empty for the same
+    /// reason as the capabilities.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub worker_measurements: Vec<String>,
+    /// Steps applied to the return value before it leaves the execution
+    /// enclave, in order. Left out when empty for the same reason as the
+    /// capabilities.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_processing: Vec<PostProcessingStep>,
 }
 
 impl Function {
@@ -122,6 +187,41 @@ impl Function {
             ..self
         }
     }
+
+    pub fn resource_profile(self, resource_profile: ResourceProfile) -> Self {
+        Self {
+            resource_profile,
+            ..self
+        }
+    }
+
+    pub fn entry_points(self, entry_points: Vec<String>) -> Self {
+        Self {
+            entry_points,
+            ..self
+        }
+    }
+
+    pub fn capabilities(self, capabilities: FunctionCapabilities) -> Self {
+        Self {
+            capabilities: Some(capabilities),
+            ..self
+        }
+    }
+
+    pub fn worker_measurements(self, worker_measurements: Vec<String>) -> Self {
+        Self {
+            worker_measurements,
+            ..self
+        }
+    }
+
+    pub fn post_processing(self, post_processing: Vec<PostProcessingStep>) -> Self {
+        Self {
+            post_processing,
+            ..self
+        }
+    }
 }
 
 impl Storable for Function {
@@ -132,4 +232,8 @@ impl Storable for Function {
     fn uuid(&self) -> Uuid {
         self.id
     }
+
+    fn index_terms(&self) -> Vec<IndexTerm> {
+        vec![IndexTerm::new(FUNCTION_OWNER_INDEX, self.owner.to_string())]
+    }
 }
@@ -0,0 +1,86 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::prelude::v1::*;
+
+/// Largest sample input, and largest output returned, of a function test in
+/// bytes.
+pub const MAX_FUNCTION_TEST_FILE_SIZE: usize = 64 * 1024;
+/// Seconds a function test may run, whatever the function declares.
+pub const FUNCTION_TEST_TIMEOUT_SECS: u64 = 30;
+
+/// A test run of a function by its owner in a throwaway sandbox, against
+/// sample inputs instead of registered files. The outputs are returned in the
+/// result of the test to the owner instead of being uploaded.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct FunctionTest {
+    /// Contents of the inputs by their names in the function, empty for the
+    /// inputs without samples
+    pub sample_inputs: HashMap<String, Vec<u8>>,
+    /// Names of the outputs in the function
+    pub outputs: Vec<String>,
+}
+
+impl FunctionTest {
+    pub fn new(sample_inputs: HashMap<String, Vec<u8>>, function: &Function) -> Result<Self> {
+        let inputs_spec: HashSet<&String> = function.inputs.iter().map(|f| &f.name).collect();
+        for (fname, sample) in sample_inputs.iter() {
+            ensure!(inputs_spec.contains(fname), "unknown input: {}", fname);
+            ensure!(
+                sample.len() <= MAX_FUNCTION_TEST_FILE_SIZE,
+                "sample input too large: {}",
+                fname
+            );
+        }
+
+        let mut sample_inputs = sample_inputs;
+        for fname in inputs_spec {
+            sample_inputs.entry(fname.to_owned()).or_default();
+        }
+        let outputs = function.outputs.iter().map(|f| f.name.clone()).collect();
+        Ok(Self {
+            sample_inputs,
+            outputs,
+        })
+    }
+
+    /// Tests are confined to what the function declares, and never write to
+    /// the scratch disk of a workflow or outputs larger than returned.
+    pub fn capabilities(declared: Option<FunctionCapabilities>) -> FunctionCapabilities {
+        let declared = declared.unwrap_or_else(|| FunctionCapabilities::new().egress(true));
+        let max_output_size = match declared.max_output_size {
+            0 => MAX_FUNCTION_TEST_FILE_SIZE as u64,
+            size => size.min(MAX_FUNCTION_TEST_FILE_SIZE as u64),
+        };
+        declared
+            .scratch_disk(false)
+            .max_output_size(max_output_size)
+    }
+
+    /// Tests run no longer than the test timeout.
+    pub fn resource_profile(declared: ResourceProfile) -> ResourceProfile {
+        let timeout = match declared.timeout {
+            0 => FUNCTION_TEST_TIMEOUT_SECS,
+            timeout => timeout.min(FUNCTION_TEST_TIMEOUT_SECS),
+        };
+        declared.timeout(timeout)
+    }
+}
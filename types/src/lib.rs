@@ -23,41 +23,113 @@ extern crate sgx_tstd as std;
 #[cfg(feature = "mesalock_sgx")]
 use std::prelude::v1::*;
 
+mod approval_policy;
+mod approval_receipt;
+mod argument_template;
 mod attestation;
 mod crypto;
 mod error;
 mod file;
 mod file_agent;
+mod file_credential;
 mod function;
+mod function_test;
 mod macros;
+mod model;
+mod post_processing;
+mod prewarm_task;
+mod request_signature;
+mod resource_limits;
+mod resource_profile;
+mod return_value;
+mod reusable_result;
 mod staged_file;
 mod staged_function;
 mod staged_task;
 mod storage;
 mod task;
+mod task_approval_window;
+mod task_archive;
+mod task_deadline;
+mod task_dependency;
+mod task_dispatch;
+mod task_estimate;
+mod task_index;
+mod task_key_exchange;
+mod task_manifest;
+mod task_notification;
+mod task_retry;
+mod task_schedule;
+mod task_share_link;
 mod task_state;
+mod task_template;
+mod task_upload_slots;
+mod token_scope;
+mod trusted_time;
+mod user_activity;
 mod worker;
+mod worker_registration;
+mod workflow_cache;
 
+pub use approval_policy::*;
+pub use approval_receipt::*;
+pub use argument_template::*;
 pub use attestation::*;
 pub use crypto::*;
 pub use error::*;
 pub use file::*;
 pub use file_agent::*;
+pub use file_credential::*;
 pub use function::*;
+pub use function_test::*;
 pub use macros::*;
+pub use model::*;
+pub use post_processing::*;
+pub use prewarm_task::*;
+pub use request_signature::*;
+pub use resource_limits::*;
+pub use resource_profile::*;
+pub use return_value::*;
+pub use reusable_result::*;
 pub use staged_file::*;
 pub use staged_function::*;
 pub use staged_task::*;
 pub use storage::*;
 pub use task::*;
+pub use task_approval_window::*;
+pub use task_archive::*;
+pub use task_deadline::*;
+pub use task_dependency::*;
+pub use task_dispatch::*;
+pub use task_estimate::*;
+pub use task_index::*;
+pub use task_key_exchange::*;
+pub use task_manifest::*;
+pub use task_notification::*;
+pub use task_retry::*;
+pub use task_schedule::*;
+pub use task_share_link::*;
 pub use task_state::*;
+pub use task_template::*;
+pub use task_upload_slots::*;
+pub use token_scope::*;
+pub use trusted_time::*;
+pub use user_activity::*;
 pub use worker::*;
+pub use worker_registration::*;
+pub use workflow_cache::*;
 
 #[cfg(feature = "enclave_unit_test")]
 pub mod tests {
     use super::*;
+    use teaclave_test_utils::check_all_passed;
 
     pub fn run_tests() -> bool {
-        worker::tests::run_tests()
+        check_all_passed!(
+            worker::tests::run_tests(),
+            post_processing::tests::run_tests(),
+            request_signature::tests::run_tests(),
+            trusted_time::tests::run_tests(),
+        )
     }
 }
@@ -0,0 +1,166 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use anyhow::{anyhow, ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const MODEL_PREFIX: &str = "model";
+const MODEL_REFERENCE_SCHEME: &str = "model:";
+
+/// Reference to a registered model in the form of `model:<name>@<version>`,
+/// the latest version is referenced if the version is omitted.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ModelReference {
+    pub name: String,
+    pub version: Option<u32>,
+}
+
+impl ModelReference {
+    pub fn new(name: impl ToString, version: Option<u32>) -> Self {
+        Self {
+            name: name.to_string(),
+            version,
+        }
+    }
+
+    pub fn is_model_reference(s: &str) -> bool {
+        s.starts_with(MODEL_REFERENCE_SCHEME)
+    }
+}
+
+impl ToString for ModelReference {
+    fn to_string(&self) -> String {
+        match self.version {
+            Some(version) => format!("{}{}@{}", MODEL_REFERENCE_SCHEME, self.name, version),
+            None => format!("{}{}", MODEL_REFERENCE_SCHEME, self.name),
+        }
+    }
+}
+
+impl std::convert::TryFrom<&str> for ModelReference {
+    type Error = anyhow::Error;
+    fn try_from(s: &str) -> Result<Self> {
+        ensure!(
+            Self::is_model_reference(s),
+            "Invalid model reference: {}",
+            s
+        );
+        let reference = &s[MODEL_REFERENCE_SCHEME.len()..];
+        let (name, version) = match reference.find('@') {
+            Some(pos) => {
+                let version = reference[pos + 1..]
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid model version: {}", s))?;
+                (&reference[..pos], Some(version))
+            }
+            None => (reference, None),
+        };
+        ensure!(!name.is_empty(), "Invalid model reference: {}", s);
+        Ok(Self::new(name, version))
+    }
+}
+
+impl std::convert::TryFrom<String> for ModelReference {
+    type Error = anyhow::Error;
+    fn try_from(s: String) -> Result<Self> {
+        s.as_str().try_into()
+    }
+}
+
+/// A trained model stored as an input file, along with the task producing it
+/// and the metrics reported by its owner.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ModelVersion {
+    pub version: u32,
+    pub data_id: ExternalID,
+    pub task_id: ExternalID,
+    pub function_id: ExternalID,
+    pub metrics: HashMap<String, f64>,
+}
+
+/// Versions of a model registered by a user, keyed by the user id and the
+/// name of the model. Versions are numbered from 1.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Model {
+    pub owner: UserID,
+    pub name: String,
+    pub versions: Vec<ModelVersion>,
+}
+
+impl Storable for Model {
+    fn key_prefix() -> &'static str {
+        MODEL_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        model_uuid(&self.owner, &self.name)
+    }
+}
+
+impl Model {
+    pub fn new(owner: UserID, name: impl ToString) -> Self {
+        Self {
+            owner,
+            name: name.to_string(),
+            versions: Vec::new(),
+        }
+    }
+
+    pub fn external_id_of(owner: &UserID, name: &str) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), model_uuid(owner, name))
+    }
+
+    pub fn add_version(
+        &mut self,
+        data_id: ExternalID,
+        task_id: ExternalID,
+        function_id: ExternalID,
+        metrics: HashMap<String, f64>,
+    ) -> u32 {
+        let version = self.versions.len() as u32 + 1;
+        self.versions.push(ModelVersion {
+            version,
+            data_id,
+            task_id,
+            function_id,
+            metrics,
+        });
+        version
+    }
+
+    /// Gets the given version, or the latest one if not specified.
+    pub fn get(&self, version: Option<u32>) -> Option<&ModelVersion> {
+        match version {
+            Some(version) => self.versions.iter().find(|v| v.version == version),
+            None => self.versions.last(),
+        }
+    }
+}
+
+// Model names are only unique per owner, the storage key is derived from both.
+fn model_uuid(owner: &UserID, name: &str) -> Uuid {
+    let key = serde_json::json!([owner.to_string(), name]).to_string();
+    let digest = ring::digest::digest(&ring::digest::SHA256, key.as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest.as_ref()[..16]);
+    Uuid::from_bytes(bytes)
+}
@@ -0,0 +1,313 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use anyhow::{bail, ensure, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::prelude::v1::*;
+
+/// Most decimals a return value can be rounded to, beyond which rounding a
+/// double does not minimize anything.
+pub const MAX_ROUND_DECIMALS: u32 = 15;
+
+/// Keywords of JSON Schema supported by `ValidateSchema`. Schemas using any
+/// other keyword are rejected when the function is registered, rather than
+/// leaving the keyword unenforced.
+const SCHEMA_KEYWORDS: &[&str] = &[
+    "type",
+    "properties",
+    "required",
+    "additionalProperties",
+    "items",
+    "maxItems",
+    "maxLength",
+];
+
+const SCHEMA_TYPES: &[&str] = &[
+    "null", "boolean", "integer", "number", "string", "array", "object",
+];
+
+/// Step applied by the execution enclave to the return value of a function
+/// before it leaves the enclave, declared when the function is registered and
+/// shown to the participants approving its tasks. The return value must be
+/// JSON for a function declaring any step.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum PostProcessingStep {
+    /// Rounds every number with a fraction to the decimals
+    Round { decimals: u32 },
+    /// Truncates the return value to its first k elements if it is an array,
+    /// or else each array in the fields of the returned object
+    TopK { k: u64 },
+    /// Fails the task unless the return value matches the schema, written in
+    /// the subset of JSON Schema listed in `SCHEMA_KEYWORDS`
+    ValidateSchema { schema: Value },
+}
+
+impl PostProcessingStep {
+    pub fn round(decimals: u32) -> Self {
+        PostProcessingStep::Round { decimals }
+    }
+
+    pub fn top_k(k: u64) -> Self {
+        PostProcessingStep::TopK { k }
+    }
+
+    pub fn validate_schema(schema: Value) -> Self {
+        PostProcessingStep::ValidateSchema { schema }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PostProcessingStep::Round { .. } => "round",
+            PostProcessingStep::TopK { .. } => "top_k",
+            PostProcessingStep::ValidateSchema { .. } => "validate_schema",
+        }
+    }
+
+    /// Checks the step when the function is registered.
+    pub fn check(&self) -> Result<()> {
+        match self {
+            PostProcessingStep::Round { decimals } => ensure!(
+                *decimals <= MAX_ROUND_DECIMALS,
+                "Cannot round to more than {} decimals",
+                MAX_ROUND_DECIMALS
+            ),
+            PostProcessingStep::TopK { k } => ensure!(*k > 0, "Cannot truncate to zero elements"),
+            PostProcessingStep::ValidateSchema { schema } => check_schema(schema)?,
+        }
+        Ok(())
+    }
+
+    fn apply(&self, value: &mut Value) -> Result<()> {
+        match self {
+            PostProcessingStep::Round { decimals } => round(value, *decimals),
+            PostProcessingStep::TopK { k } => match value {
+                Value::Array(elements) => elements.truncate(*k as usize),
+                Value::Object(fields) => {
+                    for field in fields.values_mut() {
+                        if let Value::Array(elements) = field {
+                            elements.truncate(*k as usize);
+                        }
+                    }
+                }
+                _ => bail!("Return value is neither an array nor an object"),
+            },
+            PostProcessingStep::ValidateSchema { schema } => validate(value, schema, "$")?,
+        }
+        Ok(())
+    }
+}
+
+/// Applies the steps in order to the return value, which is left unchanged
+/// without any step. The task fails if any step fails.
+pub fn post_process(steps: &[PostProcessingStep], return_value: &[u8]) -> Result<Vec<u8>> {
+    if steps.is_empty() {
+        return Ok(return_value.to_vec());
+    }
+    let mut value: Value = serde_json::from_slice(return_value)
+        .map_err(|_| anyhow::anyhow!("Return value is not JSON"))?;
+    for step in steps {
+        step.apply(&mut value)
+            .map_err(|e| anyhow::anyhow!("Post-processing {} failed: {}", step.name(), e))?;
+    }
+    Ok(serde_json::to_vec(&value)?)
+}
+
+fn round(value: &mut Value, decimals: u32) {
+    match value {
+        Value::Number(number) if number.is_f64() => {
+            let scale = 10f64.powi(decimals as i32);
+            let rounded = number.as_f64().map(|n| (n * scale).round() / scale);
+            if let Some(rounded) = rounded.and_then(serde_json::Number::from_f64) {
+                *number = rounded;
+            }
+        }
+        Value::Array(elements) => elements.iter_mut().for_each(|e| round(e, decimals)),
+        Value::Object(fields) => fields.values_mut().for_each(|f| round(f, decimals)),
+        _ => (),
+    }
+}
+
+fn check_schema(schema: &Value) -> Result<()> {
+    let schema = match schema {
+        Value::Object(schema) => schema,
+        _ => bail!("Schema is not an object"),
+    };
+    for (keyword, value) in schema {
+        ensure!(
+            SCHEMA_KEYWORDS.contains(&keyword.as_str()),
+            "Unsupported schema keyword: {}",
+            keyword
+        );
+        match keyword.as_str() {
+            "type" => ensure!(
+                value.as_str().map_or(false, |t| SCHEMA_TYPES.contains(&t)),
+                "Invalid schema type: {}",
+                value
+            ),
+            "properties" => {
+                let properties = value
+                    .as_object()
+                    .ok_or_else(|| anyhow::anyhow!("Schema properties is not an object"))?;
+                properties.values().try_for_each(check_schema)?;
+            }
+            "required" => ensure!(
+                value
+                    .as_array()
+                    .map_or(false, |names| names.iter().all(Value::is_string)),
+                "Schema required is not an array of names"
+            ),
+            "additionalProperties" => ensure!(
+                value.is_boolean(),
+                "Schema additionalProperties is not a boolean"
+            ),
+            "items" => check_schema(value)?,
+            _ => ensure!(value.is_u64(), "Schema {} is not a count", keyword),
+        }
+    }
+    Ok(())
+}
+
+fn validate(value: &Value, schema: &Value, path: &str) -> Result<()> {
+    let schema: &Map<String, Value> = match schema.as_object() {
+        Some(schema) => schema,
+        None => return Ok(()),
+    };
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        let matched = match expected {
+            "null" => value.is_null(),
+            "boolean" => value.is_boolean(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "string" => value.is_string(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => false,
+        };
+        // The value itself is not reported, as it is what the schema keeps
+        // from leaving the enclave.
+        ensure!(matched, "{} is not of type {}", path, expected);
+    }
+    match value {
+        Value::String(string) => {
+            if let Some(max_length) = schema.get("maxLength").and_then(Value::as_u64) {
+                ensure!(
+                    string.chars().count() as u64 <= max_length,
+                    "{} is longer than {}",
+                    path,
+                    max_length
+                );
+            }
+        }
+        Value::Array(elements) => {
+            if let Some(max_items) = schema.get("maxItems").and_then(Value::as_u64) {
+                ensure!(
+                    elements.len() as u64 <= max_items,
+                    "{} has more than {} items",
+                    path,
+                    max_items
+                );
+            }
+            if let Some(items) = schema.get("items") {
+                for (index, element) in elements.iter().enumerate() {
+                    validate(element, items, &format!("{}[{}]", path, index))?;
+                }
+            }
+        }
+        Value::Object(fields) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for name in required.iter().filter_map(Value::as_str) {
+                    ensure!(fields.contains_key(name), "{}.{} is missing", path, name);
+                }
+            }
+            let additional = schema.get("additionalProperties").and_then(Value::as_bool);
+            for (name, field) in fields {
+                match properties.and_then(|properties| properties.get(name)) {
+                    Some(property) => validate(field, property, &format!("{}.{}", path, name))?,
+                    None => ensure!(
+                        additional != Some(false),
+                        "{}.{} is not allowed",
+                        path,
+                        name
+                    ),
+                }
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+    use teaclave_test_utils::*;
+
+    pub fn run_tests() -> bool {
+        run_tests!(test_post_process, test_check_steps)
+    }
+
+    fn test_post_process() {
+        let return_value = br#"{"scores": [0.12345, 0.6789, 0.5], "count": 3}"#;
+        assert_eq!(post_process(&[], b"not json").unwrap(), b"not json");
+        assert!(post_process(&[PostProcessingStep::round(2)], b"not json").is_err());
+
+        let steps = vec![PostProcessingStep::round(2), PostProcessingStep::top_k(2)];
+        let processed = post_process(&steps, return_value).unwrap();
+        let processed: Value = serde_json::from_slice(&processed).unwrap();
+        assert_eq!(processed, json!({"scores": [0.12, 0.68], "count": 3}));
+
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "scores": {"type": "array", "items": {"type": "number"}, "maxItems": 2},
+                "count": {"type": "integer"},
+            },
+            "required": ["scores"],
+            "additionalProperties": false,
+        });
+        let steps = vec![PostProcessingStep::validate_schema(schema.clone())];
+        assert!(post_process(&steps, return_value).is_err());
+        assert!(post_process(&steps, br#"{"scores": [1.0], "name": "a"}"#).is_err());
+        assert!(post_process(&steps, br#"{"count": 1}"#).is_err());
+        assert!(post_process(&steps, br#"{"scores": [1.0, 2.0], "count": 1}"#).is_ok());
+
+        // Steps apply in order, so the truncated value matches the schema.
+        let steps = vec![
+            PostProcessingStep::top_k(2),
+            PostProcessingStep::validate_schema(schema),
+        ];
+        assert!(post_process(&steps, return_value).is_ok());
+    }
+
+    fn test_check_steps() {
+        assert!(PostProcessingStep::round(2).check().is_ok());
+        assert!(PostProcessingStep::round(MAX_ROUND_DECIMALS + 1)
+            .check()
+            .is_err());
+        assert!(PostProcessingStep::top_k(0).check().is_err());
+        let schema = json!({"type": "array", "items": {"type": "string", "maxLength": 8}});
+        assert!(PostProcessingStep::validate_schema(schema).check().is_ok());
+        let schema = json!({"type": "array", "pattern": "^a"});
+        assert!(PostProcessingStep::validate_schema(schema).check().is_err());
+        let schema = json!({"type": "decimal"});
+        assert!(PostProcessingStep::validate_schema(schema).check().is_err());
+    }
+}
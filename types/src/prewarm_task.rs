@@ -0,0 +1,97 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::prelude::v1::*;
+use url::Url;
+use uuid::Uuid;
+
+const PREWARM_TASK_PREFIX: &str = "prewarm-"; // prewarm-task-uuid
+pub const PREWARM_QUEUE_KEY: &str = "prewarm-task";
+
+/// Location of an input of a task to be pre-fetched by a worker.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PrewarmInputFile {
+    pub url: Url,
+    pub cmac: FileAuthTag,
+}
+
+impl From<TeaclaveInputFile> for PrewarmInputFile {
+    fn from(file: TeaclaveInputFile) -> Self {
+        Self {
+            url: file.url,
+            cmac: file.cmac,
+        }
+    }
+}
+
+impl PrewarmInputFile {
+    pub fn matches(&self, file: &FunctionInputFile) -> bool {
+        self.url == file.url && self.cmac == file.cmac
+    }
+}
+
+/// Hint for workers to download the inputs and prepare the executor of a task
+/// whose data are assigned but approvals are still pending. Unlike the staged
+/// task, it carries no keys of the inputs, which are only released after all
+/// participants approved the task.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct PrewarmTask {
+    pub task_id: Uuid,
+    pub executor: Executor,
+    pub executor_type: ExecutorType,
+    pub function_name: String,
+    pub function_payload: Vec<u8>,
+    pub input_files: HashMap<String, PrewarmInputFile>,
+    #[serde(default)]
+    pub creator: UserID,
+}
+
+impl Storable for PrewarmTask {
+    fn key_prefix() -> &'static str {
+        PREWARM_TASK_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.task_id
+    }
+}
+
+impl PrewarmTask {
+    pub fn new(ts: &TaskState, function: &Function) -> Self {
+        Self {
+            task_id: ts.task_id,
+            executor: ts.executor,
+            executor_type: function.executor_type,
+            function_name: function.name.clone(),
+            function_payload: function.payload.clone(),
+            input_files: ts
+                .assigned_inputs
+                .clone()
+                .into_iter()
+                .map(|(name, file)| (name, file.into()))
+                .collect(),
+            creator: ts.creator.clone(),
+        }
+    }
+
+    pub fn get_queue_key() -> &'static str {
+        PREWARM_QUEUE_KEY
+    }
+}
@@ -0,0 +1,279 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use anyhow::{anyhow, bail, ensure, Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const SIGNING_KEY_PREFIX: &str = "signingkey";
+/// Length of an Ed25519 public key.
+pub const SIGNING_PUBLIC_KEY_LENGTH: usize = 32;
+
+/// Ed25519 public key registered by a user to sign requests with, keyed by
+/// the user id.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct UserSigningKey {
+    pub user_id: UserID,
+    pub public_key: Vec<u8>,
+    /// Seconds since the Unix epoch
+    pub registered_at: u64,
+}
+
+impl Storable for UserSigningKey {
+    fn key_prefix() -> &'static str {
+        SIGNING_KEY_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        user_uuid(&self.user_id)
+    }
+}
+
+impl UserSigningKey {
+    pub fn new(user_id: UserID, public_key: Vec<u8>, registered_at: u64) -> Result<Self> {
+        ensure!(
+            public_key.len() == SIGNING_PUBLIC_KEY_LENGTH,
+            "Invalid signing key length: {}",
+            public_key.len()
+        );
+        Ok(Self {
+            user_id,
+            public_key,
+            registered_at,
+        })
+    }
+
+    pub fn external_id_of(user_id: &UserID) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), user_uuid(user_id))
+    }
+}
+
+/// Requests a user can sign.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum SignedAction {
+    CreateTask,
+    AssignData,
+    ApproveTask,
+}
+
+impl std::convert::TryFrom<&str> for SignedAction {
+    type Error = Error;
+
+    fn try_from(action: &str) -> Result<Self> {
+        let action = match action {
+            "create_task" => SignedAction::CreateTask,
+            "assign_data" => SignedAction::AssignData,
+            "approve_task" => SignedAction::ApproveTask,
+            _ => bail!("Invalid signed action: {}", action),
+        };
+        Ok(action)
+    }
+}
+
+impl std::fmt::Display for SignedAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SignedAction::CreateTask => write!(f, "create_task"),
+            SignedAction::AssignData => write!(f, "assign_data"),
+            SignedAction::ApproveTask => write!(f, "approve_task"),
+        }
+    }
+}
+
+/// Signature of a user on a request, kept with the task as evidence that the
+/// user made the request, which the bearer token of the user is not.
+///
+/// The signed message is `<action>|<user id>|<digest>`, where the digest is
+/// the hex encoded SHA-256 of the content of the request in JSON with sorted
+/// keys and no whitespace.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RequestSignature {
+    pub user_id: UserID,
+    pub action: SignedAction,
+    pub digest: String,
+    pub signature: Vec<u8>,
+    pub public_key: Vec<u8>,
+    /// Seconds since the Unix epoch the signature was verified at.
+    pub timestamp: u64,
+}
+
+impl RequestSignature {
+    pub fn new(user_id: UserID, action: SignedAction, content: &Value) -> Self {
+        Self {
+            user_id,
+            action,
+            digest: request_digest(content),
+            signature: Vec::new(),
+            public_key: Vec::new(),
+            timestamp: 0,
+        }
+    }
+
+    pub fn signature(self, signature: Vec<u8>, public_key: Vec<u8>) -> Self {
+        Self {
+            signature,
+            public_key,
+            ..self
+        }
+    }
+
+    pub fn timestamp(self, timestamp: u64) -> Self {
+        Self { timestamp, ..self }
+    }
+
+    /// The signed content: action, user id and digest of the request.
+    pub fn message(&self) -> Vec<u8> {
+        format!("{}|{}|{}", self.action, self.user_id, self.digest).into_bytes()
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        let public_key =
+            ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, &self.public_key);
+        public_key
+            .verify(&self.message(), &self.signature)
+            .map_err(|_| anyhow!("invalid request signature"))
+    }
+}
+
+fn request_digest(content: &Value) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, content.to_string().as_bytes());
+    hex::encode(digest.as_ref())
+}
+
+/// Content of a task creation signed by the creator: the function,
+/// arguments, executor and file ownership of the task.
+pub fn create_task_content(
+    function_id: &ExternalID,
+    function_arguments: &FunctionArguments,
+    executor: Executor,
+    inputs_ownership: &TaskFileOwners,
+    outputs_ownership: &TaskFileOwners,
+) -> Value {
+    json!({
+        "function_id": function_id.to_string(),
+        "function_arguments": function_arguments.inner(),
+        "executor": executor.to_string(),
+        "inputs_ownership": sorted_owners(inputs_ownership),
+        "outputs_ownership": sorted_owners(outputs_ownership),
+    })
+}
+
+/// Content of a data assignment signed by the assigning user: the task and
+/// the files assigned to it, where models are named by their references as
+/// they are assigned, e.g., `model:fraud@7`.
+pub fn assign_data_content(
+    task_id: &ExternalID,
+    inputs: &HashMap<String, ExternalID>,
+    outputs: &HashMap<String, ExternalID>,
+    input_models: &HashMap<String, ModelReference>,
+) -> Value {
+    let mut assigned_inputs = sorted_file_ids(inputs.clone());
+    assigned_inputs.extend(
+        input_models
+            .iter()
+            .map(|(name, reference)| (name.to_owned(), reference.to_string())),
+    );
+    json!({
+        "task_id": task_id.to_string(),
+        "inputs": assigned_inputs,
+        "outputs": sorted_file_ids(outputs.clone()),
+    })
+}
+
+/// Content of an approval signed by the approving user: the task and the
+/// hash of the specification being approved.
+pub fn approve_task_content(task_id: &ExternalID, spec_hash: &str) -> Value {
+    json!({
+        "task_id": task_id.to_string(),
+        "spec_hash": spec_hash,
+    })
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+    use teaclave_test_utils::*;
+
+    pub fn run_tests() -> bool {
+        run_tests!(test_verify_request_signature, test_signed_message)
+    }
+
+    fn test_verify_request_signature() {
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = ring::signature::Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = ring::signature::Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let public_key = ring::signature::KeyPair::public_key(&key_pair)
+            .as_ref()
+            .to_vec();
+        let task_id = ExternalID::new("task", Uuid::new_v4());
+
+        let request = RequestSignature::new(
+            "user".into(),
+            SignedAction::ApproveTask,
+            &approve_task_content(&task_id, "spec"),
+        );
+        let signature = key_pair.sign(&request.message()).as_ref().to_vec();
+        let signed = request.clone().signature(signature, public_key.clone());
+        assert!(signed.verify().is_ok());
+
+        // The signature is bound to the content, user and action.
+        let other = RequestSignature::new(
+            "user".into(),
+            SignedAction::ApproveTask,
+            &approve_task_content(&task_id, "other spec"),
+        );
+        let signature = signed.signature.clone();
+        assert!(other
+            .signature(signature.clone(), public_key.clone())
+            .verify()
+            .is_err());
+        let other = RequestSignature {
+            user_id: "other".into(),
+            ..signed.clone()
+        };
+        assert!(other.verify().is_err());
+        let other = RequestSignature {
+            action: SignedAction::AssignData,
+            ..signed
+        };
+        assert!(other.verify().is_err());
+
+        assert!(UserSigningKey::new("user".into(), public_key, 0).is_ok());
+        assert!(UserSigningKey::new("user".into(), vec![0; 33], 0).is_err());
+    }
+
+    fn test_signed_message() {
+        let task_id = ExternalID::try_from("task-00000000-0000-0000-0000-000000000001").unwrap();
+        let request = RequestSignature::new(
+            "user".into(),
+            SignedAction::ApproveTask,
+            &approve_task_content(&task_id, "spec"),
+        );
+        let content =
+            r#"{"spec_hash":"spec","task_id":"task-00000000-0000-0000-0000-000000000001"}"#;
+        let digest = ring::digest::digest(&ring::digest::SHA256, content.as_bytes());
+        assert_eq!(
+            request.message(),
+            format!("approve_task|user|{}", hex::encode(digest.as_ref())).into_bytes()
+        );
+    }
+}
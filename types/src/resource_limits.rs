@@ -0,0 +1,144 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+use crate::{ResourceProfile, TrustedTime};
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+#[cfg(not(feature = "mesalock_sgx"))]
+use std::sync::Mutex;
+#[cfg(feature = "mesalock_sgx")]
+use std::sync::SgxMutex as Mutex;
+use std::time::{Duration, SystemTime};
+
+/// Limits on the resources a task can use on a worker, given when the task is
+/// created. Zero means unlimited.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct ResourceLimits {
+    pub max_memory_bytes: u64,
+    pub max_cpu_seconds: u64,
+    pub max_output_bytes: u64,
+}
+
+impl ResourceLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_memory_bytes(self, max_memory_bytes: u64) -> Self {
+        Self {
+            max_memory_bytes,
+            ..self
+        }
+    }
+
+    pub fn max_cpu_seconds(self, max_cpu_seconds: u64) -> Self {
+        Self {
+            max_cpu_seconds,
+            ..self
+        }
+    }
+
+    pub fn max_output_bytes(self, max_output_bytes: u64) -> Self {
+        Self {
+            max_output_bytes,
+            ..self
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Checks the heap declared by the function, since the heap actually
+    /// used by the executor is not observable.
+    pub fn check_memory(&self, profile: &ResourceProfile) -> Result<(), ResourceExceeded> {
+        if self.max_memory_bytes > 0 && profile.heap_size > self.max_memory_bytes {
+            return Err(ResourceExceeded::Memory(self.max_memory_bytes));
+        }
+        Ok(())
+    }
+}
+
+/// A resource limit exceeded by a task, with the limit.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+pub enum ResourceExceeded {
+    #[error("Memory limit of {0} bytes exceeded")]
+    Memory(u64),
+    #[error("CPU limit of {0} seconds exceeded")]
+    Cpu(u64),
+    #[error("Output limit of {0} bytes exceeded")]
+    Output(u64),
+}
+
+/// Meters the resources used by a running function against the limits of the
+/// task. The running time is counted from when the meter is started, and is
+/// checked whenever the function accesses a file, since the function cannot
+/// be preempted. The first limit exceeded is recorded, so that the task fails
+/// even if the function recovers from the failed access.
+#[derive(Debug, Clone)]
+pub struct ResourceMeter {
+    limits: ResourceLimits,
+    started: SystemTime,
+    output_bytes: Arc<AtomicU64>,
+    exceeded: Arc<Mutex<Option<ResourceExceeded>>>,
+}
+
+impl ResourceMeter {
+    /// Starts the meter from now.
+    pub fn new(limits: ResourceLimits) -> Self {
+        Self {
+            limits,
+            started: TrustedTime::now(),
+            output_bytes: Arc::new(AtomicU64::new(0)),
+            exceeded: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn check_cpu(&self) -> Result<(), ResourceExceeded> {
+        let limit = self.limits.max_cpu_seconds;
+        let elapsed = TrustedTime::now()
+            .duration_since(self.started)
+            .unwrap_or_default();
+        if limit > 0 && elapsed > Duration::from_secs(limit) {
+            return Err(self.record(ResourceExceeded::Cpu(limit)));
+        }
+        Ok(())
+    }
+
+    /// Counts bytes written to the outputs, failing the write exceeding the
+    /// limit.
+    pub fn add_output_bytes(&self, bytes: u64) -> Result<(), ResourceExceeded> {
+        let limit = self.limits.max_output_bytes;
+        let total = self.output_bytes.fetch_add(bytes, Ordering::SeqCst) + bytes;
+        if limit > 0 && total > limit {
+            return Err(self.record(ResourceExceeded::Output(limit)));
+        }
+        Ok(())
+    }
+
+    pub fn exceeded(&self) -> Option<ResourceExceeded> {
+        self.exceeded.lock().ok().and_then(|exceeded| *exceeded)
+    }
+
+    fn record(&self, resource: ResourceExceeded) -> ResourceExceeded {
+        if let Ok(mut exceeded) = self.exceeded.lock() {
+            exceeded.get_or_insert(resource);
+        }
+        resource
+    }
+}
@@ -0,0 +1,76 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+
+/// Resources a function is expected to need, declared when it is registered.
+/// Sizes are in bytes and the timeout is in seconds, zero means unspecified.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct ResourceProfile {
+    pub heap_size: u64,
+    pub input_size: u64,
+    pub timeout: u64,
+}
+
+impl ResourceProfile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn heap_size(self, heap_size: u64) -> Self {
+        Self { heap_size, ..self }
+    }
+
+    pub fn input_size(self, input_size: u64) -> Self {
+        Self { input_size, ..self }
+    }
+
+    pub fn timeout(self, timeout: u64) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Whether nothing is specified in the profile.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Checks the profile against the resources a worker can provide, given in
+    /// the same form where zero means unlimited.
+    pub fn check(&self, capacity: &ResourceProfile) -> Result<()> {
+        ensure!(
+            capacity.heap_size == 0 || self.heap_size <= capacity.heap_size,
+            "Heap size {} exceeds the worker limit {}",
+            self.heap_size,
+            capacity.heap_size
+        );
+        ensure!(
+            capacity.input_size == 0 || self.input_size <= capacity.input_size,
+            "Input size {} exceeds the worker limit {}",
+            self.input_size,
+            capacity.input_size
+        );
+        ensure!(
+            capacity.timeout == 0 || self.timeout <= capacity.timeout,
+            "Timeout {}s exceeds the worker limit {}s",
+            self.timeout,
+            capacity.timeout
+        );
+        Ok(())
+    }
+}
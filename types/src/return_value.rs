@@ -0,0 +1,105 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const RETURN_VALUE_CHUNK_PREFIX: &str = "returnvalue";
+/// Size of the chunks of a large return value, which is also the largest
+/// return value kept in the task itself and the largest page of a fetch.
+pub const RETURN_VALUE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Return value too large for a message of RPC, which is stored in chunks
+/// apart from the task. The chunks are keyed by the task producing them, which
+/// is the same task unless the result is reused.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ReturnValueHandle {
+    pub task_id: Uuid,
+    pub size: u64,
+    /// SHA-256 digest of the whole return value in hex
+    pub digest: String,
+}
+
+impl ReturnValueHandle {
+    pub fn new(task_id: Uuid, return_value: &[u8]) -> Self {
+        let digest = ring::digest::digest(&ring::digest::SHA256, return_value);
+        Self {
+            task_id,
+            size: return_value.len() as u64,
+            digest: hex::encode(digest.as_ref()),
+        }
+    }
+
+    pub fn chunk_count(&self) -> u32 {
+        ((self.size as usize + RETURN_VALUE_CHUNK_SIZE - 1) / RETURN_VALUE_CHUNK_SIZE) as u32
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReturnValueChunk {
+    pub task_id: Uuid,
+    pub index: u32,
+    pub data: Vec<u8>,
+}
+
+impl Storable for ReturnValueChunk {
+    fn key_prefix() -> &'static str {
+        RETURN_VALUE_CHUNK_PREFIX
+    }
+
+    // Chunks are bytes, which JSON encodes as arrays of numbers.
+    fn codec() -> StorageCodec {
+        StorageCodec::Bincode
+    }
+
+    fn uuid(&self) -> Uuid {
+        chunk_uuid(&self.task_id, self.index)
+    }
+}
+
+impl ReturnValueChunk {
+    /// Splits the return value of the task into chunks, along with the handle
+    /// kept in the task instead.
+    pub fn split(task_id: Uuid, return_value: &[u8]) -> (ReturnValueHandle, Vec<Self>) {
+        let handle = ReturnValueHandle::new(task_id, return_value);
+        let chunks = return_value
+            .chunks(RETURN_VALUE_CHUNK_SIZE)
+            .enumerate()
+            .map(|(index, data)| Self {
+                task_id,
+                index: index as u32,
+                data: data.to_vec(),
+            })
+            .collect();
+        (handle, chunks)
+    }
+
+    pub fn external_id_of(task_id: &Uuid, index: u32) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), chunk_uuid(task_id, index))
+    }
+}
+
+fn chunk_uuid(task_id: &Uuid, index: u32) -> Uuid {
+    let mut input = task_id.as_bytes().to_vec();
+    input.extend_from_slice(&index.to_le_bytes());
+    let digest = ring::digest::digest(&ring::digest::SHA256, &input);
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest.as_ref()[..16]);
+    Uuid::from_bytes(bytes)
+}
@@ -0,0 +1,73 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const REUSABLE_RESULT_PREFIX: &str = "reusableresult";
+
+/// Return value of a finished task consenting to reuse, keyed by the result
+/// fingerprint of the task, which later identical tasks are served from.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReusableResult {
+    pub fingerprint: String,
+    pub task_id: ExternalID,
+    pub return_value: Vec<u8>,
+    #[serde(default)]
+    pub return_value_handle: Option<ReturnValueHandle>,
+}
+
+impl Storable for ReusableResult {
+    fn key_prefix() -> &'static str {
+        REUSABLE_RESULT_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        fingerprint_uuid(&self.fingerprint)
+    }
+}
+
+impl ReusableResult {
+    pub fn new(fingerprint: impl ToString, task_id: ExternalID, return_value: Vec<u8>) -> Self {
+        Self {
+            fingerprint: fingerprint.to_string(),
+            task_id,
+            return_value,
+            return_value_handle: None,
+        }
+    }
+
+    pub fn return_value_handle(self, return_value_handle: Option<ReturnValueHandle>) -> Self {
+        Self {
+            return_value_handle,
+            ..self
+        }
+    }
+
+    pub fn external_id_of(fingerprint: &str) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), fingerprint_uuid(fingerprint))
+    }
+}
+
+fn fingerprint_uuid(fingerprint: &str) -> Uuid {
+    let digest = ring::digest::digest(&ring::digest::SHA256, fingerprint.as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest.as_ref()[..16]);
+    Uuid::from_bytes(bytes)
+}
@@ -106,7 +106,6 @@ impl StagedFileInfo {
         Self::create_with_bytes(dst, &bytes)
     }
 
-    #[cfg(test_mode)]
     pub fn get_plaintext(&self) -> anyhow::Result<Vec<u8>> {
         let mut content = Vec::new();
         let mut f = ProtectedFile::open_ex(&self.path, &self.crypto_info.key)?;
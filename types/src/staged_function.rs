@@ -15,7 +15,10 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use crate::{Executor, ExecutorType, StagedFiles, TeaclaveRuntime};
+use crate::{
+    Executor, ExecutorType, FunctionCapabilities, ResourceMeter, StagedFiles, TaskDeadline,
+    TeaclaveRuntime, WorkflowArtifacts,
+};
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -114,6 +117,12 @@ pub struct StagedFunction {
     pub executor_type: ExecutorType,
     pub executor: Executor,
     pub runtime_name: String,
+    pub artifacts: Option<WorkflowArtifacts>,
+    pub entry_point: Option<String>,
+    pub tenant: String,
+    pub capabilities: Option<FunctionCapabilities>,
+    pub deadline: Option<TaskDeadline>,
+    pub meter: Option<ResourceMeter>,
 }
 
 impl StagedFunction {
@@ -170,4 +179,37 @@ impl StagedFunction {
             ..self
         }
     }
+
+    pub fn artifacts(self, artifacts: Option<WorkflowArtifacts>) -> Self {
+        Self { artifacts, ..self }
+    }
+
+    pub fn entry_point(self, entry_point: Option<String>) -> Self {
+        Self {
+            entry_point,
+            ..self
+        }
+    }
+
+    pub fn tenant(self, tenant: impl ToString) -> Self {
+        Self {
+            tenant: tenant.to_string(),
+            ..self
+        }
+    }
+
+    pub fn capabilities(self, capabilities: Option<FunctionCapabilities>) -> Self {
+        Self {
+            capabilities,
+            ..self
+        }
+    }
+
+    pub fn deadline(self, deadline: Option<TaskDeadline>) -> Self {
+        Self { deadline, ..self }
+    }
+
+    pub fn meter(self, meter: Option<ResourceMeter>) -> Self {
+        Self { meter, ..self }
+    }
 }
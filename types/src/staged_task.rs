@@ -24,12 +24,96 @@ use url::Url;
 use uuid::Uuid;
 
 use crate::{
-    Executor, ExecutorType, FileAuthTag, FileCrypto, FunctionArguments, Storable,
-    TeaclaveInputFile, TeaclaveOutputFile,
+    Executor, ExecutorType, FileAuthTag, FileCredential, FileCrypto, FunctionArguments,
+    FunctionCapabilities, FunctionTest, KeyBrokerReference, PostProcessingStep, ResourceProfile,
+    Storable, TeaclaveInputFile, TeaclaveOutputFile, UserID, WorkflowCache,
 };
 
 const STAGED_TASK_PREFIX: &str = "staged-"; // staged-task-uuid
 pub const QUEUE_KEY: &str = "staged-task";
+const HIGH_PRIORITY_QUEUE_KEY: &str = "staged-task-high";
+const LOW_PRIORITY_QUEUE_KEY: &str = "staged-task-low";
+// Every so many pulls, a lower priority is served first, so that its tasks are
+// not starved by a steady flow of tasks of higher priorities.
+const STARVATION_PULLS: u64 = 8;
+
+/// Staged tasks of a priority are queued on their own, and the scheduler hands
+/// out the tasks of higher priorities first. Priorities are ordered from the
+/// lowest.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum TaskPriority {
+    Low,
+    Normal,
+    High,
+}
+
+impl std::default::Default for TaskPriority {
+    fn default() -> Self {
+        TaskPriority::Normal
+    }
+}
+
+impl TaskPriority {
+    /// All priorities, the highest first.
+    pub fn levels() -> &'static [TaskPriority] {
+        &[TaskPriority::High, TaskPriority::Normal, TaskPriority::Low]
+    }
+
+    /// The priorities in the order their queues are searched on a pull, the
+    /// highest first, except that the lower ones take turns to be searched
+    /// first on every eighth pull.
+    pub fn pull_order(pull: u64) -> Vec<TaskPriority> {
+        let mut levels = Self::levels().to_vec();
+        if pull % STARVATION_PULLS == STARVATION_PULLS - 1 {
+            let lower = levels.len() as u64 - 1;
+            let boosted = 1 + (pull / STARVATION_PULLS % lower) as usize;
+            let priority = levels.remove(boosted);
+            levels.insert(0, priority);
+        }
+        levels
+    }
+
+    /// Tasks of the normal priority stay in the queue of tasks staged before
+    /// priorities were introduced.
+    pub fn queue_key(self) -> &'static str {
+        match self {
+            TaskPriority::Low => LOW_PRIORITY_QUEUE_KEY,
+            TaskPriority::Normal => QUEUE_KEY,
+            TaskPriority::High => HIGH_PRIORITY_QUEUE_KEY,
+        }
+    }
+
+    /// Tasks are staged in the queue of their priority and executor, so that
+    /// tasks of one executor do not hold up those of another. The queue of the
+    /// priority alone is shared by tasks staged before.
+    pub fn executor_queue_key(self, executor: Executor) -> String {
+        format!("{}-{}", self.queue_key(), executor)
+    }
+}
+
+impl std::convert::TryFrom<&str> for TaskPriority {
+    type Error = anyhow::Error;
+
+    fn try_from(priority: &str) -> anyhow::Result<Self> {
+        let priority = match priority {
+            "low" => TaskPriority::Low,
+            "normal" => TaskPriority::Normal,
+            "high" => TaskPriority::High,
+            _ => anyhow::bail!("Unsupported task priority: {}", priority),
+        };
+        Ok(priority)
+    }
+}
+
+impl std::fmt::Display for TaskPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TaskPriority::Low => write!(f, "low"),
+            TaskPriority::Normal => write!(f, "normal"),
+            TaskPriority::High => write!(f, "high"),
+        }
+    }
+}
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct FunctionInputFiles {
@@ -43,6 +127,10 @@ impl FunctionInputFiles {
     pub fn iter(&self) -> Iter<String, FunctionInputFile> {
         self.inner.iter()
     }
+
+    pub fn iter_mut(&mut self) -> IterMut<String, FunctionInputFile> {
+        self.inner.iter_mut()
+    }
 }
 
 impl IntoIterator for FunctionInputFiles {
@@ -129,6 +217,10 @@ pub struct FunctionInputFile {
     pub url: Url,
     pub cmac: FileAuthTag,
     pub crypto_info: FileCrypto,
+    /// The crypto info is replaced with the key released by the broker
+    /// before the file is read.
+    #[serde(default)]
+    pub key_broker: Option<KeyBrokerReference>,
 }
 
 impl FunctionInputFile {
@@ -137,6 +229,7 @@ impl FunctionInputFile {
             url,
             cmac,
             crypto_info: crypto.into(),
+            key_broker: None,
         }
     }
 }
@@ -147,6 +240,7 @@ impl From<TeaclaveInputFile> for FunctionInputFile {
             url: file.url,
             cmac: file.cmac,
             crypto_info: file.crypto_info,
+            key_broker: file.key_broker,
         }
     }
 }
@@ -155,6 +249,8 @@ impl From<TeaclaveInputFile> for FunctionInputFile {
 pub struct FunctionOutputFile {
     pub url: Url,
     pub crypto_info: FileCrypto,
+    #[serde(default)]
+    pub credential: Option<FileCredential>,
 }
 
 impl FunctionOutputFile {
@@ -162,6 +258,7 @@ impl FunctionOutputFile {
         Self {
             url,
             crypto_info: crypto.into(),
+            credential: None,
         }
     }
 }
@@ -171,6 +268,7 @@ impl From<TeaclaveOutputFile> for FunctionOutputFile {
         Self {
             url: file.url,
             crypto_info: file.crypto_info,
+            credential: file.credential,
         }
     }
 }
@@ -186,6 +284,46 @@ pub struct StagedTask {
     pub function_payload: Vec<u8>,
     pub input_data: FunctionInputFiles,
     pub output_data: FunctionOutputFiles,
+    #[serde(default)]
+    pub workflow_cache: Option<WorkflowCache>,
+    #[serde(default)]
+    pub resource_profile: ResourceProfile,
+    #[serde(default)]
+    pub entry_point: Option<String>,
+    #[serde(default)]
+    pub creator: UserID,
+    #[serde(default)]
+    pub capabilities: Option<FunctionCapabilities>,
+    /// Steps the execution enclave applies to the return value of the
+    /// function, see `PostProcessingStep`.
+    #[serde(default)]
+    pub post_processing: Vec<PostProcessingStep>,
+    #[serde(default)]
+    pub key_exchange: bool,
+    /// Hex encoded MRENCLAVE of the execution enclaves allowed to pull the
+    /// task, any of them when empty.
+    #[serde(default)]
+    pub worker_measurements: Vec<String>,
+    /// Hex encoded MRSIGNER of the execution enclaves allowed to pull the
+    /// task, any of them when empty.
+    #[serde(default)]
+    pub worker_signers: Vec<String>,
+    /// Set if the task is a test run of the function against sample inputs
+    /// instead of the files above.
+    #[serde(default)]
+    pub function_test: Option<FunctionTest>,
+    #[serde(default)]
+    pub priority: TaskPriority,
+    #[serde(default)]
+    pub resource_limits: ResourceLimits,
+    /// Seconds the task is given to finish once it is dispatched.
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    #[serde(default)]
+    pub retry_policy: RetryPolicy,
+    /// Retries of the task before this run.
+    #[serde(default)]
+    pub retry_count: u32,
 }
 
 impl Storable for StagedTask {
@@ -260,7 +398,124 @@ impl StagedTask {
         }
     }
 
+    pub fn workflow_cache(self, workflow_cache: WorkflowCache) -> Self {
+        Self {
+            workflow_cache: Some(workflow_cache),
+            ..self
+        }
+    }
+
+    pub fn resource_profile(self, resource_profile: ResourceProfile) -> Self {
+        Self {
+            resource_profile,
+            ..self
+        }
+    }
+
+    pub fn entry_point(self, entry_point: impl ToString) -> Self {
+        Self {
+            entry_point: Some(entry_point.to_string()),
+            ..self
+        }
+    }
+
+    pub fn creator(self, creator: impl Into<UserID>) -> Self {
+        Self {
+            creator: creator.into(),
+            ..self
+        }
+    }
+
+    pub fn capabilities(self, capabilities: FunctionCapabilities) -> Self {
+        Self {
+            capabilities: Some(capabilities),
+            ..self
+        }
+    }
+
+    pub fn post_processing(self, post_processing: Vec<PostProcessingStep>) -> Self {
+        Self {
+            post_processing,
+            ..self
+        }
+    }
+
+    pub fn key_exchange(self, key_exchange: bool) -> Self {
+        Self {
+            key_exchange,
+            ..self
+        }
+    }
+
+    pub fn worker_measurements(self, worker_measurements: Vec<String>) -> Self {
+        Self {
+            worker_measurements,
+            ..self
+        }
+    }
+
+    pub fn worker_signers(self, worker_signers: Vec<String>) -> Self {
+        Self {
+            worker_signers,
+            ..self
+        }
+    }
+
+    pub fn function_test(self, function_test: FunctionTest) -> Self {
+        Self {
+            function_test: Some(function_test),
+            ..self
+        }
+    }
+
+    pub fn priority(self, priority: TaskPriority) -> Self {
+        Self { priority, ..self }
+    }
+
+    pub fn resource_limits(self, resource_limits: ResourceLimits) -> Self {
+        Self {
+            resource_limits,
+            ..self
+        }
+    }
+
+    pub fn timeout(self, timeout: Option<u64>) -> Self {
+        Self { timeout, ..self }
+    }
+
+    pub fn retry_policy(self, retry_policy: RetryPolicy) -> Self {
+        Self {
+            retry_policy,
+            ..self
+        }
+    }
+
+    /// Whether the execution enclave of the measurement and the signer is
+    /// allowed to run the task. Unattested workers are only allowed for
+    /// unpinned tasks.
+    pub fn accepts_worker(&self, mr_enclave: Option<&str>, mr_signer: Option<&str>) -> bool {
+        is_pinned_to(&self.worker_measurements, mr_enclave)
+            && is_pinned_to(&self.worker_signers, mr_signer)
+    }
+
+    /// Shared queue of the tasks of the normal priority.
     pub fn get_queue_key() -> &'static str {
         QUEUE_KEY
     }
+
+    /// Queue of the tasks of the priority and the executor of this task.
+    pub fn queue_key(&self) -> String {
+        self.priority.executor_queue_key(self.executor)
+    }
+}
+
+// Whether a worker of the measurement is among the pinned ones, or any worker
+// if none is pinned.
+fn is_pinned_to(pinned: &[String], measurement: Option<&str>) -> bool {
+    if pinned.is_empty() {
+        return true;
+    }
+    measurement.map_or(false, |measurement| {
+        pinned.iter().any(|p| p.eq_ignore_ascii_case(measurement))
+    })
 }
@@ -15,11 +15,93 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use crate::ExternalID;
+use crate::{ExternalID, FileAuthTag};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::prelude::v1::*;
+use teaclave_crypto::TeaclaveFile128Key;
 use uuid::Uuid;
 
+// JSON records never start with a zero byte.
+const BINCODE_HEADER: &[u8] = b"\0bincode";
+
+/// Serialization format of stored records, which is chosen by the type, i.e.,
+/// the key prefix, of the records. Records are read in either format, so that
+/// records written before their type switched formats are still read, and they
+/// are migrated once written again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StorageCodec {
+    Json,
+    /// Compact binary format, tagged with a header. Fields are encoded by
+    /// position, so it only suits types whose fields are not added to with
+    /// defaults, and which hold no arbitrary JSON values.
+    Bincode,
+}
+
+impl StorageCodec {
+    /// Format of a stored record.
+    pub fn of(bytes: &[u8]) -> Self {
+        if bytes.starts_with(BINCODE_HEADER) {
+            StorageCodec::Bincode
+        } else {
+            StorageCodec::Json
+        }
+    }
+
+    pub fn encode<T: Serialize>(self, value: &T) -> anyhow::Result<Vec<u8>> {
+        let bytes = match self {
+            StorageCodec::Json => serde_json::to_vec(value)?,
+            StorageCodec::Bincode => {
+                let mut bytes = BINCODE_HEADER.to_vec();
+                bincode::serialize_into(&mut bytes, value)?;
+                bytes
+            }
+        };
+        Ok(bytes)
+    }
+
+    /// Decodes a record in the format it is written in.
+    pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> anyhow::Result<T> {
+        let value = match Self::of(bytes) {
+            StorageCodec::Json => serde_json::from_slice(bytes)?,
+            StorageCodec::Bincode => bincode::deserialize(&bytes[BINCODE_HEADER.len()..])?,
+        };
+        Ok(value)
+    }
+}
+
+/// Term under which a record is found in a secondary index of the storage
+/// service. The name of an index starts with the key prefix of its records,
+/// so that the index is kept by the same storage shard as the records.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexTerm {
+    pub index: String,
+    pub term: Vec<u8>,
+}
+
+impl IndexTerm {
+    pub fn new(index: impl ToString, term: impl Into<Vec<u8>>) -> Self {
+        Self {
+            index: index.to_string(),
+            term: term.into(),
+        }
+    }
+
+    /// Term of a record soft-deleted at the seconds since the Unix epoch in
+    /// one of the `DELETED_RECORD_INDEXES`. Terms are big-endian, so that
+    /// the records are ordered by the time they are deleted at.
+    pub fn deleted_at(index: impl ToString, deleted_at: u64) -> Self {
+        Self::new(index, deleted_at.to_be_bytes().to_vec())
+    }
+}
+
+/// Indexes of the soft-deleted records by the time they are deleted at, whose
+/// records the storage service purges once the retention window passed.
+pub const DELETED_RECORD_INDEXES: &[&str] = &[
+    crate::file::INPUT_FILE_DELETED_INDEX,
+    crate::file::OUTPUT_FILE_DELETED_INDEX,
+];
+
 pub trait Storable: Serialize + for<'de> Deserialize<'de> {
     fn key_prefix() -> &'static str;
 
@@ -37,17 +119,67 @@ pub trait Storable: Serialize + for<'de> Deserialize<'de> {
         key.starts_with(Self::key_prefix())
     }
 
+    fn codec() -> StorageCodec {
+        StorageCodec::Json
+    }
+
+    /// Terms of the secondary indexes the record is written with, which
+    /// replace the terms it was written with before.
+    fn index_terms(&self) -> Vec<IndexTerm> {
+        Vec::new()
+    }
+
+    /// Whether the record is soft-deleted, i.e., kept until it is purged but
+    /// read as if it were missing.
+    fn is_deleted(&self) -> bool {
+        false
+    }
+
     fn to_vec(&self) -> anyhow::Result<Vec<u8>> {
-        let bytes = serde_json::to_vec(self)?;
-        Ok(bytes)
+        Self::codec().encode(self)
     }
 
     fn from_slice(bytes: &[u8]) -> anyhow::Result<Self> {
-        let obj = serde_json::from_slice(bytes)?;
-        Ok(obj)
+        StorageCodec::decode(bytes)
+    }
+
+    /// Re-encodes a stored record in the format of its type, or returns
+    /// `None` if it is already in that format.
+    fn migrate(bytes: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        if StorageCodec::of(bytes) == Self::codec() {
+            return Ok(None);
+        }
+        Self::from_slice(bytes)?.to_vec().map(Some)
     }
 
     fn external_id(&self) -> ExternalID {
         ExternalID::new(Self::key_prefix(), self.uuid())
     }
 }
+
+/// Sealed snapshot of all keys of a storage service, kept by the storage
+/// service under the name. Anyone holding the key can read the snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StorageSnapshot {
+    pub name: String,
+    pub crypto_info: TeaclaveFile128Key,
+    pub cmac: FileAuthTag,
+    /// Number of the keys
+    pub entries: u64,
+}
+
+impl StorageSnapshot {
+    pub fn new(
+        name: impl Into<String>,
+        crypto_info: TeaclaveFile128Key,
+        cmac: FileAuthTag,
+        entries: u64,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            crypto_info,
+            cmac,
+            entries,
+        }
+    }
+}
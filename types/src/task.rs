@@ -44,6 +44,15 @@ impl std::convert::From<UserID> for String {
     }
 }
 
+impl UserID {
+    /// Identifier of the tenant owning the caches and scratch files of tasks
+    /// created by the user on workers, safe to be used as a file name.
+    pub fn tenant_id(&self) -> String {
+        let digest = ring::digest::digest(&ring::digest::SHA256, self.0.as_bytes());
+        hex::encode(&digest.as_ref()[..16])
+    }
+}
+
 impl std::fmt::Display for UserID {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -130,6 +139,12 @@ pub enum TaskStatus {
     Staged,
     Running,
     Finished,
+    /// Canceled by the creator before it started running.
+    Canceled,
+    /// Expired before it started running.
+    Expired,
+    /// Rejected by one of the participants before it was invoked.
+    Rejected,
 }
 
 impl Default for TaskStatus {
@@ -199,6 +214,17 @@ impl std::iter::FromIterator<(String, FileAuthTag)> for OutputsTags {
 pub struct TaskOutputs {
     pub return_value: Vec<u8>,
     pub tags_map: OutputsTags,
+    // Set instead of the return value if it is stored in chunks
+    #[serde(default)]
+    pub return_value_handle: Option<ReturnValueHandle>,
+    // Set if the task timed out, and only the outputs in the tags map were
+    // salvaged
+    #[serde(default)]
+    pub partial: bool,
+    // Contents of the outputs of a function test, which are returned instead
+    // of being uploaded
+    #[serde(default)]
+    pub sample_outputs: HashMap<String, Vec<u8>>,
 }
 
 impl TaskOutputs {
@@ -206,21 +232,128 @@ impl TaskOutputs {
         TaskOutputs {
             return_value: value.into(),
             tags_map: OutputsTags::new(tags_map),
+            return_value_handle: None,
+            partial: false,
+            sample_outputs: HashMap::new(),
         }
     }
+
+    pub fn partial(self, partial: bool) -> Self {
+        Self { partial, ..self }
+    }
+
+    pub fn sample_outputs(self, sample_outputs: HashMap<String, Vec<u8>>) -> Self {
+        Self {
+            sample_outputs,
+            ..self
+        }
+    }
+
+    pub fn return_value_handle(self, return_value_handle: Option<ReturnValueHandle>) -> Self {
+        Self {
+            return_value_handle,
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum TaskFailureKind {
+    Error,
+    /// The task exceeded its resource limits, see `ResourceLimits`.
+    ResourceExceeded,
+    /// The task did not finish within its timeout.
+    TimedOut,
+    /// The task failed for a reason other than the task itself, see
+    /// `TransientFailure`, and may be retried.
+    Transient,
+}
+
+impl Default for TaskFailureKind {
+    fn default() -> Self {
+        TaskFailureKind::Error
+    }
+}
+
+impl std::convert::TryFrom<&str> for TaskFailureKind {
+    type Error = anyhow::Error;
+
+    fn try_from(kind: &str) -> Result<Self> {
+        let kind = match kind {
+            "error" => TaskFailureKind::Error,
+            "resource_exceeded" => TaskFailureKind::ResourceExceeded,
+            "timed_out" => TaskFailureKind::TimedOut,
+            "transient" => TaskFailureKind::Transient,
+            _ => bail!("Unsupported task failure kind: {}", kind),
+        };
+        Ok(kind)
+    }
+}
+
+impl std::fmt::Display for TaskFailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            TaskFailureKind::Error => "error",
+            TaskFailureKind::ResourceExceeded => "resource_exceeded",
+            TaskFailureKind::TimedOut => "timed_out",
+            TaskFailureKind::Transient => "transient",
+        };
+        write!(f, "{}", kind)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TaskFailure {
     pub reason: String,
+    #[serde(default)]
+    pub kind: TaskFailureKind,
 }
 
 impl TaskFailure {
     pub fn new(reason: impl ToString) -> Self {
         TaskFailure {
             reason: reason.to_string(),
+            kind: TaskFailureKind::Error,
+        }
+    }
+
+    pub fn resource_exceeded(resource: ResourceExceeded) -> Self {
+        TaskFailure {
+            reason: resource.to_string(),
+            kind: TaskFailureKind::ResourceExceeded,
         }
     }
+
+    pub fn timed_out(timed_out: TaskTimedOut) -> Self {
+        TaskFailure {
+            reason: timed_out.to_string(),
+            kind: TaskFailureKind::TimedOut,
+        }
+    }
+
+    pub fn transient(failure: &TransientFailure) -> Self {
+        TaskFailure {
+            reason: failure.to_string(),
+            kind: TaskFailureKind::Transient,
+        }
+    }
+
+    /// Fails with the resource limit exceeded, the timeout or the transient
+    /// failure in the chain of the error, if any.
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(resource) = cause.downcast_ref::<ResourceExceeded>() {
+                return TaskFailure::resource_exceeded(*resource);
+            }
+            if let Some(timed_out) = cause.downcast_ref::<TaskTimedOut>() {
+                return TaskFailure::timed_out(*timed_out);
+            }
+            if let Some(failure) = cause.downcast_ref::<TransientFailure>() {
+                return TaskFailure::transient(failure);
+            }
+        }
+        TaskFailure::new(error)
+    }
 }
 
 impl std::fmt::Display for TaskFailure {
@@ -298,6 +431,13 @@ impl TaskResult {
         }
     }
 
+    pub fn is_partial(&self) -> bool {
+        match self {
+            TaskResult::Ok(outputs) => outputs.partial,
+            _ => false,
+        }
+    }
+
     #[cfg(test_mode)]
     pub fn unwrap(self) -> TaskOutputs {
         match self {
@@ -362,6 +502,14 @@ impl TaskFileOwners {
         self.inner.keys()
     }
 
+    pub fn values(&self) -> std::collections::hash_map::Values<String, OwnerList> {
+        self.inner.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.inner.is_empty()
     }
@@ -445,6 +593,14 @@ where
         self.inner.keys()
     }
 
+    pub fn get(&self, fname: &str) -> Option<&T> {
+        self.inner.get(fname)
+    }
+
+    pub fn contains_key(&self, fname: &str) -> bool {
+        self.inner.contains_key(fname)
+    }
+
     pub fn external_ids(&self) -> HashMap<String, ExternalID> {
         self.inner
             .iter()
@@ -458,10 +614,11 @@ impl TaskFiles<TeaclaveOutputFile> {
         &mut self,
         fname: &str,
         auth_tag: &FileAuthTag,
+        producer: ExternalID,
     ) -> Result<&TeaclaveOutputFile> {
         let file = match self.inner.get_mut(fname) {
             Some(file) => {
-                file.assign_cmac(auth_tag)?;
+                file.assign_cmac(auth_tag, producer)?;
                 file
             }
             _ => bail!("Upadate_cmac: file not found. {:?}", fname),
@@ -469,6 +626,25 @@ impl TaskFiles<TeaclaveOutputFile> {
 
         Ok(file)
     }
+
+    /// Replaces the URL and credential of the deferred outputs with the ones
+    /// of their current registration returned by `current`.
+    pub fn resolve_deferred<F>(&mut self, mut current: F) -> Result<()>
+    where
+        F: FnMut(&ExternalID) -> Result<TeaclaveOutputFile>,
+    {
+        for file in self.inner.values_mut().filter(|file| file.is_deferred()) {
+            let registered = current(&file.external_id())?;
+            ensure!(
+                !registered.is_deferred(),
+                "Output file url not set. {:?}",
+                file.external_id()
+            );
+            file.url = registered.url;
+            file.credential = registered.credential;
+        }
+        Ok(())
+    }
 }
 
 impl<T> IntoIterator for TaskFiles<T>
@@ -0,0 +1,61 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const TASK_APPROVAL_WINDOW_PREFIX: &str = "taskapprovalwindow";
+const TASK_APPROVAL_WINDOW_QUEUE_KEY: &str = "task-approval-window-queue";
+
+/// End of the approval window of a task, queued for the scheduler to expire
+/// the task and notify the participants unless every participant has
+/// approved it by then.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaskApprovalWindow {
+    pub task_id: Uuid,
+    /// Seconds since the Unix epoch
+    pub expires_at: u64,
+}
+
+impl Storable for TaskApprovalWindow {
+    fn key_prefix() -> &'static str {
+        TASK_APPROVAL_WINDOW_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.task_id
+    }
+}
+
+impl TaskApprovalWindow {
+    pub fn new(task_id: Uuid, expires_at: u64) -> Self {
+        Self {
+            task_id,
+            expires_at,
+        }
+    }
+
+    pub fn get_queue_key() -> &'static str {
+        TASK_APPROVAL_WINDOW_QUEUE_KEY
+    }
+
+    pub fn has_elapsed(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+}
@@ -0,0 +1,96 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+use std::prelude::v1::*;
+use teaclave_crypto::TeaclaveFile128Key;
+use url::Url;
+use uuid::Uuid;
+
+const ARCHIVED_TASK_PREFIX: &str = "archivedtask";
+
+/// Records of a finished task moved out of the storage service, which are
+/// sealed into a bundle kept in the archive storage.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaskArchive {
+    pub task: TaskState,
+    pub manifest: Option<TaskManifest>,
+}
+
+impl TaskArchive {
+    pub fn new(task: TaskState, manifest: Option<TaskManifest>) -> Self {
+        Self { task, manifest }
+    }
+
+    /// Seals the bundle into a protected file with a random key.
+    pub fn seal(&self, path: impl AsRef<Path>) -> Result<StagedFileInfo> {
+        let bytes = serde_json::to_vec(self)?;
+        StagedFileInfo::create_with_bytes(path, &bytes)
+    }
+
+    /// Opens a sealed bundle, which is rejected if its cmac does not match.
+    pub fn unseal(sealed: &StagedFileInfo) -> Result<Self> {
+        let mut bytes = Vec::new();
+        sealed.create_readable_io()?.read_to_end(&mut bytes)?;
+        let archive: Self = serde_json::from_slice(&bytes)?;
+        Ok(archive)
+    }
+}
+
+/// Record of an archived task left in the storage service, with the key and
+/// cmac of the sealed bundle for restoring it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ArchivedTask {
+    pub task_id: Uuid,
+    pub participants: UserList,
+    pub url: Url,
+    pub crypto_info: TeaclaveFile128Key,
+    pub cmac: FileAuthTag,
+    /// Seconds since the Unix epoch
+    pub archived_at: u64,
+}
+
+impl Storable for ArchivedTask {
+    fn key_prefix() -> &'static str {
+        ARCHIVED_TASK_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.task_id
+    }
+}
+
+impl ArchivedTask {
+    pub fn new(archive: &TaskArchive, url: Url, sealed: &StagedFileInfo, archived_at: u64) -> Self {
+        Self {
+            task_id: archive.task.task_id,
+            participants: archive.task.participants.clone(),
+            url,
+            crypto_info: sealed.crypto_info,
+            cmac: sealed.cmac,
+            archived_at,
+        }
+    }
+
+    pub fn external_id_of(task_id: &Uuid) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), task_id.to_owned())
+    }
+}
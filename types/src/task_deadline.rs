@@ -0,0 +1,81 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::TrustedTime;
+use std::collections::HashSet;
+use std::prelude::v1::*;
+use std::sync::Arc;
+#[cfg(not(feature = "mesalock_sgx"))]
+use std::sync::Mutex;
+#[cfg(feature = "mesalock_sgx")]
+use std::sync::SgxMutex as Mutex;
+use std::time::{Duration, SystemTime};
+
+/// Deadline of a running function. After the timeout, the function can no
+/// longer open inputs or create outputs, and it is given a grace period to
+/// finish writing the outputs it has created. The outputs closed before the
+/// grace period ends are salvaged as a partial result of the task.
+#[derive(Debug, Clone)]
+pub struct TaskDeadline {
+    timeout: SystemTime,
+    grace_end: SystemTime,
+    closed_outputs: Arc<Mutex<HashSet<String>>>,
+}
+
+impl TaskDeadline {
+    /// Starts the deadline from now.
+    pub fn new(timeout: Duration, grace_period: Duration) -> Self {
+        let now = TrustedTime::now();
+        Self {
+            timeout: now + timeout,
+            grace_end: now + timeout + grace_period,
+            closed_outputs: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    pub fn is_timed_out(&self) -> bool {
+        TrustedTime::now() >= self.timeout
+    }
+
+    pub fn is_grace_period_over(&self) -> bool {
+        TrustedTime::now() >= self.grace_end
+    }
+
+    /// Records an output closed by the function, unless the grace period is
+    /// over.
+    pub fn close_output(&self, identifier: &str) {
+        if self.is_grace_period_over() {
+            return;
+        }
+        if let Ok(mut closed_outputs) = self.closed_outputs.lock() {
+            closed_outputs.insert(identifier.to_string());
+        }
+    }
+
+    pub fn closed_outputs(&self) -> HashSet<String> {
+        self.closed_outputs
+            .lock()
+            .map(|closed_outputs| closed_outputs.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// A task not finished within the timeout given when it was created, in
+/// seconds.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+#[error("Task timed out after {0} seconds")]
+pub struct TaskTimedOut(pub u64);
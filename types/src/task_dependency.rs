@@ -0,0 +1,68 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const DEFERRED_TASK_PREFIX: &str = "deferredtask";
+const DEFERRED_TASK_QUEUE_KEY: &str = "deferred-task-queue";
+
+/// An output of an upstream task consumed as an input of a task.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TaskOutputReference {
+    pub task_id: ExternalID,
+    /// Name of the output in the function of the upstream task
+    pub output: String,
+}
+
+impl TaskOutputReference {
+    pub fn new(task_id: ExternalID, output: impl ToString) -> Self {
+        Self {
+            task_id,
+            output: output.to_string(),
+        }
+    }
+}
+
+/// A task invoked before the upstream tasks of its inputs finished, queued
+/// for the scheduler to stage it once they have. The task is staged by the
+/// management service except for the inputs of the upstream tasks.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeferredTask {
+    pub staged_task: StagedTask,
+}
+
+impl Storable for DeferredTask {
+    fn key_prefix() -> &'static str {
+        DEFERRED_TASK_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.staged_task.task_id
+    }
+}
+
+impl DeferredTask {
+    pub fn new(staged_task: StagedTask) -> Self {
+        Self { staged_task }
+    }
+
+    pub fn get_queue_key() -> &'static str {
+        DEFERRED_TASK_QUEUE_KEY
+    }
+}
@@ -0,0 +1,77 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const TASK_DISPATCH_PREFIX: &str = "taskdispatch";
+const TASK_DISPATCH_QUEUE_KEY: &str = "task-dispatch-queue";
+
+/// Seconds the scheduler waits past the timeout of a dispatched task before
+/// failing it, leaving the execution service time to time out the task
+/// itself, including the grace period of the function.
+pub const TASK_TIMEOUT_SLACK: u64 = 300;
+
+/// Dispatch of a task with a timeout to an execution service, queued for the
+/// scheduler to fail the task if its result is not reported in time, e.g.,
+/// because the execution service hung.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaskDispatch {
+    pub task_id: Uuid,
+    /// Seconds since the Unix epoch
+    pub dispatched_at: u64,
+    /// Seconds
+    pub timeout: u64,
+    /// Retries of the task before the dispatched run.
+    #[serde(default)]
+    pub attempt: u32,
+}
+
+impl Storable for TaskDispatch {
+    fn key_prefix() -> &'static str {
+        TASK_DISPATCH_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.task_id
+    }
+}
+
+impl TaskDispatch {
+    pub fn new(task_id: Uuid, dispatched_at: u64, timeout: u64) -> Self {
+        Self {
+            task_id,
+            dispatched_at,
+            timeout,
+            attempt: 0,
+        }
+    }
+
+    pub fn attempt(self, attempt: u32) -> Self {
+        Self { attempt, ..self }
+    }
+
+    pub fn get_queue_key() -> &'static str {
+        TASK_DISPATCH_QUEUE_KEY
+    }
+
+    pub fn is_overdue(&self, now: u64) -> bool {
+        let due = self.dispatched_at.saturating_add(self.timeout);
+        now >= due.saturating_add(TASK_TIMEOUT_SLACK)
+    }
+}
@@ -0,0 +1,203 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const FUNCTION_USAGE_PREFIX: &str = "functionusage";
+const QUEUE_BACKLOG_PREFIX: &str = "queuebacklog";
+
+/// Weight of the earlier runs of a function each time a run is recorded, so
+/// that the statistics follow changes of the function and of its inputs.
+const USAGE_DECAY: f64 = 0.95;
+
+/// Resources used by a run of a task, reported by the execution service along
+/// with the result of the task.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct TaskUsage {
+    /// Milliseconds from the task starting to run until its outputs are
+    /// uploaded
+    pub run_time: u64,
+    /// Bytes of the input files of the task
+    pub input_size: u64,
+}
+
+impl TaskUsage {
+    pub fn new(run_time: u64, input_size: u64) -> Self {
+        Self {
+            run_time,
+            input_size,
+        }
+    }
+}
+
+/// Usage statistics of the successful runs of a function. The run time is
+/// fitted to be linear in the input size by least squares, where earlier runs
+/// weigh less than recent ones.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct FunctionUsage {
+    pub function_id: Uuid,
+    pub runs: u64,
+    weight: f64,
+    sum_size: f64,
+    sum_time: f64,
+    sum_size_squared: f64,
+    sum_size_time: f64,
+}
+
+impl Storable for FunctionUsage {
+    fn key_prefix() -> &'static str {
+        FUNCTION_USAGE_PREFIX
+    }
+
+    // Statistics are rewritten on every run of the function.
+    fn codec() -> StorageCodec {
+        StorageCodec::Bincode
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.function_id
+    }
+}
+
+impl FunctionUsage {
+    pub fn new(function_id: Uuid) -> Self {
+        Self {
+            function_id,
+            ..Default::default()
+        }
+    }
+
+    pub fn external_id_of(function_id: &Uuid) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), *function_id)
+    }
+
+    pub fn record(&mut self, usage: &TaskUsage) {
+        let size = usage.input_size as f64;
+        let time = usage.run_time as f64;
+        self.runs += 1;
+        self.weight = self.weight * USAGE_DECAY + 1.0;
+        self.sum_size = self.sum_size * USAGE_DECAY + size;
+        self.sum_time = self.sum_time * USAGE_DECAY + time;
+        self.sum_size_squared = self.sum_size_squared * USAGE_DECAY + size * size;
+        self.sum_size_time = self.sum_size_time * USAGE_DECAY + size * time;
+    }
+
+    /// Milliseconds a run with the input size is expected to take, none if
+    /// the function has never run. The run time never decreases with the
+    /// input size, and is the mean run time if the input sizes of the runs
+    /// hardly vary.
+    pub fn estimate_run_time(&self, input_size: u64) -> Option<u64> {
+        if self.runs == 0 {
+            return None;
+        }
+        let mean_size = self.sum_size / self.weight;
+        let mean_time = self.sum_time / self.weight;
+        let variance = self.sum_size_squared / self.weight - mean_size * mean_size;
+        let covariance = self.sum_size_time / self.weight - mean_size * mean_time;
+        let slope = if variance > 1.0 + mean_size * mean_size * 1e-9 {
+            (covariance / variance).max(0.0)
+        } else {
+            0.0
+        };
+        let time = mean_time + slope * (input_size as f64 - mean_size);
+        Some(time.max(0.0).round() as u64)
+    }
+
+    /// Milliseconds a run with inputs of the usual size is expected to take.
+    pub fn mean_run_time(&self) -> Option<u64> {
+        if self.runs == 0 {
+            return None;
+        }
+        Some((self.sum_time / self.weight).round() as u64)
+    }
+}
+
+/// Staged tasks of a priority, and the estimated milliseconds of running
+/// them.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct PriorityBacklog {
+    pub priority: TaskPriority,
+    pub tasks: u64,
+    pub work: u64,
+}
+
+impl PriorityBacklog {
+    pub fn new(priority: TaskPriority, tasks: u64, work: u64) -> Self {
+        Self {
+            priority,
+            tasks,
+            work,
+        }
+    }
+}
+
+/// Snapshot of the staged queues taken by the scheduler on its periodic
+/// sweep, from which the wait of a task before it runs is estimated.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct QueueBacklog {
+    pub priorities: Vec<PriorityBacklog>,
+    /// Registered execution workers which are heartbeating
+    pub live_workers: u32,
+    /// Seconds since the Unix epoch
+    pub taken_at: u64,
+}
+
+impl Storable for QueueBacklog {
+    fn key_prefix() -> &'static str {
+        QUEUE_BACKLOG_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        Uuid::nil()
+    }
+}
+
+impl QueueBacklog {
+    pub fn new(priorities: Vec<PriorityBacklog>, live_workers: u32, taken_at: u64) -> Self {
+        Self {
+            priorities,
+            live_workers,
+            taken_at,
+        }
+    }
+
+    pub fn external_id() -> ExternalID {
+        ExternalID::new(Self::key_prefix(), Uuid::nil())
+    }
+
+    /// Milliseconds a task of the priority staged now is expected to wait
+    /// before it is pulled: the work queued at the priority and above, shared
+    /// by the live workers. Turns given to lower priorities against
+    /// starvation are not accounted for.
+    pub fn queue_wait(&self, priority: TaskPriority) -> u64 {
+        let levels = TaskPriority::levels();
+        let ahead = match levels.iter().position(|level| *level == priority) {
+            Some(position) => &levels[..=position],
+            None => levels,
+        };
+        let work: u64 = self
+            .priorities
+            .iter()
+            .filter(|backlog| ahead.contains(&backlog.priority))
+            .map(|backlog| backlog.work)
+            .sum();
+        work / u64::from(self.live_workers.max(1))
+    }
+}
@@ -0,0 +1,191 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const USER_TASKS_PREFIX: &str = "usertasks";
+
+/// Index of the tasks a user participates in, keyed by the user id.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct UserTasks {
+    pub user_id: UserID,
+    pub task_ids: Vec<Uuid>,
+    /// Entries of the tasks, which tasks indexed before entries were recorded
+    /// have none of.
+    #[serde(default)]
+    pub entries: HashMap<Uuid, TaskIndexEntry>,
+}
+
+/// Attributes of an indexed task which never change, by which the tasks of a
+/// user are filtered without reading them.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TaskIndexEntry {
+    pub function_id: ExternalID,
+    /// Seconds since the Unix epoch
+    pub created_at: Option<u64>,
+}
+
+/// Filters of the tasks listed for a user, all of which a listed task
+/// matches. Tasks are created within the range `[created_after,
+/// created_before)`, and tasks whose creation time is unknown are not.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskFilter {
+    pub statuses: Vec<TaskStatus>,
+    pub function_id: Option<ExternalID>,
+    pub participant: Option<UserID>,
+    pub created_after: Option<u64>,
+    pub created_before: Option<u64>,
+}
+
+impl Storable for UserTasks {
+    fn key_prefix() -> &'static str {
+        USER_TASKS_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        user_uuid(&self.user_id)
+    }
+}
+
+impl UserTasks {
+    pub fn new(user_id: UserID) -> Self {
+        Self {
+            user_id,
+            task_ids: Vec::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn external_id_of(user_id: &UserID) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), user_uuid(user_id))
+    }
+
+    pub fn add_task(&mut self, ts: &TaskState) {
+        if !self.task_ids.contains(&ts.task_id) {
+            self.task_ids.push(ts.task_id);
+        }
+        let entry = TaskIndexEntry {
+            function_id: ts.function_id.clone(),
+            created_at: ts.created_at,
+        };
+        self.entries.insert(ts.task_id, entry);
+    }
+
+    pub fn remove_task(&mut self, task_id: &Uuid) {
+        self.task_ids.retain(|id| id != task_id);
+        self.entries.remove(task_id);
+    }
+
+    pub fn entry(&self, task_id: &Uuid) -> Option<&TaskIndexEntry> {
+        self.entries.get(task_id)
+    }
+
+    pub fn task_external_ids(&self) -> impl Iterator<Item = ExternalID> + '_ {
+        self.task_ids
+            .iter()
+            .map(|task_id| ExternalID::new(TaskState::key_prefix(), *task_id))
+    }
+}
+
+impl TaskFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn statuses(self, statuses: impl IntoIterator<Item = TaskStatus>) -> Self {
+        Self {
+            statuses: statuses.into_iter().collect(),
+            ..self
+        }
+    }
+
+    pub fn function_id(self, function_id: ExternalID) -> Self {
+        Self {
+            function_id: Some(function_id),
+            ..self
+        }
+    }
+
+    pub fn participant(self, participant: UserID) -> Self {
+        Self {
+            participant: Some(participant),
+            ..self
+        }
+    }
+
+    pub fn created_between(self, created_after: Option<u64>, created_before: Option<u64>) -> Self {
+        Self {
+            created_after,
+            created_before,
+            ..self
+        }
+    }
+
+    /// Whether a task with the index entry may match, which is checked before
+    /// the task is read. Tasks without an entry are only checked once read.
+    pub fn may_match(&self, entry: Option<&TaskIndexEntry>) -> bool {
+        match entry {
+            Some(entry) => {
+                self.matches_function(&entry.function_id) && self.matches_creation(entry.created_at)
+            }
+            None => true,
+        }
+    }
+
+    pub fn matches(&self, ts: &TaskState) -> bool {
+        (self.statuses.is_empty() || self.statuses.contains(&ts.status))
+            && self.matches_function(&ts.function_id)
+            && self
+                .participant
+                .as_ref()
+                .map_or(true, |p| ts.has_participant(p))
+            && self.matches_creation(ts.created_at)
+    }
+
+    fn matches_function(&self, function_id: &ExternalID) -> bool {
+        self.function_id
+            .as_ref()
+            .map_or(true, |id| id == function_id)
+    }
+
+    fn matches_creation(&self, created_at: Option<u64>) -> bool {
+        if self.created_after.is_none() && self.created_before.is_none() {
+            return true;
+        }
+        match created_at {
+            Some(created_at) => {
+                self.created_after.map_or(true, |after| created_at >= after)
+                    && self
+                        .created_before
+                        .map_or(true, |before| created_at < before)
+            }
+            None => false,
+        }
+    }
+}
+
+// User ids are arbitrary strings, derive a stable uuid for the storage key.
+pub(crate) fn user_uuid(user_id: &UserID) -> Uuid {
+    let digest = ring::digest::digest(&ring::digest::SHA256, user_id.to_string().as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest.as_ref()[..16]);
+    Uuid::from_bytes(bytes)
+}
@@ -0,0 +1,192 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use anyhow::{anyhow, Result};
+use ring::{agreement, hkdf, rand};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::prelude::v1::*;
+use teaclave_crypto::AesGcm256Key;
+use uuid::Uuid;
+
+const TASK_KEY_EXCHANGE_PREFIX: &str = "taskkeyexchange";
+const TASK_KEY_OFFER_CONTEXT: &[u8] = b"teaclave-task-key-offer";
+// Key and IV of AES-256-GCM wrapping the key of a file.
+const WRAPPING_KEY_LENGTH: usize = 32 + 12;
+
+/// Ephemeral ECDH (NIST P-256) public key of the execution enclave running a
+/// task. The offer is signed by the attested TLS key of the enclave, which is
+/// bound in the report data of the attestation report in the certificate.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TaskKeyOffer {
+    /// Public key in the uncompressed encoding
+    pub public_key: Vec<u8>,
+    /// Attested TLS certificate of the enclave in DER
+    pub cert: Vec<u8>,
+    /// ECDSA (NIST P-256, SHA-256) signature of the message of the offer in
+    /// ASN.1 DER
+    pub signature: Vec<u8>,
+}
+
+impl TaskKeyOffer {
+    pub fn new(public_key: Vec<u8>, cert: Vec<u8>, signature: Vec<u8>) -> Self {
+        Self {
+            public_key,
+            cert,
+            signature,
+        }
+    }
+
+    /// The message signed by the enclave, binding the public key to the task.
+    pub fn message(task_id: &Uuid, public_key: &[u8]) -> Vec<u8> {
+        let mut message = TASK_KEY_OFFER_CONTEXT.to_vec();
+        message.extend_from_slice(task_id.as_bytes());
+        message.extend_from_slice(public_key);
+        message
+    }
+}
+
+/// Keys of the files of a participant wrapped under the secret shared with
+/// the enclave making the offer.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WrappedTaskKeys {
+    /// Public key of the offer the keys are wrapped for
+    pub offer_public_key: Vec<u8>,
+    /// Ephemeral ECDH public key of the participant in the uncompressed
+    /// encoding
+    pub public_key: Vec<u8>,
+    /// Wrapped crypto info of the files by their names in the function
+    pub keys: HashMap<String, Vec<u8>>,
+}
+
+impl WrappedTaskKeys {
+    /// Wraps the keys for the offer with a fresh ephemeral key pair.
+    pub fn wrap(
+        task_id: &Uuid,
+        offer_public_key: &[u8],
+        keys: &HashMap<String, FileCrypto>,
+    ) -> Result<Self> {
+        let rng = rand::SystemRandom::new();
+        let private_key = agreement::EphemeralPrivateKey::generate(&agreement::ECDH_P256, &rng)
+            .map_err(|_| anyhow!("Cannot generate ephemeral key"))?;
+        let public_key = private_key
+            .compute_public_key()
+            .map_err(|_| anyhow!("Cannot compute ephemeral public key"))?
+            .as_ref()
+            .to_vec();
+        let peer_public_key =
+            agreement::UnparsedPublicKey::new(&agreement::ECDH_P256, offer_public_key);
+        let shared_secret = agreement::agree_ephemeral(
+            private_key,
+            &peer_public_key,
+            anyhow!("Invalid offer public key"),
+            |shared_secret| Ok(shared_secret.to_vec()),
+        )?;
+
+        let mut wrapped_keys = HashMap::new();
+        for (fname, crypto) in keys.iter() {
+            let mut wrapped = serde_json::to_vec(crypto)?;
+            wrapping_key(task_id, fname, &shared_secret)?.encrypt(&mut wrapped)?;
+            wrapped_keys.insert(fname.to_owned(), wrapped);
+        }
+
+        Ok(Self {
+            offer_public_key: offer_public_key.to_vec(),
+            public_key,
+            keys: wrapped_keys,
+        })
+    }
+
+    /// Unwraps the keys with the secret shared with the participant, i.e., the
+    /// big-endian x-coordinate of the ECDH result.
+    pub fn unwrap(
+        &self,
+        task_id: &Uuid,
+        shared_secret: &[u8],
+    ) -> Result<HashMap<String, FileCrypto>> {
+        let mut keys = HashMap::new();
+        for (fname, wrapped) in self.keys.iter() {
+            let mut crypto = wrapped.to_owned();
+            wrapping_key(task_id, fname, shared_secret)?.decrypt(&mut crypto)?;
+            keys.insert(fname.to_owned(), serde_json::from_slice(&crypto)?);
+        }
+        Ok(keys)
+    }
+}
+
+// Every file has its own wrapping key derived with HKDF-SHA256, salted with
+// the task id, so that no key and IV is used twice.
+fn wrapping_key(task_id: &Uuid, fname: &str, shared_secret: &[u8]) -> Result<AesGcm256Key> {
+    struct WrappingKeyLength;
+
+    impl hkdf::KeyType for WrappingKeyLength {
+        fn len(&self) -> usize {
+            WRAPPING_KEY_LENGTH
+        }
+    }
+
+    let mut okm = [0u8; WRAPPING_KEY_LENGTH];
+    hkdf::Salt::new(hkdf::HKDF_SHA256, task_id.as_bytes())
+        .extract(shared_secret)
+        .expand(&[fname.as_bytes()], WrappingKeyLength)
+        .and_then(|okm_ref| okm_ref.fill(&mut okm))
+        .map_err(|_| anyhow!("Cannot derive wrapping key"))?;
+    AesGcm256Key::new(&okm[..32], &okm[32..])
+}
+
+/// The offer of the enclave running a task and the keys wrapped for it by the
+/// participants.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaskKeyExchange {
+    pub task_id: Uuid,
+    pub offer: TaskKeyOffer,
+    pub wrapped_keys: Vec<WrappedTaskKeys>,
+}
+
+impl Storable for TaskKeyExchange {
+    fn key_prefix() -> &'static str {
+        TASK_KEY_EXCHANGE_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.task_id
+    }
+}
+
+impl TaskKeyExchange {
+    pub fn new(task_id: Uuid, offer: TaskKeyOffer) -> Self {
+        Self {
+            task_id,
+            offer,
+            wrapped_keys: Vec::new(),
+        }
+    }
+
+    pub fn external_id_of(task_id: &Uuid) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), task_id.to_owned())
+    }
+
+    /// Names of the files whose keys are wrapped for the current offer.
+    pub fn wrapped_files(&self) -> Vec<&String> {
+        self.wrapped_keys
+            .iter()
+            .filter(|wrapped| wrapped.offer_public_key == self.offer.public_key)
+            .flat_map(|wrapped| wrapped.keys.keys())
+            .collect()
+    }
+}
@@ -0,0 +1,127 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const TASK_MANIFEST_PREFIX: &str = "manifest";
+
+/// Hex encoded measurement of an enclave.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ManifestMeasurement {
+    pub mr_enclave: String,
+    pub mr_signer: String,
+}
+
+impl From<&EnclaveMeasurement> for ManifestMeasurement {
+    fn from(measurement: &EnclaveMeasurement) -> Self {
+        Self {
+            mr_enclave: hex::encode(measurement.mr_enclave),
+            mr_signer: hex::encode(measurement.mr_signer),
+        }
+    }
+}
+
+/// Record of a finished task for reproducing it: what was executed, on which
+/// data, by which enclaves, and which results it produced. Hashes are hex
+/// encoded SHA-256 digests and file tags are hex encoded CMACs.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct TaskManifest {
+    pub task_id: Uuid,
+    pub function_id: String,
+    pub function_hash: String,
+    pub arguments_hash: String,
+    pub executor: String,
+    pub executor_version: String,
+    pub input_cmacs: BTreeMap<String, String>,
+    pub output_cmacs: BTreeMap<String, String>,
+    pub enclave_measurements: BTreeMap<String, ManifestMeasurement>,
+}
+
+impl Storable for TaskManifest {
+    fn key_prefix() -> &'static str {
+        TASK_MANIFEST_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.task_id
+    }
+}
+
+impl TaskManifest {
+    pub fn new(
+        ts: &TaskState,
+        function: &Function,
+        output_tags: &OutputsTags,
+        measurements: &HashMap<String, EnclaveMeasurement>,
+    ) -> Self {
+        let function_hash = ring::digest::digest(&ring::digest::SHA256, &function.payload);
+        // Arguments are hashed in the canonical form with sorted keys.
+        let arguments = serde_json::json!(ts.function_arguments.inner()).to_string();
+        let arguments_hash = ring::digest::digest(&ring::digest::SHA256, arguments.as_bytes());
+
+        Self {
+            task_id: ts.task_id,
+            function_id: ts.function_id.to_string(),
+            function_hash: hex::encode(function_hash.as_ref()),
+            arguments_hash: hex::encode(arguments_hash.as_ref()),
+            executor: ts.executor.to_string(),
+            executor_version: env!("CARGO_PKG_VERSION").to_string(),
+            input_cmacs: ts
+                .assigned_inputs
+                .clone()
+                .into_iter()
+                .map(|(name, file)| (name, file.cmac.to_hex()))
+                .collect(),
+            output_cmacs: output_tags
+                .iter()
+                .map(|(name, tag)| (name.to_string(), tag.to_hex()))
+                .collect(),
+            enclave_measurements: measurements
+                .iter()
+                .map(|(name, measurement)| (name.to_string(), measurement.into()))
+                .collect(),
+        }
+    }
+
+    pub fn external_id_of(task_id: Uuid) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), task_id)
+    }
+}
+
+/// A task in the lineage of a data: the manifest of the task, i.e., the
+/// function version and the enclaves executing it, along with the data it
+/// consumed and produced.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LineageStep {
+    pub manifest: TaskManifest,
+    pub input_data_ids: BTreeMap<String, ExternalID>,
+    pub output_data_ids: BTreeMap<String, ExternalID>,
+}
+
+impl LineageStep {
+    pub fn new(manifest: TaskManifest, ts: &TaskState) -> Self {
+        Self {
+            manifest,
+            input_data_ids: ts.assigned_inputs.external_ids().into_iter().collect(),
+            output_data_ids: ts.assigned_outputs.external_ids().into_iter().collect(),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const TASK_NOTIFICATION_PREFIX: &str = "notification";
+const NOTIFICATION_QUEUE_PREFIX: &str = "notification-queue";
+
+/// What happened to the task a notification is about.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskNotificationKind {
+    /// The task is created and awaits the approval of the recipient.
+    Created,
+    /// The approval window of the task elapsed before every participant
+    /// approved it, and the task expired.
+    ApprovalExpired,
+}
+
+impl Default for TaskNotificationKind {
+    fn default() -> Self {
+        TaskNotificationKind::Created
+    }
+}
+
+/// Summary of a task sent to a participant, when the task is newly created
+/// and the approval of the participant is needed, or when it expired before
+/// being approved.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TaskNotification {
+    #[serde(default)]
+    pub kind: TaskNotificationKind,
+    pub task_id: Uuid,
+    pub recipient: UserID,
+    pub creator: UserID,
+    pub function_id: ExternalID,
+    pub function_name: String,
+    pub function_arguments: FunctionArguments,
+    pub requested_inputs: Vec<String>,
+    pub requested_outputs: Vec<String>,
+}
+
+impl Storable for TaskNotification {
+    fn key_prefix() -> &'static str {
+        TASK_NOTIFICATION_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.task_id
+    }
+}
+
+impl TaskNotification {
+    /// Create notifications for every participant other than the creator.
+    pub fn for_participants(ts: &TaskState, function_name: &str) -> Vec<Self> {
+        ts.participants
+            .clone()
+            .into_iter()
+            .filter(|participant| !ts.has_creator(participant))
+            .map(|recipient| Self {
+                kind: TaskNotificationKind::Created,
+                task_id: ts.task_id,
+                creator: ts.creator.clone(),
+                function_id: ts.function_id.clone(),
+                function_name: function_name.to_string(),
+                function_arguments: ts.function_arguments.clone(),
+                requested_inputs: requested_files(&ts.inputs_ownership, &recipient),
+                requested_outputs: requested_files(&ts.outputs_ownership, &recipient),
+                recipient,
+            })
+            .collect()
+    }
+
+    /// Create notifications for every participant, including the creator, of
+    /// a task whose approval window elapsed.
+    pub fn for_approval_expired(ts: &TaskState, function_name: &str) -> Vec<Self> {
+        ts.participants
+            .clone()
+            .into_iter()
+            .map(|recipient| Self {
+                kind: TaskNotificationKind::ApprovalExpired,
+                task_id: ts.task_id,
+                creator: ts.creator.clone(),
+                function_id: ts.function_id.clone(),
+                function_name: function_name.to_string(),
+                function_arguments: ts.function_arguments.clone(),
+                requested_inputs: requested_files(&ts.inputs_ownership, &recipient),
+                requested_outputs: requested_files(&ts.outputs_ownership, &recipient),
+                recipient,
+            })
+            .collect()
+    }
+
+    pub fn get_queue_key(user_id: &UserID) -> String {
+        format!("{}-{}", NOTIFICATION_QUEUE_PREFIX, user_id)
+    }
+
+    pub fn queue_key(&self) -> String {
+        Self::get_queue_key(&self.recipient)
+    }
+}
+
+fn requested_files(owners: &TaskFileOwners, user_id: &UserID) -> Vec<String> {
+    owners
+        .keys()
+        .filter(|name| owners.get(name).map_or(false, |o| o.contains(user_id)))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,103 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const TASK_RETRY_PREFIX: &str = "taskretry";
+const TASK_RETRY_QUEUE_KEY: &str = "task-retry-queue";
+
+/// Largest number of retries a task can be created with.
+pub const MAX_TASK_RETRIES: u32 = 10;
+/// Longest wait before a retry, in seconds, however many retries there were.
+pub const MAX_RETRY_BACKOFF: u64 = 3600;
+
+/// Policy of retrying a task failed transiently, given when the task is
+/// created. The task is retried up to `max_retries` times, and the wait before
+/// a retry doubles from `backoff` seconds with every retry. Never retried by
+/// default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub backoff: u64,
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, backoff: u64) -> Self {
+        Self {
+            max_retries,
+            backoff,
+        }
+    }
+
+    /// Seconds to wait before retrying a task retried `retry_count` times
+    /// already.
+    pub fn backoff_of(&self, retry_count: u32) -> u64 {
+        let factor = 1u64.checked_shl(retry_count).unwrap_or(u64::max_value());
+        self.backoff.saturating_mul(factor).min(MAX_RETRY_BACKOFF)
+    }
+}
+
+/// Failures of a task which are not caused by the task itself, so that the
+/// task may succeed when it is run again.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum TransientFailure {
+    #[error("Failed to fetch input files: {0}")]
+    FileFetch(String),
+    #[error("Worker crashed while invoking the function")]
+    WorkerCrash,
+    #[error("Worker stopped heartbeating while running the task")]
+    WorkerLost,
+}
+
+/// A task failed transiently, queued for the scheduler to stage it again
+/// once the backoff is over.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TaskRetry {
+    pub staged_task: StagedTask,
+    /// Seconds since the Unix epoch
+    pub retry_at: u64,
+}
+
+impl Storable for TaskRetry {
+    fn key_prefix() -> &'static str {
+        TASK_RETRY_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.staged_task.task_id
+    }
+}
+
+impl TaskRetry {
+    pub fn new(staged_task: StagedTask, retry_at: u64) -> Self {
+        Self {
+            staged_task,
+            retry_at,
+        }
+    }
+
+    pub fn get_queue_key() -> &'static str {
+        TASK_RETRY_QUEUE_KEY
+    }
+
+    pub fn is_due(&self, now: u64) -> bool {
+        now >= self.retry_at
+    }
+}
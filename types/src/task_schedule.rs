@@ -0,0 +1,299 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use anyhow::{anyhow, ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryInto;
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const TASK_SCHEDULE_PREFIX: &str = "taskschedule";
+const TASK_SCHEDULE_TICK_PREFIX: &str = "taskscheduletick";
+const TASK_SCHEDULE_TICK_QUEUE_KEY: &str = "task-schedule-tick-queue";
+/// The scheduler checks the ticks once a minute, so shorter intervals would
+/// not be kept anyway.
+pub const MIN_SCHEDULE_INTERVAL: u64 = 60;
+
+/// When the tasks of a schedule run: every `interval` seconds from
+/// `start_at`, for `max_runs` runs or until the schedule is canceled.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ScheduleSpec {
+    /// Seconds since the Unix epoch
+    pub start_at: u64,
+    /// Seconds between two runs
+    pub interval: u64,
+    pub max_runs: Option<u64>,
+}
+
+impl ScheduleSpec {
+    pub fn new(start_at: u64, interval: u64, max_runs: Option<u64>) -> Result<Self> {
+        ensure!(
+            interval >= MIN_SCHEDULE_INTERVAL,
+            "Schedule interval is shorter than {} seconds",
+            MIN_SCHEDULE_INTERVAL
+        );
+        ensure!(max_runs != Some(0), "Schedule has no run");
+        Ok(Self {
+            start_at,
+            interval,
+            max_runs,
+        })
+    }
+
+    /// Time of the given run, counted from zero, unless the schedule ends
+    /// before it.
+    pub fn run_at(&self, run: u64) -> Option<u64> {
+        if self.max_runs.map_or(false, |max_runs| run >= max_runs) {
+            return None;
+        }
+        run.checked_mul(self.interval)
+            .and_then(|offset| self.start_at.checked_add(offset))
+    }
+}
+
+/// Recurring tasks of a task template, stored alongside the template. Every
+/// participant approves the schedule once, with its inputs, instead of
+/// approving the task of each run. The task of a run is assigned the same
+/// input files, new fusion outputs of the owners of the outputs, and is
+/// staged by the scheduler service right away.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaskSchedule {
+    /// A template has one schedule at most, stored with the id of the
+    /// template.
+    pub template_id: Uuid,
+    /// Creation spec hash of the tasks of the runs, so that they fail to run
+    /// once the function is changed after the approvals
+    pub creation_spec_hash: String,
+    pub spec: ScheduleSpec,
+    /// Input files assigned to the task of every run by their names in the
+    /// function
+    pub inputs: HashMap<String, ExternalID>,
+    /// Participants of the template, and the owner of a private function
+    pub participants: UserList,
+    pub approved_users: UserList,
+    pub approval_receipts: Vec<ApprovalReceipt>,
+    /// Tasks of the runs so far, which is the audit trail of the schedule
+    pub task_ids: Vec<ExternalID>,
+    /// Number of runs so far, including those skipped
+    pub runs: u64,
+    pub canceled: bool,
+}
+
+impl Storable for TaskSchedule {
+    fn key_prefix() -> &'static str {
+        TASK_SCHEDULE_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.template_id
+    }
+}
+
+impl TaskSchedule {
+    /// The function arguments of the template must be complete, and the
+    /// inputs must be owned as the template specifies, since nobody assigns
+    /// anything to the tasks of the runs.
+    pub fn new(
+        template: &TaskTemplate,
+        function: &Function,
+        spec: ScheduleSpec,
+        inputs: &HashMap<String, TeaclaveInputFile>,
+    ) -> Result<Self> {
+        ensure!(
+            template.function_id == function.external_id(),
+            "function mismatch"
+        );
+        let ts: TaskState = create_task(template, function.clone())?.into();
+
+        let inputs_spec: HashSet<&String> = template.inputs_ownership.keys().collect();
+        let input_fkeys: HashSet<&String> = inputs.keys().collect();
+        ensure!(inputs_spec == input_fkeys, "input keys mismatch");
+        for (fname, file) in inputs.iter() {
+            template.inputs_ownership.check(fname, &file.owner)?;
+        }
+
+        Ok(Self {
+            template_id: template.template_id,
+            creation_spec_hash: ts.creation_spec_hash,
+            spec,
+            inputs: inputs
+                .iter()
+                .map(|(fname, file)| (fname.to_owned(), file.external_id()))
+                .collect(),
+            participants: ts.participants,
+            approved_users: UserList::default(),
+            approval_receipts: Vec::new(),
+            task_ids: Vec::new(),
+            runs: 0,
+            canceled: false,
+        })
+    }
+
+    pub fn external_id_of(template_id: &Uuid) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), template_id.to_owned())
+    }
+
+    pub fn has_participant(&self, user_id: &UserID) -> bool {
+        self.participants.contains(user_id)
+    }
+
+    pub fn everyone_approved(&self) -> bool {
+        self.participants.len() == self.approved_users.len()
+    }
+
+    /// Hash of what the participants approve: the template and function, the
+    /// schedule and the input files.
+    pub fn spec_hash(&self) -> String {
+        let inputs: BTreeMap<&String, String> = self
+            .inputs
+            .iter()
+            .map(|(fname, file_id)| (fname, file_id.to_string()))
+            .collect();
+        let spec = serde_json::json!({
+            "template_id": self.template_id.to_string(),
+            "creation_spec_hash": self.creation_spec_hash,
+            "start_at": self.spec.start_at,
+            "interval": self.spec.interval,
+            "max_runs": self.spec.max_runs,
+            "inputs": inputs,
+        });
+        let digest = ring::digest::digest(&ring::digest::SHA256, spec.to_string().as_bytes());
+        hex::encode(digest.as_ref())
+    }
+
+    pub fn approve(&mut self, receipt: ApprovalReceipt) -> Result<()> {
+        ensure!(!self.canceled, "Schedule is canceled");
+        ensure!(
+            self.participants.contains(&receipt.user_id),
+            "Unexpected user trying to approve a schedule: {:?}",
+            receipt.user_id
+        );
+        ensure!(
+            receipt.task_id == self.template_id && receipt.task_spec_hash == self.spec_hash(),
+            "Receipt does not match the schedule"
+        );
+        receipt.verify()?;
+
+        if self.approved_users.insert(receipt.user_id.clone()) {
+            self.approval_receipts.push(receipt);
+        }
+        Ok(())
+    }
+
+    /// Builds the staged task of a run from the template. The outputs are
+    /// created by the caller for the owners in the template.
+    pub fn instantiate(
+        &self,
+        template: &TaskTemplate,
+        function: Function,
+        inputs: HashMap<String, TeaclaveInputFile>,
+        outputs: HashMap<String, TeaclaveOutputFile>,
+        run: u64,
+    ) -> Result<(TaskState, StagedTask)> {
+        ensure!(self.everyone_approved(), "Schedule is not approved");
+
+        let mut labels = HashMap::new();
+        labels.insert("schedule".to_string(), self.external_id().to_string());
+        labels.insert("schedule_run".to_string(), run.to_string());
+        let task = create_task(template, function.clone())?.labels(labels);
+        let ts = TaskState::from(task);
+        ensure!(
+            ts.creation_spec_hash == self.creation_spec_hash,
+            "Task spec changed after the schedule was approved"
+        );
+
+        let mut task: Task<Assign> = ts.try_into()?;
+        for (fname, file) in inputs {
+            let owner = first_owner(&file.owner)?;
+            task.assign_input(&owner, &fname, file)?;
+        }
+        for (fname, file) in outputs {
+            let owner = first_owner(&file.owner)?;
+            task.assign_output(&owner, &fname, file)?;
+        }
+
+        let mut task: Task<Approve> = TaskState::from(task).try_into()?;
+        for user_id in self.approved_users.clone() {
+            task.approve(&user_id)?;
+        }
+
+        let mut task: Task<Stage> = TaskState::from(task).try_into()?;
+        let staged_task = task.stage_for_running(&template.creator, function)?;
+        Ok((task.into(), staged_task))
+    }
+}
+
+fn create_task(template: &TaskTemplate, function: Function) -> Result<Task<Create>> {
+    Task::<Create>::new(
+        template.creator.clone(),
+        template.executor,
+        template.function_arguments.clone(),
+        template.inputs_ownership.clone(),
+        template.outputs_ownership.clone(),
+        function,
+    )
+}
+
+fn first_owner(owners: &OwnerList) -> Result<UserID> {
+    owners
+        .uids
+        .iter()
+        .next()
+        .cloned()
+        .ok_or_else(|| anyhow!("File has no owner"))
+}
+
+/// Next run of a schedule, queued for the scheduler to create and stage the
+/// task of the run once it is due.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaskScheduleTick {
+    pub template_id: Uuid,
+    /// Runs are counted from zero
+    pub run: u64,
+    /// Seconds since the Unix epoch
+    pub run_at: u64,
+}
+
+impl Storable for TaskScheduleTick {
+    fn key_prefix() -> &'static str {
+        TASK_SCHEDULE_TICK_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.template_id
+    }
+}
+
+impl TaskScheduleTick {
+    pub fn new(template_id: Uuid, run: u64, run_at: u64) -> Self {
+        Self {
+            template_id,
+            run,
+            run_at,
+        }
+    }
+
+    pub fn get_queue_key() -> &'static str {
+        TASK_SCHEDULE_TICK_QUEUE_KEY
+    }
+
+    pub fn is_due(&self, now: u64) -> bool {
+        now >= self.run_at
+    }
+}
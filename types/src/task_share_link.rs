@@ -0,0 +1,107 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const TASK_SHARE_LINK_PREFIX: &str = "tasksharelink";
+const TASK_SHARE_TOKEN_LENGTH: usize = 32;
+
+/// Capability minted by the creator of a task, granting anyone holding the
+/// token read-only access to the status and progress of the task until it
+/// expires. Only a digest of the token is kept in the storage.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaskShareLink {
+    pub link_id: Uuid,
+    pub task_id: ExternalID,
+    pub creator: UserID,
+    /// Seconds since the Unix epoch
+    pub expires_at: u64,
+}
+
+impl Storable for TaskShareLink {
+    fn key_prefix() -> &'static str {
+        TASK_SHARE_LINK_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.link_id
+    }
+}
+
+impl TaskShareLink {
+    pub fn new(token: &str, task_id: ExternalID, creator: UserID, expires_at: u64) -> Self {
+        Self {
+            link_id: token_uuid(token),
+            task_id,
+            creator,
+            expires_at,
+        }
+    }
+
+    /// Generates a random token in the hex format.
+    pub fn generate_token() -> String {
+        let mut token = [0u8; TASK_SHARE_TOKEN_LENGTH];
+        rand::thread_rng().fill_bytes(&mut token);
+        hex::encode(token)
+    }
+
+    pub fn external_id_of(token: &str) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), token_uuid(token))
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// Progress of a task disclosed through a share link, leaving out the
+/// participants, the data and the result.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TaskProgress {
+    pub status: TaskStatus,
+    pub participants: u32,
+    pub approvals: u32,
+    pub assigned_inputs: u32,
+    pub expected_inputs: u32,
+    pub assigned_outputs: u32,
+    pub expected_outputs: u32,
+}
+
+impl From<&TaskState> for TaskProgress {
+    fn from(ts: &TaskState) -> Self {
+        Self {
+            status: ts.status.clone(),
+            participants: ts.participants.len() as u32,
+            approvals: ts.approved_users.len() as u32,
+            assigned_inputs: ts.assigned_inputs.keys().len() as u32,
+            expected_inputs: ts.inputs_ownership.keys().len() as u32,
+            assigned_outputs: ts.assigned_outputs.keys().len() as u32,
+            expected_outputs: ts.outputs_ownership.keys().len() as u32,
+        }
+    }
+}
+
+fn token_uuid(token: &str) -> Uuid {
+    let digest = ring::digest::digest(&ring::digest::SHA256, token.as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest.as_ref()[..16]);
+    Uuid::from_bytes(bytes)
+}
@@ -16,13 +16,18 @@
 // under the License.
 
 use crate::*;
-use anyhow::{bail, ensure, Error, Result};
+use anyhow::{anyhow, bail, ensure, Error, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::TryInto;
 use uuid::Uuid;
 
 const TASK_PREFIX: &str = "task";
+/// Index of the tasks by their participants.
+pub const TASK_PARTICIPANT_INDEX: &str = "task-participant";
+/// Version of the function fields covered by the creation spec hash, bumped
+/// whenever the covered fields change.
+const CREATION_SPEC_VERSION: u32 = 1;
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct TaskState {
@@ -36,10 +41,109 @@ pub struct TaskState {
     pub function_owner: UserID,
     pub participants: UserList,
     pub approved_users: UserList,
+    #[serde(default)]
+    pub approval_receipts: Vec<ApprovalReceipt>,
+    /// Signatures of the participants on the requests they made on the task,
+    /// if signed.
+    #[serde(default)]
+    pub request_signatures: Vec<RequestSignature>,
     pub assigned_inputs: TaskFiles<TeaclaveInputFile>,
     pub assigned_outputs: TaskFiles<TeaclaveOutputFile>,
     pub result: TaskResult,
     pub status: TaskStatus,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    #[serde(default)]
+    pub workflow_id: Option<String>,
+    #[serde(default)]
+    pub entry_point: Option<String>,
+    /// Consent of the creator to share the result with identical tasks, and
+    /// to be served from the result of an identical task instead of running.
+    #[serde(default)]
+    pub reuse_result: bool,
+    /// Keys of the files are exchanged with the execution enclave running the
+    /// task instead of being registered along with the files.
+    #[serde(default)]
+    pub key_exchange: bool,
+    /// Incremented on every update of the task, so that an update based on a
+    /// stale copy of the task can be detected when it is written back.
+    #[serde(default)]
+    pub revision: u64,
+    /// Hash of the task specification computed when the task is created.
+    #[serde(default)]
+    pub creation_spec_hash: String,
+    /// Capabilities of the function, kept for the participants to review
+    /// before approving the task.
+    #[serde(default)]
+    pub function_capabilities: Option<FunctionCapabilities>,
+    /// Post-processing steps of the function, kept for the participants to
+    /// review before approving the task.
+    #[serde(default)]
+    pub function_post_processing: Vec<PostProcessingStep>,
+    /// Execution enclaves pinned by the function owner to run the task, kept
+    /// for the participants to review before approving the task.
+    #[serde(default)]
+    pub worker_measurements: Vec<String>,
+    /// Signers of the execution enclaves pinned by the creator to run the
+    /// task.
+    #[serde(default)]
+    pub worker_signers: Vec<String>,
+    /// Seconds since the Unix epoch after which the task expires unless it
+    /// has started running.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    /// The participant who rejected the task, if rejected.
+    #[serde(default)]
+    pub rejected_by: Option<UserID>,
+    /// The reason given by the participant who rejected the task.
+    #[serde(default)]
+    pub rejection_reason: String,
+    /// Seconds since the Unix epoch after which the task expires unless every
+    /// participant has approved it.
+    #[serde(default)]
+    pub approval_expires_at: Option<u64>,
+    /// Set if the task is a test run of the function by its owner.
+    #[serde(default)]
+    pub function_test: Option<FunctionTest>,
+    /// Inputs which are outputs of upstream tasks, assigned once the
+    /// upstream tasks have finished.
+    #[serde(default)]
+    pub input_dependencies: HashMap<String, TaskOutputReference>,
+    /// Set if the task was invoked before the upstream tasks of its inputs
+    /// finished, and is staged by the scheduler once they have.
+    #[serde(default)]
+    pub staging_deferred: bool,
+    /// Staged tasks of higher priorities are run first. The priority is not
+    /// part of the task specification, since it does not change the result.
+    #[serde(default)]
+    pub priority: TaskPriority,
+    /// Limits on the resources of the task enforced by the worker, which
+    /// fails the task if they are exceeded. The limits are not part of the
+    /// task specification either.
+    #[serde(default)]
+    pub resource_limits: ResourceLimits,
+    /// Seconds the task is given to finish once it is dispatched to an
+    /// execution service, after which it fails.
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// Retries of the task when it fails transiently, e.g., when its input
+    /// files cannot be fetched.
+    #[serde(default)]
+    pub retry_policy: RetryPolicy,
+    #[serde(default)]
+    pub retry_count: u32,
+    /// Seconds since the Unix epoch the task is created at, unknown for tasks
+    /// created before it was recorded.
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    /// Name of the federated deployment the task is delegated to, which runs
+    /// it instead of the execution services of this deployment.
+    #[serde(default)]
+    pub federation_peer: Option<String>,
+    /// Name of the federated deployment which delegated the task, set on the
+    /// copy of the task run here.
+    #[serde(default)]
+    pub delegated_from: Option<String>,
 }
 
 impl Storable for TaskState {
@@ -50,6 +154,14 @@ impl Storable for TaskState {
     fn uuid(&self) -> Uuid {
         self.task_id
     }
+
+    fn index_terms(&self) -> Vec<IndexTerm> {
+        self.participants
+            .uids
+            .iter()
+            .map(|uid| IndexTerm::new(TASK_PARTICIPANT_INDEX, uid.to_string()))
+            .collect()
+    }
 }
 
 impl TaskState {
@@ -60,7 +172,11 @@ impl TaskState {
 
     pub fn all_data_assigned(&self) -> bool {
         let input_args: HashSet<&String> = self.inputs_ownership.keys().collect();
-        let assiged_inputs: HashSet<&String> = self.assigned_inputs.keys().collect();
+        let assiged_inputs: HashSet<&String> = self
+            .assigned_inputs
+            .keys()
+            .chain(self.input_dependencies.keys())
+            .collect();
         if input_args != assiged_inputs {
             return false;
         }
@@ -81,6 +197,418 @@ impl TaskState {
     pub fn has_creator(&self, user_id: &UserID) -> bool {
         &self.creator == user_id
     }
+
+    /// The copy of the task run for the federated deployment `origin`. Users
+    /// of the origin are not users here, so the copy has no participants, and
+    /// its creator is qualified by the origin. Results are not reused across
+    /// deployments.
+    pub fn into_delegated(mut self, origin: &str) -> Self {
+        self.creator = UserID::from(format!("{}/{}", origin, self.creator));
+        self.participants = UserList::default();
+        self.approved_users = UserList::default();
+        self.labels.clear();
+        self.reuse_result = false;
+        self.federation_peer = None;
+        self.delegated_from = Some(origin.to_string());
+        self
+    }
+
+    /// Follows the copy of the task run by the federated deployment it is
+    /// delegated to, returning the outputs whose tags are taken from the
+    /// result of the copy.
+    pub fn follow_delegated(&mut self, delegated: TaskState) -> Result<Vec<TeaclaveOutputFile>> {
+        ensure!(delegated.task_id == self.task_id, "Delegated task mismatch");
+        let mut outputs = Vec::new();
+        if let TaskResult::Ok(result) = &delegated.result {
+            let producer = self.external_id();
+            for (fname, auth_tag) in result.tags_map.iter() {
+                let file = self
+                    .assigned_outputs
+                    .update_cmac(fname, auth_tag, producer.clone())?;
+                outputs.push(file.clone());
+            }
+        }
+        self.status = delegated.status;
+        self.result = delegated.result;
+        self.retry_count = delegated.retry_count;
+        Ok(outputs)
+    }
+
+    pub fn awaits_approval_from(&self, user_id: &UserID) -> bool {
+        let approvable = match self.status {
+            TaskStatus::Created | TaskStatus::DataAssigned => true,
+            _ => false,
+        };
+        approvable && self.has_participant(user_id) && !self.approved_users.contains(user_id)
+    }
+
+    /// Tasks can be canceled until they start running.
+    pub fn is_cancelable(&self) -> bool {
+        match self.status {
+            TaskStatus::Created
+            | TaskStatus::DataAssigned
+            | TaskStatus::Approved
+            | TaskStatus::Staged => true,
+            _ => false,
+        }
+    }
+
+    /// Moves the task to the terminal Canceled state, failing its result. A
+    /// staged task is dropped by the scheduler when it is pulled.
+    pub fn cancel(&mut self) -> Result<()> {
+        ensure!(
+            self.is_cancelable(),
+            "Cannot cancel a task in status {:?}",
+            self.status
+        );
+        self.status = TaskStatus::Canceled;
+        self.result = TaskResult::Err(TaskFailure::new("canceled by the creator"));
+        Ok(())
+    }
+
+    /// Participants can reject a task until it is invoked.
+    pub fn is_rejectable(&self) -> bool {
+        match self.status {
+            TaskStatus::Created | TaskStatus::DataAssigned => true,
+            TaskStatus::Approved => !self.staging_deferred,
+            _ => false,
+        }
+    }
+
+    /// Moves the task to the terminal Rejected state on behalf of a
+    /// participant, failing its result with the reason.
+    pub fn reject(&mut self, user_id: &UserID, reason: &str) -> Result<()> {
+        ensure!(
+            self.has_participant(user_id),
+            "{} is not a participant of the task",
+            user_id
+        );
+        ensure!(
+            self.is_rejectable(),
+            "Cannot reject a task in status {:?}",
+            self.status
+        );
+        self.status = TaskStatus::Rejected;
+        self.rejected_by = Some(user_id.to_owned());
+        self.rejection_reason = reason.to_owned();
+        self.result = TaskResult::Err(TaskFailure::new(format!("rejected by {}", user_id)));
+        Ok(())
+    }
+
+    /// Tasks expire if they have not started running by the time they expire
+    /// at, or have not been approved by everyone by the end of the approval
+    /// window, whether or not they have been moved to the Expired state yet.
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.status == TaskStatus::Expired
+            || (self.is_cancelable() && self.expires_at.map_or(false, |at| now >= at))
+            || self.is_approval_expired(now)
+    }
+
+    /// Whether the approval window of the task has elapsed before every
+    /// participant approved it.
+    pub fn is_approval_expired(&self, now: u64) -> bool {
+        self.is_cancelable()
+            && !self.everyone_approved()
+            && self.approval_expires_at.map_or(false, |at| now >= at)
+    }
+
+    /// Moves the task to the terminal Expired state, failing its result. A
+    /// staged task is dropped by the scheduler like a canceled one.
+    pub fn expire(&mut self) -> Result<()> {
+        ensure!(
+            self.is_cancelable(),
+            "Cannot expire a task in status {:?}",
+            self.status
+        );
+        self.status = TaskStatus::Expired;
+        self.result = TaskResult::Err(TaskFailure::new("expired"));
+        Ok(())
+    }
+
+    /// Finishes a dispatched task with a failure when it has not finished
+    /// within its timeout, e.g., because its execution service hung.
+    pub fn time_out(&mut self, timeout: u64) -> Result<()> {
+        ensure!(
+            self.status == TaskStatus::Staged || self.status == TaskStatus::Running,
+            "Cannot time out a task in status {:?}",
+            self.status
+        );
+        self.status = TaskStatus::Finished;
+        self.result = TaskResult::Err(TaskFailure::timed_out(TaskTimedOut(timeout)));
+        Ok(())
+    }
+
+    /// Whether the task is retried rather than finished with the failure.
+    pub fn is_retryable(&self, failure: &TaskFailure) -> bool {
+        failure.kind == TaskFailureKind::Transient
+            && self.retry_count < self.retry_policy.max_retries
+    }
+
+    /// Moves a running task failed transiently back to the Staged state, to be
+    /// run again.
+    pub fn retry(&mut self) -> Result<()> {
+        ensure!(
+            self.status == TaskStatus::Running,
+            "Cannot retry a task in status {:?}",
+            self.status
+        );
+        ensure!(
+            self.retry_count < self.retry_policy.max_retries,
+            "No retries left"
+        );
+        self.status = TaskStatus::Staged;
+        self.retry_count += 1;
+        self.result = TaskResult::NotReady;
+        Ok(())
+    }
+
+    /// Whether a task whose worker was lost is queued again rather than
+    /// finished with the failure. Lost tasks are queued again a few times
+    /// even if the retry policy allows fewer retries.
+    pub fn is_requeueable(&self) -> bool {
+        self.retry_count < self.retry_policy.max_retries.max(MAX_LOST_TASK_REQUEUES)
+    }
+
+    /// Moves a task pulled by a worker which was lost back to the Staged
+    /// state, to be run again. Counted as a retry.
+    pub fn requeue(&mut self) -> Result<()> {
+        ensure!(
+            self.status == TaskStatus::Staged || self.status == TaskStatus::Running,
+            "Cannot requeue a task in status {:?}",
+            self.status
+        );
+        ensure!(self.is_requeueable(), "No requeues left");
+        self.status = TaskStatus::Staged;
+        self.retry_count += 1;
+        self.result = TaskResult::NotReady;
+        Ok(())
+    }
+
+    /// Fails a staged or running task with a transient failure which is not
+    /// retried.
+    pub fn fail_transiently(&mut self, failure: &TransientFailure) -> Result<()> {
+        ensure!(
+            self.status == TaskStatus::Staged || self.status == TaskStatus::Running,
+            "Cannot fail a task in status {:?}",
+            self.status
+        );
+        self.status = TaskStatus::Finished;
+        self.result = TaskResult::Err(TaskFailure::transient(failure));
+        Ok(())
+    }
+
+    /// Hex encoded SHA-256 of the task specification a participant approves:
+    /// the function, arguments, executor, file ownership and assigned data,
+    /// bound to the specification the task was created with. Inputs of
+    /// upstream tasks are identified by the upstream outputs, so that the
+    /// hash is unchanged when they are assigned.
+    pub fn spec_hash(&self) -> String {
+        let mut assigned_inputs = self.assigned_inputs.external_ids();
+        assigned_inputs.retain(|fname, _| !self.input_dependencies.contains_key(fname));
+        let mut spec = serde_json::json!({
+            "creation_spec_hash": self.creation_spec_hash,
+            "function_id": self.function_id.to_string(),
+            "function_arguments": self.function_arguments.inner(),
+            "executor": self.executor.to_string(),
+            "inputs_ownership": sorted_owners(&self.inputs_ownership),
+            "outputs_ownership": sorted_owners(&self.outputs_ownership),
+            "assigned_inputs": sorted_file_ids(assigned_inputs),
+            "assigned_outputs": sorted_file_ids(self.assigned_outputs.external_ids()),
+        });
+        if !self.input_dependencies.is_empty() {
+            let dependencies: BTreeMap<&String, (String, &String)> = self
+                .input_dependencies
+                .iter()
+                .map(|(fname, upstream)| (fname, (upstream.task_id.to_string(), &upstream.output)))
+                .collect();
+            spec["input_dependencies"] = serde_json::json!(dependencies);
+        }
+        // Tasks running the default entry point keep the hash they had before
+        // entry points were introduced.
+        if let Some(entry_point) = &self.entry_point {
+            spec["entry_point"] = entry_point.as_str().into();
+        }
+        if self.reuse_result {
+            spec["reuse_result"] = true.into();
+        }
+        if self.key_exchange {
+            spec["key_exchange"] = true.into();
+        }
+        if !self.worker_measurements.is_empty() {
+            spec["worker_measurements"] = sorted_measurements(&self.worker_measurements).into();
+        }
+        if !self.worker_signers.is_empty() {
+            spec["worker_signers"] = sorted_measurements(&self.worker_signers).into();
+        }
+        // Participants approve where their data goes.
+        if let Some(federation_peer) = &self.federation_peer {
+            spec["federation_peer"] = federation_peer.as_str().into();
+        }
+        let digest = ring::digest::digest(&ring::digest::SHA256, spec.to_string().as_bytes());
+        hex::encode(digest.as_ref())
+    }
+
+    /// Hex encoded SHA-256 of the function id and version, arguments,
+    /// executor and file ownership of the task. The version of the function
+    /// is the digest of the fields defining its computation, i.e., its id,
+    /// payload, arguments, inputs and outputs, so that fields added to the
+    /// function later do not change the hash of existing tasks.
+    pub fn compute_creation_spec_hash(&self, function: &Function) -> Result<String> {
+        let function_definition = serde_json::json!({
+            "version": CREATION_SPEC_VERSION,
+            "id": function.id.to_string(),
+            "payload": hex::encode(&function.payload),
+            "arguments": function.arguments,
+            "inputs": function.inputs.iter().map(|input| &input.name).collect::<Vec<_>>(),
+            "outputs": function.outputs.iter().map(|output| &output.name).collect::<Vec<_>>(),
+        });
+        let function_version = ring::digest::digest(
+            &ring::digest::SHA256,
+            function_definition.to_string().as_bytes(),
+        );
+        let spec = serde_json::json!({
+            "function_id": self.function_id.to_string(),
+            "function_version": hex::encode(function_version.as_ref()),
+            "function_arguments": self.function_arguments.inner(),
+            "executor": self.executor.to_string(),
+            "inputs_ownership": sorted_owners(&self.inputs_ownership),
+            "outputs_ownership": sorted_owners(&self.outputs_ownership),
+        });
+        let digest = ring::digest::digest(&ring::digest::SHA256, spec.to_string().as_bytes());
+        Ok(hex::encode(digest.as_ref()))
+    }
+
+    /// Hex encoded SHA-256 identifying the computation of the task, i.e., the
+    /// function payload, arguments, executor and the content of the inputs.
+    /// Only tasks consenting to reuse and writing no output file, whose
+    /// result is the return value only, have a fingerprint. The registered
+    /// keys of a task exchanging keys do not identify the content.
+    pub fn result_fingerprint(&self, function: &Function) -> Option<String> {
+        if !self.reuse_result
+            || !self.outputs_ownership.is_empty()
+            || self.workflow_id.is_some()
+            || self.key_exchange
+        {
+            return None;
+        }
+        let payload = ring::digest::digest(&ring::digest::SHA256, &function.payload);
+        // The key is hashed along with the tag, as the same tag under a
+        // different key does not imply the same content.
+        let inputs: BTreeMap<String, (String, String)> = self
+            .assigned_inputs
+            .clone()
+            .into_iter()
+            .map(|(name, file)| {
+                let crypto = serde_json::to_vec(&file.crypto_info).unwrap_or_default();
+                let crypto = ring::digest::digest(&ring::digest::SHA256, &crypto);
+                (name, (file.cmac.to_hex(), hex::encode(crypto.as_ref())))
+            })
+            .collect();
+        let mut spec = serde_json::json!({
+            "function_name": function.name,
+            "executor_type": function.executor_type.to_string(),
+            "function_payload": hex::encode(payload.as_ref()),
+            "function_arguments": self.function_arguments.inner(),
+            "executor": self.executor.to_string(),
+            "entry_point": self.entry_point,
+            "inputs": inputs,
+        });
+        // Results are only reused by tasks post-processing them alike.
+        if !function.post_processing.is_empty() {
+            spec["post_processing"] = serde_json::json!(function.post_processing);
+        }
+        // Results of unpinned workers are not served to pinned tasks.
+        if !self.worker_measurements.is_empty() {
+            spec["worker_measurements"] = sorted_measurements(&self.worker_measurements).into();
+        }
+        if !self.worker_signers.is_empty() {
+            spec["worker_signers"] = sorted_measurements(&self.worker_signers).into();
+        }
+        let digest = ring::digest::digest(&ring::digest::SHA256, spec.to_string().as_bytes());
+        Some(hex::encode(digest.as_ref()))
+    }
+
+    /// Assigns the inputs of the upstream tasks which have finished, with
+    /// `upstream` returning the state of an upstream task, and returns
+    /// whether every input of upstream tasks is assigned. It fails if an
+    /// upstream task will never produce the output.
+    pub fn resolve_dependencies<F>(&mut self, mut upstream: F) -> Result<bool>
+    where
+        F: FnMut(&ExternalID) -> Result<TaskState>,
+    {
+        let mut resolved = true;
+        for (fname, reference) in self.input_dependencies.iter() {
+            if self.assigned_inputs.contains_key(fname) {
+                continue;
+            }
+            let upstream_ts = upstream(&reference.task_id)?;
+            match upstream_ts.status {
+                TaskStatus::Finished => (),
+                TaskStatus::Canceled | TaskStatus::Expired | TaskStatus::Rejected => {
+                    bail!(
+                        "Upstream task {} did not run",
+                        reference.task_id.to_string()
+                    )
+                }
+                _ => {
+                    resolved = false;
+                    continue;
+                }
+            }
+            ensure!(
+                upstream_ts.result.is_ok(),
+                "Upstream task {} failed",
+                reference.task_id.to_string()
+            );
+            let output = upstream_ts
+                .assigned_outputs
+                .get(&reference.output)
+                .ok_or_else(|| anyhow!("Upstream output not found: {}", reference.output))?;
+            let input = TeaclaveInputFile::from_output(output.to_owned())?;
+            self.inputs_ownership.check(fname, &input.owner)?;
+            self.assigned_inputs.assign(fname, input)?;
+        }
+        Ok(resolved)
+    }
+
+    /// Finishes a deferred task with a failure without running it, when an
+    /// upstream task of its inputs will never produce the output.
+    pub fn fail_deferred(&mut self, reason: impl ToString) -> Result<()> {
+        ensure!(
+            self.status == TaskStatus::Approved && self.staging_deferred,
+            "Task is not deferred"
+        );
+        self.status = TaskStatus::Finished;
+        self.staging_deferred = false;
+        self.result = TaskResult::Err(TaskFailure::new(reason));
+        Ok(())
+    }
+
+    pub fn has_labels(&self, labels: &HashMap<String, String>) -> bool {
+        labels
+            .iter()
+            .all(|(k, v)| self.labels.get(k).map_or(false, |value| value == v))
+    }
+}
+
+fn sorted_measurements(measurements: &[String]) -> Vec<String> {
+    let measurements: BTreeSet<String> = measurements.iter().cloned().collect();
+    measurements.into_iter().collect()
+}
+
+pub(crate) fn sorted_owners(owners: &TaskFileOwners) -> BTreeMap<String, BTreeSet<String>> {
+    owners
+        .clone()
+        .into_iter()
+        .map(|(name, owner_list)| (name, owner_list.into_iter().map(String::from).collect()))
+        .collect()
+}
+
+pub(crate) fn sorted_file_ids(file_ids: HashMap<String, ExternalID>) -> BTreeMap<String, String> {
+    file_ids
+        .into_iter()
+        .map(|(name, file_id)| (name, file_id.to_string()))
+        .collect()
 }
 
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
@@ -134,7 +662,7 @@ impl Task<Create> {
         let req_output_fkeys: HashSet<&String> = req_output_owners.keys().collect();
         ensure!(outputs_spec == req_output_fkeys, "output keys mismatch");
 
-        let ts = TaskState {
+        let mut ts = TaskState {
             task_id: Uuid::new_v4(),
             creator: requester,
             executor: req_executor,
@@ -144,14 +672,155 @@ impl Task<Create> {
             inputs_ownership: req_input_owners,
             outputs_ownership: req_output_owners,
             participants,
+            function_capabilities: function.capabilities,
+            function_post_processing: function.post_processing.clone(),
+            worker_measurements: function.worker_measurements.clone(),
+            ..Default::default()
+        };
+        ts.creation_spec_hash = ts.compute_creation_spec_hash(&function)?;
+
+        Ok(Task {
+            state: ts,
+            extra: Create,
+        })
+    }
+
+    /// A test run of the function by its owner. It has neither files nor
+    /// participants other than the owner, so it is staged right away.
+    pub fn new_function_test(
+        requester: UserID,
+        req_executor: Executor,
+        req_func_args: FunctionArguments,
+        function: Function,
+        function_test: FunctionTest,
+    ) -> Result<Self> {
+        ensure!(
+            function.owner == requester,
+            "Only the function owner can test the function"
+        );
+
+        let fn_args_spec: HashSet<&String> = function.arguments.iter().collect();
+        let req_args: HashSet<&String> = req_func_args.inner().keys().collect();
+        ensure!(fn_args_spec == req_args, "function_arguments mismatch");
+
+        let participants = UserList::new(vec![&requester]);
+        let mut ts = TaskState {
+            task_id: Uuid::new_v4(),
+            creator: requester,
+            executor: req_executor,
+            function_id: function.external_id(),
+            function_owner: function.owner.clone(),
+            function_arguments: req_func_args,
+            participants,
+            function_capabilities: function.capabilities,
+            function_post_processing: function.post_processing.clone(),
+            worker_measurements: function.worker_measurements.clone(),
+            function_test: Some(function_test),
             ..Default::default()
         };
+        ts.creation_spec_hash = ts.compute_creation_spec_hash(&function)?;
 
         Ok(Task {
             state: ts,
             extra: Create,
         })
     }
+
+    pub fn labels(mut self, labels: HashMap<String, String>) -> Self {
+        self.state.labels = labels;
+        self
+    }
+
+    pub fn created_at(mut self, created_at: u64) -> Self {
+        self.state.created_at = Some(created_at);
+        self
+    }
+
+    pub fn federation_peer(mut self, federation_peer: Option<String>) -> Self {
+        self.state.federation_peer = federation_peer;
+        self
+    }
+
+    pub fn workflow_id(mut self, workflow_id: Option<String>) -> Self {
+        self.state.workflow_id = workflow_id;
+        self
+    }
+
+    pub fn entry_point(mut self, entry_point: Option<String>) -> Self {
+        self.state.entry_point = entry_point;
+        self
+    }
+
+    pub fn reuse_result(mut self, reuse_result: bool) -> Self {
+        self.state.reuse_result = reuse_result;
+        self
+    }
+
+    pub fn key_exchange(mut self, key_exchange: bool) -> Self {
+        self.state.key_exchange = key_exchange;
+        self
+    }
+
+    pub fn priority(mut self, priority: TaskPriority) -> Self {
+        self.state.priority = priority;
+        self
+    }
+
+    pub fn resource_limits(mut self, resource_limits: ResourceLimits) -> Self {
+        self.state.resource_limits = resource_limits;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Option<u64>) -> Self {
+        self.state.timeout = timeout;
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.state.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn expires_at(mut self, expires_at: Option<u64>) -> Self {
+        self.state.expires_at = expires_at;
+        self
+    }
+
+    pub fn approval_expires_at(mut self, approval_expires_at: Option<u64>) -> Self {
+        self.state.approval_expires_at = approval_expires_at;
+        self
+    }
+
+    pub fn input_dependencies(
+        mut self,
+        input_dependencies: HashMap<String, TaskOutputReference>,
+    ) -> Self {
+        self.state.input_dependencies = input_dependencies;
+        self
+    }
+
+    /// Pins the task to the execution enclaves of the measurements and the
+    /// signers. The measurements can only narrow down those pinned by the
+    /// owner of the function.
+    pub fn worker_affinity(
+        mut self,
+        worker_measurements: Vec<String>,
+        worker_signers: Vec<String>,
+    ) -> Result<Self> {
+        if !worker_measurements.is_empty() {
+            let pinned = &self.state.worker_measurements;
+            ensure!(
+                pinned.is_empty()
+                    || worker_measurements
+                        .iter()
+                        .all(|m| pinned.iter().any(|p| p.eq_ignore_ascii_case(m))),
+                "worker_measurements not pinned by the function"
+            );
+            self.state.worker_measurements = worker_measurements;
+        }
+        self.state.worker_signers = worker_signers;
+        Ok(self)
+    }
 }
 
 impl Task<Assign> {
@@ -174,6 +843,11 @@ impl Task<Assign> {
             "Assign: requester is not in the owner list. {:?}.",
             file.external_id()
         );
+        ensure!(
+            !self.state.input_dependencies.contains_key(fname),
+            "Assign: input is the output of an upstream task. {:?}",
+            fname
+        );
 
         self.state.inputs_ownership.check(fname, &file.owner)?;
         self.state.assigned_inputs.assign(fname, file)?;
@@ -217,6 +891,23 @@ impl Task<Approve> {
         self.state.approved_users.insert(requester.clone());
         Ok(())
     }
+
+    pub fn add_approval_receipt(&mut self, receipt: ApprovalReceipt) -> Result<()> {
+        ensure!(
+            self.state.approved_users.contains(&receipt.user_id),
+            "Receipt of a user who has not approved the task: {:?}",
+            receipt.user_id
+        );
+        ensure!(
+            receipt.task_id == self.state.task_id
+                && receipt.task_spec_hash == self.state.spec_hash(),
+            "Receipt does not match the task"
+        );
+        receipt.verify()?;
+
+        self.state.approval_receipts.push(receipt);
+        Ok(())
+    }
 }
 
 impl Task<Stage> {
@@ -237,6 +928,23 @@ impl Task<Stage> {
             self.state.has_creator(&requester),
             "Requestor is not the task creater"
         );
+        // The function or the task must not be modified after creation, and
+        // every approval must be granted to the specification being staged.
+        // Tasks created before the hash was recorded have none to check.
+        ensure!(
+            self.state.creation_spec_hash.is_empty()
+                || self.state.creation_spec_hash
+                    == self.state.compute_creation_spec_hash(&function)?,
+            "Task spec changed after creation"
+        );
+        let spec_hash = self.state.spec_hash();
+        ensure!(
+            self.state
+                .approval_receipts
+                .iter()
+                .all(|receipt| receipt.task_spec_hash == spec_hash),
+            "Task spec changed after approval"
+        );
 
         let function_arguments = self.state.function_arguments.clone();
         let staged_task = StagedTask {
@@ -249,9 +957,47 @@ impl Task<Stage> {
             function_arguments,
             input_data: self.state.assigned_inputs.clone().into(),
             output_data: self.state.assigned_outputs.clone().into(),
+            workflow_cache: None,
+            resource_profile: function.resource_profile,
+            entry_point: self.state.entry_point.clone(),
+            creator: self.state.creator.clone(),
+            capabilities: function.capabilities,
+            post_processing: function.post_processing,
+            key_exchange: self.state.key_exchange,
+            worker_measurements: self.state.worker_measurements.clone(),
+            worker_signers: self.state.worker_signers.clone(),
+            function_test: self.state.function_test.clone(),
+            priority: self.state.priority,
+            resource_limits: self.state.resource_limits,
+            timeout: self.state.timeout,
+            retry_policy: self.state.retry_policy,
+            retry_count: self.state.retry_count,
         };
         Ok(staged_task)
     }
+
+    /// Leaves the task approved until the upstream tasks of its inputs have
+    /// finished, instead of moving it to the Staged state.
+    pub fn defer(mut self) -> TaskState {
+        self.state.status = TaskStatus::Approved;
+        self.state.staging_deferred = true;
+        self.state
+    }
+
+    /// Stages a deferred task once the inputs of its upstream tasks are
+    /// assigned, returning them to complete the staged task.
+    pub fn stage_deferred(&mut self) -> Result<FunctionInputFiles> {
+        ensure!(self.state.staging_deferred, "Task is not deferred");
+        ensure!(
+            self.state
+                .input_dependencies
+                .keys()
+                .all(|fname| self.state.assigned_inputs.contains_key(fname)),
+            "Inputs of upstream tasks not assigned"
+        );
+        self.state.staging_deferred = false;
+        Ok(self.state.assigned_inputs.clone().into())
+    }
 }
 
 impl Task<Run> {
@@ -278,7 +1024,10 @@ impl Task<Finish> {
         fname: &str,
         auth_tag: &FileAuthTag,
     ) -> Result<&TeaclaveOutputFile> {
-        self.state.assigned_outputs.update_cmac(fname, auth_tag)
+        let producer = self.state.external_id();
+        self.state
+            .assigned_outputs
+            .update_cmac(fname, auth_tag, producer)
     }
 
     pub fn update_result(&mut self, result: TaskResult) -> Result<()> {
@@ -0,0 +1,117 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const TASK_TEMPLATE_PREFIX: &str = "tasktemplate";
+
+/// Function, argument defaults and ownership of the inputs and outputs shared
+/// by recurring tasks of the same participants, so that they are not specified
+/// for each task. Only the participants, i.e., the creator and the owners of
+/// the files, can create tasks from the template.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TaskTemplate {
+    pub template_id: Uuid,
+    pub creator: UserID,
+    pub function_id: ExternalID,
+    pub executor: Executor,
+    /// Defaults of the function arguments, overridden by the arguments of
+    /// the tasks created from the template
+    pub function_arguments: FunctionArguments,
+    pub inputs_ownership: TaskFileOwners,
+    pub outputs_ownership: TaskFileOwners,
+    pub participants: UserList,
+}
+
+impl Storable for TaskTemplate {
+    fn key_prefix() -> &'static str {
+        TASK_TEMPLATE_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.template_id
+    }
+}
+
+impl TaskTemplate {
+    pub fn new(
+        creator: UserID,
+        executor: Executor,
+        function_arguments: FunctionArguments,
+        inputs_ownership: impl Into<TaskFileOwners>,
+        outputs_ownership: impl Into<TaskFileOwners>,
+        function: &Function,
+    ) -> Result<Self> {
+        let inputs_ownership = inputs_ownership.into();
+        let outputs_ownership = outputs_ownership.into();
+
+        // defaults can be left out, but cannot be unknown to the function
+        ensure!(
+            function_arguments
+                .inner()
+                .keys()
+                .all(|key| function.arguments.contains(key)),
+            "function_arguments mismatch"
+        );
+
+        let inputs_spec: HashSet<&String> = function.inputs.iter().map(|f| &f.name).collect();
+        let input_fkeys: HashSet<&String> = inputs_ownership.keys().collect();
+        ensure!(inputs_spec == input_fkeys, "input keys mismatch");
+
+        let outputs_spec: HashSet<&String> = function.outputs.iter().map(|f| &f.name).collect();
+        let output_fkeys: HashSet<&String> = outputs_ownership.keys().collect();
+        ensure!(outputs_spec == output_fkeys, "output keys mismatch");
+
+        let mut participants = UserList::unions(vec![
+            inputs_ownership.all_owners(),
+            outputs_ownership.all_owners(),
+        ]);
+        participants.insert(creator.clone());
+
+        Ok(Self {
+            template_id: Uuid::new_v4(),
+            creator,
+            function_id: function.external_id(),
+            executor,
+            function_arguments,
+            inputs_ownership,
+            outputs_ownership,
+            participants,
+        })
+    }
+
+    pub fn has_participant(&self, user_id: &UserID) -> bool {
+        self.participants.contains(user_id)
+    }
+
+    /// Arguments of a task created from the template, i.e., the defaults
+    /// overridden by the given arguments.
+    pub fn function_arguments_with(&self, overrides: FunctionArguments) -> FunctionArguments {
+        let mut arguments = self.function_arguments.clone();
+        for (key, value) in overrides.inner() {
+            arguments
+                .inner_mut()
+                .insert(key.to_owned(), value.to_owned());
+        }
+        arguments
+    }
+}
@@ -0,0 +1,84 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use anyhow::{anyhow, Result};
+use ring::rand::{self, SecureRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::prelude::v1::*;
+use url::Url;
+use uuid::Uuid;
+
+const TASK_UPLOAD_SLOTS_PREFIX: &str = "taskuploadslots";
+/// Scheme of the urls of inputs pushed by clients to the worker host running
+/// the task, e.g., `push:`.
+pub const PUSH_URL_SCHEME: &str = "push";
+const UPLOAD_TOKEN_LENGTH: usize = 32;
+
+/// Upload endpoint of the worker host running a task, and the capability
+/// tokens of the inputs to push to it. A token is only good for one upload of
+/// the input of this task.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TaskUploadSlots {
+    pub task_id: Uuid,
+    pub endpoint: Url,
+    /// Tokens by the names of the inputs in the function
+    pub tokens: HashMap<String, String>,
+}
+
+impl Storable for TaskUploadSlots {
+    fn key_prefix() -> &'static str {
+        TASK_UPLOAD_SLOTS_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.task_id
+    }
+}
+
+impl TaskUploadSlots {
+    pub fn new(task_id: Uuid, endpoint: Url, tokens: HashMap<String, String>) -> Self {
+        Self {
+            task_id,
+            endpoint,
+            tokens,
+        }
+    }
+
+    pub fn external_id_of(task_id: &Uuid) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), task_id.to_owned())
+    }
+
+    /// A fresh token, 32 random bytes in hex.
+    pub fn new_token() -> Result<String> {
+        let mut token = [0u8; UPLOAD_TOKEN_LENGTH];
+        rand::SystemRandom::new()
+            .fill(&mut token)
+            .map_err(|_| anyhow!("Cannot generate upload token"))?;
+        Ok(hex::encode(token))
+    }
+
+    pub fn is_token(token: &str) -> bool {
+        token.len() == 2 * UPLOAD_TOKEN_LENGTH && token.chars().all(|c| c.is_ascii_hexdigit())
+    }
+
+    /// The url the file agent of the worker host waits on for the input.
+    pub fn push_url(token: &str) -> Result<Url> {
+        Ok(Url::parse(&format!("{}:{}", PUSH_URL_SCHEME, token))?)
+    }
+}
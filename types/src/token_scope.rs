@@ -0,0 +1,97 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+use crate::{ExternalID, Storable};
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const SCOPED_TOKEN_PREFIX: &str = "scopedtoken";
+
+/// Operations a scoped token can never be used for, so that a scoped token
+/// cannot mint further tokens.
+const UNSCOPABLE_OPERATIONS: &[&str] = &["mint_scoped_token"];
+
+/// Down-scoped credential of a user, restricting a token to the listed
+/// operations (names of the frontend methods, e.g., `approve_task`) and, if
+/// any resources are listed, to requests naming only those resources (e.g.,
+/// the external ID of a task or a file).
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct TokenScope {
+    pub operations: Vec<String>,
+    pub resources: Vec<String>,
+}
+
+impl TokenScope {
+    pub fn new(operations: Vec<String>, resources: Vec<String>) -> Self {
+        Self {
+            operations,
+            resources,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        !self.operations.is_empty()
+            && self
+                .operations
+                .iter()
+                .all(|o| !UNSCOPABLE_OPERATIONS.contains(&o.as_str()))
+    }
+
+    /// Checks whether a request of the operation naming the resources is
+    /// permitted by the scope.
+    pub fn permits(&self, operation: &str, resources: &[String]) -> bool {
+        if !self.operations.iter().any(|o| o == operation) {
+            return false;
+        }
+        self.resources.is_empty() || resources.iter().all(|r| self.resources.contains(r))
+    }
+}
+
+/// Record of a minted scoped token, which the authentication service keeps in
+/// the storage with a TTL until the token expires. A scoped token is only
+/// accepted while its record is kept, so deleting the record revokes it.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ScopedTokenRecord {
+    pub token_id: Uuid,
+    pub user_id: String,
+    /// Seconds since the Unix epoch
+    pub expires_at: u64,
+}
+
+impl Storable for ScopedTokenRecord {
+    fn key_prefix() -> &'static str {
+        SCOPED_TOKEN_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.token_id
+    }
+}
+
+impl ScopedTokenRecord {
+    pub fn new(user_id: impl ToString, expires_at: u64) -> Self {
+        Self {
+            token_id: Uuid::new_v4(),
+            user_id: user_id.to_string(),
+            expires_at,
+        }
+    }
+
+    pub fn external_id_of(token_id: &Uuid) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), *token_id)
+    }
+}
@@ -0,0 +1,187 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::prelude::v1::*;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "mesalock_sgx")]
+use std::untrusted::time::SystemTimeEx;
+
+/// Largest disagreement, in seconds, tolerated between the clock of the
+/// platform and an attested time source. A larger drift suggests the host
+/// tampers with the clock, and the calibration is refused.
+pub const MAX_CLOCK_DRIFT: u64 = 60;
+
+// Milliseconds to add to the clock of the platform, from the latest
+// calibration.
+static OFFSET: AtomicI64 = AtomicI64::new(0);
+// Milliseconds since the Unix epoch of the latest calibration.
+static CALIBRATED_AT: AtomicU64 = AtomicU64::new(0);
+// Milliseconds since the Unix epoch the trusted time never goes back before,
+// i.e., the latest time attested or returned.
+static FLOOR: AtomicU64 = AtomicU64::new(0);
+static CALIBRATED: AtomicBool = AtomicBool::new(false);
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+pub enum TrustedTimeError {
+    #[error("Attested time {0} is earlier than that of the latest calibration")]
+    StaleAttestedTime(u64),
+    #[error("Clock of the platform drifts {0} seconds from the attested time")]
+    ExcessiveDrift(i64),
+}
+
+/// Current time trusted in the enclave, for token expiry, task deadlines and
+/// the timestamps of records. The clock of the platform is read with an OCALL,
+/// so the host is free to set it forward or back. The trusted time corrects
+/// the clock by its offset to the latest calibration against an attested time
+/// source, i.e., the timestamp of a fresh attestation report of the enclave,
+/// and never goes back before a time attested, e.g., that of a verified report
+/// of a peer, or a time returned already. Between two calibrations, it is only
+/// as accurate as the clock of the platform.
+pub struct TrustedTime;
+
+impl TrustedTime {
+    pub fn now() -> SystemTime {
+        let corrected = (platform_millis() as i64).saturating_add(OFFSET.load(Ordering::SeqCst));
+        let now = raise_floor(corrected.max(0) as u64);
+        UNIX_EPOCH + Duration::from_millis(now)
+    }
+
+    /// Seconds since the Unix epoch
+    pub fn now_secs() -> u64 {
+        Self::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default()
+    }
+
+    /// Calibrates the clock against a time just attested, returning the drift
+    /// of the clock of the platform in seconds, positive if it lags behind.
+    /// Attested times earlier than that of the latest calibration are
+    /// rejected, so that a replayed report cannot set the clock back, and so
+    /// is a drift over `MAX_CLOCK_DRIFT`.
+    pub fn calibrate(attested: SystemTime) -> Result<i64, TrustedTimeError> {
+        let attested = millis_since_epoch(attested);
+        let latest = CALIBRATED_AT.load(Ordering::SeqCst);
+        if attested < latest {
+            return Err(TrustedTimeError::StaleAttestedTime(attested / 1000));
+        }
+        let offset = attested as i64 - platform_millis() as i64;
+        let drift = offset / 1000;
+        if drift.abs() as u64 > MAX_CLOCK_DRIFT {
+            return Err(TrustedTimeError::ExcessiveDrift(drift));
+        }
+        OFFSET.store(offset, Ordering::SeqCst);
+        CALIBRATED_AT.store(attested, Ordering::SeqCst);
+        CALIBRATED.store(true, Ordering::SeqCst);
+        raise_floor(attested);
+        Ok(drift)
+    }
+
+    /// Observes a time attested earlier, e.g., the timestamp of a verified
+    /// report of a peer, which the trusted time is then no earlier than.
+    pub fn observe(attested: SystemTime) {
+        raise_floor(millis_since_epoch(attested));
+    }
+
+    pub fn is_calibrated() -> bool {
+        CALIBRATED.load(Ordering::SeqCst)
+    }
+}
+
+fn millis_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn platform_millis() -> u64 {
+    millis_since_epoch(SystemTime::now())
+}
+
+fn raise_floor(candidate: u64) -> u64 {
+    let mut floor = FLOOR.load(Ordering::SeqCst);
+    while candidate > floor {
+        match FLOOR.compare_exchange_weak(floor, candidate, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) => return candidate,
+            Err(current) => floor = current,
+        }
+    }
+    floor
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use teaclave_test_utils::*;
+
+    pub fn run_tests() -> bool {
+        run_tests!(
+            test_calibrate,
+            test_stale_attested_time,
+            test_excessive_drift,
+            test_floor_monotonicity,
+        )
+    }
+
+    fn test_calibrate() {
+        let attested = SystemTime::now() + Duration::from_secs(10);
+        let drift = TrustedTime::calibrate(attested).unwrap();
+        assert!(drift == 9 || drift == 10);
+        assert!(TrustedTime::is_calibrated());
+
+        // The clock of the platform is corrected by the offset.
+        let now = TrustedTime::now();
+        assert!(now >= attested);
+        assert!(now < attested + Duration::from_secs(5));
+    }
+
+    fn test_stale_attested_time() {
+        let attested = SystemTime::now() + Duration::from_secs(20);
+        TrustedTime::calibrate(attested).unwrap();
+        let replayed = attested - Duration::from_secs(1);
+        assert_eq!(
+            TrustedTime::calibrate(replayed),
+            Err(TrustedTimeError::StaleAttestedTime(
+                millis_since_epoch(replayed) / 1000
+            ))
+        );
+        assert!(TrustedTime::now() >= attested);
+    }
+
+    fn test_excessive_drift() {
+        let before = TrustedTime::now();
+        let attested = SystemTime::now() + Duration::from_secs(MAX_CLOCK_DRIFT + 60);
+        assert!(match TrustedTime::calibrate(attested) {
+            Err(TrustedTimeError::ExcessiveDrift(drift)) => drift as u64 > MAX_CLOCK_DRIFT,
+            _ => false,
+        });
+
+        // The refused calibration does not move the clock.
+        assert!(TrustedTime::now() < before + Duration::from_secs(MAX_CLOCK_DRIFT));
+    }
+
+    fn test_floor_monotonicity() {
+        let observed = TrustedTime::now() + Duration::from_secs(30);
+        TrustedTime::observe(observed);
+        assert!(TrustedTime::now() >= observed);
+
+        // A time observed earlier does not set the trusted time back.
+        TrustedTime::observe(UNIX_EPOCH);
+        assert!(TrustedTime::now() >= observed);
+    }
+}
@@ -0,0 +1,136 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use anyhow::{bail, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const USER_ACTIVITY_PREFIX: &str = "useractivity";
+// Only the latest events of a user are kept.
+const MAX_ACTIVITY_EVENTS: usize = 1000;
+
+/// Kinds of the events in the activity feed of a user.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum ActivityKind {
+    TaskCreated,
+    TaskApproved,
+    TaskFinished,
+    DataRegistered,
+    FunctionRegistered,
+}
+
+impl std::convert::TryFrom<&str> for ActivityKind {
+    type Error = Error;
+
+    fn try_from(kind: &str) -> Result<Self> {
+        let kind = match kind {
+            "task_created" => ActivityKind::TaskCreated,
+            "task_approved" => ActivityKind::TaskApproved,
+            "task_finished" => ActivityKind::TaskFinished,
+            "data_registered" => ActivityKind::DataRegistered,
+            "function_registered" => ActivityKind::FunctionRegistered,
+            _ => bail!("Invalid activity kind: {}", kind),
+        };
+        Ok(kind)
+    }
+}
+
+impl std::fmt::Display for ActivityKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ActivityKind::TaskCreated => write!(f, "task_created"),
+            ActivityKind::TaskApproved => write!(f, "task_approved"),
+            ActivityKind::TaskFinished => write!(f, "task_finished"),
+            ActivityKind::DataRegistered => write!(f, "data_registered"),
+            ActivityKind::FunctionRegistered => write!(f, "function_registered"),
+        }
+    }
+}
+
+/// An event in the activity feed of a user, on the task, data or function of
+/// the id.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ActivityEvent {
+    pub kind: ActivityKind,
+    pub object_id: ExternalID,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl ActivityEvent {
+    pub fn new(kind: ActivityKind, object_id: ExternalID, timestamp: u64) -> Self {
+        Self {
+            kind,
+            object_id,
+            timestamp,
+        }
+    }
+}
+
+/// Recent events of a user in the order they are recorded, keyed by the
+/// user id.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct UserActivity {
+    pub user_id: UserID,
+    pub events: VecDeque<ActivityEvent>,
+}
+
+impl Storable for UserActivity {
+    fn key_prefix() -> &'static str {
+        USER_ACTIVITY_PREFIX
+    }
+
+    // Feeds of up to 1000 events are rewritten on every event of the user.
+    fn codec() -> StorageCodec {
+        StorageCodec::Bincode
+    }
+
+    fn uuid(&self) -> Uuid {
+        user_uuid(&self.user_id)
+    }
+}
+
+impl UserActivity {
+    pub fn new(user_id: UserID) -> Self {
+        Self {
+            user_id,
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn external_id_of(user_id: &UserID) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), user_uuid(user_id))
+    }
+
+    pub fn record(&mut self, event: ActivityEvent) {
+        if self.events.len() >= MAX_ACTIVITY_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Events recorded at or after the time, newest first.
+    pub fn since(&self, timestamp: u64) -> impl Iterator<Item = &ActivityEvent> {
+        self.events
+            .iter()
+            .rev()
+            .filter(move |event| event.timestamp >= timestamp)
+    }
+}
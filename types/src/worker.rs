@@ -17,16 +17,32 @@
 
 use crate::{FunctionArguments, FunctionRuntime, OutputsTags};
 use serde::{Deserialize, Serialize};
+use std::any::Any;
 use std::collections::HashSet;
 use std::convert::TryInto;
 use std::io;
 use std::prelude::v1::*;
+use std::sync::Arc;
 
 pub trait TeaclaveRuntime {
     fn open_input(&self, identifier: &str) -> anyhow::Result<Box<dyn io::Read>>;
     fn create_output(&self, identifier: &str) -> anyhow::Result<Box<dyn io::Write>>;
+
+    /// Publishes an intermediate artifact to the cache of the task's workflow.
+    fn publish_artifact(&self, name: &str) -> anyhow::Result<Box<dyn io::Write>> {
+        anyhow::bail!("Workflow cache is not available: {}", name)
+    }
+
+    /// Opens an artifact published by a task of the same workflow.
+    fn consume_artifact(&self, name: &str) -> anyhow::Result<Box<dyn io::Read>> {
+        anyhow::bail!("Workflow cache is not available: {}", name)
+    }
 }
 
+/// Function payload compiled by an executor, e.g., a parsed module. Workers
+/// cache compiled payloads by their content and share them across tasks.
+pub type CompiledPayload = Arc<dyn Any + Send + Sync>;
+
 pub trait TeaclaveExecutor {
     fn execute(
         &self,
@@ -35,6 +51,34 @@ pub trait TeaclaveExecutor {
         payload: String,
         runtime: FunctionRuntime,
     ) -> anyhow::Result<String>;
+
+    /// Initializes the executor for a function ahead of its execution, e.g.,
+    /// loads and instantiates the function module.
+    fn prepare(&mut self, _name: &str, _payload: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Selects a named entry point exposed by the payload instead of the
+    /// default one for the following executions.
+    fn set_entry_point(&mut self, entry_point: &str) -> anyhow::Result<()> {
+        anyhow::bail!("Entry point is not supported: {}", entry_point)
+    }
+
+    /// Compiles a function payload to be run by `execute_compiled`. Executors
+    /// which don't support compiled payloads return `None`.
+    fn compile(&self, _payload: &str) -> anyhow::Result<Option<CompiledPayload>> {
+        Ok(None)
+    }
+
+    fn execute_compiled(
+        &self,
+        _name: String,
+        _arguments: FunctionArguments,
+        _payload: CompiledPayload,
+        _runtime: FunctionRuntime,
+    ) -> anyhow::Result<String> {
+        anyhow::bail!("Compiled payload is not supported")
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
@@ -97,6 +141,13 @@ impl std::default::Default for Executor {
     }
 }
 
+impl Executor {
+    /// All executors, each with staged task queues of its own.
+    pub fn all() -> &'static [Executor] {
+        &[Executor::MesaPy, Executor::Builtin]
+    }
+}
+
 impl std::convert::TryFrom<&str> for Executor {
     type Error = anyhow::Error;
 
@@ -0,0 +1,164 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::prelude::v1::*;
+use uuid::Uuid;
+
+const WORKER_HEARTBEAT_PREFIX: &str = "workerheartbeat";
+
+/// Seconds between the heartbeats of a registered execution worker.
+pub const WORKER_HEARTBEAT_INTERVAL: u64 = 10;
+/// Seconds without a heartbeat after which a worker is considered lost, and
+/// the tasks it pulled are queued again.
+pub const WORKER_LIVENESS_TIMEOUT: u64 = 60;
+/// Times a task is queued again after losing its worker, unless its retry
+/// policy allows more retries.
+pub const MAX_LOST_TASK_REQUEUES: u32 = 3;
+
+/// An executor a worker can invoke functions with.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct WorkerExecutor {
+    pub executor_type: ExecutorType,
+    pub executor: Executor,
+}
+
+impl WorkerExecutor {
+    pub fn new(executor_type: ExecutorType, executor: Executor) -> Self {
+        Self {
+            executor_type,
+            executor,
+        }
+    }
+}
+
+/// What an execution worker is able to run, given when it registers with the
+/// scheduler.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WorkerCapabilities {
+    pub executors: Vec<WorkerExecutor>,
+    pub capacity: ResourceProfile,
+}
+
+impl WorkerCapabilities {
+    pub fn new(executors: Vec<WorkerExecutor>, capacity: ResourceProfile) -> Self {
+        Self {
+            executors,
+            capacity,
+        }
+    }
+}
+
+/// An execution worker registered with the scheduler, which is tracked as
+/// long as it keeps heartbeating.
+#[derive(Debug, Clone)]
+pub struct WorkerRegistration {
+    pub worker_id: Uuid,
+    /// Measurement of the execution enclave the worker runs in
+    pub mr_enclave: Option<String>,
+    pub capabilities: WorkerCapabilities,
+    /// Tasks running on the worker, as of its last heartbeat
+    pub load: u32,
+    /// Tasks pulled by the worker which are not known to be finished, with
+    /// the times they were retried when pulled
+    pub pulled_tasks: HashMap<Uuid, u32>,
+    /// Seconds since the Unix epoch
+    pub registered_at: u64,
+    pub last_heartbeat: u64,
+}
+
+impl WorkerRegistration {
+    pub fn new(mr_enclave: Option<String>, capabilities: WorkerCapabilities, now: u64) -> Self {
+        Self {
+            worker_id: Uuid::new_v4(),
+            mr_enclave,
+            capabilities,
+            load: 0,
+            pulled_tasks: HashMap::new(),
+            registered_at: now,
+            last_heartbeat: now,
+        }
+    }
+
+    pub fn heartbeat(&mut self, load: u32, now: u64) {
+        self.last_heartbeat = now;
+        self.load = load;
+    }
+
+    pub fn pull(&mut self, task_id: Uuid, retry_count: u32) {
+        self.pulled_tasks.insert(task_id, retry_count);
+    }
+
+    pub fn is_live(&self, now: u64) -> bool {
+        now < self.last_heartbeat + WORKER_LIVENESS_TIMEOUT
+    }
+
+    /// Executors whose queues the worker pulls tasks from, its home queue
+    /// first. Workers are spread over the queues of the executors they
+    /// support, and steal from the others when their own queue is empty.
+    pub fn queue_executors(&self) -> Vec<Executor> {
+        let mut executors = Vec::new();
+        for worker_executor in self.capabilities.executors.iter() {
+            if !executors.contains(&worker_executor.executor) {
+                executors.push(worker_executor.executor);
+            }
+        }
+        if executors.is_empty() {
+            return Executor::all().to_vec();
+        }
+        let home = (self.worker_id.as_u128() % executors.len() as u128) as usize;
+        executors.rotate_left(home);
+        executors
+    }
+}
+
+/// Latest heartbeat of a worker, which the scheduler keeps in the storage with
+/// a TTL of `WORKER_LIVENESS_TIMEOUT`, so that the record of a lost worker
+/// expires by itself.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct WorkerHeartbeat {
+    pub worker_id: Uuid,
+    pub mr_enclave: Option<String>,
+    pub load: u32,
+    /// Seconds since the Unix epoch
+    pub registered_at: u64,
+    pub last_heartbeat: u64,
+}
+
+impl Storable for WorkerHeartbeat {
+    fn key_prefix() -> &'static str {
+        WORKER_HEARTBEAT_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        self.worker_id
+    }
+}
+
+impl From<&WorkerRegistration> for WorkerHeartbeat {
+    fn from(worker: &WorkerRegistration) -> Self {
+        Self {
+            worker_id: worker.worker_id,
+            mr_enclave: worker.mr_enclave.clone(),
+            load: worker.load,
+            registered_at: worker.registered_at,
+            last_heartbeat: worker.last_heartbeat,
+        }
+    }
+}
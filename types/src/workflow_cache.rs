@@ -0,0 +1,125 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use crate::*;
+use anyhow::{ensure, Result};
+use protected_fs::ProtectedFile;
+use serde::{Deserialize, Serialize};
+#[cfg(not(feature = "mesalock_sgx"))]
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::prelude::v1::*;
+#[cfg(feature = "mesalock_sgx")]
+use std::untrusted::fs;
+#[cfg(feature = "mesalock_sgx")]
+use std::untrusted::path::PathEx;
+use teaclave_crypto::TeaclaveFile128Key;
+use uuid::Uuid;
+
+const WORKFLOW_CACHE_PREFIX: &str = "workflow";
+
+/// Sealing key of the intermediate artifacts shared by the tasks of a
+/// workflow. A workflow is identified by the task creator and the workflow id,
+/// so that other users cannot access the cache by reusing a workflow id.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WorkflowCache {
+    pub creator: UserID,
+    pub workflow_id: String,
+    pub key: TeaclaveFile128Key,
+}
+
+impl Storable for WorkflowCache {
+    fn key_prefix() -> &'static str {
+        WORKFLOW_CACHE_PREFIX
+    }
+
+    fn uuid(&self) -> Uuid {
+        workflow_uuid(&self.creator, &self.workflow_id)
+    }
+}
+
+impl WorkflowCache {
+    pub fn new(creator: UserID, workflow_id: impl ToString) -> Self {
+        Self {
+            creator,
+            workflow_id: workflow_id.to_string(),
+            key: TeaclaveFile128Key::random(),
+        }
+    }
+
+    pub fn external_id_of(creator: &UserID, workflow_id: &str) -> ExternalID {
+        ExternalID::new(Self::key_prefix(), workflow_uuid(creator, workflow_id))
+    }
+}
+
+// The creator is length-prefixed, so different (creator, workflow id) pairs
+// never map to the same input of the digest.
+fn workflow_uuid(creator: &UserID, workflow_id: &str) -> Uuid {
+    let creator = creator.to_string();
+    let input = format!("{}:{}{}", creator.len(), creator, workflow_id);
+    let digest = ring::digest::digest(&ring::digest::SHA256, input.as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest.as_ref()[..16]);
+    Uuid::from_bytes(bytes)
+}
+
+/// Artifacts of a workflow cache on the worker. Artifacts are sealed with the
+/// key of the cache and can only be published once.
+#[derive(Debug, Clone)]
+pub struct WorkflowArtifacts {
+    dir: PathBuf,
+    key: TeaclaveFile128Key,
+}
+
+impl WorkflowArtifacts {
+    // Artifacts are put in $base/workflow-$uuid/$name
+    pub fn new(base: impl AsRef<Path>, cache: &WorkflowCache) -> Self {
+        Self {
+            dir: base.as_ref().join(cache.external_id().to_string()),
+            key: cache.key,
+        }
+    }
+
+    pub fn open_artifact(&self, name: &str) -> Result<Box<dyn io::Read>> {
+        let path = self.artifact_path(name)?;
+        ensure!(path.exists(), "Artifact not found: {}", name);
+        let f = ProtectedFile::open_ex(&path, &self.key.key)?;
+        Ok(Box::new(f))
+    }
+
+    pub fn create_artifact(&self, name: &str) -> Result<Box<dyn io::Write>> {
+        let path = self.artifact_path(name)?;
+        ensure!(!path.exists(), "Artifact already published: {}", name);
+        fs::create_dir_all(&self.dir)?;
+        let f = ProtectedFile::create_ex(&path, &self.key.key)?;
+        Ok(Box::new(f))
+    }
+
+    fn artifact_path(&self, name: &str) -> Result<PathBuf> {
+        ensure!(
+            !name.is_empty()
+                && !name.starts_with('.')
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'),
+            "Invalid artifact name: {}",
+            name
+        );
+        Ok(self.dir.join(name))
+    }
+}
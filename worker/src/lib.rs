@@ -19,12 +19,16 @@
 #[cfg(feature = "mesalock_sgx")]
 extern crate sgx_tstd as std;
 
+mod payload_cache;
 mod worker;
 pub use worker::Worker;
 
 #[cfg(feature = "enclave_unit_test")]
 pub mod tests {
+    use super::*;
+    use teaclave_test_utils::check_all_passed;
+
     pub fn run_tests() -> bool {
-        true
+        check_all_passed!(payload_cache::tests::run_tests(),)
     }
 }
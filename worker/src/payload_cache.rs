@@ -0,0 +1,205 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+#[cfg(feature = "mesalock_sgx")]
+use std::prelude::v1::*;
+
+use std::collections::VecDeque;
+
+use teaclave_types::{CompiledPayload, Executor, ExecutorType};
+
+/// Compiled payloads are addressed by the tenant, the executor and the SHA-256
+/// digest of the payload, so that tasks of the same function share the
+/// compilation within a tenant only.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PayloadKey {
+    tenant: String,
+    executor_type: ExecutorType,
+    executor: Executor,
+    digest: Vec<u8>,
+}
+
+impl PayloadKey {
+    pub(crate) fn new(
+        tenant: &str,
+        executor_type: ExecutorType,
+        executor: Executor,
+        payload: &str,
+    ) -> Self {
+        let digest = ring::digest::digest(&ring::digest::SHA256, payload.as_bytes());
+        Self {
+            tenant: tenant.to_string(),
+            executor_type,
+            executor,
+            digest: digest.as_ref().to_vec(),
+        }
+    }
+}
+
+/// Least recently used entries of a tenant.
+#[derive(Default)]
+struct Partition {
+    size: usize,
+    // The most recently used entry is at the back.
+    entries: VecDeque<(PayloadKey, CompiledPayload, usize)>,
+}
+
+/// Cache of compiled payloads partitioned by tenant. Each tenant has a quota of
+/// `tenant_capacity` bytes and only evicts its own entries, so that a tenant
+/// can neither observe nor flush the payloads of others. The partition of the
+/// least recently active tenant is dropped when the cache runs out of
+/// partitions. The size of an entry is accounted as the size of its source
+/// payload.
+pub(crate) struct PayloadCache {
+    capacity: usize,
+    tenant_capacity: usize,
+    // The partition of the most recently active tenant is at the back.
+    partitions: VecDeque<(String, Partition)>,
+}
+
+impl PayloadCache {
+    pub(crate) fn new(capacity: usize, tenant_capacity: usize) -> Self {
+        Self {
+            capacity,
+            tenant_capacity: tenant_capacity.min(capacity),
+            partitions: VecDeque::new(),
+        }
+    }
+
+    fn max_partitions(&self) -> usize {
+        self.capacity / self.tenant_capacity.max(1)
+    }
+
+    // Moves the partition of the tenant to the back.
+    fn touch(&mut self, tenant: &str) -> Option<&mut Partition> {
+        let position = self.partitions.iter().position(|(t, _)| t == tenant)?;
+        let partition = self.partitions.remove(position)?;
+        self.partitions.push_back(partition);
+        self.partitions.back_mut().map(|(_, partition)| partition)
+    }
+
+    pub(crate) fn get(&mut self, key: &PayloadKey) -> Option<CompiledPayload> {
+        let partition = self.touch(&key.tenant)?;
+        let position = partition.entries.iter().position(|(k, _, _)| k == key)?;
+        let entry = partition.entries.remove(position)?;
+        let payload = entry.1.clone();
+        partition.entries.push_back(entry);
+        Some(payload)
+    }
+
+    pub(crate) fn insert(&mut self, key: PayloadKey, payload: CompiledPayload, size: usize) {
+        if size > self.tenant_capacity {
+            return;
+        }
+        if self.touch(&key.tenant).is_none() {
+            if self.partitions.len() >= self.max_partitions() {
+                self.partitions.pop_front();
+            }
+            self.partitions
+                .push_back((key.tenant.clone(), Partition::default()));
+        }
+        let tenant_capacity = self.tenant_capacity;
+        let partition = match self.partitions.back_mut() {
+            Some((_, partition)) => partition,
+            None => return,
+        };
+        if let Some(position) = partition.entries.iter().position(|(k, _, _)| k == &key) {
+            if let Some((_, _, old_size)) = partition.entries.remove(position) {
+                partition.size -= old_size;
+            }
+        }
+        while partition.size + size > tenant_capacity {
+            match partition.entries.pop_front() {
+                Some((_, _, evicted_size)) => partition.size -= evicted_size,
+                None => break,
+            }
+        }
+        partition.size += size;
+        partition.entries.push_back((key, payload, size));
+    }
+}
+
+#[cfg(feature = "enclave_unit_test")]
+pub mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use teaclave_test_utils::*;
+
+    pub fn run_tests() -> bool {
+        run_tests!(
+            test_payload_cache_eviction,
+            test_payload_cache_tenant_isolation,
+        )
+    }
+
+    fn test_payload_cache_eviction() {
+        let key_a = PayloadKey::new("tenant", ExecutorType::Python, Executor::MesaPy, "a");
+        let key_b = PayloadKey::new("tenant", ExecutorType::Python, Executor::MesaPy, "b");
+        let key_c = PayloadKey::new("tenant", ExecutorType::Python, Executor::MesaPy, "c");
+        let mut cache = PayloadCache::new(10, 10);
+
+        cache.insert(key_a.clone(), Arc::new("a"), 4);
+        cache.insert(key_b.clone(), Arc::new("b"), 4);
+        assert!(cache.get(&key_a).is_some());
+
+        // b is the least recently used entry
+        cache.insert(key_c.clone(), Arc::new("c"), 4);
+        assert!(cache.get(&key_a).is_some());
+        assert!(cache.get(&key_b).is_none());
+        assert!(cache.get(&key_c).is_some());
+
+        // Payloads larger than the cache are not cached
+        let key_d = PayloadKey::new("tenant", ExecutorType::Python, Executor::MesaPy, "d");
+        cache.insert(key_d.clone(), Arc::new("d"), 11);
+        assert!(cache.get(&key_d).is_none());
+        assert!(cache.get(&key_a).is_some());
+
+        let key_builtin = PayloadKey::new("tenant", ExecutorType::Builtin, Executor::Builtin, "a");
+        assert!(cache.get(&key_builtin).is_none());
+    }
+
+    fn test_payload_cache_tenant_isolation() {
+        let key_a = PayloadKey::new("tenant_a", ExecutorType::Python, Executor::MesaPy, "a");
+        let key_b = PayloadKey::new("tenant_b", ExecutorType::Python, Executor::MesaPy, "a");
+        let key_b2 = PayloadKey::new("tenant_b", ExecutorType::Python, Executor::MesaPy, "b");
+        let mut cache = PayloadCache::new(16, 8);
+
+        // The same payload compiled by another tenant is not shared.
+        cache.insert(key_a.clone(), Arc::new("a"), 4);
+        assert!(cache.get(&key_b).is_none());
+
+        // A tenant exceeding its quota evicts its own entries only.
+        cache.insert(key_b.clone(), Arc::new("a"), 4);
+        cache.insert(key_b2.clone(), Arc::new("b"), 6);
+        assert!(cache.get(&key_b).is_none());
+        assert!(cache.get(&key_b2).is_some());
+        assert!(cache.get(&key_a).is_some());
+
+        // Payloads larger than the tenant quota are not cached
+        let key_c = PayloadKey::new("tenant_a", ExecutorType::Python, Executor::MesaPy, "c");
+        cache.insert(key_c.clone(), Arc::new("c"), 9);
+        assert!(cache.get(&key_c).is_none());
+
+        // The partition of the least recently active tenant is dropped for a
+        // new tenant.
+        let key_d = PayloadKey::new("tenant_c", ExecutorType::Python, Executor::MesaPy, "a");
+        cache.insert(key_d.clone(), Arc::new("d"), 4);
+        assert!(cache.get(&key_d).is_some());
+        assert!(cache.get(&key_a).is_some());
+        assert!(cache.get(&key_b2).is_none());
+    }
+}
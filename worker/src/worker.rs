@@ -20,21 +20,45 @@ use std::prelude::v1::*;
 
 use std::collections::HashMap;
 use std::format;
+#[cfg(not(feature = "mesalock_sgx"))]
+use std::sync::Mutex;
+#[cfg(feature = "mesalock_sgx")]
+use std::sync::SgxMutex as Mutex;
 
-use teaclave_types::{Executor, ExecutorType, StagedFiles, StagedFunction};
+use teaclave_types::{
+    CompiledPayload, Executor, ExecutorType, StagedFiles, StagedFunction, WorkflowArtifacts,
+};
 
 use teaclave_executor::{BuiltinFunctionExecutor, MesaPy};
-use teaclave_runtime::DefaultRuntime;
+use teaclave_runtime::{DeadlineRuntime, DefaultRuntime, LimitedRuntime, SandboxRuntime};
 use teaclave_types::{TeaclaveExecutor, TeaclaveRuntime};
 
+use crate::payload_cache::{PayloadCache, PayloadKey};
+
 type BoxedTeaclaveExecutor = Box<dyn TeaclaveExecutor + Send + Sync>;
 type BoxedTeaclaveRuntime = Box<dyn TeaclaveRuntime + Send + Sync>;
 type ExecutorBuilder = fn() -> BoxedTeaclaveExecutor;
-type RuntimeBuilder = fn(StagedFiles, StagedFiles) -> BoxedTeaclaveRuntime;
+type RuntimeBuilder =
+    fn(StagedFiles, StagedFiles, Option<WorkflowArtifacts>) -> BoxedTeaclaveRuntime;
+
+// Executors prepared for upcoming tasks, the oldest one is dropped first.
+const MAX_PREPARED_EXECUTORS: usize = 8;
+const DEFAULT_PAYLOAD_CACHE_SIZE: usize = 64 * 1024 * 1024;
+const DEFAULT_TENANT_PAYLOAD_CACHE_SIZE: usize = 16 * 1024 * 1024;
+
+struct PreparedFunction {
+    tenant: String,
+    executor_type: ExecutorType,
+    executor: Executor,
+    name: String,
+    payload: String,
+}
 
 pub struct Worker {
     runtimes: HashMap<String, RuntimeBuilder>,
     executors: HashMap<(ExecutorType, Executor), ExecutorBuilder>,
+    prepared: Mutex<Vec<(PreparedFunction, BoxedTeaclaveExecutor)>>,
+    payload_cache: Mutex<PayloadCache>,
 }
 
 impl Default for Worker {
@@ -42,12 +66,12 @@ impl Default for Worker {
         let mut worker = Worker::new();
 
         // Register supported runtimes
-        worker.register_runtime("default", |input, output| {
-            Box::new(DefaultRuntime::new(input, output))
+        worker.register_runtime("default", |input, output, artifacts| {
+            Box::new(DefaultRuntime::new(input, output).artifacts(artifacts))
         });
 
         #[cfg(test_mode)]
-        worker.register_runtime("raw-io", |input, output| {
+        worker.register_runtime("raw-io", |input, output, _| {
             Box::new(teaclave_runtime::RawIoRuntime::new(input, output))
         });
 
@@ -68,6 +92,20 @@ impl Worker {
         Self {
             runtimes: HashMap::new(),
             executors: HashMap::new(),
+            prepared: Mutex::new(Vec::new()),
+            payload_cache: Mutex::new(PayloadCache::new(
+                DEFAULT_PAYLOAD_CACHE_SIZE,
+                DEFAULT_TENANT_PAYLOAD_CACHE_SIZE,
+            )),
+        }
+    }
+
+    /// Sets the total size in bytes of the function payloads whose compiled
+    /// form is cached, and the quota of each tenant in the cache.
+    pub fn payload_cache_size(self, size: usize, tenant_size: usize) -> Self {
+        Self {
+            payload_cache: Mutex::new(PayloadCache::new(size, tenant_size)),
+            ..self
         }
     }
 
@@ -79,14 +117,104 @@ impl Worker {
         self.executors.insert(key, builder);
     }
 
+    /// Executors registered with the worker, which it can invoke functions
+    /// with.
+    pub fn executors(&self) -> Vec<(ExecutorType, Executor)> {
+        self.executors.keys().cloned().collect()
+    }
+
+    /// Prepares an executor for a function to be invoked later. The prepared
+    /// executor is used by the next invocation of the same function by the
+    /// same tenant.
+    pub fn prepare_function(
+        &self,
+        tenant: &str,
+        executor_type: ExecutorType,
+        executor: Executor,
+        name: &str,
+        payload: &str,
+    ) -> anyhow::Result<()> {
+        let mut prepared_executor = self.get_executor(executor_type, executor)?;
+        prepared_executor.prepare(name, payload)?;
+
+        let function = PreparedFunction {
+            tenant: tenant.to_string(),
+            executor_type,
+            executor,
+            name: name.to_string(),
+            payload: payload.to_string(),
+        };
+        let mut prepared = self
+            .prepared
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Cannot lock prepared executors"))?;
+        if prepared.len() >= MAX_PREPARED_EXECUTORS {
+            prepared.remove(0);
+        }
+        prepared.push((function, prepared_executor));
+        Ok(())
+    }
+
     pub fn invoke_function(&self, function: StagedFunction) -> anyhow::Result<String> {
-        let executor = self.get_executor(function.executor_type, function.executor)?;
-        let runtime = self.get_runtime(
+        let mut executor = match self.take_prepared_executor(&function)? {
+            Some(executor) => executor,
+            None => self.get_executor(function.executor_type, function.executor)?,
+        };
+        if let Some(entry_point) = &function.entry_point {
+            executor.set_entry_point(entry_point)?;
+        }
+        let mut runtime = self.get_runtime(
             &function.runtime_name,
             function.input_files,
             function.output_files,
+            function.artifacts,
         )?;
-        executor.execute(function.name, function.arguments, function.payload, runtime)
+        if let Some(capabilities) = function.capabilities {
+            runtime = Box::new(SandboxRuntime::new(runtime, capabilities));
+        }
+        if let Some(deadline) = function.deadline {
+            runtime = Box::new(DeadlineRuntime::new(runtime, deadline));
+        }
+        if let Some(meter) = function.meter {
+            runtime = Box::new(LimitedRuntime::new(runtime, meter));
+        }
+        match self.compile_payload(&executor, &function)? {
+            Some(payload) => {
+                executor.execute_compiled(function.name, function.arguments, payload, runtime)
+            }
+            None => executor.execute(function.name, function.arguments, function.payload, runtime),
+        }
+    }
+
+    fn compile_payload(
+        &self,
+        executor: &BoxedTeaclaveExecutor,
+        function: &StagedFunction,
+    ) -> anyhow::Result<Option<CompiledPayload>> {
+        let key = PayloadKey::new(
+            &function.tenant,
+            function.executor_type,
+            function.executor,
+            &function.payload,
+        );
+        let cached = self
+            .payload_cache
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Cannot lock payload cache"))?
+            .get(&key);
+        if cached.is_some() {
+            return Ok(cached);
+        }
+
+        // The cache is not locked while compiling.
+        let compiled = executor.compile(&function.payload)?;
+        if let Some(payload) = &compiled {
+            self.payload_cache
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Cannot lock payload cache"))?
+                .insert(key, payload.clone(), function.payload.len());
+        }
+        Ok(compiled)
     }
 
     fn get_runtime(
@@ -94,16 +222,35 @@ impl Worker {
         name: &str,
         input_files: StagedFiles,
         output_files: StagedFiles,
+        artifacts: Option<WorkflowArtifacts>,
     ) -> anyhow::Result<BoxedTeaclaveRuntime> {
         let build_runtime = self
             .runtimes
             .get(name)
             .ok_or_else(|| anyhow::anyhow!(format!("Runtime {} not available.", name)))?;
 
-        let runtime = build_runtime(input_files, output_files);
+        let runtime = build_runtime(input_files, output_files, artifacts);
         Ok(runtime)
     }
 
+    fn take_prepared_executor(
+        &self,
+        function: &StagedFunction,
+    ) -> anyhow::Result<Option<BoxedTeaclaveExecutor>> {
+        let mut prepared = self
+            .prepared
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Cannot lock prepared executors"))?;
+        let position = prepared.iter().position(|(prepared_function, _)| {
+            prepared_function.tenant == function.tenant
+                && prepared_function.executor_type == function.executor_type
+                && prepared_function.executor == function.executor
+                && prepared_function.name == function.name
+                && prepared_function.payload == function.payload
+        });
+        Ok(position.map(|i| prepared.remove(i).1))
+    }
+
     fn get_executor(
         &self,
         exec_type: ExecutorType,